@@ -1,19 +1,92 @@
 import { app, BrowserWindow, ipcMain, Menu } from 'electron';
 import * as path from 'path';
 import { AppState } from './services/models';
-import { openDatabase } from './services/db';
+import { dbFinalizeOrphanedDrafts, openDatabase } from './services/db';
+import * as accounts from './services/accounts';
+import * as agents from './services/agents';
+import * as apiServer from './services/api-server';
+import * as archives from './services/archives';
+import * as changelog from './services/changelog';
+import * as chores from './services/chores';
 import * as codex from './services/codex';
+import * as codexConfig from './services/config';
+import * as conversationExport from './services/conversation-export';
 import * as db from './services/db';
+import * as dependencies from './services/dependencies';
+import * as eventLog from './services/event-log';
 import * as fsOps from './services/fs-ops';
+import * as instructions from './services/instructions';
+import * as git from './services/git';
+import * as github from './services/github';
+import * as guardrails from './services/guardrails';
+import * as history from './services/history';
+import * as housekeeping from './services/housekeeping';
+import * as installer from './services/installer';
+import * as jobs from './services/jobs';
+import * as mcp from './services/mcp';
+import * as narration from './services/narration';
+import * as notifications from './services/notifications';
+import * as packages from './services/packages';
+import * as permissions from './services/permissions';
 import * as auth from './services/auth';
+import * as presets from './services/presets';
+import * as promptHistory from './services/prompt-history';
+import * as protocol from './services/protocol';
+import * as release from './services/release';
+import * as resources from './services/resources';
+import * as runs from './services/runs';
+import * as scheduler from './services/scheduler';
+import * as screenshot from './services/screenshot';
+import * as secrets from './services/secrets';
 import * as shell from './services/shell';
+import * as snapshots from './services/snapshots';
+import * as spellcheck from './services/spellcheck';
+import * as startup from './services/startup';
+import * as stats from './services/stats';
+import * as streamBuffer from './services/stream-buffer';
+import * as subscriptions from './services/subscriptions';
 import * as teams from './services/teams';
+import * as tempdirs from './services/tempdirs';
+import * as templates from './services/templates';
+import * as themes from './services/themes';
+import * as translate from './services/translate';
+import * as turns from './services/turns';
+import * as diffs from './services/diffs';
+import * as urlContent from './services/url-content';
+import * as usage from './services/usage';
+import * as watch from './services/watch';
+import * as watchdog from './services/watchdog';
+import * as webSearch from './services/web-search';
+import * as webhooks from './services/webhooks';
+import * as workspaceBundle from './services/workspace-bundle';
+import * as workspaceSummary from './services/workspace-summary';
+import * as workspaceWatcher from './services/workspace-watcher';
 
 let mainWindow: BrowserWindow | null = null;
 let appState: AppState;
+let stopWatchdog: (() => void) | null = null;
+let stopChoreScheduler: (() => void) | null = null;
+let stopScheduler: (() => void) | null = null;
+let stopWatchRules: (() => void) | null = null;
+let stopHousekeeping: (() => void) | null = null;
+let stopMcpMonitor: (() => void) | null = null;
+let stopAuthWatcher: (() => void) | null = null;
+let eventRecorder: eventLog.EventRecorder;
+let startupIntent: startup.StartupIntent;
+
+/** Point the services that keep the database (or settings read from it) at `database`. */
+function initServices(database: AppState['db']) {
+    resources.initResourceLimits(database);
+    fsOps.initProtectedPaths(database);
+    secrets.initSecrets(database);
+    narration.initNarration(database);
+}
 
 function createAppState(): AppState {
     const database = openDatabase();
+    initServices(database);
+    const orphanedDrafts = dbFinalizeOrphanedDrafts(database);
+    if (orphanedDrafts > 0) console.error(`[drafts] Kept ${orphanedDrafts} partial message(s) from an interrupted run`);
     return {
         config: {
             mode: 'fast',
@@ -34,6 +107,14 @@ function createAppState(): AppState {
         runningCodex: new Map(),
         pendingApprovals: new Map(),
         ptyTerminals: new Map(),
+        turnFileChanges: new Map(),
+        turnUsage: new Map(),
+        promptQueues: new Map(),
+        maxConcurrentTurns: codex.loadMaxConcurrentTurns(database),
+        runningCommands: new Map(),
+        tempDirs: new Map(),
+        pendingReviews: new Map(),
+        streamBuffer: streamBuffer.createStreamReplayBuffer(),
     };
 }
 
@@ -67,7 +148,28 @@ function createWindow() {
 }
 
 function getWebContents() {
-    return mainWindow!.webContents;
+    return streamBuffer.tapStreamBuffer(
+        narration.tapNarration(
+            webhooks.tapWebhooks(
+                notifications.tapNotifications(
+                    eventLog.tapWebContents(subscriptions.filterSubscribedEvents(mainWindow!.webContents), eventRecorder),
+                    appState,
+                    () => mainWindow,
+                ),
+                appState,
+            ),
+        ),
+        appState,
+    );
+}
+
+/**
+ * Push a conversation's unread count to the renderer and refresh the dock/taskbar badge.
+ */
+function publishUnreadCount(conversationId: string) {
+    const unreadCount = db.dbGetUnreadCount(appState.db, conversationId);
+    app.setBadgeCount(db.dbGetUnreadTotal(appState.db));
+    if (mainWindow) getWebContents().send('conversation-unread-count', { cid: conversationId, unreadCount });
 }
 
 function registerIpcHandlers() {
@@ -76,32 +178,182 @@ function registerIpcHandlers() {
     ipcMain.handle('get-mode', () => codex.getMode(appState));
     ipcMain.handle('set-yolo-mode', (_e, enabled) => codex.setYoloMode(appState, enabled));
     ipcMain.handle('get-yolo-mode', () => codex.getYoloMode(appState));
-    ipcMain.handle('get-models', () => codex.getModels());
+    ipcMain.handle('get-models', () => codex.getModels(appState));
+    ipcMain.handle('refresh-models', () => codex.refreshModels(appState));
+    ipcMain.handle('get-custom-models', () => codex.getCustomModels(appState));
+    ipcMain.handle('set-custom-models', (_e, models) => codex.setCustomModels(appState, models));
     ipcMain.handle('get-model', () => codex.getModel(appState));
     ipcMain.handle('set-model', (_e, modelId) => codex.setModel(appState, modelId));
-    ipcMain.handle('set-cli-options', (_e, options) => codex.setCliOptions(appState, options));
+    ipcMain.handle('set-cli-options', (_e, options) => {
+        const invalidProfile = codexConfig.validateCodexProfile(options?.profile);
+        if (invalidProfile) throw new Error(invalidProfile);
+        return codex.setCliOptions(appState, options);
+    });
     ipcMain.handle('get-cli-options', () => codex.getCliOptions(appState));
+    ipcMain.handle('get-workspace-cli-options', (_e, workspaceId) =>
+        db.dbGetWorkspaceCliOptions(appState.db, workspaceId),
+    );
+    ipcMain.handle('set-workspace-cli-options', (_e, workspaceId, options) => {
+        const invalidProfile = codexConfig.validateCodexProfile(options?.profile);
+        if (invalidProfile) throw new Error(invalidProfile);
+        return db.dbSetWorkspaceCliOptions(appState.db, workspaceId, options);
+    });
+    ipcMain.handle('get-codex-profiles', () => codexConfig.getCodexProfiles());
+    ipcMain.handle('validate-codex-profile', (_e, name) => codexConfig.validateCodexProfile(name));
 
     // ===== Codex: ACP / Workspace =====
     ipcMain.handle('init-acp', () => codex.initAcp(getWebContents()));
-    ipcMain.handle('switch-workspace', (_e, workspaceId, cwd) =>
-        codex.switchWorkspace(appState, workspaceId, cwd),
-    );
+    ipcMain.handle('switch-workspace', (_e, workspaceId, cwd) => {
+        workspaceWatcher.startWorkspaceWatcher(() => (mainWindow ? getWebContents() : null), workspaceId, cwd);
+        if (cwd && !fsOps.hasFileIndex(cwd)) {
+            jobs.startJob(
+                () => (mainWindow ? getWebContents() : null),
+                appState.db,
+                'file-index',
+                `Index ${path.basename(cwd)}`,
+                { workspacePath: cwd },
+            );
+        }
+        return codex.switchWorkspace(appState, workspaceId, cwd);
+    });
+    ipcMain.handle('watch-paths', (_e, workspaceId, globs) => workspaceWatcher.watchPaths(workspaceId, globs));
     ipcMain.handle('debug-log', (_e, msg) => codex.debugLog(msg));
 
+    // ===== Event Log (flight recorder) =====
+    ipcMain.handle('set-event-log-enabled', (_e, enabled) =>
+        eventLog.setEventLogEnabled(eventRecorder, enabled),
+    );
+    ipcMain.handle('get-event-log-status', () => eventLog.getEventLogStatus(eventRecorder));
+    ipcMain.handle('get-recent-event-log', async (_e, limit) => {
+        await eventRecorder.flush();
+        return eventLog.getRecentEventLogEntries(limit);
+    });
+
+    // ===== Notifications =====
+    ipcMain.handle('get-notification-prefs', () => notifications.getNotificationPrefs(appState.db));
+    ipcMain.handle('set-notification-prefs', (_e, prefs) =>
+        notifications.setNotificationPrefs(appState.db, prefs),
+    );
+    ipcMain.handle('get-notification-routes', () => notifications.getNotificationRoutes(appState.db));
+    ipcMain.handle('save-notification-route', (_e, route) => notifications.saveNotificationRoute(appState.db, route));
+    ipcMain.handle('delete-notification-route', (_e, id) => notifications.deleteNotificationRoute(appState.db, id));
+    ipcMain.handle('test-notification-route', (_e, workspaceId, event, dryRun) =>
+        notifications.testNotificationRoute(appState.db, workspaceId, event, !!dryRun),
+    );
+    ipcMain.handle('get-narration-enabled', () => narration.getNarrationEnabled());
+    ipcMain.handle('set-narration-enabled', (_e, enabled) => narration.setNarrationEnabled(appState.db, !!enabled));
+
+    // ===== Webhooks =====
+    ipcMain.handle('list-webhooks', () => webhooks.listWebhooks(appState.db));
+    ipcMain.handle('add-webhook', (_e, url, events, secret) => webhooks.addWebhook(appState.db, url, events, secret));
+    ipcMain.handle('remove-webhook', (_e, id) => webhooks.removeWebhook(appState.db, id));
+    ipcMain.handle('set-webhook-enabled', (_e, id, enabled) => webhooks.setWebhookEnabled(appState.db, id, !!enabled));
+    ipcMain.handle('test-webhook', (_e, id) => webhooks.testWebhook(appState.db, id));
+
+    // ===== API Server =====
+    ipcMain.handle('get-api-server-settings', () => apiServer.getApiServerSettings(appState.db));
+    ipcMain.handle('set-api-server-settings', async (_e, settings) => {
+        const saved = apiServer.setApiServerSettings(appState.db, settings);
+        const status = await apiServer.applyApiServerSettings(appState, () => (mainWindow ? getWebContents() : null));
+        return { settings: saved, status };
+    });
+    ipcMain.handle('get-api-server-status', () => apiServer.getApiServerStatus());
+
+    // ===== Startup =====
+    ipcMain.handle('get-startup-intent', () => startupIntent);
+    ipcMain.handle('get-startup-settings', () => startup.getStartupSettings(appState.db));
+    ipcMain.handle('set-startup-settings', (_e, settings) =>
+        startup.setStartupSettings(appState.db, settings),
+    );
+    ipcMain.handle('record-last-session', (_e, workspaceId, conversationId) =>
+        startup.recordLastSession(appState.db, workspaceId, conversationId),
+    );
+
     // ===== Codex: Check / Install =====
     ipcMain.handle('check-codex', () => codex.checkCodex());
-    ipcMain.handle('install-codex', () => codex.installCodex(getWebContents()));
+    ipcMain.handle('install-codex', (_e, method) => codex.installCodex(getWebContents(), appState.db, method));
+    ipcMain.handle('upgrade-codex', (_e, method) => codex.upgradeCodex(getWebContents(), appState.db, method));
+    ipcMain.handle('get-codex-install-methods', async () => ({
+        methods: await installer.listInstallMethods(),
+        preferred: installer.getInstallPreference(appState.db),
+    }));
+    ipcMain.handle('set-codex-install-method', (_e, method) => installer.setInstallPreference(appState.db, method));
+    ipcMain.handle('get-codex-version', () => protocol.getCodexVersion());
+    ipcMain.handle('get-codex-protocol', async () => {
+        await protocol.detectCodexVersion();
+        return protocol.codexProtocolInfo();
+    });
+    ipcMain.handle('list-agent-backends', () => agents.listAgentBackends());
 
     // ===== Codex: Workspace =====
     ipcMain.handle('open-workspace', () => codex.openWorkspace());
 
     // ===== Codex: Stream / Cancel =====
-    ipcMain.handle('stream-codex', (_e, conversationId, prompt, conversationHistory) =>
-        codex.streamCodex(getWebContents(), conversationId, prompt, conversationHistory, appState),
+    ipcMain.handle('stream-codex', (_e, conversationId, prompt, conversationHistory, attachments) =>
+        codex.streamCodex(getWebContents(), conversationId, prompt, conversationHistory, appState, attachments),
+    );
+    ipcMain.handle('stream-codex-plan', (_e, conversationId, prompt, conversationHistory, attachments) =>
+        codex.streamCodexPlan(getWebContents(), conversationId, prompt, conversationHistory, appState, attachments),
+    );
+    ipcMain.handle('capture-screen', (_e, region, displayId, hideWindow) =>
+        screenshot.captureScreen(mainWindow, region, displayId, hideWindow ?? true),
+    );
+    ipcMain.handle('check-prompt-paths', (_e, conversationId, prompt) =>
+        codex.checkPromptPathsForConversation(appState, conversationId, prompt),
+    );
+    ipcMain.handle('analyze-prompt', (_e, text, workspaceId, conversationId) =>
+        guardrails.analyzePrompt(appState.db, appState.config, text, workspaceId, conversationId),
+    );
+    ipcMain.handle('cancel-prompt', (_e, conversationId, graceful) =>
+        codex.cancelPrompt(getWebContents(), conversationId, appState, !!graceful),
+    );
+    ipcMain.handle('subscribe-stream', (e, conversationId, kinds) =>
+        subscriptions.subscribeStream(e.sender, conversationId, kinds || []),
     );
-    ipcMain.handle('cancel-prompt', (_e, conversationId) =>
-        codex.cancelPrompt(getWebContents(), conversationId, appState),
+    ipcMain.handle('unsubscribe-stream', (e, conversationId) => subscriptions.unsubscribeStream(e.sender, conversationId));
+    ipcMain.handle('get-stream-snapshot', (_e, conversationId) => codex.getStreamSnapshot(appState, conversationId));
+    ipcMain.handle('get-stream-buffer', (_e, conversationId, sinceSeq) =>
+        streamBuffer.getStreamBuffer(appState, conversationId, sinceSeq),
+    );
+    ipcMain.handle('get-queue-mode', () => codex.getQueueMode(appState));
+    ipcMain.handle('set-queue-mode', (_e, enabled) => codex.setQueueMode(appState, !!enabled));
+    ipcMain.handle('get-review-mode', () => codex.getReviewMode(appState));
+    ipcMain.handle('set-review-mode', (_e, enabled) => codex.setReviewMode(appState, !!enabled));
+    ipcMain.handle('get-pending-review', (_e, conversationId) => codex.getPendingReview(appState, conversationId));
+    ipcMain.handle('approve-review', (_e, conversationId, scope) =>
+        codex.approveReview(getWebContents(), appState, conversationId, scope),
+    );
+    ipcMain.handle('execute-plan', (_e, conversationId, stepIndices) =>
+        codex.executePlan(getWebContents(), appState, conversationId, stepIndices || []),
+    );
+    ipcMain.handle('reject-review', (_e, conversationId) => codex.rejectReview(getWebContents(), appState, conversationId));
+    ipcMain.handle('get-cancel-grace-period', () => codex.getCancelGracePeriod(appState));
+    ipcMain.handle('set-cancel-grace-period', (_e, ms) => codex.setCancelGracePeriod(appState, ms));
+    ipcMain.handle('get-history-settings', () => history.getHistorySettings(appState.db));
+    ipcMain.handle('set-history-settings', (_e, settings) => history.setHistorySettings(appState.db, settings || {}));
+    ipcMain.handle('get-history-summary', (_e, conversationId) => history.getHistorySummary(appState.db, conversationId));
+    ipcMain.handle('clear-history-summary', (_e, conversationId) => history.clearHistorySummary(appState.db, conversationId));
+    ipcMain.handle('get-prompt-queue', (_e, conversationId) =>
+        codex.getPromptQueue(appState, conversationId),
+    );
+    ipcMain.handle('clear-prompt-queue', (_e, conversationId) =>
+        codex.clearPromptQueue(getWebContents(), appState, conversationId),
+    );
+    ipcMain.handle('get-max-concurrent-turns', () => codex.getMaxConcurrentTurns(appState));
+    ipcMain.handle('set-max-concurrent-turns', (_e, limit) =>
+        codex.setMaxConcurrentTurns(getWebContents(), appState, limit),
+    );
+    ipcMain.handle('get-running-turns', () => codex.getRunningTurns(appState));
+    ipcMain.handle('allocate-temp-dir', (_e, conversationId) => tempdirs.allocateTempDir(appState, conversationId));
+    ipcMain.handle('release-temp-dirs', (_e, conversationId) => ({
+        success: true,
+        removed: tempdirs.releaseTempDirs(appState, conversationId),
+    }));
+    ipcMain.handle('set-conversation-cwd', (_e, conversationId, cwdPath) =>
+        codex.setConversationCwd(appState, conversationId, cwdPath ?? null),
+    );
+    ipcMain.handle('move-conversation', (_e, conversationId, targetWorkspaceId, migrateCwd) =>
+        codex.moveConversation(getWebContents(), appState, conversationId, targetWorkspaceId, !!migrateCwd),
     );
     ipcMain.handle('run-codex-command', (_e, subcommand, args, cwd) =>
         codex.runCodexCommand(appState, subcommand, args, cwd),
@@ -109,60 +361,351 @@ function registerIpcHandlers() {
     ipcMain.handle('update-title-bar-overlay', (_e, color, symbolColor) =>
         codex.updateTitleBarOverlay(color, symbolColor),
     );
-    ipcMain.handle('respond-to-approval', (_e, requestId, approved) =>
-        codex.respondToApproval(requestId, approved, appState),
+    ipcMain.handle('respond-to-approval', (_e, requestId, decision, message) =>
+        codex.respondToApproval(requestId, decision, appState, message),
     );
+    ipcMain.handle('list-orphan-processes', () => watchdog.listOrphanProcesses(appState));
 
     // ===== Auth =====
-    ipcMain.handle('codex-login', (_e, method, apiKey) => auth.codexLogin(method, apiKey));
+    ipcMain.handle('codex-login', (_e, method) =>
+        auth.codexLogin(method, (progress) => getWebContents().send('codex-login-progress', progress)),
+    );
+    ipcMain.handle('cancel-login', () => auth.cancelLogin());
     ipcMain.handle('codex-logout', () => auth.codexLogout());
     ipcMain.handle('codex-login-methods', () => auth.codexLoginMethods());
     ipcMain.handle('get-user', () => auth.getUser());
+    ipcMain.handle('secret-set', (_e, name, value) => secrets.setSecret(name, value));
+    ipcMain.handle('secret-get', (_e, name) => secrets.getSecretForRenderer(name));
+    ipcMain.handle('secret-delete', (_e, name) => secrets.deleteSecret(name));
+    ipcMain.handle('get-secret-settings', () => ({
+        ...secrets.getSecretSettings(appState.db),
+        keychainAvailable: secrets.keychainAvailable(),
+    }));
+    ipcMain.handle('set-secret-settings', (_e, settings) => secrets.setSecretSettings(appState.db, settings));
+    ipcMain.handle('list-accounts', () => accounts.listAccounts());
+    ipcMain.handle('add-account', (_e, name, method) =>
+        accounts.addAccount(name, method, (progress) => getWebContents().send('codex-login-progress', progress)),
+    );
+    ipcMain.handle('save-current-account', (_e, name) => accounts.saveCurrentAccount(name));
+    ipcMain.handle('switch-account', (_e, name) => accounts.switchAccount(getWebContents(), appState, name));
+    ipcMain.handle('remove-account', (_e, name) => accounts.removeAccount(name));
 
     // ===== File System =====
-    ipcMain.handle('search-files', (_e, workspacePath, query) =>
-        fsOps.searchFiles(workspacePath, query),
+    ipcMain.handle('search-files', (_e, workspacePath, query, conversationId) =>
+        fsOps.searchFiles(
+            conversationId ? packages.conversationScope(appState, conversationId, workspacePath) : workspacePath,
+            query,
+        ),
+    );
+    ipcMain.handle('search-file-contents', (_e, workspacePath, query, options) =>
+        fsOps.searchFileContents(workspacePath, query, options),
     );
     ipcMain.handle('read-file-content', (_e, filePath, workspacePath) =>
         fsOps.readFileContent(filePath, workspacePath),
     );
-    ipcMain.handle('write-file', (_e, filePath, content, workspacePath) =>
-        fsOps.writeFile(filePath, content, workspacePath),
+    ipcMain.handle('read-file-range', (_e, filePath, offset, maxBytes, workspacePath) =>
+        fsOps.readFileRange(filePath, offset, maxBytes, workspacePath),
+    );
+    ipcMain.handle('read-file-hex', (_e, filePath, offset, length, workspacePath) =>
+        fsOps.readFileHex(filePath, offset, length, workspacePath),
+    );
+    ipcMain.handle('get-protected-paths', () => fsOps.getProtectedPathSettings(appState.db));
+    ipcMain.handle('set-protected-paths', (_e, settings) => fsOps.setProtectedPathSettings(appState.db, settings));
+    ipcMain.handle('allow-protected-file', (_e, filePath, workspacePath) =>
+        fsOps.allowProtectedFile(appState.db, filePath, workspacePath),
+    );
+    ipcMain.handle('revoke-protected-file', (_e, filePath) => fsOps.revokeProtectedFile(appState.db, filePath));
+    ipcMain.handle('write-file', async (_e, filePath, content, workspacePath, options) =>
+        (await permissions.requirePermission(appState.db, mainWindow, workspacePath, 'write')) ??
+        fsOps.writeFile(filePath, content, workspacePath, options),
+    );
+    ipcMain.handle('restore-file-backup', async (_e, backupId, workspacePath) =>
+        (await permissions.requirePermission(appState.db, mainWindow, workspacePath, 'write')) ??
+        fsOps.restoreFileBackup(backupId, workspacePath),
     );
-    ipcMain.handle('list-directory', (_e, dirPath, workspacePath) =>
-        fsOps.listDirectory(dirPath, workspacePath),
+    ipcMain.handle('list-directory', (_e, dirPath, workspacePath, options) =>
+        fsOps.listDirectory(dirPath, workspacePath, options),
     );
     ipcMain.handle('file-exists', (_e, filePath, workspacePath) =>
         fsOps.fileExists(filePath, workspacePath),
     );
+    ipcMain.handle('create-directory', async (_e, dirPath, workspacePath) =>
+        (await permissions.requirePermission(appState.db, mainWindow, workspacePath, 'write')) ??
+        fsOps.createDirectory(dirPath, workspacePath),
+    );
+    ipcMain.handle('delete-path', async (_e, targetPath, workspacePath, options) =>
+        (await permissions.requirePermission(appState.db, mainWindow, workspacePath, 'delete')) ??
+        fsOps.deletePath(targetPath, workspacePath, options),
+    );
+    ipcMain.handle('rename-path', async (_e, targetPath, newName, workspacePath) =>
+        (await permissions.requirePermission(appState.db, mainWindow, workspacePath, 'write')) ??
+        fsOps.renamePath(targetPath, newName, workspacePath),
+    );
+    ipcMain.handle('copy-path', async (_e, sourcePath, destinationPath, workspacePath, overwrite) =>
+        (await permissions.requirePermission(appState.db, mainWindow, workspacePath, 'write')) ??
+        fsOps.copyPath(sourcePath, destinationPath, workspacePath, !!overwrite),
+    );
+    ipcMain.handle('move-path', async (_e, sourcePath, destinationPath, workspacePath, overwrite) =>
+        (await permissions.requirePermission(appState.db, mainWindow, workspacePath, 'write')) ??
+        fsOps.movePath(sourcePath, destinationPath, workspacePath, !!overwrite),
+    );
     ipcMain.handle('open-in-editor', (_e, filePath, editor) =>
         fsOps.openInEditor(filePath, editor),
     );
 
+    // ===== Archives =====
+    ipcMain.handle('list-archive', (_e, archivePath, workspacePath) =>
+        archives.listArchive(archivePath, workspacePath),
+    );
+    ipcMain.handle('extract-archive', async (_e, archivePath, dest, workspacePath, members) =>
+        (await permissions.requirePermission(appState.db, mainWindow, workspacePath, 'write')) ??
+        archives.extractArchive(archivePath, dest, workspacePath, members),
+    );
+
+    // ===== Permissions =====
+    ipcMain.handle('revoke-permission', (_e, workspacePath, capability) =>
+        permissions.revokePermission(appState.db, workspacePath, capability),
+    );
+    ipcMain.handle('list-permissions', (_e, workspacePath) =>
+        permissions.listPermissions(appState.db, workspacePath),
+    );
+
     // ===== Web Search =====
-    ipcMain.handle('web-search', (_e, query) => fsOps.webSearch(query));
+    ipcMain.handle('web-search', (_e, query, provider, maxResults) =>
+        webSearch.webSearch(appState.db, query, provider, maxResults),
+    );
+    ipcMain.handle('fetch-url-content', (_e, url, mode) => urlContent.fetchUrlContent(url, mode));
+    ipcMain.handle('get-web-search-settings', () => webSearch.getWebSearchSettings(appState.db));
+    ipcMain.handle('set-web-search-settings', (_e, settings) =>
+        webSearch.setWebSearchSettings(appState.db, settings),
+    );
 
     // ===== Shell =====
-    ipcMain.handle('run-command', (_e, command, cwd) =>
+    ipcMain.handle('run-command', async (_e, command, cwd) =>
+        (await permissions.requirePermission(appState.db, mainWindow, cwd || appState.config.cwd, 'run')) ??
         shell.runCommand(getWebContents(), command, cwd, appState),
     );
-    ipcMain.handle('kill-command', (_e, commandId) => shell.killCommand(commandId));
+    ipcMain.handle('start-command', async (_e, command, cwd) =>
+        (await permissions.requirePermission(appState.db, mainWindow, cwd || appState.config.cwd, 'run')) ?? {
+            success: true,
+            commandId: shell.startCommand(getWebContents(), command, cwd, appState).commandId,
+        },
+    );
+    ipcMain.handle('command-write-stdin', (_e, commandId, data) =>
+        shell.commandWriteStdin(appState, commandId, data),
+    );
+    ipcMain.handle('kill-command', (_e, commandId) => shell.killCommand(appState, commandId));
+    ipcMain.handle('rerun-command', async (_e, commandId) =>
+        (await permissions.requirePermission(appState.db, mainWindow, runs.getRun(commandId)?.cwd || appState.config.cwd, 'run')) ??
+        shell.rerunCommand(getWebContents(), appState, commandId),
+    );
+    ipcMain.handle('list-command-runs', () => runs.listRuns());
+    ipcMain.handle('compare-command-runs', (_e, runA, runB) => runs.compareRuns(runA, runB));
+
+    // ===== Git =====
+    ipcMain.handle('git-status', (_e, workspacePath) => git.gitStatus(workspacePath));
+    ipcMain.handle('git-diff', (_e, workspacePath, file) => git.gitDiff(workspacePath, file));
+    ipcMain.handle('git-log', (_e, workspacePath, limit) => git.gitLog(workspacePath, limit));
+
+    // ===== GitHub =====
+    ipcMain.handle('get-github-settings', () => github.getGitHubSettings(appState.db));
+    ipcMain.handle('set-github-settings', (_e, settings) => github.setGitHubSettings(appState.db, settings));
+    ipcMain.handle('create-pr-from-changes', (_e, workspaceId, title, body, base) =>
+        github.createPrFromChanges(appState.db, mainWindow, workspaceId, title, body, base),
+    );
+
+    // ===== Changelog =====
+    ipcMain.handle('generate-changelog', (_e, workspaceId, since) =>
+        changelog.generateChangelog(appState, mainWindow, workspaceId, since),
+    );
+
+    // ===== Release =====
+    ipcMain.handle('run-release', (_e, workspaceId, version, steps, dryRun) =>
+        release.runRelease(getWebContents(), appState, mainWindow, workspaceId, version, steps, !!dryRun),
+    );
+
+    // ===== Agent Instructions =====
+    ipcMain.handle('read-instructions', (_e, workspacePath) => instructions.readInstructions(workspacePath));
+    ipcMain.handle('write-instructions', async (_e, workspacePath, content, override) =>
+        (await permissions.requirePermission(appState.db, mainWindow, workspacePath, 'write')) ??
+        instructions.writeInstructions(workspacePath, content, !!override),
+    );
+    ipcMain.handle('generate-starter-instructions', (_e, workspacePath) => ({
+        content: instructions.generateStarterInstructions(workspacePath),
+        kinds: instructions.detectProjectKinds(workspacePath),
+    }));
+    ipcMain.handle('create-starter-instructions', async (_e, workspacePath) =>
+        (await permissions.requirePermission(appState.db, mainWindow, workspacePath, 'write')) ??
+        instructions.createStarterInstructions(workspacePath),
+    );
+    ipcMain.handle('append-instructions-conventions', async (_e, workspacePath, conventions) =>
+        (await permissions.requirePermission(appState.db, mainWindow, workspacePath, 'write')) ??
+        instructions.appendConventions(workspacePath, conventions),
+    );
+    ipcMain.handle('get-instructions-status', (_e, cwd) => instructions.instructionsStatus(cwd));
+    ipcMain.handle('summarize-workspace', (_e, workspaceId, force) =>
+        workspaceSummary.summarizeWorkspace(appState, workspaceId, !!force),
+    );
+    ipcMain.handle('get-workspace-summary', (_e, workspaceId) => workspaceSummary.getWorkspaceSummary(appState.db, workspaceId));
+
+    // ===== Monorepo Packages =====
+    ipcMain.handle('detect-packages', (_e, workspacePath) => packages.detectPackages(workspacePath));
+    ipcMain.handle('set-conversation-target-package', (_e, conversationId, packagePath) =>
+        packages.setConversationTargetPackage(appState, conversationId, packagePath ?? null),
+    );
+    ipcMain.handle('run-target-tests', async (_e, conversationId) =>
+        (await permissions.requirePermission(
+            appState.db,
+            mainWindow,
+            db.dbGetConversationOverrides(appState.db, conversationId).workspacePath,
+            'run',
+        )) ?? packages.runTargetTests(getWebContents(), appState, conversationId),
+    );
+
+    // ===== Dependencies =====
+    ipcMain.handle('check-outdated-dependencies', (_e, workspacePath) =>
+        dependencies.checkOutdatedDependencies(workspacePath),
+    );
+    ipcMain.handle('build-dependency-upgrade-prompt', (_e, selected) =>
+        dependencies.buildUpgradePrompt(selected),
+    );
 
     // ===== PTY =====
-    ipcMain.handle('pty-create', (_e, cwd, shellPath) =>
+    ipcMain.handle('pty-create', async (_e, cwd, shellPath) =>
+        (await permissions.requirePermission(appState.db, mainWindow, cwd || appState.config.cwd, 'pty')) ??
         shell.ptyCreate(getWebContents(), appState, cwd, shellPath),
     );
     ipcMain.handle('pty-write', (_e, id, data) => shell.ptyWrite(appState, id, data));
     ipcMain.handle('pty-kill', (_e, id) => shell.ptyKill(appState, id));
     ipcMain.handle('pty-list', () => shell.ptyList(appState));
+    ipcMain.handle('list-available-shells', () => shell.listAvailableShells());
 
     // ===== Teams =====
     ipcMain.handle('send-to-teams', (_e, webhookUrl, title, content) =>
-        teams.sendToTeams(webhookUrl, title, content),
+        teams.sendToTeams(webhookUrl, title, content, themes.getArtifactTheme(appState.db)),
+    );
+
+    // ===== Artifact Themes =====
+    ipcMain.handle('get-artifact-theme', () => themes.getArtifactTheme(appState.db));
+    ipcMain.handle('set-artifact-theme', (_e, theme) => themes.setArtifactTheme(appState.db, theme));
+
+    // ===== Turns =====
+    ipcMain.handle('get-turns', (_e, conversationId, status) => turns.getTurns(appState.db, conversationId, status));
+    ipcMain.handle('get-turn', (_e, turnId) => turns.getTurn(appState.db, turnId));
+    ipcMain.handle('get-turn-for-message', (_e, messageId) => turns.getTurnForMessage(appState.db, messageId));
+    ipcMain.handle('rate-turn', (_e, turnId, outcome, comment) => turns.rateTurn(appState.db, turnId, outcome ?? null, comment));
+    ipcMain.handle('get-turn-diffs', (_e, conversationId, turnId) => diffs.getTurnDiffs(appState.db, conversationId, turnId));
+
+    // ===== MCP =====
+    ipcMain.handle('get-mcp-calls', (_e, turnId) => mcp.getMcpCalls(appState.db, turnId));
+    ipcMain.handle('get-mcp-servers', () => codexConfig.getMcpServers());
+    ipcMain.handle('get-mcp-server-statuses', () => mcp.getMcpServerStatuses());
+    ipcMain.handle('restart-mcp-server', (_e, name) =>
+        mcp.restartMcpServer(() => (mainWindow ? getWebContents() : null), name),
+    );
+
+    // ===== Chores =====
+    ipcMain.handle('list-chores', (_e, workspaceId) => chores.listChores(appState.db, workspaceId));
+    ipcMain.handle('save-chore', (_e, chore) => chores.saveChore(appState.db, chore));
+    ipcMain.handle('delete-chore', (_e, id) => chores.deleteChore(appState.db, id));
+    ipcMain.handle('run-chore-now', (_e, id) => chores.runChore(getWebContents(), appState, id));
+    ipcMain.handle('list-chore-runs', (_e, workspaceId, status) =>
+        chores.listChoreRuns(appState.db, workspaceId, status),
+    );
+    ipcMain.handle('apply-chore-run', (_e, runId) =>
+        chores.applyChoreRun(getWebContents(), appState, runId),
+    );
+    ipcMain.handle('dismiss-chore-run', (_e, runId) => chores.dismissChoreRun(appState.db, runId));
+
+    // ===== Scheduled Tasks =====
+    ipcMain.handle('list-scheduled-tasks', (_e, workspaceId) => scheduler.listScheduledTasks(appState.db, workspaceId));
+    ipcMain.handle('save-scheduled-task', (_e, task) => scheduler.saveScheduledTask(appState.db, task));
+    ipcMain.handle('delete-scheduled-task', (_e, id) => scheduler.deleteScheduledTask(appState.db, id));
+    ipcMain.handle('run-scheduled-task-now', (_e, id) =>
+        scheduler.runScheduledTask(() => (mainWindow ? getWebContents() : null), appState, id),
+    );
+    ipcMain.handle('validate-cron', (_e, expression) => scheduler.validateCron(expression));
+
+    // ===== Watch Rules =====
+    const watchWebContents = () => (mainWindow ? getWebContents() : null);
+    ipcMain.handle('list-watch-rules', (_e, workspaceId) => watch.listWatchRules(appState.db, workspaceId));
+    ipcMain.handle('watch-and-prompt', (_e, workspaceId, glob, promptTemplate, debounceMs, maxPerHour) =>
+        watch.watchAndPrompt(watchWebContents, appState, workspaceId, glob, promptTemplate, debounceMs, maxPerHour),
+    );
+    ipcMain.handle('set-watch-rule-enabled', (_e, ruleId, enabled) =>
+        watch.setWatchRuleEnabled(watchWebContents, appState, ruleId, enabled),
+    );
+    ipcMain.handle('delete-watch-rule', (_e, ruleId) => watch.deleteWatchRule(appState.db, ruleId));
+
+    // ===== Launch Presets =====
+    ipcMain.handle('list-presets', () => presets.listPresets(appState.db));
+    ipcMain.handle('save-preset', (_e, preset) => presets.savePreset(appState.db, preset));
+    ipcMain.handle('set-preset-starred', (_e, id, starred) =>
+        presets.setPresetStarred(appState.db, id, starred),
+    );
+    ipcMain.handle('delete-preset', (_e, id) => presets.deletePreset(appState.db, id));
+    ipcMain.handle('apply-preset', (_e, presetId, conversationId) =>
+        presets.applyPreset(appState.db, presetId, conversationId),
+    );
+
+    // ===== Prompt History =====
+    ipcMain.handle('get-prompt-history', (_e, workspaceId, prefix, limit) =>
+        promptHistory.getPromptHistory(appState.db, workspaceId, prefix, limit),
+    );
+    ipcMain.handle('clear-prompt-history', (_e, workspaceId) =>
+        promptHistory.clearPromptHistory(appState.db, workspaceId),
+    );
+
+    // ===== Spell Check =====
+    ipcMain.handle('check-prompt-text', (_e, text, lang) => spellcheck.checkPromptText(text, lang));
+
+    // ===== Translation =====
+    ipcMain.handle('translate-text', (_e, text, targetLang) =>
+        translate.translateText(appState.db, text, targetLang),
+    );
+    ipcMain.handle('get-translation-settings', () => translate.getTranslationSettings(appState.db));
+    ipcMain.handle('set-translation-settings', (_e, settings) =>
+        translate.setTranslationSettings(appState.db, settings),
+    );
+    ipcMain.handle('set-conversation-auto-translate', (_e, conversationId, targetLang) =>
+        translate.setConversationAutoTranslate(appState.db, conversationId, targetLang),
+    );
+
+    // ===== Conversation Notes / Export =====
+    ipcMain.handle('get-conversation-notes', (_e, conversationId) =>
+        db.dbGetConversationNotes(appState.db, conversationId),
+    );
+    ipcMain.handle('set-conversation-notes', (_e, conversationId, notes, includeInPrompt) =>
+        db.dbSetConversationNotes(appState.db, conversationId, notes, includeInPrompt),
+    );
+    ipcMain.handle('export-conversation', (_e, conversationId, format, includeNotes) =>
+        conversationExport.exportConversation(appState.db, conversationId, format, !!includeNotes),
+    );
+    ipcMain.handle('import-conversation', (_e, jsonPath, workspaceId) =>
+        conversationExport.importConversation(appState.db, jsonPath, workspaceId),
+    );
+    ipcMain.handle('export-workspace-bundle', (_e, workspaceId, destPath, includeCheckpoints) =>
+        workspaceBundle.exportWorkspaceBundle(appState.db, workspaceId, destPath, !!includeCheckpoints),
+    );
+    ipcMain.handle('import-workspace-bundle', async (_e, bundlePath, workspacePath, name) =>
+        (await permissions.requirePermission(appState.db, mainWindow, workspacePath, 'write')) ??
+        workspaceBundle.importWorkspaceBundle(appState.db, bundlePath, workspacePath, name),
+    );
+
+    // ===== Housekeeping =====
+    ipcMain.handle('get-housekeeping-suggestions', () => housekeeping.getHousekeepingSuggestions(appState.db));
+    ipcMain.handle('get-housekeeping-settings', () => housekeeping.getHousekeepingSettings(appState.db));
+    ipcMain.handle('set-housekeeping-settings', (_e, settings) =>
+        housekeeping.setHousekeepingSettings(appState.db, settings),
+    );
+    ipcMain.handle('archive-workspace', (_e, workspaceId) => housekeeping.archiveWorkspace(appState.db, workspaceId));
+    ipcMain.handle('export-and-prune-workspace', (_e, workspaceId, destPath) =>
+        housekeeping.exportAndPruneWorkspace(appState.db, workspaceId, destPath),
     );
 
     // ===== Database =====
     ipcMain.handle('db-get-state', () => db.dbGetState(appState.db));
+    ipcMain.handle('db-get-changes', (_e, sinceToken) => db.dbGetChanges(appState.db, sinceToken));
     ipcMain.handle('db-create-workspace', (_e, id, name, workspacePath) =>
         db.dbCreateWorkspace(appState.db, id, name, workspacePath),
     );
@@ -173,9 +716,24 @@ function registerIpcHandlers() {
     ipcMain.handle('db-get-conversations', (_e, workspaceId) =>
         db.dbGetConversations(appState.db, workspaceId),
     );
+    ipcMain.handle('db-get-conversation-groups', (_e, workspaceId) =>
+        db.dbGetConversationGroups(appState.db, workspaceId),
+    );
     ipcMain.handle('db-create-conversation', (_e, id, workspaceId, title) =>
         db.dbCreateConversation(appState.db, id, workspaceId, title),
     );
+    ipcMain.handle('db-list-conversations', (_e, workspaceId, options) =>
+        db.dbListConversations(appState.db, workspaceId, options || {}),
+    );
+    ipcMain.handle('db-set-conversation-archived', (_e, id, archived) =>
+        db.dbSetConversationArchived(appState.db, id, !!archived),
+    );
+    ipcMain.handle('db-set-conversation-pinned', (_e, id, pinned) =>
+        db.dbSetConversationPinned(appState.db, id, !!pinned),
+    );
+    ipcMain.handle('db-fork-conversation', (_e, conversationId, fromMessageId, newTitle) =>
+        db.dbForkConversation(appState.db, conversationId, fromMessageId, newTitle),
+    );
     ipcMain.handle('db-update-conversation-title', (_e, id, title) =>
         db.dbUpdateConversationTitle(appState.db, id, title),
     );
@@ -183,17 +741,178 @@ function registerIpcHandlers() {
         db.dbDeleteConversation(appState.db, id),
     );
     ipcMain.handle('db-get-messages', (_e, conversationId) =>
-        db.dbGetMessages(appState.db, conversationId),
+        db.dbGetMessages(appState.db, conversationId, false),
+    );
+    ipcMain.handle('db-get-messages-page', (_e, conversationId, beforeId, limit) =>
+        db.dbGetMessagesPage(appState.db, conversationId, beforeId, limit),
+    );
+    ipcMain.handle('db-get-message-content', (_e, messageId) =>
+        db.dbGetMessageContent(appState.db, messageId),
+    );
+    ipcMain.handle('db-get-message-part', (_e, messageId, index) =>
+        db.dbGetMessagePart(appState.db, messageId, index),
+    );
+    ipcMain.handle('db-update-message-content', (_e, messageId, content) =>
+        db.dbUpdateMessageContent(appState.db, messageId, content),
+    );
+    ipcMain.handle('db-get-turn-events', (_e, conversationId, turnId) =>
+        db.dbGetTurnEvents(appState.db, conversationId, turnId),
+    );
+    ipcMain.handle('db-search-messages', (_e, query, workspaceId) =>
+        db.dbSearchMessages(appState.db, query, workspaceId),
+    );
+    ipcMain.handle('db-create-message', (_e, message) => {
+        const created = db.dbCreateMessage(appState.db, message);
+        stats.recordAssistantMessageStats(appState, created);
+        usage.recordAssistantMessageUsage(appState, created);
+        turns.linkMessageToTurn(appState.db, created);
+        if (created.role === 'assistant') publishUnreadCount(created.conversationId);
+        void translate.autoTranslateMessage(appState.db, created, getWebContents());
+        return created;
+    });
+    ipcMain.handle('db-mark-conversation-read', (_e, conversationId, messageId) => {
+        const result = db.dbMarkConversationRead(appState.db, conversationId, messageId);
+        app.setBadgeCount(db.dbGetUnreadTotal(appState.db));
+        return result;
+    });
+    ipcMain.handle('db-get-usage-summary', (_e, workspaceId, range) =>
+        usage.getUsageSummary(appState.db, workspaceId, range),
+    );
+    ipcMain.handle('db-backup', (_e, destPath) => db.dbBackup(appState.db, destPath));
+    ipcMain.handle('db-restore', (_e, srcPath) => {
+        if (appState.runningCodex.size > 0) {
+            return { success: false, error: 'Stop running Codex turns before restoring' };
+        }
+        const result = db.dbRestore(appState.db, srcPath);
+        appState.db = result.db;
+        initServices(appState.db);
+        eventRecorder.flushSync();
+        eventRecorder = new eventLog.EventRecorder(appState.db);
+        return { success: result.success, error: result.error };
+    });
+    ipcMain.handle('db-get-schema-version', () => db.dbGetSchemaVersion(appState.db));
+
+    // ===== Token Usage =====
+    ipcMain.handle('get-usage-pricing', () => usage.getUsagePricing(appState.db));
+    ipcMain.handle('set-usage-pricing', (_e, pricing) => usage.setUsagePricing(appState.db, pricing));
+    ipcMain.handle('estimate-turn-cost', (_e, conversationId, prompt, attachments) =>
+        usage.estimateTurnCost(appState.db, appState.config, conversationId, prompt, attachments || []),
+    );
+    ipcMain.handle('get-cost-confirm-threshold', () => usage.getCostConfirmThreshold(appState.db));
+    ipcMain.handle('set-cost-confirm-threshold', (_e, thresholdUsd) =>
+        usage.setCostConfirmThreshold(appState.db, thresholdUsd),
+    );
+
+    // ===== Jobs =====
+    jobs.registerJobKind('file-index', (params, job) => fsOps.indexWorkspaceFiles(params.workspacePath, job));
+    jobs.registerJobKind('export-workspace-bundle', async (params) => {
+        const result = workspaceBundle.exportWorkspaceBundle(
+            appState.db,
+            params.workspaceId,
+            params.destPath,
+            !!params.includeCheckpoints,
+        );
+        if (!result.success) throw new Error(result.error);
+        return result;
+    });
+    jobs.registerJobKind('import-workspace-bundle', async (params) => {
+        const denied = await permissions.requirePermission(appState.db, mainWindow, params.workspacePath, 'write');
+        if (denied) throw new Error(denied.error);
+        const result = workspaceBundle.importWorkspaceBundle(appState.db, params.bundlePath, params.workspacePath, params.name);
+        if (!result.success) throw new Error(result.error);
+        return result.result;
+    });
+    ipcMain.handle('list-jobs', () => jobs.listJobs(appState.db));
+    ipcMain.handle('start-job', (_e, kind, title, params) =>
+        jobs.startJob(() => (mainWindow ? getWebContents() : null), appState.db, kind, title, params),
     );
-    ipcMain.handle('db-create-message', (_e, message) =>
-        db.dbCreateMessage(appState.db, message),
+    ipcMain.handle('cancel-job', (_e, jobId) => jobs.cancelJob(jobId));
+    ipcMain.handle('retry-job', (_e, jobId) =>
+        jobs.retryJob(() => (mainWindow ? getWebContents() : null), appState.db, jobId),
+    );
+    ipcMain.handle('dismiss-job', (_e, jobId) => jobs.dismissJob(appState.db, jobId));
+
+    // ===== Resources =====
+    ipcMain.handle('get-low-memory-mode', () => resources.getLowMemoryMode(appState.db));
+    ipcMain.handle('set-low-memory-mode', (_e, enabled) => {
+        const result = resources.setLowMemoryMode(appState.db, !!enabled);
+        // Rebuild indexes within the new limits; leaving the mode frees turn slots.
+        if (result.lowMemory) fsOps.closeFileIndexes();
+        else codex.startWaitingTurns(getWebContents(), appState);
+        return result;
+    });
+    ipcMain.handle('get-resource-usage', () => resources.getResourceUsage(appState, fsOps.fileIndexStats()));
+
+    // ===== Turn Snapshots =====
+    ipcMain.handle('list-turn-snapshots', (_e, conversationId) =>
+        snapshots.listTurnSnapshots(appState.db, conversationId),
+    );
+    ipcMain.handle('rollback-turn', async (_e, turnId) => {
+        const snapshot = snapshots.getTurnSnapshot(appState.db, turnId);
+        const denied = snapshot
+            ? (await permissions.requirePermission(appState.db, mainWindow, snapshot.cwd, 'write')) ??
+              (snapshot.files.some((f) => !f.existed)
+                  ? await permissions.requirePermission(appState.db, mainWindow, snapshot.cwd, 'delete')
+                  : null)
+            : null;
+        return denied ?? snapshots.rollbackTurn(appState.db, turnId);
+    });
+
+    // ===== Response Stats =====
+    ipcMain.handle('get-message-stats', (_e, messageId) =>
+        stats.getMessageStats(appState.db, messageId),
+    );
+    ipcMain.handle('get-conversation-stats', (_e, conversationId) =>
+        stats.getConversationStats(appState.db, conversationId),
+    );
+
+    // ===== Conversation Templates =====
+    ipcMain.handle('list-conversation-templates', () =>
+        templates.listConversationTemplates(appState.db),
+    );
+    ipcMain.handle('save-conversation-template', (_e, template) =>
+        templates.saveConversationTemplate(appState.db, template),
+    );
+    ipcMain.handle('delete-conversation-template', (_e, id) =>
+        templates.deleteConversationTemplate(appState.db, id),
+    );
+    ipcMain.handle('create-conversation-from-template', (_e, templateId, conversationId, workspaceId) =>
+        templates.createConversationFromTemplate(appState.db, templateId, conversationId, workspaceId),
+    );
+
+    // ===== Prompt Templates =====
+    ipcMain.handle('list-prompt-templates', () =>
+        templates.listPromptTemplates(appState.db),
+    );
+    ipcMain.handle('save-prompt-template', (_e, template) =>
+        templates.savePromptTemplate(appState.db, template),
+    );
+    ipcMain.handle('delete-prompt-template', (_e, id) =>
+        templates.deletePromptTemplate(appState.db, id),
+    );
+    ipcMain.handle('render-prompt-template', (_e, id, values, workspaceId) =>
+        templates.renderPromptTemplate(appState.db, id, values, workspaceId),
     );
 }
 
 app.whenReady().then(() => {
     appState = createAppState();
+    turns.closeStaleTurns(appState.db);
+    tempdirs.cleanupTempDirs(appState);
+    eventRecorder = new eventLog.EventRecorder(appState.db);
+    startupIntent = startup.resolveStartupIntent(appState.db);
+    void protocol.detectCodexVersion();
     registerIpcHandlers();
     createWindow();
+    app.setBadgeCount(db.dbGetUnreadTotal(appState.db));
+    stopWatchdog = watchdog.startWatchdog(() => (mainWindow ? getWebContents() : null), appState);
+    stopChoreScheduler = chores.startChoreScheduler(() => (mainWindow ? getWebContents() : null), appState);
+    stopScheduler = scheduler.startScheduler(() => (mainWindow ? getWebContents() : null), appState);
+    stopWatchRules = watch.startWatchRules(() => (mainWindow ? getWebContents() : null), appState);
+    stopHousekeeping = housekeeping.startHousekeeping(() => (mainWindow ? getWebContents() : null), appState);
+    stopMcpMonitor = mcp.startMcpMonitor(() => (mainWindow ? getWebContents() : null));
+    stopAuthWatcher = auth.startAuthWatcher(() => (mainWindow ? getWebContents() : null));
+    apiServer.applyApiServerSettings(appState, () => (mainWindow ? getWebContents() : null));
 
     app.on('activate', () => {
         if (BrowserWindow.getAllWindows().length === 0) {
@@ -209,13 +928,28 @@ app.on('window-all-closed', () => {
 });
 
 app.on('before-quit', () => {
+    stopWatchdog?.();
+    stopChoreScheduler?.();
+    stopScheduler?.();
+    stopWatchRules?.();
+    stopHousekeeping?.();
+    stopMcpMonitor?.();
+    stopAuthWatcher?.();
+    apiServer.stopApiServer();
+    fsOps.closeFileIndexes();
+    workspaceWatcher.stopWorkspaceWatcher();
+    tempdirs.cleanupTempDirs(appState);
+    eventRecorder?.flushSync();
     // Clean up running processes
     for (const [, proc] of appState.runningCodex) {
-        try { proc.child.kill(); } catch { }
+        try { proc.child?.kill(); } catch { }
     }
     for (const [, child] of appState.ptyTerminals) {
         try { child.kill(); } catch { }
     }
+    for (const [commandId] of appState.runningCommands) {
+        shell.killCommand(appState, commandId);
+    }
     // Close database
     try { appState.db.close(); } catch { }
 });
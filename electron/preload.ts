@@ -15,57 +15,258 @@ contextBridge.exposeInMainWorld('codexApi', {
     setYoloMode: (enabled: boolean) => ipcRenderer.invoke('set-yolo-mode', enabled),
     getYoloMode: () => ipcRenderer.invoke('get-yolo-mode'),
     getModels: () => ipcRenderer.invoke('get-models'),
+    refreshModels: () => ipcRenderer.invoke('refresh-models'),
+    getCustomModels: () => ipcRenderer.invoke('get-custom-models'),
+    setCustomModels: (models: any[]) => ipcRenderer.invoke('set-custom-models', models),
     getModel: () => ipcRenderer.invoke('get-model'),
     setModel: (modelId: string) => ipcRenderer.invoke('set-model', modelId),
     setCliOptions: (options: any) => ipcRenderer.invoke('set-cli-options', options),
     getCliOptions: () => ipcRenderer.invoke('get-cli-options'),
+    getWorkspaceCliOptions: (workspaceId: string) => ipcRenderer.invoke('get-workspace-cli-options', workspaceId),
+    setWorkspaceCliOptions: (workspaceId: string, options: any) =>
+        ipcRenderer.invoke('set-workspace-cli-options', workspaceId, options),
+    getCodexProfiles: () => ipcRenderer.invoke('get-codex-profiles'),
+    validateCodexProfile: (name: string) => ipcRenderer.invoke('validate-codex-profile', name),
 
     // ACP / Workspace
     initAcp: () => ipcRenderer.invoke('init-acp'),
     openWorkspace: () => ipcRenderer.invoke('open-workspace'),
     switchWorkspace: (workspaceId: string, cwd: string) =>
         ipcRenderer.invoke('switch-workspace', workspaceId, cwd),
+    watchPaths: (workspaceId: string, globs: string[]) => ipcRenderer.invoke('watch-paths', workspaceId, globs),
+    onWorkspaceFileChanged: (callback: (payload: { workspaceId: string; changes: any[] }) => void): UnlistenFn =>
+        onEvent<{ workspaceId: string; changes: any[] }>('workspace-file-changed', callback),
+
+    // Startup
+    getStartupIntent: () => ipcRenderer.invoke('get-startup-intent'),
+    getStartupSettings: () => ipcRenderer.invoke('get-startup-settings'),
+    setStartupSettings: (settings: any) => ipcRenderer.invoke('set-startup-settings', settings),
+    recordLastSession: (workspaceId: string, conversationId?: string) =>
+        ipcRenderer.invoke('record-last-session', workspaceId, conversationId),
 
     // Codex
     checkCodex: () => ipcRenderer.invoke('check-codex'),
-    installCodex: () => ipcRenderer.invoke('install-codex'),
-    streamCodex: (conversationId: string, prompt: string, conversationHistory?: any[]) =>
-        ipcRenderer.invoke('stream-codex', conversationId, prompt, conversationHistory),
-    cancelPrompt: (conversationId: string) => ipcRenderer.invoke('cancel-prompt', conversationId),
+    getCodexProtocol: () => ipcRenderer.invoke('get-codex-protocol'),
+    installCodex: (method?: string) => ipcRenderer.invoke('install-codex', method),
+    upgradeCodex: (method?: string) => ipcRenderer.invoke('upgrade-codex', method),
+    getCodexInstallMethods: () => ipcRenderer.invoke('get-codex-install-methods'),
+    setCodexInstallMethod: (method: string) => ipcRenderer.invoke('set-codex-install-method', method),
+    getCodexVersion: () => ipcRenderer.invoke('get-codex-version'),
+    listAgentBackends: () => ipcRenderer.invoke('list-agent-backends'),
+    streamCodex: (conversationId: string, prompt: string, conversationHistory?: any[], attachments?: any[]) =>
+        ipcRenderer.invoke('stream-codex', conversationId, prompt, conversationHistory, attachments),
+    streamCodexPlan: (conversationId: string, prompt: string, conversationHistory?: any[], attachments?: any[]) =>
+        ipcRenderer.invoke('stream-codex-plan', conversationId, prompt, conversationHistory, attachments),
+    captureScreen: (region?: any, displayId?: number, hideWindow?: boolean) =>
+        ipcRenderer.invoke('capture-screen', region, displayId, hideWindow),
+    checkPromptPaths: (conversationId: string, prompt: string) =>
+        ipcRenderer.invoke('check-prompt-paths', conversationId, prompt),
+    analyzePrompt: (text: string, workspaceId: string, conversationId?: string) =>
+        ipcRenderer.invoke('analyze-prompt', text, workspaceId, conversationId),
+    cancelPrompt: (conversationId: string, graceful?: boolean) =>
+        ipcRenderer.invoke('cancel-prompt', conversationId, graceful),
+    getStreamSnapshot: (conversationId: string) => ipcRenderer.invoke('get-stream-snapshot', conversationId),
+    getStreamBuffer: (conversationId: string, sinceSeq?: number) =>
+        ipcRenderer.invoke('get-stream-buffer', conversationId, sinceSeq),
+    subscribeStream: (conversationId: string, kinds?: string[]) =>
+        ipcRenderer.invoke('subscribe-stream', conversationId, kinds),
+    unsubscribeStream: (conversationId?: string) => ipcRenderer.invoke('unsubscribe-stream', conversationId),
+    getQueueMode: () => ipcRenderer.invoke('get-queue-mode'),
+    setQueueMode: (enabled: boolean) => ipcRenderer.invoke('set-queue-mode', enabled),
+    getReviewMode: () => ipcRenderer.invoke('get-review-mode'),
+    setReviewMode: (enabled: boolean) => ipcRenderer.invoke('set-review-mode', enabled),
+    getPendingReview: (conversationId: string) => ipcRenderer.invoke('get-pending-review', conversationId),
+    approveReview: (conversationId: string, scope?: any) => ipcRenderer.invoke('approve-review', conversationId, scope),
+    executePlan: (conversationId: string, stepIndices: number[]) =>
+        ipcRenderer.invoke('execute-plan', conversationId, stepIndices),
+    rejectReview: (conversationId: string) => ipcRenderer.invoke('reject-review', conversationId),
+    getCancelGracePeriod: () => ipcRenderer.invoke('get-cancel-grace-period'),
+    setCancelGracePeriod: (ms: number) => ipcRenderer.invoke('set-cancel-grace-period', ms),
+    getHistorySettings: () => ipcRenderer.invoke('get-history-settings'),
+    setHistorySettings: (settings: any) => ipcRenderer.invoke('set-history-settings', settings),
+    getHistorySummary: (conversationId: string) => ipcRenderer.invoke('get-history-summary', conversationId),
+    clearHistorySummary: (conversationId: string) => ipcRenderer.invoke('clear-history-summary', conversationId),
+    getPromptQueue: (conversationId: string) => ipcRenderer.invoke('get-prompt-queue', conversationId),
+    clearPromptQueue: (conversationId: string) => ipcRenderer.invoke('clear-prompt-queue', conversationId),
+    getMaxConcurrentTurns: () => ipcRenderer.invoke('get-max-concurrent-turns'),
+    setMaxConcurrentTurns: (limit: number) => ipcRenderer.invoke('set-max-concurrent-turns', limit),
+    getRunningTurns: () => ipcRenderer.invoke('get-running-turns'),
+    allocateTempDir: (conversationId: string) => ipcRenderer.invoke('allocate-temp-dir', conversationId),
+    releaseTempDirs: (conversationId: string) => ipcRenderer.invoke('release-temp-dirs', conversationId),
+    setConversationCwd: (conversationId: string, cwdPath: string | null) =>
+        ipcRenderer.invoke('set-conversation-cwd', conversationId, cwdPath),
+    moveConversation: (conversationId: string, targetWorkspaceId: string, migrateCwd: boolean) =>
+        ipcRenderer.invoke('move-conversation', conversationId, targetWorkspaceId, migrateCwd),
     debugLog: (msg: string) => ipcRenderer.invoke('debug-log', msg),
+    setEventLogEnabled: (enabled: boolean) => ipcRenderer.invoke('set-event-log-enabled', enabled),
+    getEventLogStatus: () => ipcRenderer.invoke('get-event-log-status'),
+    getRecentEventLog: (limit?: number) => ipcRenderer.invoke('get-recent-event-log', limit),
+    getNotificationPrefs: () => ipcRenderer.invoke('get-notification-prefs'),
+    setNotificationPrefs: (prefs: any) => ipcRenderer.invoke('set-notification-prefs', prefs),
+    getNotificationRoutes: () => ipcRenderer.invoke('get-notification-routes'),
+    saveNotificationRoute: (route: any) => ipcRenderer.invoke('save-notification-route', route),
+    deleteNotificationRoute: (id: string) => ipcRenderer.invoke('delete-notification-route', id),
+    testNotificationRoute: (workspaceId: string, event: string, dryRun?: boolean) =>
+        ipcRenderer.invoke('test-notification-route', workspaceId, event, dryRun),
+    onNotificationClicked: (callback: (payload: { cid?: string; channel: string }) => void): UnlistenFn =>
+        onEvent<{ cid?: string; channel: string }>('notification-clicked', callback),
+    getNarrationEnabled: () => ipcRenderer.invoke('get-narration-enabled'),
+    setNarrationEnabled: (enabled: boolean) => ipcRenderer.invoke('set-narration-enabled', enabled),
+    onNarration: (callback: (narration: { cid: string; text: string; priority: 'polite' | 'assertive' }) => void): UnlistenFn =>
+        onEvent<{ cid: string; text: string; priority: 'polite' | 'assertive' }>('a11y-narration', callback),
+    onUnreadCount: (callback: (cid: string, unreadCount: number) => void): UnlistenFn =>
+        onEvent<{ cid: string; unreadCount: number }>('conversation-unread-count', (p) => callback(p.cid, p.unreadCount)),
+    listWebhooks: () => ipcRenderer.invoke('list-webhooks'),
+    addWebhook: (url: string, events: string[], secret?: string) => ipcRenderer.invoke('add-webhook', url, events, secret),
+    removeWebhook: (id: string) => ipcRenderer.invoke('remove-webhook', id),
+    setWebhookEnabled: (id: string, enabled: boolean) => ipcRenderer.invoke('set-webhook-enabled', id, enabled),
+    testWebhook: (id: string) => ipcRenderer.invoke('test-webhook', id),
+    getApiServerSettings: () => ipcRenderer.invoke('get-api-server-settings'),
+    setApiServerSettings: (settings: any) => ipcRenderer.invoke('set-api-server-settings', settings),
+    getApiServerStatus: () => ipcRenderer.invoke('get-api-server-status'),
+    onApiConversationUpdated: (callback: (cid: string) => void): UnlistenFn =>
+        onEvent<{ cid: string }>('api-conversation-updated', (p) => callback(p.cid)),
     runCodexCommand: (subcommand: string, args: string[], cwd?: string) =>
         ipcRenderer.invoke('run-codex-command', subcommand, args, cwd),
     updateTitleBarOverlay: (color: string, symbolColor: string) =>
         ipcRenderer.invoke('update-title-bar-overlay', color, symbolColor),
-    respondToApproval: (requestId: string, approved: boolean) =>
-        ipcRenderer.invoke('respond-to-approval', requestId, approved),
+    respondToApproval: (requestId: string, decision: boolean | string, message?: string) =>
+        ipcRenderer.invoke('respond-to-approval', requestId, decision, message),
+    listOrphanProcesses: () => ipcRenderer.invoke('list-orphan-processes'),
 
     // Auth
-    codexLogin: (method?: string, apiKey?: string) => ipcRenderer.invoke('codex-login', method, apiKey),
+    codexLogin: (method?: string) => ipcRenderer.invoke('codex-login', method),
+    cancelLogin: () => ipcRenderer.invoke('cancel-login'),
     codexLogout: () => ipcRenderer.invoke('codex-logout'),
     codexLoginMethods: () => ipcRenderer.invoke('codex-login-methods'),
     getUser: () => ipcRenderer.invoke('get-user'),
+    secretSet: (name: string, value: string) => ipcRenderer.invoke('secret-set', name, value),
+    secretGet: (name: string) => ipcRenderer.invoke('secret-get', name),
+    secretDelete: (name: string) => ipcRenderer.invoke('secret-delete', name),
+    getSecretSettings: () => ipcRenderer.invoke('get-secret-settings'),
+    setSecretSettings: (settings: any) => ipcRenderer.invoke('set-secret-settings', settings),
+    listAccounts: () => ipcRenderer.invoke('list-accounts'),
+    addAccount: (name: string, method?: string) => ipcRenderer.invoke('add-account', name, method),
+    saveCurrentAccount: (name: string) => ipcRenderer.invoke('save-current-account', name),
+    switchAccount: (name: string) => ipcRenderer.invoke('switch-account', name),
+    removeAccount: (name: string) => ipcRenderer.invoke('remove-account', name),
 
     // File System
-    searchFiles: (workspacePath: string, query: string) =>
-        ipcRenderer.invoke('search-files', workspacePath, query),
+    searchFiles: (workspacePath: string, query: string, conversationId?: string) =>
+        ipcRenderer.invoke('search-files', workspacePath, query, conversationId),
+    searchFileContents: (workspacePath: string, query: string, options?: Record<string, unknown>) =>
+        ipcRenderer.invoke('search-file-contents', workspacePath, query, options),
     readFileContent: (filePath: string, workspacePath?: string) =>
         ipcRenderer.invoke('read-file-content', filePath, workspacePath),
-    writeFile: (filePath: string, content: string, workspacePath?: string) =>
-        ipcRenderer.invoke('write-file', filePath, content, workspacePath),
-    listDirectory: (dirPath: string, workspacePath?: string) =>
-        ipcRenderer.invoke('list-directory', dirPath, workspacePath),
+    readFileRange: (filePath: string, offset: number, maxBytes: number, workspacePath?: string) =>
+        ipcRenderer.invoke('read-file-range', filePath, offset, maxBytes, workspacePath),
+    readFileHex: (filePath: string, offset: number, length: number, workspacePath?: string) =>
+        ipcRenderer.invoke('read-file-hex', filePath, offset, length, workspacePath),
+    getProtectedPaths: () => ipcRenderer.invoke('get-protected-paths'),
+    setProtectedPaths: (settings: any) => ipcRenderer.invoke('set-protected-paths', settings),
+    allowProtectedFile: (filePath: string, workspacePath: string) =>
+        ipcRenderer.invoke('allow-protected-file', filePath, workspacePath),
+    revokeProtectedFile: (filePath: string) => ipcRenderer.invoke('revoke-protected-file', filePath),
+    writeFile: (filePath: string, content: string, workspacePath?: string, options?: { createBackup?: boolean }) =>
+        ipcRenderer.invoke('write-file', filePath, content, workspacePath, options),
+    restoreFileBackup: (backupId: string, workspacePath?: string) =>
+        ipcRenderer.invoke('restore-file-backup', backupId, workspacePath),
+    listDirectory: (dirPath: string, workspacePath?: string, options?: { followSymlinks?: boolean }) =>
+        ipcRenderer.invoke('list-directory', dirPath, workspacePath, options),
     fileExists: (filePath: string, workspacePath?: string) =>
         ipcRenderer.invoke('file-exists', filePath, workspacePath),
+    createDirectory: (dirPath: string, workspacePath?: string) =>
+        ipcRenderer.invoke('create-directory', dirPath, workspacePath),
+    deletePath: (targetPath: string, workspacePath?: string, options?: { recursive?: boolean; toTrash?: boolean }) =>
+        ipcRenderer.invoke('delete-path', targetPath, workspacePath, options),
+    renamePath: (targetPath: string, newName: string, workspacePath?: string) =>
+        ipcRenderer.invoke('rename-path', targetPath, newName, workspacePath),
+    copyPath: (sourcePath: string, destinationPath: string, workspacePath?: string, overwrite?: boolean) =>
+        ipcRenderer.invoke('copy-path', sourcePath, destinationPath, workspacePath, overwrite),
+    movePath: (sourcePath: string, destinationPath: string, workspacePath?: string, overwrite?: boolean) =>
+        ipcRenderer.invoke('move-path', sourcePath, destinationPath, workspacePath, overwrite),
     openInEditor: (filePath: string, editor?: string) =>
         ipcRenderer.invoke('open-in-editor', filePath, editor),
 
+    // Archives
+    listArchive: (archivePath: string, workspacePath?: string) =>
+        ipcRenderer.invoke('list-archive', archivePath, workspacePath),
+    extractArchive: (archivePath: string, dest: string, workspacePath?: string, members?: string[]) =>
+        ipcRenderer.invoke('extract-archive', archivePath, dest, workspacePath, members),
+
+    // Permissions
+    permissions: {
+        revoke: (workspacePath: string, capability?: string) =>
+            ipcRenderer.invoke('revoke-permission', workspacePath, capability),
+        list: (workspacePath?: string) => ipcRenderer.invoke('list-permissions', workspacePath),
+    },
+
     // Web Search
-    webSearch: (query: string) => ipcRenderer.invoke('web-search', query),
+    webSearch: (query: string, provider?: string, maxResults?: number) =>
+        ipcRenderer.invoke('web-search', query, provider, maxResults),
+    fetchUrlContent: (url: string, mode?: string) => ipcRenderer.invoke('fetch-url-content', url, mode),
+    getWebSearchSettings: () => ipcRenderer.invoke('get-web-search-settings'),
+    setWebSearchSettings: (settings: Record<string, unknown>) => ipcRenderer.invoke('set-web-search-settings', settings),
 
     // Shell
     runCommand: (command: string, cwd: string) => ipcRenderer.invoke('run-command', command, cwd),
+    startCommand: (command: string, cwd: string) => ipcRenderer.invoke('start-command', command, cwd),
+    commandWriteStdin: (commandId: string, data: string) =>
+        ipcRenderer.invoke('command-write-stdin', commandId, data),
     killCommand: (commandId: string) => ipcRenderer.invoke('kill-command', commandId),
+    rerunCommand: (commandId: string) => ipcRenderer.invoke('rerun-command', commandId),
+    listCommandRuns: () => ipcRenderer.invoke('list-command-runs'),
+    compareCommandRuns: (runA: string, runB: string) => ipcRenderer.invoke('compare-command-runs', runA, runB),
+
+    // Git
+    git: {
+        status: (workspacePath: string) => ipcRenderer.invoke('git-status', workspacePath),
+        diff: (workspacePath: string, file?: string) => ipcRenderer.invoke('git-diff', workspacePath, file),
+        log: (workspacePath: string, limit?: number) => ipcRenderer.invoke('git-log', workspacePath, limit),
+    },
+
+    // GitHub
+    getGitHubSettings: () => ipcRenderer.invoke('get-github-settings'),
+    setGitHubSettings: (settings: any) => ipcRenderer.invoke('set-github-settings', settings),
+    createPrFromChanges: (workspaceId: string, title: string, body?: string, base?: string) =>
+        ipcRenderer.invoke('create-pr-from-changes', workspaceId, title, body, base),
+
+    // Changelog
+    generateChangelog: (workspaceId: string, since: string) =>
+        ipcRenderer.invoke('generate-changelog', workspaceId, since),
+
+    // Release
+    runRelease: (workspaceId: string, version: string, steps: any[], dryRun?: boolean) =>
+        ipcRenderer.invoke('run-release', workspaceId, version, steps, dryRun),
+    onReleaseStep: (callback: (data: any) => void): UnlistenFn => onEvent<any>('release-step', callback),
+    onReleaseOutput: (callback: (data: { releaseId: string; index: number; data: string }) => void): UnlistenFn =>
+        onEvent<{ releaseId: string; index: number; data: string }>('release-output', callback),
+
+    // Agent Instructions
+    readInstructions: (workspacePath: string) => ipcRenderer.invoke('read-instructions', workspacePath),
+    writeInstructions: (workspacePath: string, content: string, override?: boolean) =>
+        ipcRenderer.invoke('write-instructions', workspacePath, content, override),
+    generateStarterInstructions: (workspacePath: string) =>
+        ipcRenderer.invoke('generate-starter-instructions', workspacePath),
+    createStarterInstructions: (workspacePath: string) =>
+        ipcRenderer.invoke('create-starter-instructions', workspacePath),
+    appendInstructionsConventions: (workspacePath: string, conventions: string) =>
+        ipcRenderer.invoke('append-instructions-conventions', workspacePath, conventions),
+    getInstructionsStatus: (cwd: string) => ipcRenderer.invoke('get-instructions-status', cwd),
+    summarizeWorkspace: (workspaceId: string, force?: boolean) => ipcRenderer.invoke('summarize-workspace', workspaceId, force),
+    getWorkspaceSummary: (workspaceId: string) => ipcRenderer.invoke('get-workspace-summary', workspaceId),
+
+    // Monorepo Packages
+    detectPackages: (workspacePath: string) => ipcRenderer.invoke('detect-packages', workspacePath),
+    setConversationTargetPackage: (conversationId: string, packagePath: string | null) =>
+        ipcRenderer.invoke('set-conversation-target-package', conversationId, packagePath),
+    runTargetTests: (conversationId: string) => ipcRenderer.invoke('run-target-tests', conversationId),
+
+    // Dependencies
+    checkOutdatedDependencies: (workspacePath: string) =>
+        ipcRenderer.invoke('check-outdated-dependencies', workspacePath),
+    buildDependencyUpgradePrompt: (selected: any[]) =>
+        ipcRenderer.invoke('build-dependency-upgrade-prompt', selected),
 
     // PTY
     pty: {
@@ -73,6 +274,7 @@ contextBridge.exposeInMainWorld('codexApi', {
         write: (id: string, data: string) => ipcRenderer.invoke('pty-write', id, data),
         kill: (id: string) => ipcRenderer.invoke('pty-kill', id),
         list: () => ipcRenderer.invoke('pty-list'),
+        listAvailableShells: () => ipcRenderer.invoke('list-available-shells'),
         onData: (callback: (id: string, data: string) => void): UnlistenFn =>
             onEvent<{ id: string; data: string }>('pty-data', (p) => callback(p.id, p.data)),
         onExit: (callback: (id: string, exitCode: number) => void): UnlistenFn =>
@@ -82,10 +284,111 @@ contextBridge.exposeInMainWorld('codexApi', {
     // Teams
     sendToTeams: (webhookUrl: string, title: string, content: string) =>
         ipcRenderer.invoke('send-to-teams', webhookUrl, title, content),
+    getTurns: (conversationId: string, status?: string) => ipcRenderer.invoke('get-turns', conversationId, status),
+    getTurn: (turnId: string) => ipcRenderer.invoke('get-turn', turnId),
+    getTurnForMessage: (messageId: string) => ipcRenderer.invoke('get-turn-for-message', messageId),
+    rateTurn: (turnId: string, outcome: string | null, comment?: string) =>
+        ipcRenderer.invoke('rate-turn', turnId, outcome, comment),
+    getTurnDiffs: (conversationId: string, turnId?: string) => ipcRenderer.invoke('get-turn-diffs', conversationId, turnId),
+    getMcpCalls: (turnId: string) => ipcRenderer.invoke('get-mcp-calls', turnId),
+    getMcpServers: () => ipcRenderer.invoke('get-mcp-servers'),
+    getMcpServerStatuses: () => ipcRenderer.invoke('get-mcp-server-statuses'),
+    restartMcpServer: (name: string) => ipcRenderer.invoke('restart-mcp-server', name),
+    onMcpServerStatus: (callback: (status: any) => void): UnlistenFn => onEvent<any>('mcp-server-status', callback),
+    getArtifactTheme: () => ipcRenderer.invoke('get-artifact-theme'),
+    setArtifactTheme: (theme: string) => ipcRenderer.invoke('set-artifact-theme', theme),
+
+    // Chores
+    chores: {
+        list: (workspaceId?: string) => ipcRenderer.invoke('list-chores', workspaceId),
+        save: (chore: any) => ipcRenderer.invoke('save-chore', chore),
+        delete: (id: string) => ipcRenderer.invoke('delete-chore', id),
+        runNow: (id: string) => ipcRenderer.invoke('run-chore-now', id),
+        listRuns: (workspaceId?: string, status?: string) =>
+            ipcRenderer.invoke('list-chore-runs', workspaceId, status),
+        applyRun: (runId: string) => ipcRenderer.invoke('apply-chore-run', runId),
+        dismissRun: (runId: string) => ipcRenderer.invoke('dismiss-chore-run', runId),
+        onRunCompleted: (callback: (run: any) => void): UnlistenFn =>
+            onEvent<any>('chore-run-completed', callback),
+    },
+
+    // Scheduled Tasks
+    scheduler: {
+        list: (workspaceId?: string) => ipcRenderer.invoke('list-scheduled-tasks', workspaceId),
+        save: (task: any) => ipcRenderer.invoke('save-scheduled-task', task),
+        delete: (id: string) => ipcRenderer.invoke('delete-scheduled-task', id),
+        runNow: (id: string) => ipcRenderer.invoke('run-scheduled-task-now', id),
+        validateCron: (expression: string) => ipcRenderer.invoke('validate-cron', expression),
+        onTaskCompleted: (callback: (result: any) => void): UnlistenFn =>
+            onEvent<any>('scheduled-task-completed', callback),
+    },
+
+    // Watch Rules
+    watchRules: {
+        list: (workspaceId?: string) => ipcRenderer.invoke('list-watch-rules', workspaceId),
+        create: (workspaceId: string, glob: string, promptTemplate: string, debounceMs?: number, maxPerHour?: number) =>
+            ipcRenderer.invoke('watch-and-prompt', workspaceId, glob, promptTemplate, debounceMs, maxPerHour),
+        setEnabled: (ruleId: string, enabled: boolean) => ipcRenderer.invoke('set-watch-rule-enabled', ruleId, enabled),
+        delete: (ruleId: string) => ipcRenderer.invoke('delete-watch-rule', ruleId),
+        onTriggered: (callback: (data: any) => void): UnlistenFn => onEvent<any>('watch-rule-triggered', callback),
+        onSkipped: (callback: (data: any) => void): UnlistenFn => onEvent<any>('watch-rule-skipped', callback),
+    },
+
+    // Launch Presets
+    presets: {
+        list: () => ipcRenderer.invoke('list-presets'),
+        save: (preset: any) => ipcRenderer.invoke('save-preset', preset),
+        setStarred: (id: string, starred: boolean) => ipcRenderer.invoke('set-preset-starred', id, starred),
+        delete: (id: string) => ipcRenderer.invoke('delete-preset', id),
+        apply: (presetId: string, conversationId: string) =>
+            ipcRenderer.invoke('apply-preset', presetId, conversationId),
+    },
+
+    // Prompt History
+    getPromptHistory: (workspaceId?: string, prefix?: string, limit?: number) =>
+        ipcRenderer.invoke('get-prompt-history', workspaceId, prefix, limit),
+    clearPromptHistory: (workspaceId?: string) => ipcRenderer.invoke('clear-prompt-history', workspaceId),
+
+    // Spell Check
+    checkPromptText: (text: string, lang?: string) => ipcRenderer.invoke('check-prompt-text', text, lang),
+
+    // Translation
+    translateText: (text: string, targetLang: string) =>
+        ipcRenderer.invoke('translate-text', text, targetLang),
+    getTranslationSettings: () => ipcRenderer.invoke('get-translation-settings'),
+    setTranslationSettings: (settings: any) => ipcRenderer.invoke('set-translation-settings', settings),
+    setConversationAutoTranslate: (conversationId: string, targetLang: string | null) =>
+        ipcRenderer.invoke('set-conversation-auto-translate', conversationId, targetLang),
+    onMessageTranslated: (callback: (event: { cid: string; messageId: string; targetLang: string; content: string }) => void): UnlistenFn =>
+        onEvent<{ cid: string; messageId: string; targetLang: string; content: string }>('message-translated', callback),
+
+    // Conversation Notes / Export
+    getConversationNotes: (conversationId: string) => ipcRenderer.invoke('get-conversation-notes', conversationId),
+    setConversationNotes: (conversationId: string, notes: string, includeInPrompt?: boolean) =>
+        ipcRenderer.invoke('set-conversation-notes', conversationId, notes, includeInPrompt),
+    exportConversation: (conversationId: string, format?: string, includeNotes?: boolean) =>
+        ipcRenderer.invoke('export-conversation', conversationId, format, includeNotes),
+    importConversation: (jsonPath: string, workspaceId: string) =>
+        ipcRenderer.invoke('import-conversation', jsonPath, workspaceId),
+    exportWorkspaceBundle: (workspaceId: string, destPath: string, includeCheckpoints?: boolean) =>
+        ipcRenderer.invoke('export-workspace-bundle', workspaceId, destPath, includeCheckpoints),
+    importWorkspaceBundle: (bundlePath: string, workspacePath: string, name?: string) =>
+        ipcRenderer.invoke('import-workspace-bundle', bundlePath, workspacePath, name),
+
+    // Housekeeping
+    getHousekeepingSuggestions: () => ipcRenderer.invoke('get-housekeeping-suggestions'),
+    getHousekeepingSettings: () => ipcRenderer.invoke('get-housekeeping-settings'),
+    setHousekeepingSettings: (settings: any) => ipcRenderer.invoke('set-housekeeping-settings', settings),
+    archiveWorkspace: (workspaceId: string) => ipcRenderer.invoke('archive-workspace', workspaceId),
+    exportAndPruneWorkspace: (workspaceId: string, destPath: string) =>
+        ipcRenderer.invoke('export-and-prune-workspace', workspaceId, destPath),
+    onHousekeepingSuggestions: (callback: (suggestions: any[]) => void): UnlistenFn =>
+        onEvent<any[]>('housekeeping-suggestions', callback),
 
     // Database
     db: {
         getState: () => ipcRenderer.invoke('db-get-state'),
+        getChanges: (sinceToken: string) => ipcRenderer.invoke('db-get-changes', sinceToken),
         createWorkspace: (id: string, name: string, path: string) =>
             ipcRenderer.invoke('db-create-workspace', id, name, path),
         deleteWorkspace: (id: string) => ipcRenderer.invoke('db-delete-workspace', id),
@@ -93,14 +396,91 @@ contextBridge.exposeInMainWorld('codexApi', {
             ipcRenderer.invoke('db-update-workspace-name', id, name),
         getConversations: (workspaceId: string) =>
             ipcRenderer.invoke('db-get-conversations', workspaceId),
+        getConversationGroups: (workspaceId: string) =>
+            ipcRenderer.invoke('db-get-conversation-groups', workspaceId),
         createConversation: (id: string, workspaceId: string, title: string) =>
             ipcRenderer.invoke('db-create-conversation', id, workspaceId, title),
+        listConversations: (workspaceId: string, options?: any) =>
+            ipcRenderer.invoke('db-list-conversations', workspaceId, options),
+        setConversationArchived: (id: string, archived: boolean) =>
+            ipcRenderer.invoke('db-set-conversation-archived', id, archived),
+        setConversationPinned: (id: string, pinned: boolean) =>
+            ipcRenderer.invoke('db-set-conversation-pinned', id, pinned),
+        forkConversation: (conversationId: string, fromMessageId: string, newTitle?: string) =>
+            ipcRenderer.invoke('db-fork-conversation', conversationId, fromMessageId, newTitle),
         updateConversationTitle: (id: string, title: string) =>
             ipcRenderer.invoke('db-update-conversation-title', id, title),
         deleteConversation: (id: string) => ipcRenderer.invoke('db-delete-conversation', id),
         getMessages: (conversationId: string) =>
             ipcRenderer.invoke('db-get-messages', conversationId),
+        getMessagesPage: (conversationId: string, beforeId?: string | null, limit?: number) =>
+            ipcRenderer.invoke('db-get-messages-page', conversationId, beforeId, limit),
         createMessage: (message: any) => ipcRenderer.invoke('db-create-message', message),
+        markConversationRead: (conversationId: string, messageId?: string) =>
+            ipcRenderer.invoke('db-mark-conversation-read', conversationId, messageId),
+        getMessageContent: (messageId: string) => ipcRenderer.invoke('db-get-message-content', messageId),
+        getMessagePart: (messageId: string, index: number) =>
+            ipcRenderer.invoke('db-get-message-part', messageId, index),
+        updateMessageContent: (messageId: string, content: string) =>
+            ipcRenderer.invoke('db-update-message-content', messageId, content),
+        getTurnEvents: (conversationId: string, turnId: string) =>
+            ipcRenderer.invoke('db-get-turn-events', conversationId, turnId),
+        searchMessages: (query: string, workspaceId?: string) =>
+            ipcRenderer.invoke('db-search-messages', query, workspaceId),
+        getUsageSummary: (workspaceId?: string, range?: string) =>
+            ipcRenderer.invoke('db-get-usage-summary', workspaceId, range),
+        backup: (destPath: string) => ipcRenderer.invoke('db-backup', destPath),
+        restore: (srcPath: string) => ipcRenderer.invoke('db-restore', srcPath),
+        getSchemaVersion: () => ipcRenderer.invoke('db-get-schema-version'),
+    },
+
+    // Token Usage
+    getUsagePricing: () => ipcRenderer.invoke('get-usage-pricing'),
+    setUsagePricing: (pricing: any) => ipcRenderer.invoke('set-usage-pricing', pricing),
+    estimateTurnCost: (conversationId: string, prompt: string, attachments?: any[]) =>
+        ipcRenderer.invoke('estimate-turn-cost', conversationId, prompt, attachments),
+    getCostConfirmThreshold: () => ipcRenderer.invoke('get-cost-confirm-threshold'),
+    setCostConfirmThreshold: (thresholdUsd: number | null) =>
+        ipcRenderer.invoke('set-cost-confirm-threshold', thresholdUsd),
+
+    // Jobs
+    listJobs: () => ipcRenderer.invoke('list-jobs'),
+    startJob: (kind: string, title: string, params: any) => ipcRenderer.invoke('start-job', kind, title, params),
+    cancelJob: (jobId: string) => ipcRenderer.invoke('cancel-job', jobId),
+    retryJob: (jobId: string) => ipcRenderer.invoke('retry-job', jobId),
+    dismissJob: (jobId: string) => ipcRenderer.invoke('dismiss-job', jobId),
+    onJobUpdated: (callback: (job: any) => void): UnlistenFn => onEvent<any>('job-updated', callback),
+
+    // Resources
+    getLowMemoryMode: () => ipcRenderer.invoke('get-low-memory-mode'),
+    setLowMemoryMode: (enabled: boolean) => ipcRenderer.invoke('set-low-memory-mode', enabled),
+    getResourceUsage: () => ipcRenderer.invoke('get-resource-usage'),
+
+    // Turn Snapshots
+    listTurnSnapshots: (conversationId: string) => ipcRenderer.invoke('list-turn-snapshots', conversationId),
+    rollbackTurn: (turnId: string) => ipcRenderer.invoke('rollback-turn', turnId),
+
+    // Response Stats
+    getMessageStats: (messageId: string) => ipcRenderer.invoke('get-message-stats', messageId),
+    getConversationStats: (conversationId: string) =>
+        ipcRenderer.invoke('get-conversation-stats', conversationId),
+
+    // Conversation Templates
+    templates: {
+        list: () => ipcRenderer.invoke('list-conversation-templates'),
+        save: (template: any) => ipcRenderer.invoke('save-conversation-template', template),
+        delete: (id: string) => ipcRenderer.invoke('delete-conversation-template', id),
+        createConversation: (templateId: string, conversationId: string, workspaceId: string) =>
+            ipcRenderer.invoke('create-conversation-from-template', templateId, conversationId, workspaceId),
+    },
+
+    // Prompt Templates
+    promptTemplates: {
+        list: () => ipcRenderer.invoke('list-prompt-templates'),
+        save: (template: any) => ipcRenderer.invoke('save-prompt-template', template),
+        delete: (id: string) => ipcRenderer.invoke('delete-prompt-template', id),
+        render: (id: string, values: Record<string, string>, workspaceId?: string) =>
+            ipcRenderer.invoke('render-prompt-template', id, values, workspaceId),
     },
 
     // Event Listeners (return cleanup functions)
@@ -112,8 +492,13 @@ contextBridge.exposeInMainWorld('codexApi', {
         onEvent<{ cid: string; data: string }>('codex-thinking', (p) => callback(p.cid, p.data)),
     onThinkingDelta: (callback: (cid: string, delta: string) => void): UnlistenFn =>
         onEvent<{ cid: string; data: string }>('codex-thinking-delta', (p) => callback(p.cid, p.data)),
-    onStreamEnd: (callback: (cid: string) => void): UnlistenFn =>
-        onEvent<{ cid: string }>('codex-stream-end', (p) => callback(p.cid)),
+    onStreamEnd: (
+        callback: (cid: string, info: { cancelled?: boolean; partial?: boolean; text?: string; messageId?: string }) => void,
+    ): UnlistenFn =>
+        onEvent<{ cid: string; cancelled?: boolean; partial?: boolean; text?: string; messageId?: string }>('codex-stream-end', (p) => {
+            const { cid, ...rest } = p;
+            callback(cid, rest);
+        }),
     onStreamError: (callback: (cid: string, error: string) => void): UnlistenFn =>
         onEvent<{ cid: string; data: string }>('codex-stream-error', (p) => callback(p.cid, p.data)),
     onMode: (callback: (mode: string) => void): UnlistenFn =>
@@ -147,12 +532,103 @@ contextBridge.exposeInMainWorld('codexApi', {
                 callback(cid, rest);
             },
         ),
+    onReviewRequest: (
+        callback: (cid: string, data: { turnId: string; plan: string; commands: string[]; files: string[] }) => void,
+    ): UnlistenFn =>
+        onEvent<{ cid: string; turnId: string; plan: string; commands: string[]; files: string[] }>(
+            'codex-review-request',
+            (p) => {
+                const { cid, ...rest } = p;
+                callback(cid, rest);
+            },
+        ),
+    onReviewViolation: (
+        callback: (cid: string, data: { turnId: string; items: string[]; blocked: boolean }) => void,
+    ): UnlistenFn =>
+        onEvent<{ cid: string; turnId: string; items: string[]; blocked: boolean }>(
+            'codex-review-violation',
+            (p) => {
+                const { cid, ...rest } = p;
+                callback(cid, rest);
+            },
+        ),
     onProgress: (callback: (cid: string, text: string) => void): UnlistenFn =>
         onEvent<{ cid: string; data: string }>('codex-progress', (p) => callback(p.cid, p.data)),
+    onQueueUpdated: (
+        callback: (cid: string, items: Array<{ id: string; prompt: string; queuedAt: string; position: number }>) => void,
+    ): UnlistenFn =>
+        onEvent<{ cid: string; items: Array<{ id: string; prompt: string; queuedAt: string; position: number }> }>(
+            'codex-queue-updated',
+            (p) => callback(p.cid, p.items),
+        ),
+    onUsage: (callback: (cid: string, usage: { promptTokens: number; cachedTokens: number; completionTokens: number; totalTokens: number }) => void): UnlistenFn =>
+        onEvent<{ cid: string; promptTokens: number; cachedTokens: number; completionTokens: number; totalTokens: number }>(
+            'codex-usage',
+            (p) => {
+                const { cid, ...rest } = p;
+                callback(cid, rest);
+            },
+        ),
+    onTurnStatus: (callback: (cid: string, turnId: string, status: string) => void): UnlistenFn =>
+        onEvent<{ cid: string; turnId: string; status: string }>('codex-turn-status', (p) => callback(p.cid, p.turnId, p.status)),
+    onProtocolMismatch: (callback: (cid: string, mismatch: any) => void): UnlistenFn =>
+        onEvent<{ cid: string } & Record<string, any>>('codex-protocol-mismatch', (p) => {
+            const { cid, ...rest } = p;
+            callback(cid, rest);
+        }),
+    onTurnQueued: (callback: (cid: string, turnId: string, position: number, limit: number) => void): UnlistenFn =>
+        onEvent<{ cid: string; turnId: string; position: number; limit: number }>('codex-turn-queued', (p) =>
+            callback(p.cid, p.turnId, p.position, p.limit),
+        ),
+    onFileDiff: (callback: (cid: string, diff: any) => void): UnlistenFn =>
+        onEvent<{ cid: string } & Record<string, any>>('codex-file-diff', (p) => {
+            const { cid, ...rest } = p;
+            callback(cid, rest);
+        }),
+    onMcpCall: (
+        callback: (cid: string, call: { turnId: string; callId: string; server: string; tool: string; status: string }) => void,
+    ): UnlistenFn =>
+        onEvent<{ cid: string; turnId: string; callId: string; server: string; tool: string; status: string }>(
+            'codex-mcp-call',
+            (p) => {
+                const { cid, ...rest } = p;
+                callback(cid, rest);
+            },
+        ),
+    onPathWarning: (
+        callback: (cid: string, paths: Array<{ reference: string; resolved: string }>) => void,
+    ): UnlistenFn =>
+        onEvent<{ cid: string; paths: Array<{ reference: string; resolved: string }> }>(
+            'codex-path-warning',
+            (p) => callback(p.cid, p.paths),
+        ),
+    onContextAttached: (callback: (cid: string, turnId: string, mentions: any[]) => void): UnlistenFn =>
+        onEvent<{ cid: string; turnId: string; mentions: any[] }>(
+            'codex-context-attached',
+            (p) => callback(p.cid, p.turnId, p.mentions),
+        ),
+    onConversationMoved: (
+        callback: (cid: string, data: { workspaceId: string; cwd: string; cancelled: boolean }) => void,
+    ): UnlistenFn =>
+        onEvent<{ cid: string; workspaceId: string; cwd: string; cancelled: boolean }>(
+            'codex-conversation-moved',
+            (p) => {
+                const { cid, ...rest } = p;
+                callback(cid, rest);
+            },
+        ),
+    onOrphanWarning: (callback: (data: { pid: number; command: string }) => void): UnlistenFn =>
+        onEvent<{ pid: number; command: string }>('codex-orphan-warning', callback),
     onCodexInstallProgress: (callback: (data: { status: string; message: string }) => void): UnlistenFn =>
         onEvent<{ status: string; message: string }>('codex-install-progress', callback),
+    onLoginProgress: (callback: (data: any) => void): UnlistenFn =>
+        onEvent<any>('codex-login-progress', callback),
+    onAuthChanged: (callback: (data: { reason: string; user: any | null }) => void): UnlistenFn =>
+        onEvent<{ reason: string; user: any | null }>('auth-changed', callback),
     onCommandOutput: (
         callback: (data: { commandId: string; type: 'stdout' | 'stderr'; data: string }) => void,
     ): UnlistenFn =>
         onEvent<{ commandId: string; type: 'stdout' | 'stderr'; data: string }>('command-output', callback),
+    onCommandExit: (callback: (data: { commandId: string; exitCode: number }) => void): UnlistenFn =>
+        onEvent<{ commandId: string; exitCode: number }>('command-exit', callback),
 });
@@ -0,0 +1,180 @@
+import * as fs from 'fs';
+import * as path from 'path';
+import { WebContents } from 'electron';
+import { checkCachedCredentials, codexAuthPath, codexLogin, LoginProgress, parseCodexUser } from './auth';
+import { cancelPrompt } from './codex';
+import { atomicWriteFile } from './fs-ops';
+import { AppState, CodexUser } from './models';
+import { homeDir } from './utils';
+
+const ACCOUNT_NAME = /^[\w-][\w.-]{0,63}$/;
+
+export interface CodexAccount {
+    name: string;
+    /** Who the stored snapshot signs in as; null when it cannot be read. */
+    user: CodexUser | null;
+    /** The snapshot is what ~/.codex/auth.json holds now. */
+    active: boolean;
+}
+
+/** `~/.codex-wui/accounts`, one directory with an `auth.json` snapshot per account. */
+export function accountsRoot(): string {
+    const home = homeDir();
+    if (!home) throw new Error('Unable to resolve home directory');
+    return path.join(home, '.codex-wui', 'accounts');
+}
+
+function snapshotPath(name: string): string {
+    if (!ACCOUNT_NAME.test(name)) throw new Error('Account names may only use letters, digits, "-", "_" and "."');
+    return path.join(accountsRoot(), name, 'auth.json');
+}
+
+function activeMarkerPath(): string {
+    return path.join(accountsRoot(), '.active');
+}
+
+function readActiveName(): string | null {
+    try {
+        return fs.readFileSync(activeMarkerPath(), 'utf-8').trim() || null;
+    } catch {
+        return null;
+    }
+}
+
+function readUser(file: string): CodexUser | null {
+    try {
+        return parseCodexUser(JSON.parse(fs.readFileSync(file, 'utf-8')));
+    } catch {
+        return null;
+    }
+}
+
+function writeSnapshot(name: string, data: Buffer): void {
+    const target = snapshotPath(name);
+    fs.mkdirSync(path.dirname(target), { recursive: true, mode: 0o700 });
+    atomicWriteFile(target, data, 0o600);
+}
+
+/**
+ * The account auth.json belongs to: the last one switched to, unless auth.json has since
+ * been signed in as someone else.
+ */
+function activeAccount(): string | null {
+    const name = readActiveName();
+    if (!name) return null;
+    const current = checkCachedCredentials();
+    const stored = readUser(snapshotPath(name));
+    return current && stored && current.id === stored.id ? name : null;
+}
+
+/**
+ * Copy auth.json back into the active account's snapshot, so tokens codex refreshed
+ * since the switch are not lost when switching away.
+ */
+function saveActiveSnapshot(): void {
+    const name = activeAccount();
+    const authPath = codexAuthPath();
+    if (!name || !authPath || !fs.existsSync(authPath)) return;
+    writeSnapshot(name, fs.readFileSync(authPath));
+}
+
+export function listAccounts(): CodexAccount[] {
+    let names: string[] = [];
+    try {
+        names = fs.readdirSync(accountsRoot(), { withFileTypes: true })
+            .filter((entry) => entry.isDirectory() && ACCOUNT_NAME.test(entry.name))
+            .map((entry) => entry.name)
+            .sort((a, b) => a.localeCompare(b));
+    } catch { }
+    const active = activeAccount();
+    return names
+        .filter((name) => fs.existsSync(snapshotPath(name)))
+        .map((name) => ({ name, user: readUser(snapshotPath(name)), active: name === active }));
+}
+
+/** Store the current sign-in as `name`, e.g. to keep the account that was set up before. */
+export function saveCurrentAccount(name: string): { success: boolean; account?: CodexAccount; error?: string } {
+    try {
+        const authPath = codexAuthPath();
+        if (!authPath || !fs.existsSync(authPath)) return { success: false, error: 'Not signed in to codex' };
+        writeSnapshot(name, fs.readFileSync(authPath));
+        atomicWriteFile(activeMarkerPath(), name);
+        return { success: true, account: { name, user: readUser(snapshotPath(name)), active: true } };
+    } catch (err: any) {
+        return { success: false, error: err.message };
+    }
+}
+
+/**
+ * Make `name` the signed-in account: running turns are cancelled (they were started with
+ * the other account's credentials) and auth.json is replaced with the account's snapshot
+ * in one rename.
+ */
+export function switchAccount(
+    webContents: WebContents,
+    state: AppState,
+    name: string,
+): { success: boolean; account?: CodexAccount; cancelledTurns?: number; error?: string } {
+    try {
+        const snapshot = snapshotPath(name);
+        if (!fs.existsSync(snapshot)) return { success: false, error: `No account named ${name}` };
+        const authPath = codexAuthPath();
+        if (!authPath) return { success: false, error: 'Unable to resolve home directory' };
+
+        const running = Array.from(state.runningCodex.keys());
+        for (const conversationId of running) cancelPrompt(webContents, conversationId, state);
+
+        saveActiveSnapshot();
+        fs.mkdirSync(path.dirname(authPath), { recursive: true });
+        atomicWriteFile(authPath, fs.readFileSync(snapshot), 0o600);
+        atomicWriteFile(activeMarkerPath(), name);
+        return { success: true, account: { name, user: readUser(snapshot), active: true }, cancelledTurns: running.length };
+    } catch (err: any) {
+        return { success: false, error: err.message };
+    }
+}
+
+/**
+ * Sign in to another account and store it as `name`. The current sign-in is saved and set
+ * aside for the login flow, and put back if the login fails or is cancelled.
+ */
+export async function addAccount(
+    name: string,
+    method?: string,
+    onProgress?: (progress: LoginProgress) => void,
+): Promise<{ success: boolean; account?: CodexAccount; error?: string }> {
+    let target: string;
+    try {
+        target = snapshotPath(name);
+    } catch (err: any) {
+        return { success: false, error: err.message };
+    }
+    if (fs.existsSync(target)) return { success: false, error: `An account named ${name} already exists` };
+    const authPath = codexAuthPath();
+    if (!authPath) return { success: false, error: 'Unable to resolve home directory' };
+
+    saveActiveSnapshot();
+    const previous = fs.existsSync(authPath) ? fs.readFileSync(authPath) : null;
+    if (previous) fs.rmSync(authPath);
+
+    const login = await codexLogin(method, onProgress);
+    if (!login.success || !fs.existsSync(authPath)) {
+        if (previous) atomicWriteFile(authPath, previous, 0o600);
+        return { success: false, error: login.error || 'Login did not store credentials' };
+    }
+    writeSnapshot(name, fs.readFileSync(authPath));
+    atomicWriteFile(activeMarkerPath(), name);
+    return { success: true, account: { name, user: login.user ?? readUser(target), active: true } };
+}
+
+export function removeAccount(name: string): { success: boolean; error?: string } {
+    try {
+        const snapshot = snapshotPath(name);
+        if (!fs.existsSync(snapshot)) return { success: false, error: `No account named ${name}` };
+        if (activeAccount() === name) return { success: false, error: 'Switch to another account before removing this one' };
+        fs.rmSync(path.dirname(snapshot), { recursive: true, force: true });
+        return { success: true };
+    } catch (err: any) {
+        return { success: false, error: err.message };
+    }
+}
@@ -0,0 +1,281 @@
+import { AgentBackendId, RuntimeConfig } from './models';
+import { createCodexProtocolAdapter } from './protocol';
+import { buildCodexExecArgs, expandTildePath, isCommandAvailable, parseExtraArgs, withConversationHistory } from './utils';
+
+/**
+ * What the app needs from an agent CLI to run a turn. Each backend builds its own
+ * command line and translates the JSON lines it prints into `codex exec --json` events,
+ * so the turn pipeline (stream events, tool calls, diffs, usage) stays the same for all.
+ */
+export interface AgentBackend {
+    id: AgentBackendId;
+    label: string;
+    bin: string;
+    /** Whether `resumeSessionId` continues a CLI session; otherwise history is pasted. */
+    supportsResume: boolean;
+    /** `[fullPrompt, cwd, args]` for one headless turn, like `buildCodexExecArgs`. */
+    buildArgs(
+        prompt: string,
+        cfg: RuntimeConfig,
+        history?: Array<{ role: string; content: string }>,
+        resumeSessionId?: string,
+        images?: string[],
+    ): [string, string, string[]];
+    /** A translator for one turn's output lines; it remembers tool calls awaiting their result. */
+    createMapper(): (event: any) => any[];
+}
+
+export interface AgentBackendInfo {
+    id: AgentBackendId;
+    label: string;
+    installed: boolean;
+}
+
+function runCwdFor(cfg: RuntimeConfig): string {
+    return expandTildePath(cfg.cliOptions.cwdOverride.trim() || cfg.cwd);
+}
+
+/** Other CLIs take no image flag; the paths are named in the prompt for the agent to read. */
+function withImagePaths(prompt: string, images: string[], mention: (image: string) => string): string {
+    if (images.length === 0) return prompt;
+    return `${prompt}\n\n[Attached images]\n${images.map(mention).join('\n')}`;
+}
+
+/** A tool call as a codex item: shell commands and file edits keep their own item types. */
+function toolItem(id: string, name: string, input: any, shellTools: string[], editTools: string[]): any {
+    if (shellTools.includes(name)) {
+        return { id, type: 'command_execution', command: input?.command || name, status: 'in_progress' };
+    }
+    if (editTools.includes(name)) {
+        const filePath = input?.file_path || input?.notebook_path || input?.path;
+        return {
+            id,
+            type: 'file_change',
+            status: 'in_progress',
+            changes: filePath ? [{ path: filePath, kind: /write/i.test(name) ? 'add' : 'update' }] : [],
+        };
+    }
+    const detail = input && typeof input === 'object' ? Object.values(input).find((v) => typeof v === 'string') : '';
+    return { id, type: 'command_execution', command: detail ? `${name} ${detail}` : name, status: 'in_progress' };
+}
+
+function completeToolItem(item: any, output: string, failed: boolean): any {
+    return item.type === 'command_execution'
+        ? { ...item, status: failed ? 'failed' : 'completed', aggregated_output: output, exit_code: failed ? 1 : 0 }
+        : { ...item, status: failed ? 'failed' : 'completed' };
+}
+
+function contentText(content: any): string {
+    if (typeof content === 'string') return content;
+    if (Array.isArray(content)) return content.map((c) => (typeof c === 'string' ? c : c?.text || '')).join('');
+    return content ? JSON.stringify(content) : '';
+}
+
+// ===== Codex =====
+
+const codexBackend: AgentBackend = {
+    id: 'codex',
+    label: 'Codex',
+    bin: 'codex',
+    supportsResume: true,
+    buildArgs: buildCodexExecArgs,
+    // Maps older and renamed event fields for the detected CLI version; see protocol.ts.
+    createMapper: () => createCodexProtocolAdapter(),
+};
+
+// ===== Claude Code =====
+
+const CLAUDE_SHELL_TOOLS = ['Bash'];
+const CLAUDE_EDIT_TOOLS = ['Edit', 'MultiEdit', 'Write', 'NotebookEdit'];
+
+/** `claude -p --output-format stream-json`; the model is passed only if it is a Claude one. */
+function buildClaudeArgs(
+    prompt: string,
+    cfg: RuntimeConfig,
+    history?: Array<{ role: string; content: string }>,
+    resumeSessionId?: string,
+    images: string[] = [],
+): [string, string, string[]] {
+    const fullPrompt = withImagePaths(
+        resumeSessionId ? prompt : withConversationHistory(prompt, history),
+        images,
+        (image) => image,
+    );
+    const args = ['-p', '--output-format', 'stream-json', '--verbose'];
+    if (/^(claude|opus|sonnet|haiku)/i.test(cfg.model)) args.push('--model', cfg.model);
+    if (cfg.yoloMode) {
+        args.push('--dangerously-skip-permissions');
+    } else {
+        args.push('--permission-mode', cfg.cliOptions.sandbox === 'read-only' ? 'plan' : 'acceptEdits');
+    }
+    if (resumeSessionId) args.push('--resume', resumeSessionId);
+    args.push(...parseExtraArgs(cfg.cliOptions.extraArgs));
+    args.push(fullPrompt);
+    return [fullPrompt, runCwdFor(cfg), args];
+}
+
+function createClaudeMapper(): (event: any) => any[] {
+    const tools = new Map<string, any>();
+    return (event) => {
+        switch (event?.type) {
+            case 'system':
+                return event.subtype === 'init' && event.session_id
+                    ? [{ type: 'thread.started', thread_id: event.session_id }]
+                    : [];
+            case 'assistant': {
+                const messageId = event.message?.id || 'message';
+                return (event.message?.content || []).flatMap((block: any, i: number) => {
+                    if (block?.type === 'text' && block.text) {
+                        return [{ type: 'item.completed', item: { id: `${messageId}-${i}`, type: 'agent_message', text: block.text } }];
+                    }
+                    if (block?.type === 'thinking' && block.thinking) {
+                        return [{ type: 'item.completed', item: { id: `${messageId}-${i}`, type: 'reasoning', text: block.thinking } }];
+                    }
+                    if (block?.type === 'tool_use') {
+                        const item = toolItem(block.id, block.name, block.input, CLAUDE_SHELL_TOOLS, CLAUDE_EDIT_TOOLS);
+                        tools.set(block.id, item);
+                        return [{ type: 'item.started', item }];
+                    }
+                    return [];
+                });
+            }
+            case 'user':
+                return (event.message?.content || []).flatMap((block: any) => {
+                    const item = block?.type === 'tool_result' ? tools.get(block.tool_use_id) : undefined;
+                    if (!item) return [];
+                    tools.delete(block.tool_use_id);
+                    return [{ type: 'item.completed', item: completeToolItem(item, contentText(block.content), !!block.is_error) }];
+                });
+            case 'result': {
+                const usage = event.usage || {};
+                const cached = Number(usage.cache_read_input_tokens) || 0;
+                const input = (Number(usage.input_tokens) || 0) + (Number(usage.cache_creation_input_tokens) || 0) + cached;
+                const completed = {
+                    type: 'turn.completed',
+                    usage: { input_tokens: input, cached_input_tokens: cached, output_tokens: Number(usage.output_tokens) || 0 },
+                };
+                return event.is_error
+                    ? [completed, { type: 'turn.failed', error: { message: contentText(event.result) || event.subtype || 'Turn failed' } }]
+                    : [completed];
+            }
+            default:
+                return [];
+        }
+    };
+}
+
+const claudeBackend: AgentBackend = {
+    id: 'claude',
+    label: 'Claude Code',
+    bin: 'claude',
+    supportsResume: true,
+    buildArgs: buildClaudeArgs,
+    createMapper: createClaudeMapper,
+};
+
+// ===== Gemini CLI =====
+
+const GEMINI_SHELL_TOOLS = ['run_shell_command'];
+const GEMINI_EDIT_TOOLS = ['write_file', 'replace'];
+
+/** `gemini --output-format stream-json -p`; sessions are not resumed, so history is pasted. */
+function buildGeminiArgs(
+    prompt: string,
+    cfg: RuntimeConfig,
+    history?: Array<{ role: string; content: string }>,
+    _resumeSessionId?: string,
+    images: string[] = [],
+): [string, string, string[]] {
+    const fullPrompt = withImagePaths(withConversationHistory(prompt, history), images, (image) => `@${image}`);
+    const args = ['--output-format', 'stream-json'];
+    if (/^gemini/i.test(cfg.model)) args.push('-m', cfg.model);
+    if (cfg.yoloMode) {
+        args.push('--approval-mode', 'yolo');
+    } else if (cfg.cliOptions.sandbox !== 'read-only') {
+        args.push('--approval-mode', 'auto_edit');
+    }
+    args.push(...parseExtraArgs(cfg.cliOptions.extraArgs));
+    args.push('-p', fullPrompt);
+    return [fullPrompt, runCwdFor(cfg), args];
+}
+
+/** Gemini streams message deltas; each run of them between tool calls becomes one agent message. */
+function createGeminiMapper(): (event: any) => any[] {
+    const tools = new Map<string, any>();
+    let messageCount = 0;
+    let text = '';
+    const flush = (): any[] => {
+        if (!text) return [];
+        const item = { id: `gemini-message-${messageCount++}`, type: 'agent_message', text };
+        text = '';
+        return [{ type: 'item.completed', item }];
+    };
+    return (event) => {
+        switch (event?.type) {
+            case 'init':
+                return event.session_id ? [{ type: 'thread.started', thread_id: event.session_id }] : [];
+            case 'message':
+                if (event.role !== 'assistant' || !event.content) return [];
+                text = event.delta ? text + event.content : event.content;
+                return [{ type: 'item.updated', item: { id: `gemini-message-${messageCount}`, type: 'agent_message', text } }];
+            case 'tool_use': {
+                const item = toolItem(event.tool_id, event.tool_name, event.parameters, GEMINI_SHELL_TOOLS, GEMINI_EDIT_TOOLS);
+                tools.set(event.tool_id, item);
+                return [...flush(), { type: 'item.started', item }];
+            }
+            case 'tool_result': {
+                const item = tools.get(event.tool_id);
+                if (!item) return [];
+                tools.delete(event.tool_id);
+                const output = contentText(event.output ?? event.error?.message);
+                return [{ type: 'item.completed', item: completeToolItem(item, output, event.status === 'error') }];
+            }
+            case 'error':
+                return event.severity === 'warning' ? [] : [{ type: 'error', message: event.message || 'Gemini error' }];
+            case 'result': {
+                const stats = event.stats || {};
+                const completed = {
+                    type: 'turn.completed',
+                    usage: {
+                        input_tokens: Number(stats.input_tokens) || 0,
+                        cached_input_tokens: Number(stats.cached) || 0,
+                        output_tokens: Number(stats.output_tokens) || 0,
+                    },
+                };
+                const failed = event.status === 'error'
+                    ? [{ type: 'turn.failed', error: { message: event.error?.message || 'Turn failed' } }]
+                    : [];
+                return [...flush(), completed, ...failed];
+            }
+            default:
+                return [];
+        }
+    };
+}
+
+const geminiBackend: AgentBackend = {
+    id: 'gemini',
+    label: 'Gemini CLI',
+    bin: 'gemini',
+    supportsResume: false,
+    buildArgs: buildGeminiArgs,
+    createMapper: createGeminiMapper,
+};
+
+const BACKENDS: Record<AgentBackendId, AgentBackend> = {
+    codex: codexBackend,
+    claude: claudeBackend,
+    gemini: geminiBackend,
+};
+
+/** The backend for a config's `cliOptions.agent`; codex when unset or unknown. */
+export function agentBackend(cfg: RuntimeConfig): AgentBackend {
+    return BACKENDS[cfg.cliOptions.agent as AgentBackendId] ?? codexBackend;
+}
+
+/** Every supported agent CLI and whether it is on PATH, for the workspace settings. */
+export function listAgentBackends(): Promise<AgentBackendInfo[]> {
+    return Promise.all(
+        Object.values(BACKENDS).map(async ({ id, label, bin }) => ({ id, label, installed: await isCommandAvailable(bin) })),
+    );
+}
@@ -0,0 +1,318 @@
+import Database from 'better-sqlite3';
+import * as crypto from 'crypto';
+import * as http from 'http';
+import { WebContents } from 'electron';
+import { AppState } from './models';
+import {
+    dbCreateConversation,
+    dbCreateMessage,
+    dbGetMessages,
+    dbGetMessagesPage,
+    dbGetSetting,
+    dbGetState,
+    dbSetSetting,
+} from './db';
+import { respondToApproval, streamCodex } from './codex';
+import { recordStreamEvent } from './stream-buffer';
+import { autoTranslateMessage } from './translate';
+import { generateId } from './utils';
+
+const SETTINGS_KEY = 'apiServer.settings';
+const DEFAULT_PORT = 8787;
+const MAX_BODY_BYTES = 1024 * 1024;
+const HISTORY_MESSAGES = 20;
+
+export interface ApiServerSettings {
+    /** Off by default; the server only listens on 127.0.0.1. */
+    enabled: boolean;
+    port: number;
+    /** Sent by clients as `Authorization: Bearer <token>`; generated when empty. */
+    token: string;
+}
+
+export interface ApiServerStatus {
+    running: boolean;
+    url: string | null;
+    error?: string;
+}
+
+interface ApiResponse {
+    status: number;
+    body: unknown;
+}
+
+let server: http.Server | null = null;
+let status: ApiServerStatus = { running: false, url: null };
+
+export function getApiServerSettings(db: Database.Database): ApiServerSettings {
+    return { enabled: false, port: DEFAULT_PORT, token: '', ...dbGetSetting<Partial<ApiServerSettings>>(db, SETTINGS_KEY, {}) };
+}
+
+export function setApiServerSettings(db: Database.Database, settings: Partial<ApiServerSettings>): ApiServerSettings {
+    const merged = { ...getApiServerSettings(db), ...settings };
+    const port = Math.floor(Number(merged.port));
+    merged.port = port >= 1024 && port <= 65535 ? port : DEFAULT_PORT;
+    if (!merged.token) merged.token = crypto.randomBytes(24).toString('hex');
+    dbSetSetting(db, SETTINGS_KEY, merged);
+    return merged;
+}
+
+/** Constant-time check of an `Authorization: Bearer <token>` header. */
+export function isAuthorized(header: string | undefined, token: string): boolean {
+    const match = header?.match(/^Bearer\s+(\S+)$/i);
+    if (!match || !token) return false;
+    const given = Buffer.from(match[1]);
+    const expected = Buffer.from(token);
+    return given.length === expected.length && crypto.timingSafeEqual(given, expected);
+}
+
+/** Requests addressed to another host name are refused, so DNS rebinding cannot reach the server. */
+function isLocalHost(host: string | undefined): boolean {
+    return /^(127\.0\.0\.1|localhost|\[::1\])(:\d+)?$/i.test(host ?? '');
+}
+
+function readJson(req: http.IncomingMessage): Promise<any> {
+    return new Promise((resolve, reject) => {
+        let size = 0;
+        const chunks: Buffer[] = [];
+        req.on('data', (chunk: Buffer) => {
+            size += chunk.length;
+            if (size > MAX_BODY_BYTES) {
+                reject(new Error('Request body is too large'));
+                req.destroy();
+            } else {
+                chunks.push(chunk);
+            }
+        });
+        req.on('end', () => {
+            try {
+                resolve(chunks.length ? JSON.parse(Buffer.concat(chunks).toString('utf-8')) : {});
+            } catch {
+                reject(new Error('Request body is not valid JSON'));
+            }
+        });
+        req.on('error', reject);
+    });
+}
+
+function sendJson(res: http.ServerResponse, { status: code, body }: ApiResponse): void {
+    res.writeHead(code, { 'Content-Type': 'application/json' });
+    res.end(JSON.stringify(body));
+}
+
+/**
+ * The read-only routes: `GET /workspaces`, `GET /conversations?workspaceId=` and
+ * `GET /conversations/:id/messages?before=&limit=`. Null for anything else.
+ */
+export function handleApiQuery(db: Database.Database, method: string, url: URL): ApiResponse | null {
+    if (method !== 'GET') return null;
+    if (url.pathname === '/workspaces') {
+        const workspaces = dbGetState(db).workspaces.map(({ conversations, ...workspace }) => ({
+            ...workspace,
+            conversationCount: conversations.length,
+        }));
+        return { status: 200, body: { workspaces } };
+    }
+    if (url.pathname === '/conversations') {
+        const workspaceId = url.searchParams.get('workspaceId');
+        const conversations = dbGetState(db).workspaces
+            .filter((w) => !workspaceId || w.id === workspaceId)
+            .flatMap((w) => w.conversations.map(({ messages, ...conversation }) => conversation));
+        return { status: 200, body: { conversations } };
+    }
+    const messages = url.pathname.match(/^\/conversations\/([^/]+)\/messages$/);
+    if (messages) {
+        const conversationId = decodeURIComponent(messages[1]);
+        if (!db.prepare('SELECT 1 FROM conversations WHERE id = ?').get(conversationId)) {
+            return { status: 404, body: { error: 'Conversation not found' } };
+        }
+        const limit = Number(url.searchParams.get('limit')) || undefined;
+        return { status: 200, body: dbGetMessagesPage(db, conversationId, url.searchParams.get('before'), limit) };
+    }
+    return null;
+}
+
+/**
+ * A WebContents stand-in for a turn started over the API. The turn's own events go to
+ * `onEvent` only; the GUI does not render it live and picks it up from the database.
+ * They are still numbered and kept in the replay buffer like the GUI's own turns. Events
+ * of other conversations (queued turns the run may start) still reach the GUI.
+ */
+export function apiSink(
+    state: AppState,
+    conversationId: string,
+    onEvent: (channel: string, payload: any) => void,
+    getWebContents: () => WebContents | null,
+): WebContents {
+    return {
+        send: (channel: string, ...args: any[]) => {
+            if (args[0]?.cid === conversationId) onEvent(channel, recordStreamEvent(state.streamBuffer, channel, args[0]));
+            else getWebContents()?.send(channel, ...args);
+        },
+        isDestroyed: () => false,
+    } as unknown as WebContents;
+}
+
+/**
+ * `POST /prompt` with `{ prompt, conversationId }` or `{ prompt, workspaceId, title? }` for a
+ * new conversation. Streams the turn as server-sent events named after the IPC channels
+ * without their `codex-` prefix (`stream-delta`, `approval-request`, `stream-end`, ...);
+ * with `"stream": false` the reply is one JSON object once the turn ends. The prompt and
+ * the answer are stored as messages, as the GUI does for its own turns, and the GUI is
+ * told with `api-conversation-updated` once the turn is over.
+ */
+async function handlePrompt(
+    state: AppState,
+    getWebContents: () => WebContents | null,
+    req: http.IncomingMessage,
+    res: http.ServerResponse,
+): Promise<void> {
+    const body = await readJson(req);
+    const prompt = typeof body.prompt === 'string' ? body.prompt.trim() : '';
+    if (!prompt) return sendJson(res, { status: 400, body: { error: 'prompt is required' } });
+
+    let conversationId: string = body.conversationId;
+    if (conversationId) {
+        if (!state.db.prepare('SELECT 1 FROM conversations WHERE id = ?').get(conversationId)) {
+            return sendJson(res, { status: 404, body: { error: 'Conversation not found' } });
+        }
+        if (state.runningCodex.has(conversationId)) {
+            return sendJson(res, { status: 409, body: { error: 'A turn is already running in this conversation' } });
+        }
+    } else {
+        if (!state.db.prepare('SELECT 1 FROM workspaces WHERE id = ?').get(body.workspaceId)) {
+            return sendJson(res, { status: 404, body: { error: 'Workspace not found' } });
+        }
+        conversationId = generateId('conv');
+        dbCreateConversation(state.db, conversationId, body.workspaceId, String(body.title || prompt).slice(0, 60));
+    }
+
+    const history = dbGetMessages(state.db, conversationId)
+        .slice(-HISTORY_MESSAGES)
+        .map((m) => ({ role: m.role, content: m.content }));
+    dbCreateMessage(state.db, {
+        id: generateId('msg'),
+        conversationId,
+        role: 'user',
+        content: prompt,
+        timestamp: new Date().toISOString(),
+    });
+
+    const stream = body.stream !== false;
+    let turnId: string | undefined;
+    let text = '';
+    let finished = false;
+    // `messageId` is the turn's draft message; saving under it replaces the draft.
+    const finish = (messageId: string | undefined, error?: string) => {
+        if (finished) return;
+        finished = true;
+        const content = error ? [text, `Error: ${error}`].filter(Boolean).join('\n\n') : text;
+        if (content) {
+            const created = dbCreateMessage(state.db, {
+                id: messageId || generateId('msg'),
+                conversationId,
+                role: 'assistant',
+                content,
+                timestamp: new Date().toISOString(),
+            });
+            void autoTranslateMessage(state.db, created, getWebContents());
+        }
+        getWebContents()?.send('api-conversation-updated', { cid: conversationId });
+        if (stream) res.end();
+        else if (!res.destroyed) sendJson(res, { status: error ? 500 : 200, body: { conversationId, turnId, text, error } });
+    };
+    const send = (event: string, data: unknown) => {
+        if (stream && !res.writableEnded && !res.destroyed) res.write(`event: ${event}\ndata: ${JSON.stringify(data)}\n\n`);
+    };
+
+    if (stream) {
+        res.writeHead(200, { 'Content-Type': 'text/event-stream', 'Cache-Control': 'no-cache', Connection: 'keep-alive' });
+    }
+    const sink = apiSink(state, conversationId, (channel, { cid, ...payload }) => {
+        if (channel === 'codex-stream-delta') text += payload.data ?? '';
+        send(channel.replace(/^codex-/, ''), payload);
+        if (channel === 'codex-stream-end') finish(payload.messageId, payload.cancelled ? 'Turn cancelled' : undefined);
+        else if (channel === 'codex-stream-error') finish(payload.messageId, String(payload.data ?? 'Codex failed'));
+    }, getWebContents);
+
+    const started = streamCodex(sink, conversationId, prompt, history, state);
+    turnId = started.turnId;
+    if (started.queued) {
+        // A queued turn later runs with whichever window started it, so it is not streamed.
+        finished = true;
+        if (stream) {
+            send('queued', { conversationId, turnId, position: started.position });
+            res.end();
+        } else {
+            sendJson(res, { status: 202, body: { conversationId, turnId, queued: true, position: started.position } });
+        }
+        return;
+    }
+    send('started', { conversationId, turnId });
+}
+
+async function handleRequest(
+    state: AppState,
+    getWebContents: () => WebContents | null,
+    req: http.IncomingMessage,
+    res: http.ServerResponse,
+): Promise<void> {
+    if (!isLocalHost(req.headers.host)) return sendJson(res, { status: 403, body: { error: 'Forbidden host' } });
+    if (!isAuthorized(req.headers.authorization, getApiServerSettings(state.db).token)) {
+        return sendJson(res, { status: 401, body: { error: 'Missing or invalid bearer token' } });
+    }
+    const url = new URL(req.url || '/', 'http://127.0.0.1');
+    const method = req.method || 'GET';
+
+    const query = handleApiQuery(state.db, method, url);
+    if (query) return sendJson(res, query);
+    if (method === 'POST' && url.pathname === '/prompt') return handlePrompt(state, getWebContents, req, res);
+    const approval = url.pathname.match(/^\/approvals\/([^/]+)$/);
+    if (method === 'POST' && approval) {
+        const body = await readJson(req);
+        const result = respondToApproval(decodeURIComponent(approval[1]), body.decision, state, body.message);
+        return sendJson(res, { status: result.success ? 200 : 400, body: result });
+    }
+    sendJson(res, { status: 404, body: { error: `No route for ${method} ${url.pathname}` } });
+}
+
+export function getApiServerStatus(): ApiServerStatus {
+    return status;
+}
+
+export function stopApiServer(): void {
+    server?.close();
+    server = null;
+    status = { running: false, url: null };
+}
+
+/** (Re)start the server from the saved settings, or stop it when disabled. */
+export function applyApiServerSettings(
+    state: AppState,
+    getWebContents: () => WebContents | null,
+): Promise<ApiServerStatus> {
+    stopApiServer();
+    const settings = getApiServerSettings(state.db);
+    if (!settings.enabled) return Promise.resolve(status);
+    if (!settings.token) setApiServerSettings(state.db, {});
+
+    return new Promise((resolve) => {
+        const instance = http.createServer((req, res) => {
+            handleRequest(state, getWebContents, req, res).catch((err) => {
+                if (res.headersSent) res.end();
+                else sendJson(res, { status: 400, body: { error: err.message } });
+            });
+        });
+        instance.on('error', (err) => {
+            console.error('[api-server] Failed to start:', err);
+            if (server === instance) server = null;
+            status = { running: false, url: null, error: err.message };
+            resolve(status);
+        });
+        instance.listen(settings.port, '127.0.0.1', () => {
+            status = { running: true, url: `http://127.0.0.1:${settings.port}` };
+            resolve(status);
+        });
+        server = instance;
+    });
+}
@@ -0,0 +1,320 @@
+import * as fs from 'fs';
+import * as path from 'path';
+import * as zlib from 'zlib';
+import { resolveWorkspaceScopedPath } from './fs-ops';
+
+export type ArchiveFormat = 'zip' | 'tar' | 'tar.gz';
+
+export interface ArchiveEntry {
+    /** Path inside the archive, always with forward slashes. */
+    name: string;
+    type: 'file' | 'directory' | 'symlink' | 'other';
+    size: number;
+    /** Stored size; equals `size` for tar members. */
+    compressedSize: number;
+    modifiedAt: string | null;
+}
+
+export interface ReadableEntry extends ArchiveEntry {
+    read: () => Buffer;
+}
+
+const MAX_ARCHIVE_BYTES = 512 * 1024 * 1024;
+// Guards against zip bombs: the sum of all extracted entries.
+const MAX_EXTRACT_BYTES = 2 * 1024 * 1024 * 1024;
+
+function detectFormat(data: Buffer): ArchiveFormat | null {
+    if (data.length >= 4 && data.readUInt32LE(0) === 0x04034b50) return 'zip';
+    if (data.length >= 22 && data.readUInt32LE(0) === 0x06054b50) return 'zip';
+    if (data.length >= 2 && data[0] === 0x1f && data[1] === 0x8b) return 'tar.gz';
+    if (data.length >= 262 && data.toString('latin1', 257, 262) === 'ustar') return 'tar';
+    return null;
+}
+
+function dosDateTime(date: number, time: number): string | null {
+    if (!date) return null;
+    const value = new Date(
+        ((date >> 9) & 0x7f) + 1980,
+        ((date >> 5) & 0x0f) - 1,
+        date & 0x1f,
+        time >> 11,
+        (time >> 5) & 0x3f,
+        (time & 0x1f) * 2,
+    );
+    return isNaN(value.getTime()) ? null : value.toISOString();
+}
+
+function readZip(data: Buffer): ReadableEntry[] {
+    let eocd = -1;
+    for (let i = data.length - 22; i >= Math.max(0, data.length - 22 - 0xffff); i--) {
+        if (data.readUInt32LE(i) === 0x06054b50) {
+            eocd = i;
+            break;
+        }
+    }
+    if (eocd < 0) throw new Error('Not a zip archive (no central directory)');
+    const count = data.readUInt16LE(eocd + 10);
+    let offset = data.readUInt32LE(eocd + 16);
+    if (offset === 0xffffffff) throw new Error('ZIP64 archives are not supported');
+
+    const entries: ReadableEntry[] = [];
+    for (let i = 0; i < count; i++) {
+        if (data.readUInt32LE(offset) !== 0x02014b50) throw new Error('Corrupt zip central directory');
+        const flags = data.readUInt16LE(offset + 8);
+        const method = data.readUInt16LE(offset + 10);
+        const time = data.readUInt16LE(offset + 12);
+        const date = data.readUInt16LE(offset + 14);
+        const compressedSize = data.readUInt32LE(offset + 20);
+        const size = data.readUInt32LE(offset + 24);
+        const nameLength = data.readUInt16LE(offset + 28);
+        const extraLength = data.readUInt16LE(offset + 30);
+        const commentLength = data.readUInt16LE(offset + 32);
+        const externalAttrs = data.readUInt32LE(offset + 38);
+        const localOffset = data.readUInt32LE(offset + 42);
+        const name = data.toString(flags & 0x800 ? 'utf-8' : 'latin1', offset + 46, offset + 46 + nameLength).replace(/\\/g, '/');
+        offset += 46 + nameLength + extraLength + commentLength;
+
+        const unixMode = externalAttrs >>> 16;
+        const type = name.endsWith('/')
+            ? 'directory'
+            : (unixMode & 0o170000) === 0o120000
+                ? 'symlink'
+                : 'file';
+        entries.push({
+            name,
+            type,
+            size,
+            compressedSize,
+            modifiedAt: dosDateTime(date, time),
+            read: () => {
+                if (flags & 0x1) throw new Error('Encrypted entries are not supported');
+                if (data.readUInt32LE(localOffset) !== 0x04034b50) throw new Error('Corrupt zip entry');
+                const start = localOffset + 30 + data.readUInt16LE(localOffset + 26) + data.readUInt16LE(localOffset + 28);
+                const raw = data.subarray(start, start + compressedSize);
+                if (method === 0) return Buffer.from(raw);
+                if (method === 8) return zlib.inflateRawSync(raw, { maxOutputLength: Math.max(size, 1) });
+                throw new Error(`Unsupported zip compression method ${method}`);
+            },
+        });
+    }
+    return entries;
+}
+
+function tarString(block: Buffer, start: number, length: number): string {
+    const raw = block.subarray(start, start + length);
+    const end = raw.indexOf(0);
+    return raw.toString('utf-8', 0, end < 0 ? raw.length : end);
+}
+
+function tarNumber(block: Buffer, start: number, length: number): number {
+    return parseInt(tarString(block, start, length).trim() || '0', 8) || 0;
+}
+
+function readTar(data: Buffer): ReadableEntry[] {
+    const entries: ReadableEntry[] = [];
+    let offset = 0;
+    let longName: string | null = null;
+    let paxPath: string | null = null;
+    while (offset + 512 <= data.length) {
+        const header = data.subarray(offset, offset + 512);
+        if (header.every((b) => b === 0)) break;
+        const size = tarNumber(header, 124, 12);
+        const typeflag = String.fromCharCode(header[156] || 0x30);
+        const bodyStart = offset + 512;
+        const body = data.subarray(bodyStart, bodyStart + size);
+        offset = bodyStart + Math.ceil(size / 512) * 512;
+
+        if (typeflag === 'L') {
+            longName = tarString(body, 0, body.length);
+            continue;
+        }
+        if (typeflag === 'x' || typeflag === 'g') {
+            const match = body.toString('utf-8').match(/^\d+ path=(.*)$/m);
+            if (typeflag === 'x' && match) paxPath = match[1];
+            continue;
+        }
+
+        const prefix = tarString(header, 345, 155);
+        const shortName = tarString(header, 0, 100);
+        const name = (paxPath || longName || (prefix ? `${prefix}/${shortName}` : shortName)).replace(/\\/g, '/');
+        longName = null;
+        paxPath = null;
+        const mtime = tarNumber(header, 136, 12);
+        entries.push({
+            name,
+            type: typeflag === '5' ? 'directory' : typeflag === '2' ? 'symlink' : typeflag === '0' || typeflag === '\0' || typeflag === '7' ? 'file' : 'other',
+            size,
+            compressedSize: size,
+            modifiedAt: mtime ? new Date(mtime * 1000).toISOString() : null,
+            read: () => Buffer.from(body),
+        });
+    }
+    return entries;
+}
+
+/** Parse a zip, tar or tar.gz file; entry contents are read on demand. */
+export function openArchive(resolved: string): { format: ArchiveFormat; entries: ReadableEntry[] } {
+    if (fs.statSync(resolved).size > MAX_ARCHIVE_BYTES) throw new Error('Archive is too large');
+    const data = fs.readFileSync(resolved);
+    const format = detectFormat(data);
+    if (format === 'zip') return { format, entries: readZip(data) };
+    if (format === 'tar') return { format, entries: readTar(data) };
+    if (format === 'tar.gz') {
+        return { format, entries: readTar(zlib.gunzipSync(data, { maxOutputLength: MAX_ARCHIVE_BYTES })) };
+    }
+    throw new Error('Unsupported archive format (expected zip, tar or tar.gz)');
+}
+
+const CRC_TABLE = (() => {
+    const table = new Uint32Array(256);
+    for (let n = 0; n < 256; n++) {
+        let c = n;
+        for (let k = 0; k < 8; k++) c = c & 1 ? 0xedb88320 ^ (c >>> 1) : c >>> 1;
+        table[n] = c >>> 0;
+    }
+    return table;
+})();
+
+function crc32(data: Buffer): number {
+    let crc = 0xffffffff;
+    for (const byte of data) crc = CRC_TABLE[(crc ^ byte) & 0xff] ^ (crc >>> 8);
+    return (crc ^ 0xffffffff) >>> 0;
+}
+
+/** Build a deflate-compressed zip from in-memory files. */
+export function writeZip(files: Array<{ name: string; data: Buffer }>): Buffer {
+    const locals: Buffer[] = [];
+    const centrals: Buffer[] = [];
+    let offset = 0;
+    const now = new Date();
+    const dosTime = (now.getHours() << 11) | (now.getMinutes() << 5) | (now.getSeconds() >> 1);
+    const dosDate = ((now.getFullYear() - 1980) << 9) | ((now.getMonth() + 1) << 5) | now.getDate();
+
+    for (const file of files) {
+        const name = Buffer.from(file.name, 'utf-8');
+        const compressed = zlib.deflateRawSync(file.data);
+        const crc = crc32(file.data);
+
+        const local = Buffer.alloc(30);
+        local.writeUInt32LE(0x04034b50, 0);
+        local.writeUInt16LE(20, 4);
+        local.writeUInt16LE(0x800, 6);
+        local.writeUInt16LE(8, 8);
+        local.writeUInt16LE(dosTime, 10);
+        local.writeUInt16LE(dosDate, 12);
+        local.writeUInt32LE(crc, 14);
+        local.writeUInt32LE(compressed.length, 18);
+        local.writeUInt32LE(file.data.length, 22);
+        local.writeUInt16LE(name.length, 26);
+
+        const central = Buffer.alloc(46);
+        central.writeUInt32LE(0x02014b50, 0);
+        central.writeUInt16LE(20, 4);
+        central.writeUInt16LE(20, 6);
+        central.writeUInt16LE(0x800, 8);
+        central.writeUInt16LE(8, 10);
+        central.writeUInt16LE(dosTime, 12);
+        central.writeUInt16LE(dosDate, 14);
+        central.writeUInt32LE(crc, 16);
+        central.writeUInt32LE(compressed.length, 20);
+        central.writeUInt32LE(file.data.length, 24);
+        central.writeUInt16LE(name.length, 28);
+        central.writeUInt32LE(offset, 42);
+
+        locals.push(local, name, compressed);
+        centrals.push(central, name);
+        offset += local.length + name.length + compressed.length;
+    }
+
+    const centralDirectory = Buffer.concat(centrals);
+    const end = Buffer.alloc(22);
+    end.writeUInt32LE(0x06054b50, 0);
+    end.writeUInt16LE(files.length, 8);
+    end.writeUInt16LE(files.length, 10);
+    end.writeUInt32LE(centralDirectory.length, 12);
+    end.writeUInt32LE(offset, 16);
+    return Buffer.concat([...locals, centralDirectory, end]);
+}
+
+/**
+ * Where `name` extracts to under `destRoot`, or null when it would land outside it
+ * (absolute paths, `..` segments, drive letters): the zip-slip check.
+ */
+export function archiveTargetPath(destRoot: string, name: string): string | null {
+    const normalized = name.replace(/\\/g, '/');
+    if (!normalized || normalized.startsWith('/') || /^[a-zA-Z]:/.test(normalized)) return null;
+    const target = path.resolve(destRoot, normalized);
+    return target.startsWith(destRoot + path.sep) ? target : null;
+}
+
+function selected(name: string, members?: string[]): boolean {
+    if (!members || members.length === 0) return true;
+    return members.some((member) => {
+        const prefix = member.replace(/\/+$/, '');
+        return name === member || name.replace(/\/+$/, '') === prefix || name.startsWith(`${prefix}/`);
+    });
+}
+
+export function listArchive(
+    archivePath: string,
+    workspacePath?: string,
+): { success: boolean; format?: ArchiveFormat; entries?: ArchiveEntry[]; error?: string } {
+    try {
+        const { format, entries } = openArchive(resolveWorkspaceScopedPath(archivePath, workspacePath));
+        return { success: true, format, entries: entries.map(({ read: _read, ...entry }) => entry) };
+    } catch (err: any) {
+        return { success: false, error: err.message };
+    }
+}
+
+/**
+ * Extract an archive (or only `members`, matching entries and everything under them) into
+ * `dest` inside the workspace. Entries that would escape `dest` and links are skipped
+ * and reported rather than failing the whole extraction.
+ */
+export function extractArchive(
+    archivePath: string,
+    dest: string,
+    workspacePath?: string,
+    members?: string[],
+): { success: boolean; extracted?: string[]; skipped?: Array<{ name: string; reason: string }>; error?: string } {
+    try {
+        const { entries } = openArchive(resolveWorkspaceScopedPath(archivePath, workspacePath));
+        const destResolved = resolveWorkspaceScopedPath(dest, workspacePath);
+        fs.mkdirSync(destResolved, { recursive: true });
+        const destRoot = fs.realpathSync(destResolved);
+
+        const extracted: string[] = [];
+        const skipped: Array<{ name: string; reason: string }> = [];
+        let written = 0;
+        for (const entry of entries) {
+            if (!selected(entry.name, members)) continue;
+            const target = archiveTargetPath(destRoot, entry.name);
+            if (!target) {
+                skipped.push({ name: entry.name, reason: 'outside destination' });
+                continue;
+            }
+            if (entry.type === 'symlink' || entry.type === 'other') {
+                skipped.push({ name: entry.name, reason: `${entry.type} entries are not extracted` });
+                continue;
+            }
+            // A directory created by an earlier entry could be a link out of the destination.
+            const parent = entry.type === 'directory' ? target : path.dirname(target);
+            fs.mkdirSync(parent, { recursive: true });
+            const realParent = fs.realpathSync(parent);
+            if (realParent !== destRoot && !realParent.startsWith(destRoot + path.sep)) {
+                skipped.push({ name: entry.name, reason: 'outside destination' });
+                continue;
+            }
+            if (entry.type === 'directory') continue;
+
+            written += entry.size;
+            if (written > MAX_EXTRACT_BYTES) throw new Error('Archive expands beyond the extraction limit');
+            fs.writeFileSync(target, entry.read());
+            extracted.push(path.relative(destRoot, target));
+        }
+        return { success: true, extracted, skipped };
+    } catch (err: any) {
+        return { success: false, error: err.message };
+    }
+}
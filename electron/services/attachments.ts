@@ -0,0 +1,100 @@
+import * as fs from 'fs';
+import * as path from 'path';
+import { isProtectedPath } from './fs-ops';
+import { MessageAttachment, PromptAttachment } from './models';
+import { expandTildePath, generateId, homeDir } from './utils';
+
+const MAX_ATTACHMENT_BYTES = 20 * 1024 * 1024;
+
+const IMAGE_TYPES: Record<string, string> = {
+    '.png': 'image/png',
+    '.jpg': 'image/jpeg',
+    '.jpeg': 'image/jpeg',
+    '.gif': 'image/gif',
+    '.webp': 'image/webp',
+    '.bmp': 'image/bmp',
+};
+
+export function attachmentsDir(): string {
+    const home = homeDir();
+    if (!home) throw new Error('Unable to resolve home directory');
+    return path.join(home, '.codex-wui', 'attachments');
+}
+
+function extensionFor(mimeType: string): string | null {
+    const match = Object.entries(IMAGE_TYPES).find(([, type]) => type === mimeType);
+    return match ? match[0] : null;
+}
+
+/**
+ * Decode a base64 attachment, accepting either a bare payload or a `data:` URL. The
+ * MIME type of a data URL wins over the one passed in.
+ */
+export function decodeBase64Image(data: string, mimeType?: string): { bytes: Buffer; mimeType: string } {
+    const dataUrl = data.match(/^data:([^;,]+);base64,(.*)$/s);
+    const type = dataUrl ? dataUrl[1] : mimeType || '';
+    if (!extensionFor(type)) throw new Error(`Unsupported image type: ${type || 'unknown'}`);
+    const bytes = Buffer.from((dataUrl ? dataUrl[2] : data).replace(/\s+/g, ''), 'base64');
+    if (bytes.length === 0) throw new Error('Attachment is empty');
+    return { bytes, mimeType: type };
+}
+
+/**
+ * Store prompt attachments under `~/.codex-wui/attachments` and return their metadata.
+ * Files given by path are copied so the conversation keeps working if the original moves
+ * or is deleted; base64 blobs (pasted screenshots) are written out. Only images are
+ * accepted since that is all `codex exec --image` takes, and protected files never are.
+ */
+export function saveAttachments(attachments: PromptAttachment[]): MessageAttachment[] {
+    if (!attachments.length) return [];
+    const dir = attachmentsDir();
+
+    return attachments.map((attachment): MessageAttachment => {
+        let bytes: Buffer;
+        let mimeType: string;
+        let name: string;
+
+        if (attachment.path) {
+            const source = expandTildePath(attachment.path);
+            if (isProtectedPath(source)) throw new Error(`${path.basename(source)} is a protected file`);
+            mimeType = IMAGE_TYPES[path.extname(source).toLowerCase()];
+            if (!mimeType) throw new Error(`Unsupported image type: ${path.basename(source)}`);
+            // Already stored (queued prompts, resends): reuse the copy.
+            if (path.dirname(path.resolve(source)) === dir && fs.existsSync(source)) {
+                return {
+                    id: path.basename(source, path.extname(source)),
+                    kind: 'image',
+                    name: attachment.name || path.basename(source),
+                    path: source,
+                    mimeType,
+                    size: fs.statSync(source).size,
+                };
+            }
+            if (fs.statSync(source).size > MAX_ATTACHMENT_BYTES) {
+                throw new Error(`${path.basename(source)} is larger than 20 MB`);
+            }
+            bytes = fs.readFileSync(source);
+            name = attachment.name || path.basename(source);
+        } else if (attachment.data) {
+            ({ bytes, mimeType } = decodeBase64Image(attachment.data, attachment.mimeType));
+            name = attachment.name || `image${extensionFor(mimeType)}`;
+        } else {
+            throw new Error('Attachment needs a path or data');
+        }
+        if (bytes.length > MAX_ATTACHMENT_BYTES) throw new Error(`${name} is larger than 20 MB`);
+
+        return storeImage(bytes, mimeType, name);
+    });
+}
+
+/** Write image bytes into the attachments store. */
+export function storeImage(bytes: Buffer, mimeType: string, name: string): MessageAttachment {
+    const ext = extensionFor(mimeType);
+    if (!ext) throw new Error(`Unsupported image type: ${mimeType}`);
+    const id = generateId('att');
+    const dir = attachmentsDir();
+    fs.mkdirSync(dir, { recursive: true });
+    const stored = path.join(dir, `${id}${ext}`);
+    fs.writeFileSync(stored, bytes);
+    return { id, kind: 'image', name, path: stored, mimeType, size: bytes.length };
+}
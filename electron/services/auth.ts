@@ -1,9 +1,20 @@
 import * as fs from 'fs';
 import * as path from 'path';
+import { ChildProcess, execFile } from 'child_process';
+import { WebContents } from 'electron';
 import { CodexUser } from './models';
-import { commandSpawnOptions, homeDir, spawnCommand } from './utils';
+import { scrubSecrets } from './mcp';
+import { CODEX_API_KEY_SECRET, deleteSecret, getSecret, rememberSecret } from './secrets';
+import { commandSpawnOptions, homeDir, spawnCommand, trackChild } from './utils';
 
-function codexAuthPath(): string | null {
+/** How often auth.json is checked for changes; it is replaced, not edited, so it is polled. */
+const AUTH_POLL_INTERVAL_MS = 2000;
+/** setTimeout's longest delay; a later expiry is rechecked after this long. */
+const MAX_TIMER_MS = 2 ** 31 - 1;
+
+export type AuthChangeReason = 'login' | 'logout' | 'refresh' | 'updated' | 'expired';
+
+export function codexAuthPath(): string | null {
     const home = homeDir();
     if (!home) return null;
     return path.join(home, '.codex', 'auth.json');
@@ -22,7 +33,11 @@ function parseJwtPayload(token: string): any | null {
     }
 }
 
-function parseCodexUser(auth: any): CodexUser | null {
+/**
+ * The signed-in user from auth.json. ChatGPT sign-ins expire with their access token (or
+ * the ID token when there is none); API keys do not expire.
+ */
+export function parseCodexUser(auth: any, now: number = Date.now()): CodexUser | null {
     const authMode = auth.auth_mode || 'unknown';
     const accountId = auth.tokens?.account_id || 'codex-user';
 
@@ -30,12 +45,10 @@ function parseCodexUser(auth: any): CodexUser | null {
     let authProvider = '';
 
     const idToken = auth.tokens?.id_token;
-    if (idToken) {
-        const payload = parseJwtPayload(idToken);
-        if (payload) {
-            email = payload.email || '';
-            authProvider = payload.auth_provider || '';
-        }
+    const idPayload = idToken ? parseJwtPayload(idToken) : null;
+    if (idPayload) {
+        email = idPayload.email || '';
+        authProvider = idPayload.auth_provider || '';
     }
 
     if (authMode === 'api_key') {
@@ -44,6 +57,10 @@ function parseCodexUser(auth: any): CodexUser | null {
 
     const name = email ? email.split('@')[0] || 'codex-user' : `codex-${authMode}`;
 
+    const accessToken = auth.tokens?.access_token;
+    const exp = (accessToken ? parseJwtPayload(accessToken)?.exp : undefined) ?? idPayload?.exp;
+    const expiresAt = authMode !== 'api_key' && typeof exp === 'number' ? new Date(exp * 1000).toISOString() : undefined;
+
     return {
         id: accountId,
         email,
@@ -51,6 +68,8 @@ function parseCodexUser(auth: any): CodexUser | null {
         picture: '',
         authMode,
         authProvider,
+        expiresAt,
+        expired: expiresAt ? exp * 1000 <= now : false,
     };
 }
 
@@ -67,25 +86,115 @@ export function checkCachedCredentials(): CodexUser | null {
     }
 }
 
-export function codexLogin(
+const API_KEY_PROMPT = 'Enter your OpenAI API key to sign in to Codex.';
+
+/** Run a dialog helper; a non-zero exit (the user cancelled) resolves with a null text. */
+function runDialog(command: string, args: string[]): Promise<string | null> {
+    return new Promise((resolve, reject) => {
+        trackChild(execFile(command, args, (err: any, stdout) => {
+            if (err?.code === 'ENOENT') reject(new Error(`${command} is not available`));
+            else resolve(err ? null : stdout.replace(/\r?\n$/, ''));
+        }));
+    });
+}
+
+/**
+ * Ask for the API key with the operating system's own password dialog, so the key never
+ * passes through the renderer. Resolves with null when the user cancels.
+ */
+export async function promptForApiKey(): Promise<string | null> {
+    if (process.platform === 'darwin') {
+        const script = `text returned of (display dialog ${JSON.stringify(API_KEY_PROMPT)} default answer "" `
+            + 'with hidden answer with title "Codex" buttons {"Cancel", "Sign In"} default button "Sign In")';
+        return runDialog('osascript', ['-e', script]);
+    }
+    if (process.platform === 'win32') {
+        const script = `$c = $host.UI.PromptForCredential('Codex', '${API_KEY_PROMPT}', 'api-key', ''); `
+            + 'if ($c) { [Console]::Out.Write($c.GetNetworkCredential().Password) } else { exit 1 }';
+        return runDialog('powershell.exe', ['-NoProfile', '-Command', script]);
+    }
+    try {
+        return await runDialog('zenity', ['--password', '--title', 'Codex API key']);
+    } catch {
+        try {
+            return await runDialog('kdialog', ['--title', 'Codex', '--password', API_KEY_PROMPT]);
+        } catch {
+            throw new Error('No password dialog is available; install zenity or kdialog to sign in with an API key');
+        }
+    }
+}
+
+/** Remove an API key (and anything that looks like one) from text shown to the UI. */
+export function scrubApiKey(text: string, apiKey?: string | null): string {
+    const withoutKey = apiKey ? text.split(apiKey).join('[REDACTED]') : text;
+    return scrubSecrets(withoutKey) as string;
+}
+
+/** Sent as `codex-login-progress` while `codex login` runs. */
+export interface LoginProgress {
+    status: 'started' | 'waiting' | 'complete' | 'error' | 'cancelled';
+    method: string;
+    /** The page to sign in on, for a machine whose browser did not open (or cannot). */
+    url?: string;
+    /** The one-time code to enter there, in the device-auth flow. */
+    code?: string;
+    message?: string;
+}
+
+let loginChild: ChildProcess | null = null;
+let loginCancelled = false;
+
+/** The sign-in URL and device code `codex login` printed so far, if any. */
+export function extractLoginPrompt(output: string): { url?: string; code?: string } {
+    const text = output.replace(/\x1B\[[0-9;]*[a-zA-Z]/g, '');
+    const urls = Array.from(text.matchAll(/https?:\/\/[^\s"'<>]+/g), (match) => match[0].replace(/[.,)]+$/, ''));
+    const url = urls.find((candidate) => /oauth|authorize|device|login|auth\./i.test(candidate)) ?? urls[0];
+    const code = text.match(/\b([A-Z0-9]{4,5}-[A-Z0-9]{4,5})\b/)?.[1];
+    return { url, code };
+}
+
+/** Stop a login that is waiting on the browser or a device code. */
+export function cancelLogin(): { success: boolean } {
+    if (!loginChild) return { success: false };
+    loginCancelled = true;
+    try {
+        loginChild.kill();
+    } catch { }
+    return { success: true };
+}
+
+/**
+ * Sign in with the codex CLI. In `api-key` mode the key comes from the keychain when one
+ * was saved there, else from a native password dialog (and is saved when the keychain is
+ * on), and is piped to `codex login --with-api-key`; it is scrubbed from any error. The
+ * browser and device flows report the URL and code they print through `onProgress`, so
+ * a machine without a usable browser can finish the login elsewhere.
+ */
+export async function codexLogin(
     method?: string,
-    apiKey?: string,
+    onProgress: (progress: LoginProgress) => void = () => { },
 ): Promise<{ success: boolean; user?: CodexUser; error?: string }> {
+    if (loginChild) return { success: false, error: 'A login is already in progress' };
     const cached = checkCachedCredentials();
-    if (cached) {
-        return Promise.resolve({ success: true, user: cached });
+    if (cached && !cached.expired) {
+        return { success: true, user: cached };
     }
 
     const chosen = (method || 'browser').toLowerCase();
 
+    let apiKey: string | null = null;
+    let savedKey = false;
     if (chosen === 'api-key') {
-        const trimmed = (apiKey || '').trim();
-        if (!trimmed) {
-            return Promise.resolve({
-                success: false,
-                error: 'API key login requires a non-empty apiKey value',
-            });
+        apiKey = getSecret(CODEX_API_KEY_SECRET);
+        savedKey = !!apiKey;
+        if (!apiKey) {
+            try {
+                apiKey = ((await promptForApiKey()) || '').trim();
+            } catch (err: any) {
+                return { success: false, error: err.message };
+            }
         }
+        if (!apiKey) return { success: false, error: 'API key entry was cancelled' };
     }
 
     return new Promise((resolve) => {
@@ -97,37 +206,63 @@ export function codexLogin(
         }
 
         const child = spawnCommand('codex', args);
+        loginChild = child;
+        loginCancelled = false;
+        onProgress({ status: 'started', method: chosen });
 
-        if (chosen === 'api-key' && child.stdin) {
-            const key = (apiKey || '').trim();
-            child.stdin.write(key + '\n');
+        if (apiKey && child.stdin) {
+            child.stdin.write(apiKey + '\n');
             try { child.stdin.end(); } catch { }
         }
 
         let stdout = '';
         let stderr = '';
+        let reported: { url?: string; code?: string } = {};
+        const scan = () => {
+            const found = extractLoginPrompt(stdout + stderr);
+            if (found.url === reported.url && found.code === reported.code) return;
+            reported = found;
+            onProgress({ status: 'waiting', method: chosen, ...found });
+        };
 
         if (child.stdout) {
-            child.stdout.on('data', (data) => { stdout += data.toString(); });
+            child.stdout.on('data', (data) => {
+                stdout += data.toString();
+                scan();
+            });
         }
         if (child.stderr) {
-            child.stderr.on('data', (data) => { stderr += data.toString(); });
+            child.stderr.on('data', (data) => {
+                stderr += data.toString();
+                scan();
+            });
         }
 
+        const finish = (result: { success: boolean; user?: CodexUser; error?: string }, status: LoginProgress['status']) => {
+            loginChild = null;
+            onProgress({ status, method: chosen, message: result.error });
+            resolve(result);
+        };
+
         child.on('exit', (code) => {
             if (code === 0) {
+                if (apiKey && !savedKey) rememberSecret(CODEX_API_KEY_SECRET, apiKey);
                 const user = checkCachedCredentials();
-                resolve({ success: true, user: user || undefined });
+                finish({ success: true, user: user || undefined }, 'complete');
+            } else if (loginCancelled) {
+                finish({ success: false, error: 'Login was cancelled' }, 'cancelled');
             } else {
-                resolve({
+                // A saved key that no longer works is forgotten, so the next login asks again.
+                if (savedKey) deleteSecret(CODEX_API_KEY_SECRET);
+                finish({
                     success: false,
-                    error: stderr || stdout || `Login failed with exit code ${code}`,
-                });
+                    error: scrubApiKey(stderr || stdout || `Login failed with exit code ${code}`, apiKey),
+                }, 'error');
             }
         });
 
         child.on('error', (err) => {
-            resolve({ success: false, error: err.message });
+            finish({ success: false, error: scrubApiKey(err.message, apiKey) }, 'error');
         });
     });
 }
@@ -168,3 +303,53 @@ export function codexLoginMethods(): {
         ],
     };
 }
+
+function authChangeReason(before: CodexUser | null, after: CodexUser | null): AuthChangeReason {
+    if (!before && after) return 'login';
+    if (before && !after) return 'logout';
+    if (before && after && before.id === after.id && before.expiresAt !== after.expiresAt) return 'refresh';
+    return 'updated';
+}
+
+/**
+ * Watch ~/.codex/auth.json for sign-ins, token refreshes and sign-outs made outside the app
+ * (e.g. `codex login` in a terminal), and for the credentials running out, emitting
+ * `auth-changed` with the current user and the reason. Returns a stop function.
+ */
+export function startAuthWatcher(getWebContents: () => WebContents | null): () => void {
+    const authPath = codexAuthPath();
+    if (!authPath) return () => { };
+    let user = checkCachedCredentials();
+    let expiryTimer: NodeJS.Timeout | null = null;
+
+    const emit = (reason: AuthChangeReason) => {
+        getWebContents()?.send('auth-changed', { reason, user });
+    };
+    const scheduleExpiry = () => {
+        if (expiryTimer) clearTimeout(expiryTimer);
+        expiryTimer = null;
+        if (!user?.expiresAt || user.expired) return;
+        const delay = new Date(user.expiresAt).getTime() - Date.now();
+        expiryTimer = setTimeout(() => {
+            user = checkCachedCredentials();
+            if (user?.expired) emit('expired');
+            scheduleExpiry();
+        }, Math.min(Math.max(delay, 0) + 1000, MAX_TIMER_MS));
+        expiryTimer.unref();
+    };
+
+    const onChange = (current: fs.Stats, previous: fs.Stats) => {
+        if (current.mtimeMs === previous.mtimeMs && current.size === previous.size) return;
+        const before = user;
+        user = checkCachedCredentials();
+        emit(user?.expired ? 'expired' : authChangeReason(before, user));
+        scheduleExpiry();
+    };
+    fs.watchFile(authPath, { interval: AUTH_POLL_INTERVAL_MS, persistent: false }, onChange);
+    scheduleExpiry();
+
+    return () => {
+        fs.unwatchFile(authPath, onChange);
+        if (expiryTimer) clearTimeout(expiryTimer);
+    };
+}
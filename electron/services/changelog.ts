@@ -0,0 +1,115 @@
+import { BrowserWindow } from 'electron';
+import { AppState } from './models';
+import { runCodexToCompletion } from './codex';
+import { writeFile } from './fs-ops';
+import { GitCommit, gitLog } from './git';
+import { requirePermission } from './permissions';
+import { expandTildePath } from './utils';
+
+export const CHANGELOG_DRAFT_FILE = 'CHANGELOG.draft.md';
+
+const MAX_COMMITS = 500;
+const MAX_PROMPT_CHARS = 300;
+
+interface TurnRecord {
+    conversationTitle: string;
+    prompt: string;
+    timestamp: string;
+    filesTouched: number | null;
+}
+
+/**
+ * User turns in the workspace since `since`, each with the file count its reply reported.
+ */
+function turnsSince(state: AppState, workspaceId: string, since: string): TurnRecord[] {
+    const sinceMs = Date.parse(since);
+    const rows = state.db.prepare(`
+    SELECT m.content, m.timestamp, c.title,
+      (SELECT s.files_touched FROM messages a
+        JOIN message_stats s ON s.message_id = a.id
+        WHERE a.conversation_id = m.conversation_id AND a.role = 'assistant' AND a.rowid > m.rowid
+        ORDER BY a.rowid ASC LIMIT 1) AS files_touched
+    FROM messages m
+    JOIN conversations c ON c.id = m.conversation_id
+    WHERE c.workspace_id = ? AND m.role = 'user'
+    ORDER BY m.rowid ASC
+  `).all(workspaceId) as any[];
+
+    // Relative git dates ("2 weeks ago") don't parse here; keep every turn in that case.
+    return rows
+        .filter((row) => Number.isNaN(sinceMs) || Date.parse(row.timestamp) >= sinceMs)
+        .map((row) => ({
+            conversationTitle: row.title,
+            prompt: row.content,
+            timestamp: row.timestamp,
+            filesTouched: row.files_touched ?? null,
+        }));
+}
+
+export function buildChangelogPrompt(since: string, commits: GitCommit[], turns: TurnRecord[]): string {
+    const commitLines = commits.map((c) => `- ${c.shortHash} ${c.subject}`);
+    const turnLines = turns.map((t) => {
+        const prompt = t.prompt.replace(/\s+/g, ' ').slice(0, MAX_PROMPT_CHARS);
+        const files = t.filesTouched ? ` (${t.filesTouched} files changed)` : '';
+        return `- [${t.conversationTitle}] ${prompt}${files}`;
+    });
+
+    return [
+        `Draft a changelog for the changes made in this repository since ${since}.`,
+        'Group entries under "### Features", "### Fixes" and "### Chores", omit empty groups, ' +
+            'and write one concise user-facing line per change. Merge commits and agent turns that ' +
+            'describe the same change. Reply with the markdown only; do not modify any files.',
+        '',
+        '## Git commits',
+        ...(commitLines.length ? commitLines : ['(none)']),
+        '',
+        '## Agent turns',
+        ...(turnLines.length ? turnLines : ['(none)']),
+    ].join('\n');
+}
+
+/**
+ * Draft a grouped changelog from git history and recorded turns via a read-only codex run,
+ * then save it to CHANGELOG.draft.md in the workspace for review. Write permission is
+ * requested up front so a denial does not waste a codex run.
+ */
+export async function generateChangelog(
+    state: AppState,
+    window: BrowserWindow | null,
+    workspaceId: string,
+    since: string,
+): Promise<{ success: boolean; path?: string; content?: string; error?: string }> {
+    const workspace = state.db.prepare('SELECT path FROM workspaces WHERE id = ?').get(workspaceId) as any;
+    if (!workspace) return { success: false, error: 'Workspace not found' };
+    const workspacePath = expandTildePath(workspace.path);
+
+    const denied = await requirePermission(state.db, window, workspacePath, 'write');
+    if (denied) return denied;
+
+    const log = await gitLog(workspacePath, MAX_COMMITS, since);
+    const commits = log.success ? log.commits : [];
+    const turns = turnsSince(state, workspaceId, since);
+    if (!commits.length && !turns.length) {
+        return { success: false, error: `No commits or turns since ${since}` };
+    }
+
+    const result = await runCodexToCompletion(buildChangelogPrompt(since, commits, turns), {
+        ...state.config,
+        yoloMode: false,
+        cwd: workspacePath,
+        cliOptions: {
+            ...state.config.cliOptions,
+            sandbox: 'read-only',
+            askForApproval: 'never',
+            cwdOverride: '',
+        },
+    });
+    if (!result.success || !result.text.trim()) {
+        return { success: false, error: result.error || 'Codex returned an empty changelog' };
+    }
+
+    const content = result.text.trim() + '\n';
+    const written = writeFile(CHANGELOG_DRAFT_FILE, content, workspacePath);
+    if (!written.success) return { success: false, content, error: written.error };
+    return { success: true, path: CHANGELOG_DRAFT_FILE, content };
+}
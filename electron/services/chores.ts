@@ -0,0 +1,251 @@
+import Database from 'better-sqlite3';
+import { WebContents } from 'electron';
+import { AppState, Conversation } from './models';
+import { dbCreateConversation, dbCreateMessage } from './db';
+import { runCodexToCompletion, streamCodex } from './codex';
+import { expandTildePath, generateId, nowIso } from './utils';
+
+export interface Chore {
+    id: string;
+    workspaceId: string;
+    name: string;
+    prompt: string;
+    /** Local time of day, `HH:MM`. */
+    runAt: string;
+    enabled: boolean;
+    lastRunAt: string | null;
+}
+
+export type ChoreRunStatus = 'running' | 'draft' | 'failed' | 'applied' | 'dismissed';
+
+export interface ChoreRun {
+    id: string;
+    choreId: string;
+    workspaceId: string;
+    status: ChoreRunStatus;
+    plan: string | null;
+    error: string | null;
+    conversationId: string | null;
+    startedAt: string;
+    finishedAt: string | null;
+}
+
+const CHORE_CHECK_INTERVAL_MS = 60_000;
+
+const PLAN_ONLY_PREFIX =
+    'This is a scheduled maintenance chore running in a read-only sandbox. ' +
+    'Do not modify any files. Investigate and produce a concrete step-by-step plan ' +
+    '(commands to run and files to change) that a human can review and apply later.\n\n';
+
+function rowToChore(row: any): Chore {
+    return {
+        id: row.id,
+        workspaceId: row.workspace_id,
+        name: row.name,
+        prompt: row.prompt,
+        runAt: row.run_at,
+        enabled: !!row.enabled,
+        lastRunAt: row.last_run_at,
+    };
+}
+
+function rowToRun(row: any): ChoreRun {
+    return {
+        id: row.id,
+        choreId: row.chore_id,
+        workspaceId: row.workspace_id,
+        status: row.status,
+        plan: row.plan,
+        error: row.error,
+        conversationId: row.conversation_id,
+        startedAt: row.started_at,
+        finishedAt: row.finished_at,
+    };
+}
+
+export function listChores(db: Database.Database, workspaceId?: string): Chore[] {
+    const rows = workspaceId
+        ? db.prepare('SELECT * FROM chores WHERE workspace_id = ? ORDER BY run_at').all(workspaceId)
+        : db.prepare('SELECT * FROM chores ORDER BY run_at').all();
+    return rows.map(rowToChore);
+}
+
+export function saveChore(
+    db: Database.Database,
+    chore: Omit<Chore, 'id' | 'lastRunAt'> & { id?: string },
+): { success: boolean; chore?: Chore; error?: string } {
+    if (!/^([01]\d|2[0-3]):[0-5]\d$/.test(chore.runAt)) {
+        return { success: false, error: 'runAt must be HH:MM' };
+    }
+    const id = chore.id || generateId('chore');
+    db.prepare(`
+    INSERT INTO chores (id, workspace_id, name, prompt, run_at, enabled)
+    VALUES (?, ?, ?, ?, ?, ?)
+    ON CONFLICT(id) DO UPDATE SET
+      name = excluded.name,
+      prompt = excluded.prompt,
+      run_at = excluded.run_at,
+      enabled = excluded.enabled
+  `).run(id, chore.workspaceId, chore.name, chore.prompt, chore.runAt, chore.enabled ? 1 : 0);
+    return { success: true, chore: rowToChore(db.prepare('SELECT * FROM chores WHERE id = ?').get(id)) };
+}
+
+export function deleteChore(db: Database.Database, id: string): { success: boolean } {
+    db.prepare('DELETE FROM chores WHERE id = ?').run(id);
+    return { success: true };
+}
+
+export function listChoreRuns(
+    db: Database.Database,
+    workspaceId?: string,
+    status?: ChoreRunStatus,
+): ChoreRun[] {
+    const clauses: string[] = [];
+    const params: string[] = [];
+    if (workspaceId) {
+        clauses.push('workspace_id = ?');
+        params.push(workspaceId);
+    }
+    if (status) {
+        clauses.push('status = ?');
+        params.push(status);
+    }
+    const where = clauses.length ? `WHERE ${clauses.join(' AND ')}` : '';
+    return db
+        .prepare(`SELECT * FROM chore_runs ${where} ORDER BY CAST(started_at AS INTEGER) DESC`)
+        .all(...params)
+        .map(rowToRun);
+}
+
+/**
+ * Epoch seconds of today's scheduled time for `runAt` (HH:MM, local time).
+ */
+function scheduledToday(runAt: string, now: Date): number {
+    const [h, m] = runAt.split(':').map(Number);
+    const at = new Date(now);
+    at.setHours(h, m, 0, 0);
+    return Math.floor(at.getTime() / 1000);
+}
+
+export function isChoreDue(chore: Chore, now = new Date()): boolean {
+    if (!chore.enabled) return false;
+    const due = scheduledToday(chore.runAt, now);
+    if (Math.floor(now.getTime() / 1000) < due) return false;
+    return !chore.lastRunAt || Number(chore.lastRunAt) < due;
+}
+
+/**
+ * Run a chore in a read-only sandbox and store its plan as a draft awaiting review.
+ */
+export async function runChore(
+    webContents: WebContents | null,
+    state: AppState,
+    choreId: string,
+): Promise<{ success: boolean; run?: ChoreRun; error?: string }> {
+    const row = state.db.prepare('SELECT * FROM chores WHERE id = ?').get(choreId);
+    if (!row) return { success: false, error: 'Chore not found' };
+    const chore = rowToChore(row);
+
+    const workspace = state.db
+        .prepare('SELECT path FROM workspaces WHERE id = ?')
+        .get(chore.workspaceId) as any;
+    if (!workspace) return { success: false, error: 'Workspace not found' };
+
+    const runId = generateId('chorerun');
+    const startedAt = nowIso();
+    state.db
+        .prepare(`
+    INSERT INTO chore_runs (id, chore_id, workspace_id, status, started_at)
+    VALUES (?, ?, ?, 'running', ?)
+  `)
+        .run(runId, chore.id, chore.workspaceId, startedAt);
+    state.db.prepare('UPDATE chores SET last_run_at = ? WHERE id = ?').run(startedAt, chore.id);
+
+    const result = await runCodexToCompletion(PLAN_ONLY_PREFIX + chore.prompt, {
+        ...state.config,
+        yoloMode: false,
+        cwd: expandTildePath(workspace.path),
+        cliOptions: {
+            ...state.config.cliOptions,
+            sandbox: 'read-only',
+            askForApproval: 'never',
+            cwdOverride: '',
+        },
+    });
+
+    state.db
+        .prepare('UPDATE chore_runs SET status = ?, plan = ?, error = ?, finished_at = ? WHERE id = ?')
+        .run(result.success ? 'draft' : 'failed', result.text || null, result.error || null, nowIso(), runId);
+
+    const run = rowToRun(state.db.prepare('SELECT * FROM chore_runs WHERE id = ?').get(runId));
+    webContents?.send('chore-run-completed', run);
+    return { success: result.success, run, error: result.error };
+}
+
+/**
+ * Turn a reviewed draft into a real conversation and execute it with the normal
+ * (write-enabled) workspace settings.
+ */
+export function applyChoreRun(
+    webContents: WebContents,
+    state: AppState,
+    runId: string,
+): { success: boolean; conversation?: Conversation; error?: string } {
+    const runRow = state.db.prepare('SELECT * FROM chore_runs WHERE id = ?').get(runId);
+    if (!runRow) return { success: false, error: 'Chore run not found' };
+    const run = rowToRun(runRow);
+    if (run.status !== 'draft') return { success: false, error: `Chore run is ${run.status}` };
+
+    const chore = rowToChore(state.db.prepare('SELECT * FROM chores WHERE id = ?').get(run.choreId));
+    const conversationId = generateId('conv');
+    const conversation = dbCreateConversation(state.db, conversationId, run.workspaceId, `Chore: ${chore.name}`);
+
+    const seeded = [
+        { role: 'user', content: chore.prompt },
+        { role: 'assistant', content: run.plan || '' },
+    ];
+    for (const seed of seeded) {
+        conversation.messages.push(
+            dbCreateMessage(state.db, {
+                id: generateId('msg'),
+                conversationId,
+                role: seed.role,
+                content: seed.content,
+                timestamp: new Date().toISOString(),
+            }),
+        );
+    }
+
+    state.db
+        .prepare("UPDATE chore_runs SET status = 'applied', conversation_id = ? WHERE id = ?")
+        .run(conversationId, runId);
+
+    streamCodex(webContents, conversationId, 'Apply the plan above.', seeded, state);
+    return { success: true, conversation };
+}
+
+export function dismissChoreRun(db: Database.Database, runId: string): { success: boolean } {
+    db.prepare("UPDATE chore_runs SET status = 'dismissed' WHERE id = ? AND status = 'draft'").run(runId);
+    return { success: true };
+}
+
+/**
+ * Check every minute for chores whose daily time has passed. Returns a stop function.
+ */
+export function startChoreScheduler(
+    getWebContents: () => WebContents | null,
+    state: AppState,
+): () => void {
+    const running = new Set<string>();
+    const timer = setInterval(() => {
+        for (const chore of listChores(state.db)) {
+            if (running.has(chore.id) || !isChoreDue(chore)) continue;
+            running.add(chore.id);
+            runChore(getWebContents(), state, chore.id)
+                .catch((err) => console.error('[chores] Run failed:', err))
+                .finally(() => running.delete(chore.id));
+        }
+    }, CHORE_CHECK_INTERVAL_MS);
+    timer.unref();
+    return () => clearInterval(timer);
+}
@@ -1,17 +1,81 @@
+import Database from 'better-sqlite3';
 import { ChildProcess } from 'child_process';
 import { dialog, BrowserWindow } from 'electron';
 import { WebContents } from 'electron';
-import { AppState, CliOptions, CommandResult, ModelInfo, RunningCodexProcess } from './models';
 import {
-    buildCodexExecArgs,
+    AppState,
+    ApprovalDecision,
+    CliOptions,
+    CommandResult,
+    MessageAttachment,
+    ModelInfo,
+    PendingReview,
+    PlanStep,
+    PromptAttachment,
+    QueuedPrompt,
+    ReviewScope,
+    RunningCodexProcess,
+    RunningTurnStatus,
+    RuntimeConfig,
+    StreamSnapshot,
+    TurnReview,
+} from './models';
+import {
+    dbGetConversationOverrides,
+    dbGetSetting,
+    dbMoveConversation,
+    dbRecordTurnEvent,
+    dbSetConversationCwd,
+    dbSetConversationSessionId,
+    dbSetSetting,
+    dbUpdateConversationCliOverrides,
+} from './db';
+import {
+    applyNotes,
+    applyPersona,
+    applyProjectSummary,
+    buildApprovalResponse,
     cleanProgressText,
-    commandSpawnOptions,
+    commandOutput,
     defaultModels,
     expandTildePath,
+    generateId,
     parseCodexEvent,
+    parseModelList,
+    resolveRuntimeConfig,
     spawnCommand,
     StreamParseCache,
+    tryExtractApprovalRequest,
+    untrackChild,
 } from './utils';
+import { agentBackend } from './agents';
+import { saveAttachments } from './attachments';
+import { recordFileDiffs } from './diffs';
+import { CodexInstallMethod, installCodexWith } from './installer';
+import { checkPromptPaths } from './guardrails';
+import { getHistorySettings, getHistorySummary, refreshHistorySummary, storedHistory, trimHistory } from './history';
+import { recordMcpCall } from './mcp';
+import { attachMentionedContext } from './mentions';
+import { MessageDraft } from './message-drafts';
+import { recordPrompt } from './prompt-history';
+import { resourceLimits } from './resources';
+import {
+    buildExecutionPrompt,
+    buildPlanningPrompt,
+    normalizeReviewScope,
+    parsePlanSteps,
+    parseReviewProposal,
+    reviewRunConfig,
+    reviewViolations,
+    stepsScope,
+} from './review';
+import { captureTurnBaseline, releaseTurnBaseline, snapshotFileChange, TurnBaseline } from './snapshots';
+import { releaseTempDirs } from './tempdirs';
+import { createTurn, finishTurn, markTurnCancelled, markTurnRunning, recordTurnToolCall } from './turns';
+import { freshWorkspaceSummary } from './workspace-summary';
+import { recordLastSession } from './startup';
+import * as fs from 'fs';
+import * as path from 'path';
 import * as readline from 'readline';
 
 // ===== Mode / Model / Config =====
@@ -34,8 +98,105 @@ export function getYoloMode(state: AppState): boolean {
     return state.config.yoloMode;
 }
 
-export function getModels(): ModelInfo[] {
-    return defaultModels();
+const MODEL_CACHE_KEY = 'models.cache';
+const CUSTOM_MODELS_KEY = 'models.custom';
+const MODEL_CACHE_TTL_MS = 24 * 60 * 60 * 1000;
+const MODEL_LIST_TIMEOUT_MS = 15000;
+
+interface ModelCache {
+    models: ModelInfo[];
+    fetchedAt: number;
+}
+
+let modelRefresh: Promise<{ success: boolean; models: ModelInfo[]; error?: string }> | null = null;
+
+function runModelsList(args: string[]): Promise<string> {
+    return new Promise((resolve, reject) => {
+        const child = spawnCommand('codex', args);
+        let stdout = '';
+        let stderr = '';
+        const timer = setTimeout(() => {
+            child.kill();
+            reject(new Error('codex models list timed out'));
+        }, MODEL_LIST_TIMEOUT_MS);
+        child.stdout?.on('data', (d: Buffer) => (stdout += d.toString()));
+        child.stderr?.on('data', (d: Buffer) => (stderr += d.toString()));
+        child.on('error', (err) => {
+            clearTimeout(timer);
+            reject(err);
+        });
+        child.on('close', (code) => {
+            clearTimeout(timer);
+            if (code === 0) resolve(stdout);
+            else reject(new Error(stderr.trim() || `codex models list exited with code ${code}`));
+        });
+    });
+}
+
+function mergeModels(live: ModelInfo[], custom: ModelInfo[]): ModelInfo[] {
+    const merged = new Map(live.map((m) => [m.id, m]));
+    for (const model of custom) merged.set(model.id, { ...merged.get(model.id), ...model });
+    return Array.from(merged.values());
+}
+
+function cachedModels(state: AppState): ModelCache | null {
+    const cache = dbGetSetting<ModelCache | null>(state.db, MODEL_CACHE_KEY, null);
+    return cache?.models?.length ? cache : null;
+}
+
+export function getCustomModels(state: AppState): ModelInfo[] {
+    return dbGetSetting<ModelInfo[]>(state.db, CUSTOM_MODELS_KEY, []);
+}
+
+export function setCustomModels(state: AppState, models: ModelInfo[]): ModelInfo[] {
+    const cleaned = models
+        .filter((m) => m && typeof m.id === 'string' && m.id.trim())
+        .map((m) => ({ id: m.id.trim(), name: m.name?.trim() || m.id.trim(), description: m.description || '' }));
+    dbSetSetting(state.db, CUSTOM_MODELS_KEY, cleaned);
+    return cleaned;
+}
+
+/**
+ * Ask the CLI for its model list (`codex models list --json`, then the plain listing for
+ * CLIs without `--json`) and cache it. A failed refresh keeps the previous cache.
+ */
+export function refreshModels(state: AppState): Promise<{ success: boolean; models: ModelInfo[]; error?: string }> {
+    if (modelRefresh) return modelRefresh;
+    modelRefresh = (async () => {
+        let models: ModelInfo[] = [];
+        let error = '';
+        for (const args of [['models', 'list', '--json'], ['models', 'list']]) {
+            try {
+                models = parseModelList(await runModelsList(args));
+                if (models.length) break;
+            } catch (err: any) {
+                error = err.message;
+            }
+        }
+        if (!models.length) {
+            const fallback = mergeModels(cachedModels(state)?.models || defaultModels(), getCustomModels(state));
+            return { success: false, models: fallback, error: error || 'codex models list returned no models' };
+        }
+        const cache: ModelCache = { models, fetchedAt: Date.now() };
+        dbSetSetting(state.db, MODEL_CACHE_KEY, cache);
+        return { success: true, models: mergeModels(models, getCustomModels(state)) };
+    })().finally(() => {
+        modelRefresh = null;
+    });
+    return modelRefresh;
+}
+
+/**
+ * The cached live model list (or the built-in defaults before the first successful
+ * refresh) merged with custom entries. A stale cache is returned as-is while a refresh
+ * runs in the background.
+ */
+export function getModels(state: AppState): ModelInfo[] {
+    const cache = cachedModels(state);
+    if (!cache || Date.now() - cache.fetchedAt > MODEL_CACHE_TTL_MS) {
+        refreshModels(state).catch(() => { });
+    }
+    return mergeModels(cache?.models || defaultModels(), getCustomModels(state));
 }
 
 export function getModel(state: AppState): string {
@@ -56,6 +217,7 @@ export function setCliOptions(state: AppState, options: Partial<CliOptions>): Cl
     if (options.cwdOverride !== undefined) merged.cwdOverride = options.cwdOverride;
     if (options.extraArgs !== undefined) merged.extraArgs = options.extraArgs;
     if (options.enableWebSearch !== undefined) merged.enableWebSearch = options.enableWebSearch;
+    if (options.reasoningEffort !== undefined) merged.reasoningEffort = options.reasoningEffort;
     state.config.cliOptions = merged;
     return merged;
 }
@@ -77,6 +239,7 @@ export function switchWorkspace(
     cwd: string,
 ): { success: boolean; sessionId?: string } {
     state.config.cwd = expandTildePath(cwd);
+    recordLastSession(state.db, workspaceId);
     return { success: true, sessionId: workspaceId };
 }
 
@@ -86,87 +249,29 @@ export function debugLog(msg: string): void {
 
 // ===== Check / Install Codex =====
 
-export function checkCodex(): { installed: boolean } {
-    try {
-        const { execSync } = require('child_process');
-        const opts = commandSpawnOptions();
-        execSync('codex --version', { ...opts, stdio: 'ignore' });
-        return { installed: true };
-    } catch {
-        return { installed: false };
-    }
+export async function checkCodex(): Promise<{ installed: boolean }> {
+    return { installed: (await commandOutput('codex', ['--version'])) !== null };
 }
 
-export function installCodex(webContents: WebContents): { success: boolean; error?: string } {
-    webContents.send('codex-install-progress', {
-        status: 'installing',
-        message: 'Installing Codex CLI...',
-        percent: 0,
-    });
-
-    const opts = commandSpawnOptions();
-    const child = spawnCommand('npm', ['install', '-g', '@openai/codex']);
-
-    return new Promise((resolve) => {
-        let lineCount = 0;
-
-        if (child.stderr) {
-            const rl = readline.createInterface({ input: child.stderr });
-            rl.on('line', (line: string) => {
-                if (!line.trim()) return;
-                lineCount++;
-                const percent = Math.min(10 + lineCount * 5, 80);
-                webContents.send('codex-install-progress', {
-                    status: 'installing',
-                    message: line.trim(),
-                    percent,
-                });
-            });
-        }
-
-        if (child.stdout) {
-            const rl = readline.createInterface({ input: child.stdout });
-            rl.on('line', (line: string) => {
-                if (!line.trim()) return;
-                webContents.send('codex-install-progress', {
-                    status: 'installing',
-                    message: line.trim(),
-                    percent: 85,
-                });
-            });
-        }
-
-        child.on('exit', (code) => {
-            if (code === 0) {
-                webContents.send('codex-install-progress', {
-                    status: 'complete',
-                    message: 'Codex CLI installed successfully',
-                    percent: 100,
-                });
-                resolve({ success: true });
-            } else {
-                const msg = `Install failed: exit ${code}`;
-                webContents.send('codex-install-progress', {
-                    status: 'error',
-                    message: msg,
-                    percent: 0,
-                });
-                resolve({ success: false, error: 'Installation failed' });
-            }
-        });
+/**
+ * Install codex with `method`, or the preferred (else first available) package manager,
+ * Homebrew or release binary. Progress arrives as `codex-install-progress`.
+ */
+export function installCodex(
+    webContents: WebContents,
+    db: Database.Database,
+    method?: CodexInstallMethod,
+): Promise<{ success: boolean; method?: CodexInstallMethod; error?: string }> {
+    return installCodexWith(webContents, db, false, method);
+}
 
-        child.on('error', (err) => {
-            const msg = err.message.includes('ENOENT')
-                ? 'npm executable was not found. Please install Node.js and make sure npm is available in PATH.'
-                : err.message;
-            webContents.send('codex-install-progress', {
-                status: 'error',
-                message: msg,
-                percent: 0,
-            });
-            resolve({ success: false, error: msg });
-        });
-    }) as any;
+/** Install the latest release over the current one, with the same progress events as installCodex. */
+export function upgradeCodex(
+    webContents: WebContents,
+    db: Database.Database,
+    method?: CodexInstallMethod,
+): Promise<{ success: boolean; method?: CodexInstallMethod; error?: string }> {
+    return installCodexWith(webContents, db, true, method);
 }
 
 // ===== Open Workspace =====
@@ -181,21 +286,228 @@ export async function openWorkspace(): Promise<{ path: string; name: string } |
     return { path: folderPath, name: folderName };
 }
 
+// ===== Prompt Queue =====
+
+const QUEUE_MODE_KEY = 'promptQueue.enabled';
+
+export function getQueueMode(state: AppState): boolean {
+    return dbGetSetting(state.db, QUEUE_MODE_KEY, false);
+}
+
+export function setQueueMode(state: AppState, enabled: boolean): boolean {
+    dbSetSetting(state.db, QUEUE_MODE_KEY, enabled);
+    return enabled;
+}
+
+function emitQueue(webContents: WebContents, state: AppState, conversationId: string): void {
+    const queue = state.promptQueues.get(conversationId) || [];
+    webContents.send('codex-queue-updated', {
+        cid: conversationId,
+        items: queue.map((item, index) => ({
+            id: item.id,
+            prompt: item.prompt,
+            queuedAt: item.queuedAt,
+            position: index + 1,
+        })),
+    });
+}
+
+export function getPromptQueue(state: AppState, conversationId: string): QueuedPrompt[] {
+    return state.promptQueues.get(conversationId) || [];
+}
+
+export function clearPromptQueue(
+    webContents: WebContents,
+    state: AppState,
+    conversationId: string,
+): { success: boolean; cleared: number } {
+    const queue = state.promptQueues.get(conversationId) || [];
+    for (const item of queue) markTurnCancelled(state.db, item.id);
+    const cleared = queue.length;
+    state.promptQueues.delete(conversationId);
+    emitQueue(webContents, state, conversationId);
+    return { success: true, cleared };
+}
+
+/**
+ * Start a conversation's next queued prompt. With a resumable codex session the queued
+ * turn sees the previous answer; otherwise it only gets the history that was current
+ * when it was queued.
+ */
+function startQueuedHead(webContents: WebContents, state: AppState, conversationId: string): void {
+    const queue = state.promptQueues.get(conversationId);
+    const next = queue?.shift();
+    if (!next) return;
+    if (queue!.length === 0) state.promptQueues.delete(conversationId);
+    emitQueue(webContents, state, conversationId);
+    streamCodex(webContents, conversationId, next.prompt, next.conversationHistory, state, next.attachments, next.id, next.plan);
+}
+
+/**
+ * Once the previous turn ended normally, the next queued prompt waits for a slot like any
+ * other, so a conversation with a long queue cannot keep its slot from older waiters.
+ */
+function startNextQueued(webContents: WebContents, state: AppState, conversationId: string): void {
+    const next = state.promptQueues.get(conversationId)?.[0];
+    if (!next) return;
+    next.waitingForSlot = true;
+    startWaitingTurns(webContents, state);
+}
+
+const MAX_CONCURRENT_TURNS_KEY = 'turns.maxConcurrent';
+
+/** The saved limit on turns running at once; called once at startup. */
+export function loadMaxConcurrentTurns(db: Database.Database): number {
+    return dbGetSetting(db, MAX_CONCURRENT_TURNS_KEY, 0);
+}
+
+export function getMaxConcurrentTurns(state: AppState): number {
+    return state.maxConcurrentTurns;
+}
+
+/** 0 is unlimited. Raising the limit starts waiting turns right away. */
+export function setMaxConcurrentTurns(webContents: WebContents, state: AppState, limit: number): number {
+    state.maxConcurrentTurns = Math.max(0, Math.floor(Number(limit) || 0));
+    dbSetSetting(state.db, MAX_CONCURRENT_TURNS_KEY, state.maxConcurrentTurns);
+    startWaitingTurns(webContents, state);
+    return state.maxConcurrentTurns;
+}
+
+/** The stricter of the configured limit and the low-memory one; 0 is unlimited. */
+function turnLimit(state: AppState): number {
+    const limits = [state.maxConcurrentTurns, resourceLimits().maxConcurrentTurns].filter((n) => n > 0);
+    return limits.length > 0 ? Math.min(...limits) : 0;
+}
+
+function atTurnLimit(state: AppState): boolean {
+    const limit = turnLimit(state);
+    return limit > 0 && state.runningCodex.size >= limit;
+}
+
+/** Conversations whose next prompt waits for a slot, oldest prompt first. */
+function waitingTurns(state: AppState): Array<[string, QueuedPrompt]> {
+    return Array.from(state.promptQueues)
+        .filter(([conversationId, queue]) => queue[0]?.waitingForSlot && !state.runningCodex.has(conversationId))
+        .map(([conversationId, queue]): [string, QueuedPrompt] => [conversationId, queue[0]])
+        .sort(([, a], [, b]) => a.queuedAt.localeCompare(b.queuedAt));
+}
+
+/** Tell every waiting turn its current place in line. */
+function emitWaitingTurns(webContents: WebContents, state: AppState): void {
+    const limit = turnLimit(state);
+    waitingTurns(state).forEach(([conversationId, item], index) => {
+        webContents.send('codex-turn-queued', { cid: conversationId, turnId: item.id, position: index + 1, limit });
+    });
+}
+
+/** Start prompts held back by the turn limit while slots are free, oldest prompt first across conversations. */
+export function startWaitingTurns(webContents: WebContents, state: AppState): void {
+    while (!atTurnLimit(state)) {
+        const next = waitingTurns(state)[0];
+        if (!next) break;
+        startQueuedHead(webContents, state, next[0]);
+    }
+    emitWaitingTurns(webContents, state);
+}
+
+/** Every conversation with a running turn or one waiting for a slot. */
+export function getRunningTurns(state: AppState): { limit: number; running: number; turns: RunningTurnStatus[] } {
+    const queued = (conversationId: string) => state.promptQueues.get(conversationId)?.length ?? 0;
+    const turns: RunningTurnStatus[] = Array.from(state.runningCodex, ([conversationId, running]) => ({
+        conversationId,
+        turnId: running.turnId,
+        status: 'running' as const,
+        since: running.startedAt,
+        queued: queued(conversationId),
+    }));
+    waitingTurns(state).forEach(([conversationId, item], index) => {
+        turns.push({
+            conversationId,
+            turnId: item.id,
+            status: 'waiting',
+            since: item.queuedAt,
+            position: index + 1,
+            queued: queued(conversationId) - 1,
+        });
+    });
+    return { limit: turnLimit(state), running: state.runningCodex.size, turns };
+}
+
 // ===== Cancel / Stream Codex =====
 
+const CANCEL_GRACE_KEY = 'cancel.gracePeriodMs';
+const DEFAULT_CANCEL_GRACE_MS = 3000;
+const MAX_CANCEL_GRACE_MS = 30000;
+
+export function getCancelGracePeriod(state: AppState): number {
+    return dbGetSetting(state.db, CANCEL_GRACE_KEY, DEFAULT_CANCEL_GRACE_MS);
+}
+
+export function setCancelGracePeriod(state: AppState, ms: number): number {
+    const value = Math.max(0, Math.min(MAX_CANCEL_GRACE_MS, Math.round(Number(ms) || 0)));
+    dbSetSetting(state.db, CANCEL_GRACE_KEY, value);
+    return value;
+}
+
+/**
+ * Ask the CLI to stop: an interrupt op on stdin and, on Unix, SIGINT. The process stays
+ * registered so its last events are still parsed; it is killed when the grace period
+ * runs out. The exit handler reports the turn as cancelled with the partial text.
+ */
+function interruptProcess(running: RunningCodexProcess, graceMs: number): void {
+    const { child } = running;
+    if (!child) return;
+    running.interrupting = true;
+    try {
+        running.stdin?.write(JSON.stringify({ id: generateId('interrupt'), op: { type: 'interrupt' } }) + '\n');
+    } catch { }
+    if (process.platform !== 'win32') {
+        try {
+            child.kill('SIGINT');
+        } catch { }
+    }
+    setTimeout(() => {
+        if (child.exitCode === null && child.signalCode === null) {
+            try {
+                child.kill();
+            } catch { }
+        }
+    }, graceMs);
+}
+
+/**
+ * Stop the conversation's running turn. A graceful cancel gives the CLI the configured
+ * grace period to flush its final items and ends the stream with `partial: true`; a
+ * second cancel, or a grace period of 0, kills immediately.
+ */
 export function cancelPrompt(
     webContents: WebContents,
     conversationId: string,
     state: AppState,
+    graceful = false,
 ): { success: boolean } {
     let hadProcess = false;
     const process = state.runningCodex.get(conversationId);
+    const graceMs = graceful ? getCancelGracePeriod(state) : 0;
+    if (process?.child && graceMs > 0 && !process.interrupting) {
+        interruptProcess(process, graceMs);
+        for (const [key, pending] of state.pendingApprovals) {
+            if (pending.conversationId === conversationId) {
+                state.pendingApprovals.delete(key);
+            }
+        }
+        return { success: true };
+    }
     if (process) {
         hadProcess = true;
+        process.cancelled = true;
         try {
-            process.child.kill();
+            process.child?.kill();
         } catch { }
         state.runningCodex.delete(conversationId);
+        markTurnCancelled(state.db, process.turnId);
+        releaseTempDirs(state, conversationId);
+        process.draft?.finish();
     }
 
     // Clean up pending approvals for this conversation
@@ -206,7 +518,9 @@ export function cancelPrompt(
     }
 
     if (hadProcess) {
-        webContents.send('codex-stream-end', { cid: conversationId, cancelled: true });
+        webContents.send('codex-stream-end', { cid: conversationId, cancelled: true, messageId: process?.draft?.messageId });
+        // Prompts queued behind a stopped turn are dropped with it.
+        clearPromptQueue(webContents, state, conversationId);
     }
     return { success: true };
 }
@@ -217,137 +531,782 @@ export function streamCodex(
     prompt: string,
     conversationHistory: Array<{ role: string; content: string }> | undefined,
     state: AppState,
-): void {
-    // Kill existing process for this conversation
+    attachments: PromptAttachment[] = [],
+    turnId?: string,
+    plan = false,
+): { queued: boolean; position?: number; turnId?: string; attachments?: MessageAttachment[] } {
+    // Validate and store attachments before touching the running turn, so a bad file
+    // does not cancel it.
+    const saved = saveAttachments(attachments);
+
+    // Kill existing process for this conversation, or wait behind it and the prompts already
+    // queued in queue mode. A new turn also waits while the low-memory turn limit is reached.
     const existing = state.runningCodex.get(conversationId);
+    const queuedAhead = !turnId && (state.promptQueues.get(conversationId)?.length ?? 0) > 0;
+    const waitingForSlot = !existing && !turnId && atTurnLimit(state);
+    if (((existing || queuedAhead) && getQueueMode(state)) || waitingForSlot) {
+        const queue = state.promptQueues.get(conversationId) || [];
+        const queuedTurnId = generateId('turn');
+        createTurn(state.db, queuedTurnId, conversationId, prompt, 'queued');
+        queue.push({
+            id: queuedTurnId,
+            prompt,
+            conversationHistory,
+            attachments: saved.map((a) => ({ path: a.path, name: a.name })),
+            queuedAt: new Date().toISOString(),
+            waitingForSlot: waitingForSlot || undefined,
+            plan: plan || undefined,
+        });
+        state.promptQueues.set(conversationId, queue);
+        emitQueue(webContents, state, conversationId);
+        if (waitingForSlot) emitWaitingTurns(webContents, state);
+        return { queued: true, position: queue.length, turnId: queuedTurnId, attachments: saved };
+    }
     if (existing) {
+        existing.cancelled = true;
         try {
-            existing.child.kill();
+            existing.child?.kill();
         } catch { }
         state.runningCodex.delete(conversationId);
+        markTurnCancelled(state.db, existing.turnId);
     }
 
-    // Clean up pending approvals
+    // Clean up pending approvals, and a plan still waiting for review
     for (const [key, pending] of state.pendingApprovals) {
         if (pending.conversationId === conversationId) {
             state.pendingApprovals.delete(key);
         }
     }
+    state.pendingReviews.delete(conversationId);
+
+    recordPrompt(state.db, conversationId, prompt);
+    const currentTurnId = turnId || generateId('turn');
+    createTurn(state.db, currentTurnId, conversationId, prompt, 'running');
+
+    const overrides = dbGetConversationOverrides(state.db, conversationId);
+    let runConfig = resolveRuntimeConfig(state.config, overrides.cliOverrides, overrides.workspaceCliOptions);
+    // The conversation's own cwd wins over its target package, which wins over the
+    // workspace/global cwd. A cwd that no longer exists (e.g. after a move) is skipped.
+    const conversationCwd = overrides.cwd && fs.existsSync(overrides.cwd) ? overrides.cwd : undefined;
+    const scopedCwd = conversationCwd || overrides.targetPackage?.absolutePath;
+    if (scopedCwd) {
+        runConfig = {
+            ...runConfig,
+            cliOptions: { ...runConfig.cliOptions, cwdOverride: scopedCwd },
+        };
+    }
+
+    const pathCheck = checkPromptPaths(prompt, runConfig);
+    if (pathCheck.paths.length > 0) {
+        webContents.send('codex-path-warning', { cid: conversationId, paths: pathCheck.paths });
+    }
+
+    // `@file` and `@dir/` mentions are inlined from the workspace, so codex does not have to
+    // spend a command reading them.
+    const context = attachMentionedContext(prompt, overrides.workspacePath || runConfig.cwd);
+    if (context.mentions.length > 0) {
+        webContents.send('codex-context-attached', { cid: conversationId, turnId: currentTurnId, mentions: context.mentions });
+    }
 
-    const [_fullPrompt, runCwd, args] = buildCodexExecArgs(prompt, state.config, conversationHistory);
+    // A conversation's first turn starts from the workspace summary, when one is cached
+    // and still matches the project files.
+    const firstTurn = !overrides.sessionId && !conversationHistory?.length;
+    const projectSummary = firstTurn && overrides.workspaceId && overrides.workspacePath
+        ? freshWorkspaceSummary(state.db, overrides.workspaceId, overrides.workspacePath)
+        : undefined;
+    // In review mode, or for a plan turn, the turn only plans, read-only; approveReview or
+    // executePlan runs the approved part.
+    const seeded = applyProjectSummary(context.prompt, projectSummary);
+    const task = applyPersona(applyNotes(seeded, overrides.promptNotes), overrides.persona);
+    const review: TurnReview | undefined =
+        plan || (getReviewMode(state) && !runConfig.yoloMode) ? { phase: 'plan', prompt, task, runConfig } : undefined;
+
+    // Pasted history is the stored conversation trimmed to the token budget. Older messages
+    // that no longer fit are summarized in the background, for the turns after this one.
+    let history = conversationHistory;
+    if (!overrides.sessionId || !agentBackend(runConfig).supportsResume) {
+        const full = storedHistory(state.db, conversationId, prompt, conversationHistory);
+        const trimmed = trimHistory(full, getHistorySettings(state.db), getHistorySummary(state.db, conversationId));
+        history = trimmed.messages;
+        if (trimmed.dropped > 0) void refreshHistorySummary(state, conversationId, full, runCodexToCompletion);
+    }
+    runCodexTurn(
+        webContents,
+        state,
+        conversationId,
+        review ? buildPlanningPrompt(task) : task,
+        history,
+        saved.map((a) => a.path),
+        review ? reviewRunConfig(runConfig, 'plan') : runConfig,
+        currentTurnId,
+        overrides.sessionId,
+        review,
+    );
+    return { queued: false, turnId: currentTurnId, attachments: saved };
+}
+
+/**
+ * Run a prompt as a read-only planning pass, even outside review mode and in yolo mode. The
+ * plan and its steps arrive with `codex-review-request`; executePlan runs chosen steps.
+ */
+export function streamCodexPlan(
+    webContents: WebContents,
+    conversationId: string,
+    prompt: string,
+    conversationHistory: Array<{ role: string; content: string }> | undefined,
+    state: AppState,
+    attachments: PromptAttachment[] = [],
+): { queued: boolean; position?: number; turnId?: string; attachments?: MessageAttachment[] } {
+    return streamCodex(webContents, conversationId, prompt, conversationHistory, state, attachments, undefined, true);
+}
+
+/**
+ * Spawn one `codex exec` turn and stream its events. When `sessionId` is set the turn
+ * resumes that CLI session; if the resume fails before codex produced any item, the
+ * stale session id is dropped and the turn is retried with pasted history instead.
+ * A safety review's planning pass ends in a pending review; its approved pass answers
+ * approvals by the approved scope and stops at a file change outside it.
+ */
+function runCodexTurn(
+    webContents: WebContents,
+    state: AppState,
+    conversationId: string,
+    prompt: string,
+    conversationHistory: Array<{ role: string; content: string }> | undefined,
+    images: string[],
+    runConfig: RuntimeConfig,
+    turnId: string,
+    sessionId?: string,
+    review?: TurnReview,
+): void {
+    const backend = agentBackend(runConfig);
+    const resumeId = backend.supportsResume ? sessionId : undefined;
+    const [_fullPrompt, runCwd, args] = backend.buildArgs(
+        prompt,
+        runConfig,
+        resumeId ? undefined : conversationHistory,
+        resumeId,
+        images,
+    );
 
-    console.error(`[streamCodex] codex ${args.join(' ')}`);
+    console.error(`[streamCodex] ${backend.bin} ${args.join(' ')}`);
     console.error(`[streamCodex] cwd=${runCwd}`);
 
-    const child = spawnCommand('codex', args, runCwd);
+    const mapEvent = backend.createMapper();
 
-    state.runningCodex.set(conversationId, {
-        child,
-        stdin: child.stdin,
-    });
+    const cache = new StreamParseCache(resourceLimits().streamTextChars);
+    // The partial output is kept in the database as it streams, in case the app dies mid-turn.
+    const draft = new MessageDraft(state.db, conversationId, cache);
+    const running: RunningCodexProcess = {
+        child: null,
+        stdin: null,
+        turnId,
+        startedAt: new Date().toISOString(),
+        stream: cache,
+        draft,
+        review,
+    };
+    const scope = review?.phase === 'execute' ? review.scope : null;
+    state.runningCodex.set(conversationId, running);
+    markTurnRunning(state.db, turnId, runConfig.model);
+    webContents.send('codex-turn-status', { cid: conversationId, turnId, status: 'running' });
 
-    // Read stdout (JSON events)
-    if (child.stdout) {
-        const cache = new StreamParseCache();
-        const rl = readline.createInterface({ input: child.stdout });
-        rl.on('line', (line: string) => {
-            if (!line.trim()) return;
-            try {
-                const value = JSON.parse(line);
-                const approval = parseCodexEvent(webContents, conversationId, value, cache);
-                if (approval) {
-                    state.pendingApprovals.set(approval.requestId, {
-                        conversationId,
+    state.turnFileChanges.delete(conversationId);
+    state.turnUsage.delete(conversationId);
+
+    // The CLI reports a file change only once it is applied, so the work tree's pre-turn
+    // state is recorded before it starts.
+    void captureTurnBaseline(turnId, runCwd)
+        .catch((err) => {
+            console.error('[streamCodex] Failed to record the pre-turn state:', err);
+            return null;
+        })
+        .then((baseline) => {
+            // Cancelled or replaced while the baseline was taken.
+            if (state.runningCodex.get(conversationId) !== running) {
+                releaseTurnBaseline(turnId, baseline);
+                return;
+            }
+            startTurnProcess(baseline);
+        });
+
+    function startTurnProcess(baseline: TurnBaseline | null): void {
+        const child = spawnCommand(backend.bin, args, runCwd);
+        // A turn's child is tracked through runningCodex instead, so the watchdog can reap it
+        // once the turn lets go of it without the process exiting.
+        untrackChild(child);
+        running.child = child;
+        running.stdin = child.stdin;
+
+        // Read stdout (JSON events)
+        if (child.stdout) {
+            const rl = readline.createInterface({ input: child.stdout });
+            rl.on('line', (line: string) => {
+                if (!line.trim()) return;
+                try {
+                    for (const value of mapEvent(JSON.parse(line))) {
+                        if (value.type === 'protocol.mismatch') {
+                            console.error(`[streamCodex] ${value.mismatch.message}`);
+                            webContents.send('codex-protocol-mismatch', { cid: conversationId, turnId, ...value.mismatch });
+                            continue;
+                        }
+                        dbRecordTurnEvent(state.db, turnId, conversationId, value);
+                        const scopedApproval = scope ? tryExtractApprovalRequest(value) : null;
+                        if (scope && scopedApproval) {
+                            const outside = reviewViolations(value, scope, runCwd);
+                            const reason = outside.length > 0 ? `Not in the approved plan: ${outside.join(', ')}` : undefined;
+                            try {
+                                running.stdin?.write(buildApprovalResponse(scopedApproval, reason ? 'deny' : 'approve', reason) + '\n');
+                            } catch { }
+                            if (reason) {
+                                webContents.send('codex-review-violation', { cid: conversationId, turnId, items: outside, blocked: true });
+                            }
+                            continue;
+                        }
+                        const approval = parseCodexEvent(webContents, conversationId, value, cache);
+                        if (approval) {
+                            state.pendingApprovals.set(approval.requestId, {
+                                conversationId,
+                                request: approval,
+                            });
+                        }
+                        if (value.item) recordTurnToolCall(state.db, turnId, value.item, value.type === 'item.completed');
+                        if (value.type === 'item.completed' && (value.item?.type || '').toLowerCase() === 'file_change') {
+                            const item = value.item;
+                            void snapshotFileChange(state.db, turnId, conversationId, runCwd, item).then(() => {
+                                for (const diff of recordFileDiffs(state.db, turnId, conversationId, runCwd, item)) {
+                                    webContents.send('codex-file-diff', { cid: conversationId, turnId, ...diff });
+                                }
+                            });
+                        }
+                        // workspace-write applies patches without asking, so an out-of-scope change
+                        // can only be stopped after the fact; the turn's snapshots can roll it back.
+                        if (scope && !running.interrupting && (value.item?.type || '').toLowerCase() === 'file_change') {
+                            const outside = reviewViolations(value, scope, runCwd);
+                            if (outside.length > 0) {
+                                webContents.send('codex-review-violation', { cid: conversationId, turnId, items: outside, blocked: false });
+                                interruptProcess(running, getCancelGracePeriod(state) || DEFAULT_CANCEL_GRACE_MS);
+                            }
+                        }
+                        if ((value.item?.type || '').toLowerCase() === 'mcp_tool_call') {
+                            const call = recordMcpCall(state.db, turnId, conversationId, value.item);
+                            if (call) {
+                                webContents.send('codex-mcp-call', {
+                                    cid: conversationId,
+                                    turnId,
+                                    callId: call.id,
+                                    server: call.server,
+                                    tool: call.tool,
+                                    status: call.status,
+                                });
+                            }
+                        }
+                        if (cache.sessionId && cache.sessionId !== sessionId) {
+                            dbSetConversationSessionId(state.db, conversationId, cache.sessionId);
+                            sessionId = cache.sessionId;
+                        }
+                    }
+                } catch {
+                    webContents.send('codex-stream-token', { cid: conversationId, data: line });
+                }
+                draft.touch();
+            });
+        }
+
+        // Read stderr (progress)
+        let stderrAccum = '';
+        if (child.stderr) {
+            const rl = readline.createInterface({ input: child.stderr });
+            rl.on('line', (line: string) => {
+                stderrAccum += line + '\n';
+                console.error(`[streamCodex:stderr] ${line}`);
+                const cleaned = cleanProgressText(line);
+                if (cleaned) {
+                    webContents.send('codex-progress', { cid: conversationId, data: cleaned });
+                }
+            });
+        }
+
+        // Monitor process exit
+        child.on('exit', (code) => {
+            // A cancelled or replaced turn has already been unregistered; it must not advance the queue.
+            const wasCurrent = state.runningCodex.get(conversationId) === running;
+            if (wasCurrent) state.runningCodex.delete(conversationId);
+            draft.finish();
+            // The rest of the conversation's state belongs to the turn that replaced this one.
+            if (wasCurrent) {
+                state.turnFileChanges.set(conversationId, Array.from(cache.touchedFiles));
+                if (cache.usage) {
+                    state.turnUsage.set(conversationId, { ...cache.usage, model: runConfig.model });
+                }
+
+                // Clean up approvals for this conversation
+                for (const [key, pending] of state.pendingApprovals) {
+                    if (pending.conversationId === conversationId) {
+                        state.pendingApprovals.delete(key);
+                    }
+                }
+            }
+
+            // Checked before the exit code: a killed CLI exits with 1 on Windows, not a signal.
+            if (running.interrupting || running.cancelled) {
+                if (wasCurrent) releaseTempDirs(state, conversationId);
+                markTurnCancelled(state.db, turnId);
+                if (wasCurrent) {
+                    webContents.send('codex-turn-status', { cid: conversationId, turnId, status: 'cancelled' });
+                    webContents.send('codex-stream-end', {
+                        cid: conversationId,
+                        cancelled: true,
+                        partial: true,
+                        text: cache.agentText,
+                        truncated: cache.agentTextTruncated,
+                        messageId: draft.messageId,
                     });
+                    clearPromptQueue(webContents, state, conversationId);
                 }
-            } catch {
-                webContents.send('codex-stream-token', { cid: conversationId, data: line });
+                startWaitingTurns(webContents, state);
+                return;
             }
-        });
-    }
 
-    // Read stderr (progress)
-    let stderrAccum = '';
-    if (child.stderr) {
-        const rl = readline.createInterface({ input: child.stderr });
-        rl.on('line', (line: string) => {
-            stderrAccum += line + '\n';
-            console.error(`[streamCodex:stderr] ${line}`);
-            const cleaned = cleanProgressText(line);
-            if (cleaned) {
-                webContents.send('codex-progress', { cid: conversationId, data: cleaned });
+            const resumeFailed = code !== 0 && code !== null && resumeId && cache.itemCount === 0;
+            if (resumeFailed) {
+                console.error(`[streamCodex] resume of session ${resumeId} failed, falling back to history`);
+                dbSetConversationSessionId(state.db, conversationId, null);
+                webContents.send('codex-progress', {
+                    cid: conversationId,
+                    data: `Could not resume the previous ${backend.label} session; continuing with conversation history.`,
+                });
+                runCodexTurn(webContents, state, conversationId, prompt, conversationHistory, images, runConfig, turnId, undefined, review);
+                return;
             }
-        });
-    }
 
-    // Monitor process exit
-    child.on('exit', (code) => {
-        state.runningCodex.delete(conversationId);
+            // A replaced turn's scratch space now belongs to the turn that replaced it.
+            if (wasCurrent) releaseTempDirs(state, conversationId);
 
-        // Clean up approvals for this conversation
-        for (const [key, pending] of state.pendingApprovals) {
-            if (pending.conversationId === conversationId) {
-                state.pendingApprovals.delete(key);
+            if (code === 0 || code === null) {
+                if (wasCurrent) {
+                    finishTurn(state.db, turnId, { status: 'completed', exitCode: code, usage: cache.usage || undefined });
+                    webContents.send('codex-turn-status', { cid: conversationId, turnId, status: 'completed' });
+                    webContents.send('codex-stream-end', { cid: conversationId, messageId: draft.messageId });
+                }
+                if (wasCurrent && review?.phase === 'plan') {
+                    holdForReview(webContents, state, conversationId, turnId, review, cache.agentText, conversationHistory, images);
+                } else if (wasCurrent) {
+                    startNextQueued(webContents, state, conversationId);
+                }
+            } else {
+                const detail = stderrAccum.trim();
+                const msg = detail
+                    ? `${backend.label} exited with code ${code}: ${detail}`
+                    : `${backend.label} exited with code ${code}`;
+                console.error(`[streamCodex] ${msg}`);
+                finishTurn(state.db, turnId, { status: 'failed', exitCode: code, error: msg, usage: cache.usage || undefined });
+                if (wasCurrent) {
+                    webContents.send('codex-turn-status', { cid: conversationId, turnId, status: 'failed' });
+                    webContents.send('codex-stream-error', {
+                        cid: conversationId,
+                        data: msg,
+                        messageId: draft.messageId,
+                    });
+                    // Later prompts were queued to follow this one; they are dropped with it.
+                    clearPromptQueue(webContents, state, conversationId);
+                }
             }
-        }
+            startWaitingTurns(webContents, state);
+        });
 
-        if (code === 0 || code === null) {
-            webContents.send('codex-stream-end', { cid: conversationId });
-        } else {
-            const detail = stderrAccum.trim();
-            const msg = detail
-                ? `Codex exited with code ${code}: ${detail}`
-                : `Codex exited with code ${code}`;
-            console.error(`[streamCodex] ${msg}`);
+        child.on('close', () => releaseTurnBaseline(turnId, baseline));
+
+        child.on('error', (err) => {
+            releaseTurnBaseline(turnId, baseline);
+            state.runningCodex.delete(conversationId);
+            draft.finish();
+            releaseTempDirs(state, conversationId);
+            finishTurn(state.db, turnId, { status: 'failed', error: err.message });
+            webContents.send('codex-turn-status', { cid: conversationId, turnId, status: 'failed' });
             webContents.send('codex-stream-error', {
                 cid: conversationId,
-                data: msg,
+                data: err.message,
+                messageId: draft.messageId,
             });
+            clearPromptQueue(webContents, state, conversationId);
+            startWaitingTurns(webContents, state);
+        });
+    }
+}
+
+// ===== Stream Snapshot =====
+
+/**
+ * What a conversation's running turn has streamed so far: the agent and reasoning text,
+ * the latest state of each tool call and the approvals still waiting for an answer. A UI
+ * that reloaded mid-turn renders this and then keeps applying the live events.
+ */
+export function getStreamSnapshot(state: AppState, conversationId: string): StreamSnapshot {
+    const running = state.runningCodex.get(conversationId);
+    const stream = running?.stream;
+    const approvals = Array.from(state.pendingApprovals.values())
+        .filter((pending) => pending.conversationId === conversationId)
+        .map(({ request }) => ({ requestId: request.requestId, title: request.title, description: request.description }));
+    return {
+        cid: conversationId,
+        running: !!running,
+        turnId: running?.turnId ?? null,
+        interrupting: !!running?.interrupting,
+        text: stream?.agentText ?? '',
+        thinking: stream?.thinkingText ?? '',
+        truncated: !!stream && (stream.agentTextTruncated || stream.thinkingTextTruncated),
+        toolCalls: stream ? Array.from(stream.toolCalls.values()) : [],
+        approvals,
+    };
+}
+
+export function checkPromptPathsForConversation(
+    state: AppState,
+    conversationId: string,
+    prompt: string,
+): ReturnType<typeof checkPromptPaths> {
+    const overrides = dbGetConversationOverrides(state.db, conversationId);
+    const runConfig = resolveRuntimeConfig(state.config, overrides.cliOverrides, overrides.workspaceCliOptions);
+    return checkPromptPaths(prompt, runConfig);
+}
+
+// ===== Move Conversation =====
+
+/**
+ * Move a conversation to another workspace. With `migrateCwd`, a running turn is cancelled
+ * (it was started in the old directory; it is not restarted) and a conversation-level cwd
+ * override that pointed inside the old workspace is rebased onto the new root.
+ */
+export function moveConversation(
+    webContents: WebContents,
+    state: AppState,
+    conversationId: string,
+    targetWorkspaceId: string,
+    migrateCwd: boolean,
+): { success: boolean; cwd?: string; cancelled?: boolean; error?: string } {
+    const moved = dbMoveConversation(state.db, conversationId, targetWorkspaceId);
+    if (!moved.success) return { success: false, error: moved.error };
+
+    let cwd = moved.toPath!;
+    let cancelled = false;
+
+    if (migrateCwd) {
+        const { cliOverrides } = dbGetConversationOverrides(state.db, conversationId);
+        const override = cliOverrides?.cwdOverride?.trim();
+        if (cliOverrides && override) {
+            const relative = path.relative(moved.fromPath!, expandTildePath(override));
+            const insideOld = relative === '' || (!relative.startsWith('..') && !path.isAbsolute(relative));
+            if (insideOld) {
+                cwd = path.join(moved.toPath!, relative);
+                dbUpdateConversationCliOverrides(state.db, conversationId, {
+                    ...cliOverrides,
+                    cwdOverride: cwd,
+                });
+            }
+        }
+
+        if (state.runningCodex.has(conversationId)) {
+            cancelPrompt(webContents, conversationId, state);
+            cancelled = true;
         }
+    }
+
+    webContents.send('codex-conversation-moved', {
+        cid: conversationId,
+        workspaceId: targetWorkspaceId,
+        cwd,
+        cancelled,
     });
+    return { success: true, cwd, cancelled };
+}
 
-    child.on('error', (err) => {
-        state.runningCodex.delete(conversationId);
-        webContents.send('codex-stream-error', {
-            cid: conversationId,
-            data: err.message,
+// ===== Conversation Cwd =====
+
+/**
+ * Run a conversation's turns in `cwdPath` (relative to the workspace root, or absolute
+ * inside it) instead of the workspace/global cwd; null clears it. Takes effect from the
+ * next turn.
+ */
+export function setConversationCwd(
+    state: AppState,
+    conversationId: string,
+    cwdPath: string | null,
+): { success: boolean; cwd?: string | null; error?: string } {
+    if (cwdPath === null || !cwdPath.trim()) {
+        dbSetConversationCwd(state.db, conversationId, null);
+        return { success: true, cwd: null };
+    }
+    const row = state.db
+        .prepare('SELECT w.path FROM conversations c JOIN workspaces w ON w.id = c.workspace_id WHERE c.id = ?')
+        .get(conversationId) as any;
+    if (!row) return { success: false, error: 'Conversation not found' };
+
+    try {
+        const root = fs.realpathSync(expandTildePath(row.path));
+        const target = fs.realpathSync(path.resolve(root, expandTildePath(cwdPath.trim())));
+        const relative = path.relative(root, target);
+        if (relative.startsWith('..') || path.isAbsolute(relative)) {
+            return { success: false, error: 'Directory is outside the workspace' };
+        }
+        if (!fs.statSync(target).isDirectory()) return { success: false, error: 'Not a directory' };
+        const cwd = relative.split(path.sep).join('/');
+        dbSetConversationCwd(state.db, conversationId, cwd || null);
+        return { success: true, cwd: cwd || null };
+    } catch (err: any) {
+        return { success: false, error: err.code === 'ENOENT' ? 'Directory does not exist' : err.message };
+    }
+}
+
+// ===== Safety Review =====
+
+const REVIEW_MODE_KEY = 'reviewMode.enabled';
+
+export function getReviewMode(state: AppState): boolean {
+    return dbGetSetting(state.db, REVIEW_MODE_KEY, false);
+}
+
+/**
+ * Plan-then-execute: every turn (outside yolo mode) first runs read-only and proposes
+ * the commands and files it needs. After one approval the turn is rerun with write
+ * access, where only the approved commands and files go through.
+ */
+export function setReviewMode(state: AppState, enabled: boolean): boolean {
+    dbSetSetting(state.db, REVIEW_MODE_KEY, enabled);
+    return enabled;
+}
+
+function holdForReview(
+    webContents: WebContents,
+    state: AppState,
+    conversationId: string,
+    turnId: string,
+    review: Extract<TurnReview, { phase: 'plan' }>,
+    plan: string,
+    conversationHistory: Array<{ role: string; content: string }> | undefined,
+    images: string[],
+): void {
+    const pending: PendingReview = {
+        conversationId,
+        turnId,
+        prompt: review.prompt,
+        task: review.task,
+        conversationHistory,
+        images,
+        runConfig: review.runConfig,
+        plan,
+        proposed: parseReviewProposal(plan),
+        steps: parsePlanSteps(plan),
+        createdAt: new Date().toISOString(),
+    };
+    state.pendingReviews.set(conversationId, pending);
+    webContents.send('codex-review-request', { cid: conversationId, turnId, plan, ...pending.proposed, steps: pending.steps });
+}
+
+export function getPendingReview(
+    state: AppState,
+    conversationId: string,
+): { turnId: string; prompt: string; plan: string; proposed: ReviewScope; steps: PlanStep[]; createdAt: string } | null {
+    const pending = state.pendingReviews.get(conversationId);
+    if (!pending) return null;
+    return {
+        turnId: pending.turnId,
+        prompt: pending.prompt,
+        plan: pending.plan,
+        proposed: pending.proposed,
+        steps: pending.steps,
+        createdAt: pending.createdAt,
+    };
+}
+
+/**
+ * Approve a reviewed plan and run it with write access. `scope` narrows (or widens)
+ * what the plan proposed; without it the proposal is approved as is.
+ */
+export function approveReview(
+    webContents: WebContents,
+    state: AppState,
+    conversationId: string,
+    scope?: Partial<ReviewScope>,
+): { success: boolean; turnId?: string; scope?: ReviewScope; error?: string } {
+    const pending = state.pendingReviews.get(conversationId);
+    if (!pending) return { success: false, error: 'No plan is waiting for review' };
+    return runApprovedPlan(webContents, state, pending, scope ? normalizeReviewScope(scope) : pending.proposed);
+}
+
+/**
+ * Run only some steps of a reviewed plan, by their 0-based indices. The approved pass is
+ * limited to those steps' commands and files; when the plan's steps list none (a plain
+ * numbered list) it gets the whole proposal's scope and the prompt names the steps.
+ */
+export function executePlan(
+    webContents: WebContents,
+    state: AppState,
+    conversationId: string,
+    approvedStepIndices: number[],
+): { success: boolean; turnId?: string; scope?: ReviewScope; error?: string } {
+    const pending = state.pendingReviews.get(conversationId);
+    if (!pending) return { success: false, error: 'No plan is waiting for review' };
+    const indices = Array.from(new Set(approvedStepIndices)).sort((a, b) => a - b);
+    if (indices.length === 0) return { success: false, error: 'Approve at least one step' };
+    const invalid = indices.find((i) => !Number.isInteger(i) || i < 0 || i >= pending.steps.length);
+    if (invalid !== undefined) return { success: false, error: `No step ${invalid} in the plan` };
+
+    const steps = indices.map((index) => ({ ...pending.steps[index], index }));
+    const scoped = pending.steps.some((step) => step.commands.length > 0 || step.files.length > 0);
+    return runApprovedPlan(webContents, state, pending, scoped ? stepsScope(steps) : pending.proposed, steps);
+}
+
+function runApprovedPlan(
+    webContents: WebContents,
+    state: AppState,
+    pending: PendingReview,
+    approved: ReviewScope,
+    steps?: Array<PlanStep & { index: number }>,
+): { success: boolean; turnId?: string; scope?: ReviewScope; error?: string } {
+    const { conversationId } = pending;
+    if (state.runningCodex.has(conversationId)) return { success: false, error: 'A turn is already running' };
+    if (atTurnLimit(state)) return { success: false, error: 'Too many turns are running; try again when one finishes' };
+
+    state.pendingReviews.delete(conversationId);
+    const turnId = generateId('turn');
+    createTurn(state.db, turnId, conversationId, pending.prompt, 'running');
+    runCodexTurn(
+        webContents,
+        state,
+        conversationId,
+        buildExecutionPrompt(pending.task, pending.plan, approved, steps),
+        pending.conversationHistory,
+        pending.images,
+        reviewRunConfig(pending.runConfig, 'execute'),
+        turnId,
+        dbGetConversationOverrides(state.db, conversationId).sessionId,
+        { phase: 'execute', scope: approved },
+    );
+    return { success: true, turnId, scope: approved };
+}
+
+/** Drop a reviewed plan without running it, and go on with queued prompts. */
+export function rejectReview(webContents: WebContents, state: AppState, conversationId: string): { success: boolean } {
+    const rejected = state.pendingReviews.delete(conversationId);
+    if (rejected && !state.runningCodex.has(conversationId)) startNextQueued(webContents, state, conversationId);
+    return { success: rejected };
+}
+
+// ===== Headless Turns =====
+
+/**
+ * Run a single non-interactive codex turn without streaming to the UI and resolve with
+ * the final agent message text. Used by background features (chores, summaries).
+ */
+export function runCodexToCompletion(
+    prompt: string,
+    cfg: RuntimeConfig,
+): Promise<{ success: boolean; text: string; error?: string }> {
+    const backend = agentBackend(cfg);
+    const [, runCwd, args] = backend.buildArgs(prompt, cfg);
+    const child = spawnCommand(backend.bin, args, runCwd);
+    const mapEvent = backend.createMapper();
+    try { child.stdin?.end(); } catch { }
+
+    return new Promise((resolve) => {
+        const messages: string[] = [];
+        let stderr = '';
+        let failure = '';
+
+        if (child.stdout) {
+            const rl = readline.createInterface({ input: child.stdout });
+            rl.on('line', (line: string) => {
+                try {
+                    for (const event of mapEvent(JSON.parse(line))) {
+                        const itemType = (event.item?.type || '').toLowerCase();
+                        if (
+                            event.type === 'item.completed' &&
+                            (itemType === 'agent_message' || itemType === 'message') &&
+                            event.item.text
+                        ) {
+                            messages.push(event.item.text);
+                        } else if (event.type === 'turn.failed' || event.type === 'error') {
+                            failure = event.error?.message || event.message || 'Turn failed';
+                        } else if (event.type === 'protocol.mismatch') {
+                            console.error(`[runCodexToCompletion] ${event.mismatch.message}`);
+                        }
+                    }
+                } catch { }
+            });
+        }
+        if (child.stderr) {
+            child.stderr.on('data', (data) => { stderr += data.toString(); });
+        }
+
+        child.on('exit', (code) => {
+            const text = messages.join('\n\n');
+            if (code === 0 && !failure) {
+                resolve({ success: true, text });
+            } else {
+                resolve({
+                    success: false,
+                    text,
+                    error: failure || stderr.trim() || `${backend.label} exited with code ${code}`,
+                });
+            }
         });
+        child.on('error', (err) => resolve({ success: false, text: '', error: err.message }));
     });
 }
 
 // ===== Run Codex Command =====
 
+const MAX_COMMAND_OUTPUT = 10 * 1024 * 1024;
+
+/**
+ * Run `codex <subcommand> <args...>` and collect its output. The child is awaited rather
+ * than run synchronously, so a slow subcommand does not stall the main process and every
+ * other IPC call with it. Arguments are passed as given, without a shell.
+ */
 export function runCodexCommand(
     state: AppState,
     subcommand: string,
     args: string[],
     cwd?: string,
-): CommandResult {
+): Promise<CommandResult> {
     const runCwd = expandTildePath(cwd || state.config.cwd);
 
-    try {
-        const { execSync } = require('child_process');
-        const opts = commandSpawnOptions(runCwd);
-        const result = execSync(`codex ${subcommand} ${args.join(' ')}`, {
-            ...opts,
-            stdio: ['pipe', 'pipe', 'pipe'],
-            maxBuffer: 10 * 1024 * 1024,
-        });
-        return {
-            success: true,
-            stdout: result.toString(),
-            stderr: '',
-            exitCode: 0,
-        };
-    } catch (err: any) {
-        return {
-            success: false,
-            stdout: err.stdout?.toString() || '',
-            stderr: err.stderr?.toString() || '',
-            exitCode: err.status ?? -1,
-            error: err.message,
+    return new Promise((resolve) => {
+        const child = spawnCommand('codex', [subcommand, ...args], runCwd);
+        child.stdin?.end();
+        let stdout = '';
+        let stderr = '';
+        let overflow = false;
+        const collect = (chunk: Buffer, into: 'stdout' | 'stderr') => {
+            if (stdout.length + stderr.length + chunk.length > MAX_COMMAND_OUTPUT) {
+                overflow = true;
+                child.kill();
+                return;
+            }
+            if (into === 'stdout') stdout += chunk.toString();
+            else stderr += chunk.toString();
         };
-    }
+        child.stdout?.on('data', (chunk: Buffer) => collect(chunk, 'stdout'));
+        child.stderr?.on('data', (chunk: Buffer) => collect(chunk, 'stderr'));
+        child.on('error', (err) => {
+            resolve({ success: false, stdout, stderr, exitCode: -1, error: err.message });
+        });
+        child.on('close', (code) => {
+            const exitCode = code ?? -1;
+            if (exitCode === 0 && !overflow) {
+                resolve({ success: true, stdout, stderr, exitCode });
+                return;
+            }
+            resolve({
+                success: false,
+                stdout,
+                stderr,
+                exitCode,
+                error: overflow ? 'Command output exceeded 10 MB' : `codex ${subcommand} exited with code ${exitCode}`,
+            });
+        });
+    });
 }
 
 // ===== Title Bar Overlay =====
@@ -361,11 +1320,20 @@ export function updateTitleBarOverlay(
 
 // ===== Respond to Approval =====
 
+/**
+ * Answer a pending approval. `decision` may be a plain boolean (approve/deny); `message`
+ * is passed to codex as the reason when denying.
+ */
 export function respondToApproval(
     requestId: string,
-    approved: boolean,
+    decision: ApprovalDecision | boolean,
     state: AppState,
+    message?: string,
 ): { success: boolean; error?: string } {
+    const resolved: ApprovalDecision = decision === true ? 'approve' : decision === false ? 'deny' : decision;
+    if (!['approve', 'approve_for_session', 'deny', 'abort'].includes(resolved)) {
+        return { success: false, error: `Unknown approval decision: ${resolved}` };
+    }
     const pending = state.pendingApprovals.get(requestId);
     if (!pending) {
         return { success: false, error: 'Approval request not found' };
@@ -381,7 +1349,7 @@ export function respondToApproval(
         return { success: false, error: 'Process stdin is not available' };
     }
 
-    const payload = JSON.stringify({ request_id: requestId, approved });
+    const payload = buildApprovalResponse(pending.request, resolved, message);
     try {
         process.stdin.write(payload + '\n');
         return { success: true };
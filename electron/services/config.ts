@@ -0,0 +1,189 @@
+import * as fs from 'fs';
+import * as path from 'path';
+import { homeDir } from './utils';
+
+export type TomlValue = string | number | boolean | TomlValue[];
+
+export interface CodexProfile {
+    name: string;
+    model: string | null;
+    modelProvider: string | null;
+    sandbox: string | null;
+    approvalPolicy: string | null;
+    reasoningEffort: string | null;
+}
+
+export interface McpServerConfig {
+    name: string;
+    /** stdio servers: the launch command. */
+    command: string | null;
+    args: string[];
+    env: Record<string, string>;
+    /** Remote (streamable HTTP) servers. */
+    url: string | null;
+    enabled: boolean;
+    startupTimeoutSec: number | null;
+}
+
+export function codexConfigPath(): string {
+    const home = homeDir();
+    if (!home) throw new Error('Unable to resolve home directory');
+    return path.join(process.env.CODEX_HOME || path.join(home, '.codex'), 'config.toml');
+}
+
+function stripComment(line: string): string {
+    let quote: string | null = null;
+    for (let i = 0; i < line.length; i++) {
+        const ch = line[i];
+        if (quote) {
+            if (ch === '\\' && quote === '"') i++;
+            else if (ch === quote) quote = null;
+        } else if (ch === '"' || ch === "'") {
+            quote = ch;
+        } else if (ch === '#') {
+            return line.slice(0, i);
+        }
+    }
+    return line;
+}
+
+/** Split a dotted key or table header (`profiles."my profile"`) into its parts. */
+function splitKey(raw: string): string[] {
+    const parts: string[] = [];
+    for (const match of raw.matchAll(/\s*(?:"((?:[^"\\]|\\.)*)"|'([^']*)'|([A-Za-z0-9_-]+))\s*(?:\.|$)/g)) {
+        parts.push(match[1] ?? match[2] ?? match[3]);
+    }
+    return parts;
+}
+
+function parseValue(raw: string): TomlValue {
+    const value = raw.trim();
+    if (value.startsWith('"')) return JSON.parse(value);
+    if (value.startsWith("'")) return value.slice(1, -1);
+    if (value === 'true' || value === 'false') return value === 'true';
+    if (value.startsWith('[')) {
+        const inner = value.slice(1, -1).trim();
+        if (!inner) return [];
+        return (inner.match(/"(?:[^"\\]|\\.)*"|'[^']*'|[^,\s][^,]*/g) || []).map((item) => parseValue(item));
+    }
+    const num = Number(value.replace(/_/g, ''));
+    return Number.isNaN(num) ? value : num;
+}
+
+/**
+ * Minimal TOML reader covering what codex's config.toml uses: tables, dotted keys and
+ * single-line strings, numbers, booleans and arrays. Inline tables and multi-line values
+ * are skipped. Returns a map of table path (`""` for the root, `profiles.fast`, ...) to
+ * its keys.
+ */
+export function parseTomlTables(text: string): Record<string, Record<string, TomlValue>> {
+    const tables: Record<string, Record<string, TomlValue>> = { '': {} };
+    let current = '';
+
+    for (const rawLine of text.split(/\r?\n/)) {
+        const line = stripComment(rawLine).trim();
+        if (!line) continue;
+
+        const header = line.match(/^\[\s*([^\[\]]+?)\s*\]$/);
+        if (header) {
+            current = splitKey(header[1]).join('.');
+            tables[current] = tables[current] || {};
+            continue;
+        }
+
+        const eq = line.indexOf('=');
+        if (eq < 0) continue;
+        const keyParts = splitKey(line.slice(0, eq));
+        const rawValue = line.slice(eq + 1).trim();
+        if (!keyParts.length || rawValue.startsWith('{')) continue;
+
+        let value: TomlValue;
+        try {
+            value = parseValue(rawValue);
+        } catch {
+            continue;
+        }
+        const table = [current, ...keyParts.slice(0, -1)].filter(Boolean).join('.');
+        tables[table] = tables[table] || {};
+        tables[table][keyParts[keyParts.length - 1]] = value;
+    }
+
+    return tables;
+}
+
+export function profilesFromConfig(text: string): CodexProfile[] {
+    const tables = parseTomlTables(text);
+    const str = (v: TomlValue | undefined) => (typeof v === 'string' ? v : null);
+    return Object.entries(tables)
+        .filter(([table]) => /^profiles\.[^.]+$/.test(table))
+        .map(([table, values]) => ({
+            name: table.slice('profiles.'.length),
+            model: str(values.model),
+            modelProvider: str(values.model_provider),
+            sandbox: str(values.sandbox_mode),
+            approvalPolicy: str(values.approval_policy),
+            reasoningEffort: str(values.model_reasoning_effort),
+        }))
+        .sort((a, b) => a.name.localeCompare(b.name));
+}
+
+/**
+ * `[mcp_servers.<name>]` entries. `env` is read from a `[mcp_servers.<name>.env]` table;
+ * the inline-table form is not supported by the reader and comes back empty.
+ */
+export function mcpServersFromConfig(text: string): McpServerConfig[] {
+    const tables = parseTomlTables(text);
+    const str = (v: TomlValue | undefined) => (typeof v === 'string' ? v : null);
+    return Object.entries(tables)
+        .filter(([table]) => /^mcp_servers\.[^.]+$/.test(table))
+        .map(([table, values]) => {
+            const name = table.slice('mcp_servers.'.length);
+            const env = Object.fromEntries(
+                Object.entries(tables[`${table}.env`] || {}).map(([key, value]) => [key, String(value)]),
+            );
+            return {
+                name,
+                command: str(values.command),
+                args: Array.isArray(values.args) ? values.args.map(String) : [],
+                env,
+                url: str(values.url),
+                enabled: values.enabled !== false,
+                startupTimeoutSec: typeof values.startup_timeout_sec === 'number' ? values.startup_timeout_sec : null,
+            };
+        })
+        .sort((a, b) => a.name.localeCompare(b.name));
+}
+
+export function getMcpServers(): McpServerConfig[] {
+    try {
+        const configPath = codexConfigPath();
+        if (!fs.existsSync(configPath)) return [];
+        return mcpServersFromConfig(fs.readFileSync(configPath, 'utf-8'));
+    } catch {
+        return [];
+    }
+}
+
+export function getCodexProfiles(): { success: boolean; profiles: CodexProfile[]; path: string; error?: string } {
+    let configPath = '';
+    try {
+        configPath = codexConfigPath();
+        if (!fs.existsSync(configPath)) return { success: true, profiles: [], path: configPath };
+        return { success: true, profiles: profilesFromConfig(fs.readFileSync(configPath, 'utf-8')), path: configPath };
+    } catch (err: any) {
+        return { success: false, profiles: [], path: configPath, error: err.message };
+    }
+}
+
+/**
+ * Error message for a profile name codex would reject, or null. An empty name (no
+ * profile) is always valid; an unreadable config is not treated as a reason to block.
+ */
+export function validateCodexProfile(name: string | undefined): string | null {
+    const profile = name?.trim();
+    if (!profile) return null;
+    const result = getCodexProfiles();
+    if (!result.success) return null;
+    if (result.profiles.some((p) => p.name === profile)) return null;
+    return `Codex profile "${profile}" is not defined in ${result.path}`;
+}
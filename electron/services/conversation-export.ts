@@ -0,0 +1,213 @@
+import Database from 'better-sqlite3';
+import * as fs from 'fs';
+import { CliOverrides, Conversation, Message } from './models';
+import {
+    dbCreateConversation,
+    dbCreateMessage,
+    dbGetConversationNotes,
+    dbGetMessages,
+    dbSetConversationNotes,
+    parseJsonColumn,
+} from './db';
+import { ArtifactTheme, getArtifactTheme, THEME_PALETTES } from './themes';
+import { expandTildePath, generateId } from './utils';
+
+export type ConversationExportFormat = 'markdown' | 'json' | 'html';
+
+export const CONVERSATION_EXPORT_VERSION = 1;
+
+export interface ConversationExport {
+    version: number;
+    exportedAt: string;
+    conversation: {
+        id: string;
+        title: string;
+        createdAt: string;
+        updatedAt: string;
+        persona?: string;
+        cliOverrides?: CliOverrides;
+    };
+    notes?: string;
+    messages: Message[];
+}
+
+const ROLE_LABELS: Record<string, string> = { user: 'User', assistant: 'Assistant', system: 'System' };
+
+function safeFileName(title: string): string {
+    return title.replace(/[\\/:*?"<>|]+/g, '_').trim().slice(0, 80) || 'conversation';
+}
+
+function toMarkdown(data: ConversationExport): string {
+    const parts = [`# ${data.conversation.title}`, '', `_Exported ${data.exportedAt}_`, ''];
+    if (data.notes) parts.push('## Notes', '', data.notes.trim(), '');
+    for (const message of data.messages) {
+        parts.push(`## ${ROLE_LABELS[message.role] || message.role}`, '', message.content.trim(), '');
+    }
+    return parts.join('\n');
+}
+
+function escapeHtml(text: string): string {
+    return text.replace(/[&<>"']/g, (ch) => `&#${ch.charCodeAt(0)};`);
+}
+
+/** Self-contained HTML page colored with the given artifact theme. */
+export function toHtml(data: ConversationExport, theme: ArtifactTheme): string {
+    const p = THEME_PALETTES[theme];
+    const block = (label: string, body: string, background: string) =>
+        `<section style="background:${background}"><h2>${escapeHtml(label)}</h2><div class="body">${escapeHtml(body.trim())}</div></section>`;
+    const sections = [
+        data.notes ? block('Notes', data.notes, p.background) : '',
+        ...data.messages.map((m) =>
+            block(ROLE_LABELS[m.role] || m.role, m.content, m.role === 'user' ? p.user : p.assistant),
+        ),
+    ];
+    return `<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>${escapeHtml(data.conversation.title)}</title>
+<style>
+body { background: ${p.background}; color: ${p.text}; font: 15px/1.55 -apple-system, "Segoe UI", sans-serif; max-width: 860px; margin: 2rem auto; padding: 0 1rem; }
+h1 { border-bottom: 2px solid ${p.accent}; padding-bottom: .4rem; }
+.meta { color: ${p.muted}; }
+section { border: 1px solid ${p.border}; border-radius: 6px; padding: .75rem 1rem; margin: 1rem 0; }
+h2 { font-size: .85rem; text-transform: uppercase; color: ${p.accent}; margin: 0 0 .5rem; }
+.body { white-space: pre-wrap; overflow-wrap: anywhere; }
+</style>
+</head>
+<body>
+<h1>${escapeHtml(data.conversation.title)}</h1>
+<p class="meta">Exported ${escapeHtml(data.exportedAt)}</p>
+${sections.filter(Boolean).join('\n')}
+</body>
+</html>
+`;
+}
+
+/** The JSON export structure of a conversation, or null if it does not exist. */
+export function buildConversationExport(
+    db: Database.Database,
+    conversationId: string,
+    includeNotes: boolean,
+): ConversationExport | null {
+    const row = db
+        .prepare('SELECT id, title, created_at, updated_at, persona, cli_overrides FROM conversations WHERE id = ?')
+        .get(conversationId) as any;
+    if (!row) return null;
+
+    const notes = includeNotes ? dbGetConversationNotes(db, conversationId).notes : '';
+    return {
+        version: CONVERSATION_EXPORT_VERSION,
+        exportedAt: new Date().toISOString(),
+        conversation: {
+            id: row.id,
+            title: row.title,
+            createdAt: row.created_at,
+            updatedAt: row.updated_at,
+            persona: row.persona || undefined,
+            cliOverrides: parseJsonColumn<CliOverrides>(row.cli_overrides),
+        },
+        notes: notes || undefined,
+        messages: dbGetMessages(db, conversationId),
+    };
+}
+
+/**
+ * Serialize a conversation as Markdown (for reading), HTML (for sharing, colored with
+ * the artifact theme setting) or JSON (for re-import). Scratchpad notes are left out
+ * unless `includeNotes` is set.
+ */
+export function exportConversation(
+    db: Database.Database,
+    conversationId: string,
+    format: ConversationExportFormat = 'markdown',
+    includeNotes = false,
+): { success: boolean; fileName?: string; content?: string; error?: string } {
+    const data = buildConversationExport(db, conversationId, includeNotes);
+    if (!data) return { success: false, error: 'Conversation not found' };
+
+    const base = safeFileName(data.conversation.title);
+    if (format === 'json') return { success: true, fileName: `${base}.json`, content: JSON.stringify(data, null, 2) };
+    if (format === 'html') return { success: true, fileName: `${base}.html`, content: toHtml(data, getArtifactTheme(db)) };
+    return { success: true, fileName: `${base}.md`, content: toMarkdown(data) };
+}
+
+/**
+ * Re-create a conversation from a JSON export in `workspaceId`. The conversation and its
+ * messages get new ids so importing twice (or on the machine it came from) never
+ * collides; timestamps, persona, CLI overrides and notes are kept as exported.
+ */
+export function importConversation(
+    db: Database.Database,
+    jsonPath: string,
+    workspaceId: string,
+): { success: boolean; conversation?: Conversation; error?: string } {
+    let data: ConversationExport;
+    try {
+        data = JSON.parse(fs.readFileSync(expandTildePath(jsonPath), 'utf-8'));
+    } catch (err: any) {
+        return { success: false, error: `Could not read export: ${err.message}` };
+    }
+    return importConversationData(db, data, workspaceId);
+}
+
+/** importConversation for an already parsed export (also used by workspace bundles). */
+export function importConversationData(
+    db: Database.Database,
+    data: ConversationExport,
+    workspaceId: string,
+): { success: boolean; conversation?: Conversation; error?: string } {
+    if (typeof data?.version !== 'number' || !data.conversation || !Array.isArray(data.messages)) {
+        return { success: false, error: 'Not a conversation export' };
+    }
+    if (data.version > CONVERSATION_EXPORT_VERSION) {
+        return { success: false, error: `Export version ${data.version} is newer than this app supports` };
+    }
+    if (!db.prepare('SELECT 1 FROM workspaces WHERE id = ?').get(workspaceId)) {
+        return { success: false, error: 'Workspace not found' };
+    }
+
+    const source = data.conversation;
+    const conversationId = generateId('conv');
+    const importAll = db.transaction(() => {
+        const conversation = dbCreateConversation(db, conversationId, workspaceId, source.title || 'Imported conversation');
+        for (const message of data.messages) {
+            conversation.messages.push(
+                dbCreateMessage(db, {
+                    id: generateId('msg'),
+                    conversationId,
+                    role: message.role,
+                    content: message.content ?? '',
+                    thinking: message.thinking,
+                    thinkingDuration: message.thinkingDuration,
+                    attachments: message.attachments,
+                    timestamp: message.timestamp,
+                }),
+            );
+        }
+        if (data.notes) dbSetConversationNotes(db, conversationId, data.notes);
+        // dbCreateMessage bumps updated_at; put the exported timestamps back afterwards.
+        db.prepare(`
+    UPDATE conversations SET created_at = ?, updated_at = ?, persona = ?, cli_overrides = ? WHERE id = ?
+  `).run(
+            source.createdAt || conversation.createdAt,
+            source.updatedAt || source.createdAt || conversation.updatedAt,
+            source.persona || null,
+            source.cliOverrides ? JSON.stringify(source.cliOverrides) : null,
+            conversationId,
+        );
+        return {
+            ...conversation,
+            createdAt: source.createdAt || conversation.createdAt,
+            updatedAt: source.updatedAt || source.createdAt || conversation.updatedAt,
+            persona: source.persona,
+            cliOverrides: source.cliOverrides,
+        };
+    });
+
+    try {
+        return { success: true, conversation: importAll() };
+    } catch (err: any) {
+        return { success: false, error: err.message };
+    }
+}
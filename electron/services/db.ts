@@ -1,8 +1,19 @@
 import Database from 'better-sqlite3';
 import * as fs from 'fs';
 import * as path from 'path';
-import { Conversation, DbState, Message, Workspace } from './models';
-import { expandTildePath, homeDir, nowIso } from './utils';
+import {
+    CliOverrides,
+    Conversation,
+    DbState,
+    Message,
+    MessageAttachment,
+    MessageSearchResult,
+    TurnEvent,
+    Workspace,
+    WorkspaceCliOptions,
+    WorkspacePackage,
+} from './models';
+import { expandTildePath, generateId, homeDir, nowIso } from './utils';
 
 function dbFilePath(): string {
     const home = homeDir();
@@ -12,10 +23,72 @@ function dbFilePath(): string {
     return path.join(dir, 'state.sqlite3');
 }
 
-function ensureSchema(db: Database.Database): void {
+// ===== Schema Migrations =====
+
+interface SchemaMigration {
+    version: number;
+    description: string;
+    up: (db: Database.Database) => void;
+}
+
+/**
+ * Schema changes in the order they were made. The database's `user_version` records the
+ * last one applied; append new steps with the next version number and never edit one
+ * that has shipped.
+ */
+const MIGRATIONS: SchemaMigration[] = [
+    // Databases from before versioning start at 0; the base schema only adds what they lack.
+    { version: 1, description: 'Base schema', up: createBaseSchema },
+    { version: 2, description: 'Change log for incremental sync', up: createChangeLog },
+    { version: 3, description: 'Outgoing webhooks', up: createWebhookTable },
+    { version: 4, description: 'Turn outcomes and launch presets of turns', up: addTurnOutcomes },
+    { version: 5, description: 'Scheduled prompts', up: createScheduledTaskTable },
+    { version: 6, description: 'Rolling summaries of conversation history', up: createConversationSummaryTable },
+    { version: 7, description: 'Prompt templates', up: createPromptTemplateTable },
+    { version: 8, description: 'Assistant message drafts saved while streaming', up: addMessageDrafts },
+    { version: 9, description: 'Codex events of each turn', up: createTurnEventTable },
+];
+
+export const SCHEMA_VERSION = MIGRATIONS[MIGRATIONS.length - 1].version;
+
+export function dbGetSchemaVersion(db: Database.Database): { version: number; latest: number } {
+    return { version: db.pragma('user_version', { simple: true }) as number, latest: SCHEMA_VERSION };
+}
+
+/**
+ * Bring the database up to SCHEMA_VERSION. Each migration runs in its own transaction
+ * together with the version bump, so a failure leaves the database at the last complete
+ * step. A file database with data is first copied to `<db>.before-v<latest>`; migrating
+ * is refused when that copy cannot be made, or when the database comes from a newer app.
+ */
+export function ensureSchema(db: Database.Database): void {
     db.pragma('foreign_keys = ON');
     db.pragma('journal_mode = WAL');
 
+    const { version } = dbGetSchemaVersion(db);
+    if (version > SCHEMA_VERSION) {
+        throw new Error(`Database schema version ${version} is newer than this app supports (${SCHEMA_VERSION})`);
+    }
+    const pending = MIGRATIONS.filter((m) => m.version > version);
+    if (pending.length === 0) return;
+
+    const hasData = db.prepare("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'workspaces'").get();
+    if (hasData && !db.memory) {
+        const backup = dbBackup(db, `${db.name}.before-v${SCHEMA_VERSION}`);
+        if (!backup.success) throw new Error(`Could not back up the database before migrating: ${backup.error}`);
+    }
+    for (const migration of pending) {
+        db.transaction(() => {
+            migration.up(db);
+            db.pragma(`user_version = ${migration.version}`);
+        })();
+        console.log(`[db] Migrated schema to v${migration.version}: ${migration.description}`);
+    }
+}
+
+function createBaseSchema(db: Database.Database): void {
+    const hadTurns = db.prepare("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'turns'").get();
+
     db.exec(`
     CREATE TABLE IF NOT EXISTS workspaces (
       id TEXT PRIMARY KEY,
@@ -43,11 +116,584 @@ function ensureSchema(db: Database.Database): void {
       FOREIGN KEY(conversation_id) REFERENCES conversations(id) ON DELETE CASCADE
     );
 
+    CREATE TABLE IF NOT EXISTS conversation_templates (
+      id TEXT PRIMARY KEY,
+      name TEXT NOT NULL,
+      title_pattern TEXT NOT NULL,
+      system_message TEXT,
+      initial_messages TEXT NOT NULL,
+      persona TEXT,
+      cli_overrides TEXT,
+      created_at TEXT NOT NULL,
+      updated_at TEXT NOT NULL
+    );
+
+    CREATE TABLE IF NOT EXISTS settings (
+      key TEXT PRIMARY KEY,
+      value TEXT NOT NULL
+    );
+
+    CREATE TABLE IF NOT EXISTS workspace_permissions (
+      workspace_path TEXT NOT NULL,
+      capability TEXT NOT NULL,
+      granted_at TEXT NOT NULL,
+      PRIMARY KEY(workspace_path, capability)
+    );
+
+    CREATE TABLE IF NOT EXISTS message_parts (
+      message_id TEXT NOT NULL,
+      part_index INTEGER NOT NULL,
+      content TEXT NOT NULL,
+      PRIMARY KEY(message_id, part_index),
+      FOREIGN KEY(message_id) REFERENCES messages(id) ON DELETE CASCADE
+    );
+
+    CREATE TABLE IF NOT EXISTS message_stats (
+      message_id TEXT PRIMARY KEY,
+      length INTEGER NOT NULL,
+      words INTEGER NOT NULL,
+      reading_seconds INTEGER NOT NULL,
+      code_lines INTEGER NOT NULL,
+      prose_lines INTEGER NOT NULL,
+      code_ratio REAL NOT NULL,
+      files_touched INTEGER NOT NULL,
+      review_recommended INTEGER NOT NULL,
+      FOREIGN KEY(message_id) REFERENCES messages(id) ON DELETE CASCADE
+    );
+
+    CREATE TABLE IF NOT EXISTS message_usage (
+      message_id TEXT PRIMARY KEY,
+      conversation_id TEXT NOT NULL,
+      model TEXT NOT NULL,
+      prompt_tokens INTEGER NOT NULL,
+      cached_tokens INTEGER NOT NULL,
+      completion_tokens INTEGER NOT NULL,
+      total_tokens INTEGER NOT NULL,
+      created_at TEXT NOT NULL,
+      FOREIGN KEY(message_id) REFERENCES messages(id) ON DELETE CASCADE
+    );
+
+    CREATE TABLE IF NOT EXISTS turn_snapshots (
+      id TEXT PRIMARY KEY,
+      conversation_id TEXT NOT NULL,
+      cwd TEXT NOT NULL,
+      status TEXT NOT NULL DEFAULT 'active',
+      created_at TEXT NOT NULL,
+      rolled_back_at TEXT,
+      FOREIGN KEY(conversation_id) REFERENCES conversations(id) ON DELETE CASCADE
+    );
+
+    CREATE TABLE IF NOT EXISTS turn_snapshot_files (
+      turn_id TEXT NOT NULL,
+      path TEXT NOT NULL,
+      existed INTEGER NOT NULL,
+      backup_path TEXT,
+      PRIMARY KEY(turn_id, path),
+      FOREIGN KEY(turn_id) REFERENCES turn_snapshots(id) ON DELETE CASCADE
+    );
+
+    CREATE TABLE IF NOT EXISTS chores (
+      id TEXT PRIMARY KEY,
+      workspace_id TEXT NOT NULL,
+      name TEXT NOT NULL,
+      prompt TEXT NOT NULL,
+      run_at TEXT NOT NULL,
+      enabled INTEGER NOT NULL DEFAULT 1,
+      last_run_at TEXT,
+      FOREIGN KEY(workspace_id) REFERENCES workspaces(id) ON DELETE CASCADE
+    );
+
+    CREATE TABLE IF NOT EXISTS chore_runs (
+      id TEXT PRIMARY KEY,
+      chore_id TEXT NOT NULL,
+      workspace_id TEXT NOT NULL,
+      status TEXT NOT NULL,
+      plan TEXT,
+      error TEXT,
+      conversation_id TEXT,
+      started_at TEXT NOT NULL,
+      finished_at TEXT,
+      FOREIGN KEY(chore_id) REFERENCES chores(id) ON DELETE CASCADE
+    );
+
+    CREATE TABLE IF NOT EXISTS workspace_settings (
+      workspace_id TEXT PRIMARY KEY,
+      cli_options TEXT,
+      updated_at TEXT NOT NULL,
+      FOREIGN KEY(workspace_id) REFERENCES workspaces(id) ON DELETE CASCADE
+    );
+
+    CREATE TABLE IF NOT EXISTS mcp_calls (
+      id TEXT PRIMARY KEY,
+      turn_id TEXT NOT NULL,
+      conversation_id TEXT NOT NULL,
+      server TEXT NOT NULL,
+      tool TEXT NOT NULL,
+      status TEXT NOT NULL,
+      request TEXT NOT NULL,
+      response TEXT,
+      error TEXT,
+      request_bytes INTEGER NOT NULL DEFAULT 0,
+      response_bytes INTEGER NOT NULL DEFAULT 0,
+      truncated INTEGER NOT NULL DEFAULT 0,
+      started_at TEXT NOT NULL,
+      finished_at TEXT,
+      FOREIGN KEY(conversation_id) REFERENCES conversations(id) ON DELETE CASCADE
+    );
+
+    CREATE INDEX IF NOT EXISTS idx_mcp_calls_turn ON mcp_calls(turn_id);
+
+    CREATE TABLE IF NOT EXISTS watch_rules (
+      id TEXT PRIMARY KEY,
+      workspace_id TEXT NOT NULL,
+      glob TEXT NOT NULL,
+      prompt_template TEXT NOT NULL,
+      debounce_ms INTEGER NOT NULL,
+      max_per_hour INTEGER NOT NULL,
+      enabled INTEGER NOT NULL DEFAULT 1,
+      conversation_id TEXT,
+      last_triggered_at TEXT,
+      created_at TEXT NOT NULL,
+      FOREIGN KEY(workspace_id) REFERENCES workspaces(id) ON DELETE CASCADE
+    );
+
+    CREATE TABLE IF NOT EXISTS prompt_history (
+      id INTEGER PRIMARY KEY AUTOINCREMENT,
+      workspace_id TEXT NOT NULL,
+      prompt TEXT NOT NULL,
+      use_count INTEGER NOT NULL DEFAULT 1,
+      last_used_at TEXT NOT NULL,
+      UNIQUE(workspace_id, prompt)
+    );
+
+    CREATE TABLE IF NOT EXISTS launch_presets (
+      id TEXT PRIMARY KEY,
+      name TEXT NOT NULL,
+      starred INTEGER NOT NULL DEFAULT 0,
+      options TEXT NOT NULL,
+      created_at TEXT NOT NULL
+    );
+
     CREATE INDEX IF NOT EXISTS idx_conversations_workspace_id ON conversations(workspace_id);
+    CREATE TABLE IF NOT EXISTS turns (
+      id TEXT PRIMARY KEY,
+      conversation_id TEXT NOT NULL,
+      user_message_id TEXT,
+      assistant_message_id TEXT,
+      status TEXT NOT NULL,
+      prompt TEXT NOT NULL DEFAULT '',
+      model TEXT,
+      exit_code INTEGER,
+      error TEXT,
+      prompt_tokens INTEGER,
+      cached_tokens INTEGER,
+      completion_tokens INTEGER,
+      total_tokens INTEGER,
+      created_at TEXT NOT NULL,
+      started_at TEXT,
+      finished_at TEXT,
+      FOREIGN KEY(conversation_id) REFERENCES conversations(id) ON DELETE CASCADE
+    );
+
+    CREATE TABLE IF NOT EXISTS turn_tool_calls (
+      turn_id TEXT NOT NULL,
+      item_id TEXT NOT NULL,
+      kind TEXT NOT NULL,
+      name TEXT NOT NULL,
+      status TEXT NOT NULL,
+      started_at TEXT NOT NULL,
+      finished_at TEXT,
+      PRIMARY KEY(turn_id, item_id),
+      FOREIGN KEY(turn_id) REFERENCES turns(id) ON DELETE CASCADE
+    );
+
+    CREATE TABLE IF NOT EXISTS turn_file_diffs (
+      turn_id TEXT NOT NULL,
+      conversation_id TEXT NOT NULL,
+      path TEXT NOT NULL,
+      kind TEXT NOT NULL,
+      move_path TEXT,
+      hunks TEXT NOT NULL,
+      added INTEGER NOT NULL,
+      removed INTEGER NOT NULL,
+      truncated INTEGER NOT NULL DEFAULT 0,
+      created_at TEXT NOT NULL,
+      PRIMARY KEY(turn_id, path),
+      FOREIGN KEY(conversation_id) REFERENCES conversations(id) ON DELETE CASCADE
+    );
+
+    CREATE TABLE IF NOT EXISTS failed_jobs (
+      id TEXT PRIMARY KEY,
+      kind TEXT NOT NULL,
+      title TEXT NOT NULL,
+      params TEXT,
+      error TEXT,
+      started_at TEXT NOT NULL,
+      failed_at TEXT NOT NULL
+    );
+
     CREATE INDEX IF NOT EXISTS idx_messages_conversation_id ON messages(conversation_id);
+    CREATE INDEX IF NOT EXISTS idx_turns_conversation_id ON turns(conversation_id);
+    CREATE INDEX IF NOT EXISTS idx_turn_file_diffs_conversation_id ON turn_file_diffs(conversation_id);
+  `);
+
+    ensureMessageSearchIndex(db);
+
+    ensureColumn(db, 'conversations', 'persona', 'TEXT');
+    ensureColumn(db, 'conversations', 'cli_overrides', 'TEXT');
+    ensureColumn(db, 'conversations', 'auto_translate_lang', 'TEXT');
+    ensureColumn(db, 'conversations', 'codex_session_id', 'TEXT');
+    ensureColumn(db, 'conversations', 'target_package', 'TEXT');
+    ensureColumn(db, 'conversations', 'cwd', 'TEXT');
+    ensureColumn(db, 'conversations', 'forked_from', 'TEXT');
+    ensureColumn(db, 'conversations', 'archived', 'INTEGER NOT NULL DEFAULT 0');
+    ensureColumn(db, 'conversations', 'pinned', 'INTEGER NOT NULL DEFAULT 0');
+    if (ensureColumn(db, 'conversations', 'last_activity_at', 'TEXT')) {
+        db.exec('UPDATE conversations SET last_activity_at = updated_at');
+    }
+    ensureColumn(db, 'conversations', 'notes', 'TEXT');
+    ensureColumn(db, 'conversations', 'notes_in_prompt', 'INTEGER NOT NULL DEFAULT 0');
+    if (ensureColumn(db, 'conversations', 'last_read_message_id', 'TEXT')) {
+        // History from before read tracking starts out read.
+        db.exec(`
+      UPDATE conversations SET last_read_message_id = (
+        SELECT id FROM messages WHERE conversation_id = conversations.id ORDER BY rowid DESC LIMIT 1
+      )
+    `);
+    }
+    ensureColumn(db, 'messages', 'attachments', 'TEXT');
+    ensureColumn(db, 'messages', 'part_count', 'INTEGER NOT NULL DEFAULT 1');
+
+    if (!hadTurns) migrateLegacyTurns(db);
+}
+
+/**
+ * Build turn rows for history recorded before turns existed. Each user message opens a
+ * turn and the assistant message that follows closes it (a reply without a prompt, like a
+ * seeded chore plan, gets a turn of its own). Turn ids already used by snapshots and MCP
+ * call records are reattached to the turn that was running at the time, and token usage
+ * and MCP calls are copied over.
+ */
+function migrateLegacyTurns(db: Database.Database): void {
+    interface LegacyTurn {
+        id: string;
+        conversationId: string;
+        userMessageId: string | null;
+        assistantMessageId: string | null;
+        prompt: string;
+        createdAt: number;
+        reused: boolean;
+    }
+
+    const rows = db.prepare(`
+    SELECT id, conversation_id, role, content, timestamp FROM messages
+    WHERE role IN ('user', 'assistant')
+    ORDER BY conversation_id, rowid
+  `).all() as any[];
+    if (rows.length === 0) return;
+
+    const byConversation = new Map<string, LegacyTurn[]>();
+    for (const row of rows) {
+        const list = byConversation.get(row.conversation_id) || [];
+        byConversation.set(row.conversation_id, list);
+        const current = list[list.length - 1];
+        const createdAt = Math.floor((Date.parse(row.timestamp) || Date.now()) / 1000);
+        if (row.role === 'assistant' && current && !current.assistantMessageId) {
+            current.assistantMessageId = row.id;
+            continue;
+        }
+        list.push({
+            id: `turn-${row.id}`,
+            conversationId: row.conversation_id,
+            userMessageId: row.role === 'user' ? row.id : null,
+            assistantMessageId: row.role === 'assistant' ? row.id : null,
+            prompt: row.role === 'user' ? row.content : '',
+            createdAt,
+            reused: false,
+        });
+    }
+
+    // Snapshot and MCP records carry the ids of turns that ran; match each to the latest
+    // turn that had started by then (message timestamps are a little earlier than the spawn).
+    const known = db.prepare(`
+    SELECT id, conversation_id, CAST(created_at AS INTEGER) AS at FROM turn_snapshots
+    UNION ALL
+    SELECT turn_id, conversation_id, MIN(CAST(started_at AS INTEGER)) FROM mcp_calls GROUP BY turn_id
+    ORDER BY at
+  `).all() as any[];
+    for (const record of known) {
+        const candidates = byConversation.get(record.conversation_id) || [];
+        if (candidates.some((t) => t.id === record.id)) continue;
+        const match = [...candidates].reverse().find((t) => t.createdAt <= record.at);
+        if (match && !match.reused) {
+            match.id = record.id;
+            match.reused = true;
+        }
+    }
+
+    const insertTurn = db.prepare(`
+    INSERT OR IGNORE INTO turns (id, conversation_id, user_message_id, assistant_message_id, status, prompt,
+      model, prompt_tokens, cached_tokens, completion_tokens, total_tokens, created_at, started_at, finished_at)
+    SELECT @id, @conversationId, @userMessageId, @assistantMessageId, @status, @prompt,
+      u.model, u.prompt_tokens, u.cached_tokens, u.completion_tokens, u.total_tokens,
+      @createdAt, @createdAt, COALESCE(u.created_at, @createdAt)
+    FROM (SELECT 1) LEFT JOIN message_usage u ON u.message_id = @assistantMessageId
+  `);
+    const copyMcpCalls = db.prepare(`
+    INSERT OR IGNORE INTO turn_tool_calls (turn_id, item_id, kind, name, status, started_at, finished_at)
+    SELECT turn_id, id, 'mcp_tool_call', server || '.' || tool, status, started_at, finished_at
+    FROM mcp_calls WHERE turn_id = ?
+  `);
+
+    db.transaction(() => {
+        for (const turns of byConversation.values()) {
+            for (const turn of turns) {
+                insertTurn.run({
+                    id: turn.id,
+                    conversationId: turn.conversationId,
+                    userMessageId: turn.userMessageId,
+                    assistantMessageId: turn.assistantMessageId,
+                    status: turn.assistantMessageId ? 'completed' : 'cancelled',
+                    prompt: turn.prompt,
+                    createdAt: String(turn.createdAt),
+                });
+                if (turn.reused) copyMcpCalls.run(turn.id);
+            }
+        }
+    })();
+}
+
+/**
+ * Full-text index over message content, kept in sync by triggers. It stores message ids
+ * rather than mirroring rowids because `messages` has no INTEGER PRIMARY KEY, so its
+ * implicit rowids are not stable across VACUUM.
+ */
+function ensureMessageSearchIndex(db: Database.Database): void {
+    const exists = db
+        .prepare("SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'messages_fts'")
+        .get();
+
+    db.exec(`
+    CREATE VIRTUAL TABLE IF NOT EXISTS messages_fts USING fts5(
+      content,
+      message_id UNINDEXED,
+      tokenize = 'unicode61'
+    );
+
+    CREATE TRIGGER IF NOT EXISTS messages_fts_insert AFTER INSERT ON messages BEGIN
+      INSERT INTO messages_fts (content, message_id) VALUES (new.content, new.id);
+    END;
+
+    CREATE TRIGGER IF NOT EXISTS messages_fts_delete AFTER DELETE ON messages BEGIN
+      DELETE FROM messages_fts WHERE message_id = old.id;
+    END;
+
+    CREATE TRIGGER IF NOT EXISTS messages_fts_update AFTER UPDATE OF content ON messages BEGIN
+      DELETE FROM messages_fts WHERE message_id = old.id;
+      INSERT INTO messages_fts (content, message_id) VALUES (new.content, new.id);
+    END;
+
+    -- Later parts of a segmented message are indexed under the parent's id.
+    CREATE TRIGGER IF NOT EXISTS message_parts_fts_insert AFTER INSERT ON message_parts BEGIN
+      INSERT INTO messages_fts (content, message_id) VALUES (new.content, new.message_id);
+    END;
+  `);
+
+    if (!exists) {
+        db.exec(`
+      INSERT INTO messages_fts (content, message_id) SELECT content, id FROM messages;
+      INSERT INTO messages_fts (content, message_id) SELECT content, message_id FROM message_parts;
+    `);
+    }
+}
+
+/**
+ * One row per workspace, conversation and message, bumped to a new `seq` by triggers on
+ * every write, so `dbGetChanges` can return what changed after a token. Deletes leave a
+ * tombstone, except for rows removed along with their parent: the parent's tombstone
+ * covers them.
+ */
+function createChangeLog(db: Database.Database): void {
+    db.exec(`
+    CREATE TABLE IF NOT EXISTS change_log (
+      seq INTEGER PRIMARY KEY AUTOINCREMENT,
+      workspace_id TEXT NOT NULL,
+      entity TEXT NOT NULL,
+      entity_id TEXT NOT NULL,
+      conversation_id TEXT,
+      deleted INTEGER NOT NULL DEFAULT 0
+    );
+
+    CREATE UNIQUE INDEX IF NOT EXISTS idx_change_log_entity ON change_log(entity, entity_id);
+    CREATE INDEX IF NOT EXISTS idx_change_log_workspace ON change_log(workspace_id, seq);
+
+    CREATE TRIGGER IF NOT EXISTS workspaces_change_insert AFTER INSERT ON workspaces BEGIN
+      INSERT OR REPLACE INTO change_log (workspace_id, entity, entity_id) VALUES (new.id, 'workspace', new.id);
+    END;
+
+    CREATE TRIGGER IF NOT EXISTS workspaces_change_update AFTER UPDATE ON workspaces BEGIN
+      INSERT OR REPLACE INTO change_log (workspace_id, entity, entity_id) VALUES (new.id, 'workspace', new.id);
+    END;
+
+    CREATE TRIGGER IF NOT EXISTS workspaces_change_delete AFTER DELETE ON workspaces BEGIN
+      DELETE FROM change_log WHERE workspace_id = old.id;
+      INSERT INTO change_log (workspace_id, entity, entity_id, deleted) VALUES (old.id, 'workspace', old.id, 1);
+    END;
+
+    CREATE TRIGGER IF NOT EXISTS conversations_change_insert AFTER INSERT ON conversations BEGIN
+      INSERT OR REPLACE INTO change_log (workspace_id, entity, entity_id, conversation_id)
+      VALUES (new.workspace_id, 'conversation', new.id, new.id);
+    END;
+
+    CREATE TRIGGER IF NOT EXISTS conversations_change_update AFTER UPDATE ON conversations BEGIN
+      INSERT OR REPLACE INTO change_log (workspace_id, entity, entity_id, conversation_id)
+      VALUES (new.workspace_id, 'conversation', new.id, new.id);
+    END;
+
+    CREATE TRIGGER IF NOT EXISTS conversations_change_delete AFTER DELETE ON conversations BEGIN
+      DELETE FROM change_log WHERE conversation_id = old.id;
+      INSERT INTO change_log (workspace_id, entity, entity_id, conversation_id, deleted)
+      SELECT old.workspace_id, 'conversation', old.id, old.id, 1 FROM workspaces WHERE id = old.workspace_id;
+    END;
+
+    CREATE TRIGGER IF NOT EXISTS messages_change_insert AFTER INSERT ON messages BEGIN
+      INSERT OR REPLACE INTO change_log (workspace_id, entity, entity_id, conversation_id)
+      SELECT workspace_id, 'message', new.id, new.conversation_id FROM conversations WHERE id = new.conversation_id;
+    END;
+
+    CREATE TRIGGER IF NOT EXISTS messages_change_update AFTER UPDATE ON messages BEGIN
+      INSERT OR REPLACE INTO change_log (workspace_id, entity, entity_id, conversation_id)
+      SELECT workspace_id, 'message', new.id, new.conversation_id FROM conversations WHERE id = new.conversation_id;
+    END;
+
+    CREATE TRIGGER IF NOT EXISTS messages_change_delete AFTER DELETE ON messages BEGIN
+      DELETE FROM change_log WHERE entity = 'message' AND entity_id = old.id;
+      INSERT INTO change_log (workspace_id, entity, entity_id, conversation_id, deleted)
+      SELECT workspace_id, 'message', old.id, old.conversation_id, 1 FROM conversations WHERE id = old.conversation_id;
+    END;
+  `);
+    if (!db.prepare('SELECT 1 FROM settings WHERE key = ?').get(SYNC_EPOCH_KEY)) renewSyncEpoch(db);
+}
+
+function createWebhookTable(db: Database.Database): void {
+    db.exec(`
+    CREATE TABLE IF NOT EXISTS webhooks (
+      id TEXT PRIMARY KEY,
+      url TEXT NOT NULL,
+      events TEXT NOT NULL,
+      secret TEXT NOT NULL,
+      enabled INTEGER NOT NULL DEFAULT 1,
+      created_at TEXT NOT NULL,
+      last_delivery_at TEXT,
+      last_status INTEGER,
+      last_error TEXT
+    );
+  `);
+}
+
+/** A conversation remembers the preset applied to it, and each turn the preset it ran with. */
+function addTurnOutcomes(db: Database.Database): void {
+    ensureColumn(db, 'turns', 'outcome', 'TEXT');
+    ensureColumn(db, 'turns', 'outcome_comment', 'TEXT');
+    ensureColumn(db, 'turns', 'rated_at', 'TEXT');
+    ensureColumn(db, 'turns', 'preset_id', 'TEXT');
+    ensureColumn(db, 'conversations', 'preset_id', 'TEXT');
+}
+
+function createScheduledTaskTable(db: Database.Database): void {
+    db.exec(`
+    CREATE TABLE IF NOT EXISTS scheduled_tasks (
+      id TEXT PRIMARY KEY,
+      workspace_id TEXT NOT NULL,
+      name TEXT NOT NULL,
+      cron TEXT NOT NULL,
+      prompt TEXT NOT NULL,
+      cli_options TEXT,
+      enabled INTEGER NOT NULL DEFAULT 1,
+      created_at TEXT NOT NULL,
+      last_run_at TEXT,
+      last_status TEXT,
+      last_error TEXT,
+      last_conversation_id TEXT,
+      FOREIGN KEY(workspace_id) REFERENCES workspaces(id) ON DELETE CASCADE
+    );
+  `);
+}
+
+function createConversationSummaryTable(db: Database.Database): void {
+    db.exec(`
+    CREATE TABLE IF NOT EXISTS conversation_summaries (
+      conversation_id TEXT PRIMARY KEY,
+      summary TEXT NOT NULL,
+      covered_messages INTEGER NOT NULL,
+      updated_at TEXT NOT NULL,
+      FOREIGN KEY(conversation_id) REFERENCES conversations(id) ON DELETE CASCADE
+    );
+  `);
+}
+
+function createPromptTemplateTable(db: Database.Database): void {
+    db.exec(`
+    CREATE TABLE IF NOT EXISTS prompt_templates (
+      id TEXT PRIMARY KEY,
+      name TEXT NOT NULL,
+      body TEXT NOT NULL,
+      variables TEXT NOT NULL DEFAULT '[]',
+      created_at TEXT NOT NULL,
+      updated_at TEXT NOT NULL
+    );
   `);
 }
 
+/** Assistant messages written while their turn streams are flagged until it ends. */
+function addMessageDrafts(db: Database.Database): void {
+    ensureColumn(db, 'messages', 'in_progress', 'INTEGER NOT NULL DEFAULT 0');
+}
+
+function createTurnEventTable(db: Database.Database): void {
+    db.exec(`
+    CREATE TABLE IF NOT EXISTS turn_events (
+      turn_id TEXT NOT NULL,
+      conversation_id TEXT NOT NULL,
+      seq INTEGER NOT NULL,
+      type TEXT NOT NULL,
+      item_type TEXT,
+      item_id TEXT,
+      payload TEXT NOT NULL,
+      created_at TEXT NOT NULL,
+      PRIMARY KEY(turn_id, seq),
+      FOREIGN KEY(turn_id) REFERENCES turns(id) ON DELETE CASCADE
+    );
+
+    CREATE INDEX IF NOT EXISTS idx_turn_events_conversation ON turn_events(conversation_id, turn_id);
+  `);
+}
+
+/**
+ * Add a column to an existing table if it is missing.
+ * CREATE TABLE IF NOT EXISTS does not touch tables created by older versions.
+ */
+function ensureColumn(
+    db: Database.Database,
+    table: string,
+    column: string,
+    definition: string,
+): boolean {
+    const columns = db.prepare(`PRAGMA table_info(${table})`).all() as Array<{ name: string }>;
+    if (columns.some((c) => c.name === column)) return false;
+    db.exec(`ALTER TABLE ${table} ADD COLUMN ${column} ${definition}`);
+    return true;
+}
+
+export function parseJsonColumn<T>(raw: unknown): T | undefined {
+    if (typeof raw !== 'string' || !raw) return undefined;
+    try {
+        return JSON.parse(raw) as T;
+    } catch {
+        return undefined;
+    }
+}
+
 export function openDatabase(): Database.Database {
     const dbPath = dbFilePath();
     const db = new Database(dbPath);
@@ -55,40 +701,212 @@ export function openDatabase(): Database.Database {
     return db;
 }
 
-function loadMessages(db: Database.Database, conversationId: string): Message[] {
+// ===== Backup / Restore =====
+
+/**
+ * Write a compacted copy of the database to `destPath` with `VACUUM INTO`. The copy is
+ * built next to the destination and renamed over it, so an existing backup is only
+ * replaced once the new one is complete.
+ */
+export function dbBackup(db: Database.Database, destPath: string): { success: boolean; path?: string; size?: number; error?: string } {
+    const target = path.resolve(expandTildePath(destPath));
+    const temp = `${target}.tmp-${process.pid}`;
+    try {
+        fs.mkdirSync(path.dirname(target), { recursive: true });
+        fs.rmSync(temp, { force: true });
+        db.prepare('VACUUM INTO ?').run(temp);
+        fs.renameSync(temp, target);
+        return { success: true, path: target, size: fs.statSync(target).size };
+    } catch (err: any) {
+        fs.rmSync(temp, { force: true });
+        return { success: false, error: err.message };
+    }
+}
+
+/**
+ * Replace the database with a backup. The backup is checked before anything is touched,
+ * the current database is kept as `state.sqlite3.before-restore`, and the restored file
+ * is migrated to the current schema. The caller must switch to the returned handle; on
+ * success the old one is closed.
+ */
+export function dbRestore(
+    db: Database.Database,
+    srcPath: string,
+): { success: boolean; db: Database.Database; error?: string } {
+    const source = path.resolve(expandTildePath(srcPath));
+    try {
+        const check = new Database(source, { readonly: true, fileMustExist: true });
+        try {
+            if (check.pragma('integrity_check', { simple: true }) !== 'ok') {
+                throw new Error('Backup failed the integrity check');
+            }
+            const found = check
+                .prepare("SELECT COUNT(*) AS n FROM sqlite_master WHERE type = 'table' AND name IN ('workspaces', 'conversations', 'messages')")
+                .get() as any;
+            if (found.n !== 3) throw new Error('Not a Codex WUI database');
+            if ((check.pragma('user_version', { simple: true }) as number) > SCHEMA_VERSION) {
+                throw new Error('Backup is from a newer version of the app');
+            }
+        } finally {
+            check.close();
+        }
+    } catch (err: any) {
+        return { success: false, db, error: err.message };
+    }
+
+    const dbPath = dbFilePath();
+    const previous = `${dbPath}.before-restore`;
+    db.pragma('wal_checkpoint(TRUNCATE)');
+    db.close();
+    let saved = false;
+    try {
+        fs.copyFileSync(dbPath, previous);
+        saved = true;
+        for (const suffix of ['-wal', '-shm']) fs.rmSync(`${dbPath}${suffix}`, { force: true });
+        fs.copyFileSync(source, dbPath);
+        const restored = openDatabase();
+        // Sync tokens handed out for the replaced database must not match the restored one.
+        renewSyncEpoch(restored);
+        return { success: true, db: restored };
+    } catch (err: any) {
+        if (saved) fs.copyFileSync(previous, dbPath);
+        return { success: false, db: openDatabase(), error: err.message };
+    }
+}
+
+// ===== Settings (JSON values keyed by name) =====
+
+export function dbGetSetting<T>(db: Database.Database, key: string, fallback: T): T {
+    try {
+        const row = db.prepare('SELECT value FROM settings WHERE key = ?').get(key) as any;
+        const parsed = parseJsonColumn<T>(row?.value);
+        return parsed === undefined ? fallback : parsed;
+    } catch (error) {
+        console.error(`[db] Failed to read setting ${key}:`, error);
+        return fallback;
+    }
+}
+
+export function dbSetSetting(db: Database.Database, key: string, value: unknown): void {
+    db.prepare(`
+    INSERT INTO settings (key, value) VALUES (?, ?)
+    ON CONFLICT(key) DO UPDATE SET value = excluded.value
+  `).run(key, JSON.stringify(value));
+}
+
+// ===== Message Segmentation =====
+
+/** Messages longer than this are stored as ordered parts (see message_parts). */
+export const MESSAGE_PART_CHARS = 32_000;
+
+/**
+ * Split message content into parts of at most `maxChars`, preferring a line break in the
+ * back half of each part so code blocks are not cut mid-line. Joining the parts gives
+ * back the original content.
+ */
+export function splitMessageContent(content: string, maxChars = MESSAGE_PART_CHARS): string[] {
+    if (content.length <= maxChars) return [content];
+    const parts: string[] = [];
+    let start = 0;
+    while (start < content.length) {
+        let end = Math.min(start + maxChars, content.length);
+        if (end < content.length) {
+            const newline = content.lastIndexOf('\n', end - 1);
+            if (newline >= start + maxChars / 2) {
+                end = newline + 1;
+            } else if (/[\ud800-\udbff]/.test(content[end - 1])) {
+                end -= 1;
+            }
+        }
+        parts.push(content.slice(start, end));
+        start = end;
+    }
+    return parts;
+}
+
+function loadMessageParts(db: Database.Database, messageId: string): string[] {
+    return (db
+        .prepare('SELECT content FROM message_parts WHERE message_id = ? ORDER BY part_index ASC')
+        .all(messageId) as any[]).map((row) => row.content);
+}
+
+const MESSAGE_COLUMNS = 'id, conversation_id, role, content, thinking, thinking_duration, attachments, part_count, in_progress, timestamp';
+
+/**
+ * Segmented messages come back with their first part only and `partCount` set unless
+ * `assemble` is true; the renderer fetches the rest with dbGetMessageContent/dbGetMessagePart.
+ */
+function loadMessages(db: Database.Database, conversationId: string, assemble = true): Message[] {
     const stmt = db.prepare(`
-    SELECT id, conversation_id, role, content, thinking, thinking_duration, timestamp
+    SELECT ${MESSAGE_COLUMNS}
     FROM messages
     WHERE conversation_id = ?
     ORDER BY rowid ASC
   `);
 
-    return stmt.all(conversationId).map((row: any) => ({
+    return stmt.all(conversationId).map((row: any) => messageFromRow(db, row, assemble));
+}
+
+function messageFromRow(db: Database.Database, row: any, assemble: boolean): Message {
+    const segmented = row.part_count > 1;
+    return {
         id: row.id,
         conversationId: row.conversation_id,
         role: row.role,
-        content: row.content,
+        content: segmented && assemble ? row.content + loadMessageParts(db, row.id).join('') : row.content,
         thinking: row.thinking || undefined,
         thinkingDuration: row.thinking_duration || undefined,
+        attachments: parseJsonColumn<MessageAttachment[]>(row.attachments),
+        partCount: segmented && !assemble ? row.part_count : undefined,
+        inProgress: row.in_progress ? true : undefined,
         timestamp: row.timestamp,
-    }));
+    };
+}
+
+// Assistant messages after the conversation's last read message (`c` is the conversation).
+const UNREAD_COUNT_SQL = `
+  (SELECT COUNT(*) FROM messages m
+    WHERE m.conversation_id = c.id AND m.role = 'assistant'
+      AND m.rowid > COALESCE((SELECT r.rowid FROM messages r WHERE r.id = c.last_read_message_id), 0))
+`;
+
+/** With `withMessages` false, `messages` is left empty for dbGetMessagesPage to fill. */
+function loadConversations(db: Database.Database, workspaceId: string, withMessages = true): Conversation[] {
+    return selectConversations(db, 'c.workspace_id = ?', [workspaceId], withMessages);
 }
 
-function loadConversations(db: Database.Database, workspaceId: string): Conversation[] {
+function selectConversations(db: Database.Database, where: string, params: unknown[], withMessages: boolean): Conversation[] {
     const stmt = db.prepare(`
-    SELECT id, workspace_id, title, created_at, updated_at
-    FROM conversations
-    WHERE workspace_id = ?
-    ORDER BY rowid ASC
+    SELECT c.id, c.workspace_id, c.title, c.created_at, c.updated_at, c.persona, c.cli_overrides,
+      c.auto_translate_lang, c.codex_session_id, c.target_package, c.cwd, c.forked_from, c.last_read_message_id,
+      c.archived, c.pinned, c.last_activity_at,
+      (SELECT COUNT(*) FROM messages m WHERE m.conversation_id = c.id) AS message_count,
+      ${UNREAD_COUNT_SQL} AS unread_count
+    FROM conversations c
+    WHERE ${where}
+    ORDER BY c.rowid ASC
   `);
 
-    return stmt.all(workspaceId).map((row: any) => ({
+    return stmt.all(...params).map((row: any) => ({
         id: row.id,
         workspaceId: row.workspace_id,
         title: row.title,
         createdAt: row.created_at,
         updatedAt: row.updated_at,
-        messages: loadMessages(db, row.id),
+        persona: row.persona || undefined,
+        cliOverrides: parseJsonColumn<CliOverrides>(row.cli_overrides),
+        autoTranslateLang: row.auto_translate_lang || undefined,
+        codexSessionId: row.codex_session_id || undefined,
+        targetPackage: parseJsonColumn<WorkspacePackage>(row.target_package),
+        cwd: row.cwd || undefined,
+        forkedFrom: row.forked_from || undefined,
+        archived: !!row.archived,
+        pinned: !!row.pinned,
+        lastActivityAt: row.last_activity_at || row.updated_at,
+        lastReadMessageId: row.last_read_message_id || undefined,
+        unreadCount: row.unread_count,
+        messageCount: row.message_count,
+        messages: withMessages ? loadMessages(db, row.id, false) : [],
     }));
 }
 
@@ -99,16 +917,22 @@ function loadState(db: Database.Database): DbState {
     ORDER BY rowid ASC
   `);
 
+    const etags = workspaceEtags(db);
     const workspaces: Workspace[] = stmt.all().map((row: any) => ({
         id: row.id,
         name: row.name,
         path: expandTildePath(row.path),
-        conversations: loadConversations(db, row.id),
+        etag: etags[row.id] ?? '0',
+        conversations: loadConversations(db, row.id, false),
     }));
 
-    return { workspaces };
+    return { workspaces, syncToken: syncToken(db) };
 }
 
+/**
+ * Workspaces and their conversations, without messages: each conversation comes with
+ * `messageCount` and an empty `messages`, to be loaded with dbGetMessagesPage.
+ */
 export function dbGetState(db: Database.Database): DbState {
     try {
         return loadState(db);
@@ -118,6 +942,108 @@ export function dbGetState(db: Database.Database): DbState {
     }
 }
 
+// ===== Incremental Sync =====
+
+const SYNC_EPOCH_KEY = 'sync.epoch';
+// Beyond this many changed rows a full dbGetState is cheaper than a delta.
+const MAX_SYNC_CHANGES = 5000;
+
+export interface DbChanges {
+    token: string;
+    /** The token is unknown, from another database or too far behind: reload with dbGetState. */
+    reset: boolean;
+    /** Changed workspaces, with `conversations` left empty. */
+    workspaces: Workspace[];
+    /** Changed conversations, and those whose messages changed, without `messages`. */
+    conversations: Conversation[];
+    messages: Message[];
+    deleted: { workspaces: string[]; conversations: string[]; messages: string[] };
+    /** Latest change counter of every workspace. */
+    etags: Record<string, string>;
+}
+
+function renewSyncEpoch(db: Database.Database): void {
+    dbSetSetting(db, SYNC_EPOCH_KEY, generateId('sync'));
+}
+
+function syncSeq(db: Database.Database): number {
+    const row = db.prepare("SELECT seq FROM sqlite_sequence WHERE name = 'change_log'").get() as any;
+    return row?.seq ?? 0;
+}
+
+/** `<epoch>:<seq>`; the epoch changes when the database is replaced by a restore. */
+function syncToken(db: Database.Database): string {
+    return `${dbGetSetting(db, SYNC_EPOCH_KEY, '')}:${syncSeq(db)}`;
+}
+
+function workspaceEtags(db: Database.Database): Record<string, string> {
+    const rows = db.prepare('SELECT workspace_id, MAX(seq) AS seq FROM change_log GROUP BY workspace_id').all() as any[];
+    return Object.fromEntries(rows.map((row) => [row.workspace_id, String(row.seq)]));
+}
+
+/**
+ * Workspaces, conversations and messages written or deleted since `sinceToken` (from
+ * dbGetState or an earlier call), so a renderer that cached the state can catch up
+ * without reloading it. Messages come back unassembled, as dbGetMessagesPage returns them.
+ */
+export function dbGetChanges(db: Database.Database, sinceToken: string): DbChanges {
+    const token = syncToken(db);
+    const changes: DbChanges = {
+        token,
+        reset: false,
+        workspaces: [],
+        conversations: [],
+        messages: [],
+        deleted: { workspaces: [], conversations: [], messages: [] },
+        etags: workspaceEtags(db),
+    };
+
+    const [epoch, seqText] = String(sinceToken ?? '').split(':');
+    const since = Number(seqText);
+    const current = syncSeq(db);
+    if (epoch !== dbGetSetting(db, SYNC_EPOCH_KEY, '') || !Number.isInteger(since) || since < 0 || since > current) {
+        return { ...changes, reset: true };
+    }
+
+    const rows = db
+        .prepare('SELECT entity, entity_id, conversation_id, deleted FROM change_log WHERE seq > ? ORDER BY seq LIMIT ?')
+        .all(since, MAX_SYNC_CHANGES + 1) as any[];
+    if (rows.length > MAX_SYNC_CHANGES) return { ...changes, reset: true };
+
+    const changed = { workspace: [] as string[], conversation: new Set<string>(), message: [] as string[] };
+    for (const row of rows) {
+        if (row.deleted) {
+            const list = row.entity === 'workspace' ? changes.deleted.workspaces
+                : row.entity === 'conversation' ? changes.deleted.conversations
+                    : changes.deleted.messages;
+            list.push(row.entity_id);
+            // A deleted message still changes its conversation's counts.
+            if (row.entity === 'message') changed.conversation.add(row.conversation_id);
+        } else if (row.entity === 'workspace') {
+            changed.workspace.push(row.entity_id);
+        } else {
+            changed.conversation.add(row.conversation_id);
+            if (row.entity === 'message') changed.message.push(row.entity_id);
+        }
+    }
+
+    const ids = (values: Iterable<string>) => JSON.stringify(Array.from(values));
+    changes.workspaces = (db
+        .prepare('SELECT id, name, path FROM workspaces WHERE id IN (SELECT value FROM json_each(?)) ORDER BY rowid ASC')
+        .all(ids(changed.workspace)) as any[]).map((row) => ({
+        id: row.id,
+        name: row.name,
+        path: expandTildePath(row.path),
+        etag: changes.etags[row.id] ?? '0',
+        conversations: [],
+    }));
+    changes.conversations = selectConversations(db, 'c.id IN (SELECT value FROM json_each(?))', [ids(changed.conversation)], false);
+    changes.messages = (db
+        .prepare(`SELECT ${MESSAGE_COLUMNS} FROM messages WHERE id IN (SELECT value FROM json_each(?)) ORDER BY rowid ASC`)
+        .all(ids(changed.message)) as any[]).map((row) => messageFromRow(db, row, false));
+    return changes;
+}
+
 export function dbCreateWorkspace(
     db: Database.Database,
     id: string,
@@ -159,6 +1085,38 @@ export function dbGetConversations(db: Database.Database, workspaceId: string):
     }
 }
 
+/**
+ * Mark a conversation read up to `messageId`, or up to its latest message when omitted.
+ */
+export function dbMarkConversationRead(
+    db: Database.Database,
+    conversationId: string,
+    messageId?: string,
+): { success: boolean; unreadCount: number } {
+    const target = messageId
+        || (db
+            .prepare('SELECT id FROM messages WHERE conversation_id = ? ORDER BY rowid DESC LIMIT 1')
+            .get(conversationId) as any)?.id
+        || null;
+    const result = db
+        .prepare('UPDATE conversations SET last_read_message_id = ? WHERE id = ?')
+        .run(target, conversationId);
+    return { success: result.changes > 0, unreadCount: dbGetUnreadCount(db, conversationId) };
+}
+
+export function dbGetUnreadCount(db: Database.Database, conversationId: string): number {
+    const row = db
+        .prepare(`SELECT ${UNREAD_COUNT_SQL} AS n FROM conversations c WHERE c.id = ?`)
+        .get(conversationId) as any;
+    return row?.n ?? 0;
+}
+
+/** Unread assistant messages across every conversation, for the dock/taskbar badge. */
+export function dbGetUnreadTotal(db: Database.Database): number {
+    const row = db.prepare(`SELECT SUM(${UNREAD_COUNT_SQL}) AS n FROM conversations c`).get() as any;
+    return row?.n ?? 0;
+}
+
 export function dbCreateConversation(
     db: Database.Database,
     id: string,
@@ -167,11 +1125,491 @@ export function dbCreateConversation(
 ): Conversation {
     const now = nowIso();
     db.prepare(`
-    INSERT INTO conversations (id, workspace_id, title, created_at, updated_at)
-    VALUES (?, ?, ?, ?, ?)
-  `).run(id, workspaceId, title, now, now);
+    INSERT INTO conversations (id, workspace_id, title, created_at, updated_at, last_activity_at)
+    VALUES (?, ?, ?, ?, ?, ?)
+  `).run(id, workspaceId, title, now, now, now);
+
+    return { id, workspaceId, title, createdAt: now, updatedAt: now, lastActivityAt: now, messages: [] };
+}
+
+export function dbGetWorkspaceCliOptions(db: Database.Database, workspaceId: string): WorkspaceCliOptions {
+    const row = db.prepare('SELECT cli_options FROM workspace_settings WHERE workspace_id = ?').get(workspaceId) as any;
+    return parseJsonColumn<WorkspaceCliOptions>(row?.cli_options) || {};
+}
+
+/** Replace a workspace's CLI options. Undefined fields are dropped; an empty object clears them. */
+export function dbSetWorkspaceCliOptions(
+    db: Database.Database,
+    workspaceId: string,
+    options: WorkspaceCliOptions,
+): WorkspaceCliOptions {
+    const cleaned = Object.fromEntries(
+        Object.entries(options || {}).filter(([, value]) => value !== undefined && value !== null),
+    ) as WorkspaceCliOptions;
+    if (Object.keys(cleaned).length === 0) {
+        db.prepare('DELETE FROM workspace_settings WHERE workspace_id = ?').run(workspaceId);
+        return {};
+    }
+    db.prepare(`
+    INSERT INTO workspace_settings (workspace_id, cli_options, updated_at) VALUES (?, ?, ?)
+    ON CONFLICT(workspace_id) DO UPDATE SET cli_options = excluded.cli_options, updated_at = excluded.updated_at
+  `).run(workspaceId, JSON.stringify(cleaned), nowIso());
+    return cleaned;
+}
+
+/**
+ * Per-conversation persona, CLI overrides and Codex session id, applied on top of the
+ * global RuntimeConfig (and the workspace's CLI options) when a turn starts.
+ */
+export function dbGetConversationOverrides(
+    db: Database.Database,
+    conversationId: string,
+): {
+    persona?: string;
+    cliOverrides?: CliOverrides;
+    sessionId?: string;
+    targetPackage?: WorkspacePackage;
+    /** Absolute conversation working directory, when one is set. */
+    cwd?: string;
+    promptNotes?: string;
+    workspaceCliOptions?: WorkspaceCliOptions;
+    workspaceId?: string;
+    /** Absolute workspace root. */
+    workspacePath?: string;
+} {
+    try {
+        const row = db
+            .prepare(`
+      SELECT c.workspace_id, c.persona, c.cli_overrides, c.codex_session_id, c.target_package, c.cwd, c.notes, c.notes_in_prompt,
+        ws.cli_options AS workspace_cli_options, w.path AS workspace_path
+      FROM conversations c
+      LEFT JOIN workspace_settings ws ON ws.workspace_id = c.workspace_id
+      LEFT JOIN workspaces w ON w.id = c.workspace_id
+      WHERE c.id = ?
+    `)
+            .get(conversationId) as any;
+        if (!row) return {};
+        return {
+            workspaceCliOptions: parseJsonColumn<WorkspaceCliOptions>(row.workspace_cli_options),
+            persona: row.persona || undefined,
+            cliOverrides: parseJsonColumn<CliOverrides>(row.cli_overrides),
+            sessionId: row.codex_session_id || undefined,
+            targetPackage: parseJsonColumn<WorkspacePackage>(row.target_package),
+            cwd: row.cwd && row.workspace_path ? path.join(expandTildePath(row.workspace_path), row.cwd) : undefined,
+            promptNotes: row.notes_in_prompt && row.notes ? row.notes : undefined,
+            workspaceId: row.workspace_id,
+            workspacePath: row.workspace_path ? expandTildePath(row.workspace_path) : undefined,
+        };
+    } catch (error) {
+        console.error('[db] Failed to load conversation overrides:', error);
+        return {};
+    }
+}
 
-    return { id, workspaceId, title, createdAt: now, updatedAt: now, messages: [] };
+/**
+ * SQL expression turning a stored timestamp into local time. Conversation timestamps are
+ * epoch seconds (see nowIso) while message timestamps are ISO strings, so accept both.
+ */
+function sqlLocalTime(column: string): string {
+    return `(CASE WHEN ${column} NOT GLOB '*[^0-9]*'
+        THEN datetime(CAST(${column} AS INTEGER), 'unixepoch', 'localtime')
+        ELSE datetime(${column}, 'localtime') END)`;
+}
+
+/**
+ * Turn free text into an FTS5 query: every word must match, the last one as a prefix
+ * so results appear while typing. Quoting keeps FTS operators in user input inert.
+ */
+export function toFtsQuery(input: string): string {
+    const terms = input
+        .split(/\s+/)
+        .map((t) => t.replace(/"/g, ''))
+        .filter(Boolean);
+    return terms
+        .map((t, i) => (i === terms.length - 1 ? `"${t}"*` : `"${t}"`))
+        .join(' ');
+}
+
+export function dbSearchMessages(
+    db: Database.Database,
+    query: string,
+    workspaceId?: string,
+    limit = 50,
+): MessageSearchResult[] {
+    const ftsQuery = toFtsQuery(query);
+    if (!ftsQuery) return [];
+    try {
+        const rows = db.prepare(`
+      SELECT m.id, m.conversation_id, m.role, m.timestamp,
+        c.title AS conversation_title, c.workspace_id, w.name AS workspace_name,
+        snippet(messages_fts, 0, '<mark>', '</mark>', '…', 16) AS snippet
+      FROM messages_fts
+      JOIN messages m ON m.id = messages_fts.message_id
+      JOIN conversations c ON c.id = m.conversation_id
+      JOIN workspaces w ON w.id = c.workspace_id
+      WHERE messages_fts MATCH ? ${workspaceId ? 'AND c.workspace_id = ?' : ''}
+      ORDER BY rank
+      LIMIT ?
+    `).all(...(workspaceId ? [ftsQuery, workspaceId, limit] : [ftsQuery, limit]));
+
+        return rows.map((row: any) => ({
+            messageId: row.id,
+            conversationId: row.conversation_id,
+            conversationTitle: row.conversation_title,
+            workspaceId: row.workspace_id,
+            workspaceName: row.workspace_name,
+            role: row.role,
+            timestamp: row.timestamp,
+            snippet: row.snippet,
+        }));
+    } catch (error) {
+        console.error('[db] Failed to search messages:', error);
+        return [];
+    }
+}
+
+export type ConversationBucket = 'today' | 'yesterday' | 'thisWeek' | 'thisMonth' | 'older';
+
+export interface ConversationGroups {
+    groups: Array<{ bucket: ConversationBucket; conversationIds: string[] }>;
+    dailyTurns: Array<{ day: string; turns: number }>;
+}
+
+export type ConversationSort = 'activity' | 'created' | 'title';
+
+export interface ConversationListOptions {
+    /** Archived conversations are listed only when true (default false). */
+    archived?: boolean;
+    /** Only pinned (true) or unpinned (false) conversations; both when unset. */
+    pinned?: boolean;
+    sort?: ConversationSort;
+    offset?: number;
+    /** Page size, 1-200 (default 50). */
+    limit?: number;
+}
+
+export interface ConversationSummary {
+    id: string;
+    workspaceId: string;
+    title: string;
+    createdAt: string;
+    updatedAt: string;
+    lastActivityAt: string;
+    archived: boolean;
+    pinned: boolean;
+    forkedFrom?: string;
+    messageCount: number;
+    unreadCount: number;
+}
+
+const CONVERSATION_ORDER: Record<ConversationSort, string> = {
+    activity: `${sqlLocalTime('COALESCE(c.last_activity_at, c.updated_at)')} DESC`,
+    created: `${sqlLocalTime('c.created_at')} DESC`,
+    title: 'c.title COLLATE NOCASE ASC',
+};
+
+/**
+ * One page of a workspace's conversations without their messages, pinned ones first, for
+ * a sidebar with many conversations. `total` counts every match of the filters.
+ */
+export function dbListConversations(
+    db: Database.Database,
+    workspaceId: string,
+    options: ConversationListOptions = {},
+): { conversations: ConversationSummary[]; total: number } {
+    const where = ['c.workspace_id = ?', 'c.archived = ?'];
+    const params: unknown[] = [workspaceId, options.archived ? 1 : 0];
+    if (options.pinned !== undefined) {
+        where.push('c.pinned = ?');
+        params.push(options.pinned ? 1 : 0);
+    }
+    const order = CONVERSATION_ORDER[options.sort || 'activity'] || CONVERSATION_ORDER.activity;
+    const limit = Math.min(200, Math.max(1, Math.floor(options.limit ?? 50)));
+    const offset = Math.max(0, Math.floor(options.offset ?? 0));
+
+    const total = (db.prepare(`SELECT COUNT(*) AS n FROM conversations c WHERE ${where.join(' AND ')}`).get(...params) as any).n;
+    const rows = db.prepare(`
+    SELECT c.id, c.workspace_id, c.title, c.created_at, c.updated_at, c.last_activity_at, c.archived, c.pinned,
+      c.forked_from,
+      (SELECT COUNT(*) FROM messages m WHERE m.conversation_id = c.id) AS message_count,
+      ${UNREAD_COUNT_SQL} AS unread_count
+    FROM conversations c
+    WHERE ${where.join(' AND ')}
+    ORDER BY c.pinned DESC, ${order}, c.rowid DESC
+    LIMIT ? OFFSET ?
+  `).all(...params, limit, offset) as any[];
+
+    return {
+        total,
+        conversations: rows.map((row) => ({
+            id: row.id,
+            workspaceId: row.workspace_id,
+            title: row.title,
+            createdAt: row.created_at,
+            updatedAt: row.updated_at,
+            lastActivityAt: row.last_activity_at || row.updated_at,
+            archived: !!row.archived,
+            pinned: !!row.pinned,
+            forkedFrom: row.forked_from || undefined,
+            messageCount: row.message_count,
+            unreadCount: row.unread_count,
+        })),
+    };
+}
+
+/** Archiving also unpins, so archived conversations never float to the top. */
+export function dbSetConversationArchived(db: Database.Database, id: string, archived: boolean): { success: boolean } {
+    const result = archived
+        ? db.prepare('UPDATE conversations SET archived = 1, pinned = 0 WHERE id = ?').run(id)
+        : db.prepare('UPDATE conversations SET archived = 0 WHERE id = ?').run(id);
+    return { success: result.changes > 0 };
+}
+
+export function dbSetConversationPinned(db: Database.Database, id: string, pinned: boolean): { success: boolean } {
+    const result = db.prepare('UPDATE conversations SET pinned = ? WHERE id = ?').run(pinned ? 1 : 0, id);
+    return { success: result.changes > 0 };
+}
+
+/**
+ * Group a workspace's conversations by last activity and count user turns per day,
+ * so the sidebar can render date sections without loading every message.
+ */
+export function dbGetConversationGroups(
+    db: Database.Database,
+    workspaceId: string,
+): ConversationGroups {
+    const order: ConversationBucket[] = ['today', 'yesterday', 'thisWeek', 'thisMonth', 'older'];
+    try {
+        const updated = sqlLocalTime('updated_at');
+        const rows = db.prepare(`
+      SELECT id,
+        CASE
+          WHEN date(${updated}) = date('now', 'localtime') THEN 'today'
+          WHEN date(${updated}) = date('now', 'localtime', '-1 day') THEN 'yesterday'
+          WHEN date(${updated}) >= date('now', 'localtime', '-6 days', 'weekday 1') THEN 'thisWeek'
+          WHEN strftime('%Y-%m', ${updated}) = strftime('%Y-%m', 'now', 'localtime') THEN 'thisMonth'
+          ELSE 'older'
+        END AS bucket
+      FROM conversations
+      WHERE workspace_id = ?
+      ORDER BY ${updated} DESC
+    `).all(workspaceId) as Array<{ id: string; bucket: ConversationBucket }>;
+
+        const byBucket = new Map<ConversationBucket, string[]>();
+        for (const row of rows) {
+            const ids = byBucket.get(row.bucket) || [];
+            ids.push(row.id);
+            byBucket.set(row.bucket, ids);
+        }
+
+        const dailyTurns = db.prepare(`
+      SELECT date(${sqlLocalTime('m.timestamp')}) AS day, COUNT(*) AS turns
+      FROM messages m JOIN conversations c ON c.id = m.conversation_id
+      WHERE c.workspace_id = ? AND m.role = 'user'
+      GROUP BY day
+      ORDER BY day DESC
+    `).all(workspaceId) as Array<{ day: string; turns: number }>;
+
+        return {
+            groups: order
+                .filter((bucket) => byBucket.has(bucket))
+                .map((bucket) => ({ bucket, conversationIds: byBucket.get(bucket)! })),
+            dailyTurns: dailyTurns.filter((d) => d.day !== null),
+        };
+    } catch (error) {
+        console.error('[db] Failed to group conversations:', error);
+        return { groups: [], dailyTurns: [] };
+    }
+}
+
+/**
+ * Remember the Codex CLI session (thread) id so follow-up turns can `codex exec resume` it.
+ */
+export function dbSetConversationSessionId(
+    db: Database.Database,
+    id: string,
+    sessionId: string | null,
+): void {
+    try {
+        db.prepare('UPDATE conversations SET codex_session_id = ? WHERE id = ?').run(sessionId, id);
+    } catch (error) {
+        console.error('[db] Failed to store codex session id:', error);
+    }
+}
+
+export function dbGetConversationNotes(
+    db: Database.Database,
+    id: string,
+): { notes: string; includeInPrompt: boolean } {
+    const row = db.prepare('SELECT notes, notes_in_prompt FROM conversations WHERE id = ?').get(id) as any;
+    return { notes: row?.notes || '', includeInPrompt: !!row?.notes_in_prompt };
+}
+
+export function dbSetConversationNotes(
+    db: Database.Database,
+    id: string,
+    notes: string,
+    includeInPrompt?: boolean,
+): { success: boolean } {
+    if (includeInPrompt === undefined) {
+        db.prepare('UPDATE conversations SET notes = ? WHERE id = ?').run(notes, id);
+    } else {
+        db.prepare('UPDATE conversations SET notes = ?, notes_in_prompt = ? WHERE id = ?').run(
+            notes,
+            includeInPrompt ? 1 : 0,
+            id,
+        );
+    }
+    return { success: true };
+}
+
+export function dbSetConversationTargetPackage(
+    db: Database.Database,
+    id: string,
+    targetPackage: WorkspacePackage | null,
+): { success: boolean } {
+    db.prepare('UPDATE conversations SET target_package = ?, updated_at = ? WHERE id = ?').run(
+        targetPackage ? JSON.stringify(targetPackage) : null,
+        nowIso(),
+        id,
+    );
+    return { success: true };
+}
+
+/** `cwd` is relative to the workspace root; null runs the conversation at the root again. */
+export function dbSetConversationCwd(db: Database.Database, id: string, cwd: string | null): { success: boolean } {
+    db.prepare('UPDATE conversations SET cwd = ?, updated_at = ? WHERE id = ?').run(cwd || null, nowIso(), id);
+    return { success: true };
+}
+
+export function dbUpdateConversationCliOverrides(
+    db: Database.Database,
+    id: string,
+    overrides: CliOverrides | undefined,
+): { success: boolean } {
+    db.prepare('UPDATE conversations SET cli_overrides = ?, updated_at = ? WHERE id = ?').run(
+        overrides ? JSON.stringify(overrides) : null,
+        nowIso(),
+        id,
+    );
+    return { success: true };
+}
+
+/**
+ * Re-home a conversation under another workspace. Returns both workspace roots so the
+ * caller can rebase any paths that were relative to the old one.
+ */
+export function dbMoveConversation(
+    db: Database.Database,
+    id: string,
+    targetWorkspaceId: string,
+): { success: boolean; fromPath?: string; toPath?: string; error?: string } {
+    const current = db
+        .prepare(`
+    SELECT w.path AS path
+    FROM conversations c JOIN workspaces w ON w.id = c.workspace_id
+    WHERE c.id = ?
+  `)
+        .get(id) as any;
+    if (!current) return { success: false, error: 'Conversation not found' };
+
+    const target = db.prepare('SELECT path FROM workspaces WHERE id = ?').get(targetWorkspaceId) as any;
+    if (!target) return { success: false, error: 'Target workspace not found' };
+
+    // A target package belongs to the old workspace's layout, and the codex session was
+    // started in the old directory, so the next turn starts a fresh one from history.
+    db.prepare(`
+    UPDATE conversations SET workspace_id = ?, target_package = NULL, codex_session_id = NULL, updated_at = ?
+    WHERE id = ?
+  `).run(
+        targetWorkspaceId,
+        nowIso(),
+        id,
+    );
+    return {
+        success: true,
+        fromPath: expandTildePath(current.path),
+        toPath: expandTildePath(target.path),
+    };
+}
+
+/**
+ * Branch a conversation: a new conversation in the same workspace with copies of the
+ * messages up to and including `fromMessageId` and the same per-conversation settings.
+ * The Codex session is carried over only when forking at the last message, since the
+ * CLI session also holds the turns after the fork point; otherwise the fork continues
+ * from its pasted history.
+ */
+export function dbForkConversation(
+    db: Database.Database,
+    conversationId: string,
+    fromMessageId: string,
+    newTitle?: string,
+): { success: boolean; conversation?: Conversation; error?: string } {
+    const source = db.prepare('SELECT * FROM conversations WHERE id = ?').get(conversationId) as any;
+    if (!source) return { success: false, error: 'Conversation not found' };
+    const messages = db
+        .prepare('SELECT rowid, * FROM messages WHERE conversation_id = ? ORDER BY rowid ASC')
+        .all(conversationId) as any[];
+    const forkIndex = messages.findIndex((m) => m.id === fromMessageId);
+    if (forkIndex < 0) return { success: false, error: 'Message not found in conversation' };
+
+    const id = generateId('conv');
+    const now = nowIso();
+    const atLastMessage = forkIndex === messages.length - 1;
+    const insertMessage = db.prepare(`
+    INSERT INTO messages (id, conversation_id, role, content, thinking, thinking_duration, attachments, part_count, timestamp)
+    VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+  `);
+    const copyParts = db.prepare(`
+    INSERT INTO message_parts (message_id, part_index, content)
+    SELECT ?, part_index, content FROM message_parts WHERE message_id = ?
+  `);
+
+    db.transaction(() => {
+        db.prepare(`
+      INSERT INTO conversations (id, workspace_id, title, created_at, updated_at, last_activity_at, persona, cli_overrides,
+        auto_translate_lang, codex_session_id, target_package, cwd, notes, notes_in_prompt, forked_from)
+      VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+    `).run(
+            id,
+            source.workspace_id,
+            newTitle?.trim() || `${source.title} (fork)`,
+            now,
+            now,
+            now,
+            source.persona,
+            source.cli_overrides,
+            source.auto_translate_lang,
+            atLastMessage ? source.codex_session_id : null,
+            source.target_package,
+            source.cwd,
+            source.notes,
+            source.notes_in_prompt,
+            conversationId,
+        );
+        let lastId: string | null = null;
+        for (const message of messages.slice(0, forkIndex + 1)) {
+            lastId = generateId('msg');
+            insertMessage.run(
+                lastId,
+                id,
+                message.role,
+                message.content,
+                message.thinking,
+                message.thinking_duration,
+                message.attachments,
+                message.part_count,
+                message.timestamp,
+            );
+            copyParts.run(lastId, message.id);
+        }
+        // The copied history has already been read in the original.
+        db.prepare('UPDATE conversations SET last_read_message_id = ? WHERE id = ?').run(lastId, id);
+    })();
+
+    const conversation = loadConversations(db, source.workspace_id).find((c) => c.id === id);
+    return { success: true, conversation };
 }
 
 export function dbUpdateConversationTitle(
@@ -193,38 +1631,232 @@ export function dbDeleteConversation(db: Database.Database, id: string): { succe
     return { success: true };
 }
 
-export function dbGetMessages(db: Database.Database, conversationId: string): Message[] {
+export function dbGetMessages(db: Database.Database, conversationId: string, assemble = true): Message[] {
     try {
-        return loadMessages(db, conversationId);
+        return loadMessages(db, conversationId, assemble);
     } catch (error) {
         console.error('[db] Failed to load messages:', error);
         return [];
     }
 }
 
+const MAX_MESSAGE_PAGE = 200;
+
+/**
+ * Up to `limit` (1-200, default 50) messages of a conversation that come before
+ * `beforeId`, or its latest ones without it, oldest first. Segmented messages come back
+ * with their first part only, as in dbGetState. An unknown `beforeId` yields no messages.
+ */
+export function dbGetMessagesPage(
+    db: Database.Database,
+    conversationId: string,
+    beforeId?: string | null,
+    limit = 50,
+): { messages: Message[]; hasMore: boolean } {
+    const pageSize = Math.min(MAX_MESSAGE_PAGE, Math.max(1, Math.floor(Number(limit) || 50)));
+    let beforeRowid = Number.MAX_SAFE_INTEGER;
+    if (beforeId) {
+        const anchor = db
+            .prepare('SELECT rowid FROM messages WHERE id = ? AND conversation_id = ?')
+            .get(beforeId, conversationId) as any;
+        if (!anchor) return { messages: [], hasMore: false };
+        beforeRowid = anchor.rowid;
+    }
+    const rows = db.prepare(`
+    SELECT ${MESSAGE_COLUMNS}
+    FROM messages
+    WHERE conversation_id = ? AND rowid < ?
+    ORDER BY rowid DESC
+    LIMIT ?
+  `).all(conversationId, beforeRowid, pageSize + 1) as any[];
+    return {
+        messages: rows.slice(0, pageSize).reverse().map((row) => messageFromRow(db, row, false)),
+        hasMore: rows.length > pageSize,
+    };
+}
+
+/**
+ * Save a message. A message with the same id in the conversation, such as the draft of
+ * the turn's streamed output, is replaced and no longer in progress.
+ */
 export function dbCreateMessage(db: Database.Database, message: Message): Message {
     const insertMsg = db.prepare(`
-    INSERT INTO messages (id, conversation_id, role, content, thinking, thinking_duration, timestamp)
-    VALUES (?, ?, ?, ?, ?, ?, ?)
+    INSERT INTO messages (id, conversation_id, role, content, thinking, thinking_duration, attachments, part_count, timestamp)
+    VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+    ON CONFLICT(id) DO UPDATE SET role = excluded.role, content = excluded.content, thinking = excluded.thinking,
+      thinking_duration = excluded.thinking_duration, attachments = excluded.attachments, part_count = excluded.part_count,
+      timestamp = excluded.timestamp, in_progress = 0
+    WHERE messages.conversation_id = excluded.conversation_id
   `);
+    const deleteParts = db.prepare('DELETE FROM message_parts WHERE message_id = ?');
+    const insertPart = db.prepare('INSERT INTO message_parts (message_id, part_index, content) VALUES (?, ?, ?)');
+    const parts = splitMessageContent(message.content);
 
     const updateConv = db.prepare(
-        'UPDATE conversations SET updated_at = ? WHERE id = ?',
+        'UPDATE conversations SET updated_at = ?, last_activity_at = ? WHERE id = ?',
     );
 
     const transaction = db.transaction(() => {
+        deleteParts.run(message.id);
         insertMsg.run(
             message.id,
             message.conversationId,
             message.role,
-            message.content,
+            parts[0],
             message.thinking || null,
             message.thinkingDuration || null,
+            message.attachments?.length ? JSON.stringify(message.attachments) : null,
+            parts.length,
             message.timestamp,
         );
-        updateConv.run(nowIso(), message.conversationId);
+        parts.slice(1).forEach((part, i) => insertPart.run(message.id, i + 1, part));
+        const now = nowIso();
+        updateConv.run(now, now, message.conversationId);
     });
 
     transaction();
     return message;
 }
+
+/**
+ * Write what a turn has streamed so far as an assistant message flagged in progress, so
+ * it survives a crash. Does nothing once the message was finalized or saved.
+ */
+export function dbSaveMessageDraft(
+    db: Database.Database,
+    draft: { id: string; conversationId: string; content: string; thinking?: string; timestamp: string },
+): void {
+    const parts = splitMessageContent(draft.content);
+    db.transaction(() => {
+        const existing = db.prepare('SELECT in_progress FROM messages WHERE id = ?').get(draft.id) as any;
+        if (existing && !existing.in_progress) return;
+        db.prepare('DELETE FROM message_parts WHERE message_id = ?').run(draft.id);
+        db.prepare(`
+      INSERT INTO messages (id, conversation_id, role, content, thinking, part_count, in_progress, timestamp)
+      VALUES (?, ?, 'assistant', ?, ?, ?, 1, ?)
+      ON CONFLICT(id) DO UPDATE SET content = excluded.content, thinking = excluded.thinking, part_count = excluded.part_count
+    `).run(draft.id, draft.conversationId, parts[0], draft.thinking || null, parts.length, draft.timestamp);
+        const insertPart = db.prepare('INSERT INTO message_parts (message_id, part_index, content) VALUES (?, ?, ?)');
+        parts.slice(1).forEach((part, i) => insertPart.run(draft.id, i + 1, part));
+    })();
+}
+
+/** End a draft: it is no longer in progress, or removed when nothing was streamed. */
+export function dbFinalizeMessageDraft(db: Database.Database, messageId: string): void {
+    db.prepare(`
+    DELETE FROM messages WHERE id = ? AND in_progress = 1 AND content = '' AND COALESCE(thinking, '') = ''
+  `).run(messageId);
+    db.prepare('UPDATE messages SET in_progress = 0 WHERE id = ? AND in_progress = 1').run(messageId);
+}
+
+/**
+ * End every draft still marked in progress, left behind when the app died mid-turn.
+ * Only call this while no turn runs, i.e. at startup. Returns how many were kept.
+ */
+export function dbFinalizeOrphanedDrafts(db: Database.Database): number {
+    return db.transaction(() => {
+        db.prepare(`
+      DELETE FROM messages WHERE in_progress = 1 AND content = '' AND COALESCE(thinking, '') = ''
+    `).run();
+        return db.prepare('UPDATE messages SET in_progress = 0 WHERE in_progress = 1').run().changes;
+    })();
+}
+
+/** Replace a message's content, re-segmenting it. */
+export function dbUpdateMessageContent(
+    db: Database.Database,
+    messageId: string,
+    content: string,
+): { success: boolean; partCount?: number; error?: string } {
+    const parts = splitMessageContent(content);
+    const insertPart = db.prepare('INSERT INTO message_parts (message_id, part_index, content) VALUES (?, ?, ?)');
+    const transaction = db.transaction(() => {
+        // Parts go first: the content update resets the message's search rows to the head.
+        db.prepare('DELETE FROM message_parts WHERE message_id = ?').run(messageId);
+        const result = db
+            .prepare('UPDATE messages SET content = ?, part_count = ? WHERE id = ?')
+            .run(parts[0], parts.length, messageId);
+        if (result.changes === 0) throw new Error('Message not found');
+        parts.slice(1).forEach((part, i) => insertPart.run(messageId, i + 1, part));
+    });
+    try {
+        transaction();
+        return { success: true, partCount: parts.length };
+    } catch (error: any) {
+        return { success: false, error: error.message };
+    }
+}
+
+/** Reassemble the full content of a (possibly segmented) message. */
+export function dbGetMessageContent(db: Database.Database, messageId: string): string | null {
+    const row = db.prepare('SELECT content, part_count FROM messages WHERE id = ?').get(messageId) as any;
+    if (!row) return null;
+    return row.part_count > 1 ? row.content + loadMessageParts(db, messageId).join('') : row.content;
+}
+
+/** One part of a message, for paging through very long ones. Part 0 is the head. */
+export function dbGetMessagePart(
+    db: Database.Database,
+    messageId: string,
+    index: number,
+): { index: number; partCount: number; content: string } | null {
+    const row = db.prepare('SELECT content, part_count FROM messages WHERE id = ?').get(messageId) as any;
+    if (!row || index < 0 || index >= row.part_count) return null;
+    if (index === 0) return { index, partCount: row.part_count, content: row.content };
+    const part = db
+        .prepare('SELECT content FROM message_parts WHERE message_id = ? AND part_index = ?')
+        .get(messageId, index) as any;
+    return part ? { index, partCount: row.part_count, content: part.content } : null;
+}
+
+// ===== Turn Events =====
+
+/** Events kept per turn; a runaway turn stops being recorded past this. */
+const MAX_TURN_EVENTS = 5000;
+/** Larger events are stored as their type and item only. */
+const MAX_TURN_EVENT_CHARS = 256 * 1024;
+/** Streaming updates are left out: the started and completed events carry the same items. */
+const SKIPPED_TURN_EVENTS = /^item\.updated$|delta/;
+
+/**
+ * Store a codex JSON event of a turn: item starts and completions (commands, file changes,
+ * MCP and web search calls, reasoning, agent messages), turn and error events.
+ */
+export function dbRecordTurnEvent(db: Database.Database, turnId: string, conversationId: string, event: any): void {
+    const type = String(event?.type || event?.msg?.type || '');
+    if (!type || SKIPPED_TURN_EVENTS.test(type)) return;
+    const itemType = event.item?.type ? String(event.item.type).toLowerCase() : null;
+    const itemId = event.item?.id != null ? String(event.item.id) : null;
+    let payload = JSON.stringify(event);
+    if (payload.length > MAX_TURN_EVENT_CHARS) {
+        payload = JSON.stringify({ type, item: itemType ? { id: itemId, type: itemType } : undefined, truncated: true });
+    }
+    try {
+        // The next seq of the turn, unless it already holds MAX_TURN_EVENTS events.
+        db.prepare(`
+      INSERT INTO turn_events (turn_id, conversation_id, seq, type, item_type, item_id, payload, created_at)
+      SELECT ?, ?, COALESCE(MAX(seq), 0) + 1, ?, ?, ?, ?, ?
+      FROM turn_events WHERE turn_id = ?
+      HAVING COUNT(*) < ?
+    `).run(turnId, conversationId, type, itemType, itemId, payload, nowIso(), turnId, MAX_TURN_EVENTS);
+    } catch (error) {
+        console.error('[db] Failed to record turn event:', error);
+    }
+}
+
+/** The stored events of a turn of the conversation, in the order they were streamed. */
+export function dbGetTurnEvents(db: Database.Database, conversationId: string, turnId: string): TurnEvent[] {
+    const rows = db.prepare(`
+    SELECT seq, type, item_type, item_id, payload, created_at FROM turn_events
+    WHERE conversation_id = ? AND turn_id = ?
+    ORDER BY seq ASC
+  `).all(conversationId, turnId) as any[];
+    return rows.map((row) => ({
+        seq: row.seq,
+        type: row.type,
+        itemType: row.item_type,
+        itemId: row.item_id,
+        event: parseJsonColumn(row.payload) ?? null,
+        createdAt: row.created_at,
+    }));
+}
@@ -0,0 +1,185 @@
+import { spawn } from 'child_process';
+import * as fs from 'fs';
+import * as path from 'path';
+import { commandSpawnOptions, expandTildePath, trackChild } from './utils';
+
+export type DependencyEcosystem = 'cargo' | 'npm' | 'pip';
+
+export interface OutdatedDependency {
+    ecosystem: DependencyEcosystem;
+    name: string;
+    current: string;
+    /** Newest version allowed by the manifest's version requirement, when the tool reports it. */
+    wanted: string | null;
+    latest: string;
+    kind: string | null;
+}
+
+export interface OutdatedCheckResult {
+    success: boolean;
+    ecosystems: DependencyEcosystem[];
+    dependencies: OutdatedDependency[];
+    errors: Array<{ ecosystem: DependencyEcosystem; error: string }>;
+}
+
+const TOOL_TIMEOUT_MS = 120_000;
+
+const TOOLS: Record<DependencyEcosystem, { manifests: string[]; bin: string; args: string[]; missing: string }> = {
+    cargo: {
+        manifests: ['Cargo.toml'],
+        bin: 'cargo',
+        args: ['outdated', '--root-deps-only', '--format', 'json'],
+        missing: 'cargo-outdated is not installed. Run `cargo install cargo-outdated`.',
+    },
+    npm: {
+        manifests: ['package.json'],
+        bin: 'npm',
+        args: ['outdated', '--json'],
+        missing: 'npm executable was not found.',
+    },
+    pip: {
+        manifests: ['requirements.txt', 'pyproject.toml', 'setup.py'],
+        bin: 'pip',
+        args: ['list', '--outdated', '--format=json'],
+        missing: 'pip executable was not found.',
+    },
+};
+
+function runTool(
+    bin: string,
+    args: string[],
+    cwd: string,
+): Promise<{ code: number | null; stdout: string; stderr: string; error?: string }> {
+    return new Promise((resolve) => {
+        const child = trackChild(spawn(bin, args, { ...commandSpawnOptions(cwd), stdio: ['ignore', 'pipe', 'pipe'] }));
+        let stdout = '';
+        let stderr = '';
+        const timer = setTimeout(() => child.kill(), TOOL_TIMEOUT_MS);
+        child.stdout?.on('data', (d: Buffer) => (stdout += d.toString()));
+        child.stderr?.on('data', (d: Buffer) => (stderr += d.toString()));
+        child.on('error', (err: any) => {
+            clearTimeout(timer);
+            resolve({ code: null, stdout, stderr, error: err.code === 'ENOENT' ? 'ENOENT' : err.message });
+        });
+        child.on('close', (code) => {
+            clearTimeout(timer);
+            resolve({ code, stdout, stderr });
+        });
+    });
+}
+
+// `npm outdated --json`: { name: { current, wanted, latest, type } }
+export function parseNpmOutdated(output: string): OutdatedDependency[] {
+    const data = JSON.parse(output || '{}');
+    return Object.entries<any>(data).map(([name, info]) => {
+        // Workspaces report one entry per dependent package as an array.
+        const entry = Array.isArray(info) ? info[0] : info;
+        return {
+            ecosystem: 'npm' as const,
+            name,
+            current: entry.current || 'missing',
+            wanted: entry.wanted || null,
+            latest: entry.latest,
+            kind: entry.type || null,
+        };
+    });
+}
+
+// `cargo outdated --format json`: { dependencies: [{ name, project, compat, latest, kind }] }
+export function parseCargoOutdated(output: string): OutdatedDependency[] {
+    const deps: OutdatedDependency[] = [];
+    // One JSON document per workspace member.
+    for (const line of output.split('\n')) {
+        if (!line.trim().startsWith('{')) continue;
+        for (const dep of JSON.parse(line).dependencies || []) {
+            if (deps.some((d) => d.name === dep.name)) continue;
+            deps.push({
+                ecosystem: 'cargo',
+                name: dep.name,
+                current: dep.project,
+                wanted: dep.compat && dep.compat !== '---' ? dep.compat : null,
+                latest: dep.latest,
+                kind: dep.kind || null,
+            });
+        }
+    }
+    return deps;
+}
+
+// `pip list --outdated --format=json`: [{ name, version, latest_version }]
+export function parsePipOutdated(output: string): OutdatedDependency[] {
+    return (JSON.parse(output || '[]') as any[]).map((dep) => ({
+        ecosystem: 'pip' as const,
+        name: dep.name,
+        current: dep.version,
+        wanted: null,
+        latest: dep.latest_version,
+        kind: null,
+    }));
+}
+
+const PARSERS: Record<DependencyEcosystem, (output: string) => OutdatedDependency[]> = {
+    cargo: parseCargoOutdated,
+    npm: parseNpmOutdated,
+    pip: parsePipOutdated,
+};
+
+export function detectEcosystems(workspacePath: string): DependencyEcosystem[] {
+    const root = expandTildePath(workspacePath);
+    return (Object.keys(TOOLS) as DependencyEcosystem[]).filter((eco) =>
+        TOOLS[eco].manifests.some((m) => fs.existsSync(path.join(root, m))),
+    );
+}
+
+/**
+ * Run each detected package manager's outdated check. `npm outdated` exits 1 when
+ * anything is outdated, so output is parsed regardless of the exit code.
+ */
+export async function checkOutdatedDependencies(workspacePath: string): Promise<OutdatedCheckResult> {
+    const root = expandTildePath(workspacePath);
+    const ecosystems = detectEcosystems(root);
+    const dependencies: OutdatedDependency[] = [];
+    const errors: OutdatedCheckResult['errors'] = [];
+
+    await Promise.all(
+        ecosystems.map(async (eco) => {
+            const tool = TOOLS[eco];
+            const result = await runTool(tool.bin, tool.args, root);
+            if (result.error) {
+                errors.push({ ecosystem: eco, error: result.error === 'ENOENT' ? tool.missing : result.error });
+                return;
+            }
+            const fail = () => {
+                const detail = result.stderr.trim() || `${tool.bin} exited with ${result.code}`;
+                errors.push({
+                    ecosystem: eco,
+                    error: eco === 'cargo' && /no such (sub)?command/i.test(detail) ? tool.missing : detail,
+                });
+            };
+            if (result.code !== 0 && !result.stdout.trim()) return fail();
+            try {
+                dependencies.push(...PARSERS[eco](result.stdout));
+            } catch {
+                fail();
+            }
+        }),
+    );
+
+    dependencies.sort((a, b) => a.ecosystem.localeCompare(b.ecosystem) || a.name.localeCompare(b.name));
+    return { success: errors.length < ecosystems.length || ecosystems.length === 0, ecosystems, dependencies, errors };
+}
+
+/**
+ * Pre-filled prompt asking codex to perform the selected upgrades.
+ */
+export function buildUpgradePrompt(dependencies: OutdatedDependency[]): string {
+    const lines = dependencies.map((d) => `- [${d.ecosystem}] ${d.name}: ${d.current} -> ${d.latest}`);
+    return [
+        'Upgrade the following dependencies:',
+        ...lines,
+        '',
+        'Update the manifests and lockfiles with the package manager, fix any breaking API changes ' +
+            'in the code, and run the build and tests afterwards. Summarize notable changelog items ' +
+            'and anything that needs manual follow-up.',
+    ].join('\n');
+}
@@ -0,0 +1,348 @@
+import Database from 'better-sqlite3';
+import * as fs from 'fs';
+import * as path from 'path';
+import { readSnapshotOriginal } from './snapshots';
+import { nowIso } from './utils';
+
+export interface DiffHunk {
+    oldStart: number;
+    oldLines: number;
+    newStart: number;
+    newLines: number;
+    /** Lines prefixed with ' ', '+' or '-' as in a unified diff. */
+    lines: string[];
+}
+
+export interface FileDiff {
+    path: string;
+    kind: 'add' | 'delete' | 'update';
+    movePath: string | null;
+    hunks: DiffHunk[];
+    added: number;
+    removed: number;
+    /** Hunks were cut at MAX_DIFF_LINES, or the file was too large or binary to diff. */
+    truncated: boolean;
+}
+
+export interface TurnDiffs {
+    turnId: string;
+    createdAt: string;
+    added: number;
+    removed: number;
+    files: FileDiff[];
+}
+
+const CONTEXT_LINES = 3;
+const MAX_DIFF_LINES = 5000;
+const MAX_FILE_BYTES = 1024 * 1024;
+// Above this many LCS cells the middle of the file is shown as replaced wholesale.
+const MAX_LCS_CELLS = 4_000_000;
+
+type Op = [' ' | '-' | '+', string];
+
+function splitLines(text: string): string[] {
+    if (!text) return [];
+    const lines = text.split('\n');
+    if (lines[lines.length - 1] === '') lines.pop();
+    return lines;
+}
+
+function middleOps(a: string[], b: string[]): Op[] {
+    if (a.length * b.length > MAX_LCS_CELLS) {
+        return [...a.map((l): Op => ['-', l]), ...b.map((l): Op => ['+', l])];
+    }
+    const n = a.length;
+    const m = b.length;
+    const table = Array.from({ length: n + 1 }, () => new Uint32Array(m + 1));
+    for (let i = n - 1; i >= 0; i--) {
+        for (let j = m - 1; j >= 0; j--) {
+            table[i][j] = a[i] === b[j] ? table[i + 1][j + 1] + 1 : Math.max(table[i + 1][j], table[i][j + 1]);
+        }
+    }
+    const ops: Op[] = [];
+    let i = 0;
+    let j = 0;
+    while (i < n && j < m) {
+        if (a[i] === b[j]) {
+            ops.push([' ', a[i]]);
+            i++;
+            j++;
+        } else if (table[i + 1][j] >= table[i][j + 1]) {
+            ops.push(['-', a[i++]]);
+        } else {
+            ops.push(['+', b[j++]]);
+        }
+    }
+    while (i < n) ops.push(['-', a[i++]]);
+    while (j < m) ops.push(['+', b[j++]]);
+    return ops;
+}
+
+/** Group an edit script into unified-diff hunks with `context` lines around each change. */
+function buildHunks(ops: Op[], context: number): { hunks: DiffHunk[]; added: number; removed: number } {
+    let oldNo = 1;
+    let newNo = 1;
+    const positioned = ops.map(([type, text]) => {
+        const op = { type, text, oldNo, newNo };
+        if (type !== '+') oldNo++;
+        if (type !== '-') newNo++;
+        return op;
+    });
+
+    const ranges: Array<[number, number]> = [];
+    positioned.forEach((op, idx) => {
+        if (op.type === ' ') return;
+        const start = Math.max(0, idx - context);
+        const end = Math.min(positioned.length - 1, idx + context);
+        const last = ranges[ranges.length - 1];
+        if (last && start <= last[1] + 1) last[1] = end;
+        else ranges.push([start, end]);
+    });
+
+    let added = 0;
+    let removed = 0;
+    const hunks = ranges.map(([start, end]) => {
+        const slice = positioned.slice(start, end + 1);
+        const oldLines = slice.filter((op) => op.type !== '+').length;
+        const newLines = slice.filter((op) => op.type !== '-').length;
+        added += slice.filter((op) => op.type === '+').length;
+        removed += slice.filter((op) => op.type === '-').length;
+        return {
+            oldStart: oldLines ? slice[0].oldNo : slice[0].oldNo - 1,
+            oldLines,
+            newStart: newLines ? slice[0].newNo : slice[0].newNo - 1,
+            newLines,
+            lines: slice.map((op) => op.type + op.text),
+        };
+    });
+    return { hunks, added, removed };
+}
+
+/** Line diff of two texts as unified-diff hunks. */
+export function diffLines(
+    oldText: string,
+    newText: string,
+    context = CONTEXT_LINES,
+): { hunks: DiffHunk[]; added: number; removed: number } {
+    const a = splitLines(oldText);
+    const b = splitLines(newText);
+    let prefix = 0;
+    while (prefix < a.length && prefix < b.length && a[prefix] === b[prefix]) prefix++;
+    let suffix = 0;
+    while (
+        suffix < a.length - prefix &&
+        suffix < b.length - prefix &&
+        a[a.length - 1 - suffix] === b[b.length - 1 - suffix]
+    ) {
+        suffix++;
+    }
+
+    const ops: Op[] = [
+        ...a.slice(0, prefix).map((l): Op => [' ', l]),
+        ...middleOps(a.slice(prefix, a.length - suffix), b.slice(prefix, b.length - suffix)),
+        ...a.slice(a.length - suffix).map((l): Op => [' ', l]),
+    ];
+    return buildHunks(ops, context);
+}
+
+/** Hunks and line counts of a single-file unified diff; file headers are skipped. */
+export function parseUnifiedDiff(diff: string): { hunks: DiffHunk[]; added: number; removed: number } {
+    const hunks: DiffHunk[] = [];
+    let current: DiffHunk | null = null;
+    let added = 0;
+    let removed = 0;
+    for (const line of diff.split('\n')) {
+        const header = line.match(/^@@ -(\d+)(?:,(\d+))? \+(\d+)(?:,(\d+))? @@/);
+        if (header) {
+            current = {
+                oldStart: Number(header[1]),
+                oldLines: header[2] === undefined ? 1 : Number(header[2]),
+                newStart: Number(header[3]),
+                newLines: header[4] === undefined ? 1 : Number(header[4]),
+                lines: [],
+            };
+            hunks.push(current);
+            continue;
+        }
+        if (!current) continue;
+        if (line.startsWith('+')) {
+            current.lines.push(line);
+            added++;
+        } else if (line.startsWith('-')) {
+            current.lines.push(line);
+            removed++;
+        } else if (line.startsWith(' ')) {
+            current.lines.push(line);
+        }
+    }
+    return { hunks, added, removed };
+}
+
+function readText(absolute: string): string | null {
+    try {
+        if (fs.statSync(absolute).size > MAX_FILE_BYTES) return null;
+        const text = fs.readFileSync(absolute, 'utf-8');
+        return text.includes('\0') ? null : text;
+    } catch {
+        return null;
+    }
+}
+
+function capHunks(diff: { hunks: DiffHunk[]; added: number; removed: number }): { hunks: DiffHunk[]; truncated: boolean } {
+    const hunks: DiffHunk[] = [];
+    let lines = 0;
+    for (const hunk of diff.hunks) {
+        if (lines + hunk.lines.length > MAX_DIFF_LINES) return { hunks, truncated: true };
+        hunks.push(hunk);
+        lines += hunk.lines.length;
+    }
+    return { hunks, truncated: false };
+}
+
+/**
+ * Per-file diffs of a completed file_change item. codex reports changes either as a list
+ * of `{ path, kind }` or as a map of path to `{ add: { content } }`, `{ delete: { content } }`
+ * or `{ update: { unified_diff, move_path } }`. When the turn snapshotted the file, the
+ * diff is computed from the pre-turn content to what is on disk now, so several patches
+ * to one file show up as one cumulative diff; otherwise the payload's own diff is used.
+ */
+export function extractFileDiffs(
+    changes: any,
+    cwd: string,
+    originalFor: (absolutePath: string) => string | null | undefined = () => undefined,
+): FileDiff[] {
+    const entries: Array<{ path: string; kind: FileDiff['kind']; movePath: string | null; content?: string; unifiedDiff?: string }> = [];
+    if (Array.isArray(changes)) {
+        for (const change of changes) {
+            if (typeof change?.path !== 'string') continue;
+            entries.push({
+                path: change.path,
+                kind: change.kind === 'add' || change.kind === 'delete' ? change.kind : 'update',
+                movePath: change.move_path || null,
+                unifiedDiff: change.unified_diff || change.diff,
+            });
+        }
+    } else if (changes && typeof changes === 'object') {
+        for (const [filePath, change] of Object.entries<any>(changes)) {
+            const kind = change?.type || Object.keys(change || {})[0];
+            const body = change?.type ? change : change?.[kind] || {};
+            if (kind !== 'add' && kind !== 'delete' && kind !== 'update') continue;
+            entries.push({
+                path: filePath,
+                kind,
+                movePath: body.move_path || null,
+                content: body.content,
+                unifiedDiff: body.unified_diff,
+            });
+        }
+    }
+
+    return entries.map((entry) => {
+        const absolute = path.resolve(cwd, entry.path);
+        const original = originalFor(absolute);
+        let diff: { hunks: DiffHunk[]; added: number; removed: number } | null = null;
+        let tooLarge = false;
+
+        if (original !== undefined) {
+            const current = entry.kind === 'delete' ? '' : readText(path.resolve(cwd, entry.movePath || entry.path));
+            if (current === null) tooLarge = true;
+            else diff = diffLines(original ?? '', current);
+        } else if (entry.unifiedDiff) {
+            diff = parseUnifiedDiff(entry.unifiedDiff);
+        } else if (typeof entry.content === 'string') {
+            diff = entry.kind === 'delete' ? diffLines(entry.content, '') : diffLines('', entry.content);
+        } else if (entry.kind === 'add') {
+            const current = readText(absolute);
+            if (current === null) tooLarge = true;
+            else diff = diffLines('', current);
+        }
+
+        const capped = diff ? capHunks(diff) : { hunks: [], truncated: tooLarge };
+        return {
+            path: entry.path,
+            kind: entry.kind,
+            movePath: entry.movePath,
+            hunks: capped.hunks,
+            added: diff?.added ?? 0,
+            removed: diff?.removed ?? 0,
+            truncated: capped.truncated,
+        };
+    });
+}
+
+/**
+ * Diff a completed file_change item, store it against the turn and return the file diffs
+ * (for the `codex-file-diff` event). Never throws; failures only lose the diff view.
+ */
+export function recordFileDiffs(
+    db: Database.Database,
+    turnId: string,
+    conversationId: string,
+    cwd: string,
+    item: any,
+): FileDiff[] {
+    try {
+        const diffs = extractFileDiffs(item?.changes, cwd, (absolute) => readSnapshotOriginal(db, turnId, absolute));
+        const upsert = db.prepare(`
+      INSERT INTO turn_file_diffs (turn_id, conversation_id, path, kind, move_path, hunks, added, removed, truncated, created_at)
+      VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+      ON CONFLICT(turn_id, path) DO UPDATE SET
+        kind = excluded.kind, move_path = excluded.move_path, hunks = excluded.hunks,
+        added = excluded.added, removed = excluded.removed, truncated = excluded.truncated
+    `);
+        const now = nowIso();
+        db.transaction(() => {
+            for (const diff of diffs) {
+                upsert.run(
+                    turnId,
+                    conversationId,
+                    diff.path,
+                    diff.kind,
+                    diff.movePath,
+                    JSON.stringify(diff.hunks),
+                    diff.added,
+                    diff.removed,
+                    diff.truncated ? 1 : 0,
+                    now,
+                );
+            }
+        })();
+        return diffs;
+    } catch (error) {
+        console.error('[diffs] Failed to record file diffs:', error);
+        return [];
+    }
+}
+
+/** Stored file diffs of a conversation grouped by turn, newest turn first. */
+export function getTurnDiffs(db: Database.Database, conversationId: string, turnId?: string): TurnDiffs[] {
+    const rows = db.prepare(`
+    SELECT * FROM turn_file_diffs
+    WHERE conversation_id = ? ${turnId ? 'AND turn_id = ?' : ''}
+    ORDER BY CAST(created_at AS INTEGER) DESC, rowid ASC
+  `).all(...(turnId ? [conversationId, turnId] : [conversationId])) as any[];
+
+    const byTurn = new Map<string, TurnDiffs>();
+    for (const row of rows) {
+        if (!byTurn.has(row.turn_id)) {
+            byTurn.set(row.turn_id, { turnId: row.turn_id, createdAt: row.created_at, added: 0, removed: 0, files: [] });
+        }
+        const turn = byTurn.get(row.turn_id)!;
+        turn.added += row.added;
+        turn.removed += row.removed;
+        let hunks: DiffHunk[] = [];
+        try {
+            hunks = JSON.parse(row.hunks);
+        } catch { }
+        turn.files.push({
+            path: row.path,
+            kind: row.kind,
+            movePath: row.move_path,
+            hunks,
+            added: row.added,
+            removed: row.removed,
+            truncated: !!row.truncated,
+        });
+    }
+    return Array.from(byTurn.values());
+}
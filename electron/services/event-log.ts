@@ -0,0 +1,275 @@
+import Database from 'better-sqlite3';
+import { WebContents } from 'electron';
+import * as fs from 'fs';
+import * as path from 'path';
+import { dbGetSetting, dbSetSetting } from './db';
+import { homeDir } from './utils';
+
+const ENABLED_KEY = 'eventLog.enabled';
+const MAX_FILE_BYTES = 5 * 1024 * 1024;
+const RECORDED_CHANNEL = /^(codex-|pty-)/;
+/** Recorded events are batched and written this often, off the send path. */
+const FLUSH_INTERVAL_MS = 1000;
+
+/** Retention: logs older than this many days, or beyond the file and byte caps, are deleted. */
+export const EVENT_LOG_RETENTION = {
+    maxDays: 7,
+    maxFiles: 40,
+    maxBytes: 100 * 1024 * 1024,
+};
+
+export interface EventLogEntry {
+    ts: string;
+    channel: string;
+    payload: unknown;
+}
+
+function eventLogDir(): string {
+    const home = homeDir();
+    if (!home) throw new Error('Unable to resolve home directory');
+    return path.join(home, '.codex-wui', 'event-log');
+}
+
+function dayStamp(date = new Date()): string {
+    const pad = (n: number) => String(n).padStart(2, '0');
+    return `${date.getFullYear()}-${pad(date.getMonth() + 1)}-${pad(date.getDate())}`;
+}
+
+function currentLogFile(dir = eventLogDir()): string {
+    return path.join(dir, `events-${dayStamp()}.jsonl`);
+}
+
+/**
+ * Move a full log aside as `events-<day>.<n>.jsonl` so the active file stays small.
+ * Returns whether it rotated.
+ */
+async function rotateIfNeeded(file: string): Promise<boolean> {
+    let size = 0;
+    try {
+        size = (await fs.promises.stat(file)).size;
+    } catch {
+        return false;
+    }
+    if (size < MAX_FILE_BYTES) return false;
+
+    const base = file.replace(/\.jsonl$/, '');
+    let n = 1;
+    while (fs.existsSync(`${base}.${n}.jsonl`)) n++;
+    await fs.promises.rename(file, `${base}.${n}.jsonl`);
+    return true;
+}
+
+/**
+ * Delete logs past the retention caps: whole days older than `maxDays`, then the oldest
+ * files until at most `maxFiles` and `maxBytes` remain. Today's active file is always kept.
+ * Returns the deleted file names.
+ */
+export function pruneEventLogs(
+    dir: string,
+    today = new Date(),
+    limits = EVENT_LOG_RETENTION,
+): string[] {
+    let names: string[];
+    try {
+        names = fs.readdirSync(dir).filter((f) => /^events-\d{4}-\d{2}-\d{2}(\.\d+)?\.jsonl$/.test(f));
+    } catch {
+        return [];
+    }
+    const oldest = new Date(today);
+    oldest.setDate(oldest.getDate() - (limits.maxDays - 1));
+    const oldestDay = dayStamp(oldest);
+    const active = `events-${dayStamp(today)}.jsonl`;
+
+    // Newest first: by day, then the active file, then rotations from the highest index down.
+    const files = names
+        .map((name) => ({ name, day: name.slice(7, 17), size: fileSize(path.join(dir, name)) }))
+        .sort((a, b) => b.day.localeCompare(a.day) || rotationIndex(b.name) - rotationIndex(a.name));
+
+    const deleted: string[] = [];
+    let kept = 0;
+    let bytes = 0;
+    for (const file of files) {
+        const keep = file.name === active || (
+            file.day >= oldestDay && kept < limits.maxFiles && bytes + file.size <= limits.maxBytes
+        );
+        if (keep) {
+            kept++;
+            bytes += file.size;
+            continue;
+        }
+        try {
+            fs.unlinkSync(path.join(dir, file.name));
+            deleted.push(file.name);
+        } catch { }
+    }
+    return deleted;
+}
+
+function fileSize(file: string): number {
+    try {
+        return fs.statSync(file).size;
+    } catch {
+        return 0;
+    }
+}
+
+/**
+ * Flight recorder that mirrors emitted codex-* and pty-* events to a per-day JSONL file.
+ * Events are buffered in memory and appended asynchronously about once a second.
+ */
+export class EventRecorder {
+    private enabled: boolean;
+    private pending: string[] = [];
+    private timer: NodeJS.Timeout | null = null;
+    private flushing: Promise<void> | null = null;
+    private prunedFor = '';
+
+    constructor(private db: Database.Database, private dir?: string) {
+        this.enabled = dbGetSetting(db, ENABLED_KEY, false);
+    }
+
+    isEnabled(): boolean {
+        return this.enabled;
+    }
+
+    setEnabled(enabled: boolean): void {
+        this.enabled = enabled;
+        dbSetSetting(this.db, ENABLED_KEY, enabled);
+    }
+
+    record(channel: string, payload: unknown): void {
+        if (!this.enabled || !RECORDED_CHANNEL.test(channel)) return;
+        try {
+            const entry: EventLogEntry = { ts: new Date().toISOString(), channel, payload };
+            this.pending.push(JSON.stringify(entry) + '\n');
+        } catch (err) {
+            console.error('[event-log] Failed to record event:', err);
+            return;
+        }
+        if (!this.timer) {
+            this.timer = setTimeout(() => {
+                this.timer = null;
+                void this.flush();
+            }, FLUSH_INTERVAL_MS);
+            this.timer.unref();
+        }
+    }
+
+    /** Append the buffered events; resolves once they are on disk. */
+    flush(): Promise<void> {
+        if (this.flushing) return this.flushing.then(() => this.flush());
+        if (this.pending.length === 0) return Promise.resolve();
+        const lines = this.pending.join('');
+        this.pending = [];
+        this.flushing = this.write(lines)
+            .catch((err) => console.error('[event-log] Failed to write events:', err))
+            .finally(() => { this.flushing = null; });
+        return this.flushing;
+    }
+
+    /** Write whatever is still buffered before the app quits. */
+    flushSync(): void {
+        if (this.timer) clearTimeout(this.timer);
+        this.timer = null;
+        if (this.pending.length === 0) return;
+        const lines = this.pending.join('');
+        this.pending = [];
+        try {
+            const dir = this.logDir();
+            fs.mkdirSync(dir, { recursive: true });
+            fs.appendFileSync(currentLogFile(dir), lines);
+        } catch (err) {
+            console.error('[event-log] Failed to write events:', err);
+        }
+    }
+
+    private async write(lines: string): Promise<void> {
+        const dir = this.logDir();
+        await fs.promises.mkdir(dir, { recursive: true });
+        const file = currentLogFile(dir);
+        const rotated = await rotateIfNeeded(file);
+        if (rotated || this.prunedFor !== file) {
+            this.prunedFor = file;
+            pruneEventLogs(dir);
+        }
+        await fs.promises.appendFile(file, lines);
+    }
+
+    private logDir(): string {
+        return this.dir ?? eventLogDir();
+    }
+}
+
+/**
+ * Wrap a WebContents so every `send` is also handed to the recorder.
+ */
+export function tapWebContents(webContents: WebContents, recorder: EventRecorder): WebContents {
+    return new Proxy(webContents, {
+        get(target, prop) {
+            if (prop === 'send') {
+                return (channel: string, ...args: any[]) => {
+                    recorder.record(channel, args[0]);
+                    target.send(channel, ...args);
+                };
+            }
+            const value = Reflect.get(target, prop);
+            return typeof value === 'function' ? value.bind(target) : value;
+        },
+    });
+}
+
+export function setEventLogEnabled(recorder: EventRecorder, enabled: boolean): { enabled: boolean } {
+    recorder.setEnabled(enabled);
+    return { enabled };
+}
+
+export function getEventLogStatus(recorder: EventRecorder): {
+    enabled: boolean;
+    directory: string;
+    currentFile: string;
+    sizeBytes: number;
+} {
+    const file = currentLogFile();
+    let sizeBytes = 0;
+    try {
+        sizeBytes = fs.statSync(file).size;
+    } catch { }
+    return { enabled: recorder.isEnabled(), directory: eventLogDir(), currentFile: file, sizeBytes };
+}
+
+/**
+ * Most recent entries from today's log, reaching into the latest rotated file if needed.
+ */
+export function getRecentEventLogEntries(limit = 200): EventLogEntry[] {
+    const dir = eventLogDir();
+    const prefix = `events-${dayStamp()}`;
+    let files: string[] = [];
+    try {
+        files = fs
+            .readdirSync(dir)
+            .filter((f) => f.startsWith(prefix) && f.endsWith('.jsonl'))
+            // Newest first: the active file, then rotations from the highest index down.
+            .sort((a, b) => rotationIndex(b) - rotationIndex(a));
+    } catch {
+        return [];
+    }
+
+    const entries: EventLogEntry[] = [];
+    for (const file of files) {
+        const lines = fs.readFileSync(path.join(dir, file), 'utf-8').split('\n').filter(Boolean);
+        const parsed: EventLogEntry[] = [];
+        for (const line of lines) {
+            try {
+                parsed.push(JSON.parse(line));
+            } catch { }
+        }
+        entries.unshift(...parsed.slice(-(limit - entries.length)));
+        if (entries.length >= limit) break;
+    }
+    return entries;
+}
+
+function rotationIndex(file: string): number {
+    const match = file.match(/\.(\d+)\.jsonl$/);
+    return match ? Number(match[1]) : Number.MAX_SAFE_INTEGER;
+}
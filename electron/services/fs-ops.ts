@@ -1,8 +1,24 @@
 import * as fs from 'fs';
 import * as path from 'path';
-import { execSync, spawn } from 'child_process';
-import { DirectoryEntry, FileSearchResult, SearchResult } from './models';
-import { expandTildePath } from './utils';
+import * as readline from 'readline';
+import { execFile, spawn } from 'child_process';
+import { promisify } from 'util';
+import { shell } from 'electron';
+import Database from 'better-sqlite3';
+import {
+    ContentSearchMatch,
+    ContentSearchOptions,
+    DirectoryEntry,
+    FileEncoding,
+    FileRange,
+    FileSearchResult,
+    HexPage,
+    HexRow,
+} from './models';
+import { dbGetSetting, dbSetSetting } from './db';
+import { JobContext } from './jobs';
+import { resourceLimits } from './resources';
+import { expandTildePath, generateId, globToRegExp, homeDir, nowIso, spawnCommand, trackChild } from './utils';
 
 function canonicalizeWorkspaceRoot(workspacePath?: string): string {
     if (!workspacePath) throw new Error('workspacePath is required');
@@ -14,7 +30,7 @@ function canonicalizeWorkspaceRoot(workspacePath?: string): string {
     return canonical;
 }
 
-function resolveWorkspaceScopedPath(rawPath: string, workspacePath?: string): string {
+export function resolveWorkspaceScopedPath(rawPath: string, workspacePath?: string): string {
     const workspaceRoot = canonicalizeWorkspaceRoot(workspacePath);
     const target = path.isAbsolute(rawPath) ? rawPath : path.join(workspaceRoot, rawPath);
 
@@ -35,19 +51,140 @@ function resolveWorkspaceScopedPath(rawPath: string, workspacePath?: string): st
     return normalized;
 }
 
+// ===== Protected Paths =====
+
+const PROTECTED_PATHS_KEY = 'fs.protectedPaths';
+
+/** Secrets that must not end up in a prompt. A leading `!` exempts files a pattern caught. */
+export const DEFAULT_PROTECTED_PATTERNS = [
+    '.env', '.env.*', '!.env.example', '!.env.sample', '!.env.template',
+    '*.pem', '*.key', '*.p12', '*.pfx', '*.keystore',
+    'id_rsa', 'id_dsa', 'id_ecdsa', 'id_ed25519',
+    '.netrc', '.npmrc', '.pypirc', '**/.aws/credentials',
+];
+
+export interface ProtectedPathSettings {
+    /** Globs as in .gitignore: without a slash they match at any depth; the last match wins. */
+    patterns: string[];
+    /** Absolute paths of protected files the user chose to let through, one by one. */
+    allowed: string[];
+}
+
+let protectedPaths: ProtectedPathSettings = { patterns: DEFAULT_PROTECTED_PATTERNS, allowed: [] };
+// Compiled once per pattern list; searchFiles checks every entry of the file index.
+let protectedMatchers: { patterns: string[]; matchers: Array<{ negated: boolean; regex: RegExp }> } | null = null;
+
+function matchersFor(patterns: string[]): Array<{ negated: boolean; regex: RegExp }> {
+    if (protectedMatchers?.patterns !== patterns) {
+        protectedMatchers = {
+            patterns,
+            matchers: patterns.map((p) => ({
+                negated: p.startsWith('!'),
+                regex: globToRegExp(p.startsWith('!') ? p.slice(1) : p),
+            })),
+        };
+    }
+    return protectedMatchers.matchers;
+}
+
+/** Load the saved patterns and overrides; called once at startup. */
+export function initProtectedPaths(db: Database.Database): void {
+    protectedPaths = getProtectedPathSettings(db);
+}
+
+export function getProtectedPathSettings(db: Database.Database): ProtectedPathSettings {
+    return {
+        patterns: DEFAULT_PROTECTED_PATTERNS,
+        allowed: [],
+        ...dbGetSetting<Partial<ProtectedPathSettings>>(db, PROTECTED_PATHS_KEY, {}),
+    };
+}
+
+export function setProtectedPathSettings(
+    db: Database.Database,
+    settings: Partial<ProtectedPathSettings>,
+): ProtectedPathSettings {
+    const merged = { ...getProtectedPathSettings(db), ...settings };
+    merged.patterns = merged.patterns.map((p) => p.trim()).filter(Boolean);
+    dbSetSetting(db, PROTECTED_PATHS_KEY, merged);
+    protectedPaths = merged;
+    return merged;
+}
+
+/**
+ * Whether `filePath` is a protected file. Patterns are matched against the path relative
+ * to `workspaceRoot`, or against the whole path when there is none (attachments).
+ */
+export function isProtectedPath(
+    filePath: string,
+    workspaceRoot?: string,
+    settings: ProtectedPathSettings = protectedPaths,
+): boolean {
+    const absolute = path.resolve(expandTildePath(filePath));
+    if (settings.allowed.includes(absolute)) return false;
+    const subject = (workspaceRoot ? path.relative(path.resolve(workspaceRoot), absolute) : absolute)
+        .split(path.sep).join('/');
+    let isProtected = false;
+    for (const { negated, regex } of matchersFor(settings.patterns)) {
+        if (regex.test(subject)) isProtected = !negated;
+    }
+    return isProtected;
+}
+
+function protectedFileError(resolved: string, workspacePath?: string): { success: false; protected: true; error: string } | null {
+    if (!isProtectedPath(resolved, canonicalizeWorkspaceRoot(workspacePath))) return null;
+    return {
+        success: false,
+        protected: true,
+        error: `${path.basename(resolved)} is a protected file; allow it explicitly to read it`,
+    };
+}
+
+/** Let one protected file through from now on, after the user confirmed it. */
+export function allowProtectedFile(
+    db: Database.Database,
+    filePath: string,
+    workspacePath?: string,
+): { success: boolean; settings?: ProtectedPathSettings; error?: string } {
+    try {
+        const resolved = resolveWorkspaceScopedPath(filePath, workspacePath);
+        const current = getProtectedPathSettings(db);
+        if (current.allowed.includes(resolved)) return { success: true, settings: current };
+        return { success: true, settings: setProtectedPathSettings(db, { allowed: [...current.allowed, resolved] }) };
+    } catch (err: any) {
+        return { success: false, error: err.message };
+    }
+}
+
+export function revokeProtectedFile(db: Database.Database, filePath: string): ProtectedPathSettings {
+    const resolved = path.resolve(expandTildePath(filePath));
+    const current = getProtectedPathSettings(db);
+    return setProtectedPathSettings(db, { allowed: current.allowed.filter((p) => p !== resolved) });
+}
+
 const IGNORE_DIRS = [
     'node_modules', '.git', 'dist', 'dist-electron', '.next',
     '.vite', 'coverage', '__pycache__', '.cache',
 ];
 
+/**
+ * Collect files under `dir`. Symlinked directories are followed, but each real directory
+ * is entered once, so link cycles (`a/loop -> ..`) terminate.
+ */
 function walkFiles(
     dir: string,
     base: string,
-    depth: number,
-    maxDepth: number,
-    out: FileSearchResult[],
+    out: Map<string, boolean>,
+    visited: Set<string> = new Set(),
 ): void {
-    if (depth > maxDepth) return;
+    if (out.size >= resourceLimits().indexedEntries) return;
+    try {
+        const real = fs.realpathSync(dir);
+        if (visited.has(real)) return;
+        visited.add(real);
+    } catch {
+        return;
+    }
 
     let entries: fs.Dirent[];
     try {
@@ -58,85 +195,772 @@ function walkFiles(
 
     for (const entry of entries) {
         const fullPath = path.join(dir, entry.name);
-        const rel = path.relative(base, fullPath);
+        const rel = path.relative(base, fullPath).split(path.sep).join('/');
+        let isDirectory = entry.isDirectory();
+        if (entry.isSymbolicLink()) {
+            try {
+                isDirectory = fs.statSync(fullPath).isDirectory();
+            } catch {
+                isDirectory = false;
+            }
+        }
 
-        if (entry.isDirectory()) {
+        if (isDirectory) {
             if (IGNORE_DIRS.includes(entry.name) || entry.name.startsWith('.')) continue;
-            out.push({ name: entry.name, path: fullPath, relativePath: rel, isDirectory: true });
-            walkFiles(fullPath, base, depth + 1, maxDepth, out);
+            out.set(rel, true);
+            walkFiles(fullPath, base, out, visited);
         } else {
-            out.push({ name: entry.name, path: fullPath, relativePath: rel, isDirectory: false });
+            out.set(rel, false);
         }
     }
 }
 
-export function searchFiles(workspacePath: string, query: string): FileSearchResult[] {
-    const base = expandTildePath(workspacePath);
-    const allFiles: FileSearchResult[] = [];
-    walkFiles(base, base, 0, 4, allFiles);
+// ===== File Index =====
+
+interface FileIndex {
+    /** Relative path (forward slashes) -> is directory. */
+    entries: Map<string, boolean>;
+    watcher: fs.FSWatcher | null;
+    builtAt: number;
+    stale: boolean;
+}
+
+// Without a watcher (unsupported platform/filesystem) the index is rebuilt after this long.
+const UNWATCHED_INDEX_TTL_MS = 30_000;
+
+const fileIndexes = new Map<string, FileIndex>();
+
+function addWithParents(entries: Map<string, boolean>, rel: string, isDirectory: boolean): void {
+    entries.set(rel, isDirectory);
+    let parent = path.posix.dirname(rel);
+    while (parent !== '.' && !entries.has(parent)) {
+        entries.set(parent, true);
+        parent = path.posix.dirname(parent);
+    }
+}
+
+/** All non-ignored files and directories: git's view (honours .gitignore) or a full walk. */
+async function buildIndexEntries(root: string): Promise<Map<string, boolean>> {
+    const entries = new Map<string, boolean>();
+    try {
+        const { stdout: listed } = await promisify(execFile)(
+            'git',
+            ['ls-files', '--cached', '--others', '--exclude-standard', '-z'],
+            { cwd: root, maxBuffer: 256 * 1024 * 1024 },
+        );
+        for (const rel of listed.split('\0')) {
+            if (!rel) continue;
+            addWithParents(entries, rel, false);
+            if (entries.size >= resourceLimits().indexedEntries) break;
+        }
+        return entries;
+    } catch {
+        walkFiles(root, root, entries);
+        return entries;
+    }
+}
+
+/** Apply one watcher event. New directories are not walked; they mark the index stale. */
+function applyIndexChange(index: FileIndex, root: string, rel: string): void {
+    if (rel.split('/').some((segment) => IGNORE_DIRS.includes(segment))) return;
+    if (path.posix.basename(rel) === '.gitignore') {
+        index.stale = true;
+        return;
+    }
+    let stat: fs.Stats | null = null;
+    try {
+        stat = fs.statSync(path.join(root, rel));
+    } catch { }
+
+    if (!stat) {
+        if (index.entries.get(rel)) {
+            for (const key of Array.from(index.entries.keys())) {
+                if (key.startsWith(`${rel}/`)) index.entries.delete(key);
+            }
+        }
+        index.entries.delete(rel);
+    } else if (stat.isDirectory()) {
+        if (!index.entries.has(rel)) index.stale = true;
+    } else if (!index.entries.has(rel)) {
+        addWithParents(index.entries, rel, false);
+    }
+}
+
+function closeFileIndex(root: string): void {
+    const index = fileIndexes.get(root);
+    if (!index) return;
+    index.watcher?.close();
+    fileIndexes.delete(root);
+}
+
+export function closeFileIndexes(): void {
+    for (const root of Array.from(fileIndexes.keys())) closeFileIndex(root);
+}
 
+/** Drop least recently used indexes beyond the current limit. */
+function trimFileIndexes(): void {
+    while (fileIndexes.size > resourceLimits().fileIndexes) closeFileIndex(fileIndexes.keys().next().value as string);
+}
+
+export function hasFileIndex(workspacePath: string): boolean {
+    return fileIndexes.has(expandTildePath(workspacePath));
+}
+
+export function fileIndexStats(): { indexes: number; entries: number } {
+    let entries = 0;
+    for (const index of fileIndexes.values()) entries += index.entries.size;
+    return { indexes: fileIndexes.size, entries };
+}
+
+/**
+ * The cached file index of a workspace, built on first use and kept current by a
+ * recursive watcher. The least recently used index is dropped beyond the resource limit.
+ */
+async function getFileIndex(root: string): Promise<FileIndex> {
+    const index = fileIndexes.get(root);
+    if (index) {
+        // Re-insert to mark as most recently used.
+        fileIndexes.delete(root);
+        fileIndexes.set(root, index);
+        const expired = !index.watcher && Date.now() - index.builtAt > UNWATCHED_INDEX_TTL_MS;
+        if (index.stale || expired) {
+            index.stale = false;
+            index.entries = await buildIndexEntries(root);
+            index.builtAt = Date.now();
+        }
+        return index;
+    }
+
+    const entries = await buildIndexEntries(root);
+    // Another search may have opened the index while git was listing files.
+    return fileIndexes.get(root) ?? openFileIndex(root, entries);
+}
+
+/** Cache `entries` as the index of `root` and start keeping it current. */
+function openFileIndex(root: string, entries: Map<string, boolean>): FileIndex {
+    const created: FileIndex = { entries, watcher: null, builtAt: Date.now(), stale: false };
+    try {
+        const watcher = fs.watch(root, { recursive: true });
+        watcher.unref();
+        watcher.on('change', (_event, filename) => {
+            if (filename) applyIndexChange(created, root, filename.toString().split(path.sep).join('/'));
+        });
+        watcher.on('error', () => {
+            watcher.close();
+            created.watcher = null;
+        });
+        created.watcher = watcher;
+    } catch { }
+
+    fileIndexes.set(root, created);
+    trimFileIndexes();
+    return created;
+}
+
+const INDEX_JOB_CHUNK = 5000;
+
+/**
+ * Rebuild a workspace's file index as a background job (see jobs.ts), reporting progress
+ * while git's file list is indexed. The cached index is replaced only when the build
+ * finishes; a cancelled build leaves the old one in place.
+ */
+export async function indexWorkspaceFiles(workspacePath: string, job: JobContext): Promise<{ entries: number }> {
+    const root = expandTildePath(workspacePath);
+    const entries = new Map<string, boolean>();
+    let listed: string[] | null = null;
+    try {
+        const { stdout } = await promisify(execFile)(
+            'git',
+            ['ls-files', '--cached', '--others', '--exclude-standard', '-z'],
+            { cwd: root, maxBuffer: 256 * 1024 * 1024 },
+        );
+        listed = stdout.split('\0').filter(Boolean);
+    } catch { }
+
+    if (listed) {
+        const limit = resourceLimits().indexedEntries;
+        for (let i = 0; i < listed.length && entries.size < limit; i++) {
+            addWithParents(entries, listed[i], false);
+            if (i % INDEX_JOB_CHUNK === 0) await job.progress(i, listed.length, `${i} of ${listed.length} files`);
+        }
+    } else {
+        await job.progress(0, 0, 'Walking the workspace');
+        walkFiles(root, root, entries);
+    }
+    await job.progress(1, 1, `${entries.size} entries`);
+
+    closeFileIndex(root);
+    openFileIndex(root, entries);
+    return { entries: entries.size };
+}
+
+function isBoundary(text: string, i: number): boolean {
+    if (i === 0) return true;
+    const prev = text[i - 1];
+    if ('/_-. '.includes(prev)) return true;
+    return prev === prev.toLowerCase() && text[i] !== text[i].toLowerCase();
+}
+
+/**
+ * Subsequence match score of `query` in `candidate` (higher is better), or null when the
+ * query characters do not all appear in order. Consecutive characters and matches at
+ * word boundaries score more, as in fzf-style pickers.
+ */
+export function fuzzyScore(query: string, candidate: string): number | null {
+    if (!query) return 0;
+    const q = query.toLowerCase();
+    const lower = candidate.toLowerCase();
+    let score = 0;
+    let qi = 0;
+    let previous = -2;
+    for (let i = 0; i < candidate.length && qi < q.length; i++) {
+        if (lower[i] !== q[qi]) continue;
+        score += 1;
+        if (previous === i - 1) score += 5;
+        if (isBoundary(candidate, i)) score += 8;
+        previous = i;
+        qi++;
+    }
+    if (qi < q.length) return null;
+    if (lower.includes(q)) score += 10 + q.length * 2;
+    return score - candidate.length * 0.05;
+}
+
+export async function searchFiles(workspacePath: string, query: string): Promise<FileSearchResult[]> {
+    const base = expandTildePath(workspacePath);
+    const { entries } = await getFileIndex(base);
     const q = query.toLowerCase();
-    let filtered = allFiles.filter(
-        (f) => f.relativePath.toLowerCase().includes(q) || f.name.toLowerCase().includes(q),
-    );
-
-    filtered.sort((a, b) => {
-        if (a.isDirectory !== b.isDirectory) return b.isDirectory ? 1 : -1;
-        const aExact = a.name.toLowerCase() === q;
-        const bExact = b.name.toLowerCase() === q;
-        if (aExact !== bExact) return bExact ? 1 : -1;
-        return a.relativePath.length - b.relativePath.length;
+
+    const scored: Array<{ result: FileSearchResult; score: number }> = [];
+    for (const [rel, isDirectory] of entries) {
+        if (!isDirectory && isProtectedPath(path.join(base, rel), base)) continue;
+        const name = path.posix.basename(rel);
+        const nameScore = fuzzyScore(query, name);
+        const pathScore = nameScore === null ? fuzzyScore(query, rel) : null;
+        if (nameScore === null && pathScore === null) continue;
+        // Matching within the file name beats matching across directories.
+        let score = nameScore !== null ? nameScore + 15 : pathScore!;
+        if (name.toLowerCase() === q) score += 50;
+        scored.push({
+            result: { name, path: path.join(base, rel), relativePath: rel.split('/').join(path.sep), isDirectory },
+            score,
+        });
+    }
+
+    scored.sort((a, b) => b.score - a.score || a.result.relativePath.length - b.result.relativePath.length);
+    return scored.slice(0, 20).map((s) => s.result);
+}
+
+const CONTENT_SEARCH_MAX_FILE_BYTES = 2 * 1024 * 1024;
+const CONTENT_SEARCH_TIMEOUT_MS = 20000;
+
+function escapeRegExp(text: string): string {
+    return text.replace(/[.*+?^${}()|[\]\\]/g, '\\$&');
+}
+
+/** In-process search used when ripgrep is not installed. */
+export async function scanFileContents(
+    root: string,
+    query: string,
+    options: ContentSearchOptions = {},
+): Promise<ContentSearchMatch[]> {
+    const context = Math.max(0, Math.min(10, options.contextLines ?? 2));
+    const maxResults = Math.max(1, options.maxResults ?? 200);
+    const matcher = new RegExp(options.regex ? query : escapeRegExp(query), options.caseSensitive ? '' : 'i');
+    const globFilter = options.glob ? globToRegExp(options.glob) : null;
+
+    const results: ContentSearchMatch[] = [];
+    for (const [relativePath, isDirectory] of (await getFileIndex(root)).entries) {
+        if (isDirectory) continue;
+        if (globFilter && !globFilter.test(relativePath)) continue;
+        const fullPath = path.join(root, relativePath);
+        if (isProtectedPath(fullPath, root)) continue;
+        let text: string;
+        try {
+            const stat = fs.statSync(fullPath);
+            if (!stat.isFile() || stat.size > CONTENT_SEARCH_MAX_FILE_BYTES) continue;
+            text = fs.readFileSync(fullPath, 'utf-8');
+        } catch {
+            continue;
+        }
+        if (text.includes('\0')) continue;
+
+        const lines = text.split(/\r?\n/);
+        for (let i = 0; i < lines.length; i++) {
+            const match = matcher.exec(lines[i]);
+            if (!match) continue;
+            results.push({
+                path: fullPath,
+                relativePath,
+                lineNumber: i + 1,
+                column: match.index + 1,
+                line: lines[i],
+                before: lines.slice(Math.max(0, i - context), i),
+                after: lines.slice(i + 1, i + 1 + context),
+            });
+            if (results.length >= maxResults) return results;
+        }
+    }
+    return results;
+}
+
+function ripgrepSearch(root: string, query: string, options: ContentSearchOptions): Promise<ContentSearchMatch[]> {
+    const context = Math.max(0, Math.min(10, options.contextLines ?? 2));
+    const maxResults = Math.max(1, options.maxResults ?? 200);
+    const args = ['--json', '--context', String(context), '--max-filesize', '2M'];
+    if (!options.regex) args.push('--fixed-strings');
+    args.push(options.caseSensitive ? '--case-sensitive' : '--ignore-case');
+    if (options.glob) args.push('--glob', options.glob);
+    args.push('--', query, '.');
+
+    return new Promise((resolve, reject) => {
+        const child = spawnCommand('rg', args, root);
+        const results: ContentSearchMatch[] = [];
+        // Context lines seen since the last match in this file, waiting to become `before`.
+        let pending: Array<{ lineNumber: number; text: string }> = [];
+        let last: ContentSearchMatch | null = null;
+        let stderr = '';
+        let done = false;
+
+        const settle = (error?: Error) => {
+            if (done) return;
+            done = true;
+            clearTimeout(timer);
+            if (error) reject(error);
+            else resolve(results);
+        };
+        const timer = setTimeout(() => {
+            child.kill();
+            settle();
+        }, CONTENT_SEARCH_TIMEOUT_MS);
+
+        const rl = readline.createInterface({ input: child.stdout! });
+        rl.on('line', (line: string) => {
+            if (done) return;
+            let event: any;
+            try {
+                event = JSON.parse(line);
+            } catch {
+                return;
+            }
+            const data = event.data || {};
+            const text = String(data.lines?.text ?? '').replace(/\r?\n$/, '');
+            if (event.type === 'begin') {
+                pending = [];
+                last = null;
+            } else if (event.type === 'context') {
+                if (last && data.line_number - last.lineNumber <= context) last.after.push(text);
+                pending.push({ lineNumber: data.line_number, text });
+            } else if (event.type === 'match') {
+                const relativePath = String(data.path?.text ?? '').replace(/^\.[\\/]/, '');
+                if (isProtectedPath(path.join(root, relativePath), root)) {
+                    pending = [];
+                    last = null;
+                    return;
+                }
+                last = {
+                    path: path.join(root, relativePath),
+                    relativePath,
+                    lineNumber: data.line_number,
+                    column: (data.submatches?.[0]?.start ?? 0) + 1,
+                    line: text,
+                    before: pending.filter((p) => data.line_number - p.lineNumber <= context).map((p) => p.text),
+                    after: [],
+                };
+                pending = [];
+                results.push(last);
+                if (results.length >= maxResults) {
+                    child.kill();
+                    settle();
+                }
+            }
+        });
+        child.stderr?.on('data', (d: Buffer) => (stderr += d.toString()));
+        child.on('error', (err) => settle(err));
+        child.on('close', (code) => {
+            // rg exits 1 when nothing matched.
+            if (code !== null && code > 1) settle(new Error(stderr.trim() || `rg exited with code ${code}`));
+            else settle();
+        });
     });
+}
+
+/**
+ * Search file contents under the workspace with ripgrep (respects .gitignore). Falls back
+ * to an in-process scan of git-visible files when `rg` is not on PATH.
+ */
+export async function searchFileContents(
+    workspacePath: string,
+    query: string,
+    options: ContentSearchOptions = {},
+): Promise<{ success: boolean; matches?: ContentSearchMatch[]; engine?: 'ripgrep' | 'builtin'; error?: string }> {
+    try {
+        if (!query) return { success: true, matches: [], engine: 'builtin' };
+        const root = canonicalizeWorkspaceRoot(workspacePath);
+        if (options.regex) new RegExp(query);
+        try {
+            return { success: true, matches: await ripgrepSearch(root, query, options), engine: 'ripgrep' };
+        } catch (err: any) {
+            if (err?.code !== 'ENOENT') throw err;
+        }
+        return { success: true, matches: await scanFileContents(root, query, options), engine: 'builtin' };
+    } catch (err: any) {
+        return { success: false, error: err.message };
+    }
+}
+
+const ENCODING_SAMPLE_BYTES = 8192;
+const READ_CONTENT_MAX_BYTES = 10 * 1024 * 1024;
+const RANGE_DEFAULT_BYTES = 1024 * 1024;
+const RANGE_MAX_BYTES = 8 * 1024 * 1024;
+
+/** Bytes at the end of `bytes` that start an incomplete UTF-8 sequence. */
+function incompleteUtf8Tail(bytes: Buffer): number {
+    for (let back = 1; back <= Math.min(3, bytes.length); back++) {
+        const byte = bytes[bytes.length - back];
+        if ((byte & 0xc0) === 0x80) continue;
+        const needed = byte >= 0xf0 ? 4 : byte >= 0xe0 ? 3 : byte >= 0xc0 ? 2 : 1;
+        return needed > back ? back : 0;
+    }
+    return 0;
+}
+
+/**
+ * Encoding of a file from its first bytes: a BOM decides; otherwise NUL bytes mean
+ * binary, valid UTF-8 is UTF-8, and mostly printable single-byte text is Latin-1.
+ */
+export function detectEncoding(sample: Buffer): FileEncoding {
+    if (sample[0] === 0xef && sample[1] === 0xbb && sample[2] === 0xbf) return 'utf-8';
+    if (sample[0] === 0xff && sample[1] === 0xfe) return 'utf-16le';
+    if (sample[0] === 0xfe && sample[1] === 0xff) return 'utf-16be';
+    if (sample.includes(0)) return 'binary';
+    try {
+        new TextDecoder('utf-8', { fatal: true }).decode(sample.subarray(0, sample.length - incompleteUtf8Tail(sample)));
+        return 'utf-8';
+    } catch { }
+    let control = 0;
+    for (const byte of sample) {
+        if (byte < 0x20 && byte !== 0x09 && byte !== 0x0a && byte !== 0x0d && byte !== 0x1b) control++;
+    }
+    return control / sample.length < 0.05 ? 'latin1' : 'binary';
+}
+
+function bomLength(encoding: FileEncoding, sample: Buffer): number {
+    if (encoding === 'utf-8') return sample[0] === 0xef && sample[1] === 0xbb && sample[2] === 0xbf ? 3 : 0;
+    return encoding === 'utf-16le' || encoding === 'utf-16be' ? 2 : 0;
+}
 
-    return filtered.slice(0, 20);
+function decodeBytes(bytes: Buffer, encoding: FileEncoding): string | null {
+    switch (encoding) {
+        case 'utf-8':
+            return bytes.toString('utf-8');
+        case 'utf-16le':
+            return bytes.toString('utf16le');
+        case 'utf-16be':
+            return Buffer.from(bytes).swap16().toString('utf16le');
+        case 'latin1':
+            return bytes.toString('latin1');
+        default:
+            return null;
+    }
+}
+
+function readBytes(fd: number, position: number, length: number): Buffer {
+    const buffer = Buffer.alloc(Math.max(0, length));
+    const read = length > 0 ? fs.readSync(fd, buffer, 0, length, position) : 0;
+    return buffer.subarray(0, read);
 }
 
+/**
+ * Read the whole file as text. Binary files are flagged rather than decoded, and files
+ * over 10MB are refused so the renderer pages them with readFileRange instead.
+ */
 export function readFileContent(
     filePath: string,
     workspacePath?: string,
-): { success: boolean; content?: string; error?: string } {
+): { success: boolean; content?: string; encoding?: FileEncoding; binary?: boolean; tooLarge?: boolean; size?: number; protected?: boolean; error?: string } {
+    let fd: number | null = null;
     try {
         const resolved = resolveWorkspaceScopedPath(filePath, workspacePath);
-        const content = fs.readFileSync(resolved, 'utf-8');
-        return { success: true, content };
+        const denied = protectedFileError(resolved, workspacePath);
+        if (denied) return denied;
+        const size = fs.statSync(resolved).size;
+        if (size > READ_CONTENT_MAX_BYTES) {
+            return { success: false, tooLarge: true, size, error: 'File is too large to open at once; read it in ranges' };
+        }
+        fd = fs.openSync(resolved, 'r');
+        const bytes = readBytes(fd, 0, size);
+        const encoding = detectEncoding(bytes.subarray(0, ENCODING_SAMPLE_BYTES));
+        if (encoding === 'binary') return { success: true, binary: true, encoding, size };
+        return { success: true, content: decodeBytes(bytes.subarray(bomLength(encoding, bytes)), encoding)!, encoding, size };
+    } catch (err: any) {
+        return { success: false, error: err.message };
+    } finally {
+        if (fd !== null) fs.closeSync(fd);
+    }
+}
+
+/**
+ * Up to `maxBytes` (1MB by default, 8MB at most) of a file from `offset`, decoded with
+ * the file's encoding. Ranges are trimmed to whole characters, so reading on from
+ * `nextOffset` never splits one; binary files return the range bounds without content.
+ */
+export function readFileRange(
+    filePath: string,
+    offset = 0,
+    maxBytes = RANGE_DEFAULT_BYTES,
+    workspacePath?: string,
+): { success: boolean; range?: FileRange; protected?: boolean; error?: string } {
+    let fd: number | null = null;
+    try {
+        const resolved = resolveWorkspaceScopedPath(filePath, workspacePath);
+        const denied = protectedFileError(resolved, workspacePath);
+        if (denied) return denied;
+        const stat = fs.statSync(resolved);
+        if (!stat.isFile()) throw new Error('Not a file');
+        fd = fs.openSync(resolved, 'r');
+
+        const sample = readBytes(fd, 0, Math.min(ENCODING_SAMPLE_BYTES, stat.size));
+        const encoding = detectEncoding(sample);
+        const wide = encoding === 'utf-16le' || encoding === 'utf-16be';
+        let start = Math.min(stat.size, Math.max(bomLength(encoding, sample), Math.floor(Number(offset) || 0)));
+        if (wide && start % 2 === 1) start++;
+        const wanted = Math.max(4, Math.min(RANGE_MAX_BYTES, Math.floor(Number(maxBytes) || RANGE_DEFAULT_BYTES)));
+        let bytes = readBytes(fd, start, Math.min(wanted, stat.size - start));
+
+        if (encoding === 'utf-8') {
+            // Skip continuation bytes of a character that began before `offset`.
+            let skip = 0;
+            while (skip < Math.min(3, bytes.length) && (bytes[skip] & 0xc0) === 0x80) skip++;
+            start += skip;
+            bytes = bytes.subarray(skip);
+            if (start + bytes.length < stat.size) bytes = bytes.subarray(0, bytes.length - incompleteUtf8Tail(bytes));
+        } else if (wide && bytes.length % 2 === 1) {
+            bytes = bytes.subarray(0, bytes.length - 1);
+        }
+        const end = start + bytes.length;
+
+        return {
+            success: true,
+            range: {
+                offset: start,
+                length: bytes.length,
+                totalSize: stat.size,
+                nextOffset: end < stat.size ? end : null,
+                encoding,
+                content: decodeBytes(bytes, encoding),
+            },
+        };
     } catch (err: any) {
         return { success: false, error: err.message };
+    } finally {
+        if (fd !== null) fs.closeSync(fd);
     }
 }
 
+const HEX_ROW_BYTES = 16;
+const HEX_DEFAULT_LENGTH = 4096;
+const HEX_MAX_LENGTH = 64 * 1024;
+
+export function formatHexRows(bytes: Buffer, baseOffset: number): HexRow[] {
+    const rows: HexRow[] = [];
+    for (let i = 0; i < bytes.length; i += HEX_ROW_BYTES) {
+        const chunk = bytes.subarray(i, i + HEX_ROW_BYTES);
+        rows.push({
+            offset: baseOffset + i,
+            hex: Array.from(chunk, (b) => b.toString(16).padStart(2, '0')).join(' '),
+            ascii: Array.from(chunk, (b) => (b >= 0x20 && b < 0x7f ? String.fromCharCode(b) : '.')).join(''),
+        });
+    }
+    return rows;
+}
+
+/**
+ * One page of a file as hex and ASCII rows, for inspecting binary or corrupted files.
+ * `length` is capped at 64KB; the offset is aligned down to a 16-byte row.
+ */
+export function readFileHex(
+    filePath: string,
+    offset = 0,
+    length = HEX_DEFAULT_LENGTH,
+    workspacePath?: string,
+): { success: boolean; page?: HexPage; protected?: boolean; error?: string } {
+    let fd: number | null = null;
+    try {
+        const resolved = resolveWorkspaceScopedPath(filePath, workspacePath);
+        const denied = protectedFileError(resolved, workspacePath);
+        if (denied) return denied;
+        const stat = fs.statSync(resolved);
+        if (!stat.isFile()) throw new Error('Not a file');
+
+        const start = Math.max(0, Math.floor((Number(offset) || 0) / HEX_ROW_BYTES) * HEX_ROW_BYTES);
+        const wanted = Math.max(HEX_ROW_BYTES, Math.min(HEX_MAX_LENGTH, Math.floor(Number(length) || HEX_DEFAULT_LENGTH)));
+        const size = Math.max(0, Math.min(wanted, stat.size - start));
+        const buffer = Buffer.alloc(size);
+        fd = fs.openSync(resolved, 'r');
+        const read = size > 0 ? fs.readSync(fd, buffer, 0, size, start) : 0;
+        const end = start + read;
+
+        return {
+            success: true,
+            page: {
+                offset: start,
+                length: read,
+                totalSize: stat.size,
+                nextOffset: end < stat.size ? end : null,
+                rows: formatHexRows(buffer.subarray(0, read), start),
+            },
+        };
+    } catch (err: any) {
+        return { success: false, error: err.message };
+    } finally {
+        if (fd !== null) fs.closeSync(fd);
+    }
+}
+
+/**
+ * Replace `target` by writing a temp file next to it and renaming it over the original,
+ * so a crash leaves either the old or the new content. The original's permission bits
+ * and (where allowed) owner are kept; `mode` applies to new files or overrides them.
+ */
+export function atomicWriteFile(target: string, data: string | Buffer, mode?: number): void {
+    let existing: fs.Stats | null = null;
+    try {
+        existing = fs.statSync(target);
+    } catch { }
+    const temp = path.join(path.dirname(target), `.${path.basename(target)}.${process.pid}.${Date.now()}.tmp`);
+    let fd: number | null = fs.openSync(temp, 'wx', mode ?? (existing ? existing.mode & 0o7777 : 0o666));
+    try {
+        fs.writeSync(fd, typeof data === 'string' ? Buffer.from(data, 'utf-8') : data);
+        fs.fsyncSync(fd);
+        fs.closeSync(fd);
+        fd = null;
+        // openSync's mode is masked by the umask; set the bits explicitly.
+        if (mode !== undefined || existing) fs.chmodSync(temp, mode ?? existing!.mode & 0o7777);
+        if (existing && process.platform !== 'win32') {
+            try {
+                fs.chownSync(temp, existing.uid, existing.gid);
+            } catch { }
+        }
+        fs.renameSync(temp, target);
+    } catch (err) {
+        if (fd !== null) fs.closeSync(fd);
+        fs.rmSync(temp, { force: true });
+        throw err;
+    }
+}
+
+export function fileBackupsDir(): string {
+    const home = homeDir();
+    if (!home) throw new Error('Unable to resolve home directory');
+    return path.join(home, '.codex-wui', 'backups');
+}
+
+function backupPaths(backupId: string): { data: string; meta: string } {
+    if (!/^[\w-]+$/.test(backupId)) throw new Error('Invalid backup id');
+    const dir = fileBackupsDir();
+    return { data: path.join(dir, `${backupId}.bak`), meta: path.join(dir, `${backupId}.json`) };
+}
+
+/**
+ * Write a file atomically (see atomicWriteFile). The file's mtime moves forward as usual,
+ * so watchers and build tools notice the edit. With `createBackup` the previous content
+ * is first copied to `~/.codex-wui/backups` and its id returned for restoreFileBackup.
+ */
 export function writeFile(
     filePath: string,
     content: string,
     workspacePath?: string,
-): { success: boolean; error?: string } {
+    options: { createBackup?: boolean } = {},
+): { success: boolean; backupId?: string; error?: string } {
     try {
         const resolved = resolveWorkspaceScopedPath(filePath, workspacePath);
-        fs.writeFileSync(resolved, content);
-        return { success: true };
+        let backupId: string | undefined;
+        if (options.createBackup && fs.existsSync(resolved)) {
+            backupId = generateId('bak');
+            const backup = backupPaths(backupId);
+            fs.mkdirSync(fileBackupsDir(), { recursive: true });
+            fs.copyFileSync(resolved, backup.data);
+            const meta = { path: resolved, mode: fs.statSync(resolved).mode & 0o7777, createdAt: nowIso() };
+            fs.writeFileSync(backup.meta, JSON.stringify(meta, null, 2));
+        }
+        atomicWriteFile(resolved, content);
+        return { success: true, backupId };
+    } catch (err: any) {
+        return { success: false, error: err.message };
+    }
+}
+
+/** Put a backup made by writeFile back in place, with its original permissions. */
+export function restoreFileBackup(
+    backupId: string,
+    workspacePath?: string,
+): { success: boolean; path?: string; error?: string } {
+    try {
+        const backup = backupPaths(backupId);
+        if (!fs.existsSync(backup.meta)) return { success: false, error: 'Backup not found' };
+        const meta = JSON.parse(fs.readFileSync(backup.meta, 'utf-8'));
+        const target = resolveWorkspaceScopedPath(meta.path, workspacePath);
+        atomicWriteFile(target, fs.readFileSync(backup.data), typeof meta.mode === 'number' ? meta.mode : undefined);
+        return { success: true, path: target };
     } catch (err: any) {
         return { success: false, error: err.message };
     }
 }
 
+function describeEntry(fullPath: string, followSymlinks: boolean): DirectoryEntry {
+    const name = path.basename(fullPath);
+    const lstat = fs.lstatSync(fullPath);
+    const isLink = lstat.isSymbolicLink();
+    let stat = lstat;
+    let brokenLink = false;
+    let symlinkTarget: string | null = null;
+    if (isLink) {
+        try {
+            symlinkTarget = fs.readlinkSync(fullPath);
+        } catch { }
+        if (followSymlinks) {
+            try {
+                stat = fs.statSync(fullPath);
+            } catch {
+                brokenLink = true;
+            }
+        }
+    }
+    const mode = stat.mode & 0o777;
+    return {
+        name,
+        path: fullPath,
+        isDirectory: stat.isDirectory(),
+        size: stat.isDirectory() ? 0 : stat.size,
+        fileType: isLink ? 'symlink' : lstat.isDirectory() ? 'directory' : lstat.isFile() ? 'file' : 'other',
+        symlinkTarget,
+        brokenLink,
+        isHidden: name.startsWith('.'),
+        mode,
+        readonly: (mode & 0o200) === 0,
+        modifiedAt: stat.mtime.toISOString(),
+    };
+}
+
+/**
+ * List a workspace directory. With `followSymlinks` (the default) a symlink reports its
+ * target's size, mode and type; otherwise the link itself is described.
+ */
 export function listDirectory(
     dirPath: string,
     workspacePath?: string,
+    options: { followSymlinks?: boolean } = {},
 ): { success: boolean; entries?: DirectoryEntry[]; error?: string } {
     try {
         const resolved = resolveWorkspaceScopedPath(dirPath, workspacePath);
-        const entries = fs.readdirSync(resolved, { withFileTypes: true }).map((entry) => {
-            const fullPath = path.join(resolved, entry.name);
-            let size = 0;
+        const followSymlinks = options.followSymlinks !== false;
+        const entries: DirectoryEntry[] = [];
+        for (const name of fs.readdirSync(resolved)) {
             try {
-                size = fs.statSync(fullPath).size;
+                entries.push(describeEntry(path.join(resolved, name), followSymlinks));
             } catch { }
-            return {
-                name: entry.name,
-                path: fullPath,
-                isDirectory: entry.isDirectory(),
-                size,
-            };
-        });
+        }
         return { success: true, entries };
     } catch (err: any) {
         return { success: false, error: err.message };
@@ -152,40 +976,164 @@ export function fileExists(filePath: string, workspacePath?: string): boolean {
     }
 }
 
-export async function webSearch(
-    query: string,
-): Promise<{ success: boolean; results: SearchResult[]; error?: string }> {
+// ===== File Management =====
+
+/**
+ * Like resolveWorkspaceScopedPath, but a symlink resolves to the link itself rather than
+ * its target, so renaming or deleting a link never touches what it points to. The
+ * workspace root itself is refused.
+ */
+function resolveWorkspaceEntry(rawPath: string, workspacePath?: string): string {
+    const workspaceRoot = canonicalizeWorkspaceRoot(workspacePath);
+    const target = path.isAbsolute(rawPath) ? rawPath : path.join(workspaceRoot, rawPath);
+    const parent = resolveWorkspaceScopedPath(path.dirname(target), workspacePath);
+    const resolved = path.join(parent, path.basename(target));
+    if (resolved === workspaceRoot || path.basename(target) === '..') {
+        throw new Error('Cannot modify the workspace root');
+    }
+    return resolved;
+}
+
+function pathExists(target: string): boolean {
     try {
-        const url = `https://api.duckduckgo.com/?q=${encodeURIComponent(query)}&format=json&no_html=1`;
-        const response = await fetch(url);
-        const data = await response.json();
+        fs.lstatSync(target);
+        return true;
+    } catch {
+        return false;
+    }
+}
 
-        const results: SearchResult[] = [];
+function ensureNotInside(source: string, destination: string): void {
+    const relative = path.relative(source, destination);
+    if (relative === '' || (!relative.startsWith('..') && !path.isAbsolute(relative))) {
+        throw new Error('Cannot copy or move a directory into itself');
+    }
+}
 
-        const abs = data.Abstract;
-        if (abs) {
-            results.push({
-                title: data.Heading || query,
-                url: data.AbstractURL || '',
-                snippet: abs,
-            });
+export function createDirectory(
+    dirPath: string,
+    workspacePath?: string,
+): { success: boolean; path?: string; error?: string } {
+    try {
+        const workspaceRoot = canonicalizeWorkspaceRoot(workspacePath);
+        const target = path.resolve(workspaceRoot, expandTildePath(dirPath));
+        const relative = path.relative(workspaceRoot, target);
+        if (relative.startsWith('..') || path.isAbsolute(relative)) throw new Error('Path is outside workspace root');
+        if (pathExists(target)) throw new Error('A file or directory with that name already exists');
+        // Parents are created as needed, so check the deepest existing ancestor stays inside.
+        let existing = path.dirname(target);
+        while (!fs.existsSync(existing)) existing = path.dirname(existing);
+        resolveWorkspaceScopedPath(existing, workspacePath);
+        fs.mkdirSync(target, { recursive: true });
+        return { success: true, path: target };
+    } catch (err: any) {
+        return { success: false, error: err.message };
+    }
+}
+
+/**
+ * Delete a file, link or directory. A non-empty directory needs `recursive`; with
+ * `toTrash` the entry goes to the system trash instead of being removed.
+ */
+export async function deletePath(
+    targetPath: string,
+    workspacePath?: string,
+    options: { recursive?: boolean; toTrash?: boolean } = {},
+): Promise<{ success: boolean; path?: string; error?: string }> {
+    try {
+        const target = resolveWorkspaceEntry(targetPath, workspacePath);
+        const stats = fs.lstatSync(target);
+        if (stats.isDirectory() && !options.recursive && fs.readdirSync(target).length > 0) {
+            return { success: false, error: 'Directory is not empty' };
         }
+        if (options.toTrash) {
+            await shell.trashItem(target);
+        } else {
+            fs.rmSync(target, { recursive: stats.isDirectory() });
+        }
+        return { success: true, path: target };
+    } catch (err: any) {
+        return { success: false, error: err.message };
+    }
+}
 
-        if (Array.isArray(data.RelatedTopics)) {
-            for (const topic of data.RelatedTopics.slice(0, 5)) {
-                if (topic.Text && topic.FirstURL) {
-                    results.push({
-                        title: topic.Text.split(' - ')[0] || topic.Text,
-                        url: topic.FirstURL,
-                        snippet: topic.Text,
-                    });
-                }
-            }
+/** Give an entry a new name in the same directory. */
+export function renamePath(
+    targetPath: string,
+    newName: string,
+    workspacePath?: string,
+): { success: boolean; path?: string; error?: string } {
+    const name = (newName || '').trim();
+    if (!name || name === '.' || name === '..' || /[\\/]/.test(name)) {
+        return { success: false, error: 'Invalid name' };
+    }
+    try {
+        const source = resolveWorkspaceEntry(targetPath, workspacePath);
+        return movePath(source, path.join(path.dirname(source), name), workspacePath);
+    } catch (err: any) {
+        return { success: false, error: err.message };
+    }
+}
+
+/**
+ * Copy a file or directory to `destinationPath` (the full new path). Symlinks are copied
+ * as links. An existing destination is only replaced with `overwrite`.
+ */
+export function copyPath(
+    sourcePath: string,
+    destinationPath: string,
+    workspacePath?: string,
+    overwrite = false,
+): { success: boolean; path?: string; error?: string } {
+    try {
+        const source = resolveWorkspaceEntry(sourcePath, workspacePath);
+        const destination = resolveWorkspaceEntry(destinationPath, workspacePath);
+        if (!pathExists(source)) throw new Error('Source does not exist');
+        if (fs.lstatSync(source).isDirectory()) ensureNotInside(source, destination);
+        if (pathExists(destination)) {
+            if (!overwrite) throw new Error('Destination already exists');
+            fs.rmSync(destination, { recursive: true });
         }
+        fs.cpSync(source, destination, { recursive: true, verbatimSymlinks: true, errorOnExist: true, force: false });
+        return { success: true, path: destination };
+    } catch (err: any) {
+        return { success: false, error: err.message };
+    }
+}
 
-        return { success: true, results };
+/**
+ * Move a file or directory to `destinationPath` (the full new path), copying and removing
+ * the original when it lives on another device. An existing destination is only replaced
+ * with `overwrite`.
+ */
+export function movePath(
+    sourcePath: string,
+    destinationPath: string,
+    workspacePath?: string,
+    overwrite = false,
+): { success: boolean; path?: string; error?: string } {
+    try {
+        const source = resolveWorkspaceEntry(sourcePath, workspacePath);
+        const destination = resolveWorkspaceEntry(destinationPath, workspacePath);
+        if (!pathExists(source)) throw new Error('Source does not exist');
+        if (source === destination) return { success: true, path: destination };
+        if (fs.lstatSync(source).isDirectory()) ensureNotInside(source, destination);
+        // Case-only renames on case-insensitive filesystems see the source as the destination.
+        const sameEntry = pathExists(destination) && source.toLowerCase() === destination.toLowerCase();
+        if (pathExists(destination) && !sameEntry) {
+            if (!overwrite) throw new Error('Destination already exists');
+            fs.rmSync(destination, { recursive: true });
+        }
+        try {
+            fs.renameSync(source, destination);
+        } catch (err: any) {
+            if (err.code !== 'EXDEV') throw err;
+            fs.cpSync(source, destination, { recursive: true, verbatimSymlinks: true, errorOnExist: true, force: false });
+            fs.rmSync(source, { recursive: true });
+        }
+        return { success: true, path: destination };
     } catch (err: any) {
-        return { success: false, results: [], error: err.message };
+        return { success: false, error: err.message };
     }
 }
 
@@ -202,7 +1150,7 @@ export function openInEditor(
 
     for (const ed of editorsToTry) {
         try {
-            spawn(ed, [expandedPath], { detached: true, stdio: 'ignore' }).unref();
+            trackChild(spawn(ed, [expandedPath], { detached: true, stdio: 'ignore' })).unref();
             return { success: true, editor: ed };
         } catch { }
     }
@@ -210,7 +1158,7 @@ export function openInEditor(
     // Fallback: system open
     try {
         const openCmd = process.platform === 'darwin' ? 'open' : process.platform === 'linux' ? 'xdg-open' : 'start';
-        spawn(openCmd, [expandedPath], { detached: true, stdio: 'ignore' }).unref();
+        trackChild(spawn(openCmd, [expandedPath], { detached: true, stdio: 'ignore' })).unref();
         return { success: true, editor: 'system' };
     } catch (err: any) {
         return { success: false, error: err.message };
@@ -0,0 +1,238 @@
+import { spawn } from 'child_process';
+import { commandSpawnOptions, expandTildePath, trackChild } from './utils';
+
+export interface GitStatusEntry {
+    path: string;
+    /** Previous path for renames and copies. */
+    oldPath: string | null;
+    /** Porcelain status letter (M, A, D, R, C, U, ?). */
+    status: string;
+}
+
+export interface GitStatus {
+    success: boolean;
+    branch: string | null;
+    staged: GitStatusEntry[];
+    unstaged: GitStatusEntry[];
+    untracked: string[];
+    conflicted: string[];
+    error?: string;
+}
+
+export interface GitDiffLine {
+    type: 'context' | 'add' | 'del';
+    content: string;
+    oldLine: number | null;
+    newLine: number | null;
+}
+
+export interface GitDiffHunk {
+    header: string;
+    oldStart: number;
+    oldLines: number;
+    newStart: number;
+    newLines: number;
+    lines: GitDiffLine[];
+}
+
+export interface GitFileDiff {
+    path: string;
+    oldPath: string | null;
+    staged: boolean;
+    binary: boolean;
+    hunks: GitDiffHunk[];
+}
+
+export interface GitCommit {
+    hash: string;
+    shortHash: string;
+    author: string;
+    email: string;
+    timestamp: number;
+    subject: string;
+}
+
+const GIT_MAX_OUTPUT = 20 * 1024 * 1024;
+
+export function runGit(
+    workspacePath: string,
+    args: string[],
+): Promise<{ success: boolean; stdout: string; error?: string }> {
+    return new Promise((resolve) => {
+        const cwd = expandTildePath(workspacePath);
+        const child = trackChild(spawn('git', args, { ...commandSpawnOptions(cwd), stdio: ['ignore', 'pipe', 'pipe'] }));
+        let stdout = '';
+        let stderr = '';
+        child.stdout?.on('data', (d: Buffer) => {
+            if (stdout.length < GIT_MAX_OUTPUT) stdout += d.toString();
+        });
+        child.stderr?.on('data', (d: Buffer) => (stderr += d.toString()));
+        child.on('error', (err: any) => {
+            const msg = err.code === 'ENOENT' ? 'git executable was not found.' : err.message;
+            resolve({ success: false, stdout: '', error: msg });
+        });
+        child.on('close', (code) => {
+            if (code === 0) resolve({ success: true, stdout });
+            else resolve({ success: false, stdout, error: stderr.trim() || `git exited with ${code}` });
+        });
+    });
+}
+
+/**
+ * Parse `git status --porcelain=v1 -z --branch`. Entries are NUL separated and a rename
+ * is followed by an extra entry holding the original path.
+ */
+export function parseGitStatus(output: string): Omit<GitStatus, 'success' | 'error'> {
+    const result: Omit<GitStatus, 'success' | 'error'> = {
+        branch: null,
+        staged: [],
+        unstaged: [],
+        untracked: [],
+        conflicted: [],
+    };
+    const entries = output.split('\0');
+
+    for (let i = 0; i < entries.length; i++) {
+        const entry = entries[i];
+        if (!entry) continue;
+        if (entry.startsWith('## ')) {
+            const head = entry.slice(3);
+            result.branch = head.startsWith('No commits yet on ')
+                ? head.slice('No commits yet on '.length)
+                : head.split('...')[0].split(' ')[0];
+            continue;
+        }
+
+        const x = entry[0];
+        const y = entry[1];
+        const filePath = entry.slice(3);
+        const oldPath = x === 'R' || x === 'C' ? entries[++i] || null : null;
+
+        if (x === '?' && y === '?') {
+            result.untracked.push(filePath);
+        } else if (x === 'U' || y === 'U' || (x === 'A' && y === 'A') || (x === 'D' && y === 'D')) {
+            result.conflicted.push(filePath);
+        } else {
+            if (x !== ' ' && x !== '!') result.staged.push({ path: filePath, oldPath, status: x });
+            if (y !== ' ' && y !== '!') result.unstaged.push({ path: filePath, oldPath: null, status: y });
+        }
+    }
+
+    return result;
+}
+
+/**
+ * Parse unified diff output (`git diff --no-color --no-ext-diff`) into files and hunks.
+ */
+export function parseUnifiedDiff(output: string, staged: boolean): GitFileDiff[] {
+    const files: GitFileDiff[] = [];
+    let file: GitFileDiff | null = null;
+    let hunk: GitDiffHunk | null = null;
+    let oldLine = 0;
+    let newLine = 0;
+
+    for (const line of output.split('\n')) {
+        const header = line.match(/^diff --git a\/(.*) b\/(.*)$/);
+        if (header) {
+            file = { path: header[2], oldPath: null, staged, binary: false, hunks: [] };
+            files.push(file);
+            hunk = null;
+            continue;
+        }
+        if (!file) continue;
+
+        if (!hunk) {
+            if (line.startsWith('rename from ')) file.oldPath = line.slice('rename from '.length);
+            else if (line.startsWith('Binary files ')) file.binary = true;
+        }
+
+        const range = line.match(/^@@ -(\d+)(?:,(\d+))? \+(\d+)(?:,(\d+))? @@/);
+        if (range) {
+            hunk = {
+                header: line,
+                oldStart: Number(range[1]),
+                oldLines: range[2] === undefined ? 1 : Number(range[2]),
+                newStart: Number(range[3]),
+                newLines: range[4] === undefined ? 1 : Number(range[4]),
+                lines: [],
+            };
+            file.hunks.push(hunk);
+            oldLine = hunk.oldStart;
+            newLine = hunk.newStart;
+            continue;
+        }
+        if (!hunk) continue;
+
+        if (line.startsWith('+')) {
+            hunk.lines.push({ type: 'add', content: line.slice(1), oldLine: null, newLine: newLine++ });
+        } else if (line.startsWith('-')) {
+            hunk.lines.push({ type: 'del', content: line.slice(1), oldLine: oldLine++, newLine: null });
+        } else if (line.startsWith(' ')) {
+            hunk.lines.push({ type: 'context', content: line.slice(1), oldLine: oldLine++, newLine: newLine++ });
+        }
+        // "\ No newline at end of file" and trailing blank lines are skipped.
+    }
+
+    return files;
+}
+
+export function parseGitLog(output: string): GitCommit[] {
+    return output
+        .split('\x1e')
+        .map((record) => record.trim())
+        .filter(Boolean)
+        .map((record) => {
+            const [hash, shortHash, author, email, timestamp, subject] = record.split('\x1f');
+            return { hash, shortHash, author, email, timestamp: Number(timestamp), subject: subject || '' };
+        });
+}
+
+export async function gitStatus(workspacePath: string): Promise<GitStatus> {
+    const result = await runGit(workspacePath, ['status', '--porcelain=v1', '-z', '--branch']);
+    if (!result.success) {
+        return { success: false, branch: null, staged: [], unstaged: [], untracked: [], conflicted: [], error: result.error };
+    }
+    return { success: true, ...parseGitStatus(result.stdout) };
+}
+
+/**
+ * Staged and unstaged changes, optionally limited to one file.
+ */
+export async function gitDiff(
+    workspacePath: string,
+    file?: string,
+): Promise<{ success: boolean; files: GitFileDiff[]; error?: string }> {
+    const base = ['diff', '--no-color', '--no-ext-diff', '-M'];
+    const pathspec = file ? ['--', file] : [];
+    const [staged, unstaged] = await Promise.all([
+        runGit(workspacePath, [...base, '--cached', ...pathspec]),
+        runGit(workspacePath, [...base, ...pathspec]),
+    ]);
+    if (!staged.success || !unstaged.success) {
+        return { success: false, files: [], error: staged.error || unstaged.error };
+    }
+    return {
+        success: true,
+        files: [...parseUnifiedDiff(staged.stdout, true), ...parseUnifiedDiff(unstaged.stdout, false)],
+    };
+}
+
+export async function gitLog(
+    workspacePath: string,
+    limit = 50,
+    since?: string,
+): Promise<{ success: boolean; commits: GitCommit[]; error?: string }> {
+    const count = Math.max(1, Math.min(Math.floor(limit) || 50, 1000));
+    const result = await runGit(workspacePath, [
+        'log',
+        `-n${count}`,
+        ...(since ? [`--since=${since}`] : []),
+        '--format=%H%x1f%h%x1f%an%x1f%ae%x1f%at%x1f%s%x1e',
+    ]);
+    if (!result.success) {
+        // A repository without commits has no log; that is not an error for the viewer.
+        if (/does not have any commits/.test(result.error || '')) return { success: true, commits: [] };
+        return { success: false, commits: [], error: result.error };
+    }
+    return { success: true, commits: parseGitLog(result.stdout) };
+}
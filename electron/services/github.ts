@@ -0,0 +1,152 @@
+import Database from 'better-sqlite3';
+import { BrowserWindow } from 'electron';
+import { dbGetSetting, dbSetSetting } from './db';
+import { runGit } from './git';
+import { requirePermission } from './permissions';
+import { getSecret, GITHUB_TOKEN_SECRET, rememberSecret } from './secrets';
+import { expandTildePath } from './utils';
+
+const SETTINGS_KEY = 'github.settings';
+const DEFAULT_API_URL = 'https://api.github.com';
+
+export interface GitHubSettings {
+    /** Personal access token with `repo` (or pull-request write) scope. */
+    token: string;
+    /** REST endpoint; GitHub Enterprise servers use `https://<host>/api/v3`. */
+    apiUrl: string;
+}
+
+export interface GitHubRepo {
+    owner: string;
+    repo: string;
+}
+
+export interface CreatePrResult {
+    success: boolean;
+    branch?: string;
+    url?: string;
+    number?: number;
+    error?: string;
+}
+
+/** The token is read from the keychain when it was saved there. */
+export function getGitHubSettings(db: Database.Database): GitHubSettings {
+    const stored = { token: '', apiUrl: DEFAULT_API_URL, ...dbGetSetting<Partial<GitHubSettings>>(db, SETTINGS_KEY, {}) };
+    return { ...stored, token: stored.token || getSecret(GITHUB_TOKEN_SECRET) || '' };
+}
+
+export function setGitHubSettings(db: Database.Database, settings: Partial<GitHubSettings>): GitHubSettings {
+    const merged = { ...getGitHubSettings(db), ...settings };
+    const inKeychain = settings.token !== undefined && rememberSecret(GITHUB_TOKEN_SECRET, merged.token);
+    dbSetSetting(db, SETTINGS_KEY, inKeychain ? { ...merged, token: '' } : merged);
+    return merged;
+}
+
+/**
+ * Owner and repository of a GitHub remote URL, for https (`https://github.com/o/r.git`),
+ * scp-style ssh (`git@github.com:o/r.git`) and `ssh://` forms. Null for anything else.
+ */
+export function parseGitHubRemote(url: string): GitHubRepo | null {
+    const match = url.trim().match(/^(?:https?:\/\/(?:[^@/]+@)?|ssh:\/\/(?:[^@/]+@)?|[^@/\s]+@)[^/:]+[:/](?:\d+\/)?([^/\s]+)\/([^/\s]+?)(?:\.git)?\/?$/);
+    return match ? { owner: match[1], repo: match[2] } : null;
+}
+
+/** A fresh branch name for the changes, e.g. `codex/fix-login-redirect-k3x9`. */
+export function prBranchName(title: string, suffix = Date.now().toString(36).slice(-4)): string {
+    const slug = title
+        .toLowerCase()
+        .replace(/[^a-z0-9]+/g, '-')
+        .replace(/^-+|-+$/g, '')
+        .slice(0, 40)
+        .replace(/-+$/, '');
+    return `codex/${slug || 'changes'}-${suffix}`;
+}
+
+async function git(cwd: string, args: string[]): Promise<string> {
+    const result = await runGit(cwd, args);
+    if (!result.success) throw new Error(result.error);
+    return result.stdout.trim();
+}
+
+/** The branch `origin/HEAD` points at, else the current branch. */
+async function defaultBase(cwd: string): Promise<string> {
+    const remoteHead = await runGit(cwd, ['symbolic-ref', '--short', 'refs/remotes/origin/HEAD']);
+    if (remoteHead.success && remoteHead.stdout.trim()) return remoteHead.stdout.trim().replace(/^origin\//, '');
+    return git(cwd, ['rev-parse', '--abbrev-ref', 'HEAD']);
+}
+
+/**
+ * Commit the workspace's working-tree changes on a new branch, push it to `origin` and
+ * open a pull request against `base` (origin's default branch when omitted). Pushing uses
+ * the user's git credentials; the API call uses the token from the GitHub settings. If
+ * anything fails before the commit, the original branch is checked out again; after it,
+ * the branch is kept so the work is not lost.
+ */
+export async function createPrFromChanges(
+    db: Database.Database,
+    window: BrowserWindow | null,
+    workspaceId: string,
+    title: string,
+    body = '',
+    base?: string,
+): Promise<CreatePrResult> {
+    if (!title?.trim()) return { success: false, error: 'A pull request title is required' };
+    const workspace = db.prepare('SELECT path FROM workspaces WHERE id = ?').get(workspaceId) as any;
+    if (!workspace) return { success: false, error: 'Workspace not found' };
+    const cwd = expandTildePath(workspace.path);
+    const settings = getGitHubSettings(db);
+    if (!settings.token) return { success: false, error: 'GitHub token is not set' };
+
+    const denied = await requirePermission(db, window, cwd, 'write');
+    if (denied) return denied;
+
+    let originalBranch: string;
+    let repo: GitHubRepo | null;
+    let baseBranch: string;
+    try {
+        repo = parseGitHubRemote(await git(cwd, ['remote', 'get-url', 'origin']));
+        if (!repo) return { success: false, error: 'origin is not a GitHub repository' };
+        if (!(await git(cwd, ['status', '--porcelain']))) return { success: false, error: 'There are no changes to commit' };
+        originalBranch = await git(cwd, ['rev-parse', '--abbrev-ref', 'HEAD']);
+        baseBranch = base?.trim() || (await defaultBase(cwd));
+    } catch (err: any) {
+        return { success: false, error: err.message };
+    }
+
+    const branch = prBranchName(title);
+    try {
+        await git(cwd, ['checkout', '-b', branch]);
+    } catch (err: any) {
+        return { success: false, error: err.message };
+    }
+    try {
+        await git(cwd, ['add', '-A']);
+        await git(cwd, ['commit', '-m', title.trim(), ...(body.trim() ? ['-m', body.trim()] : [])]);
+    } catch (err: any) {
+        await runGit(cwd, ['reset']);
+        await runGit(cwd, ['checkout', originalBranch]);
+        await runGit(cwd, ['branch', '-D', branch]);
+        return { success: false, error: err.message };
+    }
+
+    try {
+        await git(cwd, ['push', '-u', 'origin', branch]);
+        const apiUrl = (settings.apiUrl || DEFAULT_API_URL).replace(/\/+$/, '');
+        const response = await fetch(`${apiUrl}/repos/${repo.owner}/${repo.repo}/pulls`, {
+            method: 'POST',
+            headers: {
+                Accept: 'application/vnd.github+json',
+                Authorization: `Bearer ${settings.token}`,
+                'Content-Type': 'application/json',
+            },
+            body: JSON.stringify({ title: title.trim(), body, head: branch, base: baseBranch }),
+        });
+        if (!response.ok) {
+            return { success: false, branch, error: `HTTP ${response.status}: ${await response.text()}` };
+        }
+        const data: any = await response.json();
+        return { success: true, branch, url: data.html_url, number: data.number };
+    } catch (err: any) {
+        return { success: false, branch, error: err.message };
+    }
+}
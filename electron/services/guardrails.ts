@@ -0,0 +1,235 @@
+import Database from 'better-sqlite3';
+import * as fs from 'fs';
+import * as path from 'path';
+import { dbGetConversationOverrides, dbGetWorkspaceCliOptions } from './db';
+import { RuntimeConfig } from './models';
+import { expandTildePath, resolveRuntimeConfig } from './utils';
+
+export interface OutsidePathReference {
+    reference: string;
+    resolved: string;
+}
+
+// Absolute unix paths, ~ paths, Windows drive paths and ../ escapes. A path must start a
+// word, so the "//host" part of a URL never matches.
+const PATH_TOKEN = /(?:^|[\s"'`(=])((?:~|\.\.)(?:[\/\\][^\s"'`)]*)?|\/[^\s"'`)]+|[A-Za-z]:[\/\\][^\s"'`)]*)/g;
+
+/**
+ * Paths mentioned in a prompt that resolve outside the workspace root. The sandbox keeps
+ * codex from writing there in workspace-write mode, but a request aimed at such a path
+ * usually means the turn will fail or do something unintended.
+ */
+export function findOutsidePathReferences(prompt: string, workspaceRoot: string): OutsidePathReference[] {
+    const root = path.resolve(expandTildePath(workspaceRoot));
+    const seen = new Set<string>();
+    const found: OutsidePathReference[] = [];
+
+    for (const match of prompt.matchAll(PATH_TOKEN)) {
+        const reference = match[1].replace(/[.,;:!?]+$/, '');
+        if (!reference || reference === '/' || seen.has(reference)) continue;
+        seen.add(reference);
+
+        const expanded = expandTildePath(reference);
+        const resolved = path.isAbsolute(expanded) || /^[A-Za-z]:[\/\\]/.test(expanded)
+            ? path.resolve(expanded)
+            : path.resolve(root, expanded);
+        const relative = path.relative(root, resolved);
+        if (relative.startsWith('..') || path.isAbsolute(relative)) {
+            found.push({ reference, resolved });
+        }
+    }
+
+    return found;
+}
+
+/**
+ * Only meaningful for the workspace-write sandbox: read-only cannot write anywhere and
+ * full access / yolo mode intentionally allow it.
+ */
+export function checkPromptPaths(
+    prompt: string,
+    cfg: RuntimeConfig,
+    workspacePath?: string,
+): { applies: boolean; paths: OutsidePathReference[] } {
+    const applies = !cfg.yoloMode && cfg.cliOptions.sandbox === 'workspace-write';
+    if (!applies) return { applies, paths: [] };
+    const root = workspacePath || cfg.cliOptions.cwdOverride.trim() || cfg.cwd;
+    return { applies, paths: findOutsidePathReferences(prompt, root) };
+}
+
+// ===== Prompt Lint =====
+
+export type PromptIssueKind = 'missing-file' | 'ambiguous-reference' | 'sandbox-forbidden' | 'broad-scope';
+
+export interface PromptIssue {
+    kind: PromptIssueKind;
+    severity: 'warning' | 'info';
+    message: string;
+    suggestion: string;
+    /** Character range of the flagged text in the prompt, for inline highlighting. */
+    start: number;
+    end: number;
+}
+
+/** Idle time after which "it" or "that" probably no longer points at what the user means. */
+const AMBIGUOUS_GAP_SECONDS = 6 * 60 * 60;
+
+// Relative file paths with a directory and an extension (`src/app.ts`, `./lib/util.rs`),
+// or a bare file name in backticks (`main.ts`). "and/or" and URLs do not match.
+const RELATIVE_FILE = /(?:^|[\s"'`(=])((?:\.\/)?(?:[\w@.-]+\/)+[\w@-][\w@.-]*\.[A-Za-z]\w{0,7})|`([\w@-][\w@.-]*\.[A-Za-z]\w{0,7})`/g;
+
+// A pronoun that stands alone: at the end of a clause or followed by an adverb or a
+// preposition, so "fix it" counts but "fix this bug" does not.
+const LONE_PRONOUN = /\b(it|that|this|them|those|these|the same)\b(?=\s*(?:$|[.,!?;:]|(?:again|too|also|instead|now|back|up|out|please|to|with|for|in|on|like)\b))/gi;
+
+const CLAUSE_START = String.raw`(?:^|[.!?;\n]\s*|\b(?:please|and|then|also|can you|could you)\s+)`;
+
+const WRITE_ACTION = new RegExp(
+    `${CLAUSE_START}(write|create|edit|modify|change|update|fix|refactor|rename|delete|remove|implement|add|commit|apply)\\b`,
+    'gi',
+);
+
+const NETWORK_ACTION = /\b(?:(?:npm|pnpm|yarn|pip3?|cargo|gem|go|brew|apt(?:-get)?)\s+(?:install|add|get|update|upgrade)|curl|wget|git\s+(?:push|pull|clone|fetch)|download)\b/gi;
+
+const BROAD_SCOPE = [
+    /\b(?:fix|improve|refactor|clean up|update|rewrite|optimi[sz]e)\s+(?:everything|all of it|anything)\b/gi,
+    /\b(?:fix|resolve)\s+all\s+(?:the\s+)?(?:bugs|issues|errors|problems|warnings|tests)\b/gi,
+    /\b(?:the\s+)?(?:entire|whole)\s+(?:codebase|code base|repo|repository|project|app)\b/gi,
+    /\bmake\s+(?:it|everything|the code)\s+better\b/gi,
+];
+
+function issueAt(
+    match: RegExpMatchArray,
+    text: string,
+    issue: Omit<PromptIssue, 'start' | 'end'>,
+): PromptIssue {
+    const start = (match.index ?? 0) + match[0].lastIndexOf(text);
+    return { ...issue, start, end: start + text.length };
+}
+
+/**
+ * Check a prompt for things that commonly derail a turn: files that do not exist under
+ * `root`, pronouns with nothing to refer to, actions the sandbox will refuse and requests
+ * too broad to finish. `lastActivityAt` is the conversation's last activity in unix
+ * seconds, or null for a new conversation. Heuristic by design; the issues are hints.
+ */
+export function lintPrompt(
+    prompt: string,
+    cfg: RuntimeConfig,
+    root: string,
+    lastActivityAt: number | null,
+    now = Math.floor(Date.now() / 1000),
+    exists: (file: string) => boolean = fs.existsSync,
+): PromptIssue[] {
+    const issues: PromptIssue[] = [];
+    const workspaceRoot = path.resolve(expandTildePath(root));
+
+    const seen = new Set<string>();
+    for (const match of prompt.matchAll(RELATIVE_FILE)) {
+        const reference = (match[1] ?? match[2]).replace(/[.,;:!?]+$/, '');
+        if (seen.has(reference) || reference.startsWith('../')) continue;
+        seen.add(reference);
+        if (exists(path.resolve(workspaceRoot, reference))) continue;
+        issues.push(issueAt(match, reference, {
+            kind: 'missing-file',
+            severity: 'warning',
+            message: `${reference} does not exist in the workspace`,
+            suggestion: 'Check the path, or say that the file should be created.',
+        }));
+    }
+
+    const gap = lastActivityAt === null ? null : now - lastActivityAt;
+    if (gap === null || gap > AMBIGUOUS_GAP_SECONDS) {
+        const match = prompt.matchAll(LONE_PRONOUN).next().value;
+        if (match) {
+            issues.push(issueAt(match, match[1], {
+                kind: 'ambiguous-reference',
+                severity: 'info',
+                message: gap === null
+                    ? `"${match[1]}" has nothing to refer to in a new conversation`
+                    : `"${match[1]}" may be unclear after ${Math.floor(gap / 3600)} hours without activity`,
+                suggestion: 'Name the file, function or error you mean.',
+            }));
+        }
+    }
+
+    if (!cfg.yoloMode) {
+        const sandbox = cfg.cliOptions.sandbox;
+        if (sandbox === 'read-only') {
+            const match = prompt.matchAll(WRITE_ACTION).next().value;
+            if (match) {
+                issues.push(issueAt(match, match[1], {
+                    kind: 'sandbox-forbidden',
+                    severity: 'warning',
+                    message: `"${match[1]}" needs file changes, but the sandbox is read-only`,
+                    suggestion: 'Switch the sandbox to workspace-write, or ask for a plan instead.',
+                }));
+            }
+        }
+        if (sandbox !== 'danger-full-access') {
+            const match = prompt.matchAll(NETWORK_ACTION).next().value;
+            if (match) {
+                issues.push(issueAt(match, match[0], {
+                    kind: 'sandbox-forbidden',
+                    severity: 'warning',
+                    message: `"${match[0]}" needs network access, which the ${sandbox} sandbox blocks`,
+                    suggestion: 'Run it yourself first, or use full access for this turn.',
+                }));
+            }
+        }
+        if (sandbox === 'workspace-write') {
+            for (const outside of findOutsidePathReferences(prompt, workspaceRoot)) {
+                const start = prompt.indexOf(outside.reference);
+                issues.push({
+                    kind: 'sandbox-forbidden',
+                    severity: 'warning',
+                    message: `${outside.reference} is outside the workspace, where the sandbox cannot write`,
+                    suggestion: 'Copy the file into the workspace, or only ask codex to read it.',
+                    start,
+                    end: start + outside.reference.length,
+                });
+            }
+        }
+    }
+
+    for (const pattern of BROAD_SCOPE) {
+        for (const match of prompt.matchAll(pattern)) {
+            issues.push(issueAt(match, match[0], {
+                kind: 'broad-scope',
+                severity: 'info',
+                message: `"${match[0]}" is very broad; the turn may run long and touch unrelated code`,
+                suggestion: 'Narrow it to a directory, a failing test or a specific error.',
+            }));
+        }
+    }
+
+    return issues.sort((a, b) => a.start - b.start);
+}
+
+/**
+ * Lint a prompt against the workspace's effective settings before it is sent. With a
+ * conversation, its CLI overrides, working directory and last activity are used too.
+ */
+export function analyzePrompt(
+    db: Database.Database,
+    cfg: RuntimeConfig,
+    text: string,
+    workspaceId: string,
+    conversationId?: string,
+): { success: boolean; issues: PromptIssue[]; error?: string } {
+    const workspace = db.prepare('SELECT path FROM workspaces WHERE id = ?').get(workspaceId) as any;
+    if (!workspace) return { success: false, issues: [], error: 'Workspace not found' };
+
+    const overrides = conversationId ? dbGetConversationOverrides(db, conversationId) : {};
+    const runConfig = resolveRuntimeConfig(
+        cfg,
+        overrides.cliOverrides,
+        overrides.workspaceCliOptions ?? dbGetWorkspaceCliOptions(db, workspaceId),
+    );
+    const activity = conversationId
+        ? db.prepare('SELECT COALESCE(last_activity_at, updated_at) AS at FROM conversations WHERE id = ?').get(conversationId) as any
+        : null;
+    const lastActivityAt = activity?.at ? Number(activity.at) : null;
+    const root = overrides.cwd || runConfig.cliOptions.cwdOverride.trim() || workspace.path;
+    return { success: true, issues: lintPrompt(text, runConfig, root, lastActivityAt) };
+}
@@ -0,0 +1,197 @@
+import Database from 'better-sqlite3';
+import { AppState, RuntimeConfig } from './models';
+import { dbGetMessages, dbGetSetting, dbSetSetting } from './db';
+import { estimateTokens } from './utils';
+
+const SETTINGS_KEY = 'history.settings';
+/** Per pasted message, for the `User:` / `Assistant:` prefix and line breaks. */
+const MESSAGE_OVERHEAD_TOKENS = 4;
+const MAX_SUMMARY_CHARS = 6000;
+/** Longer messages are cut before they go into a summary request. */
+const MAX_SUMMARIZED_MESSAGE_CHARS = 4000;
+
+export interface HistorySettings {
+    /** Tokens of pasted history per turn, summary included. */
+    tokenBudget: number;
+    /** The newest messages always pasted verbatim, even over the budget. */
+    keepRecent: number;
+    /** Model for the rolling summary; empty uses the conversation's own. */
+    summaryModel: string;
+}
+
+/** A summary of a conversation's first `coveredMessages` messages. */
+export interface HistorySummary {
+    summary: string;
+    coveredMessages: number;
+    updatedAt: string;
+}
+
+export interface TrimmedHistory {
+    /** What to paste: a `system` summary message, when used, then the recent messages. */
+    messages: Array<{ role: string; content: string }>;
+    /** Older messages stood in for by the summary. */
+    summarized: number;
+    /** Older messages left out entirely: over the budget and not yet summarized. */
+    dropped: number;
+    tokens: number;
+}
+
+type HeadlessRunner = (prompt: string, cfg: RuntimeConfig) => Promise<{ success: boolean; text: string; error?: string }>;
+
+const DEFAULT_SETTINGS: HistorySettings = { tokenBudget: 8000, keepRecent: 4, summaryModel: 'o4-mini' };
+
+export function getHistorySettings(db: Database.Database): HistorySettings {
+    return { ...DEFAULT_SETTINGS, ...dbGetSetting<Partial<HistorySettings>>(db, SETTINGS_KEY, {}) };
+}
+
+export function setHistorySettings(db: Database.Database, settings: Partial<HistorySettings>): HistorySettings {
+    const merged = { ...getHistorySettings(db), ...settings };
+    merged.tokenBudget = Math.max(500, Math.floor(Number(merged.tokenBudget)) || DEFAULT_SETTINGS.tokenBudget);
+    merged.keepRecent = Math.min(50, Math.max(1, Math.floor(Number(merged.keepRecent)) || DEFAULT_SETTINGS.keepRecent));
+    merged.summaryModel = String(merged.summaryModel ?? '').trim();
+    dbSetSetting(db, SETTINGS_KEY, merged);
+    return merged;
+}
+
+export function getHistorySummary(db: Database.Database, conversationId: string): HistorySummary | null {
+    const row = db
+        .prepare('SELECT summary, covered_messages, updated_at FROM conversation_summaries WHERE conversation_id = ?')
+        .get(conversationId) as any;
+    return row ? { summary: row.summary, coveredMessages: row.covered_messages, updatedAt: row.updated_at } : null;
+}
+
+function saveHistorySummary(db: Database.Database, conversationId: string, summary: string, coveredMessages: number): HistorySummary {
+    const updatedAt = new Date().toISOString();
+    db.prepare(`
+    INSERT INTO conversation_summaries (conversation_id, summary, covered_messages, updated_at) VALUES (?, ?, ?, ?)
+    ON CONFLICT(conversation_id) DO UPDATE SET
+      summary = excluded.summary, covered_messages = excluded.covered_messages, updated_at = excluded.updated_at
+  `).run(conversationId, summary, coveredMessages, updatedAt);
+    return { summary, coveredMessages, updatedAt };
+}
+
+export function clearHistorySummary(db: Database.Database, conversationId: string): boolean {
+    return db.prepare('DELETE FROM conversation_summaries WHERE conversation_id = ?').run(conversationId).changes > 0;
+}
+
+function messageTokens(message: { content: string }): number {
+    return estimateTokens(message.content) + MESSAGE_OVERHEAD_TOKENS;
+}
+
+/**
+ * Fit a history into the token budget: all of it when it fits; otherwise the newest messages
+ * that fit (at least `keepRecent`) with the summary in place of the older ones. A summary
+ * covering more messages than the history has is stale (messages were deleted) and unused.
+ */
+export function trimHistory(
+    history: Array<{ role: string; content: string }>,
+    settings: Pick<HistorySettings, 'tokenBudget' | 'keepRecent'>,
+    summary?: HistorySummary | null,
+): TrimmedHistory {
+    const all = history.map(({ role, content }) => ({ role, content }));
+    const total = all.reduce((sum, message) => sum + messageTokens(message), 0);
+    if (total <= settings.tokenBudget) return { messages: all, summarized: 0, dropped: 0, tokens: total };
+
+    const usable = summary && summary.coveredMessages <= all.length ? summary : null;
+    const summaryMessage = usable ? { role: 'system', content: `[Summary of earlier messages]\n${usable.summary}` } : null;
+    let used = summaryMessage ? messageTokens(summaryMessage) : 0;
+    let start = all.length;
+    while (start > 0) {
+        const tokens = messageTokens(all[start - 1]);
+        if (all.length - start >= settings.keepRecent && used + tokens > settings.tokenBudget) break;
+        used += tokens;
+        start--;
+    }
+
+    const summarized = usable ? Math.min(usable.coveredMessages, start) : 0;
+    const recent = all.slice(start);
+    return {
+        messages: summaryMessage && summarized > 0 ? [summaryMessage, ...recent] : recent,
+        summarized,
+        dropped: start - summarized,
+        tokens: summarized > 0 ? used : used - (summaryMessage ? messageTokens(summaryMessage) : 0),
+    };
+}
+
+/**
+ * The stored messages of a conversation as history for a turn on `prompt`, or `fallback`
+ * when none are stored. The prompt's own message, saved before the turn starts, is left
+ * out since it goes to the turn as the prompt.
+ */
+export function storedHistory(
+    db: Database.Database,
+    conversationId: string,
+    prompt: string,
+    fallback: Array<{ role: string; content: string }> = [],
+): Array<{ role: string; content: string }> {
+    const stored = dbGetMessages(db, conversationId).map(({ role, content }) => ({ role, content }));
+    if (stored.length === 0) return fallback;
+    const last = stored[stored.length - 1];
+    if (last.role === 'user' && last.content.trim() === prompt.trim()) stored.pop();
+    return stored;
+}
+
+export function buildSummaryPrompt(previous: string | null, messages: Array<{ role: string; content: string }>): string {
+    const lines = messages.map((message) => {
+        const text = message.content.length > MAX_SUMMARIZED_MESSAGE_CHARS
+            ? `${message.content.slice(0, MAX_SUMMARIZED_MESSAGE_CHARS)} [...]`
+            : message.content;
+        return `${message.role === 'assistant' ? 'Assistant' : 'User'}: ${text}`;
+    });
+    return [
+        'Maintain a running summary of a conversation between a user and a coding agent. The agent',
+        'will read the summary in place of these messages, so keep what later turns need: the goals,',
+        'decisions and their reasons, files and commands involved, open questions and unfinished work.',
+        'Be concise (at most about 300 words). Do not run commands or modify any files. Reply with the',
+        'updated summary only.',
+        '',
+        '## Summary so far',
+        previous?.trim() || '(none)',
+        '',
+        '## New messages',
+        ...lines,
+    ].join('\n');
+}
+
+const inFlight = new Map<string, Promise<HistorySummary | null>>();
+
+/**
+ * Extend a conversation's rolling summary to every message but the newest `keepRecent`,
+ * by a read-only headless run on the cheap summary model. `run` is the headless runner
+ * (runCodexToCompletion). Resolves with the stored summary, or null when the run failed.
+ */
+export function refreshHistorySummary(
+    state: AppState,
+    conversationId: string,
+    history: Array<{ role: string; content: string }>,
+    run: HeadlessRunner,
+): Promise<HistorySummary | null> {
+    const running = inFlight.get(conversationId);
+    if (running) return running;
+    const settings = getHistorySettings(state.db);
+    const current = getHistorySummary(state.db, conversationId);
+    const from = current && current.coveredMessages <= history.length ? current.coveredMessages : 0;
+    const to = history.length - settings.keepRecent;
+    if (to <= from) return Promise.resolve(current);
+
+    const refresh = run(buildSummaryPrompt(from > 0 ? current!.summary : null, history.slice(from, to)), {
+        ...state.config,
+        model: settings.summaryModel || state.config.model,
+        yoloMode: false,
+        cliOptions: {
+            ...state.config.cliOptions,
+            sandbox: 'read-only',
+            askForApproval: 'never',
+            cwdOverride: '',
+        },
+    }).then((result) => {
+        const text = result.text.trim();
+        if (!result.success || !text) {
+            console.error(`[history] Summary of ${conversationId} failed: ${result.error || 'empty summary'}`);
+            return null;
+        }
+        return saveHistorySummary(state.db, conversationId, text.slice(0, MAX_SUMMARY_CHARS), to);
+    }).finally(() => inFlight.delete(conversationId));
+    inFlight.set(conversationId, refresh);
+    return refresh;
+}
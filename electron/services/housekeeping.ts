@@ -0,0 +1,205 @@
+import Database from 'better-sqlite3';
+import { WebContents } from 'electron';
+import * as fs from 'fs';
+import * as path from 'path';
+import { AppState } from './models';
+import { dbDeleteWorkspace, dbGetSetting, dbSetSetting } from './db';
+import { exportWorkspaceBundle } from './workspace-bundle';
+import { expandTildePath } from './utils';
+
+const SETTINGS_KEY = 'housekeeping.settings';
+const CHECK_INTERVAL_MS = 12 * 60 * 60 * 1000;
+const FIRST_CHECK_DELAY_MS = 60 * 1000;
+// Only the top of the tree is looked at; a full walk of a large checkout is too slow.
+const MAX_FS_ENTRIES = 500;
+const DAY_SECONDS = 24 * 60 * 60;
+
+export interface HousekeepingSettings {
+    enabled: boolean;
+    /** A workspace with no turns and no file changes for this many days is stale. */
+    staleDays: number;
+}
+
+export type HousekeepingAction = 'archive' | 'export-prune';
+
+export interface HousekeepingSuggestion {
+    workspaceId: string;
+    name: string;
+    path: string;
+    /** Unix seconds of the latest conversation, turn or file activity; null if there was none. */
+    lastActivityAt: number | null;
+    /** Null when the workspace never had any activity. */
+    idleDays: number | null;
+    /** The workspace folder no longer exists. */
+    missing: boolean;
+    conversations: number;
+    messages: number;
+    /** Approximate bytes the workspace's conversations take up in the database. */
+    footprintBytes: number;
+    /** `archive` is offered while some conversations are not archived yet. */
+    actions: HousekeepingAction[];
+}
+
+const DEFAULT_SETTINGS: HousekeepingSettings = { enabled: true, staleDays: 30 };
+
+export function getHousekeepingSettings(db: Database.Database): HousekeepingSettings {
+    return { ...DEFAULT_SETTINGS, ...dbGetSetting<Partial<HousekeepingSettings>>(db, SETTINGS_KEY, {}) };
+}
+
+export function setHousekeepingSettings(
+    db: Database.Database,
+    settings: Partial<HousekeepingSettings>,
+): HousekeepingSettings {
+    const merged = { ...getHousekeepingSettings(db), ...settings };
+    merged.staleDays = Math.max(1, Math.floor(Number(merged.staleDays)) || DEFAULT_SETTINGS.staleDays);
+    dbSetSetting(db, SETTINGS_KEY, merged);
+    return merged;
+}
+
+/**
+ * Latest modification time (unix seconds) of the workspace folder and its top-level
+ * entries, `.git` included so commits and checkouts count. Undefined if the folder is gone.
+ */
+export function workspaceFsActivity(workspacePath: string): number | null | undefined {
+    const root = expandTildePath(workspacePath);
+    let latest: number;
+    try {
+        latest = fs.statSync(root).mtimeMs;
+    } catch {
+        return undefined;
+    }
+    try {
+        const names = fs.readdirSync(root).slice(0, MAX_FS_ENTRIES);
+        if (!names.includes('.git')) names.push('.git');
+        for (const name of names) {
+            for (const entry of name === '.git' ? ['.git', '.git/index', '.git/HEAD'] : [name]) {
+                try {
+                    latest = Math.max(latest, fs.statSync(path.join(root, entry)).mtimeMs);
+                } catch { }
+            }
+        }
+    } catch {
+        return null;
+    }
+    return Math.floor(latest / 1000);
+}
+
+/** Activity times, counts and the database footprint of every workspace, or just one. */
+function workspaceUsage(db: Database.Database, workspaceId?: string): any[] {
+    return db.prepare(`
+    SELECT w.id, w.name, w.path,
+      (SELECT MAX(CAST(COALESCE(c.last_activity_at, c.updated_at) AS INTEGER))
+        FROM conversations c WHERE c.workspace_id = w.id) AS conversation_activity,
+      (SELECT MAX(CAST(t.created_at AS INTEGER))
+        FROM turns t JOIN conversations c ON c.id = t.conversation_id WHERE c.workspace_id = w.id) AS turn_activity,
+      (SELECT MAX(CAST(p.last_used_at AS INTEGER)) FROM prompt_history p WHERE p.workspace_id = w.id) AS prompt_activity,
+      (SELECT COUNT(*) FROM conversations c WHERE c.workspace_id = w.id) AS conversations,
+      (SELECT COUNT(*) FROM conversations c WHERE c.workspace_id = w.id AND c.archived = 0) AS unarchived,
+      (SELECT COUNT(*) FROM messages m JOIN conversations c ON c.id = m.conversation_id
+        WHERE c.workspace_id = w.id) AS messages,
+      (SELECT COALESCE(SUM(LENGTH(m.content) + COALESCE(LENGTH(m.thinking), 0)), 0)
+        FROM messages m JOIN conversations c ON c.id = m.conversation_id WHERE c.workspace_id = w.id)
+      + (SELECT COALESCE(SUM(LENGTH(mp.content)), 0) FROM message_parts mp
+        JOIN messages m ON m.id = mp.message_id JOIN conversations c ON c.id = m.conversation_id
+        WHERE c.workspace_id = w.id)
+      + (SELECT COALESCE(SUM(LENGTH(d.hunks)), 0) FROM turn_file_diffs d
+        JOIN conversations c ON c.id = d.conversation_id WHERE c.workspace_id = w.id)
+      + (SELECT COALESCE(SUM(LENGTH(t.prompt)), 0) FROM turns t
+        JOIN conversations c ON c.id = t.conversation_id WHERE c.workspace_id = w.id)
+      + (SELECT COALESCE(SUM(mc.request_bytes + mc.response_bytes), 0) FROM mcp_calls mc
+        JOIN conversations c ON c.id = mc.conversation_id WHERE c.workspace_id = w.id) AS footprint
+    FROM workspaces w ${workspaceId ? 'WHERE w.id = ?' : ''}
+  `).all(...(workspaceId ? [workspaceId] : [])) as any[];
+}
+
+/**
+ * Workspaces with no conversation, turn or prompt activity and no file changes for
+ * `staleDays`, oldest first. `fsActivity` is injectable so the check can be tested
+ * without real folders.
+ */
+export function findStaleWorkspaces(
+    db: Database.Database,
+    staleDays: number,
+    now = Math.floor(Date.now() / 1000),
+    fsActivity: (workspacePath: string) => number | null | undefined = workspaceFsActivity,
+): HousekeepingSuggestion[] {
+    const rows = workspaceUsage(db);
+    const cutoff = now - Math.max(1, staleDays) * DAY_SECONDS;
+    const suggestions: HousekeepingSuggestion[] = [];
+    for (const row of rows) {
+        const fsTime = fsActivity(row.path);
+        const times = [row.conversation_activity, row.turn_activity, row.prompt_activity, fsTime]
+            .filter((time): time is number => typeof time === 'number' && time > 0);
+        const lastActivityAt = times.length > 0 ? Math.max(...times) : null;
+        if (lastActivityAt !== null && lastActivityAt > cutoff) continue;
+        suggestions.push({
+            workspaceId: row.id,
+            name: row.name,
+            path: row.path,
+            lastActivityAt,
+            idleDays: lastActivityAt === null ? null : Math.floor((now - lastActivityAt) / DAY_SECONDS),
+            missing: fsTime === undefined,
+            conversations: row.conversations,
+            messages: row.messages,
+            footprintBytes: row.footprint,
+            actions: row.unarchived > 0 ? ['archive', 'export-prune'] : ['export-prune'],
+        });
+    }
+    return suggestions.sort((a, b) => (a.lastActivityAt ?? 0) - (b.lastActivityAt ?? 0));
+}
+
+export function getHousekeepingSuggestions(db: Database.Database): HousekeepingSuggestion[] {
+    return findStaleWorkspaces(db, getHousekeepingSettings(db).staleDays);
+}
+
+/** Archive every conversation of the workspace so it drops out of the sidebar lists. */
+export function archiveWorkspace(db: Database.Database, workspaceId: string): { success: boolean; archived: number } {
+    const result = db
+        .prepare('UPDATE conversations SET archived = 1, pinned = 0 WHERE workspace_id = ? AND archived = 0')
+        .run(workspaceId);
+    return { success: true, archived: result.changes };
+}
+
+/**
+ * Write the workspace bundle to `destPath`, then delete the workspace and its
+ * conversations from the database. Nothing is deleted if the export fails.
+ */
+export function exportAndPruneWorkspace(
+    db: Database.Database,
+    workspaceId: string,
+    destPath: string,
+): { success: boolean; path?: string; size?: number; freedBytes?: number; error?: string } {
+    const footprint = workspaceUsage(db, workspaceId)[0]?.footprint ?? 0;
+    const exported = exportWorkspaceBundle(db, workspaceId, destPath);
+    if (!exported.success) return { success: false, error: exported.error };
+    dbDeleteWorkspace(db, workspaceId);
+    return { success: true, path: exported.path, size: exported.size, freedBytes: footprint };
+}
+
+/**
+ * Look for stale workspaces shortly after launch and then twice a day, sending them as
+ * `housekeeping-suggestions`. Workspaces that are already archived are left out so the
+ * same suggestion does not come back every time. Returns a stop function.
+ */
+export function startHousekeeping(
+    getWebContents: () => WebContents | null,
+    state: AppState,
+): () => void {
+    const check = () => {
+        if (!getHousekeepingSettings(state.db).enabled) return;
+        try {
+            const suggestions = getHousekeepingSuggestions(state.db).filter((s) => s.actions.includes('archive'));
+            if (suggestions.length > 0) getWebContents()?.send('housekeeping-suggestions', suggestions);
+        } catch (err) {
+            console.error('[housekeeping] Check failed:', err);
+        }
+    };
+    const first = setTimeout(check, FIRST_CHECK_DELAY_MS);
+    const timer = setInterval(check, CHECK_INTERVAL_MS);
+    first.unref();
+    timer.unref();
+    return () => {
+        clearTimeout(first);
+        clearInterval(timer);
+    };
+}
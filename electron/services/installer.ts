@@ -0,0 +1,333 @@
+import Database from 'better-sqlite3';
+import * as crypto from 'crypto';
+import * as fs from 'fs';
+import * as os from 'os';
+import * as path from 'path';
+import * as readline from 'readline';
+import { WebContents } from 'electron';
+import { dbGetSetting, dbSetSetting } from './db';
+import { resetCodexVersion } from './protocol';
+import { commandSpawnOptions, homeDir, isCommandAvailable, spawnCommand } from './utils';
+
+const METHOD_KEY = 'codexInstall.method';
+const RELEASES_URL = 'https://api.github.com/repos/openai/codex/releases/latest';
+
+export type CodexInstallMethod = 'npm' | 'pnpm' | 'bun' | 'brew' | 'binary';
+
+export interface InstallMethodInfo {
+    id: CodexInstallMethod;
+    label: string;
+    available: boolean;
+}
+
+/** Tried in this order when no preference is set, or the preferred one is missing. */
+const METHOD_ORDER: CodexInstallMethod[] = ['npm', 'pnpm', 'bun', 'brew', 'binary'];
+const METHOD_LABELS: Record<CodexInstallMethod, string> = {
+    npm: 'npm',
+    pnpm: 'pnpm',
+    bun: 'Bun',
+    brew: 'Homebrew',
+    binary: 'GitHub release binary',
+};
+
+interface InstallMessages {
+    start: string;
+    done: string;
+    failed: string;
+}
+
+/** The Rust target a GitHub release of codex is built for on this machine; null when none is. */
+export function binaryTarget(platform: string = process.platform, arch: string = process.arch): string | null {
+    const cpu = arch === 'x64' ? 'x86_64' : arch === 'arm64' ? 'aarch64' : null;
+    if (!cpu) return null;
+    if (platform === 'darwin') return `${cpu}-apple-darwin`;
+    if (platform === 'linux') return `${cpu}-unknown-linux-musl`;
+    if (platform === 'win32') return `${cpu}-pc-windows-msvc`;
+    return null;
+}
+
+/** Where a release binary is installed; on PATH for spawned commands (see commandSpawnOptions). */
+export function binaryInstallDir(): string {
+    const home = homeDir();
+    if (!home) throw new Error('Unable to resolve home directory');
+    return path.join(home, '.local', 'bin');
+}
+
+export function listInstallMethods(): Promise<InstallMethodInfo[]> {
+    return Promise.all(METHOD_ORDER.map(async (id) => ({
+        id,
+        label: METHOD_LABELS[id],
+        available: id === 'binary'
+            ? binaryTarget() !== null && (await isCommandAvailable('tar'))
+            : id === 'brew'
+                ? process.platform !== 'win32' && (await isCommandAvailable('brew'))
+                : await isCommandAvailable(id),
+    })));
+}
+
+export function getInstallPreference(db: Database.Database): CodexInstallMethod | 'auto' {
+    return dbGetSetting<CodexInstallMethod | 'auto'>(db, METHOD_KEY, 'auto');
+}
+
+export function setInstallPreference(
+    db: Database.Database,
+    method: CodexInstallMethod | 'auto',
+): { success: boolean; error?: string } {
+    if (method !== 'auto' && !METHOD_ORDER.includes(method)) {
+        return { success: false, error: `Unknown install method: ${method}` };
+    }
+    dbSetSetting(db, METHOD_KEY, method);
+    return { success: true };
+}
+
+/** The preferred method when it is available, else the first available one in METHOD_ORDER. */
+export function chooseInstallMethod(
+    preference: CodexInstallMethod | 'auto',
+    available: CodexInstallMethod[],
+): CodexInstallMethod | null {
+    if (preference !== 'auto' && available.includes(preference)) return preference;
+    return METHOD_ORDER.find((method) => available.includes(method)) ?? null;
+}
+
+/** The command that installs (or upgrades) codex with a package manager. */
+export function packageManagerCommand(method: Exclude<CodexInstallMethod, 'binary'>, upgrade: boolean): [string, string[]] {
+    const spec = upgrade ? '@openai/codex@latest' : '@openai/codex';
+    switch (method) {
+        case 'npm':
+            return ['npm', ['install', '-g', spec]];
+        case 'pnpm':
+            return ['pnpm', ['add', '-g', spec]];
+        case 'bun':
+            return ['bun', ['add', '-g', spec]];
+        case 'brew':
+            return ['brew', [upgrade ? 'upgrade' : 'install', 'codex']];
+    }
+}
+
+/**
+ * Install or upgrade codex with `method`, or with the preferred/first available method.
+ * Every method reports through `codex-install-progress`, with the method it used.
+ */
+export async function installCodexWith(
+    webContents: WebContents,
+    db: Database.Database,
+    upgrade: boolean,
+    method?: CodexInstallMethod,
+): Promise<{ success: boolean; method?: CodexInstallMethod; error?: string }> {
+    const available = (await listInstallMethods()).filter((info) => info.available).map((info) => info.id);
+    const chosen = method ?? chooseInstallMethod(getInstallPreference(db), available);
+    if (!chosen || !available.includes(chosen)) {
+        const error = chosen
+            ? `${METHOD_LABELS[chosen]} is not available on this machine`
+            : 'No way to install codex was found. Install Node.js (npm), pnpm, Bun or Homebrew.';
+        webContents.send('codex-install-progress', { status: 'error', message: error, percent: 0, method: chosen });
+        return { success: false, method: chosen ?? undefined, error };
+    }
+
+    const messages: InstallMessages = upgrade
+        ? { start: `Upgrading Codex CLI with ${METHOD_LABELS[chosen]}...`, done: 'Codex CLI upgraded successfully', failed: 'Upgrade failed' }
+        : { start: `Installing Codex CLI with ${METHOD_LABELS[chosen]}...`, done: 'Codex CLI installed successfully', failed: 'Installation failed' };
+    const result = chosen === 'binary'
+        ? await installReleaseBinary(webContents, messages)
+        : await runInstallCommand(webContents, chosen, ...packageManagerCommand(chosen, upgrade), messages);
+    if (result.success) resetCodexVersion();
+    return { ...result, method: chosen };
+}
+
+function runInstallCommand(
+    webContents: WebContents,
+    method: CodexInstallMethod,
+    bin: string,
+    args: string[],
+    messages: InstallMessages,
+): Promise<{ success: boolean; error?: string }> {
+    const progress = (status: string, message: string, percent: number) =>
+        webContents.send('codex-install-progress', { status, message, percent, method });
+    progress('installing', messages.start, 0);
+
+    const child = spawnCommand(bin, args);
+
+    return new Promise((resolve) => {
+        let lineCount = 0;
+
+        if (child.stderr) {
+            const rl = readline.createInterface({ input: child.stderr });
+            rl.on('line', (line: string) => {
+                if (!line.trim()) return;
+                lineCount++;
+                progress('installing', line.trim(), Math.min(10 + lineCount * 5, 80));
+            });
+        }
+
+        if (child.stdout) {
+            const rl = readline.createInterface({ input: child.stdout });
+            rl.on('line', (line: string) => {
+                if (!line.trim()) return;
+                progress('installing', line.trim(), 85);
+            });
+        }
+
+        child.on('exit', (code) => {
+            if (code === 0) {
+                progress('complete', messages.done, 100);
+                resolve({ success: true });
+            } else {
+                progress('error', `${messages.failed}: exit ${code}`, 0);
+                resolve({ success: false, error: messages.failed });
+            }
+        });
+
+        child.on('error', (err) => {
+            const msg = err.message.includes('ENOENT')
+                ? `${bin} executable was not found. Please install it and make sure it is available in PATH.`
+                : err.message;
+            progress('error', msg, 0);
+            resolve({ success: false, error: msg });
+        });
+    });
+}
+
+// ===== Release binary =====
+
+export interface ReleaseAsset {
+    name: string;
+    browser_download_url: string;
+    /** `sha256:<hex>`, published by GitHub for release assets. */
+    digest?: string | null;
+}
+
+/** The archive for `target` among a release's assets (`codex-<target>.tar.gz`, or `.exe.zip` on Windows). */
+export function releaseAssetFor(assets: ReleaseAsset[], target: string): ReleaseAsset | null {
+    const names = target.endsWith('windows-msvc')
+        ? [`codex-${target}.exe.zip`, `codex-${target}.zip`]
+        : [`codex-${target}.tar.gz`];
+    return names.map((name) => assets.find((asset) => asset.name === name)).find(Boolean) ?? null;
+}
+
+/** The sha256 GitHub published for an asset, lowercased; null when it has none. */
+export function assetDigest(asset: ReleaseAsset): string | null {
+    return asset.digest?.match(/^sha256:([0-9a-f]{64})$/i)?.[1].toLowerCase() ?? null;
+}
+
+/** The expected sha256 of `asset`: its digest, else a `<name>.sha256` asset's contents. */
+async function assetChecksum(asset: ReleaseAsset, assets: ReleaseAsset[]): Promise<string | null> {
+    const digest = assetDigest(asset);
+    if (digest) return digest;
+    const sidecar = assets.find((candidate) => candidate.name === `${asset.name}.sha256`);
+    if (!sidecar) return null;
+    const response = await fetch(sidecar.browser_download_url);
+    if (!response.ok) return null;
+    return (await response.text()).match(/\b([0-9a-f]{64})\b/i)?.[1].toLowerCase() ?? null;
+}
+
+function runTar(args: string[]): Promise<boolean> {
+    return new Promise((resolve) => {
+        const child = spawnCommand('tar', args);
+        child.on('error', () => resolve(false));
+        child.on('exit', (code) => resolve(code === 0));
+    });
+}
+
+/** The codex executable in an extracted archive, named after its target or plain `codex`. */
+function findExtractedBinary(dir: string): string | null {
+    for (const entry of fs.readdirSync(dir, { withFileTypes: true })) {
+        const full = path.join(dir, entry.name);
+        if (entry.isDirectory()) {
+            const nested = findExtractedBinary(full);
+            if (nested) return nested;
+        } else if (/^codex(-[\w-]+)?(\.exe)?$/.test(entry.name)) {
+            return full;
+        }
+    }
+    return null;
+}
+
+/**
+ * Download the latest release archive for this machine, check it against the published
+ * sha256 and put the binary in binaryInstallDir(). An archive without a checksum is not
+ * installed.
+ */
+async function installReleaseBinary(
+    webContents: WebContents,
+    messages: InstallMessages,
+): Promise<{ success: boolean; error?: string }> {
+    const progress = (status: string, message: string, percent: number) =>
+        webContents.send('codex-install-progress', { status, message, percent, method: 'binary' });
+    const fail = (error: string) => {
+        progress('error', `${messages.failed}: ${error}`, 0);
+        return { success: false, error };
+    };
+    progress('installing', messages.start, 0);
+
+    const target = binaryTarget();
+    if (!target) return fail(`No codex release is built for ${process.platform}/${process.arch}`);
+
+    let workDir: string | null = null;
+    try {
+        const release = await fetch(RELEASES_URL, {
+            headers: { Accept: 'application/vnd.github+json', 'User-Agent': 'codex-wui' },
+        });
+        if (!release.ok) return fail(`GitHub returned ${release.status}`);
+        const data = await release.json() as { tag_name?: string; assets?: ReleaseAsset[] };
+        const assets = data.assets || [];
+        const asset = releaseAssetFor(assets, target);
+        if (!asset) return fail(`Release ${data.tag_name ?? ''} has no archive for ${target}`);
+        const expected = await assetChecksum(asset, assets);
+        if (!expected) return fail(`No checksum is published for ${asset.name}`);
+
+        progress('installing', `Downloading ${asset.name}...`, 5);
+        const download = await fetch(asset.browser_download_url);
+        if (!download.ok || !download.body) return fail(`Download returned ${download.status}`);
+        const total = Number(download.headers.get('content-length')) || 0;
+        const hash = crypto.createHash('sha256');
+        const chunks: Buffer[] = [];
+        let received = 0;
+        let lastPercent = 5;
+        const reader = download.body.getReader();
+        for (;;) {
+            const { done, value } = await reader.read();
+            if (done) break;
+            const chunk = Buffer.from(value);
+            hash.update(chunk);
+            chunks.push(chunk);
+            received += chunk.length;
+            const percent = total ? 5 + Math.floor((received / total) * 75) : lastPercent;
+            if (percent >= lastPercent + 5) {
+                lastPercent = percent;
+                progress('installing', `Downloading ${asset.name}...`, percent);
+            }
+        }
+
+        progress('installing', 'Verifying checksum...', 85);
+        const actual = hash.digest('hex');
+        if (actual !== expected) return fail(`Checksum mismatch for ${asset.name} (expected ${expected}, got ${actual})`);
+
+        workDir = fs.mkdtempSync(path.join(os.tmpdir(), 'codex-install-'));
+        const archive = path.join(workDir, asset.name);
+        fs.writeFileSync(archive, Buffer.concat(chunks));
+        progress('installing', 'Extracting...', 90);
+        const extracted = path.join(workDir, 'out');
+        fs.mkdirSync(extracted);
+        const extractArgs = asset.name.endsWith('.zip') ? ['-xf', archive, '-C', extracted] : ['-xzf', archive, '-C', extracted];
+        if (!(await runTar(extractArgs))) return fail(`Could not extract ${asset.name}`);
+        const binary = findExtractedBinary(extracted);
+        if (!binary) return fail(`${asset.name} does not contain a codex binary`);
+
+        const installDir = binaryInstallDir();
+        fs.mkdirSync(installDir, { recursive: true });
+        const destination = path.join(installDir, process.platform === 'win32' ? 'codex.exe' : 'codex');
+        // Copy next to the destination and rename, so a running codex is never half-written.
+        const staged = `${destination}.${process.pid}.tmp`;
+        fs.copyFileSync(binary, staged);
+        fs.chmodSync(staged, 0o755);
+        fs.renameSync(staged, destination);
+
+        const onPath = (commandSpawnOptions().env?.PATH || '').split(path.delimiter).includes(installDir);
+        progress('complete', onPath ? messages.done : `${messages.done}. Add ${installDir} to PATH to use it.`, 100);
+        return { success: true };
+    } catch (err: any) {
+        return fail(err.message);
+    } finally {
+        if (workDir) fs.rmSync(workDir, { recursive: true, force: true });
+    }
+}
@@ -0,0 +1,230 @@
+import * as fs from 'fs';
+import * as path from 'path';
+import { codexConfigPath, parseTomlTables } from './config';
+import { atomicWriteFile } from './fs-ops';
+import { expandTildePath } from './utils';
+
+export const INSTRUCTIONS_FILE = 'AGENTS.md';
+export const OVERRIDE_FILE = 'AGENTS.override.md';
+const DEFAULT_MAX_BYTES = 32 * 1024;
+const CONVENTIONS_HEADING = 'Team conventions';
+
+export interface InstructionsFile {
+    path: string;
+    scope: 'global' | 'project';
+    bytes: number;
+}
+
+export interface InstructionsStatus {
+    /** Where codex starts looking: the git root above `cwd`, or `cwd` itself. */
+    projectRoot: string;
+    /** Files codex combines for `cwd`, in the order it reads them. */
+    files: InstructionsFile[];
+    totalBytes: number;
+    maxBytes: number;
+    /** Project instructions beyond `maxBytes` are cut off by codex. */
+    truncated: boolean;
+}
+
+export interface ProjectKind {
+    kind: 'node' | 'rust' | 'python' | 'go';
+    build?: string;
+    test?: string;
+    lint?: string;
+}
+
+function readText(file: string): string | null {
+    try {
+        return fs.readFileSync(file, 'utf-8');
+    } catch {
+        return null;
+    }
+}
+
+function fileSize(file: string): number | null {
+    try {
+        const stats = fs.statSync(file);
+        return stats.isFile() ? stats.size : null;
+    } catch {
+        return null;
+    }
+}
+
+/** `project_doc_max_bytes` and `project_doc_fallback_filenames` from ~/.codex/config.toml. */
+function projectDocSettings(): { maxBytes: number; fallbacks: string[] } {
+    let root: Record<string, unknown> = {};
+    try {
+        root = parseTomlTables(fs.readFileSync(codexConfigPath(), 'utf-8'))[''] || {};
+    } catch { }
+    const maxBytes = typeof root.project_doc_max_bytes === 'number' ? root.project_doc_max_bytes : DEFAULT_MAX_BYTES;
+    const fallbacks = Array.isArray(root.project_doc_fallback_filenames)
+        ? root.project_doc_fallback_filenames.filter((name): name is string => typeof name === 'string')
+        : [];
+    return { maxBytes, fallbacks };
+}
+
+function findProjectRoot(cwd: string): string {
+    for (let dir = cwd; ; dir = path.dirname(dir)) {
+        if (fs.existsSync(path.join(dir, '.git'))) return dir;
+        if (path.dirname(dir) === dir) return cwd;
+    }
+}
+
+/**
+ * The instruction files codex picks up when run in `cwd`: the global AGENTS.md in the
+ * codex home, then one file per directory from the project root down to `cwd`, where
+ * AGENTS.override.md wins over AGENTS.md and the configured fallback names.
+ */
+export function instructionsStatus(cwd: string): InstructionsStatus {
+    const target = path.resolve(expandTildePath(cwd));
+    const { maxBytes, fallbacks } = projectDocSettings();
+    const files: InstructionsFile[] = [];
+
+    const codexHome = path.dirname(codexConfigPath());
+    for (const name of [OVERRIDE_FILE, INSTRUCTIONS_FILE]) {
+        const bytes = fileSize(path.join(codexHome, name));
+        if (bytes) {
+            files.push({ path: path.join(codexHome, name), scope: 'global', bytes });
+            break;
+        }
+    }
+
+    const projectRoot = findProjectRoot(target);
+    const relative = path.relative(projectRoot, target);
+    const dirs = [projectRoot];
+    for (const segment of relative ? relative.split(path.sep) : []) dirs.push(path.join(dirs[dirs.length - 1], segment));
+    let projectBytes = 0;
+    for (const dir of dirs) {
+        for (const name of [OVERRIDE_FILE, INSTRUCTIONS_FILE, ...fallbacks]) {
+            const bytes = fileSize(path.join(dir, name));
+            if (bytes) {
+                files.push({ path: path.join(dir, name), scope: 'project', bytes });
+                projectBytes += bytes;
+                break;
+            }
+        }
+    }
+
+    return {
+        projectRoot,
+        files,
+        totalBytes: files.reduce((sum, file) => sum + file.bytes, 0),
+        maxBytes,
+        truncated: projectBytes > maxBytes,
+    };
+}
+
+/** The workspace root's AGENTS.md and AGENTS.override.md; missing files are null. */
+export function readInstructions(workspacePath: string): { path: string; content: string | null; override: string | null } {
+    const root = expandTildePath(workspacePath);
+    return {
+        path: path.join(root, INSTRUCTIONS_FILE),
+        content: readText(path.join(root, INSTRUCTIONS_FILE)),
+        override: readText(path.join(root, OVERRIDE_FILE)),
+    };
+}
+
+/** Replace the workspace root's AGENTS.md (or with `override`, AGENTS.override.md). */
+export function writeInstructions(
+    workspacePath: string,
+    content: string,
+    override = false,
+): { success: boolean; path?: string; error?: string } {
+    try {
+        const file = path.join(expandTildePath(workspacePath), override ? OVERRIDE_FILE : INSTRUCTIONS_FILE);
+        atomicWriteFile(file, content.endsWith('\n') ? content : `${content}\n`);
+        return { success: true, path: file };
+    } catch (err: any) {
+        return { success: false, error: err.message };
+    }
+}
+
+/** Languages used at the workspace root and the commands to build, test and lint them. */
+export function detectProjectKinds(workspacePath: string): ProjectKind[] {
+    const root = expandTildePath(workspacePath);
+    const kinds: ProjectKind[] = [];
+
+    const pkgText = readText(path.join(root, 'package.json'));
+    if (pkgText !== null) {
+        let scripts: Record<string, string> = {};
+        try {
+            scripts = JSON.parse(pkgText).scripts || {};
+        } catch { }
+        const runner = fs.existsSync(path.join(root, 'pnpm-lock.yaml'))
+            ? 'pnpm'
+            : fs.existsSync(path.join(root, 'yarn.lock')) ? 'yarn' : 'npm';
+        const script = (name: string) => (scripts[name] ? `${runner} run ${name}` : undefined);
+        kinds.push({ kind: 'node', build: script('build'), test: scripts.test ? `${runner} test` : undefined, lint: script('lint') });
+    }
+    if (fs.existsSync(path.join(root, 'Cargo.toml'))) {
+        kinds.push({ kind: 'rust', build: 'cargo build', test: 'cargo test', lint: 'cargo clippy -- -D warnings' });
+    }
+    if (['pyproject.toml', 'setup.py', 'requirements.txt'].some((name) => fs.existsSync(path.join(root, name)))) {
+        kinds.push({ kind: 'python', test: 'pytest' });
+    }
+    if (fs.existsSync(path.join(root, 'go.mod'))) {
+        kinds.push({ kind: 'go', build: 'go build ./...', test: 'go test ./...', lint: 'go vet ./...' });
+    }
+    return kinds;
+}
+
+/** A starter AGENTS.md for the workspace, based on the detected project kinds. */
+export function generateStarterInstructions(workspacePath: string): string {
+    const name = path.basename(expandTildePath(workspacePath));
+    const kinds = detectProjectKinds(workspacePath);
+    const lines = [`# ${name}`, '', 'Guidance for coding agents working in this repository.', ''];
+
+    const commands = kinds.flatMap((k) => [
+        k.build && `- Build: \`${k.build}\``,
+        k.test && `- Test: \`${k.test}\``,
+        k.lint && `- Lint: \`${k.lint}\``,
+    ]).filter((line): line is string => !!line);
+    if (commands.length > 0) lines.push('## Commands', '', ...commands, '');
+
+    lines.push(
+        '## Working agreements',
+        '',
+        '- Follow the existing code style and project structure.',
+        '- Run the tests relevant to a change before finishing.',
+        '- Keep changes focused; do not reformat unrelated code.',
+        '',
+    );
+    return lines.join('\n');
+}
+
+/** Write a starter AGENTS.md at the workspace root unless one already exists. */
+export function createStarterInstructions(workspacePath: string): { success: boolean; path?: string; content?: string; error?: string } {
+    const existing = readInstructions(workspacePath);
+    if (existing.content !== null) return { success: false, error: `${INSTRUCTIONS_FILE} already exists` };
+    const content = generateStarterInstructions(workspacePath);
+    const written = writeInstructions(workspacePath, content);
+    return written.success ? { ...written, content } : written;
+}
+
+/**
+ * Add team conventions to AGENTS.md, at the end of its "Team conventions" section (which
+ * is created when missing). Creates AGENTS.md when there is none.
+ */
+export function appendConventions(
+    workspacePath: string,
+    conventions: string,
+): { success: boolean; path?: string; content?: string; error?: string } {
+    const text = conventions.trim();
+    if (!text) return { success: false, error: 'No conventions given' };
+
+    const current = readInstructions(workspacePath).content ?? '';
+    const lines = current.replace(/\s+$/, '').split('\n');
+    const heading = lines.findIndex((line) => line.trim() === `## ${CONVENTIONS_HEADING}`);
+    let next: string;
+    if (heading < 0) {
+        next = `${current.trim() ? `${lines.join('\n')}\n\n` : ''}## ${CONVENTIONS_HEADING}\n\n${text}\n`;
+    } else {
+        let end = lines.findIndex((line, i) => i > heading && /^#{1,2} /.test(line));
+        if (end < 0) end = lines.length;
+        while (end > heading + 1 && !lines[end - 1].trim()) end--;
+        lines.splice(end, 0, '', text);
+        next = `${lines.join('\n')}\n`;
+    }
+    const written = writeInstructions(workspacePath, next);
+    return written.success ? { ...written, content: next } : written;
+}
@@ -0,0 +1,221 @@
+import Database from 'better-sqlite3';
+import { WebContents } from 'electron';
+import { generateId, nowIso } from './utils';
+
+export type JobStatus = 'running' | 'completed' | 'failed' | 'cancelled';
+
+export interface Job {
+    id: string;
+    kind: string;
+    title: string;
+    status: JobStatus;
+    /** 0-100, or null while the job cannot tell how far along it is. */
+    progress: number | null;
+    etaMs: number | null;
+    message: string | null;
+    params: any;
+    result: any;
+    error: string | null;
+    startedAt: string;
+    finishedAt: string | null;
+}
+
+/** Handed to a job's runner to report progress and notice cancellation. */
+export interface JobContext {
+    signal: AbortSignal;
+    /**
+     * Report `done` of `total` steps. Yields to the event loop so cancel requests and
+     * other IPC get through, and throws once the job has been cancelled.
+     */
+    progress(done: number, total: number, message?: string): Promise<void>;
+}
+
+export type JobRunner = (params: any, job: JobContext) => Promise<any>;
+
+interface ActiveJob {
+    job: Job;
+    controller: AbortController;
+    startedAtMs: number;
+}
+
+class JobCancelledError extends Error {
+    constructor() {
+        super('Job cancelled');
+    }
+}
+
+const MAX_FINISHED_JOBS = 50;
+
+const runners = new Map<string, JobRunner>();
+const sessionJobs = new Map<string, ActiveJob>();
+
+/** Make a kind of job startable by `startJob` and retryable after a failure. */
+export function registerJobKind(kind: string, runner: JobRunner): void {
+    runners.set(kind, runner);
+}
+
+function emit(getWebContents: () => WebContents | null, job: Job): void {
+    getWebContents()?.send('job-updated', job);
+}
+
+function pruneFinished(): void {
+    const finished = Array.from(sessionJobs.values()).filter((active) => active.job.status !== 'running');
+    for (const active of finished.slice(0, Math.max(0, finished.length - MAX_FINISHED_JOBS))) {
+        sessionJobs.delete(active.job.id);
+    }
+}
+
+function persistFailure(db: Database.Database, job: Job): void {
+    try {
+        db.prepare(`
+      INSERT OR REPLACE INTO failed_jobs (id, kind, title, params, error, started_at, failed_at)
+      VALUES (?, ?, ?, ?, ?, ?, ?)
+    `).run(job.id, job.kind, job.title, JSON.stringify(job.params ?? null), job.error, job.startedAt, job.finishedAt);
+    } catch (err) {
+        console.error('[jobs] Failed to persist failed job:', err);
+    }
+}
+
+/**
+ * Start a registered kind of job in the background. Progress and the final state are sent
+ * as `job-updated` events; a failed job is kept in the database so it can be retried
+ * after a restart.
+ */
+export function startJob(
+    getWebContents: () => WebContents | null,
+    db: Database.Database,
+    kind: string,
+    title: string,
+    params: any,
+): { success: boolean; job?: Job; error?: string } {
+    const runner = runners.get(kind);
+    if (!runner) return { success: false, error: `Unknown job kind: ${kind}` };
+
+    const controller = new AbortController();
+    const active: ActiveJob = {
+        job: {
+            id: generateId('job'),
+            kind,
+            title: title || kind,
+            status: 'running',
+            progress: null,
+            etaMs: null,
+            message: null,
+            params: params ?? null,
+            result: null,
+            error: null,
+            startedAt: nowIso(),
+            finishedAt: null,
+        },
+        controller,
+        startedAtMs: Date.now(),
+    };
+    const { job } = active;
+    sessionJobs.set(job.id, active);
+    pruneFinished();
+
+    const context: JobContext = {
+        signal: controller.signal,
+        async progress(done, total, message) {
+            const percent = total > 0 ? Math.min(100, Math.round((done / total) * 100)) : null;
+            const elapsed = Date.now() - active.startedAtMs;
+            const eta = total > 0 && done > 0 ? Math.round((elapsed * (total - done)) / done) : null;
+            const changed = percent !== job.progress || (message ?? job.message) !== job.message;
+            job.progress = percent;
+            job.etaMs = eta;
+            if (message !== undefined) job.message = message;
+            if (changed) emit(getWebContents, job);
+            await new Promise((resolve) => setImmediate(resolve));
+            if (controller.signal.aborted) throw new JobCancelledError();
+        },
+    };
+
+    emit(getWebContents, job);
+    setImmediate(() => {
+        runner(job.params, context)
+            .then((result) => {
+                if (controller.signal.aborted) throw new JobCancelledError();
+                job.status = 'completed';
+                job.progress = 100;
+                job.etaMs = 0;
+                job.result = result ?? null;
+            })
+            .catch((err) => {
+                job.etaMs = null;
+                if (err instanceof JobCancelledError || controller.signal.aborted) {
+                    job.status = 'cancelled';
+                } else {
+                    job.status = 'failed';
+                    job.error = err?.message || String(err);
+                }
+            })
+            .finally(() => {
+                job.finishedAt = nowIso();
+                if (job.status === 'failed') persistFailure(db, job);
+                emit(getWebContents, job);
+            });
+    });
+    return { success: true, job };
+}
+
+/** Jobs of this session, newest first, followed by failed jobs of earlier sessions. */
+export function listJobs(db: Database.Database): Job[] {
+    const current = Array.from(sessionJobs.values(), (active) => active.job).reverse();
+    const known = new Set(current.map((job) => job.id));
+    const failed = (db.prepare('SELECT * FROM failed_jobs ORDER BY failed_at DESC').all() as any[])
+        .filter((row) => !known.has(row.id))
+        .map((row): Job => {
+            let params: any = null;
+            try {
+                params = JSON.parse(row.params);
+            } catch { }
+            return {
+                id: row.id,
+                kind: row.kind,
+                title: row.title,
+                status: 'failed',
+                progress: null,
+                etaMs: null,
+                message: null,
+                params,
+                result: null,
+                error: row.error,
+                startedAt: row.started_at,
+                finishedAt: row.failed_at,
+            };
+        });
+    return [...current, ...failed];
+}
+
+/** Ask a running job to stop; it ends as `cancelled` at its next progress step. */
+export function cancelJob(jobId: string): { success: boolean; error?: string } {
+    const active = sessionJobs.get(jobId);
+    if (!active) return { success: false, error: 'Job not found' };
+    if (active.job.status !== 'running') return { success: false, error: 'Job is not running' };
+    active.controller.abort();
+    return { success: true };
+}
+
+/** Run a failed job again with the same parameters. The failure record is removed. */
+export function retryJob(
+    getWebContents: () => WebContents | null,
+    db: Database.Database,
+    jobId: string,
+): { success: boolean; job?: Job; error?: string } {
+    const failed = listJobs(db).find((job) => job.id === jobId && job.status === 'failed');
+    if (!failed) return { success: false, error: 'Failed job not found' };
+    const started = startJob(getWebContents, db, failed.kind, failed.title, failed.params);
+    if (started.success) {
+        db.prepare('DELETE FROM failed_jobs WHERE id = ?').run(jobId);
+        sessionJobs.delete(jobId);
+    }
+    return started;
+}
+
+/** Forget a failed job without retrying it. */
+export function dismissJob(db: Database.Database, jobId: string): { success: boolean } {
+    db.prepare('DELETE FROM failed_jobs WHERE id = ?').run(jobId);
+    const active = sessionJobs.get(jobId);
+    if (active && active.job.status !== 'running') sessionJobs.delete(jobId);
+    return { success: true };
+}
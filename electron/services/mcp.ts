@@ -0,0 +1,359 @@
+import Database from 'better-sqlite3';
+import { ChildProcess, spawn } from 'child_process';
+import { WebContents } from 'electron';
+import * as net from 'net';
+import { getMcpServers, McpServerConfig } from './config';
+import { commandSpawnOptions, nowIso, trackChild } from './utils';
+
+export interface McpCallRecord {
+    id: string;
+    turnId: string;
+    conversationId: string;
+    server: string;
+    tool: string;
+    status: string;
+    /** Scrubbed, size-capped JSON of the call arguments. */
+    request: string;
+    /** Scrubbed, size-capped JSON of the result, or null while running / on error. */
+    response: string | null;
+    error: string | null;
+    requestBytes: number;
+    responseBytes: number;
+    truncated: boolean;
+    startedAt: string;
+    finishedAt: string | null;
+}
+
+const MAX_PAYLOAD_CHARS = 64 * 1024;
+const REDACTED = '[REDACTED]';
+
+const SECRET_KEY = /(pass(word|wd)?|secret|token|api[-_]?key|authorization|cookie|credential|private[-_]?key|session)/i;
+
+const SECRET_VALUES: RegExp[] = [
+    /\bBearer\s+[A-Za-z0-9._~+/=-]{8,}/g,
+    /\bsk-[A-Za-z0-9_-]{16,}/g,
+    /\bgh[pousr]_[A-Za-z0-9]{20,}/g,
+    /\bgithub_pat_[A-Za-z0-9_]{20,}/g,
+    /\bxox[abprs]-[A-Za-z0-9-]{10,}/g,
+    /\bAKIA[0-9A-Z]{16}\b/g,
+    /-----BEGIN [A-Z ]*PRIVATE KEY-----[\s\S]*?-----END [A-Z ]*PRIVATE KEY-----/g,
+];
+
+/**
+ * Replace likely secrets in an MCP payload: values under secret-looking keys, and
+ * bearer tokens / API keys / private keys found inside any string.
+ */
+export function scrubSecrets(value: unknown): unknown {
+    if (typeof value === 'string') {
+        return SECRET_VALUES.reduce((text, pattern) => text.replace(pattern, REDACTED), value);
+    }
+    if (Array.isArray(value)) return value.map(scrubSecrets);
+    if (value && typeof value === 'object') {
+        return Object.fromEntries(
+            Object.entries(value).map(([key, inner]) => [
+                key,
+                SECRET_KEY.test(key) && inner !== null && typeof inner !== 'object' ? REDACTED : scrubSecrets(inner),
+            ]),
+        );
+    }
+    return value;
+}
+
+/** Scrubbed JSON text of a payload, cut to the size cap. `bytes` is the size before cutting. */
+export function capPayload(value: unknown): { text: string; bytes: number; truncated: boolean } {
+    const text = JSON.stringify(scrubSecrets(value ?? null), null, 2) ?? 'null';
+    const bytes = Buffer.byteLength(text);
+    if (text.length <= MAX_PAYLOAD_CHARS) return { text, bytes, truncated: false };
+    return { text: `${text.slice(0, MAX_PAYLOAD_CHARS)}\n... (truncated)`, bytes, truncated: true };
+}
+
+function rowToCall(row: any): McpCallRecord {
+    return {
+        id: row.id,
+        turnId: row.turn_id,
+        conversationId: row.conversation_id,
+        server: row.server,
+        tool: row.tool,
+        status: row.status,
+        request: row.request,
+        response: row.response,
+        error: row.error,
+        requestBytes: row.request_bytes,
+        responseBytes: row.response_bytes,
+        truncated: !!row.truncated,
+        startedAt: row.started_at,
+        finishedAt: row.finished_at,
+    };
+}
+
+/**
+ * Store (or update) one `mcp_tool_call` item of a turn. Called for every event of the
+ * item, so the row follows it from in_progress to completed/failed. Never throws: a
+ * transcript problem must not break the turn.
+ */
+export function recordMcpCall(
+    db: Database.Database,
+    turnId: string,
+    conversationId: string,
+    item: any,
+): McpCallRecord | null {
+    try {
+        const status = (item.status || 'in_progress').toLowerCase();
+        const done = status === 'completed' || status === 'failed';
+        const request = capPayload(item.arguments);
+        const response = item.result !== undefined && item.result !== null ? capPayload(item.result) : null;
+        const error = item.error ? capPayload(item.error?.message ?? item.error).text : null;
+        const id = `${turnId}:${item.id || `${item.server}:${item.tool}`}`;
+
+        db.prepare(`
+    INSERT INTO mcp_calls (id, turn_id, conversation_id, server, tool, status, request, response, error,
+      request_bytes, response_bytes, truncated, started_at, finished_at)
+    VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+    ON CONFLICT(id) DO UPDATE SET
+      status = excluded.status,
+      request = excluded.request,
+      response = COALESCE(excluded.response, mcp_calls.response),
+      error = COALESCE(excluded.error, mcp_calls.error),
+      request_bytes = excluded.request_bytes,
+      response_bytes = MAX(excluded.response_bytes, mcp_calls.response_bytes),
+      truncated = MAX(excluded.truncated, mcp_calls.truncated),
+      finished_at = COALESCE(mcp_calls.finished_at, excluded.finished_at)
+  `).run(
+            id,
+            turnId,
+            conversationId,
+            item.server || 'mcp',
+            item.tool || 'tool',
+            status,
+            request.text,
+            response?.text ?? null,
+            error,
+            request.bytes,
+            response?.bytes ?? 0,
+            request.truncated || response?.truncated ? 1 : 0,
+            nowIso(),
+            done ? nowIso() : null,
+        );
+        return rowToCall(db.prepare('SELECT * FROM mcp_calls WHERE id = ?').get(id));
+    } catch (err) {
+        console.error('[mcp] Failed to record MCP call:', err);
+        return null;
+    }
+}
+
+export function getMcpCalls(db: Database.Database, turnId: string): McpCallRecord[] {
+    return db
+        .prepare('SELECT * FROM mcp_calls WHERE turn_id = ? ORDER BY CAST(started_at AS INTEGER), rowid')
+        .all(turnId)
+        .map(rowToCall);
+}
+
+// ===== Server health =====
+
+export type McpServerHealth = 'healthy' | 'unhealthy' | 'disabled' | 'checking';
+
+export interface McpServerStatus {
+    name: string;
+    transport: 'stdio' | 'remote';
+    status: McpServerHealth;
+    /** Server name/version from the initialize response, when it answered. */
+    serverInfo: string | null;
+    latencyMs: number | null;
+    error: string | null;
+    checkedAt: string;
+}
+
+const MCP_CHECK_INTERVAL_MS = 5 * 60_000;
+const DEFAULT_HANDSHAKE_TIMEOUT_MS = 10_000;
+
+const lastStatus = new Map<string, McpServerStatus>();
+const probes = new Map<string, ChildProcess>();
+
+/** `host` / `port` to dial for a remote server URL. */
+export function remoteEndpoint(url: string): { host: string; port: number } {
+    const parsed = new URL(url);
+    const port = parsed.port ? Number(parsed.port) : parsed.protocol === 'https:' ? 443 : 80;
+    return { host: parsed.hostname, port };
+}
+
+function tcpCheck(url: string, timeoutMs: number): Promise<{ serverInfo: string | null }> {
+    return new Promise((resolve, reject) => {
+        const { host, port } = remoteEndpoint(url);
+        const socket = net.connect({ host, port });
+        socket.setTimeout(timeoutMs);
+        socket.once('connect', () => {
+            socket.destroy();
+            resolve({ serverInfo: null });
+        });
+        socket.once('timeout', () => {
+            socket.destroy();
+            reject(new Error(`No connection to ${host}:${port} within ${timeoutMs} ms`));
+        });
+        socket.once('error', reject);
+    });
+}
+
+/**
+ * Launch a stdio server, send the MCP `initialize` request and wait for its answer.
+ * The probe process is killed either way.
+ */
+function handshakeCheck(server: McpServerConfig, timeoutMs: number): Promise<{ serverInfo: string | null }> {
+    return new Promise((resolve, reject) => {
+        const opts = commandSpawnOptions();
+        const child = trackChild(spawn(server.command!, server.args, {
+            ...opts,
+            env: { ...opts.env, ...server.env },
+            stdio: ['pipe', 'pipe', 'pipe'],
+        }));
+        probes.set(server.name, child);
+        let stdout = '';
+        let stderr = '';
+        let settled = false;
+        const finish = (err: Error | null, serverInfo: string | null = null) => {
+            if (settled) return;
+            settled = true;
+            clearTimeout(timer);
+            probes.delete(server.name);
+            try {
+                child.kill();
+            } catch { }
+            if (err) reject(err);
+            else resolve({ serverInfo });
+        };
+        const timer = setTimeout(() => {
+            finish(new Error(`No initialize response within ${timeoutMs} ms${stderr ? `: ${stderr.trim().slice(-300)}` : ''}`));
+        }, timeoutMs);
+
+        child.stdout?.on('data', (d: Buffer) => {
+            stdout += d.toString();
+            let newline: number;
+            while ((newline = stdout.indexOf('\n')) >= 0) {
+                const line = stdout.slice(0, newline).trim();
+                stdout = stdout.slice(newline + 1);
+                try {
+                    const message = JSON.parse(line);
+                    if (message.id !== 1) continue;
+                    if (message.error) return finish(new Error(message.error.message || 'initialize failed'));
+                    const info = message.result?.serverInfo;
+                    return finish(null, info ? `${info.name}${info.version ? ` ${info.version}` : ''}` : null);
+                } catch {
+                    // Servers may log non-JSON lines to stdout; keep waiting.
+                }
+            }
+        });
+        child.stderr?.on('data', (d: Buffer) => (stderr = (stderr + d.toString()).slice(-2000)));
+        child.on('error', (err) => finish(err));
+        child.on('exit', (code) => {
+            finish(new Error(`Exited with code ${code} before answering${stderr ? `: ${stderr.trim().slice(-300)}` : ''}`));
+        });
+
+        // A server that dies at once closes stdin under us; the exit handler reports it.
+        child.stdin?.on('error', () => { });
+        child.stdin?.write(
+            JSON.stringify({
+                jsonrpc: '2.0',
+                id: 1,
+                method: 'initialize',
+                params: {
+                    protocolVersion: '2025-06-18',
+                    capabilities: {},
+                    clientInfo: { name: 'codex-wui', version: 'health-check' },
+                },
+            }) + '\n',
+        );
+    });
+}
+
+export async function checkMcpServer(server: McpServerConfig): Promise<McpServerStatus> {
+    const base = { name: server.name, transport: server.url ? 'remote' as const : 'stdio' as const };
+    if (!server.enabled) {
+        return { ...base, status: 'disabled', serverInfo: null, latencyMs: null, error: null, checkedAt: nowIso() };
+    }
+    const timeoutMs = server.startupTimeoutSec ? server.startupTimeoutSec * 1000 : DEFAULT_HANDSHAKE_TIMEOUT_MS;
+    const started = Date.now();
+    try {
+        if (!server.url && !server.command) throw new Error('Neither command nor url is configured');
+        const { serverInfo } = server.url
+            ? await tcpCheck(server.url, timeoutMs)
+            : await handshakeCheck(server, timeoutMs);
+        return { ...base, status: 'healthy', serverInfo, latencyMs: Date.now() - started, error: null, checkedAt: nowIso() };
+    } catch (err: any) {
+        return { ...base, status: 'unhealthy', serverInfo: null, latencyMs: null, error: err.message, checkedAt: nowIso() };
+    }
+}
+
+async function checkAndReport(getWebContents: () => WebContents | null, server: McpServerConfig): Promise<McpServerStatus> {
+    const status = await checkMcpServer(server);
+    lastStatus.set(server.name, status);
+    getWebContents()?.send('mcp-server-status', status);
+    return status;
+}
+
+/** Latest known status of every configured server. */
+export function getMcpServerStatuses(): McpServerStatus[] {
+    return getMcpServers().map(
+        (server) =>
+            lastStatus.get(server.name) || {
+                name: server.name,
+                transport: server.url ? 'remote' : 'stdio',
+                status: server.enabled ? 'checking' : 'disabled',
+                serverInfo: null,
+                latencyMs: null,
+                error: null,
+                checkedAt: '',
+            },
+    );
+}
+
+/**
+ * Codex launches MCP servers itself for every turn, so there is no long-lived process
+ * to restart here: this kills a probe that may be hanging and checks the server again
+ * right away, so a fixed server shows up as healthy without waiting for the next round.
+ */
+export async function restartMcpServer(
+    getWebContents: () => WebContents | null,
+    name: string,
+): Promise<{ success: boolean; status?: McpServerStatus; error?: string }> {
+    const server = getMcpServers().find((s) => s.name === name);
+    if (!server) return { success: false, error: `MCP server not configured: ${name}` };
+    try {
+        probes.get(name)?.kill();
+    } catch { }
+    const status = await checkAndReport(getWebContents, server);
+    return { success: status.status !== 'unhealthy', status, error: status.error || undefined };
+}
+
+/**
+ * Check every configured MCP server now and then every few minutes, emitting
+ * `mcp-server-status` events. Returns a stop function.
+ */
+export function startMcpMonitor(getWebContents: () => WebContents | null): () => void {
+    let running = false;
+    const checkAll = async () => {
+        if (running) return;
+        running = true;
+        try {
+            const servers = getMcpServers();
+            for (const name of Array.from(lastStatus.keys())) {
+                if (!servers.some((s) => s.name === name)) lastStatus.delete(name);
+            }
+            await Promise.all(servers.map((server) => checkAndReport(getWebContents, server)));
+        } finally {
+            running = false;
+        }
+    };
+    checkAll().catch((err) => console.error('[mcp] Health check failed:', err));
+    const timer = setInterval(() => {
+        checkAll().catch((err) => console.error('[mcp] Health check failed:', err));
+    }, MCP_CHECK_INTERVAL_MS);
+    timer.unref();
+    return () => {
+        clearInterval(timer);
+        for (const child of probes.values()) {
+            try {
+                child.kill();
+            } catch { }
+        }
+        probes.clear();
+    };
+}
@@ -0,0 +1,138 @@
+import * as fs from 'fs';
+import * as path from 'path';
+import { isProtectedPath, listDirectory, readFileContent, resolveWorkspaceScopedPath } from './fs-ops';
+import { expandTildePath } from './utils';
+import { IGNORED_DIRS } from './watch';
+
+/** Files over this are mentioned by path only; codex can still read them itself. */
+const MAX_MENTION_FILE_BYTES = 32 * 1024;
+const MAX_LISTING_ENTRIES = 100;
+/** All inlined files and listings of one prompt together. */
+const MAX_CONTEXT_CHARS = 128 * 1024;
+
+// `@path` at the start of a word, so e-mail addresses never match.
+const MENTION_TOKEN = /(?:^|[\s(])@([^\s"'`()<>\[\]{},;]+)/g;
+
+export type MentionSkipReason = 'outside-workspace' | 'protected' | 'binary' | 'too-large' | 'over-budget' | 'unreadable';
+
+/** One `@mention` of a prompt, as reported with `codex-context-attached`. */
+export interface ContextMention {
+    mention: string;
+    /** Workspace-relative, with a trailing `/` for directories. */
+    path: string;
+    kind: 'file' | 'directory';
+    included: boolean;
+    skipped?: MentionSkipReason;
+    /** Size of a file, or the number of entries of a directory. */
+    size: number;
+    /** A directory listing cut at the entry limit. */
+    truncated?: boolean;
+}
+
+/** The distinct `@path` mentions of a prompt, trailing punctuation removed. */
+export function findMentions(prompt: string): string[] {
+    const found = Array.from(prompt.matchAll(MENTION_TOKEN), (match) => match[1].replace(/[.,:!?]+$/, ''));
+    return [...new Set(found.filter(Boolean))];
+}
+
+function formatSize(bytes: number): string {
+    if (bytes < 1024) return `${bytes} B`;
+    if (bytes < 1024 * 1024) return `${(bytes / 1024).toFixed(1)} KB`;
+    return `${(bytes / 1024 / 1024).toFixed(1)} MB`;
+}
+
+function directoryListing(resolved: string, root: string): { lines: string[]; count: number; truncated: boolean } | null {
+    const listed = listDirectory(resolved, root);
+    if (!listed.success) return null;
+    const entries = listed.entries!
+        .filter((entry) => !entry.isHidden && !IGNORED_DIRS.has(entry.name) && !isProtectedPath(entry.path, root))
+        .sort((a, b) => Number(b.isDirectory) - Number(a.isDirectory) || a.name.localeCompare(b.name));
+    const lines = entries
+        .slice(0, MAX_LISTING_ENTRIES)
+        .map((entry) => (entry.isDirectory ? `${entry.name}/` : `${entry.name} (${formatSize(entry.size)})`));
+    return { lines, count: entries.length, truncated: entries.length > MAX_LISTING_ENTRIES };
+}
+
+/**
+ * Resolve the `@file` and `@dir/` mentions of a prompt against the workspace: small text
+ * files are inlined and directories get a one-level listing, appended to the prompt as
+ * `[Attached context]`. Mentions that name nothing in the workspace are left as plain
+ * text and not reported; the others are reported, with why they were left out if so.
+ */
+export function attachMentionedContext(
+    prompt: string,
+    workspaceRoot: string,
+): { prompt: string; mentions: ContextMention[] } {
+    const root = path.resolve(expandTildePath(workspaceRoot));
+    const mentions: ContextMention[] = [];
+    const sections: string[] = [];
+    let used = 0;
+
+    for (const mention of findMentions(prompt)) {
+        const expanded = expandTildePath(mention);
+        const target = path.isAbsolute(expanded) ? expanded : path.resolve(root, expanded);
+        if (!fs.existsSync(target)) continue;
+        const isDirectory = fs.statSync(target).isDirectory();
+        const relative = path.relative(root, target).split(path.sep).join('/') || '.';
+        const entry: ContextMention = {
+            mention,
+            path: isDirectory ? `${relative.replace(/\/$/, '')}/` : relative,
+            kind: isDirectory ? 'directory' : 'file',
+            included: false,
+            size: 0,
+        };
+        mentions.push(entry);
+
+        let resolved: string;
+        try {
+            resolved = resolveWorkspaceScopedPath(mention, root);
+        } catch {
+            entry.skipped = 'outside-workspace';
+            continue;
+        }
+
+        let section: string;
+        if (isDirectory) {
+            const listing = directoryListing(resolved, root);
+            if (!listing) {
+                entry.skipped = 'unreadable';
+                continue;
+            }
+            entry.size = listing.count;
+            entry.truncated = listing.truncated || undefined;
+            const more = listing.truncated ? [`... and ${listing.count - MAX_LISTING_ENTRIES} more`] : [];
+            section = [`### ${entry.path} (directory)`, ...listing.lines, ...more].join('\n');
+        } else {
+            entry.size = fs.statSync(resolved).size;
+            if (isProtectedPath(resolved, root)) {
+                entry.skipped = 'protected';
+                continue;
+            }
+            if (entry.size > MAX_MENTION_FILE_BYTES) {
+                entry.skipped = 'too-large';
+                continue;
+            }
+            const read = readFileContent(resolved, root);
+            if (!read.success) {
+                entry.skipped = read.protected ? 'protected' : 'unreadable';
+                continue;
+            }
+            if (read.binary) {
+                entry.skipped = 'binary';
+                continue;
+            }
+            section = [`### ${entry.path}`, '```', read.content!.replace(/\n$/, ''), '```'].join('\n');
+        }
+
+        if (used + section.length > MAX_CONTEXT_CHARS) {
+            entry.skipped = 'over-budget';
+            continue;
+        }
+        used += section.length;
+        sections.push(section);
+        entry.included = true;
+    }
+
+    if (sections.length === 0) return { prompt, mentions };
+    return { prompt: `${prompt}\n\n[Attached context]\n${sections.join('\n\n')}`, mentions };
+}
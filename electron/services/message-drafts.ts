@@ -0,0 +1,67 @@
+import Database from 'better-sqlite3';
+import { dbFinalizeMessageDraft, dbSaveMessageDraft } from './db';
+import { generateId, nowIso, StreamParseCache } from './utils';
+
+/** How often a streaming turn's output is written to its draft message. */
+export const DRAFT_SAVE_INTERVAL_MS = 2000;
+
+/**
+ * The assistant message of a running turn, written from the stream cache every few
+ * seconds while it streams so a crash keeps the partial output. `codex-stream-end`
+ * carries `messageId`; the renderer saves the final message under that id, replacing
+ * the draft.
+ */
+export class MessageDraft {
+    readonly messageId = generateId('msg');
+    private readonly timestamp = nowIso();
+    private timer: NodeJS.Timeout | null = null;
+    private saved = { content: '', thinking: '' };
+    private finished = false;
+
+    constructor(
+        private readonly db: Database.Database,
+        private readonly conversationId: string,
+        private readonly cache: StreamParseCache,
+    ) { }
+
+    /** Schedule a save of what streamed since the last one. */
+    touch(): void {
+        if (this.timer || this.finished) return;
+        this.timer = setTimeout(() => {
+            this.timer = null;
+            this.save();
+        }, DRAFT_SAVE_INTERVAL_MS);
+    }
+
+    save(): void {
+        const content = this.cache.agentText;
+        const thinking = this.cache.thinkingText;
+        if (this.finished || (content === this.saved.content && thinking === this.saved.thinking)) return;
+        try {
+            dbSaveMessageDraft(this.db, {
+                id: this.messageId,
+                conversationId: this.conversationId,
+                content,
+                thinking,
+                timestamp: this.timestamp,
+            });
+            this.saved = { content, thinking };
+        } catch (err) {
+            console.error('[drafts] Failed to save the draft message:', err);
+        }
+    }
+
+    /** Write the last of the output and clear the in-progress flag; an empty draft is removed. */
+    finish(): void {
+        if (this.finished) return;
+        if (this.timer) clearTimeout(this.timer);
+        this.timer = null;
+        this.save();
+        this.finished = true;
+        try {
+            dbFinalizeMessageDraft(this.db, this.messageId);
+        } catch (err) {
+            console.error('[drafts] Failed to finalize the draft message:', err);
+        }
+    }
+}
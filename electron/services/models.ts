@@ -1,5 +1,7 @@
 import { ChildProcess } from 'child_process';
 import Database from 'better-sqlite3';
+import type { MessageDraft } from './message-drafts';
+import type { StreamParseCache } from './utils';
 
 export interface ModelInfo {
   id: string;
@@ -15,6 +17,34 @@ export interface CliOptions {
   cwdOverride: string;
   extraArgs: string;
   enableWebSearch: boolean;
+  reasoningEffort?: string;
+  /** Agent CLI that runs turns; unset means codex. */
+  agent?: AgentBackendId;
+}
+
+export type AgentBackendId = 'codex' | 'claude' | 'gemini';
+
+export type CliOverrides = Partial<CliOptions> & { model?: string };
+
+/** Workspace-level defaults, applied between the global config and conversation overrides. */
+export type WorkspaceCliOptions = CliOverrides & { yoloMode?: boolean };
+
+/** An image sent with a prompt. `path` points at the stored copy under ~/.codex-wui/attachments. */
+export interface MessageAttachment {
+  id: string;
+  kind: 'image';
+  name: string;
+  path: string;
+  mimeType: string;
+  size: number;
+}
+
+/** Attachment as passed to stream_codex: a file path or a base64 blob (optionally a data URL). */
+export interface PromptAttachment {
+  path?: string;
+  data?: string;
+  name?: string;
+  mimeType?: string;
 }
 
 export interface Message {
@@ -24,6 +54,14 @@ export interface Message {
   content: string;
   thinking?: string;
   thinkingDuration?: number;
+  attachments?: MessageAttachment[];
+  /** Set when `content` is only the first part of a segmented message. */
+  partCount?: number;
+  /**
+   * A draft of a turn's output, saved while it streams. Still set with no turn running,
+   * the app stopped before the turn ended.
+   */
+  inProgress?: boolean;
   timestamp: string;
 }
 
@@ -33,18 +71,97 @@ export interface Conversation {
   title: string;
   createdAt: string;
   updatedAt: string;
+  persona?: string;
+  cliOverrides?: CliOverrides;
+  autoTranslateLang?: string;
+  codexSessionId?: string;
+  targetPackage?: WorkspacePackage;
+  /** Directory codex runs in, relative to the workspace root; unset runs at the root. */
+  cwd?: string;
+  /** Conversation this one was forked from. */
+  forkedFrom?: string;
+  archived?: boolean;
+  pinned?: boolean;
+  /** When the last message was added (epoch seconds, like the other timestamps). */
+  lastActivityAt?: string;
+  lastReadMessageId?: string;
+  /** Assistant messages after `lastReadMessageId`. */
+  unreadCount?: number;
+  /** All messages of the conversation; `messages` may hold only some (see dbGetState). */
+  messageCount?: number;
   messages: Message[];
 }
 
+export interface WorkspacePackage {
+  name: string;
+  kind: 'cargo' | 'node' | 'nx';
+  /** Relative to the workspace root. */
+  path: string;
+  absolutePath: string;
+  /** Empty when the package defines no tests. */
+  testCommand: string;
+}
+
+export interface LaunchPreset {
+  id: string;
+  name: string;
+  starred: boolean;
+  options: CliOverrides;
+  createdAt: string;
+}
+
+export interface TemplateMessage {
+  role: string;
+  content: string;
+}
+
+export interface ConversationTemplate {
+  id: string;
+  name: string;
+  titlePattern: string;
+  systemMessage?: string;
+  initialMessages: TemplateMessage[];
+  persona?: string;
+  cliOverrides?: CliOverrides;
+  createdAt: string;
+  updatedAt: string;
+}
+
+/** A reusable prompt; `{{file}}`, `{{selection}}` and `{{workspace}}` are filled in when rendered. */
+export interface PromptTemplate {
+  id: string;
+  name: string;
+  body: string;
+  /** The template's own placeholders, filled in from the values passed to render. */
+  variables: string[];
+  createdAt: string;
+  updatedAt: string;
+}
+
 export interface Workspace {
   id: string;
   name: string;
   path: string;
+  /** Change counter of the workspace; it moves whenever anything in the workspace is written. */
+  etag?: string;
   conversations: Conversation[];
 }
 
+export interface MessageSearchResult {
+  messageId: string;
+  conversationId: string;
+  conversationTitle: string;
+  workspaceId: string;
+  workspaceName: string;
+  role: string;
+  timestamp: string;
+  snippet: string;
+}
+
 export interface DbState {
   workspaces: Workspace[];
+  /** Pass to dbGetChanges to get what changed after this state was read. */
+  syncToken?: string;
 }
 
 export interface FileSearchResult {
@@ -54,11 +171,81 @@ export interface FileSearchResult {
   isDirectory: boolean;
 }
 
+export interface ContentSearchOptions {
+  /** Treat the query as a regular expression instead of a literal string. */
+  regex?: boolean;
+  caseSensitive?: boolean;
+  /** Lines of context before and after each match (default 2). */
+  contextLines?: number;
+  /** Stop after this many matches (default 200). */
+  maxResults?: number;
+  /** Glob filter such as `*.ts` or `src/**`. */
+  glob?: string;
+}
+
+export interface ContentSearchMatch {
+  path: string;
+  relativePath: string;
+  lineNumber: number;
+  /** 1-based column of the first match on the line. */
+  column: number;
+  line: string;
+  before: string[];
+  after: string[];
+}
+
+export type DirectoryEntryType = 'file' | 'directory' | 'symlink' | 'other';
+
 export interface DirectoryEntry {
   name: string;
   path: string;
+  /** For a followed symlink, whether its target is a directory. */
   isDirectory: boolean;
   size: number;
+  /** Type of the entry itself; symlinks report 'symlink' whether followed or not. */
+  fileType: DirectoryEntryType;
+  /** Link text as stored in the symlink; null for other entries. */
+  symlinkTarget: string | null;
+  /** The symlink points at nothing (or a loop). */
+  brokenLink: boolean;
+  isHidden: boolean;
+  /** Permission bits (e.g. 0o644). */
+  mode: number;
+  readonly: boolean;
+  modifiedAt: string;
+}
+
+export interface HexRow {
+  offset: number;
+  /** Space-separated byte pairs, up to 16 per row. */
+  hex: string;
+  /** Printable ASCII with '.' for everything else. */
+  ascii: string;
+}
+
+export interface HexPage {
+  offset: number;
+  length: number;
+  totalSize: number;
+  /** Offset of the next page, or null at end of file. */
+  nextOffset: number | null;
+  rows: HexRow[];
+}
+
+export type FileEncoding = 'utf-8' | 'utf-16le' | 'utf-16be' | 'latin1' | 'binary';
+
+export interface FileRange {
+  /** Start of the returned text; moved forward to a character boundary when needed. */
+  offset: number;
+  /** Bytes covered by `content`. */
+  length: number;
+  totalSize: number;
+  /** Offset to continue from, or null at end of file. */
+  nextOffset: number | null;
+  /** Detected from the start of the file, so every range of a file agrees. */
+  encoding: FileEncoding;
+  /** Decoded text; null for binary files. */
+  content: string | null;
 }
 
 export interface SearchResult {
@@ -67,6 +254,63 @@ export interface SearchResult {
   snippet: string;
 }
 
+export type WebSearchProvider = 'duckduckgo' | 'brave' | 'searxng' | 'google' | 'bing';
+
+export interface WebSearchSettings {
+  provider: WebSearchProvider;
+  maxResults: number;
+  braveApiKey: string;
+  /** Base URL of a SearXNG instance with the JSON format enabled. */
+  searxngUrl: string;
+  googleApiKey: string;
+  /** Programmable Search Engine ID. */
+  googleCx: string;
+  bingApiKey: string;
+  bingEndpoint: string;
+}
+
+export interface TranslationSettings {
+  provider: 'ollama' | 'openai';
+  endpoint: string;
+  model: string;
+  apiKey: string;
+}
+
+export interface MessageStats {
+  messageId: string;
+  length: number;
+  words: number;
+  readingSeconds: number;
+  codeLines: number;
+  proseLines: number;
+  codeRatio: number;
+  filesTouched: number;
+  reviewRecommended: boolean;
+}
+
+export interface TokenUsage {
+  promptTokens: number;
+  cachedTokens: number;
+  completionTokens: number;
+  totalTokens: number;
+}
+
+export interface TurnUsage extends TokenUsage {
+  model: string;
+}
+
+/** A codex JSON event of a turn as it was streamed, kept to re-render the turn's timeline. */
+export interface TurnEvent {
+  /** Order within the turn, from 1. */
+  seq: number;
+  type: string;
+  /** Type and id of the item the event is about, when it is an item event. */
+  itemType: string | null;
+  itemId: string | null;
+  event: unknown;
+  createdAt: string;
+}
+
 export interface CodexUser {
   id: string;
   email: string;
@@ -74,6 +318,10 @@ export interface CodexUser {
   picture: string;
   authMode: string;
   authProvider: string;
+  /** When the sign-in's token runs out; unset for API keys. */
+  expiresAt?: string;
+  /** The token has run out; codex has to refresh it or sign in again. */
+  expired: boolean;
 }
 
 export interface CommandResult {
@@ -89,6 +337,9 @@ export interface ShellCommandResult {
   commandId: string;
   output?: string;
   errorOutput?: string;
+  /** Full stdout/stderr when it outgrew the in-memory limit; `output`/`errorOutput` hold the start. */
+  outputFile?: string;
+  errorOutputFile?: string;
   exitCode?: number;
   error?: string;
 }
@@ -102,12 +353,142 @@ export interface RuntimeConfig {
 }
 
 export interface RunningCodexProcess {
-  child: ChildProcess;
+  /** null until the work tree's pre-turn state is recorded and the CLI spawned. */
+  child: ChildProcess | null;
   stdin: NodeJS.WritableStream | null;
+  turnId: string;
+  /** ISO time the CLI was spawned. */
+  startedAt?: string;
+  /** Set by a graceful cancel while the CLI gets its grace period to flush. */
+  interrupting?: boolean;
+  /** Set when a cancel or a replacing turn killed the CLI; its exit code is not a failure. */
+  cancelled?: boolean;
+  /** What the turn has streamed so far, for getStreamSnapshot. */
+  stream?: StreamParseCache;
+  /** The turn's assistant message, saved as it streams. */
+  draft?: MessageDraft;
+  /** Set for the planning and the approved pass of a safety review. */
+  review?: TurnReview;
+}
+
+/** Commands and files approved for the write pass of a safety review. */
+export interface ReviewScope {
+  /** Exact command lines; one ending in `*` approves every command starting with the rest. */
+  commands: string[];
+  /** Paths relative to the turn's cwd; one ending in `/` approves everything below it. */
+  files: string[];
+}
+
+/** One step of a reviewed plan and the commands and files it needs. */
+export interface PlanStep {
+  title: string;
+  commands: string[];
+  files: string[];
+}
+
+export type TurnReview =
+  | { phase: 'plan'; prompt: string; task: string; runConfig: RuntimeConfig }
+  | { phase: 'execute'; scope: ReviewScope };
+
+/** A codex-* event as it was sent, kept for a reloaded UI to replay. */
+export interface BufferedStreamEvent {
+  seq: number;
+  channel: string;
+  payload: unknown;
+}
+
+/** Recent codex-* events per conversation, numbered by one app-wide sequence. */
+export interface StreamReplayBuffer {
+  lastSeq: number;
+  conversations: Map<string, { events: BufferedStreamEvent[]; droppedThrough: number }>;
+}
+
+/** A read-only planning pass that finished and waits for the user's approval. */
+export interface PendingReview {
+  conversationId: string;
+  /** The planning turn. */
+  turnId: string;
+  /** The prompt as sent, and with notes and persona applied. */
+  prompt: string;
+  task: string;
+  conversationHistory?: Array<{ role: string; content: string }>;
+  images: string[];
+  /** The conversation's own config, which the approved pass runs under within its scope. */
+  runConfig: RuntimeConfig;
+  plan: string;
+  proposed: ReviewScope;
+  /** The plan's steps in order; executePlan runs a chosen subset. */
+  steps: PlanStep[];
+  createdAt: string;
+}
+
+export interface StreamToolCall {
+  id: string;
+  title: string;
+  status: 'running' | 'done' | 'error';
+  output: string;
+  /** Command executions also drive a terminal view. */
+  terminalId?: string;
+  exitCode?: number | null;
+}
+
+/** Live state of a conversation's turn, for a UI that reloaded mid-stream. */
+export interface StreamSnapshot {
+  cid: string;
+  running: boolean;
+  turnId: string | null;
+  interrupting: boolean;
+  text: string;
+  thinking: string;
+  /** text or thinking stopped growing at the low-memory cap. */
+  truncated: boolean;
+  toolCalls: StreamToolCall[];
+  approvals: Array<{ requestId: string; title: string; description: string }>;
+}
+
+export type ApprovalDecision = 'approve' | 'approve_for_session' | 'deny' | 'abort';
+
+export interface ApprovalRequestEvent {
+  requestId: string;
+  title: string;
+  description: string;
+  /** For JSON-RPC requests: the id exactly as sent (number or string), echoed in the response. */
+  rpcId?: string | number;
+  method?: string;
+  params?: unknown;
+  /** Whether the request carried `"jsonrpc": "2.0"`; codex's own transport omits it. */
+  jsonrpc?: boolean;
 }
 
 export interface PendingApproval {
   conversationId: string;
+  request: ApprovalRequestEvent;
+}
+
+export interface QueuedPrompt {
+  /** Also the id of the turn recorded for this prompt. */
+  id: string;
+  prompt: string;
+  conversationHistory?: Array<{ role: string; content: string }>;
+  attachments?: PromptAttachment[];
+  queuedAt: string;
+  /** Held back by the concurrent turn limit rather than by a running turn of its conversation. */
+  waitingForSlot?: boolean;
+  /** Runs as a read-only planning pass whatever the review mode; see streamCodexPlan. */
+  plan?: boolean;
+}
+
+/** A conversation's turn that is running, or waiting for one of the concurrent turn slots. */
+export interface RunningTurnStatus {
+  conversationId: string;
+  turnId: string;
+  status: 'running' | 'waiting';
+  /** When it started running, or when it was queued. */
+  since?: string;
+  /** 1-based place among all waiting turns, which start oldest first. */
+  position?: number;
+  /** Further prompts queued behind it in the same conversation. */
+  queued: number;
 }
 
 export interface AppState {
@@ -116,4 +497,15 @@ export interface AppState {
   runningCodex: Map<string, RunningCodexProcess>;
   pendingApprovals: Map<string, PendingApproval>;
   ptyTerminals: Map<string, ChildProcess>;
+  turnFileChanges: Map<string, string[]>;
+  turnUsage: Map<string, TurnUsage>;
+  promptQueues: Map<string, QueuedPrompt[]>;
+  /** Turns running at once across conversations; 0 is unlimited. The low-memory limit applies too. */
+  maxConcurrentTurns: number;
+  runningCommands: Map<string, ChildProcess>;
+  /** Scratch directories allocated per conversation, removed when its turn ends. */
+  tempDirs: Map<string, string[]>;
+  pendingReviews: Map<string, PendingReview>;
+  /** Recently sent conversation events, for getStreamBuffer. */
+  streamBuffer: StreamReplayBuffer;
 }
@@ -0,0 +1,180 @@
+import Database from 'better-sqlite3';
+import { WebContents } from 'electron';
+import { dbGetSetting, dbSetSetting } from './db';
+
+const NARRATION_KEY = 'accessibility.narration';
+const PROGRESS_DEBOUNCE_MS = 1500;
+const SUMMARY_CHARS = 200;
+
+/** One line for a screen reader; `assertive` ones should interrupt, `polite` ones wait. */
+export interface Narration {
+    cid: string;
+    text: string;
+    priority: 'polite' | 'assertive';
+}
+
+interface TurnNarration {
+    files: Set<string>;
+    /** Files already announced by a progress narration. */
+    announcedFiles: number;
+    commands: number;
+    /** The start of the answer, for the summary when the turn finishes. */
+    text: string;
+}
+
+function plural(count: number, noun: string): string {
+    return `${count} ${noun}${count === 1 ? '' : 's'}`;
+}
+
+function firstSentence(text: string): string {
+    const line = text.replace(/\s+/g, ' ').trim();
+    const sentence = line.match(/^.*?[.!?](\s|$)/)?.[0].trim() || line;
+    return sentence.length > SUMMARY_CHARS ? `${sentence.slice(0, SUMMARY_CHARS - 1)}…` : sentence;
+}
+
+/**
+ * Condenses a conversation's raw stream events into a few narrations: turn started,
+ * files changed, awaiting approval, and turn finished with a summary. File changes are
+ * held back and reported together by `progress`.
+ */
+export class Narrator {
+    private turns = new Map<string, TurnNarration>();
+
+    private turn(cid: string): TurnNarration {
+        let turn = this.turns.get(cid);
+        if (!turn) {
+            turn = { files: new Set(), announcedFiles: 0, commands: 0, text: '' };
+            this.turns.set(cid, turn);
+        }
+        return turn;
+    }
+
+    /** Narrations the event produces right away; pending progress is reported first. */
+    observe(channel: string, payload: any): Narration[] {
+        const cid = payload?.cid;
+        if (typeof cid !== 'string') return [];
+        switch (channel) {
+            case 'codex-turn-status':
+                if (payload.status !== 'running') return [];
+                this.turns.delete(cid);
+                this.turn(cid);
+                return [{ cid, text: 'Turn started', priority: 'polite' }];
+            case 'codex-stream-delta': {
+                const turn = this.turn(cid);
+                if (turn.text.length < SUMMARY_CHARS * 2) turn.text += payload.data || '';
+                return [];
+            }
+            case 'codex-tool-call':
+                if (payload.title !== 'file_change' && (payload.status === 'done' || payload.status === 'error')) {
+                    this.turn(cid).commands++;
+                }
+                return [];
+            case 'codex-file-diff':
+                if (typeof payload.path === 'string') this.turn(cid).files.add(payload.path);
+                return [];
+            case 'codex-approval-request':
+                return this.withProgress(cid, {
+                    cid,
+                    text: `Awaiting approval: ${firstSentence(payload.title || payload.description || 'Codex request')}`,
+                    priority: 'assertive',
+                });
+            case 'codex-review-request':
+                return this.withProgress(cid, {
+                    cid,
+                    text: `Plan ready for review: ${plural(payload.commands?.length || 0, 'command')}, ${plural(payload.files?.length || 0, 'file')}`,
+                    priority: 'assertive',
+                });
+            case 'codex-stream-end': {
+                const turn = this.turns.get(cid);
+                this.turns.delete(cid);
+                if (payload.cancelled) return [{ cid, text: 'Turn cancelled', priority: 'assertive' }];
+                const parts = ['Turn finished.'];
+                if (turn?.files.size) parts.push(`${plural(turn.files.size, 'file')} changed.`);
+                if (turn?.commands) parts.push(`${plural(turn.commands, 'command')} run.`);
+                if (turn?.text.trim()) parts.push(firstSentence(turn.text));
+                return [{ cid, text: parts.join(' '), priority: 'assertive' }];
+            }
+            case 'codex-stream-error':
+                this.turns.delete(cid);
+                return [{ cid, text: `Turn failed: ${firstSentence(payload.data || 'Codex failed')}`, priority: 'assertive' }];
+            default:
+                return [];
+        }
+    }
+
+    /** Whether files changed that no narration has announced yet. */
+    hasProgress(cid: string): boolean {
+        const turn = this.turns.get(cid);
+        return !!turn && turn.files.size > turn.announcedFiles;
+    }
+
+    /** Files changed since the last progress narration, or null if there are none. */
+    progress(cid: string): Narration | null {
+        const turn = this.turns.get(cid);
+        if (!turn || turn.files.size <= turn.announcedFiles) return null;
+        turn.announcedFiles = turn.files.size;
+        return { cid, text: `${plural(turn.files.size, 'file')} changed so far`, priority: 'polite' };
+    }
+
+    private withProgress(cid: string, narration: Narration): Narration[] {
+        const progress = this.progress(cid);
+        return progress ? [progress, narration] : [narration];
+    }
+}
+
+let enabled = false;
+const narrator = new Narrator();
+const progressTimers = new Map<string, NodeJS.Timeout>();
+
+export function initNarration(db: Database.Database): void {
+    enabled = dbGetSetting(db, NARRATION_KEY, false);
+}
+
+export function getNarrationEnabled(): boolean {
+    return enabled;
+}
+
+/** Turn the `a11y-narration` channel on or off; it is off unless a frontend asks for it. */
+export function setNarrationEnabled(db: Database.Database, value: boolean): boolean {
+    enabled = value;
+    dbSetSetting(db, NARRATION_KEY, value);
+    return value;
+}
+
+/**
+ * Wrap a WebContents so stream events also feed the narrator, whose narrations are sent
+ * on `a11y-narration`. Progress goes out once no file has changed for a moment.
+ */
+export function tapNarration(webContents: WebContents): WebContents {
+    const send = (narration: Narration) => {
+        if (!webContents.isDestroyed()) webContents.send('a11y-narration', narration);
+    };
+    return new Proxy(webContents, {
+        get(target, prop) {
+            if (prop === 'send') {
+                return (channel: string, ...args: any[]) => {
+                    target.send(channel, ...args);
+                    if (!enabled) return;
+                    const cid = args[0]?.cid;
+                    const narrations = narrator.observe(channel, args[0]);
+                    if (narrations.length > 0) {
+                        // Immediate narrations already include any pending progress.
+                        clearTimeout(progressTimers.get(cid));
+                        progressTimers.delete(cid);
+                        narrations.forEach(send);
+                    }
+                    if (channel === 'codex-file-diff' && narrator.hasProgress(cid)) {
+                        clearTimeout(progressTimers.get(cid));
+                        progressTimers.set(cid, setTimeout(() => {
+                            progressTimers.delete(cid);
+                            const progress = narrator.progress(cid);
+                            if (progress) send(progress);
+                        }, PROGRESS_DEBOUNCE_MS));
+                    }
+                };
+            }
+            const value = Reflect.get(target, prop);
+            return typeof value === 'function' ? value.bind(target) : value;
+        },
+    });
+}
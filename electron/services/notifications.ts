@@ -0,0 +1,305 @@
+import Database from 'better-sqlite3';
+import { BrowserWindow, Notification, WebContents } from 'electron';
+import { AppState } from './models';
+import { dbGetSetting, dbSetSetting } from './db';
+import { sendToTeams } from './teams';
+import { getArtifactTheme } from './themes';
+import { generateId } from './utils';
+
+export interface NotificationPrefs {
+    streamEnd: boolean;
+    streamError: boolean;
+    approvalRequest: boolean;
+    /** Stay quiet while the app window has focus. */
+    onlyWhenUnfocused: boolean;
+}
+
+const PREFS_KEY = 'notifications.prefs';
+const MAX_BODY = 200;
+
+const DEFAULT_PREFS: NotificationPrefs = {
+    streamEnd: true,
+    streamError: true,
+    approvalRequest: true,
+    onlyWhenUnfocused: true,
+};
+
+const CHANNEL_PREF: Record<string, keyof NotificationPrefs> = {
+    'codex-stream-end': 'streamEnd',
+    'codex-stream-error': 'streamError',
+    'codex-approval-request': 'approvalRequest',
+};
+
+export function getNotificationPrefs(db: Database.Database): NotificationPrefs {
+    return { ...DEFAULT_PREFS, ...dbGetSetting<Partial<NotificationPrefs>>(db, PREFS_KEY, {}) };
+}
+
+export function setNotificationPrefs(db: Database.Database, prefs: Partial<NotificationPrefs>): NotificationPrefs {
+    const merged = { ...getNotificationPrefs(db) };
+    for (const key of Object.keys(DEFAULT_PREFS) as Array<keyof NotificationPrefs>) {
+        if (typeof prefs?.[key] === 'boolean') merged[key] = prefs[key]!;
+    }
+    dbSetSetting(db, PREFS_KEY, merged);
+    return merged;
+}
+
+function truncate(text: string): string {
+    const line = text.replace(/\s+/g, ' ').trim();
+    return line.length > MAX_BODY ? `${line.slice(0, MAX_BODY - 1)}…` : line;
+}
+
+/**
+ * Title and body for an OS notification about `channel`, or null when the event should
+ * not notify (unknown channel, disabled in prefs, cancelled turn).
+ */
+export function describeNotification(
+    prefs: NotificationPrefs,
+    channel: string,
+    payload: any,
+    conversationTitle: string,
+): { title: string; body: string } | null {
+    const pref = CHANNEL_PREF[channel];
+    if (!pref || !prefs[pref]) return null;
+    switch (channel) {
+        case 'codex-stream-end':
+            if (payload?.cancelled) return null;
+            return { title: conversationTitle, body: 'Turn finished' };
+        case 'codex-stream-error':
+            return { title: conversationTitle, body: truncate(`Error: ${payload?.data || 'Codex failed'}`) };
+        case 'codex-approval-request':
+            return {
+                title: conversationTitle,
+                body: truncate(`Needs approval: ${payload?.title || payload?.description || 'Codex request'}`),
+            };
+        default:
+            return null;
+    }
+}
+
+/**
+ * Show an OS notification for turn-completion, error and approval events. Clicking it
+ * brings the window forward and emits `notification-clicked` so the renderer can open
+ * the conversation.
+ */
+export function notifyForEvent(state: AppState, window: BrowserWindow | null, channel: string, payload: any): void {
+    if (!CHANNEL_PREF[channel] || !Notification.isSupported()) return;
+    try {
+        const prefs = getNotificationPrefs(state.db);
+        if (prefs.onlyWhenUnfocused && window && window.isFocused()) return;
+
+        const cid = payload?.cid;
+        const row = cid ? (state.db.prepare('SELECT title FROM conversations WHERE id = ?').get(cid) as any) : null;
+        const content = describeNotification(prefs, channel, payload, row?.title || 'Codex');
+        if (!content) return;
+
+        const notification = new Notification({ title: content.title, body: content.body });
+        notification.on('click', () => {
+            if (!window || window.isDestroyed()) return;
+            if (window.isMinimized()) window.restore();
+            window.show();
+            window.focus();
+            window.webContents.send('notification-clicked', { cid, channel });
+        });
+        notification.show();
+    } catch (err) {
+        console.error('[notifications] Failed to show notification:', err);
+    }
+}
+
+// ===== Routing =====
+
+export type RouteEvent = 'error' | 'completion' | 'approval';
+
+export interface NotificationSink {
+    type: 'teams' | 'slack' | 'webhook';
+    url: string;
+}
+
+/** Where a workspace's turn events are reported; `*` applies to every workspace. */
+export interface NotificationRoute {
+    id: string;
+    name: string;
+    workspaceId: string;
+    events: RouteEvent[];
+    sinks: NotificationSink[];
+    enabled: boolean;
+}
+
+export interface SinkResult {
+    sink: NotificationSink;
+    success: boolean;
+    error?: string;
+}
+
+const ROUTES_KEY = 'notifications.routes';
+const ROUTE_EVENTS: RouteEvent[] = ['error', 'completion', 'approval'];
+const SINK_TYPES: Array<NotificationSink['type']> = ['teams', 'slack', 'webhook'];
+
+const CHANNEL_EVENT: Record<string, RouteEvent> = {
+    'codex-stream-end': 'completion',
+    'codex-stream-error': 'error',
+    'codex-approval-request': 'approval',
+};
+
+export function getNotificationRoutes(db: Database.Database): NotificationRoute[] {
+    return dbGetSetting<NotificationRoute[]>(db, ROUTES_KEY, []);
+}
+
+/** Add a route, or replace the one with the same id. */
+export function saveNotificationRoute(
+    db: Database.Database,
+    route: Partial<NotificationRoute>,
+): { success: boolean; route?: NotificationRoute; error?: string } {
+    const events = (route.events || []).filter((e) => ROUTE_EVENTS.includes(e));
+    const sinks = (route.sinks || []).filter((s) => SINK_TYPES.includes(s?.type as NotificationSink['type']) && /^https?:\/\//.test(s?.url || ''));
+    if (!route.workspaceId) return { success: false, error: 'workspaceId is required (or * for every workspace)' };
+    if (events.length === 0) return { success: false, error: `events must include one of: ${ROUTE_EVENTS.join(', ')}` };
+    if (sinks.length === 0 || sinks.length !== (route.sinks || []).length) {
+        return { success: false, error: 'Every sink needs a type (teams, slack or webhook) and an http(s) URL' };
+    }
+
+    const saved: NotificationRoute = {
+        id: route.id || generateId('route'),
+        name: route.name?.trim() || '',
+        workspaceId: route.workspaceId,
+        events,
+        sinks: sinks.map((s) => ({ type: s.type, url: s.url.trim() })),
+        enabled: route.enabled !== false,
+    };
+    const routes = getNotificationRoutes(db).filter((r) => r.id !== saved.id);
+    dbSetSetting(db, ROUTES_KEY, [...routes, saved]);
+    return { success: true, route: saved };
+}
+
+export function deleteNotificationRoute(db: Database.Database, id: string): { success: boolean } {
+    const routes = getNotificationRoutes(db);
+    const remaining = routes.filter((r) => r.id !== id);
+    dbSetSetting(db, ROUTES_KEY, remaining);
+    return { success: remaining.length < routes.length };
+}
+
+/** The sinks an event of a workspace goes to, each once even if several routes name it. */
+export function routeSinks(routes: NotificationRoute[], workspaceId: string, event: RouteEvent): NotificationSink[] {
+    const sinks = new Map<string, NotificationSink>();
+    for (const route of routes) {
+        if (!route.enabled || !route.events.includes(event)) continue;
+        if (route.workspaceId !== '*' && route.workspaceId !== workspaceId) continue;
+        for (const sink of route.sinks) sinks.set(`${sink.type} ${sink.url}`, sink);
+    }
+    return Array.from(sinks.values());
+}
+
+async function sendToSink(
+    db: Database.Database,
+    sink: NotificationSink,
+    title: string,
+    body: string,
+    details: Record<string, unknown>,
+): Promise<SinkResult> {
+    try {
+        if (sink.type === 'teams') {
+            const result = await sendToTeams(sink.url, title, body, getArtifactTheme(db));
+            return { sink, success: result.success, error: result.error };
+        }
+        const payload = sink.type === 'slack' ? { text: `*${title}*\n${body}` } : { title, text: body, ...details };
+        const response = await fetch(sink.url, {
+            method: 'POST',
+            headers: { 'Content-Type': 'application/json' },
+            body: JSON.stringify(payload),
+        });
+        return response.ok
+            ? { sink, success: true }
+            : { sink, success: false, error: `HTTP ${response.status}: ${await response.text()}` };
+    } catch (err: any) {
+        return { sink, success: false, error: `Request failed: ${err.message}` };
+    }
+}
+
+function routeBody(event: RouteEvent, payload: any): string {
+    switch (event) {
+        case 'completion':
+            return 'Turn finished';
+        case 'error':
+            return truncate(`Error: ${payload?.data || 'Codex failed'}`);
+        case 'approval':
+            return truncate(`Needs approval: ${payload?.title || payload?.description || 'Codex request'}`);
+    }
+}
+
+/**
+ * Send a conversation event to the sinks its workspace's routes name. Cancelled turns
+ * are not reported. Failures are logged; they never reach the turn.
+ */
+export function routeEvent(state: AppState, channel: string, payload: any): void {
+    const event = CHANNEL_EVENT[channel];
+    const cid = payload?.cid;
+    if (!event || !cid || (event === 'completion' && payload?.cancelled)) return;
+    try {
+        const routes = getNotificationRoutes(state.db);
+        if (routes.length === 0) return;
+        const row = state.db.prepare(`
+      SELECT c.title, c.workspace_id, w.name AS workspace_name
+      FROM conversations c JOIN workspaces w ON w.id = c.workspace_id
+      WHERE c.id = ?
+    `).get(cid) as any;
+        if (!row) return;
+        const sinks = routeSinks(routes, row.workspace_id, event);
+        const title = `${row.workspace_name} · ${row.title}`;
+        const body = routeBody(event, payload);
+        for (const sink of sinks) {
+            sendToSink(state.db, sink, title, body, { event, workspaceId: row.workspace_id, conversationId: cid }).then((result) => {
+                if (!result.success) console.error(`[notifications] ${sink.type} route failed:`, result.error);
+            });
+        }
+    } catch (err) {
+        console.error('[notifications] Failed to route event:', err);
+    }
+}
+
+/**
+ * Which sinks an event of the workspace would go to. Unless `dryRun`, a test message is
+ * sent to each of them and the per-sink results come back.
+ */
+export async function testNotificationRoute(
+    db: Database.Database,
+    workspaceId: string,
+    event: RouteEvent,
+    dryRun = false,
+): Promise<{ success: boolean; results: SinkResult[]; error?: string }> {
+    if (!ROUTE_EVENTS.includes(event)) return { success: false, results: [], error: `Unknown event: ${event}` };
+    const sinks = routeSinks(getNotificationRoutes(db), workspaceId, event);
+    if (dryRun) return { success: true, results: sinks.map((sink) => ({ sink, success: true })) };
+    const row = db.prepare('SELECT name FROM workspaces WHERE id = ?').get(workspaceId) as any;
+    const results = await Promise.all(sinks.map((sink) => sendToSink(
+        db,
+        sink,
+        `${row?.name || workspaceId} · Test`,
+        `Test ${event} notification from Codex WUI`,
+        { event, workspaceId, test: true },
+    )));
+    return { success: results.every((r) => r.success), results };
+}
+
+/**
+ * Wrap a WebContents so notifying events are also passed to notifyForEvent and the
+ * workspace's notification routes.
+ */
+export function tapNotifications(
+    webContents: WebContents,
+    state: AppState,
+    getWindow: () => BrowserWindow | null,
+): WebContents {
+    return new Proxy(webContents, {
+        get(target, prop) {
+            if (prop === 'send') {
+                return (channel: string, ...args: any[]) => {
+                    target.send(channel, ...args);
+                    notifyForEvent(state, getWindow(), channel, args[0]);
+                    routeEvent(state, channel, args[0]);
+                };
+            }
+            const value = Reflect.get(target, prop);
+            return typeof value === 'function' ? value.bind(target) : value;
+        },
+    });
+}
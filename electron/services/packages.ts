@@ -0,0 +1,230 @@
+import { WebContents } from 'electron';
+import * as fs from 'fs';
+import * as path from 'path';
+import { AppState, WorkspacePackage } from './models';
+import { dbGetConversationOverrides, dbSetConversationTargetPackage } from './db';
+import { startCommand } from './shell';
+import { expandTildePath } from './utils';
+
+const SKIP_DIRS = new Set(['node_modules', '.git', 'target', 'dist', 'build', '.next', '.turbo']);
+const NX_SCAN_DEPTH = 3;
+
+function readText(file: string): string | null {
+    try {
+        return fs.readFileSync(file, 'utf-8');
+    } catch {
+        return null;
+    }
+}
+
+function readJson(file: string): any {
+    const text = readText(file);
+    if (text === null) return null;
+    try {
+        return JSON.parse(text);
+    } catch {
+        return null;
+    }
+}
+
+function listDirs(dir: string): string[] {
+    try {
+        return fs
+            .readdirSync(dir, { withFileTypes: true })
+            .filter((e) => e.isDirectory() && !SKIP_DIRS.has(e.name) && !e.name.startsWith('.'))
+            .map((e) => e.name);
+    } catch {
+        return [];
+    }
+}
+
+/**
+ * Expand workspace member globs (`packages/*`, `crates/**`, plain paths) to directories
+ * relative to `root`. `!pattern` entries remove matches.
+ */
+export function expandMemberGlobs(root: string, patterns: string[]): string[] {
+    const expand = (base: string, segments: string[]): string[] => {
+        if (segments.length === 0) return [base];
+        const [head, ...rest] = segments;
+        const dir = path.join(root, base);
+        if (head === '**') {
+            const deeper = listDirs(dir).flatMap((name) => expand(path.join(base, name), segments));
+            return [...expand(base, rest), ...deeper];
+        }
+        if (head.includes('*')) {
+            const re = new RegExp(`^${head.split('*').map((s) => s.replace(/[.+?^${}()|[\]\\]/g, '\\$&')).join('.*')}$`);
+            return listDirs(dir)
+                .filter((name) => re.test(name))
+                .flatMap((name) => expand(path.join(base, name), rest));
+        }
+        return expand(path.join(base, head), rest);
+    };
+
+    const included = new Set<string>();
+    const excluded = new Set<string>();
+    for (const raw of patterns) {
+        const negated = raw.startsWith('!');
+        const pattern = (negated ? raw.slice(1) : raw).replace(/^\.\//, '').replace(/\/+$/, '');
+        if (!pattern) continue;
+        for (const match of expand('', pattern.split('/'))) {
+            if (!fs.existsSync(path.join(root, match))) continue;
+            (negated ? excluded : included).add(match.split(path.sep).join('/'));
+        }
+    }
+    return Array.from(included).filter((p) => !excluded.has(p)).sort();
+}
+
+/** `members = [...]` / `exclude = [...]` from the `[workspace]` table of a Cargo.toml. */
+export function parseCargoWorkspace(text: string): { members: string[]; exclude: string[] } {
+    const section = text.match(/^\[workspace\]\s*$([\s\S]*?)(?=^\[|(?![\s\S]))/m)?.[1] || '';
+    const list = (key: string) => {
+        const body = section.match(new RegExp(`^${key}\\s*=\\s*\\[([\\s\\S]*?)\\]`, 'm'))?.[1] || '';
+        return Array.from(body.matchAll(/"([^"]+)"/g), (m) => m[1]);
+    };
+    return { members: list('members'), exclude: list('exclude') };
+}
+
+/** `packages:` entries of a pnpm-workspace.yaml. */
+export function parsePnpmWorkspace(text: string): string[] {
+    const packages: string[] = [];
+    let inPackages = false;
+    for (const line of text.split('\n')) {
+        if (/^packages\s*:/.test(line)) {
+            inPackages = true;
+            continue;
+        }
+        if (inPackages) {
+            const item = line.match(/^\s+-\s*['"]?([^'"#]+?)['"]?\s*(#.*)?$/);
+            if (item) packages.push(item[1]);
+            else if (line.trim() && !/^\s/.test(line)) inPackages = false;
+        }
+    }
+    return packages;
+}
+
+function nodeRunner(root: string): string {
+    if (fs.existsSync(path.join(root, 'pnpm-lock.yaml'))) return 'pnpm';
+    if (fs.existsSync(path.join(root, 'yarn.lock'))) return 'yarn';
+    if (fs.existsSync(path.join(root, 'bun.lockb'))) return 'bun';
+    return 'npm';
+}
+
+function findNxProjects(root: string, rel: string, depth: number, out: string[]): void {
+    if (depth > NX_SCAN_DEPTH) return;
+    for (const name of listDirs(path.join(root, rel))) {
+        const child = rel ? `${rel}/${name}` : name;
+        if (fs.existsSync(path.join(root, child, 'project.json'))) out.push(child);
+        findNxProjects(root, child, depth + 1, out);
+    }
+}
+
+/**
+ * Packages of a monorepo: Cargo workspace members, pnpm / yarn / npm workspaces (which
+ * also covers turbo) and nx projects. Each comes with the command that tests just it.
+ */
+export function detectPackages(workspacePath: string): WorkspacePackage[] {
+    const root = expandTildePath(workspacePath);
+    const found = new Map<string, WorkspacePackage>();
+    const add = (pkg: Omit<WorkspacePackage, 'absolutePath'>) => {
+        if (!pkg.path || found.has(pkg.path)) return;
+        found.set(pkg.path, { ...pkg, absolutePath: path.join(root, pkg.path) });
+    };
+
+    const cargoToml = readText(path.join(root, 'Cargo.toml'));
+    if (cargoToml) {
+        const { members, exclude } = parseCargoWorkspace(cargoToml);
+        for (const rel of expandMemberGlobs(root, [...members, ...exclude.map((e) => `!${e}`)])) {
+            const manifest = readText(path.join(root, rel, 'Cargo.toml'));
+            if (!manifest) continue;
+            const name = manifest.match(/^name\s*=\s*"([^"]+)"/m)?.[1] || path.basename(rel);
+            add({ name, kind: 'cargo', path: rel, testCommand: `cargo test -p ${name}` });
+        }
+    }
+
+    const pnpmYaml = readText(path.join(root, 'pnpm-workspace.yaml'));
+    const rootPackageJson = readJson(path.join(root, 'package.json'));
+    const workspaces = rootPackageJson?.workspaces;
+    const nodePatterns = pnpmYaml
+        ? parsePnpmWorkspace(pnpmYaml)
+        : Array.isArray(workspaces)
+            ? workspaces
+            : Array.isArray(workspaces?.packages)
+                ? workspaces.packages
+                : [];
+    if (nodePatterns.length) {
+        const runner = nodeRunner(root);
+        for (const rel of expandMemberGlobs(root, nodePatterns)) {
+            const pkgJson = readJson(path.join(root, rel, 'package.json'));
+            if (!pkgJson) continue;
+            const name = pkgJson.name || path.basename(rel);
+            const testCommand = pkgJson.scripts?.test ? `${runner} test` : '';
+            add({ name, kind: 'node', path: rel, testCommand });
+        }
+    }
+
+    if (fs.existsSync(path.join(root, 'nx.json'))) {
+        const projects: string[] = [];
+        findNxProjects(root, '', 1, projects);
+        for (const rel of projects) {
+            const project = readJson(path.join(root, rel, 'project.json')) || {};
+            const name = project.name || path.basename(rel);
+            const testCommand = project.targets?.test ? `npx nx test ${name}` : '';
+            add({ name, kind: 'nx', path: rel, testCommand });
+        }
+    }
+
+    return Array.from(found.values()).sort((a, b) => a.path.localeCompare(b.path));
+}
+
+/**
+ * Select (or with `packagePath` null, clear) the package a conversation targets. The path
+ * must be one of the packages detected in the conversation's workspace.
+ */
+export function setConversationTargetPackage(
+    state: AppState,
+    conversationId: string,
+    packagePath: string | null,
+): { success: boolean; targetPackage?: WorkspacePackage | null; error?: string } {
+    if (packagePath === null) {
+        dbSetConversationTargetPackage(state.db, conversationId, null);
+        return { success: true, targetPackage: null };
+    }
+    const row = state.db
+        .prepare('SELECT w.path FROM conversations c JOIN workspaces w ON w.id = c.workspace_id WHERE c.id = ?')
+        .get(conversationId) as any;
+    if (!row) return { success: false, error: 'Conversation not found' };
+
+    const pkg = detectPackages(row.path).find((p) => p.path === packagePath);
+    if (!pkg) return { success: false, error: `Package not found: ${packagePath}` };
+    dbSetConversationTargetPackage(state.db, conversationId, pkg);
+    return { success: true, targetPackage: pkg };
+}
+
+/**
+ * Directory a conversation's work is scoped to: its own cwd when one is set, else its
+ * target package when one is selected, otherwise the workspace root.
+ */
+export function conversationScope(state: AppState, conversationId: string, workspacePath: string): string {
+    const { cwd, targetPackage } = dbGetConversationOverrides(state.db, conversationId);
+    if (cwd && fs.existsSync(cwd)) return cwd;
+    if (targetPackage && fs.existsSync(targetPackage.absolutePath)) return targetPackage.absolutePath;
+    return expandTildePath(workspacePath);
+}
+
+/**
+ * Run the target package's test command from the package directory. Output is streamed
+ * through the regular command events.
+ */
+export function runTargetTests(
+    webContents: WebContents,
+    state: AppState,
+    conversationId: string,
+): { success: boolean; commandId?: string; error?: string } {
+    const { targetPackage } = dbGetConversationOverrides(state.db, conversationId);
+    if (!targetPackage) return { success: false, error: 'No target package selected' };
+    if (!targetPackage.testCommand) {
+        return { success: false, error: `${targetPackage.name} has no test command` };
+    }
+    const { commandId } = startCommand(webContents, targetPackage.testCommand, targetPackage.absolutePath, state);
+    return { success: true, commandId };
+}
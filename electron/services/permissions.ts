@@ -0,0 +1,162 @@
+import Database from 'better-sqlite3';
+import { BrowserWindow, dialog } from 'electron';
+import * as path from 'path';
+import { expandTildePath, nowIso } from './utils';
+
+export type Capability = 'write' | 'delete' | 'pty' | 'run';
+
+export const CAPABILITIES: Capability[] = ['write', 'delete', 'pty', 'run'];
+
+const CAPABILITY_LABELS: Record<Capability, string> = {
+    write: 'write files',
+    delete: 'delete files and folders',
+    pty: 'open terminals',
+    run: 'run shell commands',
+};
+
+export interface PermissionGrant {
+    workspacePath: string;
+    capability: Capability;
+    grantedAt: string;
+}
+
+export interface PermissionDenied {
+    success: false;
+    error: string;
+    permissionDenied: { workspacePath: string; capability: Capability };
+}
+
+function normalizeWorkspacePath(workspacePath: string): string {
+    return path.resolve(expandTildePath(workspacePath));
+}
+
+function assertCapability(capability: string): asserts capability is Capability {
+    if (!CAPABILITIES.includes(capability as Capability)) {
+        throw new Error(`Unknown capability: ${capability}`);
+    }
+}
+
+export function hasPermission(
+    db: Database.Database,
+    workspacePath: string,
+    capability: Capability,
+): boolean {
+    const row = db
+        .prepare('SELECT 1 FROM workspace_permissions WHERE workspace_path = ? AND capability = ?')
+        .get(normalizeWorkspacePath(workspacePath), capability);
+    return !!row;
+}
+
+/**
+ * Record a capability for a workspace. Only the main process calls this, after the user
+ * allowed it in requirePermission's dialog; the renderer can revoke but never grant.
+ */
+export function grantPermission(
+    db: Database.Database,
+    workspacePath: string,
+    capability: string,
+): { success: boolean; error?: string } {
+    try {
+        assertCapability(capability);
+        db.prepare(`
+      INSERT INTO workspace_permissions (workspace_path, capability, granted_at)
+      VALUES (?, ?, ?)
+      ON CONFLICT(workspace_path, capability) DO UPDATE SET granted_at = excluded.granted_at
+    `).run(normalizeWorkspacePath(workspacePath), capability, nowIso());
+        return { success: true };
+    } catch (err: any) {
+        return { success: false, error: err.message };
+    }
+}
+
+export function revokePermission(
+    db: Database.Database,
+    workspacePath: string,
+    capability?: string,
+): { success: boolean; error?: string } {
+    try {
+        const normalized = normalizeWorkspacePath(workspacePath);
+        if (capability) {
+            assertCapability(capability);
+            db.prepare(
+                'DELETE FROM workspace_permissions WHERE workspace_path = ? AND capability = ?',
+            ).run(normalized, capability);
+        } else {
+            db.prepare('DELETE FROM workspace_permissions WHERE workspace_path = ?').run(normalized);
+        }
+        return { success: true };
+    } catch (err: any) {
+        return { success: false, error: err.message };
+    }
+}
+
+export function listPermissions(db: Database.Database, workspacePath?: string): PermissionGrant[] {
+    const rows = workspacePath
+        ? db
+            .prepare('SELECT * FROM workspace_permissions WHERE workspace_path = ? ORDER BY capability')
+            .all(normalizeWorkspacePath(workspacePath))
+        : db.prepare('SELECT * FROM workspace_permissions ORDER BY workspace_path, capability').all();
+    return rows.map((row: any) => ({
+        workspacePath: row.workspace_path,
+        capability: row.capability,
+        grantedAt: row.granted_at,
+    }));
+}
+
+/**
+ * Browser-style permission check: the first use of a capability in a workspace asks the
+ * user via a native dialog and remembers an "Allow" answer. Returns a denial result the
+ * caller can hand straight back to the renderer, or null when the action may proceed.
+ */
+export async function requirePermission(
+    db: Database.Database,
+    window: BrowserWindow | null,
+    workspacePath: string | undefined,
+    capability: Capability,
+): Promise<PermissionDenied | null> {
+    if (!workspacePath) {
+        return {
+            success: false,
+            error: 'workspacePath is required',
+            permissionDenied: { workspacePath: '', capability },
+        };
+    }
+    if (hasPermission(db, workspacePath, capability)) return null;
+
+    const normalized = normalizeWorkspacePath(workspacePath);
+    const options = {
+        type: 'question' as const,
+        buttons: ['Allow', 'Deny'],
+        defaultId: 1,
+        cancelId: 1,
+        title: 'Permission required',
+        message: `Allow Codex UI to ${CAPABILITY_LABELS[capability]} in this workspace?`,
+        detail: normalized,
+    };
+    const { response } = window
+        ? await dialog.showMessageBox(window, options)
+        : await dialog.showMessageBox(options);
+    return answerPermission(db, normalized, capability, response === 0);
+}
+
+/**
+ * Apply the user's answer to a permission prompt: "Allow" is remembered for the
+ * workspace, "Deny" is not, so the next use asks again.
+ */
+export function answerPermission(
+    db: Database.Database,
+    workspacePath: string,
+    capability: Capability,
+    allowed: boolean,
+): PermissionDenied | null {
+    const normalized = normalizeWorkspacePath(workspacePath);
+    if (allowed) {
+        grantPermission(db, normalized, capability);
+        return null;
+    }
+    return {
+        success: false,
+        error: `Permission to ${CAPABILITY_LABELS[capability]} was denied`,
+        permissionDenied: { workspacePath: normalized, capability },
+    };
+}
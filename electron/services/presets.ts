@@ -0,0 +1,75 @@
+import Database from 'better-sqlite3';
+import { CliOverrides, LaunchPreset } from './models';
+import { dbGetConversationOverrides, dbUpdateConversationCliOverrides, parseJsonColumn } from './db';
+import { generateId, nowIso } from './utils';
+
+function rowToPreset(row: any): LaunchPreset {
+    return {
+        id: row.id,
+        name: row.name,
+        starred: !!row.starred,
+        options: parseJsonColumn<CliOverrides>(row.options) || {},
+        createdAt: row.created_at,
+    };
+}
+
+/**
+ * Starred presets first, then alphabetical.
+ */
+export function listPresets(db: Database.Database): LaunchPreset[] {
+    return db
+        .prepare('SELECT * FROM launch_presets ORDER BY starred DESC, name COLLATE NOCASE ASC')
+        .all()
+        .map(rowToPreset);
+}
+
+export function savePreset(
+    db: Database.Database,
+    preset: { id?: string; name: string; starred?: boolean; options: CliOverrides },
+): LaunchPreset {
+    const id = preset.id || generateId('preset');
+    db.prepare(`
+    INSERT INTO launch_presets (id, name, starred, options, created_at)
+    VALUES (?, ?, ?, ?, ?)
+    ON CONFLICT(id) DO UPDATE SET
+      name = excluded.name,
+      starred = excluded.starred,
+      options = excluded.options
+  `).run(id, preset.name, preset.starred ? 1 : 0, JSON.stringify(preset.options || {}), nowIso());
+    return rowToPreset(db.prepare('SELECT * FROM launch_presets WHERE id = ?').get(id));
+}
+
+export function setPresetStarred(
+    db: Database.Database,
+    id: string,
+    starred: boolean,
+): { success: boolean } {
+    db.prepare('UPDATE launch_presets SET starred = ? WHERE id = ?').run(starred ? 1 : 0, id);
+    return { success: true };
+}
+
+export function deletePreset(db: Database.Database, id: string): { success: boolean } {
+    db.prepare('DELETE FROM launch_presets WHERE id = ?').run(id);
+    return { success: true };
+}
+
+/**
+ * Copy a preset's model/sandbox/approval/effort/args onto a conversation's overrides.
+ * Options the preset does not set keep their current per-conversation values.
+ */
+export function applyPreset(
+    db: Database.Database,
+    presetId: string,
+    conversationId: string,
+): { success: boolean; cliOverrides?: CliOverrides; error?: string } {
+    const row = db.prepare('SELECT * FROM launch_presets WHERE id = ?').get(presetId);
+    if (!row) return { success: false, error: 'Preset not found' };
+    const preset = rowToPreset(row);
+
+    const { cliOverrides } = dbGetConversationOverrides(db, conversationId);
+    const merged: CliOverrides = { ...(cliOverrides || {}), ...preset.options };
+    dbUpdateConversationCliOverrides(db, conversationId, merged);
+    // Later turns of the conversation are counted under this preset in the outcome stats.
+    db.prepare('UPDATE conversations SET preset_id = ? WHERE id = ?').run(presetId, conversationId);
+    return { success: true, cliOverrides: merged };
+}
@@ -0,0 +1,112 @@
+import Database from 'better-sqlite3';
+import { nowIso } from './utils';
+
+export interface PromptHistoryEntry {
+    id: number;
+    workspaceId: string;
+    prompt: string;
+    useCount: number;
+    lastUsedAt: string;
+}
+
+const FUZZY_SCAN_LIMIT = 1000;
+
+function rowToEntry(row: any): PromptHistoryEntry {
+    return {
+        id: row.id,
+        workspaceId: row.workspace_id,
+        prompt: row.prompt,
+        useCount: row.use_count,
+        lastUsedAt: row.last_used_at,
+    };
+}
+
+/**
+ * Store a submitted prompt. Re-submitting the same text only bumps its count and recency.
+ */
+export function recordPrompt(db: Database.Database, conversationId: string, prompt: string): void {
+    const text = prompt.trim();
+    if (!text) return;
+    try {
+        const conv = db
+            .prepare('SELECT workspace_id FROM conversations WHERE id = ?')
+            .get(conversationId) as any;
+        db.prepare(`
+      INSERT INTO prompt_history (workspace_id, prompt, use_count, last_used_at)
+      VALUES (?, ?, 1, ?)
+      ON CONFLICT(workspace_id, prompt) DO UPDATE SET
+        use_count = use_count + 1,
+        last_used_at = excluded.last_used_at
+    `).run(conv?.workspace_id || '', text, nowIso());
+    } catch (error) {
+        console.error('[prompt-history] Failed to record prompt:', error);
+    }
+}
+
+/**
+ * Score how well `query` matches `text` as an in-order subsequence; 0 means no match.
+ * Consecutive and word-start hits score higher.
+ */
+export function fuzzyScore(query: string, text: string): number {
+    const q = query.toLowerCase();
+    const t = text.toLowerCase();
+    let score = 0;
+    let ti = 0;
+    let prev = -2;
+    for (const ch of q) {
+        const found = t.indexOf(ch, ti);
+        if (found < 0) return 0;
+        score += 1;
+        if (found === prev + 1) score += 2;
+        if (found === 0 || /\W/.test(t[found - 1])) score += 1;
+        prev = found;
+        ti = found + 1;
+    }
+    return score;
+}
+
+/**
+ * Most recent prompts first. With a prefix, exact prefix matches come first (for
+ * up-arrow recall) followed by fuzzy matches ranked by score.
+ */
+export function getPromptHistory(
+    db: Database.Database,
+    workspaceId?: string,
+    prefix?: string,
+    limit = 50,
+): PromptHistoryEntry[] {
+    const rows = (
+        workspaceId
+            ? db
+                .prepare(`SELECT * FROM prompt_history WHERE workspace_id = ?
+                  ORDER BY CAST(last_used_at AS INTEGER) DESC, id DESC LIMIT ?`)
+                .all(workspaceId, FUZZY_SCAN_LIMIT)
+            : db
+                .prepare(`SELECT * FROM prompt_history
+                  ORDER BY CAST(last_used_at AS INTEGER) DESC, id DESC LIMIT ?`)
+                .all(FUZZY_SCAN_LIMIT)
+    ).map(rowToEntry);
+
+    const needle = prefix?.trim();
+    if (!needle) return rows.slice(0, limit);
+
+    const lower = needle.toLowerCase();
+    const prefixed = rows.filter((r) => r.prompt.toLowerCase().startsWith(lower));
+    const fuzzy = rows
+        .filter((r) => !r.prompt.toLowerCase().startsWith(lower))
+        .map((r) => ({ r, score: fuzzyScore(needle, r.prompt) }))
+        .filter((x) => x.score > 0)
+        .sort((a, b) => b.score - a.score)
+        .map((x) => x.r);
+
+    return [...prefixed, ...fuzzy].slice(0, limit);
+}
+
+export function clearPromptHistory(db: Database.Database, workspaceId?: string): { success: boolean } {
+    if (workspaceId) {
+        db.prepare('DELETE FROM prompt_history WHERE workspace_id = ?').run(workspaceId);
+    } else {
+        db.prepare('DELETE FROM prompt_history').run();
+    }
+    return { success: true };
+}
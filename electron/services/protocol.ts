@@ -0,0 +1,323 @@
+import { commandOutput, tryExtractApprovalRequest } from './utils';
+
+/** First codex release whose `exec --json` prints thread/turn/item events instead of `{id, msg}`. */
+export const ITEM_EVENTS_VERSION = '0.44.0';
+/** Newest codex release the mappings below were checked against. */
+export const NEWEST_KNOWN_CODEX_VERSION = '0.50.0';
+/** Older releases print `{id, msg}` events, which are only translated best-effort. */
+export const MIN_SUPPORTED_CODEX_VERSION = ITEM_EVENTS_VERSION;
+/** How long the latest release read from npm is reused. */
+const LATEST_VERSION_TTL_MS = 60 * 60 * 1000;
+
+export type CodexProtocolId = 'exec-msg' | 'exec-items';
+
+export interface CodexProtocolInfo {
+    /** `codex --version`, or null until it has been read (or when codex is missing). */
+    version: string | null;
+    protocol: CodexProtocolId;
+    newestKnownVersion: string;
+}
+
+/** Sent as `codex-protocol-mismatch` once per turn when codex output cannot be read. */
+export interface ProtocolMismatch {
+    cliVersion: string | null;
+    protocol: CodexProtocolId;
+    advice: 'update-cli' | 'update-app' | 'update-either';
+    message: string;
+    /** What did not match, e.g. `unknown event "item.delta"`, at most a few. */
+    details: string[];
+}
+
+/** What `get-codex-version` reports about the installed CLI. */
+export interface CodexVersionInfo {
+    installed: boolean;
+    version: string | null;
+    /** The latest `@openai/codex` on npm, or null when npm could not be asked. */
+    latest: string | null;
+    updateAvailable: boolean;
+    minimumVersion: string;
+    /** False when the CLI is older than `minimumVersion`; `warning` says why it matters. */
+    supported: boolean;
+    warning?: string;
+}
+
+const KNOWN_EVENT_TYPES = new Set([
+    'thread.started',
+    'turn.started',
+    'turn.completed',
+    'turn.failed',
+    'item.started',
+    'item.updated',
+    'item.completed',
+    'item.streaming',
+    'error',
+]);
+const KNOWN_ITEM_TYPES = new Set([
+    'agent_message',
+    'message',
+    'reasoning',
+    'command_execution',
+    'file_change',
+    'mcp_tool_call',
+    'web_search',
+    'todo_list',
+    'error',
+]);
+/** `{id, msg}` events that carry nothing the app shows, or that a later event repeats. */
+const IGNORED_MSG_TYPES = new Set([
+    'task_started',
+    'agent_message_delta',
+    'agent_reasoning_delta',
+    'agent_reasoning_raw_content',
+    'agent_reasoning_raw_content_delta',
+    'agent_reasoning_section_break',
+    'exec_command_output_delta',
+    'background_event',
+    'turn_diff',
+    'user_message',
+    'plan_update',
+    'get_history_entry_response',
+    'shutdown_complete',
+]);
+const MAX_DETAILS = 5;
+
+export function compareVersions(a: string, b: string): number {
+    const pa = a.split('.').map((part) => parseInt(part, 10) || 0);
+    const pb = b.split('.').map((part) => parseInt(part, 10) || 0);
+    for (let i = 0; i < Math.max(pa.length, pb.length); i++) {
+        const diff = (pa[i] || 0) - (pb[i] || 0);
+        if (diff !== 0) return diff;
+    }
+    return 0;
+}
+
+/** `0.46.0` from `codex-cli 0.46.0` and the like. */
+export function parseCodexVersion(output: string): string | null {
+    return output.match(/(\d+\.\d+\.\d+)/)?.[1] ?? null;
+}
+
+export function protocolForVersion(version: string | null): CodexProtocolId {
+    return version && compareVersions(version, ITEM_EVENTS_VERSION) < 0 ? 'exec-msg' : 'exec-items';
+}
+
+// ===== Version handshake =====
+
+let detectedVersion: string | null = null;
+let detection: Promise<string | null> | null = null;
+
+/** Run `codex --version` once per app session (again after `resetCodexVersion`). */
+export function detectCodexVersion(): Promise<string | null> {
+    if (detection) return detection;
+    detection = commandOutput('codex', ['--version']).then((output) => {
+        detectedVersion = output === null ? null : parseCodexVersion(output);
+        if (detectedVersion && compareVersions(detectedVersion, MIN_SUPPORTED_CODEX_VERSION) < 0) {
+            console.error(`[protocol] ${versionWarning(detectedVersion)}`);
+        }
+        return detectedVersion;
+    });
+    return detection;
+}
+
+/** Forget the detected version, e.g. after codex was installed or updated. */
+export function resetCodexVersion(): void {
+    detectedVersion = null;
+    detection = null;
+}
+
+/**
+ * The handshake at the start of a stream: the version read so far and the protocol it
+ * implies. A turn started before `codex --version` answered reads the protocol from the
+ * events themselves.
+ */
+export function codexProtocolInfo(): CodexProtocolInfo {
+    if (!detection) void detectCodexVersion();
+    return { version: detectedVersion, protocol: protocolForVersion(detectedVersion), newestKnownVersion: NEWEST_KNOWN_CODEX_VERSION };
+}
+
+let latestVersion: { version: string; checkedAt: number } | null = null;
+
+/** The latest `@openai/codex` release on npm, reused for an hour; null when npm fails. */
+export async function fetchLatestCodexVersion(): Promise<string | null> {
+    if (latestVersion && Date.now() - latestVersion.checkedAt < LATEST_VERSION_TTL_MS) return latestVersion.version;
+    const output = await commandOutput('npm', ['view', '@openai/codex', 'version']);
+    const version = output === null ? null : parseCodexVersion(output);
+    if (version) latestVersion = { version, checkedAt: Date.now() };
+    return version;
+}
+
+function versionWarning(version: string): string {
+    return `codex ${version} is older than ${MIN_SUPPORTED_CODEX_VERSION}, the oldest release whose JSON events this app reads directly. Its output is translated best-effort; upgrade the codex CLI.`;
+}
+
+/** Compare an installed version with the latest release and the minimum supported one. */
+export function describeCodexVersion(version: string | null, latest: string | null): CodexVersionInfo {
+    const supported = !version || compareVersions(version, MIN_SUPPORTED_CODEX_VERSION) >= 0;
+    return {
+        installed: version !== null,
+        version,
+        latest,
+        updateAvailable: !!version && !!latest && compareVersions(latest, version) > 0,
+        minimumVersion: MIN_SUPPORTED_CODEX_VERSION,
+        supported,
+        warning: supported ? undefined : versionWarning(version!),
+    };
+}
+
+export async function getCodexVersion(): Promise<CodexVersionInfo> {
+    const [version, latest] = await Promise.all([detectCodexVersion(), fetchLatestCodexVersion()]);
+    return describeCodexVersion(version, latest);
+}
+
+export function describeMismatch(version: string | null, protocol: CodexProtocolId, details: string[]): ProtocolMismatch {
+    const found = details.join('; ');
+    let advice: ProtocolMismatch['advice'] = 'update-either';
+    let message = `Codex output did not match what this app expects (${found}). Update the codex CLI or this app.`;
+    if (version && compareVersions(version, NEWEST_KNOWN_CODEX_VERSION) > 0) {
+        advice = 'update-app';
+        message = `codex ${version} is newer than this app knows (up to ${NEWEST_KNOWN_CODEX_VERSION}) and its output did not match (${found}). Update the app, or install codex ${NEWEST_KNOWN_CODEX_VERSION}.`;
+    } else if (version && compareVersions(version, ITEM_EVENTS_VERSION) < 0) {
+        advice = 'update-cli';
+        message = `codex ${version} is older than this app expects and its output did not match (${found}). Update the codex CLI.`;
+    }
+    return { cliVersion: version, protocol, advice, message, details: details.slice(0, MAX_DETAILS) };
+}
+
+// ===== Field mappings =====
+
+interface MsgState {
+    /** Started commands and patches by call id, completed by their `*_end` event. */
+    commands: Map<string, any>;
+    /** The last `token_count`, reported with `task_complete`. */
+    usage: any;
+    nextId: number;
+}
+
+/** A legacy `{id, msg: {type, ...}}` event as thread/turn/item events; null when unknown. */
+function adaptMsgEvent(msg: any, state: MsgState): any[] | null {
+    switch (msg.type) {
+        case 'session_configured':
+            return msg.session_id ? [{ type: 'thread.started', thread_id: msg.session_id }] : [];
+        case 'agent_message':
+            return [{ type: 'item.completed', item: { id: `msg-${state.nextId++}`, type: 'agent_message', text: msg.message ?? msg.text } }];
+        case 'agent_reasoning':
+            return [{ type: 'item.completed', item: { id: `msg-${state.nextId++}`, type: 'reasoning', text: msg.text } }];
+        case 'exec_command_begin': {
+            const command = Array.isArray(msg.command) ? msg.command.join(' ') : msg.command;
+            const item = { id: msg.call_id, type: 'command_execution', command, status: 'in_progress' };
+            state.commands.set(msg.call_id, item);
+            return [{ type: 'item.started', item }];
+        }
+        case 'exec_command_end': {
+            const item = state.commands.get(msg.call_id) || { id: msg.call_id, type: 'command_execution', command: 'command' };
+            state.commands.delete(msg.call_id);
+            const output = msg.aggregated_output ?? [msg.stdout, msg.stderr].filter(Boolean).join('');
+            const status = msg.exit_code === 0 ? 'completed' : 'failed';
+            return [{ type: 'item.completed', item: { ...item, status, aggregated_output: output, exit_code: msg.exit_code } }];
+        }
+        case 'patch_apply_begin':
+        case 'patch_apply_end': {
+            const done = msg.type === 'patch_apply_end';
+            const started = state.commands.get(msg.call_id);
+            const item = {
+                id: msg.call_id,
+                type: 'file_change',
+                status: done ? (msg.success === false ? 'failed' : 'completed') : 'in_progress',
+                changes: msg.changes ? Object.keys(msg.changes).map((path) => ({ path, kind: 'update' })) : started?.changes || [],
+            };
+            if (done) state.commands.delete(msg.call_id);
+            else state.commands.set(msg.call_id, item);
+            return [{ type: done ? 'item.completed' : 'item.started', item }];
+        }
+        case 'token_count':
+            state.usage = msg.info?.total_token_usage ?? (typeof msg.input_tokens === 'number' ? msg : state.usage);
+            return [];
+        case 'task_complete':
+            return [{ type: 'turn.completed', usage: state.usage ?? undefined }];
+        case 'error':
+        case 'stream_error':
+            return [{ type: 'error', message: msg.message }];
+        default:
+            if (/_approval_request$/.test(msg.type || '')) return [{ ...msg, request_id: msg.call_id }];
+            return IGNORED_MSG_TYPES.has(msg.type) ? [] : null;
+    }
+}
+
+/** Early item releases named the item's type `item_type` and nested its fields in `details`. */
+function adaptItemEvent(event: any): any {
+    const item = event.item;
+    if (!item || typeof item !== 'object') return event;
+    const flat = item.details && typeof item.details === 'object' ? { ...item.details, ...item, details: undefined } : item;
+    const type = flat.type ?? flat.item_type;
+    return { ...event, item: { ...flat, type: type === 'assistant_message' ? 'agent_message' : type } };
+}
+
+/** What in an adapted event the stream parser would not understand, if anything. */
+function eventProblem(event: any): { problem: string; losesContent: boolean } | null {
+    if (tryExtractApprovalRequest(event)) return null;
+    const type = event?.type;
+    if (typeof type !== 'string') return { problem: 'event without a type', losesContent: false };
+    if (!KNOWN_EVENT_TYPES.has(type)) return { problem: `unknown event "${type}"`, losesContent: false };
+    if (!type.startsWith('item.')) return null;
+    const item = event.item;
+    const itemType = typeof item?.type === 'string' ? item.type.toLowerCase() : '';
+    if (!itemType) return { problem: `${type} without an item type`, losesContent: true };
+    if (!KNOWN_ITEM_TYPES.has(itemType)) return { problem: `unknown item "${itemType}"`, losesContent: false };
+    const finished = type === 'item.completed';
+    if ((itemType === 'agent_message' || itemType === 'message') && finished && typeof item.text !== 'string') {
+        return { problem: `${itemType} without text`, losesContent: true };
+    }
+    if (itemType === 'command_execution' && typeof item.command !== 'string') {
+        return { problem: 'command_execution without a command', losesContent: true };
+    }
+    return null;
+}
+
+/**
+ * The codex backend's event mapper: picks the field mappings for the CLI version from the
+ * handshake (or from the first event when the version is not known yet) and watches for
+ * output it cannot read. A `{ type: 'protocol.mismatch', mismatch }` event is added once
+ * per turn, right away when a message or command would be lost, or at the end of a turn
+ * that showed no text but unknown events.
+ */
+export function createCodexProtocolAdapter(info: CodexProtocolInfo = codexProtocolInfo()): (event: any) => any[] {
+    let protocol = info.protocol;
+    const msgState: MsgState = { commands: new Map(), usage: null, nextId: 0 };
+    const details: string[] = [];
+    let reported = false;
+    let sawText = false;
+
+    const report = (): any[] => {
+        if (reported || details.length === 0) return [];
+        reported = true;
+        return [{ type: 'protocol.mismatch', mismatch: describeMismatch(info.version, protocol, details) }];
+    };
+
+    return (event) => {
+        let adapted: any[];
+        if (event?.msg && typeof event.msg === 'object') {
+            protocol = 'exec-msg';
+            const mapped = adaptMsgEvent(event.msg, msgState);
+            if (mapped === null) {
+                const problem = `unknown event "${event.msg.type}"`;
+                if (!details.includes(problem) && details.length < MAX_DETAILS) details.push(problem);
+                return [];
+            }
+            adapted = mapped;
+        } else {
+            protocol = 'exec-items';
+            adapted = [adaptItemEvent(event)];
+        }
+
+        let losesContent = false;
+        for (const value of adapted) {
+            const itemType = value.item?.type;
+            if ((itemType === 'agent_message' || itemType === 'message') && value.item.text) sawText = true;
+            const found = eventProblem(value);
+            if (!found) continue;
+            if (!details.includes(found.problem) && details.length < MAX_DETAILS) details.push(found.problem);
+            losesContent ||= found.losesContent;
+        }
+        const ended = adapted.some((value) => value.type === 'turn.completed' || value.type === 'turn.failed');
+        return losesContent || (ended && !sawText) ? [...adapted, ...report()] : adapted;
+    };
+}
@@ -0,0 +1,259 @@
+import { spawn } from 'child_process';
+import { BrowserWindow, WebContents } from 'electron';
+import * as fs from 'fs';
+import * as path from 'path';
+import { AppState } from './models';
+import { runGit } from './git';
+import { requirePermission } from './permissions';
+import { sendToTeams } from './teams';
+import { ArtifactTheme, getArtifactTheme } from './themes';
+import { commandSpawnOptions, expandTildePath, generateId, trackChild } from './utils';
+
+export type ReleaseStep =
+    | { type: 'bump'; files: string[] }
+    | { type: 'commit'; message?: string }
+    | { type: 'tag'; name?: string; message?: string }
+    | { type: 'command'; command: string }
+    | { type: 'notify'; sink: 'teams' | 'webhook'; url: string; message?: string };
+
+export type ReleaseStepStatus = 'pending' | 'running' | 'done' | 'failed' | 'rolled_back' | 'rollback_failed';
+
+export interface ReleaseStepResult {
+    index: number;
+    type: ReleaseStep['type'];
+    description: string;
+    status: ReleaseStepStatus;
+    output: string;
+    error?: string;
+}
+
+export interface ReleaseResult {
+    success: boolean;
+    releaseId: string;
+    dryRun: boolean;
+    steps: ReleaseStepResult[];
+    rolledBack: boolean;
+    error?: string;
+}
+
+interface PreparedStep {
+    description: string;
+    run: (log: (data: string) => void) => Promise<string>;
+    rollback?: () => Promise<void>;
+}
+
+const VERSION_PATTERN = /^\d+\.\d+\.\d+(?:[-+][0-9A-Za-z.-]+)?$/;
+
+function fillVersion(template: string, version: string): string {
+    return template.replace(/\{\{version\}\}/g, version);
+}
+
+/**
+ * Replace the first version field of a manifest, keeping the rest of the file byte for
+ * byte. JSON manifests use `"version": "..."`, TOML ones `version = "..."` (the
+ * [package] / [workspace.package] entry comes first in practice).
+ */
+export function bumpVersionText(fileName: string, text: string, version: string): string | null {
+    const pattern = fileName.endsWith('.json')
+        ? /("version"\s*:\s*")[^"]*(")/
+        : fileName.endsWith('.toml')
+            ? /^(version\s*=\s*")[^"]*(")/m
+            : null;
+    if (!pattern || !pattern.test(text)) return null;
+    return text.replace(pattern, `$1${version}$2`);
+}
+
+function runShell(command: string, cwd: string, log: (data: string) => void): Promise<string> {
+    return new Promise((resolve, reject) => {
+        const child = trackChild(spawn(command, { ...commandSpawnOptions(cwd), shell: true, stdio: ['ignore', 'pipe', 'pipe'] }));
+        let output = '';
+        const onData = (d: Buffer) => {
+            const text = d.toString();
+            output += text;
+            log(text);
+        };
+        child.stdout?.on('data', onData);
+        child.stderr?.on('data', onData);
+        child.on('error', reject);
+        child.on('close', (code) => {
+            if (code === 0) resolve(output);
+            else reject(new Error(`\`${command}\` exited with code ${code}`));
+        });
+    });
+}
+
+async function git(cwd: string, args: string[]): Promise<string> {
+    const result = await runGit(cwd, args);
+    if (!result.success) throw new Error(result.error);
+    return result.stdout;
+}
+
+function prepareStep(step: ReleaseStep, cwd: string, version: string, theme: ArtifactTheme): PreparedStep {
+    switch (step.type) {
+        case 'bump': {
+            const originals = new Map<string, string>();
+            return {
+                description: `Set version ${version} in ${step.files.join(', ')}`,
+                run: async (log) => {
+                    for (const file of step.files) {
+                        const target = path.resolve(cwd, file);
+                        if (path.relative(cwd, target).startsWith('..')) {
+                            throw new Error(`${file} is outside the workspace`);
+                        }
+                        const text = fs.readFileSync(target, 'utf-8');
+                        const bumped = bumpVersionText(target, text, version);
+                        if (bumped === null) throw new Error(`No version field found in ${file}`);
+                        originals.set(target, text);
+                        fs.writeFileSync(target, bumped);
+                        log(`Updated ${file}\n`);
+                    }
+                    return `Updated ${step.files.length} file(s)`;
+                },
+                rollback: async () => {
+                    for (const [target, text] of originals) fs.writeFileSync(target, text);
+                },
+            };
+        }
+        case 'commit': {
+            const message = fillVersion(step.message || 'Release v{{version}}', version);
+            return {
+                description: `Commit "${message}"`,
+                run: async () => git(cwd, ['commit', '-a', '-m', message]),
+                // Keep the bumped files in the working tree; the bump step restores them.
+                rollback: async () => {
+                    await git(cwd, ['reset', 'HEAD~1']);
+                },
+            };
+        }
+        case 'tag': {
+            const name = fillVersion(step.name || 'v{{version}}', version);
+            const message = fillVersion(step.message || 'Release {{version}}', version);
+            return {
+                description: `Create tag ${name}`,
+                run: async () => git(cwd, ['tag', '-a', name, '-m', message]),
+                rollback: async () => {
+                    await git(cwd, ['tag', '-d', name]);
+                },
+            };
+        }
+        case 'command':
+            return {
+                description: `Run \`${step.command}\``,
+                run: (log) => runShell(fillVersion(step.command, version), cwd, log),
+            };
+        case 'notify': {
+            const message = fillVersion(step.message || 'Released {{version}}', version);
+            return {
+                description: `Notify ${step.sink}`,
+                run: async () => {
+                    if (step.sink === 'teams') {
+                        const result = await sendToTeams(step.url, `Release ${version}`, message, theme);
+                        if (!result.success) throw new Error(result.error || 'Teams notification failed');
+                        return 'Sent';
+                    }
+                    const response = await fetch(step.url, {
+                        method: 'POST',
+                        headers: { 'Content-Type': 'application/json' },
+                        body: JSON.stringify({ version, text: message }),
+                    });
+                    if (!response.ok) throw new Error(`Webhook responded with ${response.status}`);
+                    return 'Sent';
+                },
+            };
+        }
+    }
+}
+
+/**
+ * Run release steps in order, streaming progress as `release-step` / `release-output`
+ * events. When a step fails, completed steps that can be undone (bump, commit, tag) are
+ * rolled back in reverse order. Commands and notifications cannot be undone. Dry-run
+ * mode only reports what each step would do.
+ */
+export async function runRelease(
+    webContents: WebContents,
+    state: AppState,
+    window: BrowserWindow | null,
+    workspaceId: string,
+    version: string,
+    steps: ReleaseStep[],
+    dryRun = false,
+): Promise<ReleaseResult> {
+    const releaseId = generateId('release');
+    const fail = (error: string): ReleaseResult => ({ success: false, releaseId, dryRun, steps: [], rolledBack: false, error });
+
+    if (!VERSION_PATTERN.test(version)) return fail(`Invalid version: ${version}`);
+    const workspace = state.db.prepare('SELECT path FROM workspaces WHERE id = ?').get(workspaceId) as any;
+    if (!workspace) return fail('Workspace not found');
+    const cwd = expandTildePath(workspace.path);
+
+    if (!dryRun) {
+        const denied = await requirePermission(state.db, window, cwd, 'write');
+        if (denied) return fail(denied.error);
+        // Command steps run arbitrary shell commands, like run-command.
+        const deniedRun = steps.some((step) => step.type === 'command')
+            ? await requirePermission(state.db, window, cwd, 'run')
+            : null;
+        if (deniedRun) return fail(deniedRun.error);
+    }
+
+    const theme = getArtifactTheme(state.db);
+    const prepared = steps.map((step) => prepareStep(step, cwd, version, theme));
+    const results: ReleaseStepResult[] = prepared.map((p, index) => ({
+        index,
+        type: steps[index].type,
+        description: p.description,
+        status: 'pending',
+        output: '',
+    }));
+    const emit = (result: ReleaseStepResult) =>
+        webContents.send('release-step', { releaseId, dryRun, ...result });
+
+    let failedAt = -1;
+    for (const [index, step] of prepared.entries()) {
+        const result = results[index];
+        if (dryRun) {
+            result.status = 'done';
+            result.output = `Would ${step.description.charAt(0).toLowerCase()}${step.description.slice(1)}`;
+            emit(result);
+            continue;
+        }
+
+        result.status = 'running';
+        emit(result);
+        try {
+            result.output = await step.run((data) => webContents.send('release-output', { releaseId, index, data }));
+            result.status = 'done';
+        } catch (err: any) {
+            result.status = 'failed';
+            result.error = err.message;
+            failedAt = index;
+        }
+        emit(result);
+        if (failedAt >= 0) break;
+    }
+
+    if (failedAt < 0) return { success: true, releaseId, dryRun, steps: results, rolledBack: false };
+
+    for (let index = failedAt - 1; index >= 0; index--) {
+        const rollback = prepared[index].rollback;
+        if (!rollback) continue;
+        try {
+            await rollback();
+            results[index].status = 'rolled_back';
+        } catch (err: any) {
+            results[index].status = 'rollback_failed';
+            results[index].error = err.message;
+        }
+        emit(results[index]);
+    }
+
+    return {
+        success: false,
+        releaseId,
+        dryRun,
+        steps: results,
+        rolledBack: true,
+        error: `Step ${failedAt + 1} failed: ${results[failedAt].error}`,
+    };
+}
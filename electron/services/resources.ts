@@ -0,0 +1,87 @@
+import Database from 'better-sqlite3';
+import * as os from 'os';
+import { AppState } from './models';
+import { dbGetSetting, dbSetSetting } from './db';
+
+export interface ResourceLimits {
+    /** Workspaces whose file index is kept in memory. */
+    fileIndexes: number;
+    /** Files and directories per file index. */
+    indexedEntries: number;
+    /** Agent text kept per running turn for interrupted turns; 0 is unlimited. */
+    streamTextChars: number;
+    /** Command output kept in memory before the rest goes to a file on disk. */
+    outputSpillBytes: number;
+    /** Codex turns running at once across conversations; 0 is unlimited. */
+    maxConcurrentTurns: number;
+}
+
+export interface ResourceUsage {
+    lowMemory: boolean;
+    limits: ResourceLimits;
+    memory: { rss: number; heapUsed: number; heapTotal: number; external: number; arrayBuffers: number };
+    system: { totalMemory: number; freeMemory: number };
+    runningTurns: number;
+    queuedPrompts: number;
+    runningCommands: number;
+    terminals: number;
+    fileIndexes: { indexes: number; entries: number };
+}
+
+const LOW_MEMORY_KEY = 'resources.lowMemory';
+
+const DEFAULT_LIMITS: ResourceLimits = {
+    fileIndexes: 8,
+    indexedEntries: 500_000,
+    streamTextChars: 0,
+    outputSpillBytes: 10 * 1024 * 1024,
+    maxConcurrentTurns: 0,
+};
+
+const LOW_MEMORY_LIMITS: ResourceLimits = {
+    fileIndexes: 2,
+    indexedEntries: 100_000,
+    streamTextChars: 256 * 1024,
+    outputSpillBytes: 512 * 1024,
+    maxConcurrentTurns: 2,
+};
+
+let lowMemory = false;
+
+/** Limits in effect now; services read these on use so toggling takes effect immediately. */
+export function resourceLimits(): ResourceLimits {
+    return lowMemory ? LOW_MEMORY_LIMITS : DEFAULT_LIMITS;
+}
+
+/** Load the saved mode; called once at startup. */
+export function initResourceLimits(db: Database.Database): void {
+    lowMemory = dbGetSetting(db, LOW_MEMORY_KEY, false);
+}
+
+export function getLowMemoryMode(db: Database.Database): boolean {
+    return dbGetSetting(db, LOW_MEMORY_KEY, false);
+}
+
+export function setLowMemoryMode(db: Database.Database, enabled: boolean): { lowMemory: boolean; limits: ResourceLimits } {
+    lowMemory = !!enabled;
+    dbSetSetting(db, LOW_MEMORY_KEY, lowMemory);
+    return { lowMemory, limits: resourceLimits() };
+}
+
+/** Memory of the backend process and the size of its caches and running work. */
+export function getResourceUsage(state: AppState, fileIndexes: { indexes: number; entries: number }): ResourceUsage {
+    const { rss, heapUsed, heapTotal, external, arrayBuffers } = process.memoryUsage();
+    let queuedPrompts = 0;
+    for (const queue of state.promptQueues.values()) queuedPrompts += queue.length;
+    return {
+        lowMemory,
+        limits: resourceLimits(),
+        memory: { rss, heapUsed, heapTotal, external, arrayBuffers },
+        system: { totalMemory: os.totalmem(), freeMemory: os.freemem() },
+        runningTurns: state.runningCodex.size,
+        queuedPrompts,
+        runningCommands: state.runningCommands.size,
+        terminals: state.ptyTerminals.size,
+        fileIndexes,
+    };
+}
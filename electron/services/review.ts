@@ -0,0 +1,175 @@
+import * as path from 'path';
+import { PlanStep, ReviewScope, RuntimeConfig } from './models';
+import { fileChangePaths } from './utils';
+
+/**
+ * Sandbox settings for the two passes of a safety review: the plan runs read-only and
+ * never asks, the approved pass may write but has every untrusted command put to the
+ * scope check.
+ */
+export function reviewRunConfig(cfg: RuntimeConfig, phase: 'plan' | 'execute'): RuntimeConfig {
+    return {
+        ...cfg,
+        yoloMode: false,
+        cliOptions: {
+            ...cfg.cliOptions,
+            sandbox: phase === 'plan' ? 'read-only' : 'workspace-write',
+            askForApproval: phase === 'plan' ? 'never' : 'untrusted',
+        },
+    };
+}
+
+export function buildPlanningPrompt(task: string): string {
+    return [
+        '[Safety review: planning pass]',
+        'This pass runs in a read-only sandbox. Do not try to change anything. Investigate as needed and',
+        'describe how you would carry out the task below as numbered steps. End your answer with a fenced',
+        '```json block of the form {"steps": [{"title": "...", "commands": ["..."], "files": ["..."]}]} with one',
+        'entry per step, in order, listing every shell command the step runs and every file it creates,',
+        'changes or deletes (paths relative to the working directory).',
+        '',
+        '[Task]',
+        task,
+    ].join('\n');
+}
+
+/**
+ * The approved pass's prompt. With `steps` only those steps of the plan were approved, and
+ * they replace the plan text so the agent does not carry out the others.
+ */
+export function buildExecutionPrompt(
+    task: string,
+    plan: string,
+    scope: ReviewScope,
+    steps?: Array<PlanStep & { index: number }>,
+): string {
+    const list = (items: string[]) => (items.length > 0 ? items.map((item) => `- ${item}`).join('\n') : '- (none)');
+    const planSection = steps
+        ? [
+            '[Approved steps]',
+            'Carry out only these steps of your plan, in order; the other steps were not approved.',
+            ...steps.map((step) => `${step.index + 1}. ${step.title}`),
+        ]
+        : ['[Plan]', plan.trim()];
+    return [
+        '[Safety review: approved pass]',
+        'The user approved the plan below. Carry it out. Commands and file changes outside the approved',
+        'scope will be refused.',
+        '',
+        '[Approved commands]',
+        list(scope.commands),
+        '',
+        '[Approved files]',
+        list(scope.files),
+        '',
+        ...planSection,
+        '',
+        '[Task]',
+        task,
+    ].join('\n');
+}
+
+function stringList(value: unknown): string[] {
+    if (!Array.isArray(value)) return [];
+    return value.filter((item): item is string => typeof item === 'string' && item.trim() !== '').map((item) => item.trim());
+}
+
+export function normalizeReviewScope(scope: Partial<ReviewScope> | undefined): ReviewScope {
+    return { commands: stringList(scope?.commands), files: stringList(scope?.files) };
+}
+
+/** The last ```json block of a plan that parses, or null. */
+function planJson(plan: string): any {
+    const blocks = Array.from(plan.matchAll(/```json\s*\n([\s\S]*?)```/g));
+    for (const block of blocks.reverse()) {
+        try {
+            return JSON.parse(block[1]);
+        } catch { }
+    }
+    return null;
+}
+
+/** The commands and files of the given steps together, without duplicates. */
+export function stepsScope(steps: PlanStep[]): ReviewScope {
+    return {
+        commands: Array.from(new Set(steps.flatMap((step) => step.commands))),
+        files: Array.from(new Set(steps.flatMap((step) => step.files))),
+    };
+}
+
+/**
+ * The steps of a plan: the `steps` of its ```json block, or else its numbered list items,
+ * which then carry no commands or files of their own.
+ */
+export function parsePlanSteps(plan: string): PlanStep[] {
+    const json = planJson(plan);
+    if (Array.isArray(json?.steps)) {
+        return json.steps
+            .filter((step: any) => step && typeof step === 'object')
+            .map((step: any, index: number) => ({
+                title: typeof step.title === 'string' && step.title.trim() ? step.title.trim() : `Step ${index + 1}`,
+                ...normalizeReviewScope(step),
+            }));
+    }
+    const text = plan.replace(/```[\s\S]*?```/g, '');
+    return Array.from(text.matchAll(/^\s*\d+[.)]\s+(.+)$/gm), (match) => ({
+        title: match[1].trim(),
+        commands: [],
+        files: [],
+    }));
+}
+
+/**
+ * The commands and files a plan proposes, from the last ```json block of its answer: those
+ * of all its steps and any listed at the top level.
+ */
+export function parseReviewProposal(plan: string): ReviewScope {
+    const json = planJson(plan);
+    if (!json) return { commands: [], files: [] };
+    const topLevel = normalizeReviewScope(json);
+    const fromSteps = stepsScope(parsePlanSteps(plan));
+    return stepsScope([{ title: '', ...topLevel }, { title: '', ...fromSteps }]);
+}
+
+/** A command as a single line; `bash -lc <script>` wrappers are reduced to the script. */
+function commandText(command: unknown): string {
+    if (Array.isArray(command)) {
+        const parts = command.map(String);
+        if (parts.length === 3 && /(^|\/)(ba|z)?sh$/.test(parts[0]) && /^-l?c$/.test(parts[1])) return parts[2].trim();
+        return parts.join(' ').trim();
+    }
+    return typeof command === 'string' ? command.trim() : '';
+}
+
+const collapse = (text: string) => text.trim().replace(/\s+/g, ' ');
+
+export function commandInScope(command: string, scope: ReviewScope): boolean {
+    const line = collapse(command);
+    return scope.commands.some((approved) => {
+        const pattern = collapse(approved);
+        return pattern.endsWith('*') ? line.startsWith(pattern.slice(0, -1)) : line === pattern;
+    });
+}
+
+export function fileInScope(filePath: string, scope: ReviewScope, cwd: string): boolean {
+    const target = path.resolve(cwd, filePath);
+    return scope.files.some((approved) => {
+        const allowed = path.resolve(cwd, approved);
+        return target === allowed || (/[\\/]$/.test(approved) && target.startsWith(allowed + path.sep));
+    });
+}
+
+/**
+ * What an approval request or file_change item does outside the approved scope: the
+ * command line and changed paths that were not approved. Empty when it is in scope.
+ */
+export function reviewViolations(event: any, scope: ReviewScope, cwd: string): string[] {
+    const source = event?.item ?? event?.params ?? event ?? {};
+    const violations: string[] = [];
+    const command = commandText(source.command);
+    if (command && !commandInScope(command, scope)) violations.push(command);
+    for (const filePath of fileChangePaths(source.changes ?? source.fileChanges)) {
+        if (!fileInScope(filePath, scope, cwd)) violations.push(filePath);
+    }
+    return violations;
+}
@@ -0,0 +1,191 @@
+import * as fs from 'fs';
+import { diffLines } from './diffs';
+
+/** A finished shell command, kept so a rerun can be compared with it. */
+export interface CommandRun {
+    commandId: string;
+    command: string;
+    cwd: string;
+    exitCode: number;
+    startedAt: string;
+    finishedAt: string;
+    output: string;
+    errorOutput: string;
+    /** Set when the output outgrew memory; `output`/`errorOutput` then hold the start only. */
+    outputFile?: string;
+    errorOutputFile?: string;
+}
+
+export type CommandRunSummary = Omit<CommandRun, 'output' | 'errorOutput' | 'outputFile' | 'errorOutputFile'>;
+
+export interface TestResults {
+    passed: string[];
+    failed: string[];
+}
+
+export interface RunComparison {
+    runA: CommandRunSummary;
+    runB: CommandRunSummary;
+    sameCommand: boolean;
+    /** Tests failing in B but not in A. */
+    newFailures: string[];
+    /** Tests failing in A that pass in B. */
+    fixedFailures: string[];
+    stillFailing: string[];
+    /** Tests failing in A that B did not report at all. */
+    notRerun: string[];
+    /** Output lines that differ once durations and timestamps are masked. */
+    addedLines: string[];
+    removedLines: string[];
+    /** More changed lines than MAX_CHANGED_LINES; the lists hold the first ones. */
+    truncated: boolean;
+    /** One line, e.g. `exit 1 → 0, 2 fixed, 1 still failing`. */
+    summary: string;
+}
+
+const MAX_RUNS = 50;
+const MAX_COMPARE_BYTES = 4 * 1024 * 1024;
+const MAX_CHANGED_LINES = 50;
+
+const runs: CommandRun[] = [];
+
+export function recordRun(run: CommandRun): void {
+    runs.unshift(run);
+    runs.length = Math.min(runs.length, MAX_RUNS);
+}
+
+/** The last MAX_RUNS finished commands, newest first. */
+export function listRuns(): CommandRunSummary[] {
+    return runs.map(summarize);
+}
+
+export function getRun(commandId: string): CommandRun | undefined {
+    return runs.find((r) => r.commandId === commandId);
+}
+
+function summarize({ output, errorOutput, outputFile, errorOutputFile, ...summary }: CommandRun): CommandRunSummary {
+    return summary;
+}
+
+function readOutput(text: string, file?: string): string {
+    if (!file) return text;
+    try {
+        const fd = fs.openSync(file, 'r');
+        try {
+            const buffer = Buffer.alloc(Math.min(fs.fstatSync(fd).size, MAX_COMPARE_BYTES));
+            fs.readSync(fd, buffer, 0, buffer.length, 0);
+            return buffer.toString('utf-8');
+        } finally {
+            fs.closeSync(fd);
+        }
+    } catch {
+        return text;
+    }
+}
+
+// ✅/❌ are this repo's own test runner; the rest cover jest, vitest and mocha.
+const PASS_MARKS = '✓✔√✅';
+const FAIL_MARKS = '×✕✗✘❌';
+const TEST_LINE_PATTERNS: Array<[RegExp, (m: RegExpMatchArray) => [string, boolean]]> = [
+    // cargo test
+    [/^test (\S+) \.\.\. (ok|FAILED)$/, (m) => [m[1], m[2] === 'ok']],
+    // go test -v
+    [/^\s*--- (PASS|FAIL): (\S+)/, (m) => [m[2], m[1] === 'PASS']],
+    // pytest -v, and its short summary
+    [/^(\S+::\S+) (PASSED|FAILED|ERROR)\b/, (m) => [m[1], m[2] === 'PASSED']],
+    [/^(FAILED|ERROR) (\S+::\S+)/, (m) => [m[2], false]],
+    // jest/vitest file results
+    [/^\s*(PASS|FAIL)\s+(\S+)/, (m) => [m[2], m[1] === 'PASS']],
+    [
+        new RegExp(`^\\s*([${PASS_MARKS}${FAIL_MARKS}])\\s+(.+?)(?:\\s+\\(\\d+(?:\\.\\d+)?\\s*m?s\\))?$`, 'u'),
+        (m) => [m[2], PASS_MARKS.includes(m[1])],
+    ],
+];
+
+/**
+ * Test names and outcomes found in command output, for cargo, go, pytest, jest/vitest and
+ * check-mark style runners. A test reported as failing anywhere counts as failed.
+ */
+export function parseTestResults(output: string): TestResults {
+    const passed = new Set<string>();
+    const failed = new Set<string>();
+    for (const raw of output.split('\n')) {
+        const line = raw.replace(/\x1B\[[0-9;]*[a-zA-Z]/g, '').replace(/\r$/, '');
+        for (const [pattern, read] of TEST_LINE_PATTERNS) {
+            const match = line.match(pattern);
+            if (!match) continue;
+            const [name, ok] = read(match);
+            (ok ? passed : failed).add(name.trim());
+            break;
+        }
+    }
+    for (const name of failed) passed.delete(name);
+    return { passed: [...passed], failed: [...failed] };
+}
+
+/** Mask what changes on every run (colours, durations, clock times) before diffing. */
+function normalizeOutput(output: string): string {
+    return output
+        .replace(/\x1B\[[0-9;]*[a-zA-Z]/g, '')
+        .replace(/\r\n?/g, '\n')
+        .replace(/\b\d{1,2}:\d{2}:\d{2}(\.\d+)?\b/g, '<time>')
+        .replace(/\b\d+(\.\d+)?\s?(ms|s|sec|seconds)\b/g, '<duration>');
+}
+
+/**
+ * What changed between two finished runs: tests that started or stopped failing, and the
+ * output lines that differ. Meant for "did the fix change the test results".
+ */
+export function compareRuns(runIdA: string, runIdB: string): { success: boolean; comparison?: RunComparison; error?: string } {
+    const a = getRun(runIdA);
+    const b = getRun(runIdB);
+    if (!a || !b) return { success: false, error: `Run not found: ${!a ? runIdA : runIdB}` };
+    return { success: true, comparison: compareRunOutputs(a, b) };
+}
+
+export function compareRunOutputs(a: CommandRun, b: CommandRun): RunComparison {
+    const textA = [readOutput(a.output, a.outputFile), readOutput(a.errorOutput, a.errorOutputFile)].join('\n');
+    const textB = [readOutput(b.output, b.outputFile), readOutput(b.errorOutput, b.errorOutputFile)].join('\n');
+    const testsA = parseTestResults(textA);
+    const testsB = parseTestResults(textB);
+    const failedA = new Set(testsA.failed);
+    const failedB = new Set(testsB.failed);
+    const passedB = new Set(testsB.passed);
+
+    const addedLines: string[] = [];
+    const removedLines: string[] = [];
+    for (const hunk of diffLines(normalizeOutput(textA), normalizeOutput(textB), 0).hunks) {
+        for (const line of hunk.lines) {
+            if (line.slice(1).trim() === '') continue;
+            if (line[0] === '+') addedLines.push(line.slice(1));
+            else if (line[0] === '-') removedLines.push(line.slice(1));
+        }
+    }
+    const truncated = addedLines.length > MAX_CHANGED_LINES || removedLines.length > MAX_CHANGED_LINES;
+
+    const comparison: Omit<RunComparison, 'summary'> = {
+        runA: summarize(a),
+        runB: summarize(b),
+        sameCommand: a.command.trim() === b.command.trim(),
+        newFailures: testsB.failed.filter((t) => !failedA.has(t)),
+        fixedFailures: testsA.failed.filter((t) => passedB.has(t)),
+        stillFailing: testsA.failed.filter((t) => failedB.has(t)),
+        notRerun: testsA.failed.filter((t) => !failedB.has(t) && !passedB.has(t)),
+        addedLines: addedLines.slice(0, MAX_CHANGED_LINES),
+        removedLines: removedLines.slice(0, MAX_CHANGED_LINES),
+        truncated,
+    };
+    return { ...comparison, summary: summarizeComparison(comparison, addedLines.length + removedLines.length) };
+}
+
+function summarizeComparison(c: Omit<RunComparison, 'summary'>, changedLines: number): string {
+    const count = (n: number, word: string) => `${n} ${word}${n === 1 ? '' : 's'}`;
+    const parts: string[] = [];
+    if (c.runA.exitCode !== c.runB.exitCode) parts.push(`exit ${c.runA.exitCode} → ${c.runB.exitCode}`);
+    if (c.fixedFailures.length) parts.push(`${c.fixedFailures.length} fixed`);
+    if (c.newFailures.length) parts.push(count(c.newFailures.length, 'new failure'));
+    if (c.stillFailing.length) parts.push(`${c.stillFailing.length} still failing`);
+    if (c.notRerun.length) parts.push(`${c.notRerun.length} not rerun`);
+    if (parts.length === 0) return changedLines ? `Same results; ${count(changedLines, 'output line')} changed` : 'No change';
+    return parts.join(', ');
+}
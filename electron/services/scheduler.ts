@@ -0,0 +1,314 @@
+import Database from 'better-sqlite3';
+import { WebContents } from 'electron';
+import { AppState, CliOverrides } from './models';
+import { dbCreateConversation, dbCreateMessage, dbGetWorkspaceCliOptions, parseJsonColumn } from './db';
+import { runCodexToCompletion } from './codex';
+import { expandTildePath, generateId, nowIso, resolveRuntimeConfig } from './utils';
+
+export interface ScheduledTask {
+    id: string;
+    workspaceId: string;
+    name: string;
+    /** Five-field cron expression in local time, or a macro such as `@daily`. */
+    cron: string;
+    prompt: string;
+    /** Applied on top of the workspace's CLI options; approvals are always `never`. */
+    cliOptions: CliOverrides;
+    enabled: boolean;
+    createdAt: string;
+    lastRunAt: string | null;
+    /** Unix seconds of the next run, or null while disabled. */
+    nextRunAt: string | null;
+    lastStatus: 'running' | 'completed' | 'failed' | null;
+    lastError: string | null;
+    lastConversationId: string | null;
+}
+
+export interface ScheduledTaskResult {
+    taskId: string;
+    workspaceId: string;
+    name: string;
+    success: boolean;
+    conversationId: string;
+    error?: string;
+    startedAt: string;
+    finishedAt: string;
+}
+
+interface CronSchedule {
+    minutes: Set<number>;
+    hours: Set<number>;
+    days: Set<number>;
+    months: Set<number>;
+    weekdays: Set<number>;
+    /** Both day fields restricted: a day matching either one is due, as in cron. */
+    eitherDay: boolean;
+}
+
+const SCHEDULER_CHECK_INTERVAL_MS = 60_000;
+// Leap days are at most eight years apart, so `0 0 29 2 *` is always found.
+const MAX_SEARCH_YEARS = 10;
+
+const CRON_MACROS: Record<string, string> = {
+    '@yearly': '0 0 1 1 *',
+    '@annually': '0 0 1 1 *',
+    '@monthly': '0 0 1 * *',
+    '@weekly': '0 0 * * 0',
+    '@daily': '0 0 * * *',
+    '@midnight': '0 0 * * *',
+    '@hourly': '0 * * * *',
+};
+
+const MONTH_NAMES = ['jan', 'feb', 'mar', 'apr', 'may', 'jun', 'jul', 'aug', 'sep', 'oct', 'nov', 'dec'];
+const WEEKDAY_NAMES = ['sun', 'mon', 'tue', 'wed', 'thu', 'fri', 'sat'];
+
+function parseCronField(field: string, label: string, min: number, max: number, names: string[] = []): Set<number> {
+    const value = (token: string): number => {
+        const named = names.indexOf(token.toLowerCase());
+        const n = named >= 0 ? named + min : /^\d+$/.test(token) ? Number(token) : NaN;
+        if (!(n >= min && n <= max)) throw new Error(`Invalid ${label} in cron expression: ${token}`);
+        return n;
+    };
+    const values = new Set<number>();
+    for (const part of field.split(',')) {
+        const [range, stepText] = part.split('/');
+        const step = stepText === undefined ? 1 : Number(stepText);
+        if (!Number.isInteger(step) || step < 1) throw new Error(`Invalid step in cron expression: ${part}`);
+        let from: number;
+        let to: number;
+        if (range === '*') {
+            [from, to] = [min, max];
+        } else if (range.includes('-')) {
+            const [a, b] = range.split('-');
+            [from, to] = [value(a), value(b)];
+            if (from > to) throw new Error(`Invalid range in cron expression: ${range}`);
+        } else {
+            from = value(range);
+            to = stepText === undefined ? from : max;
+        }
+        for (let n = from; n <= to; n += step) values.add(n);
+    }
+    return values;
+}
+
+function parseCron(expression: string): CronSchedule {
+    const expanded = CRON_MACROS[expression.trim().toLowerCase()] ?? expression;
+    const fields = expanded.trim().split(/\s+/);
+    if (fields.length !== 5) throw new Error('Cron expression needs five fields: minute hour day month weekday');
+    const [minute, hour, day, month, weekday] = fields;
+    const weekdays = parseCronField(weekday, 'weekday', 0, 7, WEEKDAY_NAMES);
+    if (weekdays.delete(7)) weekdays.add(0);
+    return {
+        minutes: parseCronField(minute, 'minute', 0, 59),
+        hours: parseCronField(hour, 'hour', 0, 23),
+        days: parseCronField(day, 'day', 1, 31),
+        months: parseCronField(month, 'month', 1, 12, MONTH_NAMES),
+        weekdays,
+        eitherDay: !day.startsWith('*') && !weekday.startsWith('*'),
+    };
+}
+
+/** Null if the expression is valid, else what is wrong with it. */
+export function validateCron(expression: string): string | null {
+    try {
+        parseCron(expression);
+        return null;
+    } catch (err: any) {
+        return err.message;
+    }
+}
+
+/**
+ * The first time strictly after `after` that the expression matches, in local time, or
+ * null if it never does (e.g. `0 0 31 2 *`). Throws on an invalid expression.
+ */
+export function nextCronRun(expression: string, after: Date): Date | null {
+    const schedule = parseCron(expression);
+    const at = new Date(after);
+    at.setSeconds(0, 0);
+    at.setMinutes(at.getMinutes() + 1);
+    const limit = after.getFullYear() + MAX_SEARCH_YEARS;
+
+    while (at.getFullYear() <= limit) {
+        if (!schedule.months.has(at.getMonth() + 1)) {
+            at.setMonth(at.getMonth() + 1, 1);
+            at.setHours(0, 0);
+            continue;
+        }
+        const dayMatch = schedule.days.has(at.getDate());
+        const weekdayMatch = schedule.weekdays.has(at.getDay());
+        if (schedule.eitherDay ? !dayMatch && !weekdayMatch : !dayMatch || !weekdayMatch) {
+            at.setDate(at.getDate() + 1);
+            at.setHours(0, 0);
+            continue;
+        }
+        if (!schedule.hours.has(at.getHours())) {
+            at.setHours(at.getHours() + 1, 0);
+            continue;
+        }
+        if (!schedule.minutes.has(at.getMinutes())) {
+            at.setMinutes(at.getMinutes() + 1);
+            continue;
+        }
+        return at;
+    }
+    return null;
+}
+
+function nextRunFor(cron: string, enabled: boolean, lastRunAt: string | null, createdAt: string): string | null {
+    if (!enabled) return null;
+    const next = nextCronRun(cron, new Date(Number(lastRunAt ?? createdAt) * 1000));
+    return next ? String(Math.floor(next.getTime() / 1000)) : null;
+}
+
+function rowToTask(row: any): ScheduledTask {
+    return {
+        id: row.id,
+        workspaceId: row.workspace_id,
+        name: row.name,
+        cron: row.cron,
+        prompt: row.prompt,
+        cliOptions: parseJsonColumn<CliOverrides>(row.cli_options) || {},
+        enabled: !!row.enabled,
+        createdAt: row.created_at,
+        lastRunAt: row.last_run_at,
+        nextRunAt: nextRunFor(row.cron, !!row.enabled, row.last_run_at, row.created_at),
+        lastStatus: row.last_status,
+        lastError: row.last_error,
+        lastConversationId: row.last_conversation_id,
+    };
+}
+
+export function listScheduledTasks(db: Database.Database, workspaceId?: string): ScheduledTask[] {
+    const rows = workspaceId
+        ? db.prepare('SELECT * FROM scheduled_tasks WHERE workspace_id = ? ORDER BY name').all(workspaceId)
+        : db.prepare('SELECT * FROM scheduled_tasks ORDER BY name').all();
+    return rows.map(rowToTask);
+}
+
+export function saveScheduledTask(
+    db: Database.Database,
+    task: Pick<ScheduledTask, 'workspaceId' | 'name' | 'cron' | 'prompt' | 'enabled'> & {
+        id?: string;
+        cliOptions?: CliOverrides;
+    },
+): { success: boolean; task?: ScheduledTask; error?: string } {
+    const invalid = validateCron(task.cron);
+    if (invalid) return { success: false, error: invalid };
+    if (!task.prompt?.trim()) return { success: false, error: 'A prompt is required' };
+
+    const id = task.id || generateId('schedule');
+    db.prepare(`
+    INSERT INTO scheduled_tasks (id, workspace_id, name, cron, prompt, cli_options, enabled, created_at)
+    VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+    ON CONFLICT(id) DO UPDATE SET
+      name = excluded.name,
+      cron = excluded.cron,
+      prompt = excluded.prompt,
+      cli_options = excluded.cli_options,
+      enabled = excluded.enabled
+  `).run(
+        id,
+        task.workspaceId,
+        task.name.trim() || 'Scheduled task',
+        task.cron.trim(),
+        task.prompt,
+        JSON.stringify(task.cliOptions || {}),
+        task.enabled ? 1 : 0,
+        nowIso(),
+    );
+    return { success: true, task: rowToTask(db.prepare('SELECT * FROM scheduled_tasks WHERE id = ?').get(id)) };
+}
+
+export function deleteScheduledTask(db: Database.Database, id: string): { success: boolean } {
+    return { success: db.prepare('DELETE FROM scheduled_tasks WHERE id = ?').run(id).changes > 0 };
+}
+
+/** Due once its next run after the previous one (or after creation) has come. */
+export function isTaskDue(task: ScheduledTask, now = new Date()): boolean {
+    return task.nextRunAt !== null && Number(task.nextRunAt) * 1000 <= now.getTime();
+}
+
+/**
+ * Run a task headlessly with the workspace's CLI options and the task's own on top, then
+ * store the prompt and the agent's answer as a new conversation. Nobody is there to
+ * answer approval prompts, so the turn runs with approvals set to `never`.
+ */
+export async function runScheduledTask(
+    getWebContents: () => WebContents | null,
+    state: AppState,
+    taskId: string,
+): Promise<{ success: boolean; result?: ScheduledTaskResult; error?: string }> {
+    const row = state.db.prepare('SELECT * FROM scheduled_tasks WHERE id = ?').get(taskId);
+    if (!row) return { success: false, error: 'Scheduled task not found' };
+    const task = rowToTask(row);
+    const workspace = state.db.prepare('SELECT path FROM workspaces WHERE id = ?').get(task.workspaceId) as any;
+    if (!workspace) return { success: false, error: 'Workspace not found' };
+
+    const startedAt = nowIso();
+    state.db
+        .prepare("UPDATE scheduled_tasks SET last_run_at = ?, last_status = 'running', last_error = NULL WHERE id = ?")
+        .run(startedAt, task.id);
+
+    const runConfig = resolveRuntimeConfig(
+        { ...state.config, cwd: expandTildePath(workspace.path) },
+        { ...task.cliOptions, askForApproval: 'never' },
+        dbGetWorkspaceCliOptions(state.db, task.workspaceId),
+    );
+    const outcome = await runCodexToCompletion(task.prompt, runConfig);
+
+    const conversationId = generateId('conv');
+    dbCreateConversation(state.db, conversationId, task.workspaceId, `Scheduled: ${task.name}`);
+    const answer = outcome.success
+        ? outcome.text
+        : [outcome.text, `Scheduled run failed: ${outcome.error}`].filter(Boolean).join('\n\n');
+    for (const [role, content] of [['user', task.prompt], ['assistant', answer]]) {
+        dbCreateMessage(state.db, {
+            id: generateId('msg'),
+            conversationId,
+            role,
+            content,
+            timestamp: new Date().toISOString(),
+        });
+    }
+
+    const finishedAt = nowIso();
+    state.db
+        .prepare('UPDATE scheduled_tasks SET last_status = ?, last_error = ?, last_conversation_id = ? WHERE id = ?')
+        .run(outcome.success ? 'completed' : 'failed', outcome.error ?? null, conversationId, task.id);
+
+    const result: ScheduledTaskResult = {
+        taskId: task.id,
+        workspaceId: task.workspaceId,
+        name: task.name,
+        success: outcome.success,
+        conversationId,
+        error: outcome.error,
+        startedAt,
+        finishedAt,
+    };
+    getWebContents()?.send('scheduled-task-completed', result);
+    return { success: outcome.success, result, error: outcome.error };
+}
+
+/**
+ * Check every minute for due tasks. A run that was missed while the app was closed happens
+ * once at the next check, not once per missed slot. Returns a stop function.
+ */
+export function startScheduler(
+    getWebContents: () => WebContents | null,
+    state: AppState,
+): () => void {
+    const running = new Set<string>();
+    const timer = setInterval(() => {
+        for (const task of listScheduledTasks(state.db)) {
+            if (running.has(task.id) || !isTaskDue(task)) continue;
+            running.add(task.id);
+            runScheduledTask(getWebContents, state, task.id)
+                .catch((err) => console.error('[scheduler] Run failed:', err))
+                .finally(() => running.delete(task.id));
+        }
+    }, SCHEDULER_CHECK_INTERVAL_MS);
+    timer.unref();
+    return () => clearInterval(timer);
+}
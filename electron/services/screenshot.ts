@@ -0,0 +1,90 @@
+import { BrowserWindow, desktopCapturer, screen } from 'electron';
+import { MessageAttachment } from './models';
+import { storeImage } from './attachments';
+
+export interface CaptureRegion {
+    x: number;
+    y: number;
+    width: number;
+    height: number;
+}
+
+// Time for the compositor to repaint after hiding our window.
+const HIDE_SETTLE_MS = 250;
+
+/**
+ * Clamp a region (DIP, relative to the display's top-left) to the display and scale
+ * it to the pixels of the captured image. Returns null when nothing of the region is on screen.
+ */
+export function scaleRegion(
+    region: CaptureRegion,
+    display: { width: number; height: number },
+    scaleFactor: number,
+): CaptureRegion | null {
+    const x = Math.max(0, Math.floor(region.x));
+    const y = Math.max(0, Math.floor(region.y));
+    const right = Math.min(display.width, Math.ceil(region.x + region.width));
+    const bottom = Math.min(display.height, Math.ceil(region.y + region.height));
+    if (right <= x || bottom <= y) return null;
+    return {
+        x: Math.round(x * scaleFactor),
+        y: Math.round(y * scaleFactor),
+        width: Math.round((right - x) * scaleFactor),
+        height: Math.round((bottom - y) * scaleFactor),
+    };
+}
+
+/**
+ * Capture the display under the cursor (or `displayId`), optionally cropped to `region`,
+ * and save it to the attachments store. The returned attachment can be passed as
+ * `{ path }` to the next stream_codex call. Our own window is hidden while capturing so
+ * the screenshot shows the app being asked about.
+ */
+export async function captureScreen(
+    window: BrowserWindow | null,
+    region?: CaptureRegion,
+    displayId?: number,
+    hideWindow = true,
+): Promise<{ success: boolean; attachment?: MessageAttachment; error?: string }> {
+    const display = displayId !== undefined
+        ? screen.getAllDisplays().find((d) => d.id === displayId)
+        : screen.getDisplayNearestPoint(screen.getCursorScreenPoint());
+    if (!display) return { success: false, error: 'Display not found' };
+
+    const wasVisible = !!window && window.isVisible() && hideWindow;
+    try {
+        if (wasVisible) {
+            window!.hide();
+            await new Promise((resolve) => setTimeout(resolve, HIDE_SETTLE_MS));
+        }
+
+        const { width, height } = display.size;
+        const sources = await desktopCapturer.getSources({
+            types: ['screen'],
+            thumbnailSize: {
+                width: Math.round(width * display.scaleFactor),
+                height: Math.round(height * display.scaleFactor),
+            },
+        });
+        const source = sources.find((s) => s.display_id === String(display.id)) || sources[0];
+        if (!source || source.thumbnail.isEmpty()) {
+            return { success: false, error: 'Screen capture is unavailable (check screen recording permission)' };
+        }
+
+        let image = source.thumbnail;
+        if (region) {
+            // The thumbnail may be smaller than requested; scale by its actual size.
+            const scale = image.getSize().width / width;
+            const crop = scaleRegion(region, display.size, scale);
+            if (!crop) return { success: false, error: 'Region is outside the display' };
+            image = image.crop(crop);
+        }
+
+        const stamp = new Date().toISOString().replace(/[:.]/g, '-');
+        return { success: true, attachment: storeImage(image.toPNG(), 'image/png', `screenshot-${stamp}.png`) };
+    } catch (err: any) {
+        return { success: false, error: err.message };
+    } finally {
+        if (wasVisible) window!.show();
+    }
+}
@@ -0,0 +1,110 @@
+import Database from 'better-sqlite3';
+import { safeStorage } from 'electron';
+import { dbGetSetting, dbSetSetting } from './db';
+
+const SETTINGS_KEY = 'secrets.settings';
+const SECRET_KEY_PREFIX = 'secret.';
+const SECRET_NAME = /^[\w.-]{1,128}$/;
+
+export const CODEX_API_KEY_SECRET = 'codex.apiKey';
+export const GITHUB_TOKEN_SECRET = 'github.token';
+export const webhookSecretName = (webhookId: string) => `webhook.${webhookId}`;
+
+export interface SecretSettings {
+    /** Keep the API key, GitHub token and webhook secrets in the OS keychain instead of the database. */
+    useKeychain: boolean;
+}
+
+let db: Database.Database | null = null;
+let settings: SecretSettings = { useKeychain: false };
+
+/** Load the settings and keep the database for the secrets; called at startup and after a restore. */
+export function initSecrets(database: Database.Database): void {
+    db = database;
+    settings = getSecretSettings(database);
+}
+
+export function getSecretSettings(database: Database.Database): SecretSettings {
+    return { useKeychain: false, ...dbGetSetting<Partial<SecretSettings>>(database, SETTINGS_KEY, {}) };
+}
+
+export function setSecretSettings(database: Database.Database, update: Partial<SecretSettings>): SecretSettings {
+    const current = getSecretSettings(database);
+    settings = { ...current, ...update, useKeychain: !!(update.useKeychain ?? current.useKeychain) };
+    dbSetSetting(database, SETTINGS_KEY, settings);
+    return settings;
+}
+
+/**
+ * Whether the OS keychain can protect secrets: the macOS Keychain, DPAPI on Windows, or
+ * libsecret/KWallet on Linux. Linux without either only obfuscates, which does not count.
+ */
+export function keychainAvailable(): boolean {
+    try {
+        if (!safeStorage.isEncryptionAvailable()) return false;
+        return process.platform !== 'linux' || safeStorage.getSelectedStorageBackend() !== 'basic_text';
+    } catch {
+        return false;
+    }
+}
+
+function requireStore(name: string): Database.Database {
+    if (!SECRET_NAME.test(name)) throw new Error(`Invalid secret name: ${name}`);
+    if (!db) throw new Error('Secrets are not initialized');
+    return db;
+}
+
+/** Encrypt `value` with the keychain-held key and store it under `name`. */
+export function setSecret(name: string, value: string): { success: boolean; error?: string } {
+    try {
+        const store = requireStore(name);
+        if (!keychainAvailable()) return { success: false, error: 'The OS keychain is not available' };
+        dbSetSetting(store, SECRET_KEY_PREFIX + name, safeStorage.encryptString(value).toString('base64'));
+        return { success: true };
+    } catch (err: any) {
+        return { success: false, error: err.message };
+    }
+}
+
+/** The stored secret, or null when there is none or it cannot be decrypted (e.g. another machine's). */
+export function getSecret(name: string): string | null {
+    try {
+        const stored = dbGetSetting<string | null>(requireStore(name), SECRET_KEY_PREFIX + name, null);
+        if (!stored || !keychainAvailable()) return null;
+        return safeStorage.decryptString(Buffer.from(stored, 'base64'));
+    } catch {
+        return null;
+    }
+}
+
+export function deleteSecret(name: string): { success: boolean; error?: string } {
+    try {
+        const store = requireStore(name);
+        const removed = store.prepare('DELETE FROM settings WHERE key = ?').run(SECRET_KEY_PREFIX + name).changes > 0;
+        return { success: removed };
+    } catch (err: any) {
+        return { success: false, error: err.message };
+    }
+}
+
+/**
+ * Keep `value` in the keychain when the user turned that on and it is available. Returns
+ * whether it was stored there, in which case the caller leaves it out of the database.
+ */
+export function rememberSecret(name: string, value: string): boolean {
+    if (!settings.useKeychain || !value) return false;
+    return setSecret(name, value).success;
+}
+
+/**
+ * `secret-get` for the renderer: only whether the secret exists. The values are used in
+ * the main process only; the renderer may set or delete them but never read them back.
+ */
+export function getSecretForRenderer(name: string): { success: boolean; exists: boolean; error?: string } {
+    try {
+        requireStore(name);
+    } catch (err: any) {
+        return { success: false, exists: false, error: err.message };
+    }
+    return { success: true, exists: getSecret(name) !== null };
+}
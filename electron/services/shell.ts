@@ -1,82 +1,317 @@
 import { ChildProcess, spawn } from 'child_process';
 import { WebContents } from 'electron';
+import * as fs from 'fs';
+import * as os from 'os';
+import * as path from 'path';
 import { AppState, ShellCommandResult } from './models';
-import { commandSpawnOptions, expandTildePath, generateId } from './utils';
+import { resourceLimits } from './resources';
+import { getRun, recordRun } from './runs';
+import { commandSpawnOptions, expandTildePath, generateId, nowIso, trackChild } from './utils';
 
-export function runCommand(
+/**
+ * Captured command output. The first `outputSpillBytes` stay in memory; once output grows
+ * past that, the whole stream is written to a file in the temp directory instead.
+ */
+class OutputCapture {
+    text = '';
+    file: string | null = null;
+    private fd: number | null = null;
+    private readonly limit = resourceLimits().outputSpillBytes;
+
+    constructor(private readonly commandId: string, private readonly stream: 'stdout' | 'stderr') { }
+
+    append(chunk: string): void {
+        if (this.file === null && this.text.length + chunk.length <= this.limit) {
+            this.text += chunk;
+            return;
+        }
+        try {
+            if (this.file === null) {
+                const dir = path.join(os.tmpdir(), 'codex-wui-output');
+                fs.mkdirSync(dir, { recursive: true });
+                this.file = path.join(dir, `${this.commandId}.${this.stream}.log`);
+                this.fd = fs.openSync(this.file, 'w');
+                fs.writeSync(this.fd, this.text);
+            }
+            if (this.fd !== null) fs.writeSync(this.fd, chunk);
+        } catch (err) {
+            console.error('[shell] Failed to spill output to disk:', err);
+            this.close();
+        }
+    }
+
+    close(): void {
+        if (this.fd === null) return;
+        try {
+            fs.closeSync(this.fd);
+        } catch { }
+        this.fd = null;
+    }
+}
+
+/**
+ * Start a shell command and register it under its command id. stdout/stderr are streamed
+ * as `command-output` events while it runs, input can be sent with `commandWriteStdin`,
+ * and `command-exit` fires when it finishes.
+ */
+export function startCommand(
     webContents: WebContents,
     command: string,
     cwd: string,
     state: AppState,
-): ShellCommandResult {
+): { commandId: string; done: Promise<ShellCommandResult> } {
     const commandId = generateId('cmd');
     const runCwd = expandTildePath(cwd.trim() || state.config.cwd);
+    const startedAt = nowIso();
 
-    try {
-        const { execSync } = require('child_process');
-        const opts = commandSpawnOptions(runCwd);
-        const result = execSync(command, {
-            ...opts,
-            shell: true,
-            stdio: ['pipe', 'pipe', 'pipe'],
-            maxBuffer: 10 * 1024 * 1024,
-        });
-
-        const stdout = result.toString();
-        if (stdout) {
-            webContents.send('command-output', { commandId, type: 'stdout', data: stdout });
-        }
+    const child = trackChild(spawn(command, {
+        ...commandSpawnOptions(runCwd),
+        shell: true,
+        stdio: ['pipe', 'pipe', 'pipe'],
+        // Own process group so kill reaches whatever the shell started.
+        detached: process.platform !== 'win32',
+    }));
+    state.runningCommands.set(commandId, child);
 
+    const stdout = new OutputCapture(commandId, 'stdout');
+    const stderr = new OutputCapture(commandId, 'stderr');
+    child.stdout?.on('data', (data: Buffer) => {
+        const text = data.toString();
+        stdout.append(text);
+        webContents.send('command-output', { commandId, type: 'stdout', data: text });
+    });
+    child.stderr?.on('data', (data: Buffer) => {
+        const text = data.toString();
+        stderr.append(text);
+        webContents.send('command-output', { commandId, type: 'stderr', data: text });
+    });
+    const captured = () => {
+        stdout.close();
+        stderr.close();
         return {
-            success: true,
-            commandId,
-            output: stdout,
-            exitCode: 0,
+            output: stdout.text,
+            errorOutput: stderr.text,
+            outputFile: stdout.file ?? undefined,
+            errorOutputFile: stderr.file ?? undefined,
         };
+    };
+    const record = (exitCode: number) =>
+        recordRun({ commandId, command, cwd: runCwd, exitCode, startedAt, finishedAt: nowIso(), ...captured() });
+
+    const done = new Promise<ShellCommandResult>((resolve) => {
+        child.on('error', (err) => {
+            state.runningCommands.delete(commandId);
+            record(-1);
+            webContents.send('command-exit', { commandId, exitCode: -1 });
+            resolve({ success: false, commandId, ...captured(), exitCode: -1, error: err.message });
+        });
+        child.on('close', (code, signal) => {
+            state.runningCommands.delete(commandId);
+            const exitCode = code ?? -1;
+            record(exitCode);
+            webContents.send('command-exit', { commandId, exitCode });
+            const { output, outputFile, errorOutput, errorOutputFile } = captured();
+            resolve(
+                exitCode === 0
+                    ? { success: true, commandId, output, outputFile, exitCode }
+                    : {
+                        success: false,
+                        commandId,
+                        output,
+                        outputFile,
+                        errorOutput,
+                        errorOutputFile,
+                        exitCode,
+                        error: signal ? `Command terminated by ${signal}` : `Command failed with exit code ${exitCode}`,
+                    },
+            );
+        });
+    });
+
+    return { commandId, done };
+}
+
+/**
+ * Run a command and wait for it to finish. Output is streamed while it runs, so long or
+ * interactive commands can be followed and answered through `commandWriteStdin`.
+ */
+export function runCommand(
+    webContents: WebContents,
+    command: string,
+    cwd: string,
+    state: AppState,
+): Promise<ShellCommandResult> {
+    return startCommand(webContents, command, cwd, state).done;
+}
+
+/** Start a finished command again with the same command line and directory, to compare the runs. */
+export function rerunCommand(
+    webContents: WebContents,
+    state: AppState,
+    commandId: string,
+): { success: boolean; commandId?: string; error?: string } {
+    const run = getRun(commandId);
+    if (!run) return { success: false, error: 'Run not found' };
+    return { success: true, commandId: startCommand(webContents, run.command, run.cwd, state).commandId };
+}
+
+export function commandWriteStdin(
+    state: AppState,
+    commandId: string,
+    data: string,
+): { success: boolean; error?: string } {
+    const child = state.runningCommands.get(commandId);
+    if (!child) return { success: false, error: 'Command not found' };
+    if (!child.stdin || child.stdin.destroyed) return { success: false, error: 'Command stdin is closed' };
+    try {
+        child.stdin.write(data);
+        return { success: true };
     } catch (err: any) {
-        const stdout = err.stdout?.toString() || '';
-        const stderr = err.stderr?.toString() || '';
+        return { success: false, error: err.message };
+    }
+}
 
-        if (stdout) {
-            webContents.send('command-output', { commandId, type: 'stdout', data: stdout });
-        }
-        if (stderr) {
-            webContents.send('command-output', { commandId, type: 'stderr', data: stderr });
+export function killCommand(state: AppState, commandId: string): { success: boolean; error?: string } {
+    const child = state.runningCommands.get(commandId);
+    if (!child) return { success: false, error: 'Command not found' };
+    try {
+        if (process.platform !== 'win32' && child.pid) {
+            process.kill(-child.pid, 'SIGTERM');
+        } else {
+            child.kill();
         }
+        return { success: true };
+    } catch (err: any) {
+        return { success: false, error: err.message };
+    }
+}
 
-        return {
-            success: false,
-            commandId,
-            output: stdout,
-            errorOutput: stderr,
-            exitCode: err.status ?? -1,
-            error: err.message,
-        };
+// ===== PTY Terminals =====
+
+export interface AvailableShell {
+    /** `pwsh`, `powershell`, `cmd`, `git-bash`, or the executable name on Unix. */
+    name: string;
+    path: string;
+}
+
+interface ShellCandidate {
+    name: string;
+    /** Bare names are looked up on PATH; absolute paths are checked as they are. */
+    locations: (string | undefined)[];
+}
+
+function shellCandidates(platform: NodeJS.Platform, env: NodeJS.ProcessEnv): ShellCandidate[] {
+    if (platform === 'win32') {
+        const systemRoot = env.SystemRoot || env.windir || 'C:\\Windows';
+        const programFiles = [env.ProgramFiles, env['ProgramFiles(x86)'], env.LOCALAPPDATA && `${env.LOCALAPPDATA}\\Programs`];
+        return [
+            { name: 'pwsh', locations: ['pwsh.exe', env.ProgramFiles && `${env.ProgramFiles}\\PowerShell\\7\\pwsh.exe`] },
+            {
+                name: 'powershell',
+                locations: ['powershell.exe', `${systemRoot}\\System32\\WindowsPowerShell\\v1.0\\powershell.exe`],
+            },
+            { name: 'cmd', locations: [env.ComSpec, 'cmd.exe', `${systemRoot}\\System32\\cmd.exe`] },
+            // Not `bash.exe` on PATH: in System32 that is the WSL launcher.
+            { name: 'git-bash', locations: programFiles.map((dir) => dir && `${dir}\\Git\\bin\\bash.exe`) },
+        ];
     }
+    return ['zsh', 'bash', 'sh', 'dash'].map((name) => ({
+        name,
+        locations: [name, `/bin/${name}`, `/usr/bin/${name}`, `/usr/local/bin/${name}`],
+    }));
 }
 
-export function killCommand(_commandId: string): { success: boolean; error?: string } {
-    return { success: false, error: 'Not supported in current runtime' };
+function isExecutableFile(filePath: string): boolean {
+    try {
+        if (!fs.statSync(filePath).isFile()) return false;
+        if (process.platform !== 'win32') fs.accessSync(filePath, fs.constants.X_OK);
+        return true;
+    } catch {
+        return false;
+    }
 }
 
-// ===== PTY Terminals =====
+function locateExecutable(
+    location: string,
+    platform: NodeJS.Platform,
+    env: NodeJS.ProcessEnv,
+    exists: (filePath: string) => boolean,
+): string | null {
+    const paths = platform === 'win32' ? path.win32 : path.posix;
+    if (paths.isAbsolute(location)) return exists(location) ? location : null;
+    for (const dir of (env.PATH ?? env.Path ?? '').split(paths.delimiter)) {
+        if (!dir) continue;
+        const candidate = paths.join(dir, location);
+        if (exists(candidate)) return candidate;
+    }
+    return null;
+}
+
+/**
+ * Shells found on this machine in the order a terminal falls back through them: pwsh,
+ * powershell, cmd and Git Bash on Windows; zsh, bash, sh and dash elsewhere. Each is
+ * looked up on PATH and then in its usual install location.
+ */
+export function listAvailableShells(
+    platform: NodeJS.Platform = process.platform,
+    env: NodeJS.ProcessEnv = process.env,
+    exists: (filePath: string) => boolean = isExecutableFile,
+): AvailableShell[] {
+    const shells: AvailableShell[] = [];
+    for (const candidate of shellCandidates(platform, env)) {
+        for (const location of candidate.locations) {
+            const found = location && locateExecutable(location, platform, env, exists);
+            if (found) {
+                shells.push({ name: candidate.name, path: found });
+                break;
+            }
+        }
+    }
+    return shells;
+}
+
+/**
+ * The shell a terminal should start: `requested` (a name from `listAvailableShells` or an
+ * executable) when it can be found, else the first available one. `fallbackFrom` names
+ * the requested shell that was missing.
+ */
+export function resolveShell(
+    requested?: string,
+    platform: NodeJS.Platform = process.platform,
+    env: NodeJS.ProcessEnv = process.env,
+    exists: (filePath: string) => boolean = isExecutableFile,
+): (AvailableShell & { fallbackFrom?: string }) | null {
+    const available = listAvailableShells(platform, env, exists);
+    if (requested) {
+        const known = available.find((s) => s.name === requested || s.path === requested);
+        if (known) return known;
+        const found = locateExecutable(requested, platform, env, exists);
+        const paths = platform === 'win32' ? path.win32 : path.posix;
+        if (found) return { name: paths.basename(found).replace(/\.exe$/i, ''), path: found };
+    }
+    return available[0] ? { ...available[0], fallbackFrom: requested || undefined } : null;
+}
 
 export function ptyCreate(
     webContents: WebContents,
     state: AppState,
     cwd?: string,
     shell?: string,
-): { id: string; shell: string } {
+): { id: string; shell: string; shellName: string; fallbackFrom?: string } | { success: false; error: string } {
+    const resolved = resolveShell(shell);
+    if (!resolved) {
+        return { success: false, error: 'No shell found. Install PowerShell, cmd or Git Bash, or a POSIX shell, and retry.' };
+    }
     const id = generateId('pty');
-    const shellPath = shell || (process.platform === 'win32' ? 'powershell.exe' : 'bash');
+    const shellPath = resolved.path;
     const runCwd = expandTildePath(cwd || state.config.cwd);
     const opts = commandSpawnOptions(runCwd);
 
-    const child = spawn(shellPath, [], {
+    const child = trackChild(spawn(shellPath, [], {
         ...opts,
         cwd: runCwd,
         stdio: ['pipe', 'pipe', 'pipe'],
-    });
+    }));
 
     state.ptyTerminals.set(id, child);
 
@@ -92,12 +327,17 @@ export function ptyCreate(
         });
     }
 
+    child.on('error', (err) => {
+        state.ptyTerminals.delete(id);
+        webContents.send('pty-data', { id, data: `Failed to start ${shellPath}: ${err.message}\r\n` });
+        webContents.send('pty-exit', { id, exitCode: -1 });
+    });
     child.on('exit', (code) => {
         state.ptyTerminals.delete(id);
         webContents.send('pty-exit', { id, exitCode: code ?? -1 });
     });
 
-    return { id, shell: shellPath };
+    return { id, shell: shellPath, shellName: resolved.name, fallbackFrom: resolved.fallbackFrom };
 }
 
 export function ptyWrite(
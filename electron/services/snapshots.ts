@@ -0,0 +1,301 @@
+import Database from 'better-sqlite3';
+import { spawn } from 'child_process';
+import * as fs from 'fs';
+import * as path from 'path';
+import { parseGitStatus, runGit } from './git';
+import { commandSpawnOptions, generateId, homeDir, nowIso, trackChild } from './utils';
+
+export interface TurnSnapshotFile {
+    path: string;
+    /** false when the turn created the file; rollback deletes it. */
+    existed: boolean;
+}
+
+export interface TurnSnapshot {
+    turnId: string;
+    conversationId: string;
+    cwd: string;
+    status: 'active' | 'rolled_back';
+    createdAt: string;
+    rolledBackAt: string | null;
+    files: TurnSnapshotFile[];
+}
+
+/** State of a git work tree taken before the turn's CLI started. */
+export interface TurnBaseline {
+    root: string;
+    /** The turn's cwd and its real path, which git reports paths under. */
+    cwd: string;
+    realCwd: string;
+    /** Where the copies of `dirty` are kept until the turn ends. */
+    dir: string;
+    /** Files that differed from HEAD at turn start: a copy of their content, or null when missing. */
+    dirty: Map<string, string | null>;
+    /** Backups taken so far, for unique backup names. */
+    files: number;
+}
+
+const baselines = new Map<string, TurnBaseline>();
+
+function snapshotDir(turnId: string, kind: 'snapshots' | 'snapshot-baselines' = 'snapshots'): string {
+    const home = homeDir();
+    if (!home) throw new Error('Unable to resolve home directory');
+    return path.join(home, '.codex-wui', kind, turnId);
+}
+
+/** Whether `target` resolves to a path below `root`. */
+function isInside(root: string, target: string): boolean {
+    const relative = path.relative(path.resolve(root), path.resolve(root, target));
+    return relative !== '' && !relative.startsWith('..') && !path.isAbsolute(relative);
+}
+
+/** Paths and kinds of a file_change item, in either shape the CLI reports them. */
+function fileChangeKinds(changes: any): Array<{ path: string; kind: string }> {
+    if (Array.isArray(changes)) {
+        return changes
+            .filter((c) => typeof c?.path === 'string')
+            .map((c) => ({ path: c.path, kind: c.kind || 'update' }));
+    }
+    if (changes && typeof changes === 'object') {
+        return Object.entries<any>(changes).map(([filePath, c]) => ({
+            path: filePath,
+            kind: c?.type || Object.keys(c || {})[0] || 'update',
+        }));
+    }
+    return [];
+}
+
+/**
+ * Record the pre-turn state of the turn's git work tree. The CLI reports a file change
+ * only once the patch is applied, so this has to run before it starts: files that differ
+ * from HEAD are copied now, clean ones are read back from HEAD when the turn touches them.
+ * Outside a git work tree nothing is recorded and the turn cannot be rolled back.
+ */
+export async function captureTurnBaseline(turnId: string, cwd: string): Promise<TurnBaseline | null> {
+    const top = await runGit(cwd, ['rev-parse', '--show-toplevel']);
+    if (!top.success) return null;
+    const root = path.resolve(top.stdout.trim());
+    const status = await runGit(root, ['status', '--porcelain=v1', '-z', '--untracked-files=all']);
+    if (!status.success) return null;
+
+    const parsed = parseGitStatus(status.stdout);
+    const changed = new Set([
+        ...parsed.staged.map((e) => e.path),
+        ...parsed.unstaged.map((e) => e.path),
+        ...parsed.untracked,
+        ...parsed.conflicted,
+    ]);
+    const dirty = new Map<string, string | null>();
+    const dir = snapshotDir(generateId(turnId), 'snapshot-baselines');
+    for (const relative of changed) {
+        const absolute = path.join(root, relative);
+        try {
+            if (!fs.existsSync(absolute) || !fs.statSync(absolute).isFile()) {
+                dirty.set(absolute, null);
+                continue;
+            }
+            await fs.promises.mkdir(dir, { recursive: true });
+            const copy = path.join(dir, `${dirty.size}-${path.basename(absolute)}`);
+            await fs.promises.copyFile(absolute, copy);
+            dirty.set(absolute, copy);
+        } catch (error) {
+            console.error(`[snapshots] Failed to record ${absolute}:`, error);
+        }
+    }
+    const baseline: TurnBaseline = { root, cwd, realCwd: await fs.promises.realpath(cwd), dir, dirty, files: 0 };
+    baselines.set(turnId, baseline);
+    return baseline;
+}
+
+/**
+ * Drop the turn's baseline once its CLI has exited; snapshots already taken stay. A retry
+ * of the turn records its own baseline, which this leaves in place.
+ */
+export function releaseTurnBaseline(turnId: string, baseline: TurnBaseline | null): void {
+    if (!baseline) return;
+    if (baselines.get(turnId) === baseline) baselines.delete(turnId);
+    try {
+        fs.rmSync(baseline.dir, { recursive: true, force: true });
+    } catch { }
+}
+
+/** Write a file's HEAD content to `dest` byte for byte; false when HEAD has no such file. */
+function writeHeadBlob(root: string, relative: string, dest: string): Promise<boolean> {
+    return new Promise((resolve) => {
+        const child = trackChild(spawn('git', ['cat-file', 'blob', `HEAD:${relative}`], {
+            ...commandSpawnOptions(root),
+            stdio: ['ignore', 'pipe', 'ignore'],
+        }));
+        const out = fs.createWriteStream(dest);
+        child.stdout?.pipe(out);
+        child.on('error', () => resolve(false));
+        child.on('close', (code) => {
+            out.end(() => {
+                if (code === 0) return resolve(true);
+                fs.rmSync(dest, { force: true });
+                resolve(false);
+            });
+        });
+    });
+}
+
+/**
+ * Keep the pre-turn state of every file a completed file_change item touched. The turn's
+ * row is created lazily; turns that touch no files leave no snapshot. Paths outside the
+ * turn's cwd are refused.
+ */
+export async function snapshotFileChange(
+    db: Database.Database,
+    turnId: string,
+    conversationId: string,
+    cwd: string,
+    item: any,
+): Promise<void> {
+    for (const change of fileChangeKinds(item?.changes)) {
+        await snapshotFile(db, turnId, conversationId, cwd, change.path, change.kind);
+    }
+}
+
+async function snapshotFile(
+    db: Database.Database,
+    turnId: string,
+    conversationId: string,
+    cwd: string,
+    filePath: string,
+    kind: string,
+): Promise<void> {
+    const absolute = path.resolve(cwd, filePath);
+    const baseline = baselines.get(turnId);
+    if (!baseline) return;
+    if (!isInside(cwd, absolute)) {
+        console.error(`[snapshots] Refusing to snapshot ${absolute}: outside ${cwd}`);
+        return;
+    }
+    try {
+        const known = db
+            .prepare('SELECT 1 FROM turn_snapshot_files WHERE turn_id = ? AND path = ?')
+            .get(turnId, absolute);
+        if (known) return;
+
+        const dir = snapshotDir(turnId);
+        const backupPath = path.join(dir, `${baseline.files++}-${path.basename(absolute)}`);
+        const inTree = path.join(baseline.realCwd, path.relative(baseline.cwd, absolute));
+        let existed = true;
+        if (baseline.dirty.has(inTree)) {
+            const copy = baseline.dirty.get(inTree)!;
+            existed = copy !== null;
+            if (copy) {
+                fs.mkdirSync(dir, { recursive: true });
+                fs.copyFileSync(copy, backupPath);
+            }
+        } else if (kind === 'add') {
+            existed = false;
+        } else {
+            fs.mkdirSync(dir, { recursive: true });
+            const relative = path.relative(baseline.root, inTree).split(path.sep).join('/');
+            if (!(await writeHeadBlob(baseline.root, relative, backupPath))) {
+                // Ignored files are neither in HEAD nor recorded at turn start.
+                console.error(`[snapshots] No pre-turn content for ${absolute}; it cannot be rolled back`);
+                return;
+            }
+        }
+
+        db.prepare(`
+      INSERT OR IGNORE INTO turn_snapshots (id, conversation_id, cwd, created_at)
+      VALUES (?, ?, ?, ?)
+    `).run(turnId, conversationId, cwd, nowIso());
+        db.prepare(`
+      INSERT OR IGNORE INTO turn_snapshot_files (turn_id, path, existed, backup_path)
+      VALUES (?, ?, ?, ?)
+    `).run(turnId, absolute, existed ? 1 : 0, existed ? backupPath : null);
+    } catch (error) {
+        console.error(`[snapshots] Failed to snapshot ${absolute}:`, error);
+    }
+}
+
+/**
+ * Pre-turn content of a file the turn changed: null when the turn created it, undefined
+ * when the file was not snapshotted.
+ */
+export function readSnapshotOriginal(db: Database.Database, turnId: string, absolutePath: string): string | null | undefined {
+    const row = db
+        .prepare('SELECT existed, backup_path FROM turn_snapshot_files WHERE turn_id = ? AND path = ?')
+        .get(turnId, absolutePath) as any;
+    if (!row) return undefined;
+    if (!row.existed) return null;
+    try {
+        return fs.readFileSync(row.backup_path, 'utf-8');
+    } catch {
+        return undefined;
+    }
+}
+
+function toTurnSnapshots(db: Database.Database, turns: any[]): TurnSnapshot[] {
+    const filesStmt = db.prepare('SELECT path, existed FROM turn_snapshot_files WHERE turn_id = ? ORDER BY rowid');
+    return turns.map((row) => ({
+        turnId: row.id,
+        conversationId: row.conversation_id,
+        cwd: row.cwd,
+        status: row.status,
+        createdAt: row.created_at,
+        rolledBackAt: row.rolled_back_at,
+        files: (filesStmt.all(row.id) as any[]).map((f) => ({ path: f.path, existed: !!f.existed })),
+    }));
+}
+
+export function listTurnSnapshots(db: Database.Database, conversationId: string): TurnSnapshot[] {
+    const turns = db
+        .prepare('SELECT * FROM turn_snapshots WHERE conversation_id = ? ORDER BY CAST(created_at AS INTEGER) DESC, rowid DESC')
+        .all(conversationId) as any[];
+    return toTurnSnapshots(db, turns);
+}
+
+export function getTurnSnapshot(db: Database.Database, turnId: string): TurnSnapshot | null {
+    const turn = db.prepare('SELECT * FROM turn_snapshots WHERE id = ?').get(turnId);
+    return turn ? toTurnSnapshots(db, [turn])[0] : null;
+}
+
+/**
+ * Restore every file of a turn to its pre-turn content and delete the files it created.
+ * Files outside the turn's cwd are left alone.
+ */
+export function rollbackTurn(
+    db: Database.Database,
+    turnId: string,
+): { success: boolean; restored: string[]; removed: string[]; errors: string[]; error?: string } {
+    const turn = db.prepare('SELECT * FROM turn_snapshots WHERE id = ?').get(turnId) as any;
+    if (!turn) return { success: false, restored: [], removed: [], errors: [], error: 'Snapshot not found' };
+    if (turn.status === 'rolled_back') {
+        return { success: false, restored: [], removed: [], errors: [], error: 'Turn was already rolled back' };
+    }
+
+    const files = db
+        .prepare('SELECT path, existed, backup_path FROM turn_snapshot_files WHERE turn_id = ?')
+        .all(turnId) as any[];
+    const restored: string[] = [];
+    const removed: string[] = [];
+    const errors: string[] = [];
+
+    for (const file of files) {
+        if (!isInside(turn.cwd, file.path)) {
+            errors.push(`${file.path}: outside ${turn.cwd}`);
+            continue;
+        }
+        try {
+            if (file.existed) {
+                fs.mkdirSync(path.dirname(file.path), { recursive: true });
+                fs.copyFileSync(file.backup_path, file.path);
+                restored.push(file.path);
+            } else if (fs.existsSync(file.path)) {
+                fs.unlinkSync(file.path);
+                removed.push(file.path);
+            }
+        } catch (err: any) {
+            errors.push(`${file.path}: ${err.message}`);
+        }
+    }
+
+    db.prepare("UPDATE turn_snapshots SET status = 'rolled_back', rolled_back_at = ? WHERE id = ?")
+        .run(nowIso(), turnId);
+    return { success: errors.length === 0, restored, removed, errors };
+}
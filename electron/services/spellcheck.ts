@@ -0,0 +1,113 @@
+import * as fs from 'fs';
+import * as path from 'path';
+import { spawnCommand } from './utils';
+
+export interface SpellingIssue {
+    word: string;
+    line: number;
+    column: number;
+    suggestions: string[];
+}
+
+const LOCALE_DICTIONARIES: Record<string, string> = {
+    en: 'en_US',
+    ko: 'ko_KR',
+};
+
+/**
+ * Dictionaries shipped with the app (`resources/dictionaries`, copied via extraResources)
+ * take precedence over whatever hunspell finds on the system.
+ */
+function bundledDictionaryDir(): string {
+    const resourcesPath = (process as any).resourcesPath as string | undefined;
+    const packaged = resourcesPath ? path.join(resourcesPath, 'dictionaries') : '';
+    if (packaged && fs.existsSync(packaged)) return packaged;
+    return path.join(__dirname, '..', '..', 'resources', 'dictionaries');
+}
+
+function resolveDictionary(lang: string): string {
+    const name = LOCALE_DICTIONARIES[lang.toLowerCase()] || lang.replace('-', '_');
+    const bundled = path.join(bundledDictionaryDir(), name);
+    return fs.existsSync(`${bundled}.dic`) ? bundled : name;
+}
+
+/**
+ * Blank out code, URLs and paths so identifiers are not reported as typos.
+ * Characters are replaced with spaces to keep column positions intact.
+ */
+export function maskNonProse(text: string): string {
+    const blank = (match: string) => match.replace(/[^\n]/g, ' ');
+    return text
+        .replace(/```[\s\S]*?```/g, blank)
+        .replace(/`[^`\n]*`/g, blank)
+        .replace(/\bhttps?:\/\/\S+/g, blank)
+        .replace(/@?[\w.-]*[\/\\][\w./\\-]*/g, blank)
+        .replace(/\b\w*[_$]\w*\b|\b[a-z]+[A-Z]\w*\b/g, blank);
+}
+
+/**
+ * Map `hunspell -a` output back to the input lines. After the banner, every input line
+ * yields its result rows (never empty) followed by one empty row, so empty rows count
+ * lines even when a line was blank or fully masked.
+ */
+export function parseHunspellOutput(stdout: string, lines: string[]): SpellingIssue[] {
+    const issues: SpellingIssue[] = [];
+    let lineIndex = 0;
+    let cursor = 0;
+    for (const row of stdout.split('\n').slice(1)) {
+        if (lineIndex >= lines.length) break;
+        if (row === '') {
+            lineIndex++;
+            cursor = 0;
+            continue;
+        }
+        const miss = row.match(/^& (\S+) \d+ \d+: (.*)$/) || row.match(/^# (\S+) \d+$/);
+        if (!miss) continue;
+        const word = miss[1];
+        const column = lines[lineIndex].indexOf(word, cursor);
+        if (column >= 0) cursor = column + word.length;
+        issues.push({
+            word,
+            line: lineIndex + 1,
+            column: column >= 0 ? column + 1 : 0,
+            suggestions: miss[2] ? miss[2].split(', ').filter(Boolean) : [],
+        });
+    }
+    return issues;
+}
+
+/**
+ * Check prompt text with hunspell in ispell pipe mode (`hunspell -a`).
+ */
+export function checkPromptText(
+    text: string,
+    lang = 'en',
+): Promise<{ success: boolean; issues: SpellingIssue[]; error?: string }> {
+    const lines = maskNonProse(text).split('\n');
+    // '^' forces each line to be treated as text, never as a pipe-mode command.
+    const input = lines.map((l) => `^${l}`).join('\n') + '\n';
+    const dictionary = resolveDictionary(lang);
+
+    return new Promise((resolve) => {
+        const child = spawnCommand('hunspell', ['-a', '-d', dictionary]);
+        let stdout = '';
+        let stderr = '';
+        child.stdout?.on('data', (data: Buffer) => { stdout += data.toString(); });
+        child.stderr?.on('data', (data: Buffer) => { stderr += data.toString(); });
+        child.stdin?.on('error', () => { });
+        child.on('error', (err: any) => {
+            const msg = err.code === 'ENOENT'
+                ? 'hunspell executable was not found. Please install hunspell to enable spell checking.'
+                : err.message;
+            resolve({ success: false, issues: [], error: msg });
+        });
+        child.on('close', (code) => {
+            if (code === 0) return resolve({ success: true, issues: parseHunspellOutput(stdout, lines) });
+            const error = /can't open affix or dictionary/i.test(stderr)
+                ? `Spell-check dictionary "${path.basename(dictionary)}" is not installed. Add ${path.basename(dictionary)}.aff/.dic to resources/dictionaries or install it for hunspell.`
+                : stderr.trim() || `hunspell exited with ${code}`;
+            resolve({ success: false, issues: [], error });
+        });
+        child.stdin?.end(input);
+    });
+}
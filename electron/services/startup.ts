@@ -0,0 +1,97 @@
+import Database from 'better-sqlite3';
+import * as fs from 'fs';
+import { dbGetSetting, dbSetSetting } from './db';
+import { expandTildePath } from './utils';
+
+export type StartupMode = 'restore-last' | 'workspace' | 'blank';
+
+export interface StartupSettings {
+    mode: StartupMode;
+    workspaceId: string;
+}
+
+export interface StartupIntent {
+    mode: StartupMode;
+    workspaceId: string | null;
+    workspacePath: string | null;
+    conversationId: string | null;
+    missingWorkspace?: { id: string; path: string };
+}
+
+const SETTINGS_KEY = 'startup';
+const LAST_SESSION_KEY = 'startup.lastSession';
+
+const DEFAULT_SETTINGS: StartupSettings = { mode: 'restore-last', workspaceId: '' };
+
+export function getStartupSettings(db: Database.Database): StartupSettings {
+    return { ...DEFAULT_SETTINGS, ...dbGetSetting<Partial<StartupSettings>>(db, SETTINGS_KEY, {}) };
+}
+
+export function setStartupSettings(
+    db: Database.Database,
+    settings: Partial<StartupSettings>,
+): StartupSettings {
+    const merged = { ...getStartupSettings(db), ...settings };
+    dbSetSetting(db, SETTINGS_KEY, merged);
+    return merged;
+}
+
+export function recordLastSession(
+    db: Database.Database,
+    workspaceId: string,
+    conversationId?: string | null,
+): void {
+    try {
+        dbSetSetting(db, LAST_SESSION_KEY, { workspaceId, conversationId: conversationId || null });
+    } catch (error) {
+        console.error('[startup] Failed to record last session:', error);
+    }
+}
+
+/**
+ * Decide what the window should open at launch. A configured or remembered workspace
+ * whose folder has disappeared falls back to a blank start and is reported in
+ * `missingWorkspace` so the UI can offer to relocate or remove it.
+ */
+export function resolveStartupIntent(db: Database.Database): StartupIntent {
+    const settings = getStartupSettings(db);
+    const blank: StartupIntent = {
+        mode: settings.mode,
+        workspaceId: null,
+        workspacePath: null,
+        conversationId: null,
+    };
+    if (settings.mode === 'blank') return blank;
+
+    const last = dbGetSetting<{ workspaceId?: string; conversationId?: string | null }>(
+        db,
+        LAST_SESSION_KEY,
+        {},
+    );
+    const workspaceId = settings.mode === 'workspace' ? settings.workspaceId : last.workspaceId;
+    if (!workspaceId) return blank;
+
+    const workspace = db.prepare('SELECT id, path FROM workspaces WHERE id = ?').get(workspaceId) as any;
+    if (!workspace) return blank;
+
+    const workspacePath = expandTildePath(workspace.path);
+    if (!fs.existsSync(workspacePath)) {
+        return { ...blank, missingWorkspace: { id: workspace.id, path: workspacePath } };
+    }
+
+    let conversationId: string | null = null;
+    if (settings.mode === 'restore-last' && last.conversationId) {
+        const conv = db
+            .prepare('SELECT id FROM conversations WHERE id = ? AND workspace_id = ?')
+            .get(last.conversationId, workspaceId) as any;
+        conversationId = conv?.id ?? null;
+    }
+    if (!conversationId) {
+        const latest = db
+            .prepare('SELECT id FROM conversations WHERE workspace_id = ? ORDER BY rowid DESC LIMIT 1')
+            .get(workspaceId) as any;
+        conversationId = latest?.id ?? null;
+    }
+
+    return { mode: settings.mode, workspaceId, workspacePath, conversationId };
+}
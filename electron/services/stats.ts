@@ -0,0 +1,150 @@
+import Database from 'better-sqlite3';
+import { AppState, Message, MessageStats } from './models';
+
+const PROSE_WORDS_PER_MINUTE = 230;
+const SECONDS_PER_CODE_LINE = 3;
+const REVIEW_FILES_THRESHOLD = 5;
+const REVIEW_CODE_LINES_THRESHOLD = 200;
+
+/**
+ * Size, reading time and code/prose split of an assistant response.
+ * Lines inside fenced code blocks count as code; everything else is prose.
+ */
+export function analyzeResponse(
+    messageId: string,
+    content: string,
+    filesTouched: number,
+): MessageStats {
+    let inFence = false;
+    let codeLines = 0;
+    let proseLines = 0;
+    let proseWords = 0;
+
+    for (const line of content.split('\n')) {
+        if (/^\s*(```|~~~)/.test(line)) {
+            inFence = !inFence;
+            continue;
+        }
+        if (!line.trim()) continue;
+        if (inFence) {
+            codeLines++;
+        } else {
+            proseLines++;
+            proseWords += line.trim().split(/\s+/).length;
+        }
+    }
+
+    const words = content.trim() ? content.trim().split(/\s+/).length : 0;
+    const readingSeconds = Math.round(
+        (proseWords / PROSE_WORDS_PER_MINUTE) * 60 + codeLines * SECONDS_PER_CODE_LINE,
+    );
+    const totalLines = codeLines + proseLines;
+
+    return {
+        messageId,
+        length: content.length,
+        words,
+        readingSeconds,
+        codeLines,
+        proseLines,
+        codeRatio: totalLines ? Math.round((codeLines / totalLines) * 100) / 100 : 0,
+        filesTouched,
+        reviewRecommended:
+            filesTouched >= REVIEW_FILES_THRESHOLD || codeLines >= REVIEW_CODE_LINES_THRESHOLD,
+    };
+}
+
+function rowToStats(row: any): MessageStats {
+    return {
+        messageId: row.message_id,
+        length: row.length,
+        words: row.words,
+        readingSeconds: row.reading_seconds,
+        codeLines: row.code_lines,
+        proseLines: row.prose_lines,
+        codeRatio: row.code_ratio,
+        filesTouched: row.files_touched,
+        reviewRecommended: !!row.review_recommended,
+    };
+}
+
+export function saveMessageStats(db: Database.Database, stats: MessageStats): void {
+    db.prepare(`
+    INSERT INTO message_stats
+      (message_id, length, words, reading_seconds, code_lines, prose_lines, code_ratio, files_touched, review_recommended)
+    VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+    ON CONFLICT(message_id) DO UPDATE SET
+      length = excluded.length,
+      words = excluded.words,
+      reading_seconds = excluded.reading_seconds,
+      code_lines = excluded.code_lines,
+      prose_lines = excluded.prose_lines,
+      code_ratio = excluded.code_ratio,
+      files_touched = excluded.files_touched,
+      review_recommended = excluded.review_recommended
+  `).run(
+        stats.messageId,
+        stats.length,
+        stats.words,
+        stats.readingSeconds,
+        stats.codeLines,
+        stats.proseLines,
+        stats.codeRatio,
+        stats.filesTouched,
+        stats.reviewRecommended ? 1 : 0,
+    );
+}
+
+/**
+ * Post-turn hook: analyze a freshly persisted assistant message together with the files
+ * the finished turn reported through file_change events.
+ */
+export function recordAssistantMessageStats(state: AppState, message: Message): MessageStats | null {
+    if (message.role !== 'assistant') return null;
+    try {
+        const files = state.turnFileChanges.get(message.conversationId) || [];
+        state.turnFileChanges.delete(message.conversationId);
+        const stats = analyzeResponse(message.id, message.content, files.length);
+        saveMessageStats(state.db, stats);
+        return stats;
+    } catch (error) {
+        console.error('[stats] Failed to record message stats:', error);
+        return null;
+    }
+}
+
+export function getMessageStats(db: Database.Database, messageId: string): MessageStats | null {
+    const row = db.prepare('SELECT * FROM message_stats WHERE message_id = ?').get(messageId);
+    return row ? rowToStats(row) : null;
+}
+
+export function getConversationStats(
+    db: Database.Database,
+    conversationId: string,
+): {
+    messages: MessageStats[];
+    totals: { responses: number; words: number; readingSeconds: number; codeLines: number; filesTouched: number };
+} {
+    const messages = db
+        .prepare(`
+    SELECT s.* FROM message_stats s
+    JOIN messages m ON m.id = s.message_id
+    WHERE m.conversation_id = ?
+    ORDER BY m.rowid ASC
+  `)
+        .all(conversationId)
+        .map(rowToStats);
+
+    const totals = messages.reduce(
+        (acc, s) => ({
+            responses: acc.responses + 1,
+            words: acc.words + s.words,
+            readingSeconds: acc.readingSeconds + s.readingSeconds,
+            codeLines: acc.codeLines + s.codeLines,
+            filesTouched: acc.filesTouched + s.filesTouched,
+        }),
+        { responses: 0, words: 0, readingSeconds: 0, codeLines: 0, filesTouched: 0 },
+    );
+
+    return { messages, totals };
+}
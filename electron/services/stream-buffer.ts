@@ -0,0 +1,77 @@
+import { WebContents } from 'electron';
+import { AppState, BufferedStreamEvent, StreamReplayBuffer } from './models';
+
+/** Events kept per conversation; older ones are dropped first. */
+const MAX_EVENTS_PER_CONVERSATION = 2000;
+/** Conversations with a buffer; the one written to least recently is dropped first. */
+const MAX_CONVERSATIONS = 50;
+
+export function createStreamReplayBuffer(): StreamReplayBuffer {
+    return { lastSeq: 0, conversations: new Map() };
+}
+
+/**
+ * Number a codex-* event and, when it belongs to a conversation, keep it in that
+ * conversation's buffer. Returns the payload to send, carrying `seq`.
+ */
+export function recordStreamEvent(buffer: StreamReplayBuffer, channel: string, payload: any): any {
+    if (!channel.startsWith('codex-') || !payload || typeof payload !== 'object' || Array.isArray(payload)) return payload;
+    const seq = ++buffer.lastSeq;
+    const stamped = { ...payload, seq };
+    if (typeof payload.cid !== 'string') return stamped;
+
+    let conversation = buffer.conversations.get(payload.cid);
+    if (conversation) {
+        buffer.conversations.delete(payload.cid);
+    } else {
+        conversation = { events: [], droppedThrough: 0 };
+    }
+    buffer.conversations.set(payload.cid, conversation);
+    conversation.events.push({ seq, channel, payload: stamped });
+    if (conversation.events.length > MAX_EVENTS_PER_CONVERSATION) {
+        conversation.droppedThrough = conversation.events.shift()!.seq;
+    }
+    while (buffer.conversations.size > MAX_CONVERSATIONS) {
+        buffer.conversations.delete(buffer.conversations.keys().next().value!);
+    }
+    return stamped;
+}
+
+/**
+ * The conversation's buffered events after `sinceSeq`, oldest first. `complete` is false
+ * when some events after `sinceSeq` were already dropped; the UI should then rebuild
+ * from getStreamSnapshot (or the saved messages) instead of replaying.
+ */
+export function getStreamBuffer(
+    state: AppState,
+    conversationId: string,
+    sinceSeq = 0,
+): { conversationId: string; events: BufferedStreamEvent[]; lastSeq: number; complete: boolean } {
+    const conversation = state.streamBuffer.conversations.get(conversationId);
+    const since = Math.max(0, Math.floor(Number(sinceSeq)) || 0);
+    return {
+        conversationId,
+        events: conversation ? conversation.events.filter((event) => event.seq > since) : [],
+        lastSeq: state.streamBuffer.lastSeq,
+        complete: !conversation || conversation.droppedThrough <= since,
+    };
+}
+
+/**
+ * Wrap a WebContents so every codex-* event is numbered and buffered before it is sent.
+ * Wrap outside the subscription filter, so events a window skipped can still be replayed.
+ */
+export function tapStreamBuffer(webContents: WebContents, state: AppState): WebContents {
+    return new Proxy(webContents, {
+        get(target, prop) {
+            if (prop === 'send') {
+                return (channel: string, ...args: any[]) => {
+                    if (args.length > 0) args[0] = recordStreamEvent(state.streamBuffer, channel, args[0]);
+                    target.send(channel, ...args);
+                };
+            }
+            const value = Reflect.get(target, prop);
+            return typeof value === 'function' ? value.bind(target) : value;
+        },
+    });
+}
@@ -0,0 +1,101 @@
+import { WebContents } from 'electron';
+
+export type StreamEventKind = 'delta' | 'thinking' | 'tools' | 'approvals' | 'progress' | 'usage' | 'status';
+
+export const STREAM_EVENT_KINDS: StreamEventKind[] = ['delta', 'thinking', 'tools', 'approvals', 'progress', 'usage', 'status'];
+
+const CHANNEL_KINDS: Record<string, StreamEventKind> = {
+    'codex-stream-delta': 'delta',
+    'codex-stream-token': 'delta',
+    'codex-thinking': 'thinking',
+    'codex-thinking-delta': 'thinking',
+    'codex-tool-call': 'tools',
+    'codex-terminal-output': 'tools',
+    'codex-mcp-call': 'tools',
+    'codex-file-diff': 'tools',
+    'codex-approval-request': 'approvals',
+    'codex-review-request': 'approvals',
+    'codex-review-violation': 'approvals',
+    'codex-progress': 'progress',
+    'codex-path-warning': 'progress',
+    'codex-usage': 'usage',
+    'codex-turn-status': 'status',
+    'codex-stream-end': 'status',
+    'codex-stream-error': 'status',
+    'codex-queue-updated': 'status',
+    'codex-conversation-moved': 'status',
+};
+
+/** Per window (WebContents id): conversation id, or `*` for all, to the kinds it wants. */
+const subscriptions = new Map<number, Map<string, Set<StreamEventKind>>>();
+
+function describe(windowId: number): Array<{ conversationId: string; kinds: StreamEventKind[] }> {
+    return Array.from(subscriptions.get(windowId) || [], ([conversationId, kinds]) => ({
+        conversationId,
+        kinds: Array.from(kinds),
+    }));
+}
+
+/**
+ * Deliver only the given kinds of a conversation's stream events to the calling window
+ * (`*` subscribes to every conversation; no kinds means all kinds). Subscribing again
+ * replaces the kinds. A window without any subscription keeps receiving every event;
+ * after subscribing, use getStreamSnapshot to catch up on what was already streamed.
+ */
+export function subscribeStream(
+    sender: WebContents,
+    conversationId: string,
+    kinds: string[] = [],
+): { success: boolean; subscriptions?: Array<{ conversationId: string; kinds: StreamEventKind[] }>; error?: string } {
+    if (!conversationId) return { success: false, error: 'conversationId is required' };
+    const unknown = kinds.filter((kind) => !STREAM_EVENT_KINDS.includes(kind as StreamEventKind));
+    if (unknown.length > 0) return { success: false, error: `Unknown event kinds: ${unknown.join(', ')}` };
+
+    let windowSubscriptions = subscriptions.get(sender.id);
+    if (!windowSubscriptions) {
+        windowSubscriptions = new Map();
+        subscriptions.set(sender.id, windowSubscriptions);
+        const windowId = sender.id;
+        sender.once('destroyed', () => subscriptions.delete(windowId));
+    }
+    windowSubscriptions.set(conversationId, new Set((kinds.length > 0 ? kinds : STREAM_EVENT_KINDS) as StreamEventKind[]));
+    return { success: true, subscriptions: describe(sender.id) };
+}
+
+/** Drop the window's subscription to one conversation, or with no id, all of them. */
+export function unsubscribeStream(
+    sender: WebContents,
+    conversationId?: string,
+): { success: boolean; subscriptions: Array<{ conversationId: string; kinds: StreamEventKind[] }> } {
+    const windowSubscriptions = subscriptions.get(sender.id);
+    if (windowSubscriptions && conversationId) windowSubscriptions.delete(conversationId);
+    if (windowSubscriptions && (!conversationId || windowSubscriptions.size === 0)) subscriptions.delete(sender.id);
+    return { success: true, subscriptions: describe(sender.id) };
+}
+
+/** Whether an event should reach a window, given its subscriptions. */
+export function wantsStreamEvent(windowId: number, channel: string, payload: any): boolean {
+    const windowSubscriptions = subscriptions.get(windowId);
+    const kind = CHANNEL_KINDS[channel];
+    if (!windowSubscriptions || !kind || typeof payload?.cid !== 'string') return true;
+    const kinds = windowSubscriptions.get(payload.cid) || windowSubscriptions.get('*');
+    return !!kinds && kinds.has(kind);
+}
+
+/**
+ * Wrap a WebContents so conversation stream events it has not subscribed to are dropped
+ * before they cross to the renderer.
+ */
+export function filterSubscribedEvents(webContents: WebContents): WebContents {
+    return new Proxy(webContents, {
+        get(target, prop) {
+            if (prop === 'send') {
+                return (channel: string, ...args: any[]) => {
+                    if (wantsStreamEvent(target.id, channel, args[0])) target.send(channel, ...args);
+                };
+            }
+            const value = Reflect.get(target, prop);
+            return typeof value === 'function' ? value.bind(target) : value;
+        },
+    });
+}
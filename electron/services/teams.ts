@@ -1,11 +1,16 @@
+import { ArtifactTheme } from './themes';
+
 /**
  * Send a message to a Microsoft Teams channel via an Incoming Webhook URL.
  * The message is formatted as an Adaptive Card with a title and markdown body.
+ * Card colors come from the Teams host, so the theme only changes emphasis: the
+ * high-contrast variant drops subtle (low-contrast) text and uses a larger title.
  */
 export async function sendToTeams(
     webhookUrl: string,
     title: string,
     content: string,
+    theme: ArtifactTheme = 'default',
 ): Promise<{ success: boolean; status?: number; error?: string }> {
     if (!webhookUrl.trim()) {
         return { success: false, error: 'Webhook URL is empty' };
@@ -17,6 +22,7 @@ export async function sendToTeams(
             ? `${content.slice(0, 24_000)}...\n\n(truncated — original length: ${content.length} chars)`
             : content;
 
+    const highContrast = theme === 'high-contrast';
     const payload = {
         type: 'message',
         attachments: [
@@ -32,7 +38,7 @@ export async function sendToTeams(
                             type: 'TextBlock',
                             text: title,
                             weight: 'Bolder',
-                            size: 'Medium',
+                            size: highContrast ? 'Large' : 'Medium',
                             wrap: true,
                         },
                         {
@@ -44,7 +50,7 @@ export async function sendToTeams(
                         {
                             type: 'TextBlock',
                             text: '— Sent from Codex WUI',
-                            isSubtle: true,
+                            isSubtle: !highContrast,
                             size: 'Small',
                             horizontalAlignment: 'Right',
                         },
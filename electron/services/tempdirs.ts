@@ -0,0 +1,71 @@
+import * as fs from 'fs';
+import * as path from 'path';
+import { AppState } from './models';
+import { generateId, homeDir } from './utils';
+
+export function tempDirsRoot(): string {
+    const home = homeDir();
+    if (!home) throw new Error('Unable to resolve home directory');
+    return path.join(home, '.codex-wui', 'tmp');
+}
+
+function conversationTempRoot(conversationId: string): string {
+    if (!/^[\w-]+$/.test(conversationId)) throw new Error('Invalid conversation id');
+    return path.join(tempDirsRoot(), conversationId);
+}
+
+/**
+ * Create a scratch directory for a conversation under `~/.codex-wui/tmp/<conversation>`.
+ * It lives until the conversation's turn ends or is cancelled, and at the latest until
+ * the app starts again.
+ */
+export function allocateTempDir(
+    state: AppState,
+    conversationId: string,
+): { success: boolean; path?: string; error?: string } {
+    try {
+        const dir = path.join(conversationTempRoot(conversationId), generateId('tmp'));
+        fs.mkdirSync(dir, { recursive: true });
+        const dirs = state.tempDirs.get(conversationId) || [];
+        dirs.push(dir);
+        state.tempDirs.set(conversationId, dirs);
+        return { success: true, path: dir };
+    } catch (err: any) {
+        return { success: false, error: err.message };
+    }
+}
+
+export function listTempDirs(state: AppState, conversationId: string): string[] {
+    return state.tempDirs.get(conversationId) || [];
+}
+
+/** Remove every scratch directory of a conversation. Returns how many were removed. */
+export function releaseTempDirs(state: AppState, conversationId: string): number {
+    const dirs = state.tempDirs.get(conversationId);
+    if (!dirs) return 0;
+    state.tempDirs.delete(conversationId);
+    for (const dir of dirs) {
+        try {
+            fs.rmSync(dir, { recursive: true, force: true });
+        } catch (err) {
+            console.error(`[tempdirs] Failed to remove ${dir}:`, err);
+        }
+    }
+    try {
+        fs.rmdirSync(conversationTempRoot(conversationId));
+    } catch { }
+    return dirs.length;
+}
+
+/**
+ * Remove all scratch directories: at startup for those left behind by a crash, and on
+ * quit for the ones still in use.
+ */
+export function cleanupTempDirs(state?: AppState): void {
+    state?.tempDirs.clear();
+    try {
+        fs.rmSync(tempDirsRoot(), { recursive: true, force: true });
+    } catch (err) {
+        console.error('[tempdirs] Failed to clean up temp directories:', err);
+    }
+}
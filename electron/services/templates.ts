@@ -0,0 +1,303 @@
+import Database from 'better-sqlite3';
+import {
+    CliOverrides,
+    Conversation,
+    ConversationTemplate,
+    Message,
+    PromptTemplate,
+    TemplateMessage,
+} from './models';
+import { parseJsonColumn } from './db';
+import { readFileContent } from './fs-ops';
+import { generateId, nowIso } from './utils';
+
+const PLACEHOLDER = /\{\{\s*(\w+)\s*\}\}/g;
+/** Placeholders every prompt template can use; the backend fills them in. */
+export const BUILTIN_PLACEHOLDERS = ['file', 'selection', 'workspace'];
+/** A longer `{{file}}` is cut so one template cannot paste a whole bundle into a prompt. */
+const MAX_TEMPLATE_FILE_CHARS = 50_000;
+
+function rowToTemplate(row: any): ConversationTemplate {
+    return {
+        id: row.id,
+        name: row.name,
+        titlePattern: row.title_pattern,
+        systemMessage: row.system_message || undefined,
+        initialMessages: parseJsonColumn<TemplateMessage[]>(row.initial_messages) || [],
+        persona: row.persona || undefined,
+        cliOverrides: parseJsonColumn<CliOverrides>(row.cli_overrides),
+        createdAt: row.created_at,
+        updatedAt: row.updated_at,
+    };
+}
+
+/**
+ * Expand `{{date}}`, `{{time}}`, `{{workspace}}` and `{{template}}` in a title pattern.
+ */
+export function renderTitlePattern(
+    pattern: string,
+    vars: { workspace: string; template: string },
+    now: Date = new Date(),
+): string {
+    const pad = (n: number) => String(n).padStart(2, '0');
+    const values: Record<string, string> = {
+        date: `${now.getFullYear()}-${pad(now.getMonth() + 1)}-${pad(now.getDate())}`,
+        time: `${pad(now.getHours())}:${pad(now.getMinutes())}`,
+        workspace: vars.workspace,
+        template: vars.template,
+    };
+    const title = pattern.replace(PLACEHOLDER, (match, key: string) =>
+        key in values ? values[key] : match,
+    );
+    return title.trim() || vars.template;
+}
+
+export function listConversationTemplates(db: Database.Database): ConversationTemplate[] {
+    try {
+        return db
+            .prepare('SELECT * FROM conversation_templates ORDER BY name COLLATE NOCASE ASC')
+            .all()
+            .map(rowToTemplate);
+    } catch (error) {
+        console.error('[templates] Failed to list templates:', error);
+        return [];
+    }
+}
+
+export function saveConversationTemplate(
+    db: Database.Database,
+    template: Omit<ConversationTemplate, 'id' | 'createdAt' | 'updatedAt'> & { id?: string },
+): ConversationTemplate {
+    const id = template.id || generateId('tpl');
+    const now = nowIso();
+    db.prepare(`
+    INSERT INTO conversation_templates
+      (id, name, title_pattern, system_message, initial_messages, persona, cli_overrides, created_at, updated_at)
+    VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+    ON CONFLICT(id) DO UPDATE SET
+      name = excluded.name,
+      title_pattern = excluded.title_pattern,
+      system_message = excluded.system_message,
+      initial_messages = excluded.initial_messages,
+      persona = excluded.persona,
+      cli_overrides = excluded.cli_overrides,
+      updated_at = excluded.updated_at
+  `).run(
+        id,
+        template.name,
+        template.titlePattern || template.name,
+        template.systemMessage || null,
+        JSON.stringify(template.initialMessages || []),
+        template.persona || null,
+        template.cliOverrides ? JSON.stringify(template.cliOverrides) : null,
+        now,
+        now,
+    );
+
+    const row = db.prepare('SELECT * FROM conversation_templates WHERE id = ?').get(id);
+    return rowToTemplate(row);
+}
+
+export function deleteConversationTemplate(
+    db: Database.Database,
+    id: string,
+): { success: boolean } {
+    db.prepare('DELETE FROM conversation_templates WHERE id = ?').run(id);
+    return { success: true };
+}
+
+/**
+ * Create a conversation pre-seeded with the template's system and initial messages.
+ * The persona and CLI overrides are copied onto the conversation so later edits to
+ * the template do not change threads that were already started from it.
+ */
+export function createConversationFromTemplate(
+    db: Database.Database,
+    templateId: string,
+    conversationId: string,
+    workspaceId: string,
+): { success: boolean; conversation?: Conversation; error?: string } {
+    const row = db.prepare('SELECT * FROM conversation_templates WHERE id = ?').get(templateId);
+    if (!row) return { success: false, error: 'Template not found' };
+    const template = rowToTemplate(row);
+
+    const workspace = db.prepare('SELECT name FROM workspaces WHERE id = ?').get(workspaceId) as any;
+    if (!workspace) return { success: false, error: 'Workspace not found' };
+
+    const title = renderTitlePattern(template.titlePattern, {
+        workspace: workspace.name,
+        template: template.name,
+    });
+    const now = nowIso();
+
+    const seeds: TemplateMessage[] = [];
+    if (template.systemMessage) {
+        seeds.push({ role: 'system', content: template.systemMessage });
+    }
+    seeds.push(...template.initialMessages);
+
+    const messages: Message[] = seeds.map((seed) => ({
+        id: generateId('msg'),
+        conversationId,
+        role: seed.role,
+        content: seed.content,
+        timestamp: new Date().toISOString(),
+    }));
+
+    const insertConv = db.prepare(`
+    INSERT INTO conversations (id, workspace_id, title, created_at, updated_at, persona, cli_overrides)
+    VALUES (?, ?, ?, ?, ?, ?, ?)
+  `);
+    const insertMsg = db.prepare(`
+    INSERT INTO messages (id, conversation_id, role, content, timestamp)
+    VALUES (?, ?, ?, ?, ?)
+  `);
+
+    db.transaction(() => {
+        insertConv.run(
+            conversationId,
+            workspaceId,
+            title,
+            now,
+            now,
+            template.persona || null,
+            template.cliOverrides ? JSON.stringify(template.cliOverrides) : null,
+        );
+        for (const msg of messages) {
+            insertMsg.run(msg.id, msg.conversationId, msg.role, msg.content, msg.timestamp);
+        }
+    })();
+
+    return {
+        success: true,
+        conversation: {
+            id: conversationId,
+            workspaceId,
+            title,
+            createdAt: now,
+            updatedAt: now,
+            persona: template.persona,
+            cliOverrides: template.cliOverrides,
+            messages,
+        },
+    };
+}
+
+// ===== Prompt Templates =====
+
+function rowToPromptTemplate(row: any): PromptTemplate {
+    return {
+        id: row.id,
+        name: row.name,
+        body: row.body,
+        variables: parseJsonColumn<string[]>(row.variables) || [],
+        createdAt: row.created_at,
+        updatedAt: row.updated_at,
+    };
+}
+
+/** The distinct placeholder names in a template body, in order of appearance. */
+export function templatePlaceholders(body: string): string[] {
+    return [...new Set(Array.from(body.matchAll(PLACEHOLDER), (match) => match[1]))];
+}
+
+export function listPromptTemplates(db: Database.Database): PromptTemplate[] {
+    return db
+        .prepare('SELECT * FROM prompt_templates ORDER BY name COLLATE NOCASE ASC')
+        .all()
+        .map(rowToPromptTemplate);
+}
+
+/**
+ * Create or update a prompt template. Variables are the placeholder names the caller
+ * fills in at render time; placeholders in the body that are not built in are added.
+ */
+export function savePromptTemplate(
+    db: Database.Database,
+    template: { id?: string; name: string; body: string; variables?: string[] },
+): { success: boolean; template?: PromptTemplate; error?: string } {
+    const name = template.name?.trim();
+    if (!name) return { success: false, error: 'Template name is required' };
+    if (!template.body?.trim()) return { success: false, error: 'Template body is required' };
+    const declared = (template.variables || []).map((variable) => variable.trim()).filter(Boolean);
+    const invalid = declared.find((variable) => !/^\w+$/.test(variable));
+    if (invalid) return { success: false, error: `Invalid variable name: ${invalid}` };
+    const variables = [...new Set([...declared, ...templatePlaceholders(template.body)])]
+        .filter((variable) => !BUILTIN_PLACEHOLDERS.includes(variable));
+
+    const id = template.id || generateId('ptpl');
+    const now = nowIso();
+    db.prepare(`
+    INSERT INTO prompt_templates (id, name, body, variables, created_at, updated_at)
+    VALUES (?, ?, ?, ?, ?, ?)
+    ON CONFLICT(id) DO UPDATE SET
+      name = excluded.name,
+      body = excluded.body,
+      variables = excluded.variables,
+      updated_at = excluded.updated_at
+  `).run(id, name, template.body, JSON.stringify(variables), now, now);
+
+    const row = db.prepare('SELECT * FROM prompt_templates WHERE id = ?').get(id);
+    return { success: true, template: rowToPromptTemplate(row) };
+}
+
+export function deletePromptTemplate(db: Database.Database, id: string): { success: boolean } {
+    db.prepare('DELETE FROM prompt_templates WHERE id = ?').run(id);
+    return { success: true };
+}
+
+/**
+ * Fill in a prompt template. `{{workspace}}` is the workspace's name, `{{file}}` the
+ * contents of the file at `values.file` (relative to the workspace, protected files
+ * refused) and `{{selection}}` is `values.selection`; other placeholders come from
+ * `values`. Fails listing `missing` names when a used placeholder has no value.
+ */
+export function renderPromptTemplate(
+    db: Database.Database,
+    id: string,
+    values: Record<string, string> = {},
+    workspaceId?: string,
+): { success: boolean; text?: string; missing?: string[]; error?: string } {
+    const row = db.prepare('SELECT * FROM prompt_templates WHERE id = ?').get(id);
+    if (!row) return { success: false, error: 'Template not found' };
+    const template = rowToPromptTemplate(row);
+    const used = templatePlaceholders(template.body);
+    const needsWorkspace = used.includes('workspace') || used.includes('file');
+    const workspace = workspaceId
+        ? db.prepare('SELECT name, path FROM workspaces WHERE id = ?').get(workspaceId) as any
+        : null;
+    if (needsWorkspace && !workspace) {
+        return { success: false, error: workspaceId ? 'Workspace not found' : 'This template needs a workspace' };
+    }
+
+    const resolved: Record<string, string> = {};
+    const missing: string[] = [];
+    for (const name of used) {
+        if (name === 'workspace') {
+            resolved.workspace = workspace.name;
+        } else if (name === 'file') {
+            const file = values.file?.trim();
+            if (!file) {
+                missing.push('file');
+                continue;
+            }
+            const read = readFileContent(file, workspace.path);
+            if (!read.success) return { success: false, error: `Cannot read ${file}: ${read.error}` };
+            if (read.binary) return { success: false, error: `${file} is a binary file` };
+            const content = read.content!.length > MAX_TEMPLATE_FILE_CHARS
+                ? `${read.content!.slice(0, MAX_TEMPLATE_FILE_CHARS)}\n[... truncated]`
+                : read.content!;
+            resolved.file = `${file}:\n\`\`\`\n${content.replace(/\n$/, '')}\n\`\`\``;
+        } else if (typeof values[name] === 'string') {
+            resolved[name] = values[name];
+        } else {
+            missing.push(name);
+        }
+    }
+    if (missing.length > 0) {
+        return { success: false, missing, error: `Missing values for ${missing.join(', ')}` };
+    }
+
+    const text = template.body.replace(PLACEHOLDER, (match, key: string) => (key in resolved ? resolved[key] : match));
+    return { success: true, text };
+}
@@ -0,0 +1,73 @@
+import Database from 'better-sqlite3';
+import { dbGetSetting, dbSetSetting } from './db';
+
+export type ArtifactTheme = 'default' | 'high-contrast' | 'colorblind-safe';
+
+export interface ThemePalette {
+    background: string;
+    text: string;
+    muted: string;
+    border: string;
+    accent: string;
+    user: string;
+    assistant: string;
+    success: string;
+    danger: string;
+}
+
+const THEME_KEY = 'artifacts.theme';
+
+/**
+ * Palettes for exported artifacts. The color-blind-safe one uses the Okabe-Ito colors
+ * (blue/orange instead of red/green); high-contrast keeps every pair above WCAG AAA.
+ */
+export const THEME_PALETTES: Record<ArtifactTheme, ThemePalette> = {
+    default: {
+        background: '#ffffff',
+        text: '#1f2328',
+        muted: '#656d76',
+        border: '#d0d7de',
+        accent: '#0969da',
+        user: '#ddf4ff',
+        assistant: '#f6f8fa',
+        success: '#1a7f37',
+        danger: '#cf222e',
+    },
+    'high-contrast': {
+        background: '#000000',
+        text: '#ffffff',
+        muted: '#e0e0e0',
+        border: '#ffffff',
+        accent: '#ffd700',
+        user: '#00224d',
+        assistant: '#1a1a1a',
+        success: '#7fff7f',
+        danger: '#ff9e9e',
+    },
+    'colorblind-safe': {
+        background: '#ffffff',
+        text: '#1f2328',
+        muted: '#5c5c5c',
+        border: '#c8c8c8',
+        accent: '#0072b2',
+        user: '#e3f1fa',
+        assistant: '#f5f5f5',
+        success: '#0072b2',
+        danger: '#d55e00',
+    },
+};
+
+export function isArtifactTheme(value: unknown): value is ArtifactTheme {
+    return typeof value === 'string' && value in THEME_PALETTES;
+}
+
+export function getArtifactTheme(db: Database.Database): ArtifactTheme {
+    const theme = dbGetSetting<string>(db, THEME_KEY, 'default');
+    return isArtifactTheme(theme) ? theme : 'default';
+}
+
+export function setArtifactTheme(db: Database.Database, theme: ArtifactTheme): ArtifactTheme {
+    if (!isArtifactTheme(theme)) throw new Error(`Unknown theme: ${theme}`);
+    dbSetSetting(db, THEME_KEY, theme);
+    return theme;
+}
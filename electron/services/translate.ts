@@ -0,0 +1,158 @@
+import Database from 'better-sqlite3';
+import { WebContents } from 'electron';
+import { Message, TranslationSettings } from './models';
+import { dbGetMessageContent, dbGetSetting, dbSetSetting, dbUpdateMessageContent } from './db';
+import { nowIso } from './utils';
+
+const SETTINGS_KEY = 'translation';
+
+const DEFAULT_SETTINGS: TranslationSettings = {
+    provider: 'ollama',
+    endpoint: '',
+    model: '',
+    apiKey: '',
+};
+
+const DEFAULT_ENDPOINTS: Record<TranslationSettings['provider'], string> = {
+    ollama: 'http://localhost:11434',
+    openai: 'https://api.openai.com/v1',
+};
+
+const DEFAULT_MODELS: Record<TranslationSettings['provider'], string> = {
+    ollama: 'llama3.1',
+    openai: 'gpt-4.1-mini',
+};
+
+export function getTranslationSettings(db: Database.Database): TranslationSettings {
+    return { ...DEFAULT_SETTINGS, ...dbGetSetting<Partial<TranslationSettings>>(db, SETTINGS_KEY, {}) };
+}
+
+export function setTranslationSettings(
+    db: Database.Database,
+    settings: Partial<TranslationSettings>,
+): TranslationSettings {
+    const merged = { ...getTranslationSettings(db), ...settings };
+    dbSetSetting(db, SETTINGS_KEY, merged);
+    return merged;
+}
+
+function translationInstruction(targetLang: string): string {
+    return (
+        `Translate the following text into ${targetLang}. ` +
+        'Keep Markdown formatting, code blocks, file paths and identifiers unchanged. ' +
+        'Reply with the translation only.'
+    );
+}
+
+/**
+ * Translate text with the configured provider: a local Ollama model or an
+ * OpenAI-compatible chat completions API.
+ */
+export async function translateText(
+    db: Database.Database,
+    text: string,
+    targetLang: string,
+): Promise<{ success: boolean; text?: string; error?: string }> {
+    if (!text.trim()) return { success: true, text };
+    if (!targetLang.trim()) return { success: false, error: 'targetLang is required' };
+
+    const settings = getTranslationSettings(db);
+    const endpoint = (settings.endpoint || DEFAULT_ENDPOINTS[settings.provider]).replace(/\/+$/, '');
+    const model = settings.model || DEFAULT_MODELS[settings.provider];
+
+    try {
+        if (settings.provider === 'openai') {
+            if (!settings.apiKey) return { success: false, error: 'Translation API key is not set' };
+            const response = await fetch(`${endpoint}/chat/completions`, {
+                method: 'POST',
+                headers: {
+                    'Content-Type': 'application/json',
+                    Authorization: `Bearer ${settings.apiKey}`,
+                },
+                body: JSON.stringify({
+                    model,
+                    messages: [
+                        { role: 'system', content: translationInstruction(targetLang) },
+                        { role: 'user', content: text },
+                    ],
+                }),
+            });
+            if (!response.ok) {
+                return { success: false, error: `HTTP ${response.status}: ${await response.text()}` };
+            }
+            const data: any = await response.json();
+            return { success: true, text: data.choices?.[0]?.message?.content ?? '' };
+        }
+
+        const response = await fetch(`${endpoint}/api/generate`, {
+            method: 'POST',
+            headers: { 'Content-Type': 'application/json' },
+            body: JSON.stringify({
+                model,
+                prompt: `${translationInstruction(targetLang)}\n\n${text}`,
+                stream: false,
+            }),
+        });
+        if (!response.ok) {
+            return { success: false, error: `HTTP ${response.status}: ${await response.text()}` };
+        }
+        const data: any = await response.json();
+        return { success: true, text: data.response ?? '' };
+    } catch (err: any) {
+        return { success: false, error: `Request failed: ${err.message}` };
+    }
+}
+
+/**
+ * Set (or clear with null) the language assistant responses are translated into.
+ */
+export function setConversationAutoTranslate(
+    db: Database.Database,
+    conversationId: string,
+    targetLang: string | null,
+): { success: boolean } {
+    db.prepare('UPDATE conversations SET auto_translate_lang = ?, updated_at = ? WHERE id = ?').run(
+        targetLang?.trim() || null,
+        nowIso(),
+        conversationId,
+    );
+    return { success: true };
+}
+
+export function getConversationAutoTranslate(db: Database.Database, conversationId: string): string | null {
+    const row = db.prepare('SELECT auto_translate_lang FROM conversations WHERE id = ?').get(conversationId) as any;
+    return row?.auto_translate_lang || null;
+}
+
+/** The message with its translation appended below a rule, so the original stays readable. */
+export function appendTranslation(content: string, targetLang: string, translation: string): string {
+    return `${content}\n\n---\n\n*${targetLang}*\n\n${translation.trim()}`;
+}
+
+/**
+ * Translate a finalized assistant message when its conversation has auto-translate on,
+ * store the translation with it and tell the renderer with `message-translated`.
+ */
+export async function autoTranslateMessage(
+    db: Database.Database,
+    message: Message,
+    webContents: WebContents | null,
+): Promise<void> {
+    if (message.role !== 'assistant' || message.inProgress) return;
+    const targetLang = getConversationAutoTranslate(db, message.conversationId);
+    if (!targetLang) return;
+    const content = dbGetMessageContent(db, message.id) ?? message.content;
+    const result = await translateText(db, content, targetLang);
+    if (!result.success || !result.text?.trim()) {
+        if (result.error) console.error(`[translate] Auto-translate of ${message.id} failed: ${result.error}`);
+        return;
+    }
+    const translated = appendTranslation(content, targetLang, result.text);
+    if (!dbUpdateMessageContent(db, message.id, translated).success) return;
+    webContents?.send('message-translated', {
+        cid: message.conversationId,
+        messageId: message.id,
+        targetLang,
+        content: translated,
+    });
+}
@@ -0,0 +1,291 @@
+import Database from 'better-sqlite3';
+import { Message, TokenUsage } from './models';
+import { costOf, getUsagePricing } from './usage';
+import { nowIso } from './utils';
+
+export type TurnStatus = 'queued' | 'running' | 'completed' | 'failed' | 'cancelled';
+
+/** How the user judged a finished turn's work. */
+export type TurnOutcome = 'accepted' | 'rework' | 'rejected';
+
+export const TURN_OUTCOMES: TurnOutcome[] = ['accepted', 'rework', 'rejected'];
+
+export interface TurnToolCall {
+    itemId: string;
+    /** Codex item type: command_execution, file_change, mcp_tool_call or web_search. */
+    kind: string;
+    name: string;
+    status: string;
+    startedAt: string;
+    finishedAt: string | null;
+}
+
+export interface Turn {
+    id: string;
+    conversationId: string;
+    userMessageId: string | null;
+    assistantMessageId: string | null;
+    status: TurnStatus;
+    prompt: string;
+    model: string | null;
+    exitCode: number | null;
+    error: string | null;
+    usage: TokenUsage | null;
+    /** From the current pricing table; null when usage or the model's price is unknown. */
+    costUsd: number | null;
+    createdAt: string;
+    startedAt: string | null;
+    finishedAt: string | null;
+    /** Launch preset applied to the conversation when the turn was started. */
+    presetId: string | null;
+    outcome: TurnOutcome | null;
+    outcomeComment: string | null;
+    ratedAt: string | null;
+    toolCalls: TurnToolCall[];
+}
+
+const TOOL_ITEM_TYPES = new Set(['command_execution', 'file_change', 'mcp_tool_call', 'web_search']);
+
+/**
+ * Record a turn when its prompt is accepted (`running`) or queued behind another turn.
+ * The user message is linked here if the renderer already saved it.
+ */
+export function createTurn(
+    db: Database.Database,
+    turnId: string,
+    conversationId: string,
+    prompt: string,
+    status: 'queued' | 'running',
+): void {
+    const now = nowIso();
+    db.prepare(`
+    INSERT OR IGNORE INTO turns (id, conversation_id, status, prompt, created_at, started_at, preset_id)
+    VALUES (?, ?, ?, ?, ?, ?, (SELECT preset_id FROM conversations WHERE id = ?))
+  `).run(turnId, conversationId, status, prompt, now, status === 'running' ? now : null, conversationId);
+
+    db.prepare(`
+    UPDATE turns SET user_message_id = (
+      SELECT m.id FROM messages m
+      WHERE m.conversation_id = ? AND m.role = 'user' AND m.content = ?
+        AND m.id NOT IN (SELECT user_message_id FROM turns WHERE user_message_id IS NOT NULL)
+      ORDER BY m.rowid DESC LIMIT 1
+    )
+    WHERE id = ? AND user_message_id IS NULL
+  `).run(conversationId, prompt, turnId);
+}
+
+export function markTurnRunning(db: Database.Database, turnId: string, model: string): void {
+    db.prepare(`
+    UPDATE turns SET status = 'running', model = ?, started_at = COALESCE(started_at, ?)
+    WHERE id = ? AND status IN ('queued', 'running')
+  `).run(model || null, nowIso(), turnId);
+}
+
+/** Cancel a queued or running turn. Finished turns keep their status. */
+export function markTurnCancelled(db: Database.Database, turnId: string): void {
+    db.prepare(`
+    UPDATE turns SET status = 'cancelled', finished_at = ?
+    WHERE id = ? AND status IN ('queued', 'running')
+  `).run(nowIso(), turnId);
+}
+
+/** Turns still queued or running from a previous session ended when the app quit. */
+export function closeStaleTurns(db: Database.Database): number {
+    return db.prepare(`
+    UPDATE turns SET status = 'cancelled', finished_at = COALESCE(finished_at, ?)
+    WHERE status IN ('queued', 'running')
+  `).run(nowIso()).changes;
+}
+
+/** Close a running turn with its exit status and token usage. A cancelled turn stays cancelled. */
+export function finishTurn(
+    db: Database.Database,
+    turnId: string,
+    result: { status: 'completed' | 'failed'; exitCode?: number | null; error?: string; usage?: TokenUsage },
+): void {
+    db.prepare(`
+    UPDATE turns SET status = ?, exit_code = ?, error = ?, prompt_tokens = ?, cached_tokens = ?,
+      completion_tokens = ?, total_tokens = ?, finished_at = ?
+    WHERE id = ? AND status = 'running'
+  `).run(
+        result.status,
+        result.exitCode ?? null,
+        result.error || null,
+        result.usage?.promptTokens ?? null,
+        result.usage?.cachedTokens ?? null,
+        result.usage?.completionTokens ?? null,
+        result.usage?.totalTokens ?? null,
+        nowIso(),
+        turnId,
+    );
+}
+
+/**
+ * Track a tool-like codex item (command, patch, MCP call, web search) on the turn. Called
+ * for every item event; later events update the status.
+ */
+export function recordTurnToolCall(db: Database.Database, turnId: string, item: any, completed: boolean): void {
+    const kind = String(item?.type || '').toLowerCase();
+    if (!item?.id || !TOOL_ITEM_TYPES.has(kind)) return;
+
+    let name = kind;
+    if (kind === 'command_execution') {
+        name = String(item.command || kind);
+    } else if (kind === 'file_change') {
+        const changes = item.changes || [];
+        const paths = Array.isArray(changes) ? changes.map((c: any) => c?.path) : Object.keys(changes);
+        name = paths.filter(Boolean).join(', ') || kind;
+    } else if (kind === 'mcp_tool_call') {
+        name = `${item.server || '?'}.${item.tool || '?'}`;
+    } else if (kind === 'web_search') {
+        name = String(item.query || kind);
+    }
+    const status = String(item.status || (completed ? 'completed' : 'in_progress'));
+
+    const now = nowIso();
+    try {
+        db.prepare(`
+      INSERT INTO turn_tool_calls (turn_id, item_id, kind, name, status, started_at, finished_at)
+      VALUES (?, ?, ?, ?, ?, ?, ?)
+      ON CONFLICT(turn_id, item_id) DO UPDATE SET
+        name = excluded.name, status = excluded.status,
+        finished_at = COALESCE(excluded.finished_at, turn_tool_calls.finished_at)
+    `).run(turnId, String(item.id), kind, name.slice(0, 500), status, now, completed ? now : null);
+    } catch (error) {
+        console.error('[turns] Failed to record tool call:', error);
+    }
+}
+
+/**
+ * Post-persist hook: attach a saved user message to its turn (when the turn was created
+ * first) and an assistant message to the conversation's latest finished turn.
+ */
+export function linkMessageToTurn(db: Database.Database, message: Message): void {
+    try {
+        if (message.role === 'user') {
+            db.prepare(`
+        UPDATE turns SET user_message_id = ?
+        WHERE id = (
+          SELECT id FROM turns
+          WHERE conversation_id = ? AND user_message_id IS NULL AND prompt = ?
+          ORDER BY rowid DESC LIMIT 1
+        )
+      `).run(message.id, message.conversationId, message.content);
+        } else if (message.role === 'assistant') {
+            db.prepare(`
+        UPDATE turns SET assistant_message_id = ?
+        WHERE id = (
+          SELECT id FROM turns
+          WHERE conversation_id = ? AND status IN ('completed', 'failed', 'cancelled')
+          ORDER BY rowid DESC LIMIT 1
+        ) AND assistant_message_id IS NULL
+      `).run(message.id, message.conversationId);
+        }
+    } catch (error) {
+        console.error('[turns] Failed to link message:', error);
+    }
+}
+
+function rowToTurn(row: any, toolCalls: TurnToolCall[], pricing: ReturnType<typeof getUsagePricing>): Turn {
+    const usage: TokenUsage | null = row.total_tokens === null
+        ? null
+        : {
+            promptTokens: row.prompt_tokens,
+            cachedTokens: row.cached_tokens,
+            completionTokens: row.completion_tokens,
+            totalTokens: row.total_tokens,
+        };
+    return {
+        id: row.id,
+        conversationId: row.conversation_id,
+        userMessageId: row.user_message_id,
+        assistantMessageId: row.assistant_message_id,
+        status: row.status,
+        prompt: row.prompt,
+        model: row.model,
+        exitCode: row.exit_code,
+        error: row.error,
+        usage,
+        costUsd: usage && row.model ? costOf(usage, pricing[row.model]) : null,
+        createdAt: row.created_at,
+        startedAt: row.started_at,
+        finishedAt: row.finished_at,
+        presetId: row.preset_id ?? null,
+        outcome: row.outcome ?? null,
+        outcomeComment: row.outcome_comment ?? null,
+        ratedAt: row.rated_at ?? null,
+        toolCalls,
+    };
+}
+
+function loadToolCalls(db: Database.Database, turnIds: string[]): Map<string, TurnToolCall[]> {
+    const byTurn = new Map<string, TurnToolCall[]>();
+    if (turnIds.length === 0) return byTurn;
+    const rows = db.prepare(`
+    SELECT * FROM turn_tool_calls WHERE turn_id IN (${turnIds.map(() => '?').join(', ')})
+    ORDER BY CAST(started_at AS INTEGER) ASC, rowid ASC
+  `).all(...turnIds) as any[];
+    for (const row of rows) {
+        if (!byTurn.has(row.turn_id)) byTurn.set(row.turn_id, []);
+        byTurn.get(row.turn_id)!.push({
+            itemId: row.item_id,
+            kind: row.kind,
+            name: row.name,
+            status: row.status,
+            startedAt: row.started_at,
+            finishedAt: row.finished_at,
+        });
+    }
+    return byTurn;
+}
+
+/** Turns of a conversation, oldest first, optionally filtered by status. */
+export function getTurns(db: Database.Database, conversationId: string, status?: TurnStatus): Turn[] {
+    const rows = db.prepare(`
+    SELECT * FROM turns WHERE conversation_id = ? ${status ? 'AND status = ?' : ''}
+    ORDER BY CAST(created_at AS INTEGER) ASC, rowid ASC
+  `).all(...(status ? [conversationId, status] : [conversationId])) as any[];
+    const toolCalls = loadToolCalls(db, rows.map((r) => r.id));
+    const pricing = getUsagePricing(db);
+    return rows.map((row) => rowToTurn(row, toolCalls.get(row.id) || [], pricing));
+}
+
+export function getTurn(db: Database.Database, turnId: string): Turn | null {
+    const row = db.prepare('SELECT * FROM turns WHERE id = ?').get(turnId) as any;
+    if (!row) return null;
+    return rowToTurn(row, loadToolCalls(db, [turnId]).get(turnId) || [], getUsagePricing(db));
+}
+
+/** The turn a message belongs to, as its prompt or its reply. */
+export function getTurnForMessage(db: Database.Database, messageId: string): Turn | null {
+    const row = db
+        .prepare('SELECT id FROM turns WHERE user_message_id = ? OR assistant_message_id = ?')
+        .get(messageId, messageId) as any;
+    return row ? getTurn(db, row.id) : null;
+}
+
+/**
+ * Label a finished turn as accepted, needing rework or rejected, with an optional
+ * comment. A null outcome clears the rating. Queued and running turns cannot be rated.
+ */
+export function rateTurn(
+    db: Database.Database,
+    turnId: string,
+    outcome: TurnOutcome | null,
+    comment?: string,
+): { success: boolean; turn?: Turn; error?: string } {
+    if (outcome !== null && !TURN_OUTCOMES.includes(outcome)) {
+        return { success: false, error: `Unknown outcome: ${outcome}` };
+    }
+    const row = db.prepare('SELECT status FROM turns WHERE id = ?').get(turnId) as any;
+    if (!row) return { success: false, error: 'Turn not found' };
+    if (row.status === 'queued' || row.status === 'running') return { success: false, error: 'The turn has not finished' };
+
+    db.prepare('UPDATE turns SET outcome = ?, outcome_comment = ?, rated_at = ? WHERE id = ?').run(
+        outcome,
+        outcome ? comment?.trim() || null : null,
+        outcome ? nowIso() : null,
+        turnId,
+    );
+    return { success: true, turn: getTurn(db, turnId)! };
+}
@@ -0,0 +1,210 @@
+export type UrlContentMode = 'markdown' | 'text' | 'raw';
+
+/** Pages are read up to this size; the rest is not downloaded. */
+const MAX_DOWNLOAD_BYTES = 2 * 1024 * 1024;
+/** Extracted content longer than this is cut, so one page cannot crowd out the prompt. */
+const MAX_CONTENT_CHARS = 50_000;
+const REQUEST_TIMEOUT_MS = 15_000;
+const CACHE_TTL_MS = 10 * 60 * 1000;
+const CACHE_MAX_ENTRIES = 32;
+
+export interface UrlContent {
+    /** The URL after redirects. */
+    url: string;
+    title: string;
+    content: string;
+    mode: UrlContentMode;
+    contentType: string;
+    truncated: boolean;
+    cached: boolean;
+}
+
+/** Page chrome and non-content elements, removed with everything inside them. */
+const BOILERPLATE_TAGS = ['script', 'style', 'noscript', 'template', 'svg', 'canvas', 'iframe', 'head', 'nav', 'header', 'footer', 'aside', 'form', 'button', 'select', 'dialog'];
+
+const NAMED_ENTITIES: Record<string, string> = { amp: '&', lt: '<', gt: '>', quot: '"', apos: "'", nbsp: ' ', ndash: '–', mdash: '—', hellip: '…', copy: '©' };
+
+export function decodeEntities(text: string): string {
+    return text.replace(/&(#x[0-9a-f]+|#\d+|[a-z]+);/gi, (entity, name: string) => {
+        if (name[0] === '#') {
+            const code = name[1] === 'x' || name[1] === 'X' ? parseInt(name.slice(2), 16) : parseInt(name.slice(1), 10);
+            return Number.isFinite(code) && code > 0 && code <= 0x10ffff ? String.fromCodePoint(code) : entity;
+        }
+        return NAMED_ENTITIES[name.toLowerCase()] ?? entity;
+    });
+}
+
+function stripTags(html: string): string {
+    return html.replace(/<[^>]*>/g, '');
+}
+
+function pageTitle(html: string): string {
+    const match = html.match(/<title[^>]*>([\s\S]*?)<\/title>/i) || html.match(/<h1[^>]*>([\s\S]*?)<\/h1>/i);
+    return match ? decodeEntities(stripTags(match[1])).replace(/\s+/g, ' ').trim() : '';
+}
+
+/**
+ * The part of a page that holds its content: the `<article>` (or `<main>`) with the most
+ * text, else the body, with navigation, headers, footers, sidebars and scripts removed.
+ */
+export function extractMainContent(html: string): string {
+    let cleaned = html.replace(/<!--[\s\S]*?-->/g, '');
+    for (const tag of BOILERPLATE_TAGS) {
+        cleaned = cleaned.replace(new RegExp(`<${tag}\\b[^>]*>[\\s\\S]*?<\\/${tag}>`, 'gi'), '');
+    }
+    const textLength = (fragment: string) => stripTags(fragment).replace(/\s+/g, '').length;
+    for (const tag of ['article', 'main']) {
+        const candidates = Array.from(cleaned.matchAll(new RegExp(`<${tag}\\b[^>]*>([\\s\\S]*?)<\\/${tag}>`, 'gi')), (m) => m[1]);
+        const best = candidates.sort((a, b) => textLength(b) - textLength(a))[0];
+        if (best && textLength(best) > 200) return best;
+    }
+    const body = cleaned.match(/<body\b[^>]*>([\s\S]*)<\/body>/i);
+    return body ? body[1] : cleaned;
+}
+
+function absoluteUrl(href: string, baseUrl: string): string | null {
+    try {
+        const resolved = new URL(decodeEntities(href), baseUrl);
+        return resolved.protocol === 'http:' || resolved.protocol === 'https:' ? resolved.href : null;
+    } catch {
+        return null;
+    }
+}
+
+/**
+ * Convert extracted HTML to Markdown, or to plain text when `markdown` is false: headings,
+ * paragraphs, lists, links, emphasis, code and quotes are kept; everything else is text.
+ */
+export function htmlToMarkdown(html: string, baseUrl: string, markdown = true): string {
+    const blocks: string[] = [];
+    const keep = (text: string) => `\u0000${blocks.push(text) - 1}\u0000`;
+    const md = (marked: string, plain: string) => (markdown ? marked : plain);
+
+    let out = html
+        .replace(/<pre\b[^>]*>([\s\S]*?)<\/pre>/gi, (_m, code: string) =>
+            `\n\n${keep(md('```\n' + decodeEntities(stripTags(code)).replace(/\n+$/, '') + '\n```', decodeEntities(stripTags(code))))}\n\n`)
+        .replace(/<h([1-6])\b[^>]*>([\s\S]*?)<\/h\1>/gi, (_m, level: string, text: string) =>
+            `\n\n${md('#'.repeat(Number(level)) + ' ', '')}${stripTags(text).replace(/\s+/g, ' ').trim()}\n\n`)
+        .replace(/<a\b[^>]*?href\s*=\s*["']([^"']*)["'][^>]*>([\s\S]*?)<\/a>/gi, (_m, href: string, text: string) => {
+            const label = stripTags(text).replace(/\s+/g, ' ').trim();
+            const target = absoluteUrl(href, baseUrl);
+            return label && target && markdown ? `[${label}](${target})` : label;
+        })
+        .replace(/<(strong|b)\b[^>]*>([\s\S]*?)<\/\1>/gi, (_m, _tag, text: string) => (text.trim() ? md(`**${text.trim()}**`, text) : text))
+        .replace(/<(em|i)\b[^>]*>([\s\S]*?)<\/\1>/gi, (_m, _tag, text: string) => (text.trim() ? md(`_${text.trim()}_`, text) : text))
+        .replace(/<code\b[^>]*>([\s\S]*?)<\/code>/gi, (_m, code: string) => keep(md('`' + decodeEntities(stripTags(code)) + '`', decodeEntities(stripTags(code)))))
+        .replace(/<img\b[^>]*>/gi, '')
+        .replace(/<li\b[^>]*>/gi, `\n${md('- ', '• ')}`)
+        .replace(/<br\s*\/?>/gi, '\n')
+        .replace(/<hr\b[^>]*>/gi, `\n\n${md('---', '')}\n\n`)
+        .replace(/<\/?(p|div|section|ul|ol|table|tr|dl|dt|dd|figure|figcaption)\b[^>]*>/gi, '\n\n')
+        .replace(/<\/t[dh]>/gi, ' ')
+        .replace(/<blockquote\b[^>]*>([\s\S]*?)<\/blockquote>/gi, (_m, quote: string) => {
+            const lines = stripTags(quote).split('\n').map((line) => line.trim()).filter(Boolean);
+            return `\n\n${lines.map((line) => md('> ', '') + line).join('\n')}\n\n`;
+        });
+
+    out = decodeEntities(stripTags(out))
+        .split('\n')
+        .map((line) => line.replace(/[ \t ]+/g, ' ').trim())
+        .join('\n')
+        .replace(/\n{3,}/g, '\n\n')
+        .trim();
+    return out.replace(/\u0000(\d+)\u0000/g, (_m, index: string) => blocks[Number(index)]);
+}
+
+/** Cut `content` at the cap, at the last paragraph break before it when there is one. */
+export function capContent(content: string, maxChars: number = MAX_CONTENT_CHARS): { content: string; truncated: boolean } {
+    if (content.length <= maxChars) return { content, truncated: false };
+    const cut = content.slice(0, maxChars);
+    const paragraph = cut.lastIndexOf('\n\n');
+    return { content: (paragraph > maxChars / 2 ? cut.slice(0, paragraph) : cut).trimEnd(), truncated: true };
+}
+
+async function readCapped(response: Response): Promise<{ body: string; truncated: boolean }> {
+    const charset = /charset=([^;]+)/i.exec(response.headers.get('content-type') || '')?.[1]?.trim() || 'utf-8';
+    let decoder: TextDecoder;
+    try {
+        decoder = new TextDecoder(charset);
+    } catch {
+        decoder = new TextDecoder('utf-8');
+    }
+    const chunks: Uint8Array[] = [];
+    let size = 0;
+    let truncated = false;
+    const reader = response.body?.getReader();
+    while (reader) {
+        const { done, value } = await reader.read();
+        if (done) break;
+        chunks.push(value);
+        size += value.length;
+        if (size >= MAX_DOWNLOAD_BYTES) {
+            truncated = true;
+            await reader.cancel();
+            break;
+        }
+    }
+    return { body: decoder.decode(Buffer.concat(chunks).subarray(0, MAX_DOWNLOAD_BYTES)), truncated };
+}
+
+const cache = new Map<string, { content: UrlContent; fetchedAt: number }>();
+
+/**
+ * Download `url` and return its content for a prompt: the main text of HTML pages as
+ * Markdown (`markdown`) or plain text (`text`), or the body as served (`raw`). Results
+ * are cut at a size cap and cached for a few minutes.
+ */
+export async function fetchUrlContent(
+    url: string,
+    mode: UrlContentMode = 'markdown',
+): Promise<{ success: boolean; content?: UrlContent; error?: string }> {
+    let parsed: URL;
+    try {
+        parsed = new URL(url);
+    } catch {
+        return { success: false, error: 'Invalid URL' };
+    }
+    if (parsed.protocol !== 'http:' && parsed.protocol !== 'https:') return { success: false, error: 'Only http and https URLs can be fetched' };
+    if (!['markdown', 'text', 'raw'].includes(mode)) return { success: false, error: `Unknown mode: ${mode}` };
+
+    const key = `${mode}:${parsed.href}`;
+    const hit = cache.get(key);
+    if (hit && Date.now() - hit.fetchedAt < CACHE_TTL_MS) {
+        cache.delete(key);
+        cache.set(key, hit);
+        return { success: true, content: { ...hit.content, cached: true } };
+    }
+
+    try {
+        const response = await fetch(parsed.href, {
+            headers: { Accept: 'text/html,application/xhtml+xml,text/plain;q=0.9,*/*;q=0.5' },
+            redirect: 'follow',
+            signal: AbortSignal.timeout(REQUEST_TIMEOUT_MS),
+        });
+        if (!response.ok) return { success: false, error: `HTTP ${response.status}` };
+        const contentType = (response.headers.get('content-type') || '').split(';')[0].trim().toLowerCase();
+        const isHtml = contentType === 'text/html' || contentType === 'application/xhtml+xml';
+        const isText = contentType.startsWith('text/') || /json|xml|javascript|yaml/.test(contentType);
+        if (!isHtml && !isText) return { success: false, error: `Unsupported content type: ${contentType || 'unknown'}` };
+
+        const { body, truncated: cutDownload } = await readCapped(response);
+        const finalUrl = response.url || parsed.href;
+        const text = !isHtml || mode === 'raw' ? body : htmlToMarkdown(extractMainContent(body), finalUrl, mode === 'markdown');
+        const capped = capContent(text);
+        const content: UrlContent = {
+            url: finalUrl,
+            title: isHtml ? pageTitle(body) : '',
+            content: capped.content,
+            mode,
+            contentType,
+            truncated: cutDownload || capped.truncated,
+            cached: false,
+        };
+
+        cache.set(key, { content, fetchedAt: Date.now() });
+        while (cache.size > CACHE_MAX_ENTRIES) cache.delete(cache.keys().next().value!);
+        return { success: true, content };
+    } catch (err: any) {
+        return { success: false, error: err.name === 'TimeoutError' ? 'Timed out fetching the page' : err.message };
+    }
+}
@@ -0,0 +1,363 @@
+import Database from 'better-sqlite3';
+import * as fs from 'fs';
+import { AppState, Message, PromptAttachment, RuntimeConfig, TokenUsage } from './models';
+import { dbGetConversationOverrides, dbGetMessages, dbGetSetting, dbSetSetting } from './db';
+import { getHistorySettings, getHistorySummary, trimHistory } from './history';
+import { applyNotes, applyPersona, estimateTokens, expandTildePath, nowIso, resolveRuntimeConfig } from './utils';
+
+export { estimateTokens };
+
+/** USD per million tokens. Cached prompt tokens fall back to the prompt price. */
+export interface ModelPricing {
+    prompt: number;
+    cachedPrompt?: number;
+    completion: number;
+}
+
+export type UsageRange = 'today' | 'week' | 'month' | 'all';
+
+export interface UsageBucket extends TokenUsage {
+    turns: number;
+    /** null when none of the bucket's models has a configured price. */
+    costUsd: number | null;
+}
+
+/** Ratings of finished turns for one model and launch preset combination. */
+export interface OutcomeBucket {
+    model: string | null;
+    presetId: string | null;
+    /** Null when no preset was applied, or the preset was deleted since. */
+    presetName: string | null;
+    /** Finished turns, rated or not. */
+    turns: number;
+    accepted: number;
+    rework: number;
+    rejected: number;
+    /** Accepted share of the rated turns; null when none was rated. */
+    acceptanceRate: number | null;
+}
+
+export interface UsageSummary {
+    range: UsageRange;
+    totals: UsageBucket;
+    byDay: Array<UsageBucket & { day: string }>;
+    byModel: Array<UsageBucket & { model: string }>;
+    outcomes: OutcomeBucket[];
+}
+
+export interface CostEstimatePoint {
+    promptTokens: number;
+    completionTokens: number;
+    /** null when the model has no configured price. */
+    costUsd: number | null;
+}
+
+export interface TurnCostEstimate {
+    model: string;
+    /** Breakdown of the input sent on the first model call of the turn. */
+    input: { prompt: number; history: number; attachments: number; overhead: number };
+    low: CostEstimatePoint;
+    high: CostEstimatePoint;
+    thresholdUsd: number | null;
+    requiresConfirmation: boolean;
+}
+
+const PRICING_KEY = 'usage.pricing';
+const CONFIRM_THRESHOLD_KEY = 'usage.confirmThresholdUsd';
+
+// System prompt and tool schemas codex sends with every request.
+const REQUEST_OVERHEAD_TOKENS = 4000;
+// A turn that runs tools re-sends the context once per step.
+const AGENT_STEPS_HIGH = 4;
+const COMPLETION_LOW = 300;
+const COMPLETION_HIGH = 4000;
+
+// Usage rows are stamped with nowIso() (epoch seconds).
+const RANGE_SINCE: Record<UsageRange, string | null> = {
+    today: "strftime('%s', 'now', 'localtime', 'start of day', 'utc')",
+    week: "strftime('%s', 'now', '-7 days')",
+    month: "strftime('%s', 'now', '-30 days')",
+    all: null,
+};
+
+export function getUsagePricing(db: Database.Database): Record<string, ModelPricing> {
+    return dbGetSetting<Record<string, ModelPricing>>(db, PRICING_KEY, {});
+}
+
+export function setUsagePricing(
+    db: Database.Database,
+    pricing: Record<string, ModelPricing>,
+): Record<string, ModelPricing> {
+    dbSetSetting(db, PRICING_KEY, pricing);
+    return pricing;
+}
+
+/** Estimated cost at or above which the renderer asks before sending; null disables it. */
+export function getCostConfirmThreshold(db: Database.Database): number | null {
+    const value = dbGetSetting<number | null>(db, CONFIRM_THRESHOLD_KEY, null);
+    return typeof value === 'number' && value > 0 ? value : null;
+}
+
+export function setCostConfirmThreshold(db: Database.Database, thresholdUsd: number | null): number | null {
+    const value = typeof thresholdUsd === 'number' && thresholdUsd > 0 ? thresholdUsd : null;
+    dbSetSetting(db, CONFIRM_THRESHOLD_KEY, value);
+    return value;
+}
+
+/**
+ * Image input is billed by dimensions, which we don't decode; use the file size as a
+ * proxy for how many 512px tiles the model will see (85 base + 170 per tile, up to 6 tiles).
+ */
+export function estimateImageTokens(bytes: number): number {
+    const tiles = Math.min(6, Math.max(1, Math.ceil(bytes / (256 * 1024))));
+    return 85 + 170 * tiles;
+}
+
+function attachmentBytes(attachment: PromptAttachment): number {
+    if (attachment.path) {
+        try {
+            return fs.statSync(expandTildePath(attachment.path)).size;
+        } catch {
+            return 0;
+        }
+    }
+    if (attachment.data) {
+        const payload = attachment.data.replace(/^data:[^,]*,/, '').replace(/\s+/g, '');
+        return Math.floor((payload.length * 3) / 4);
+    }
+    return 0;
+}
+
+/**
+ * Low/high cost bounds for a turn whose first request carries `inputTokens`. The low end
+ * is a single answer with no tool calls; the high end assumes a few agent steps, or what
+ * the conversation's recent turns actually used plus the new input, whichever is larger.
+ */
+export function estimateCostRange(
+    inputTokens: number,
+    recent: TokenUsage[],
+    price: ModelPricing | undefined,
+): { low: CostEstimatePoint; high: CostEstimatePoint } {
+    const maxPrompt = Math.max(0, ...recent.map((u) => u.promptTokens));
+    const maxCompletion = Math.max(0, ...recent.map((u) => u.completionTokens));
+    const point = (promptTokens: number, completionTokens: number): CostEstimatePoint => ({
+        promptTokens,
+        completionTokens,
+        costUsd: costOf({ promptTokens, cachedTokens: 0, completionTokens, totalTokens: promptTokens + completionTokens }, price),
+    });
+    return {
+        low: point(inputTokens, COMPLETION_LOW),
+        high: point(
+            Math.max(inputTokens * AGENT_STEPS_HIGH, maxPrompt + inputTokens),
+            Math.max(COMPLETION_HIGH, maxCompletion),
+        ),
+    };
+}
+
+/**
+ * Estimate what sending `prompt` to the conversation would cost, using the model and
+ * prompt decoration the turn would get (persona, notes, pasted history or resumed
+ * session), attachment sizes and the configured pricing table.
+ */
+export function estimateTurnCost(
+    db: Database.Database,
+    config: RuntimeConfig,
+    conversationId: string,
+    prompt: string,
+    attachments: PromptAttachment[] = [],
+): TurnCostEstimate {
+    const overrides = dbGetConversationOverrides(db, conversationId);
+    const runConfig = resolveRuntimeConfig(config, overrides.cliOverrides, overrides.workspaceCliOptions);
+    const decorated = applyPersona(applyNotes(prompt, overrides.promptNotes), overrides.persona);
+
+    // A resumed session replays the whole thread; otherwise the history is trimmed to its
+    // token budget, with a summary of older messages when there is one.
+    const messages = dbGetMessages(db, conversationId);
+    const history = overrides.sessionId
+        ? messages.reduce((sum, m) => sum + estimateTokens(m.content) + 4, 0)
+        : trimHistory(messages, getHistorySettings(db), getHistorySummary(db, conversationId)).tokens;
+
+    const input = {
+        prompt: estimateTokens(decorated),
+        history,
+        attachments: attachments.reduce((sum, a) => sum + estimateImageTokens(attachmentBytes(a)), 0),
+        overhead: REQUEST_OVERHEAD_TOKENS,
+    };
+    const inputTokens = input.prompt + input.history + input.attachments + input.overhead;
+
+    const recent = (db.prepare(`
+    SELECT prompt_tokens, cached_tokens, completion_tokens, total_tokens FROM message_usage
+    WHERE conversation_id = ? ORDER BY CAST(created_at AS INTEGER) DESC LIMIT 5
+  `).all(conversationId) as any[]).map((row): TokenUsage => ({
+        promptTokens: row.prompt_tokens,
+        cachedTokens: row.cached_tokens,
+        completionTokens: row.completion_tokens,
+        totalTokens: row.total_tokens,
+    }));
+
+    const { low, high } = estimateCostRange(inputTokens, recent, getUsagePricing(db)[runConfig.model]);
+    const thresholdUsd = getCostConfirmThreshold(db);
+    return {
+        model: runConfig.model,
+        input,
+        low,
+        high,
+        thresholdUsd,
+        requiresConfirmation: thresholdUsd !== null && high.costUsd !== null && high.costUsd >= thresholdUsd,
+    };
+}
+
+/**
+ * Post-turn hook: attach the token usage reported by the finished turn to the assistant
+ * message the renderer just persisted.
+ */
+export function recordAssistantMessageUsage(state: AppState, message: Message): void {
+    if (message.role !== 'assistant') return;
+    const usage = state.turnUsage.get(message.conversationId);
+    if (!usage) return;
+    state.turnUsage.delete(message.conversationId);
+    try {
+        state.db.prepare(`
+      INSERT OR REPLACE INTO message_usage
+        (message_id, conversation_id, model, prompt_tokens, cached_tokens, completion_tokens, total_tokens, created_at)
+      VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+    `).run(
+            message.id,
+            message.conversationId,
+            usage.model,
+            usage.promptTokens,
+            usage.cachedTokens,
+            usage.completionTokens,
+            usage.totalTokens,
+            nowIso(),
+        );
+    } catch (error) {
+        console.error('[usage] Failed to record message usage:', error);
+    }
+}
+
+export function costOf(usage: TokenUsage, price: ModelPricing | undefined): number | null {
+    if (!price) return null;
+    const cachedPrice = price.cachedPrompt ?? price.prompt;
+    const uncached = Math.max(0, usage.promptTokens - usage.cachedTokens);
+    return (
+        (uncached * price.prompt + usage.cachedTokens * cachedPrice + usage.completionTokens * price.completion) /
+        1_000_000
+    );
+}
+
+function emptyBucket(): UsageBucket {
+    return { promptTokens: 0, cachedTokens: 0, completionTokens: 0, totalTokens: 0, turns: 0, costUsd: null };
+}
+
+function addTo(bucket: UsageBucket, row: UsageBucket): void {
+    bucket.promptTokens += row.promptTokens;
+    bucket.cachedTokens += row.cachedTokens;
+    bucket.completionTokens += row.completionTokens;
+    bucket.totalTokens += row.totalTokens;
+    bucket.turns += row.turns;
+    if (row.costUsd !== null) bucket.costUsd = (bucket.costUsd ?? 0) + row.costUsd;
+}
+
+export function getUsageSummary(
+    db: Database.Database,
+    workspaceId?: string,
+    range: UsageRange = 'month',
+): UsageSummary {
+    const clauses: string[] = [];
+    const params: string[] = [];
+    if (workspaceId) {
+        clauses.push('c.workspace_id = ?');
+        params.push(workspaceId);
+    }
+    const since = RANGE_SINCE[range];
+    if (since) clauses.push(`CAST(u.created_at AS INTEGER) >= CAST(${since} AS INTEGER)`);
+    const where = clauses.length ? `WHERE ${clauses.join(' AND ')}` : '';
+
+    const rows = db.prepare(`
+    SELECT date(CAST(u.created_at AS INTEGER), 'unixepoch', 'localtime') AS day, u.model,
+      SUM(u.prompt_tokens) AS prompt_tokens, SUM(u.cached_tokens) AS cached_tokens,
+      SUM(u.completion_tokens) AS completion_tokens, SUM(u.total_tokens) AS total_tokens,
+      COUNT(*) AS turns
+    FROM message_usage u
+    JOIN conversations c ON c.id = u.conversation_id
+    ${where}
+    GROUP BY day, u.model
+    ORDER BY day DESC
+  `).all(...params) as any[];
+
+    const pricing = getUsagePricing(db);
+    const totals = emptyBucket();
+    const byDay = new Map<string, UsageBucket & { day: string }>();
+    const byModel = new Map<string, UsageBucket & { model: string }>();
+
+    for (const row of rows) {
+        const usage: TokenUsage = {
+            promptTokens: row.prompt_tokens,
+            cachedTokens: row.cached_tokens,
+            completionTokens: row.completion_tokens,
+            totalTokens: row.total_tokens,
+        };
+        const bucket: UsageBucket = { ...usage, turns: row.turns, costUsd: costOf(usage, pricing[row.model]) };
+
+        addTo(totals, bucket);
+        if (!byDay.has(row.day)) byDay.set(row.day, { day: row.day, ...emptyBucket() });
+        addTo(byDay.get(row.day)!, bucket);
+        if (!byModel.has(row.model)) byModel.set(row.model, { model: row.model, ...emptyBucket() });
+        addTo(byModel.get(row.model)!, bucket);
+    }
+
+    return {
+        range,
+        totals,
+        byDay: Array.from(byDay.values()),
+        byModel: Array.from(byModel.values()).sort((a, b) => b.totalTokens - a.totalTokens),
+        outcomes: getOutcomeStats(db, workspaceId, range),
+    };
+}
+
+/**
+ * Turn ratings per model and launch preset, for finished turns started in the range.
+ * Combinations with the most rated turns come first.
+ */
+export function getOutcomeStats(
+    db: Database.Database,
+    workspaceId?: string,
+    range: UsageRange = 'month',
+): OutcomeBucket[] {
+    const clauses = ["t.status IN ('completed', 'failed')"];
+    const params: string[] = [];
+    if (workspaceId) {
+        clauses.push('c.workspace_id = ?');
+        params.push(workspaceId);
+    }
+    const since = RANGE_SINCE[range];
+    if (since) clauses.push(`CAST(t.created_at AS INTEGER) >= CAST(${since} AS INTEGER)`);
+
+    const rows = db.prepare(`
+    SELECT t.model, t.preset_id, p.name AS preset_name, COUNT(*) AS turns,
+      SUM(t.outcome IS 'accepted') AS accepted, SUM(t.outcome IS 'rework') AS rework,
+      SUM(t.outcome IS 'rejected') AS rejected
+    FROM turns t
+    JOIN conversations c ON c.id = t.conversation_id
+    LEFT JOIN launch_presets p ON p.id = t.preset_id
+    WHERE ${clauses.join(' AND ')}
+    GROUP BY t.model, t.preset_id
+  `).all(...params) as any[];
+
+    return rows
+        .map((row) => {
+            const rated = row.accepted + row.rework + row.rejected;
+            return {
+                model: row.model,
+                presetId: row.preset_id,
+                presetName: row.preset_name,
+                turns: row.turns,
+                accepted: row.accepted,
+                rework: row.rework,
+                rejected: row.rejected,
+                acceptanceRate: rated > 0 ? row.accepted / rated : null,
+            };
+        })
+        .sort((a, b) => (b.accepted + b.rework + b.rejected) - (a.accepted + a.rework + a.rejected) || b.turns - a.turns);
+}
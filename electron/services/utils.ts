@@ -1,8 +1,17 @@
-import { spawn, SpawnOptions } from 'child_process';
+import { ChildProcess, spawn, SpawnOptions } from 'child_process';
 import * as path from 'path';
 import * as fs from 'fs';
 import * as os from 'os';
-import { ModelInfo, RuntimeConfig } from './models';
+import {
+    ApprovalDecision,
+    ApprovalRequestEvent,
+    CliOverrides,
+    ModelInfo,
+    RuntimeConfig,
+    StreamToolCall,
+    TokenUsage,
+    WorkspaceCliOptions,
+} from './models';
 import { WebContents } from 'electron';
 
 let counter = 0;
@@ -29,15 +38,9 @@ export function expandTildePath(p: string): string {
     return p;
 }
 
-export function isCommandAvailable(bin: string): boolean {
-    try {
-        const locator = process.platform === 'win32' ? 'where' : 'which';
-        const { execSync } = require('child_process');
-        execSync(`${locator} ${bin}`, { stdio: 'ignore' });
-        return true;
-    } catch {
-        return false;
-    }
+export async function isCommandAvailable(bin: string): Promise<boolean> {
+    const locator = process.platform === 'win32' ? 'where' : 'which';
+    return (await commandOutput(locator, [bin])) !== null;
 }
 
 /**
@@ -84,17 +87,88 @@ export function commandSpawnOptions(cwd?: string): SpawnOptions {
         }
 
         opts.env!.PATH = [...extraPaths, ...currentPath.split(':')].join(':');
+    } else if (process.platform === 'linux') {
+        // A codex release binary is installed to ~/.local/bin, which a desktop session's
+        // PATH does not always include.
+        const localBin = path.join(homeDir() || '', '.local', 'bin');
+        const entries = (opts.env!.PATH || '').split(':');
+        if (homeDir() && !entries.includes(localBin)) opts.env!.PATH = [...entries, localBin].join(':');
     }
 
     return opts;
 }
 
+/** Children the app spawned that are still running; the watchdog never reaps these. */
+const trackedChildren = new Set<ChildProcess>();
+
+/**
+ * Register a spawned child so the orphan watchdog treats it (and everything below it)
+ * as ours. The child is dropped from the registry once it exits.
+ */
+export function trackChild<T extends ChildProcess>(child: T): T {
+    trackedChildren.add(child);
+    const release = () => trackedChildren.delete(child);
+    child.once('exit', release);
+    child.once('error', release);
+    return child;
+}
+
+/** Stop counting a child as tracked, e.g. when another registry owns its lifetime. */
+export function untrackChild(child: ChildProcess) {
+    trackedChildren.delete(child);
+}
+
+export function trackedChildPids(): number[] {
+    return [...trackedChildren].map((child) => child.pid).filter((pid): pid is number => !!pid);
+}
+
 /**
  * Spawn a command with enriched PATH.
  */
 export function spawnCommand(bin: string, args: string[], cwd?: string) {
     const opts = commandSpawnOptions(cwd);
-    return spawn(bin, args, { ...opts, stdio: ['pipe', 'pipe', 'pipe'] });
+    return trackChild(spawn(bin, args, { ...opts, stdio: ['pipe', 'pipe', 'pipe'] }));
+}
+
+/** Stdout of a command that exited cleanly, or null. */
+export function commandOutput(bin: string, args: string[]): Promise<string | null> {
+    return new Promise((resolve) => {
+        let output = '';
+        try {
+            const child = spawnCommand(bin, args);
+            child.stdout?.on('data', (data) => { output += data.toString(); });
+            child.on('error', () => resolve(null));
+            child.on('exit', (code) => resolve(code === 0 ? output : null));
+        } catch {
+            resolve(null);
+        }
+    });
+}
+
+/**
+ * Regex for a workspace-relative glob: `*` stays within a path segment, `**` spans
+ * segments and `?` matches one character. Patterns without a slash match the file name
+ * at any depth.
+ */
+export function globToRegExp(glob: string): RegExp {
+    const pattern = glob.trim().replace(/^\.\//, '');
+    const anyDepth = !pattern.includes('/');
+    let source = '';
+    for (let i = 0; i < pattern.length; i++) {
+        const ch = pattern[i];
+        if (ch === '*' && pattern[i + 1] === '*') {
+            const slash = pattern[i + 2] === '/';
+            source += slash ? '(?:.*/)?' : '.*';
+            i += slash ? 2 : 1;
+        } else if (ch === '*') {
+            source += '[^/]*';
+        } else if (ch === '?') {
+            source += '[^/]';
+        } else {
+            source += ch.replace(/[.+^${}()|[\]\\]/g, '\\$&');
+        }
+    }
+    return new RegExp(`^${anyDepth ? '(?:.*/)?' : ''}${source}$`);
 }
 
 export function parseExtraArgs(raw: string): string[] {
@@ -146,10 +220,108 @@ export function defaultModels(): ModelInfo[] {
     ];
 }
 
+function modelFromJson(entry: any): ModelInfo | null {
+    if (typeof entry === 'string') return entry.trim() ? { id: entry.trim(), name: entry.trim(), description: '' } : null;
+    if (!entry || typeof entry !== 'object') return null;
+    const id = entry.id ?? entry.slug ?? entry.model ?? entry.name;
+    if (typeof id !== 'string' || !id.trim()) return null;
+    const name = entry.display_name ?? entry.displayName ?? entry.name ?? id;
+    return { id: id.trim(), name: String(name), description: String(entry.description ?? '') };
+}
+
+/**
+ * Models from `codex models list` output: the `--json` form (an array, or an object with
+ * `models` / `data`), otherwise one model per line with the id first and an optional
+ * description after it. Header and separator lines are skipped.
+ */
+export function parseModelList(output: string): ModelInfo[] {
+    const text = output.trim();
+    if (!text) return [];
+
+    if (text.startsWith('[') || text.startsWith('{')) {
+        try {
+            const parsed = JSON.parse(text);
+            const entries = Array.isArray(parsed) ? parsed : parsed.models ?? parsed.data ?? [];
+            if (Array.isArray(entries)) {
+                return entries.map(modelFromJson).filter((m): m is ModelInfo => m !== null);
+            }
+        } catch {
+            // Fall through to the plain-text parser.
+        }
+    }
+
+    const models: ModelInfo[] = [];
+    for (const line of text.split(/\r?\n/)) {
+        const trimmed = line.replace(/^[\s*•-]+/, '').trim();
+        if (!trimmed || /^[-=─\s]+$/.test(trimmed)) continue;
+        const match = trimmed.match(/^(\S+)(?:\s{2,}|\s+[-–—:]\s+|\s+)?(.*)$/);
+        if (!match) continue;
+        const id = match[1].replace(/:$/, '');
+        if (/^(id|model|models|name|available)$/i.test(id)) continue;
+        if (models.some((m) => m.id === id)) continue;
+        models.push({ id, name: id, description: match[2].trim() });
+    }
+    return models;
+}
+
 // ===== Stream Parsing =====
 
 export class StreamParseCache {
     itemTextById: Map<string, string> = new Map();
+    touchedFiles: Set<string> = new Set();
+    sessionId: string | null = null;
+    itemCount = 0;
+    usage: TokenUsage | null = null;
+    /** Agent message text streamed so far, kept for a turn that is interrupted. */
+    agentText = '';
+    /** agentText stopped growing at `maxAgentText`. */
+    agentTextTruncated = false;
+    /** Reasoning text streamed so far, under the same cap as agentText. */
+    thinkingText = '';
+    thinkingTextTruncated = false;
+    /** Latest state of each tool call of the turn, in the order they started. */
+    toolCalls: Map<string, StreamToolCall> = new Map();
+
+    /** `maxAgentText` caps agentText (0 is unlimited); deltas are still emitted in full. */
+    constructor(readonly maxAgentText = 0) { }
+
+    appendAgentText(delta: string): void {
+        if (this.maxAgentText > 0 && this.agentText.length + delta.length > this.maxAgentText) {
+            this.agentText += delta.slice(0, Math.max(0, this.maxAgentText - this.agentText.length));
+            this.agentTextTruncated = true;
+            return;
+        }
+        this.agentText += delta;
+    }
+
+    appendThinkingText(delta: string): void {
+        if (this.maxAgentText > 0 && this.thinkingText.length + delta.length > this.maxAgentText) {
+            this.thinkingText += delta.slice(0, Math.max(0, this.maxAgentText - this.thinkingText.length));
+            this.thinkingTextTruncated = true;
+            return;
+        }
+        this.thinkingText += delta;
+    }
+}
+
+export function fileChangePaths(changes: any): string[] {
+    if (Array.isArray(changes)) {
+        return changes.map((c) => c?.path).filter((p): p is string => typeof p === 'string');
+    }
+    if (changes && typeof changes === 'object') return Object.keys(changes);
+    return [];
+}
+
+function describeFileChanges(changes: any): string {
+    if (Array.isArray(changes)) {
+        return changes.map((c) => `${c?.kind || 'update'} ${c?.path}`).join('\n');
+    }
+    if (changes && typeof changes === 'object') {
+        return Object.entries<any>(changes)
+            .map(([filePath, c]) => `${c?.type || Object.keys(c || {})[0] || 'update'} ${filePath}`)
+            .join('\n');
+    }
+    return '';
 }
 
 function valueAsObjectText(value: any): string {
@@ -178,19 +350,15 @@ function extractTextDelta(
     return delta;
 }
 
-export interface ApprovalRequestEvent {
-    requestId: string;
-    title: string;
-    description: string;
-}
-
 export function tryExtractApprovalRequest(event: any): ApprovalRequestEvent | null {
     const eventType = (event.type || '').toLowerCase();
     const method = (event.method || '').toLowerCase();
     if (!eventType.includes('approval') && !method.includes('approval')) return null;
 
-    const requestId = event.requestId || event.request_id || event.id || '';
-    if (!requestId) return null;
+    const rawId = event.requestId ?? event.request_id ?? event.id;
+    if (rawId === undefined || rawId === null || rawId === '') return null;
+    const requestId = String(rawId);
+    const isRpc = typeof event.method === 'string' && event.id !== undefined && event.id !== null;
 
     const title = event.title || event.method || 'Approval requested';
     const description = event.description
@@ -199,7 +367,58 @@ export function tryExtractApprovalRequest(event: any): ApprovalRequestEvent | nu
             ? valueAsObjectText(event.params)
             : valueAsObjectText(event);
 
-    return { requestId, title, description };
+    if (!isRpc) return { requestId, title, description };
+    return {
+        requestId,
+        title,
+        description,
+        rpcId: event.id,
+        method: event.method,
+        params: event.params,
+        jsonrpc: event.jsonrpc === '2.0',
+    };
+}
+
+// Newer codex protocols (`item/commandExecution/requestApproval`) use the v2 names; the
+// older `execCommandApproval`/`applyPatchApproval` requests take the snake_case ReviewDecision.
+const V2_DECISIONS: Record<ApprovalDecision, string> = {
+    approve: 'accept',
+    approve_for_session: 'acceptForSession',
+    deny: 'decline',
+    abort: 'cancel',
+};
+const LEGACY_DECISIONS: Record<ApprovalDecision, string> = {
+    approve: 'approved',
+    approve_for_session: 'approved_for_session',
+    deny: 'denied',
+    abort: 'abort',
+};
+
+/**
+ * The stdin line answering an approval request. JSON-RPC requests get a response with the
+ * original id and a `decision` in the shape their method expects; a deny message is sent
+ * as `reason`. Plain approval events keep the `{ request_id, approved }` shape.
+ */
+export function buildApprovalResponse(
+    request: ApprovalRequestEvent,
+    decision: ApprovalDecision,
+    message?: string,
+): string {
+    const reason = message?.trim() || undefined;
+    if (request.rpcId === undefined) {
+        return JSON.stringify({
+            request_id: request.requestId,
+            approved: decision === 'approve' || decision === 'approve_for_session',
+            decision: LEGACY_DECISIONS[decision],
+            ...(reason ? { reason } : {}),
+        });
+    }
+    const decisions = (request.method || '').includes('/') ? V2_DECISIONS : LEGACY_DECISIONS;
+    return JSON.stringify({
+        ...(request.jsonrpc ? { jsonrpc: '2.0' } : {}),
+        id: request.rpcId,
+        result: { decision: decisions[decision], ...(reason ? { reason } : {}) },
+    });
 }
 
 export function parseCodexEvent(
@@ -220,8 +439,14 @@ export function parseCodexEvent(
     }
 
     const eventType = event.type || '';
+    if (eventType.startsWith('item.')) cache.itemCount++;
 
     switch (eventType) {
+        case 'thread.started': {
+            const threadId = event.thread_id || event.session_id || '';
+            if (threadId) cache.sessionId = threadId;
+            break;
+        }
         case 'item.streaming': {
             const item = event.item;
             if (item) {
@@ -229,10 +454,12 @@ export function parseCodexEvent(
                 if (delta) {
                     const itemType = item.type || '';
                     if (itemType === 'reasoning') {
+                        cache.appendThinkingText(delta);
                         const payload = { cid, data: delta };
                         webContents.send('codex-thinking-delta', payload);
                         webContents.send('codex-thinking', payload);
                     } else {
+                        cache.appendAgentText(delta);
                         webContents.send('codex-stream-delta', { cid, data: delta });
                     }
                 }
@@ -252,6 +479,7 @@ export function parseCodexEvent(
                     const text = item.text || '';
                     const delta = extractTextDelta(cache, itemId, text, terminal);
                     if (delta) {
+                        cache.appendThinkingText(delta);
                         const payload = { cid, data: delta };
                         webContents.send('codex-thinking-delta', payload);
                         webContents.send('codex-thinking', payload);
@@ -260,6 +488,7 @@ export function parseCodexEvent(
                     const text = item.text || '';
                     const delta = extractTextDelta(cache, itemId, text, terminal);
                     if (delta) {
+                        cache.appendAgentText(delta);
                         webContents.send('codex-stream-delta', { cid, data: delta });
                     }
                 } else if (itemType === 'command_execution') {
@@ -281,6 +510,7 @@ export function parseCodexEvent(
 
                     const toolStatus =
                         status === 'completed' ? 'done' : status === 'failed' || status === 'declined' ? 'error' : 'running';
+                    cache.toolCalls.set(terminalId, { id: terminalId, title: command, status: toolStatus, output, terminalId, exitCode });
                     webContents.send('codex-tool-call', { cid, title: command, status: toolStatus, output });
                 } else if (itemType === 'mcp_tool_call') {
                     const server = item.server || 'mcp';
@@ -289,27 +519,50 @@ export function parseCodexEvent(
                     const output = valueAsObjectText(item.result || item.error || '');
                     const toolStatus =
                         status === 'completed' ? 'done' : status === 'failed' ? 'error' : 'running';
+                    const title = `${server}:${tool}`;
+                    cache.toolCalls.set(itemId || title, { id: itemId || title, title, status: toolStatus, output });
                     webContents.send('codex-tool-call', {
                         cid,
-                        title: `${server}:${tool}`,
+                        title,
                         status: toolStatus,
                         output,
                     });
                 } else if (itemType === 'file_change') {
                     const status = (item.status || 'in_progress').toLowerCase();
                     const changes = item.changes || null;
+                    for (const filePath of fileChangePaths(changes)) cache.touchedFiles.add(filePath);
                     const toolStatus =
                         status === 'completed' ? 'done' : status === 'failed' ? 'error' : 'running';
+                    const output = describeFileChanges(changes);
+                    const callId = itemId || `file_change-${cache.toolCalls.size}`;
+                    cache.toolCalls.set(callId, { id: callId, title: 'file_change', status: toolStatus, output });
                     webContents.send('codex-tool-call', {
                         cid,
                         title: 'file_change',
                         status: toolStatus,
-                        output: valueAsObjectText(changes),
+                        output,
                     });
                 }
             }
             break;
         }
+        case 'turn.completed': {
+            const usage = event.usage;
+            if (usage) {
+                const prompt = Number(usage.input_tokens) || 0;
+                const cached = Number(usage.cached_input_tokens) || 0;
+                const completion = Number(usage.output_tokens) || 0;
+                const prev = cache.usage;
+                cache.usage = {
+                    promptTokens: (prev?.promptTokens || 0) + prompt,
+                    cachedTokens: (prev?.cachedTokens || 0) + cached,
+                    completionTokens: (prev?.completionTokens || 0) + completion,
+                    totalTokens: (prev?.totalTokens || 0) + prompt + completion,
+                };
+                webContents.send('codex-usage', { cid, ...cache.usage });
+            }
+            break;
+        }
         case 'turn.failed': {
             const msg =
                 event.error?.message || event.error?.error || 'Turn failed';
@@ -326,26 +579,103 @@ export function parseCodexEvent(
     return null;
 }
 
+/**
+ * Merge overrides on top of the global runtime config: the workspace's CLI options first,
+ * then the conversation's. A `yoloMode` in the workspace options replaces the global one.
+ */
+export function resolveRuntimeConfig(
+    cfg: RuntimeConfig,
+    overrides?: CliOverrides,
+    workspaceOptions?: WorkspaceCliOptions,
+): RuntimeConfig {
+    const apply = (base: RuntimeConfig, layer?: CliOverrides): RuntimeConfig => {
+        if (!layer) return base;
+        const { model, ...cliOptions } = layer;
+        const defined = Object.fromEntries(
+            Object.entries(cliOptions).filter(([, value]) => value !== undefined),
+        );
+        return {
+            ...base,
+            model: model || base.model,
+            cliOptions: { ...base.cliOptions, ...defined },
+        };
+    };
+    let resolved = cfg;
+    if (workspaceOptions) {
+        const { yoloMode, ...layer } = workspaceOptions;
+        resolved = apply(resolved, layer);
+        if (yoloMode !== undefined) resolved = { ...resolved, yoloMode };
+    }
+    return apply(resolved, overrides);
+}
+
+export function applyPersona(prompt: string, persona?: string): string {
+    const trimmed = persona?.trim();
+    if (!trimmed) return prompt;
+    return `[Agent persona]\n${trimmed}\n\n${prompt}`;
+}
+
+export function applyNotes(prompt: string, notes?: string): string {
+    const trimmed = notes?.trim();
+    if (!trimmed) return prompt;
+    return `[Conversation notes]\n${trimmed}\n\n${prompt}`;
+}
+
+export function applyProjectSummary(prompt: string, summary?: string): string {
+    const trimmed = summary?.trim();
+    if (!trimmed) return prompt;
+    return `[Project summary]\n${trimmed}\n\n${prompt}`;
+}
+
+/**
+ * Rough token count without a tokenizer: about four characters per token for Latin text
+ * and code, one token per CJK/Hangul character.
+ */
+export function estimateTokens(text: string): number {
+    if (!text) return 0;
+    const wide = (text.match(/[\u1100-\u11ff\u3040-\u30ff\u3130-\u318f\u3400-\u9fff\uac00-\ud7af\uf900-\ufaff]/g) || []).length;
+    return wide + Math.ceil((text.length - wide) / 4);
+}
+
+/**
+ * The prompt with the history messages pasted ahead of it, for a turn without a session.
+ * Callers trim the history first (see history.ts); a `system` message holds the summary
+ * of older messages.
+ */
+export function withConversationHistory(prompt: string, history?: Array<{ role: string; content: string }>): string {
+    if (!history || history.length === 0) return prompt;
+    const lines = history.map((msg) => {
+        const prefix =
+            msg.role === 'assistant' ? 'Assistant' : msg.role === 'system' ? 'System' : 'User';
+        return `${prefix}: ${msg.content}`;
+    });
+    return `[Previous conversation]\n${lines.join('\n')}\n\n[Current question]\n${prompt}`;
+}
+
+/**
+ * Build `codex exec` arguments. With `resumeSessionId` the turn continues that CLI session
+ * (`codex exec ... resume <id> <prompt>`) and history is not pasted into the prompt.
+ */
 export function buildCodexExecArgs(
     prompt: string,
     cfg: RuntimeConfig,
     history?: Array<{ role: string; content: string }>,
+    resumeSessionId?: string,
+    images: string[] = [],
 ): [string, string, string[]] {
-    let fullPrompt = prompt;
-    if (history && history.length > 0) {
-        const recent = history.slice(-10);
-        const lines = recent.map((msg) => {
-            const prefix = msg.role === 'assistant' ? 'Assistant' : 'User';
-            return `${prefix}: ${msg.content}`;
-        });
-        fullPrompt = `[Previous conversation]\n${lines.join('\n')}\n\n[Current question]\n${prompt}`;
-    }
+    const fullPrompt = resumeSessionId ? prompt : withConversationHistory(prompt, history);
 
     const requestedCwd = cfg.cliOptions.cwdOverride.trim() || cfg.cwd;
     const runCwd = expandTildePath(requestedCwd);
 
     const args: string[] = ['exec', '--json'];
 
+    // `-i` takes several values, so keep it ahead of the other flags where the next
+    // option ends the list rather than the prompt being swallowed as an image.
+    for (const image of images) {
+        args.push('-i', image);
+    }
+
     if (cfg.model) {
         args.push('-m', cfg.model);
     }
@@ -373,6 +703,11 @@ export function buildCodexExecArgs(
         args.push('--config', `approval_policy="${approvalPolicy}"`);
     }
 
+    const effort = cfg.cliOptions.reasoningEffort?.trim();
+    if (effort) {
+        args.push('--config', `model_reasoning_effort="${effort}"`);
+    }
+
     // Note: web search is not yet supported by codex CLI v0.98.0
     // if (cfg.cliOptions.enableWebSearch) {
     //     args.push('--enable', 'web-search');
@@ -385,6 +720,9 @@ export function buildCodexExecArgs(
     }
 
     args.push(...parseExtraArgs(cfg.cliOptions.extraArgs));
+    if (resumeSessionId) {
+        args.push('resume', resumeSessionId);
+    }
     args.push(fullPrompt);
 
     return [fullPrompt, runCwd, args];
@@ -0,0 +1,277 @@
+import Database from 'better-sqlite3';
+import { WebContents } from 'electron';
+import * as fs from 'fs';
+import * as path from 'path';
+import { AppState } from './models';
+import { dbCreateConversation, dbCreateMessage, dbGetMessages } from './db';
+import { streamCodex } from './codex';
+import { runGit } from './git';
+import { expandTildePath, generateId, globToRegExp, nowIso } from './utils';
+
+export interface WatchRule {
+    id: string;
+    workspaceId: string;
+    glob: string;
+    /** Prompt sent on change. `{{files}}` and `{{diff}}` are filled in; the diff is appended when absent. */
+    promptTemplate: string;
+    debounceMs: number;
+    maxPerHour: number;
+    enabled: boolean;
+    conversationId: string | null;
+    lastTriggeredAt: string | null;
+}
+
+interface ActiveWatcher {
+    watcher: fs.FSWatcher;
+    timer: NodeJS.Timeout | null;
+    pending: Set<string>;
+    triggers: number[];
+}
+
+const DEFAULT_DEBOUNCE_MS = 2000;
+const DEFAULT_MAX_PER_HOUR = 6;
+const MAX_DIFF_CHARS = 20000;
+const HOUR_MS = 60 * 60 * 1000;
+export const IGNORED_DIRS = new Set(['.git', 'node_modules', 'target', 'dist', 'build']);
+
+const activeWatchers = new Map<string, ActiveWatcher>();
+
+function rowToRule(row: any): WatchRule {
+    return {
+        id: row.id,
+        workspaceId: row.workspace_id,
+        glob: row.glob,
+        promptTemplate: row.prompt_template,
+        debounceMs: row.debounce_ms,
+        maxPerHour: row.max_per_hour,
+        enabled: !!row.enabled,
+        conversationId: row.conversation_id,
+        lastTriggeredAt: row.last_triggered_at,
+    };
+}
+
+/**
+ * Fill a rule's prompt template with the changed files and their diff. The diff is
+ * truncated to keep the prompt a reasonable size.
+ */
+export function buildWatchPrompt(template: string, files: string[], diff: string): string {
+    const trimmed = diff.length > MAX_DIFF_CHARS ? `${diff.slice(0, MAX_DIFF_CHARS)}\n... (diff truncated)` : diff;
+    const diffBlock = trimmed.trim() ? `\`\`\`diff\n${trimmed.trim()}\n\`\`\`` : '(no diff available)';
+    const fileList = files.map((f) => `- ${f}`).join('\n');
+    let prompt = template.replace(/\{\{files\}\}/g, fileList);
+    if (prompt.includes('{{diff}}')) return prompt.replace(/\{\{diff\}\}/g, diffBlock);
+    if (!template.includes('{{files}}')) prompt += `\n\nChanged files:\n${fileList}`;
+    return `${prompt}\n\n${diffBlock}`;
+}
+
+/** Whether another trigger fits under the hourly cap. Prunes timestamps older than an hour. */
+export function withinTriggerCap(triggers: number[], maxPerHour: number, now = Date.now()): boolean {
+    while (triggers.length && now - triggers[0] >= HOUR_MS) triggers.shift();
+    return triggers.length < maxPerHour;
+}
+
+export function listWatchRules(db: Database.Database, workspaceId?: string): WatchRule[] {
+    const rows = workspaceId
+        ? db.prepare('SELECT * FROM watch_rules WHERE workspace_id = ? ORDER BY created_at').all(workspaceId)
+        : db.prepare('SELECT * FROM watch_rules ORDER BY created_at').all();
+    return rows.map(rowToRule);
+}
+
+function getRule(db: Database.Database, ruleId: string): WatchRule | null {
+    const row = db.prepare('SELECT * FROM watch_rules WHERE id = ?').get(ruleId);
+    return row ? rowToRule(row) : null;
+}
+
+async function changedFilesDiff(cwd: string, files: string[]): Promise<string> {
+    const tracked = await runGit(cwd, ['diff', '--no-color', '--no-ext-diff', 'HEAD', '--', ...files]);
+    if (tracked.success && tracked.stdout.trim()) return tracked.stdout;
+    // Untracked (or no git repo): show the new content instead.
+    const parts: string[] = [];
+    for (const file of files) {
+        try {
+            const text = fs.readFileSync(path.join(cwd, file), 'utf-8');
+            parts.push(`--- /dev/null\n+++ b/${file}\n${text.split('\n').map((l) => `+${l}`).join('\n')}`);
+        } catch {
+            parts.push(`--- a/${file}\n+++ /dev/null\n(deleted)`);
+        }
+    }
+    return parts.join('\n');
+}
+
+function ruleConversation(state: AppState, rule: WatchRule): string {
+    if (rule.conversationId) {
+        const exists = state.db.prepare('SELECT 1 FROM conversations WHERE id = ?').get(rule.conversationId);
+        if (exists) return rule.conversationId;
+    }
+    const conversationId = generateId('conv');
+    dbCreateConversation(state.db, conversationId, rule.workspaceId, `Watch: ${rule.glob}`);
+    state.db.prepare('UPDATE watch_rules SET conversation_id = ? WHERE id = ?').run(conversationId, rule.id);
+    return conversationId;
+}
+
+async function triggerRule(
+    getWebContents: () => WebContents | null,
+    state: AppState,
+    ruleId: string,
+    files: string[],
+): Promise<void> {
+    const active = activeWatchers.get(ruleId);
+    const rule = getRule(state.db, ruleId);
+    const webContents = getWebContents();
+    if (!active || !rule?.enabled || !webContents) return;
+
+    const workspace = state.db.prepare('SELECT path FROM workspaces WHERE id = ?').get(rule.workspaceId) as any;
+    if (!workspace) return;
+
+    if (!withinTriggerCap(active.triggers, rule.maxPerHour)) {
+        webContents.send('watch-rule-skipped', { ruleId, files, reason: 'hourly cap reached' });
+        return;
+    }
+    const conversationId = ruleConversation(state, rule);
+    if (state.runningCodex.has(conversationId)) {
+        webContents.send('watch-rule-skipped', { ruleId, files, reason: 'turn already running' });
+        return;
+    }
+    active.triggers.push(Date.now());
+
+    const cwd = expandTildePath(workspace.path);
+    const prompt = buildWatchPrompt(rule.promptTemplate, files, await changedFilesDiff(cwd, files));
+    const history = dbGetMessages(state.db, conversationId).map((m) => ({ role: m.role, content: m.content }));
+    dbCreateMessage(state.db, {
+        id: generateId('msg'),
+        conversationId,
+        role: 'user',
+        content: prompt,
+        timestamp: new Date().toISOString(),
+    });
+    state.db.prepare('UPDATE watch_rules SET last_triggered_at = ? WHERE id = ?').run(nowIso(), ruleId);
+
+    webContents.send('watch-rule-triggered', { ruleId, conversationId, files });
+    streamCodex(webContents, conversationId, prompt, history, state);
+}
+
+function stopWatcher(ruleId: string): void {
+    const active = activeWatchers.get(ruleId);
+    if (!active) return;
+    if (active.timer) clearTimeout(active.timer);
+    active.watcher.close();
+    activeWatchers.delete(ruleId);
+}
+
+function startWatcher(getWebContents: () => WebContents | null, state: AppState, rule: WatchRule): string | null {
+    stopWatcher(rule.id);
+    const workspace = state.db.prepare('SELECT path FROM workspaces WHERE id = ?').get(rule.workspaceId) as any;
+    if (!workspace) return 'Workspace not found';
+    const root = expandTildePath(workspace.path);
+    const matcher = globToRegExp(rule.glob);
+
+    let watcher: fs.FSWatcher;
+    try {
+        watcher = fs.watch(root, { recursive: true });
+    } catch (err: any) {
+        return err.message;
+    }
+    const active: ActiveWatcher = { watcher, timer: null, pending: new Set(), triggers: [] };
+    activeWatchers.set(rule.id, active);
+
+    watcher.on('error', (err) => {
+        console.error(`[watch] Watcher for ${rule.id} failed:`, err);
+        stopWatcher(rule.id);
+    });
+    watcher.on('change', (_event, filename) => {
+        if (!filename) return;
+        const rel = filename.toString().split(path.sep).join('/');
+        if (rel.split('/').some((segment) => IGNORED_DIRS.has(segment)) || !matcher.test(rel)) return;
+        // Edits made by the rule's own turn must not re-trigger it.
+        const conversationId = getRule(state.db, rule.id)?.conversationId;
+        if (conversationId && state.runningCodex.has(conversationId)) return;
+
+        active.pending.add(rel);
+        if (active.timer) clearTimeout(active.timer);
+        active.timer = setTimeout(() => {
+            active.timer = null;
+            const files = Array.from(active.pending).sort();
+            active.pending.clear();
+            triggerRule(getWebContents, state, rule.id, files).catch((err) =>
+                console.error('[watch] Trigger failed:', err),
+            );
+        }, rule.debounceMs);
+    });
+    return null;
+}
+
+/**
+ * Watch a workspace for changes to files matching `glob` and, once changes settle for
+ * `debounceMs`, run a codex turn with `promptTemplate` and the diff of the changed files
+ * in the rule's own conversation. At most `maxPerHour` turns are started per hour.
+ */
+export function watchAndPrompt(
+    getWebContents: () => WebContents | null,
+    state: AppState,
+    workspaceId: string,
+    glob: string,
+    promptTemplate: string,
+    debounceMs = DEFAULT_DEBOUNCE_MS,
+    maxPerHour = DEFAULT_MAX_PER_HOUR,
+): { success: boolean; rule?: WatchRule; error?: string } {
+    if (!glob.trim()) return { success: false, error: 'Glob is required' };
+    if (!promptTemplate.trim()) return { success: false, error: 'Prompt template is required' };
+    const workspace = state.db.prepare('SELECT id FROM workspaces WHERE id = ?').get(workspaceId);
+    if (!workspace) return { success: false, error: 'Workspace not found' };
+
+    const id = generateId('watch');
+    state.db
+        .prepare(`
+    INSERT INTO watch_rules (id, workspace_id, glob, prompt_template, debounce_ms, max_per_hour, enabled, created_at)
+    VALUES (?, ?, ?, ?, ?, ?, 1, ?)
+  `)
+        .run(
+            id,
+            workspaceId,
+            glob.trim(),
+            promptTemplate,
+            Math.max(250, Math.floor(debounceMs) || DEFAULT_DEBOUNCE_MS),
+            Math.max(1, Math.floor(maxPerHour) || DEFAULT_MAX_PER_HOUR),
+            nowIso(),
+        );
+    const rule = getRule(state.db, id)!;
+    const error = startWatcher(getWebContents, state, rule);
+    return error ? { success: false, rule, error } : { success: true, rule };
+}
+
+export function setWatchRuleEnabled(
+    getWebContents: () => WebContents | null,
+    state: AppState,
+    ruleId: string,
+    enabled: boolean,
+): { success: boolean; rule?: WatchRule; error?: string } {
+    state.db.prepare('UPDATE watch_rules SET enabled = ? WHERE id = ?').run(enabled ? 1 : 0, ruleId);
+    const rule = getRule(state.db, ruleId);
+    if (!rule) return { success: false, error: 'Watch rule not found' };
+    if (!enabled) {
+        stopWatcher(ruleId);
+        return { success: true, rule };
+    }
+    const error = startWatcher(getWebContents, state, rule);
+    return error ? { success: false, rule, error } : { success: true, rule };
+}
+
+export function deleteWatchRule(db: Database.Database, ruleId: string): { success: boolean } {
+    stopWatcher(ruleId);
+    db.prepare('DELETE FROM watch_rules WHERE id = ?').run(ruleId);
+    return { success: true };
+}
+
+/**
+ * Start watchers for every enabled rule. Returns a function that stops them all.
+ */
+export function startWatchRules(getWebContents: () => WebContents | null, state: AppState): () => void {
+    for (const rule of listWatchRules(state.db)) {
+        if (!rule.enabled) continue;
+        const error = startWatcher(getWebContents, state, rule);
+        if (error) console.error(`[watch] Could not watch ${rule.glob}:`, error);
+    }
+    return () => {
+        for (const ruleId of Array.from(activeWatchers.keys())) stopWatcher(ruleId);
+    };
+}
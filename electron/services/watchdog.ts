@@ -0,0 +1,187 @@
+import { execFile } from 'child_process';
+import { WebContents } from 'electron';
+import * as fs from 'fs';
+import * as path from 'path';
+import { promisify } from 'util';
+import { AppState } from './models';
+import { commandSpawnOptions, trackedChildPids } from './utils';
+
+const execFileAsync = promisify(execFile);
+
+export interface OsProcess {
+    pid: number;
+    ppid: number;
+    command: string;
+}
+
+const WATCHDOG_INTERVAL_MS = 60_000;
+
+async function listOsProcesses(): Promise<OsProcess[]> {
+    try {
+        if (process.platform === 'win32') {
+            const { stdout } = await execFileAsync(
+                'powershell.exe',
+                [
+                    '-NoProfile',
+                    '-Command',
+                    'Get-CimInstance Win32_Process | Select-Object ProcessId,ParentProcessId,CommandLine | ConvertTo-Json -Compress',
+                ],
+                { encoding: 'utf-8', maxBuffer: 20 * 1024 * 1024, windowsHide: true },
+            );
+            const rows = JSON.parse(stdout || '[]');
+            return (Array.isArray(rows) ? rows : [rows]).map((r: any) => ({
+                pid: Number(r.ProcessId),
+                ppid: Number(r.ParentProcessId),
+                command: String(r.CommandLine || ''),
+            }));
+        }
+
+        const { stdout } = await execFileAsync('ps', ['-A', '-o', 'pid=,ppid=,args='], {
+            encoding: 'utf-8',
+            maxBuffer: 20 * 1024 * 1024,
+        });
+        return stdout
+            .split('\n')
+            .map((line) => line.trim().match(/^(\d+)\s+(\d+)\s+(.*)$/))
+            .filter((m): m is RegExpMatchArray => m !== null)
+            .map((m) => ({ pid: Number(m[1]), ppid: Number(m[2]), command: m[3] }));
+    } catch (err) {
+        console.error('[watchdog] Failed to list processes:', err);
+        return [];
+    }
+}
+
+/**
+ * Every path the `codex` command on the app's PATH goes by: the PATH entry itself and,
+ * for the npm launcher, the script it links to plus the package's `vendor/` directory
+ * (ending in a separator) that holds the native binary it spawns.
+ */
+export function resolveCodexPaths(): string[] {
+    const dirs = (commandSpawnOptions().env?.PATH || '').split(path.delimiter).filter(Boolean);
+    const names = process.platform === 'win32' ? ['codex.exe', 'codex.cmd', 'codex'] : ['codex'];
+    for (const dir of dirs) {
+        for (const name of names) {
+            const candidate = path.join(dir, name);
+            if (!fs.existsSync(candidate)) continue;
+            const paths = [candidate];
+            try {
+                const real = fs.realpathSync(candidate);
+                if (real !== candidate) paths.push(real);
+                if (real.endsWith('.js')) paths.push(path.join(path.dirname(path.dirname(real)), 'vendor') + path.sep);
+            } catch { }
+            return paths;
+        }
+    }
+    return [];
+}
+
+function normalizePath(file: string): string {
+    const slashed = file.replace(/\\/g, '/');
+    return process.platform === 'win32' ? slashed.toLowerCase() : slashed;
+}
+
+/**
+ * Whether a process runs the resolved codex binary as argv[0], or node running the
+ * codex launcher script. Command lines that merely mention codex (Electron helpers,
+ * `npm install @openai/codex`, an editor opening codex.md) do not count. Paths are
+ * matched as prefixes because `ps` does not quote arguments that contain spaces.
+ */
+export function isCodexProcess(command: string, codexPaths: string[]): boolean {
+    const line = normalizePath(command.trim())
+        .replace(/^(?:"[^"]*\/node(?:\.exe)?"|(?:[^"\s]*\/)?node(?:\.exe)?)\s+/i, '')
+        .replace(/^"/, '');
+    return codexPaths.some((entry) => {
+        const codex = normalizePath(entry);
+        if (codex.endsWith('/')) {
+            return line.startsWith(codex) && /^[^\s"]*\/codex(?:\.exe)?(?:["\s]|$)/i.test(line.slice(codex.length));
+        }
+        return line.startsWith(codex) && /^(?:["\s]|$)/.test(line.slice(codex.length));
+    });
+}
+
+/**
+ * Collect every root pid and all processes below them in the tree.
+ */
+function descendantsOf(processes: OsProcess[], roots: number[]): Set<number> {
+    const children = new Map<number, number[]>();
+    for (const p of processes) {
+        const list = children.get(p.ppid) || [];
+        list.push(p.pid);
+        children.set(p.ppid, list);
+    }
+
+    const seen = new Set<number>();
+    const stack = [...roots];
+    while (stack.length > 0) {
+        const pid = stack.pop()!;
+        if (seen.has(pid)) continue;
+        seen.add(pid);
+        stack.push(...(children.get(pid) || []));
+    }
+    return seen;
+}
+
+/**
+ * Codex processes spawned by this app that nothing tracks any more. Turn children are
+ * tracked through `runningCodex`, every other spawn through the child registry in utils,
+ * and the npm `codex` launcher spawns the native binary as a grandchild, so the whole
+ * subtree of every tracked child counts as tracked.
+ */
+export function findOrphanProcesses(
+    state: AppState,
+    processes: OsProcess[],
+    codexPaths: string[],
+    trackedPids: number[] = trackedChildPids(),
+): OsProcess[] {
+    const ours = descendantsOf(processes, [process.pid]);
+    ours.delete(process.pid);
+
+    const trackedRoots = [...trackedPids];
+    for (const [, running] of state.runningCodex) {
+        if (running.child?.pid) trackedRoots.push(running.child.pid);
+    }
+    for (const child of [...state.ptyTerminals.values(), ...state.runningCommands.values()]) {
+        if (child.pid) trackedRoots.push(child.pid);
+    }
+    const tracked = descendantsOf(processes, trackedRoots);
+
+    return processes.filter(
+        (p) => ours.has(p.pid) && !tracked.has(p.pid) && isCodexProcess(p.command, codexPaths),
+    );
+}
+
+export async function listOrphanProcesses(state: AppState): Promise<OsProcess[]> {
+    return findOrphanProcesses(state, await listOsProcesses(), resolveCodexPaths());
+}
+
+export async function reapOrphanProcesses(webContents: WebContents | null, state: AppState): Promise<OsProcess[]> {
+    const orphans = await listOrphanProcesses(state);
+    for (const orphan of orphans) {
+        console.error(`[watchdog] Reaping orphan codex process ${orphan.pid}: ${orphan.command}`);
+        webContents?.send('codex-orphan-warning', { pid: orphan.pid, command: orphan.command });
+        try {
+            process.kill(orphan.pid, 'SIGKILL');
+        } catch { }
+    }
+    return orphans;
+}
+
+/**
+ * Periodically reap leaked codex children. Returns a function that stops the watchdog.
+ */
+export function startWatchdog(
+    getWebContents: () => WebContents | null,
+    state: AppState,
+    intervalMs = WATCHDOG_INTERVAL_MS,
+): () => void {
+    let sweeping = false;
+    const timer = setInterval(() => {
+        if (sweeping) return;
+        sweeping = true;
+        reapOrphanProcesses(getWebContents(), state)
+            .catch((err) => console.error('[watchdog] Sweep failed:', err))
+            .finally(() => { sweeping = false; });
+    }, intervalMs);
+    timer.unref();
+    return () => clearInterval(timer);
+}
@@ -0,0 +1,182 @@
+import Database from 'better-sqlite3';
+import { SearchResult, WebSearchProvider, WebSearchSettings } from './models';
+import { dbGetSetting, dbSetSetting } from './db';
+import { getSecret, rememberSecret } from './secrets';
+
+const SETTINGS_KEY = 'webSearch.settings';
+const MAX_RESULTS_LIMIT = 20;
+const REQUEST_TIMEOUT_MS = 10_000;
+/** A search that would have to wait longer than this for its provider's slot is refused. */
+const MAX_QUEUE_WAIT_MS = 10_000;
+
+export const WEB_SEARCH_PROVIDERS: WebSearchProvider[] = ['duckduckgo', 'brave', 'searxng', 'google', 'bing'];
+
+/** Settings fields kept in the keychain when it is turned on. */
+const SECRET_FIELDS = ['braveApiKey', 'googleApiKey', 'bingApiKey'] as const;
+
+const DEFAULT_SETTINGS: WebSearchSettings = {
+    provider: 'duckduckgo',
+    maxResults: 5,
+    braveApiKey: '',
+    searxngUrl: '',
+    googleApiKey: '',
+    googleCx: '',
+    bingApiKey: '',
+    bingEndpoint: 'https://api.bing.microsoft.com/v7.0/search',
+};
+
+/** The shortest gap between two requests to a provider, from their free-tier limits. */
+const MIN_INTERVAL_MS: Record<WebSearchProvider, number> = {
+    duckduckgo: 1000,
+    brave: 1000,
+    searxng: 500,
+    google: 250,
+    bing: 350,
+};
+
+export function getWebSearchSettings(db: Database.Database): WebSearchSettings {
+    const stored = { ...DEFAULT_SETTINGS, ...dbGetSetting<Partial<WebSearchSettings>>(db, SETTINGS_KEY, {}) };
+    for (const field of SECRET_FIELDS) {
+        stored[field] = stored[field] || getSecret(`webSearch.${field}`) || '';
+    }
+    return stored;
+}
+
+export function setWebSearchSettings(db: Database.Database, settings: Partial<WebSearchSettings>): WebSearchSettings {
+    const merged = { ...getWebSearchSettings(db), ...settings };
+    if (!WEB_SEARCH_PROVIDERS.includes(merged.provider)) merged.provider = DEFAULT_SETTINGS.provider;
+    merged.maxResults = Math.min(MAX_RESULTS_LIMIT, Math.max(1, Math.floor(Number(merged.maxResults)) || DEFAULT_SETTINGS.maxResults));
+    const saved = { ...merged };
+    for (const field of SECRET_FIELDS) {
+        if (settings[field] !== undefined && rememberSecret(`webSearch.${field}`, merged[field])) saved[field] = '';
+    }
+    dbSetSetting(db, SETTINGS_KEY, saved);
+    return merged;
+}
+
+function stripTags(text: string): string {
+    return text.replace(/<[^>]+>/g, '').replace(/&quot;/g, '"').replace(/&#39;/g, "'").replace(/&amp;/g, '&').trim();
+}
+
+function result(title: unknown, url: unknown, snippet: unknown): SearchResult | null {
+    if (typeof url !== 'string' || !url) return null;
+    return {
+        title: stripTags(typeof title === 'string' && title ? title : url),
+        url,
+        snippet: stripTags(typeof snippet === 'string' ? snippet : ''),
+    };
+}
+
+/** A provider's JSON response as results, most relevant first. */
+export function parseSearchResponse(provider: WebSearchProvider, data: any, query: string): SearchResult[] {
+    let found: Array<SearchResult | null> = [];
+    switch (provider) {
+        case 'duckduckgo':
+            if (data?.Abstract) found.push(result(data.Heading || query, data.AbstractURL, data.Abstract));
+            for (const topic of Array.isArray(data?.RelatedTopics) ? data.RelatedTopics : []) {
+                if (topic?.Text) found.push(result(topic.Text.split(' - ')[0], topic.FirstURL, topic.Text));
+            }
+            break;
+        case 'brave':
+            found = (data?.web?.results ?? []).map((r: any) => result(r.title, r.url, r.description));
+            break;
+        case 'searxng':
+            found = (data?.results ?? []).map((r: any) => result(r.title, r.url, r.content));
+            break;
+        case 'google':
+            found = (data?.items ?? []).map((r: any) => result(r.title, r.link, r.snippet));
+            break;
+        case 'bing':
+            found = (data?.webPages?.value ?? []).map((r: any) => result(r.name, r.url, r.snippet));
+            break;
+    }
+    return found.filter((r): r is SearchResult => r !== null);
+}
+
+/** The request for a search, or an error naming the missing setting. */
+export function buildSearchRequest(
+    provider: WebSearchProvider,
+    settings: WebSearchSettings,
+    query: string,
+    maxResults: number,
+): { url: string; headers: Record<string, string> } | { error: string } {
+    const q = encodeURIComponent(query);
+    switch (provider) {
+        case 'duckduckgo':
+            return { url: `https://api.duckduckgo.com/?q=${q}&format=json&no_html=1`, headers: {} };
+        case 'brave':
+            if (!settings.braveApiKey) return { error: 'Set a Brave Search API key first' };
+            return {
+                url: `https://api.search.brave.com/res/v1/web/search?q=${q}&count=${maxResults}`,
+                headers: { Accept: 'application/json', 'X-Subscription-Token': settings.braveApiKey },
+            };
+        case 'searxng':
+            if (!settings.searxngUrl) return { error: 'Set the SearXNG instance URL first' };
+            return { url: `${settings.searxngUrl.replace(/\/+$/, '')}/search?q=${q}&format=json`, headers: { Accept: 'application/json' } };
+        case 'google':
+            if (!settings.googleApiKey || !settings.googleCx) return { error: 'Set a Google API key and search engine ID (cx) first' };
+            return {
+                url: `https://www.googleapis.com/customsearch/v1?key=${encodeURIComponent(settings.googleApiKey)}&cx=${encodeURIComponent(settings.googleCx)}&q=${q}&num=${Math.min(maxResults, 10)}`,
+                headers: {},
+            };
+        case 'bing':
+            if (!settings.bingApiKey) return { error: 'Set a Bing Web Search API key first' };
+            return {
+                url: `${settings.bingEndpoint || DEFAULT_SETTINGS.bingEndpoint}?q=${q}&count=${maxResults}`,
+                headers: { 'Ocp-Apim-Subscription-Key': settings.bingApiKey },
+            };
+    }
+}
+
+const nextSlotAt = new Map<WebSearchProvider, number>();
+
+/**
+ * Reserve the provider's next request slot. Resolves with how long to wait for it, or
+ * null when the queue is already longer than MAX_QUEUE_WAIT_MS.
+ */
+export function reserveSearchSlot(provider: WebSearchProvider, now: number = Date.now()): number | null {
+    const slot = Math.max(now, nextSlotAt.get(provider) ?? 0);
+    const wait = slot - now;
+    if (wait > MAX_QUEUE_WAIT_MS) return null;
+    nextSlotAt.set(provider, slot + MIN_INTERVAL_MS[provider]);
+    return wait;
+}
+
+/**
+ * Search the web with `provider` (the configured one by default). Requests to a provider
+ * are spaced to stay under its rate limit; a burst beyond that is refused.
+ */
+export async function webSearch(
+    db: Database.Database,
+    query: string,
+    provider?: WebSearchProvider,
+    maxResults?: number,
+): Promise<{ success: boolean; provider: WebSearchProvider; results: SearchResult[]; error?: string }> {
+    const settings = getWebSearchSettings(db);
+    const chosen = provider ?? settings.provider;
+    if (!WEB_SEARCH_PROVIDERS.includes(chosen)) {
+        return { success: false, provider: chosen, results: [], error: `Unknown search provider: ${chosen}` };
+    }
+    if (!query.trim()) return { success: false, provider: chosen, results: [], error: 'query is required' };
+    const limit = Math.min(MAX_RESULTS_LIMIT, Math.max(1, Math.floor(maxResults ?? settings.maxResults) || DEFAULT_SETTINGS.maxResults));
+    const request = buildSearchRequest(chosen, settings, query.trim(), limit);
+    if ('error' in request) return { success: false, provider: chosen, results: [], error: request.error };
+
+    const wait = reserveSearchSlot(chosen);
+    if (wait === null) {
+        return { success: false, provider: chosen, results: [], error: `Too many ${chosen} searches; try again shortly` };
+    }
+    if (wait > 0) await new Promise((resolve) => setTimeout(resolve, wait));
+
+    try {
+        const response = await fetch(request.url, { headers: request.headers, signal: AbortSignal.timeout(REQUEST_TIMEOUT_MS) });
+        if (!response.ok) {
+            const error = response.status === 429 ? `${chosen} rate limit reached; try again later` : `${chosen} returned HTTP ${response.status}`;
+            return { success: false, provider: chosen, results: [], error };
+        }
+        const results = parseSearchResponse(chosen, await response.json(), query).slice(0, limit);
+        return { success: true, provider: chosen, results };
+    } catch (err: any) {
+        return { success: false, provider: chosen, results: [], error: err.message };
+    }
+}
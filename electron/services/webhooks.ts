@@ -0,0 +1,225 @@
+import Database from 'better-sqlite3';
+import * as crypto from 'crypto';
+import { WebContents } from 'electron';
+import { AppState } from './models';
+import { deleteSecret, getSecret, rememberSecret, webhookSecretName } from './secrets';
+import { generateId, nowIso } from './utils';
+
+export type WebhookEvent = 'turn-completed' | 'approval-requested' | 'error' | 'ping';
+
+export const WEBHOOK_EVENTS: WebhookEvent[] = ['turn-completed', 'approval-requested', 'error'];
+
+export interface Webhook {
+    id: string;
+    url: string;
+    /** Subscribed events; `*` receives all of them. */
+    events: Array<WebhookEvent | '*'>;
+    /** Only the last four characters; the full secret is returned once, by addWebhook. */
+    secretHint: string;
+    enabled: boolean;
+    createdAt: string;
+    lastDeliveryAt: string | null;
+    lastStatus: number | null;
+    lastError: string | null;
+}
+
+export interface WebhookPayload {
+    id: string;
+    event: WebhookEvent;
+    timestamp: string;
+    workspace: { id: string; name: string; path: string } | null;
+    conversation: { id: string; title: string } | null;
+    data: Record<string, unknown>;
+}
+
+const DELIVERY_TIMEOUT_MS = 10_000;
+
+const CHANNEL_EVENT: Record<string, WebhookEvent> = {
+    'codex-stream-end': 'turn-completed',
+    'codex-approval-request': 'approval-requested',
+    'codex-stream-error': 'error',
+};
+
+/** A webhook's signing secret; an empty column means it was saved in the keychain. */
+function webhookSecret(row: any): string {
+    return row.secret || getSecret(webhookSecretName(row.id)) || '';
+}
+
+function webhookFromRow(row: any): Webhook {
+    return {
+        id: row.id,
+        url: row.url,
+        events: JSON.parse(row.events),
+        secretHint: webhookSecret(row).slice(-4),
+        enabled: !!row.enabled,
+        createdAt: row.created_at,
+        lastDeliveryAt: row.last_delivery_at,
+        lastStatus: row.last_status,
+        lastError: row.last_error,
+    };
+}
+
+export function listWebhooks(db: Database.Database): Webhook[] {
+    return (db.prepare('SELECT * FROM webhooks ORDER BY created_at, rowid').all() as any[]).map(webhookFromRow);
+}
+
+/**
+ * Register a URL for the given events. Without a secret a random one is generated; it is
+ * returned here only, so the receiver can be set up to check signatures.
+ */
+export function addWebhook(
+    db: Database.Database,
+    url: string,
+    events: Array<WebhookEvent | '*'>,
+    secret?: string,
+): { success: boolean; webhook?: Webhook; secret?: string; error?: string } {
+    try {
+        if (!['http:', 'https:'].includes(new URL(url).protocol)) throw new Error();
+    } catch {
+        return { success: false, error: `Invalid webhook URL: ${url}` };
+    }
+    const valid = Array.from(new Set((Array.isArray(events) ? events : [])
+        .filter((e) => e === '*' || WEBHOOK_EVENTS.includes(e as WebhookEvent))));
+    if (valid.length === 0) return { success: false, error: `Pick at least one event: ${WEBHOOK_EVENTS.join(', ')} or *` };
+
+    const key = secret?.trim() || crypto.randomBytes(24).toString('hex');
+    const id = generateId('webhook');
+    const inKeychain = rememberSecret(webhookSecretName(id), key);
+    db.prepare('INSERT INTO webhooks (id, url, events, secret, created_at) VALUES (?, ?, ?, ?, ?)')
+        .run(id, url.trim(), JSON.stringify(valid), inKeychain ? '' : key, nowIso());
+    const row = db.prepare('SELECT * FROM webhooks WHERE id = ?').get(id);
+    return { success: true, webhook: webhookFromRow(row), secret: key };
+}
+
+export function removeWebhook(db: Database.Database, id: string): { success: boolean } {
+    const removed = db.prepare('DELETE FROM webhooks WHERE id = ?').run(id).changes > 0;
+    if (removed) deleteSecret(webhookSecretName(id));
+    return { success: removed };
+}
+
+export function setWebhookEnabled(db: Database.Database, id: string, enabled: boolean): { success: boolean } {
+    return { success: db.prepare('UPDATE webhooks SET enabled = ? WHERE id = ?').run(enabled ? 1 : 0, id).changes > 0 };
+}
+
+/** `sha256=<hex>` HMAC of the raw body, sent as `X-Codex-Signature`. */
+export function signWebhookPayload(secret: string, body: string): string {
+    return `sha256=${crypto.createHmac('sha256', secret).update(body).digest('hex')}`;
+}
+
+/** The webhook event a stream event stands for, or null. Cancelled turns are not reported. */
+export function webhookEventFor(channel: string, payload: any): WebhookEvent | null {
+    const event = CHANNEL_EVENT[channel];
+    if (!event || typeof payload?.cid !== 'string') return null;
+    return event === 'turn-completed' && payload.cancelled ? null : event;
+}
+
+export function buildWebhookPayload(
+    db: Database.Database,
+    event: WebhookEvent,
+    conversationId: string | null,
+    data: Record<string, unknown>,
+): WebhookPayload {
+    const row = conversationId
+        ? db.prepare(`
+      SELECT c.id, c.title, w.id AS workspace_id, w.name AS workspace_name, w.path AS workspace_path
+      FROM conversations c JOIN workspaces w ON w.id = c.workspace_id
+      WHERE c.id = ?
+    `).get(conversationId) as any
+        : null;
+    return {
+        id: generateId('delivery'),
+        event,
+        timestamp: new Date().toISOString(),
+        workspace: row ? { id: row.workspace_id, name: row.workspace_name, path: row.workspace_path } : null,
+        conversation: row ? { id: row.id, title: row.title } : null,
+        data,
+    };
+}
+
+async function deliver(
+    db: Database.Database,
+    row: any,
+    payload: WebhookPayload,
+): Promise<{ success: boolean; status?: number; error?: string }> {
+    const body = JSON.stringify(payload);
+    let result: { success: boolean; status?: number; error?: string };
+    try {
+        const response = await fetch(row.url, {
+            method: 'POST',
+            headers: {
+                'Content-Type': 'application/json',
+                'User-Agent': 'codex-wui-webhooks',
+                'X-Codex-Event': payload.event,
+                'X-Codex-Delivery': payload.id,
+                'X-Codex-Signature': signWebhookPayload(webhookSecret(row), body),
+            },
+            body,
+            signal: AbortSignal.timeout(DELIVERY_TIMEOUT_MS),
+        });
+        result = response.ok
+            ? { success: true, status: response.status }
+            : { success: false, status: response.status, error: `HTTP ${response.status}` };
+    } catch (err: any) {
+        result = { success: false, error: `Request failed: ${err.message}` };
+    }
+    db.prepare('UPDATE webhooks SET last_delivery_at = ?, last_status = ?, last_error = ? WHERE id = ?')
+        .run(nowIso(), result.status ?? null, result.error ?? null, row.id);
+    return result;
+}
+
+/**
+ * POST the event to every enabled webhook subscribed to it. Deliveries run in the
+ * background; their outcome is kept on the webhook row and never reaches the turn.
+ */
+export function dispatchWebhookEvent(
+    db: Database.Database,
+    event: WebhookEvent,
+    conversationId: string | null,
+    data: Record<string, unknown>,
+): void {
+    try {
+        const rows = (db.prepare('SELECT * FROM webhooks WHERE enabled = 1').all() as any[])
+            .filter((row) => {
+                const events: string[] = JSON.parse(row.events);
+                return events.includes('*') || events.includes(event);
+            });
+        if (rows.length === 0) return;
+        const payload = buildWebhookPayload(db, event, conversationId, data);
+        for (const row of rows) {
+            deliver(db, row, payload).then((result) => {
+                if (!result.success) console.error(`[webhooks] Delivery to ${row.url} failed:`, result.error);
+            });
+        }
+    } catch (err) {
+        console.error('[webhooks] Failed to dispatch event:', err);
+    }
+}
+
+/** Send a `ping` event to one webhook, enabled or not, and wait for the answer. */
+export async function testWebhook(
+    db: Database.Database,
+    id: string,
+): Promise<{ success: boolean; status?: number; error?: string }> {
+    const row = db.prepare('SELECT * FROM webhooks WHERE id = ?').get(id);
+    if (!row) return { success: false, error: 'Webhook not found' };
+    return deliver(db, row, buildWebhookPayload(db, 'ping', null, { message: 'Test delivery from Codex WUI' }));
+}
+
+/** Wrap a WebContents so turn completions, approval requests and errors go to the webhooks. */
+export function tapWebhooks(webContents: WebContents, state: AppState): WebContents {
+    return new Proxy(webContents, {
+        get(target, prop) {
+            if (prop === 'send') {
+                return (channel: string, ...args: any[]) => {
+                    target.send(channel, ...args);
+                    const event = webhookEventFor(channel, args[0]);
+                    if (!event) return;
+                    const { cid, ...data } = args[0];
+                    dispatchWebhookEvent(state.db, event, cid, data);
+                };
+            }
+            const value = Reflect.get(target, prop);
+            return typeof value === 'function' ? value.bind(target) : value;
+        },
+    });
+}
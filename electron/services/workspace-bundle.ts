@@ -0,0 +1,240 @@
+import Database from 'better-sqlite3';
+import * as fs from 'fs';
+import * as path from 'path';
+import { ConversationTemplate, Workspace, WorkspaceCliOptions } from './models';
+import { dbCreateWorkspace, dbGetWorkspaceCliOptions, dbSetWorkspaceCliOptions } from './db';
+import { openArchive, writeZip } from './archives';
+import { listChores, saveChore } from './chores';
+import { buildConversationExport, ConversationExport, importConversationData } from './conversation-export';
+import { listTurnSnapshots } from './snapshots';
+import { listConversationTemplates, saveConversationTemplate } from './templates';
+import { listWatchRules } from './watch';
+import { expandTildePath, generateId, nowIso } from './utils';
+
+export const WORKSPACE_BUNDLE_VERSION = 1;
+
+/** Workspace instruction files carried in a bundle, relative to the workspace root. */
+const INSTRUCTION_FILES = ['AGENTS.md', 'AGENTS.override.md'];
+
+export interface WorkspaceBundleManifest {
+    version: number;
+    exportedAt: string;
+    workspace: { name: string; path: string };
+    conversations: number;
+    includesCheckpoints: boolean;
+}
+
+interface BundleSettings {
+    cliOptions: WorkspaceCliOptions;
+    chores: Array<{ name: string; prompt: string; runAt: string; enabled: boolean }>;
+    watchRules: Array<{ glob: string; promptTemplate: string; debounceMs: number; maxPerHour: number; enabled: boolean }>;
+}
+
+export interface WorkspaceBundleImport {
+    workspace: Workspace;
+    conversations: number;
+    templates: number;
+    chores: number;
+    watchRules: number;
+    instructions: string[];
+    /** Files and items that were not imported, with the reason. */
+    skipped: string[];
+}
+
+/**
+ * Write a workspace's agent setup to a zip at `destPath`: conversations (JSON exports
+ * with notes), workspace CLI options, chores, watch rules, conversation templates and
+ * instruction files such as AGENTS.md. Source code is never included; checkpoint
+ * metadata (which files each turn touched, without their backups) only on request.
+ */
+export function exportWorkspaceBundle(
+    db: Database.Database,
+    workspaceId: string,
+    destPath: string,
+    includeCheckpoints = false,
+): { success: boolean; path?: string; size?: number; conversations?: number; error?: string } {
+    const workspace = db.prepare('SELECT * FROM workspaces WHERE id = ?').get(workspaceId) as any;
+    if (!workspace) return { success: false, error: 'Workspace not found' };
+
+    try {
+        const json = (value: unknown) => Buffer.from(JSON.stringify(value, null, 2), 'utf-8');
+        const files: Array<{ name: string; data: Buffer }> = [];
+
+        const conversationIds = (db
+            .prepare('SELECT id FROM conversations WHERE workspace_id = ? ORDER BY created_at')
+            .all(workspaceId) as any[]).map((row) => row.id as string);
+        for (const id of conversationIds) {
+            const data = buildConversationExport(db, id, true);
+            if (data) files.push({ name: `conversations/${id}.json`, data: json(data) });
+        }
+
+        const settings: BundleSettings = {
+            cliOptions: dbGetWorkspaceCliOptions(db, workspaceId),
+            chores: listChores(db, workspaceId).map(({ name, prompt, runAt, enabled }) => ({ name, prompt, runAt, enabled })),
+            watchRules: listWatchRules(db, workspaceId).map(({ glob, promptTemplate, debounceMs, maxPerHour, enabled }) => ({
+                glob,
+                promptTemplate,
+                debounceMs,
+                maxPerHour,
+                enabled,
+            })),
+        };
+        files.push({ name: 'settings.json', data: json(settings) });
+        files.push({
+            name: 'templates.json',
+            data: json(listConversationTemplates(db).map(({ id: _id, createdAt: _c, updatedAt: _u, ...template }) => template)),
+        });
+
+        for (const fileName of INSTRUCTION_FILES) {
+            const file = path.join(workspace.path, fileName);
+            if (fs.existsSync(file) && fs.statSync(file).isFile()) {
+                files.push({ name: `instructions/${fileName}`, data: fs.readFileSync(file) });
+            }
+        }
+
+        if (includeCheckpoints) {
+            const checkpoints = conversationIds.flatMap((id) =>
+                listTurnSnapshots(db, id).map((snapshot) => ({ ...snapshot, conversationId: id })),
+            );
+            files.push({ name: 'checkpoints.json', data: json(checkpoints) });
+        }
+
+        const manifest: WorkspaceBundleManifest = {
+            version: WORKSPACE_BUNDLE_VERSION,
+            exportedAt: new Date().toISOString(),
+            workspace: { name: workspace.name, path: workspace.path },
+            conversations: conversationIds.length,
+            includesCheckpoints: includeCheckpoints,
+        };
+        files.unshift({ name: 'manifest.json', data: json(manifest) });
+
+        const target = expandTildePath(destPath);
+        const zip = writeZip(files);
+        fs.mkdirSync(path.dirname(target), { recursive: true });
+        fs.writeFileSync(target, zip);
+        return { success: true, path: target, size: zip.length, conversations: conversationIds.length };
+    } catch (err: any) {
+        return { success: false, error: err.message };
+    }
+}
+
+/**
+ * Create a new workspace at `workspacePath` from a bundle. Conversations get new ids;
+ * templates whose name already exists are kept as they are; chores and watch rules come
+ * in disabled so nothing runs on the new machine until it is reviewed. Instruction files
+ * are written only where the workspace has none. Checkpoint metadata is not restored:
+ * without the file backups it cannot roll anything back.
+ */
+export function importWorkspaceBundle(
+    db: Database.Database,
+    bundlePath: string,
+    workspacePath: string,
+    name?: string,
+): { success: boolean; result?: WorkspaceBundleImport; error?: string } {
+    let files: Map<string, Buffer>;
+    try {
+        files = new Map(
+            openArchive(expandTildePath(bundlePath))
+                .entries.filter((entry) => entry.type === 'file')
+                .map((entry): [string, Buffer] => [entry.name, entry.read()]),
+        );
+    } catch (err: any) {
+        return { success: false, error: `Could not read bundle: ${err.message}` };
+    }
+
+    const parse = <T>(fileName: string): T | undefined => {
+        const data = files.get(fileName);
+        return data ? (JSON.parse(data.toString('utf-8')) as T) : undefined;
+    };
+
+    let manifest: WorkspaceBundleManifest | undefined;
+    try {
+        manifest = parse<WorkspaceBundleManifest>('manifest.json');
+    } catch { }
+    if (typeof manifest?.version !== 'number') return { success: false, error: 'Not a workspace bundle' };
+    if (manifest.version > WORKSPACE_BUNDLE_VERSION) {
+        return { success: false, error: `Bundle version ${manifest.version} is newer than this app supports` };
+    }
+
+    const root = expandTildePath(workspacePath);
+    if (!fs.existsSync(root) || !fs.statSync(root).isDirectory()) {
+        return { success: false, error: 'Workspace path is not a directory' };
+    }
+
+    const importAll = db.transaction(() => {
+        const workspace = dbCreateWorkspace(db, generateId('ws'), name?.trim() || manifest!.workspace.name, root);
+        const result: WorkspaceBundleImport = {
+            workspace,
+            conversations: 0,
+            templates: 0,
+            chores: 0,
+            watchRules: 0,
+            instructions: [],
+            skipped: [],
+        };
+
+        for (const [fileName, data] of files) {
+            if (!fileName.startsWith('conversations/') || !fileName.endsWith('.json')) continue;
+            const imported = importConversationData(db, JSON.parse(data.toString('utf-8')) as ConversationExport, workspace.id);
+            if (imported.success && imported.conversation) {
+                workspace.conversations.push(imported.conversation);
+                result.conversations++;
+            } else {
+                result.skipped.push(`${fileName}: ${imported.error}`);
+            }
+        }
+
+        const settings = parse<BundleSettings>('settings.json');
+        if (settings?.cliOptions) dbSetWorkspaceCliOptions(db, workspace.id, settings.cliOptions);
+        for (const chore of settings?.chores || []) {
+            const saved = saveChore(db, {
+                workspaceId: workspace.id,
+                name: chore.name,
+                prompt: chore.prompt,
+                runAt: chore.runAt,
+                enabled: false,
+            });
+            if (saved.success) result.chores++;
+            else result.skipped.push(`chore "${chore.name}": ${saved.error}`);
+        }
+        for (const rule of settings?.watchRules || []) {
+            db.prepare(`
+        INSERT INTO watch_rules (id, workspace_id, glob, prompt_template, debounce_ms, max_per_hour, enabled, created_at)
+        VALUES (?, ?, ?, ?, ?, ?, 0, ?)
+      `).run(generateId('watch'), workspace.id, rule.glob, rule.promptTemplate, rule.debounceMs, rule.maxPerHour, nowIso());
+            result.watchRules++;
+        }
+
+        const existingTemplates = new Set(listConversationTemplates(db).map((t) => t.name.toLowerCase()));
+        for (const template of parse<Array<Omit<ConversationTemplate, 'id' | 'createdAt' | 'updatedAt'>>>('templates.json') || []) {
+            if (existingTemplates.has(template.name.toLowerCase())) {
+                result.skipped.push(`template "${template.name}": already exists`);
+                continue;
+            }
+            saveConversationTemplate(db, template);
+            result.templates++;
+        }
+        return result;
+    });
+
+    let result: WorkspaceBundleImport;
+    try {
+        result = importAll();
+    } catch (err: any) {
+        return { success: false, error: err.message };
+    }
+
+    for (const fileName of INSTRUCTION_FILES) {
+        const data = files.get(`instructions/${fileName}`);
+        if (!data) continue;
+        const target = path.join(root, fileName);
+        if (fs.existsSync(target)) {
+            result.skipped.push(`${fileName}: already exists in the workspace`);
+            continue;
+        }
+        fs.writeFileSync(target, data);
+        result.instructions.push(fileName);
+    }
+    if (files.has('checkpoints.json')) result.skipped.push('checkpoints.json: checkpoint metadata is not restored');
+    return { success: true, result };
+}
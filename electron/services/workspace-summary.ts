@@ -0,0 +1,168 @@
+import Database from 'better-sqlite3';
+import * as crypto from 'crypto';
+import * as fs from 'fs';
+import * as path from 'path';
+import { AppState } from './models';
+import { dbGetSetting, dbSetSetting } from './db';
+import { runCodexToCompletion } from './codex';
+import { expandTildePath } from './utils';
+import { IGNORED_DIRS } from './watch';
+
+const SUMMARY_KEY_PREFIX = 'workspaceSummary.';
+const README_NAMES = ['README.md', 'README.markdown', 'README.rst', 'README.txt', 'README', 'readme.md'];
+const MANIFEST_NAMES = [
+    'package.json',
+    'Cargo.toml',
+    'pyproject.toml',
+    'setup.py',
+    'go.mod',
+    'pom.xml',
+    'build.gradle',
+    'build.gradle.kts',
+    'Gemfile',
+    'composer.json',
+    'CMakeLists.txt',
+];
+const MAX_README_CHARS = 6000;
+const MAX_MANIFEST_CHARS = 2000;
+const MAX_ENTRIES = 80;
+const MAX_SUMMARY_CHARS = 2000;
+
+/** What a workspace summary is made from; `fingerprint` changes when any of it does. */
+export interface ProjectSources {
+    readme: { name: string; text: string } | null;
+    manifests: Array<{ name: string; text: string }>;
+    /** Top-level files and directories (with a trailing `/`), hidden and build ones left out. */
+    entries: string[];
+    fingerprint: string;
+}
+
+export interface WorkspaceSummary {
+    summary: string;
+    fingerprint: string;
+    generatedAt: string;
+}
+
+function readText(file: string): string | null {
+    try {
+        return fs.statSync(file).isFile() ? fs.readFileSync(file, 'utf-8') : null;
+    } catch {
+        return null;
+    }
+}
+
+/** The README, manifests and top-level entries of a workspace, read fresh from disk. */
+export function readProjectSources(workspacePath: string): ProjectSources {
+    const root = expandTildePath(workspacePath);
+    const hash = crypto.createHash('sha256');
+
+    let readme: ProjectSources['readme'] = null;
+    for (const name of README_NAMES) {
+        const text = readText(path.join(root, name));
+        if (text === null) continue;
+        hash.update(`readme:${name}\0${text}\0`);
+        readme = { name, text: text.slice(0, MAX_README_CHARS) };
+        break;
+    }
+
+    const manifests: ProjectSources['manifests'] = [];
+    for (const name of MANIFEST_NAMES) {
+        const text = readText(path.join(root, name));
+        if (text === null) continue;
+        hash.update(`manifest:${name}\0${text}\0`);
+        manifests.push({ name, text: text.slice(0, MAX_MANIFEST_CHARS) });
+    }
+
+    let entries: string[] = [];
+    try {
+        entries = fs.readdirSync(root, { withFileTypes: true })
+            .filter((entry) => !entry.name.startsWith('.') && !IGNORED_DIRS.has(entry.name))
+            .map((entry) => (entry.isDirectory() ? `${entry.name}/` : entry.name))
+            .sort()
+            .slice(0, MAX_ENTRIES);
+    } catch { }
+    hash.update(`entries:${entries.join('\n')}`);
+
+    return { readme, manifests, entries, fingerprint: hash.digest('hex') };
+}
+
+export function buildSummaryPrompt(name: string, sources: ProjectSources): string {
+    const fence = (label: string, text: string) => [`### ${label}`, '```', text.trim(), '```', ''];
+    return [
+        `Summarize the project "${name}" for a coding agent that is about to start working in it.`,
+        'In one short paragraph (at most six sentences) say what the project does, its main languages',
+        'and frameworks, how the top-level directories are organized, and how to build and test it if',
+        'the files below say. Use only the information given; do not run commands or modify any files.',
+        'Reply with the paragraph only.',
+        '',
+        '## Top-level entries',
+        ...(sources.entries.length ? sources.entries.map((entry) => `- ${entry}`) : ['(none)']),
+        '',
+        ...(sources.readme ? fence(sources.readme.name, sources.readme.text) : []),
+        ...sources.manifests.flatMap((manifest) => fence(manifest.name, manifest.text)),
+    ].join('\n');
+}
+
+export function getWorkspaceSummary(db: Database.Database, workspaceId: string): WorkspaceSummary | null {
+    return dbGetSetting<WorkspaceSummary | null>(db, SUMMARY_KEY_PREFIX + workspaceId, null);
+}
+
+/** The cached summary when the files it was made from are unchanged; else undefined. */
+export function freshWorkspaceSummary(db: Database.Database, workspaceId: string, workspacePath: string): string | undefined {
+    const cached = getWorkspaceSummary(db, workspaceId);
+    if (!cached) return undefined;
+    return cached.fingerprint === readProjectSources(workspacePath).fingerprint ? cached.summary : undefined;
+}
+
+const inFlight = new Map<string, Promise<{ success: boolean; summary?: WorkspaceSummary; cached?: boolean; error?: string }>>();
+
+/**
+ * A short description of the workspace from its README, manifests and top-level layout,
+ * written by a read-only codex run and cached until one of those changes (or `force`).
+ * New conversations in the workspace start with the cached summary in their first prompt.
+ */
+export function summarizeWorkspace(
+    state: AppState,
+    workspaceId: string,
+    force = false,
+): Promise<{ success: boolean; summary?: WorkspaceSummary; cached?: boolean; error?: string }> {
+    const workspace = state.db.prepare('SELECT name, path FROM workspaces WHERE id = ?').get(workspaceId) as any;
+    if (!workspace) return Promise.resolve({ success: false, error: 'Workspace not found' });
+    const workspacePath = expandTildePath(workspace.path);
+    const sources = readProjectSources(workspacePath);
+    const cached = getWorkspaceSummary(state.db, workspaceId);
+    if (!force && cached?.fingerprint === sources.fingerprint) {
+        return Promise.resolve({ success: true, summary: cached, cached: true });
+    }
+    if (!sources.readme && sources.manifests.length === 0 && sources.entries.length === 0) {
+        return Promise.resolve({ success: false, error: 'The workspace has nothing to summarize' });
+    }
+
+    const running = inFlight.get(workspaceId);
+    if (running) return running;
+    const run = runCodexToCompletion(buildSummaryPrompt(workspace.name, sources), {
+        ...state.config,
+        yoloMode: false,
+        cwd: workspacePath,
+        cliOptions: {
+            ...state.config.cliOptions,
+            sandbox: 'read-only',
+            askForApproval: 'never',
+            cwdOverride: '',
+        },
+    }).then((result) => {
+        const text = result.text.trim();
+        if (!result.success || !text) {
+            return { success: false, error: result.error || 'Codex returned an empty summary' };
+        }
+        const summary: WorkspaceSummary = {
+            summary: text.slice(0, MAX_SUMMARY_CHARS),
+            fingerprint: sources.fingerprint,
+            generatedAt: new Date().toISOString(),
+        };
+        dbSetSetting(state.db, SUMMARY_KEY_PREFIX + workspaceId, summary);
+        return { success: true, summary, cached: false };
+    }).finally(() => inFlight.delete(workspaceId));
+    inFlight.set(workspaceId, run);
+    return run;
+}
@@ -0,0 +1,114 @@
+import { WebContents } from 'electron';
+import * as fs from 'fs';
+import * as path from 'path';
+import { IGNORED_DIRS } from './watch';
+import { expandTildePath, globToRegExp } from './utils';
+
+export type FileChangeKind = 'created' | 'modified' | 'deleted';
+
+export interface WorkspaceFileChange {
+    /** Relative to the workspace root, with `/` separators. */
+    path: string;
+    kind: FileChangeKind;
+}
+
+interface ActiveWorkspaceWatcher {
+    workspaceId: string;
+    root: string;
+    watcher: fs.FSWatcher;
+    timer: NodeJS.Timeout | null;
+    pending: Map<string, FileChangeKind>;
+}
+
+const DEBOUNCE_MS = 200;
+
+let active: ActiveWorkspaceWatcher | null = null;
+const scopes = new Map<string, { globs: string[]; matchers: RegExp[] }>();
+
+/**
+ * Fold a change into the pending batch: a file created and deleted within one window
+ * drops out, and a delete followed by a create (an atomic save) is a modification.
+ */
+export function mergeFileChange(pending: Map<string, FileChangeKind>, rel: string, kind: FileChangeKind): void {
+    const previous = pending.get(rel);
+    if (previous === 'created' && kind === 'deleted') pending.delete(rel);
+    else if (previous === 'created' && kind === 'modified') return;
+    else if (previous === 'deleted' && kind === 'created') pending.set(rel, 'modified');
+    else pending.set(rel, kind);
+}
+
+function inScope(workspaceId: string, rel: string): boolean {
+    const scope = scopes.get(workspaceId);
+    return !scope || scope.matchers.some((matcher) => matcher.test(rel));
+}
+
+/**
+ * Limit `workspace-file-changed` events of a workspace to paths matching any of `globs`
+ * (relative to the workspace root). An empty list watches everything again. Scopes last
+ * for the session and apply whether or not the workspace is currently open.
+ */
+export function watchPaths(workspaceId: string, globs: string[]): { success: boolean; globs: string[] } {
+    const cleaned = (globs || []).map((glob) => String(glob).trim()).filter(Boolean);
+    if (cleaned.length === 0) scopes.delete(workspaceId);
+    else scopes.set(workspaceId, { globs: cleaned, matchers: cleaned.map(globToRegExp) });
+    return { success: true, globs: cleaned };
+}
+
+export function stopWorkspaceWatcher(): void {
+    if (!active) return;
+    if (active.timer) clearTimeout(active.timer);
+    active.watcher.close();
+    active = null;
+}
+
+/**
+ * Watch the open workspace and emit `workspace-file-changed` with the batch of created,
+ * modified and deleted files once changes settle for 200ms, so file trees and editors can
+ * refresh after the agent edits files. Only one workspace is watched at a time; switching
+ * workspaces replaces the watcher. Failures are logged and leave the workspace unwatched.
+ */
+export function startWorkspaceWatcher(
+    getWebContents: () => WebContents | null,
+    workspaceId: string,
+    cwd: string,
+): void {
+    const root = expandTildePath(cwd);
+    if (active?.workspaceId === workspaceId && active.root === root) return;
+    stopWorkspaceWatcher();
+
+    let watcher: fs.FSWatcher;
+    try {
+        watcher = fs.watch(root, { recursive: true });
+    } catch (err) {
+        console.error(`[workspace-watcher] Cannot watch ${root}:`, err);
+        return;
+    }
+    watcher.unref();
+    const current: ActiveWorkspaceWatcher = { workspaceId, root, watcher, timer: null, pending: new Map() };
+    active = current;
+
+    const flush = () => {
+        current.timer = null;
+        if (current.pending.size === 0) return;
+        const changes = Array.from(current.pending, ([rel, kind]) => ({ path: rel, kind }));
+        current.pending.clear();
+        getWebContents()?.send('workspace-file-changed', { workspaceId, changes });
+    };
+
+    watcher.on('error', (err) => {
+        console.error(`[workspace-watcher] Watcher for ${root} failed:`, err);
+        if (active === current) stopWorkspaceWatcher();
+    });
+    watcher.on('change', (event, filename) => {
+        if (!filename) return;
+        const rel = filename.toString().split(path.sep).join('/');
+        if (rel.split('/').some((segment) => IGNORED_DIRS.has(segment)) || !inScope(workspaceId, rel)) return;
+
+        const kind: FileChangeKind = event === 'change'
+            ? 'modified'
+            : fs.existsSync(path.join(root, rel)) ? 'created' : 'deleted';
+        mergeFileChange(current.pending, rel, kind);
+        if (current.timer) clearTimeout(current.timer);
+        current.timer = setTimeout(flush, DEBOUNCE_MS);
+    });
+}
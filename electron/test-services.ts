@@ -26,6 +26,9 @@ function assert(condition: boolean, label: string) {
     }
 }
 
+/** Checks that await something; the summary waits for them. */
+const pendingTests: Promise<void>[] = [];
+
 function section(name: string) {
     console.log(`\n━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━━`);
     console.log(`  📦 ${name}`);
@@ -63,10 +66,16 @@ import {
     parseExtraArgs,
     cleanProgressText,
     defaultModels,
+    parseModelList,
     buildCodexExecArgs,
     StreamParseCache,
+    parseCodexEvent,
     generateId,
     nowIso,
+    resolveRuntimeConfig,
+    applyPersona,
+    applyNotes,
+    applyProjectSummary,
 } from './services/utils';
 
 assert(typeof homeDir() === 'string' && homeDir()!.length > 0, 'homeDir() returns path');
@@ -93,6 +102,13 @@ const models = defaultModels();
 assert(models.length > 0, 'defaultModels returns non-empty');
 assert(models[0].id === 'codex', 'defaultModels first is codex');
 
+const jsonModels = parseModelList('{"models":[{"slug":"gpt-5-codex","display_name":"GPT-5 Codex","description":"Coding"},{"id":"o3"}]}');
+assert(jsonModels.length === 2 && jsonModels[0].id === 'gpt-5-codex', 'parseModelList reads JSON models');
+assert(jsonModels[0].name === 'GPT-5 Codex' && jsonModels[1].name === 'o3', 'parseModelList uses display names');
+const plainModels = parseModelList('Available models:\n  gpt-5-codex   Coding model\n  o3\n');
+assert(plainModels.length === 2 && plainModels[1].id === 'o3', 'parseModelList falls back to plain lines');
+assert(plainModels[0].description === 'Coding model', 'parseModelList keeps plain descriptions');
+
 const id1 = generateId('test');
 const id2 = generateId('test');
 assert(id1 !== id2, 'generateId produces unique IDs');
@@ -130,10 +146,481 @@ const [histPrompt] = buildCodexExecArgs('question', testConfig, [
 assert(histPrompt.includes('[Previous conversation]'), 'buildCodexExecArgs includes history');
 assert(histPrompt.includes('[Current question]'), 'buildCodexExecArgs includes current question');
 
+// Session resume
+const [resumePrompt, , resumeArgs] = buildCodexExecArgs('next', testConfig, [
+    { role: 'user', content: 'Hi' },
+], 'thread-123');
+assert(resumePrompt === 'next', 'buildCodexExecArgs skips history when resuming');
+assert(resumeArgs.slice(-3).join(' ') === 'resume thread-123 next', 'buildCodexExecArgs resume args');
+
+// Agent backends
+import { agentBackend } from './services/agents';
+assert(agentBackend(testConfig).id === 'codex', 'agentBackend defaults to codex');
+const claudeConfig = { ...testConfig, model: 'o4-mini', cliOptions: { ...testConfig.cliOptions, agent: 'claude' as const } };
+const claude = agentBackend(claudeConfig);
+const [, , claudeArgs] = claude.buildArgs('fix it', claudeConfig, undefined, 'sess-1');
+assert(claude.bin === 'claude' && claudeArgs.includes('stream-json') && !claudeArgs.includes('--model'), 'claude args skip a non-Claude model');
+assert(claudeArgs.join(' ').includes('--permission-mode acceptEdits --resume sess-1') && claudeArgs[claudeArgs.length - 1] === 'fix it', 'claude args resume and keep the prompt last');
+const mapClaude = claude.createMapper();
+assert(mapClaude({ type: 'system', subtype: 'init', session_id: 'sess-2' })[0].thread_id === 'sess-2', 'claude init maps to thread.started');
+const claudeTool = mapClaude({ type: 'assistant', message: { id: 'm1', content: [{ type: 'text', text: 'Running' }, { type: 'tool_use', id: 't1', name: 'Bash', input: { command: 'ls' } }] } });
+assert(claudeTool[0].item.type === 'agent_message' && claudeTool[1].item.command === 'ls', 'claude assistant blocks map to a message and a command');
+const claudeResult = mapClaude({ type: 'user', message: { content: [{ type: 'tool_result', tool_use_id: 't1', content: 'a.txt' }] } })[0].item;
+assert(claudeResult.status === 'completed' && claudeResult.aggregated_output === 'a.txt' && claudeResult.exit_code === 0, 'claude tool results complete the command');
+const claudeUsage = mapClaude({ type: 'result', usage: { input_tokens: 10, cache_read_input_tokens: 90, output_tokens: 5 } })[0].usage;
+assert(claudeUsage.input_tokens === 100 && claudeUsage.cached_input_tokens === 90, 'claude usage counts cache reads as input');
+const geminiConfig = { ...testConfig, cliOptions: { ...testConfig.cliOptions, agent: 'gemini' as const } };
+const gemini = agentBackend(geminiConfig);
+const [geminiPrompt, , geminiArgs] = gemini.buildArgs('next', geminiConfig, [{ role: 'user', content: 'Hi' }]);
+assert(!gemini.supportsResume && geminiPrompt.includes('[Previous conversation]') && geminiArgs.slice(-2)[0] === '-p', 'gemini pastes history and passes the prompt with -p');
+const mapGemini = gemini.createMapper();
+mapGemini({ type: 'message', role: 'assistant', content: 'Hel', delta: true });
+assert(mapGemini({ type: 'message', role: 'assistant', content: 'lo', delta: true })[0].item.text === 'Hello', 'gemini deltas accumulate');
+const geminiTool = mapGemini({ type: 'tool_use', tool_id: 'g1', tool_name: 'write_file', parameters: { file_path: 'a.ts' } });
+assert(geminiTool[0].type === 'item.completed' && geminiTool[0].item.text === 'Hello' && geminiTool[1].item.type === 'file_change', 'gemini tool calls close the message and map edits to file changes');
+
+// Codex protocol adapter
+import { createCodexProtocolAdapter, describeCodexVersion, describeMismatch, parseCodexVersion, protocolForVersion } from './services/protocol';
+assert(parseCodexVersion('codex-cli 0.46.0\n') === '0.46.0' && parseCodexVersion('codex') === null, 'parseCodexVersion reads the version');
+assert(protocolForVersion('0.39.0') === 'exec-msg' && protocolForVersion('0.46.0') === 'exec-items' && protocolForVersion(null) === 'exec-items', 'protocolForVersion picks the event format');
+const oldCli = describeCodexVersion('0.39.0', '0.48.1');
+assert(oldCli.installed && oldCli.updateAvailable && !oldCli.supported && !!oldCli.warning?.includes('0.44.0'), 'describeCodexVersion warns about a CLI older than the minimum');
+const currentCli = describeCodexVersion('0.48.1', '0.48.1');
+assert(currentCli.supported && !currentCli.updateAvailable && !currentCli.warning, 'describeCodexVersion accepts the latest CLI');
+assert(!describeCodexVersion(null, '0.48.1').installed && !describeCodexVersion('0.46.0', null).updateAvailable, 'describeCodexVersion handles a missing CLI or npm');
+
+// Codex install methods
+import { assetDigest, binaryTarget, chooseInstallMethod, packageManagerCommand, releaseAssetFor } from './services/installer';
+assert(binaryTarget('darwin', 'arm64') === 'aarch64-apple-darwin' && binaryTarget('linux', 'x64') === 'x86_64-unknown-linux-musl' && binaryTarget('freebsd', 'x64') === null, 'binaryTarget maps the platform to a release target');
+assert(chooseInstallMethod('brew', ['npm', 'brew']) === 'brew' && chooseInstallMethod('bun', ['pnpm', 'binary']) === 'pnpm' && chooseInstallMethod('auto', []) === null, 'chooseInstallMethod prefers the chosen method when available');
+assert(packageManagerCommand('pnpm', true)[1].join(' ') === 'add -g @openai/codex@latest' && packageManagerCommand('brew', false)[1].join(' ') === 'install codex', 'packageManagerCommand builds install and upgrade commands');
+const releaseAssets = [
+    { name: 'codex-x86_64-unknown-linux-musl.tar.gz', browser_download_url: 'https://example.invalid/a', digest: `sha256:${'AB'.repeat(32)}` },
+    { name: 'codex-x86_64-pc-windows-msvc.exe.zip', browser_download_url: 'https://example.invalid/b' },
+];
+assert(releaseAssetFor(releaseAssets, 'x86_64-pc-windows-msvc')?.name === 'codex-x86_64-pc-windows-msvc.exe.zip' && releaseAssetFor(releaseAssets, 'aarch64-apple-darwin') === null, 'releaseAssetFor picks the archive for the target');
+assert(assetDigest(releaseAssets[0]) === 'ab'.repeat(32) && assetDigest(releaseAssets[1]) === null, 'assetDigest reads the published sha256');
+const currentInfo = { version: '0.46.0', protocol: 'exec-items' as const, newestKnownVersion: '0.50.0' };
+const mapCurrent = createCodexProtocolAdapter(currentInfo);
+const passThrough = mapCurrent({ type: 'item.completed', item: { id: 'i1', type: 'agent_message', text: 'Hi' } });
+assert(passThrough.length === 1 && passThrough[0].item.text === 'Hi', 'current events pass through');
+assert(mapCurrent({ type: 'turn.completed', usage: {} }).length === 1, 'a readable turn reports no mismatch');
+const renamed = createCodexProtocolAdapter(currentInfo)({ type: 'item.completed', item: { id: 'i2', item_type: 'assistant_message', text: 'Hey' } });
+assert(renamed.length === 1 && renamed[0].item.type === 'agent_message', 'item_type and assistant_message are mapped');
+const mapLegacy = createCodexProtocolAdapter({ version: null, protocol: 'exec-items', newestKnownVersion: '0.50.0' });
+assert(mapLegacy({ id: '0', msg: { type: 'session_configured', session_id: 'sess-old' } })[0].thread_id === 'sess-old', 'legacy session_configured maps to thread.started');
+assert(mapLegacy({ id: '1', msg: { type: 'agent_message', message: 'Old' } })[0].item.text === 'Old', 'legacy agent_message maps to an item');
+mapLegacy({ id: '2', msg: { type: 'exec_command_begin', call_id: 'c1', command: ['ls', '-a'] } });
+const legacyEnd = mapLegacy({ id: '3', msg: { type: 'exec_command_end', call_id: 'c1', stdout: 'a', exit_code: 0 } })[0].item;
+assert(legacyEnd.command === 'ls -a' && legacyEnd.status === 'completed' && legacyEnd.aggregated_output === 'a', 'legacy exec events map to a command item');
+mapLegacy({ id: '4', msg: { type: 'token_count', info: { total_token_usage: { input_tokens: 7, output_tokens: 2 } } } });
+assert(mapLegacy({ id: '5', msg: { type: 'task_complete' } })[0].usage.input_tokens === 7, 'legacy task_complete carries the token count');
+const mapNewer = createCodexProtocolAdapter({ version: '0.99.0', protocol: 'exec-items', newestKnownVersion: '0.50.0' });
+const lost = mapNewer({ type: 'item.completed', item: { id: 'i3', type: 'agent_message', content: 'moved' } });
+assert(lost[1]?.type === 'protocol.mismatch' && lost[1].mismatch.advice === 'update-app', 'a message without text reports a mismatch for a newer CLI');
+assert(mapNewer({ type: 'item.completed', item: { id: 'i4', type: 'agent_message', content: 'again' } }).length === 1, 'a mismatch is reported once per turn');
+const mapUnknown = createCodexProtocolAdapter(currentInfo);
+assert(mapUnknown({ type: 'response.delta', text: 'x' }).length === 1, 'an unknown event alone is not reported yet');
+const unknownEnd = mapUnknown({ type: 'turn.completed', usage: {} });
+assert(unknownEnd[1]?.mismatch.details.join() === 'unknown event "response.delta"' && unknownEnd[1].mismatch.advice === 'update-either', 'a turn without text and with unknown events reports them');
+assert(describeMismatch('0.30.0', 'exec-msg', ['x']).advice === 'update-cli', 'describeMismatch advises updating an old CLI');
+
+// Per-conversation overrides
+const overridden = resolveRuntimeConfig(testConfig, { model: 'o3', sandbox: 'read-only' });
+assert(overridden.model === 'o3', 'resolveRuntimeConfig overrides model');
+assert(overridden.cliOptions.sandbox === 'read-only', 'resolveRuntimeConfig overrides sandbox');
+assert(overridden.cliOptions.skipGitRepoCheck === true, 'resolveRuntimeConfig keeps other options');
+assert(resolveRuntimeConfig(testConfig) === testConfig, 'resolveRuntimeConfig without overrides');
+const layered = resolveRuntimeConfig(testConfig, { sandbox: 'workspace-write' }, { sandbox: 'read-only', model: 'o3', yoloMode: true });
+assert(layered.model === 'o3' && layered.yoloMode === true, 'resolveRuntimeConfig applies workspace options');
+assert(layered.cliOptions.sandbox === 'workspace-write', 'resolveRuntimeConfig lets conversation overrides win');
+assert(applyPersona('hi', 'Reviewer').startsWith('[Agent persona]'), 'applyPersona prefixes persona');
+assert(applyPersona('hi', '  ') === 'hi', 'applyPersona ignores blank persona');
+assert(applyNotes('hi', 'Use v2 API').startsWith('[Conversation notes]\nUse v2 API'), 'applyNotes prefixes notes');
+assert(applyProjectSummary('hi', 'A CLI.') === '[Project summary]\nA CLI.\n\nhi' && applyProjectSummary('hi') === 'hi', 'applyProjectSummary prefixes the summary');
+
 // StreamParseCache
 const cache = new StreamParseCache();
 assert(cache.itemTextById.size === 0, 'StreamParseCache initializes empty');
 
+// parseCodexEvent token usage
+const sentEvents: Array<[string, any]> = [];
+const usageWebContents = { send: (channel: string, payload: any) => sentEvents.push([channel, payload]) } as any;
+const usageEvent = { type: 'turn.completed', usage: { input_tokens: 120, cached_input_tokens: 20, output_tokens: 30 } };
+parseCodexEvent(usageWebContents, 'c1', usageEvent, cache);
+assert(cache.usage?.totalTokens === 150, 'parseCodexEvent records turn usage');
+assert(sentEvents.some(([ch, p]) => ch === 'codex-usage' && p.cid === 'c1' && p.cachedTokens === 20), 'parseCodexEvent emits codex-usage');
+parseCodexEvent(usageWebContents, 'c1', { type: 'item.updated', item: { id: 'm1', type: 'agent_message', text: 'Partial' } }, cache);
+parseCodexEvent(usageWebContents, 'c1', { type: 'item.updated', item: { id: 'm1', type: 'agent_message', text: 'Partial answer' } }, cache);
+assert(cache.agentText === 'Partial answer', 'parseCodexEvent accumulates agent text for interrupted turns');
+const cappedCache = new StreamParseCache(10);
+parseCodexEvent(usageWebContents, 'c1', { type: 'item.updated', item: { id: 'm2', type: 'agent_message', text: 'A long partial answer' } }, cappedCache);
+assert(cappedCache.agentText === 'A long par' && cappedCache.agentTextTruncated, 'StreamParseCache caps agent text');
+parseCodexEvent(usageWebContents, 'c1', { type: 'item.streaming', item: { type: 'reasoning', delta: { text: 'Thinking' } } }, cache);
+parseCodexEvent(usageWebContents, 'c1', { type: 'item.started', item: { id: 'cmd1', type: 'command_execution', command: 'ls', status: 'in_progress' } }, cache);
+parseCodexEvent(usageWebContents, 'c1', { type: 'item.completed', item: { id: 'cmd1', type: 'command_execution', command: 'ls', status: 'completed', aggregated_output: 'a.txt', exit_code: 0 } }, cache);
+assert(cache.thinkingText === 'Thinking', 'StreamParseCache keeps reasoning text');
+assert(cache.toolCalls.size === 1 && cache.toolCalls.get('cmd1')!.status === 'done' && cache.toolCalls.get('cmd1')!.exitCode === 0, 'StreamParseCache keeps the latest tool call state');
+
+// outdated dependency parsers
+import { parseNpmOutdated, parseCargoOutdated, parsePipOutdated, buildUpgradePrompt } from './services/dependencies';
+const npmDeps = parseNpmOutdated('{"react":{"current":"18.2.0","wanted":"18.3.1","latest":"19.0.0","type":"dependencies"}}');
+assert(npmDeps[0].name === 'react' && npmDeps[0].latest === '19.0.0', 'parseNpmOutdated');
+const cargoDeps = parseCargoOutdated('{"crate_name":"app","dependencies":[{"name":"serde","project":"1.0.100","compat":"1.0.200","latest":"1.0.200","kind":"Normal"}]}');
+assert(cargoDeps[0].wanted === '1.0.200', 'parseCargoOutdated');
+assert(parsePipOutdated('[{"name":"requests","version":"2.0.0","latest_version":"2.32.0"}]')[0].current === '2.0.0', 'parsePipOutdated');
+assert(buildUpgradePrompt(npmDeps).includes('react: 18.2.0 -> 19.0.0'), 'buildUpgradePrompt lists upgrades');
+
+// git parsers
+import { parseGitStatus, parseUnifiedDiff, parseGitLog } from './services/git';
+const gitStatusParsed = parseGitStatus('## main...origin/main\0M  staged.ts\0 M edited.ts\0R  new.ts\0old.ts\0?? notes.md\0');
+assert(gitStatusParsed.branch === 'main', 'parseGitStatus reads branch');
+assert(gitStatusParsed.staged.length === 2 && gitStatusParsed.staged[1].oldPath === 'old.ts', 'parseGitStatus staged + rename');
+assert(gitStatusParsed.unstaged[0].path === 'edited.ts' && gitStatusParsed.untracked[0] === 'notes.md', 'parseGitStatus unstaged/untracked');
+const parsedDiff = parseUnifiedDiff('diff --git a/x.ts b/x.ts\n--- a/x.ts\n+++ b/x.ts\n@@ -1,2 +1,2 @@\n keep\n-old\n+new\n', false);
+assert(parsedDiff[0].hunks[0].lines.length === 3 && parsedDiff[0].hunks[0].lines[2].newLine === 2, 'parseUnifiedDiff hunks');
+assert(parseGitLog('abc\x1fa\x1fDev\x1fd@x\x1f100\x1fInit\x1e')[0].subject === 'Init', 'parseGitLog');
+
+// GitHub
+import { parseGitHubRemote, prBranchName } from './services/github';
+assert(parseGitHubRemote('https://github.com/octo/app.git')?.repo === 'app', 'parseGitHubRemote https');
+assert(parseGitHubRemote('git@github.com:octo/app.git')?.owner === 'octo', 'parseGitHubRemote scp-style ssh');
+assert(parseGitHubRemote('ssh://git@github.com:22/octo/app')?.repo === 'app', 'parseGitHubRemote ssh url with port');
+assert(parseGitHubRemote('/srv/repos/app.git') === null, 'parseGitHubRemote rejects local paths');
+assert(prBranchName('Fix: login redirect!', 'ab12') === 'codex/fix-login-redirect-ab12', 'prBranchName slugs the title');
+assert(prBranchName('???', 'ab12') === 'codex/changes-ab12', 'prBranchName falls back for empty slugs');
+
+// release version bump
+import { bumpVersionText } from './services/release';
+assert(bumpVersionText('package.json', '{\n  "name": "x",\n  "version": "1.0.0"\n}', '1.1.0')!.includes('"version": "1.1.0"'), 'bumpVersionText updates package.json');
+assert(bumpVersionText('Cargo.toml', '[package]\nname = "x"\nversion = "0.1.0"\n', '0.2.0') === '[package]\nname = "x"\nversion = "0.2.0"\n', 'bumpVersionText updates Cargo.toml');
+assert(bumpVersionText('README.md', 'version 1', '2.0.0') === null, 'bumpVersionText rejects unknown files');
+
+// monorepo manifests
+import { parseCargoWorkspace, parsePnpmWorkspace } from './services/packages';
+const cargoWorkspace = parseCargoWorkspace('[workspace]\nmembers = [\n  "crates/*",\n  "tools/cli",\n]\nexclude = ["crates/old"]\n\n[workspace.package]\nversion = "1.0.0"\n');
+assert(cargoWorkspace.members.join(',') === 'crates/*,tools/cli', 'parseCargoWorkspace reads members');
+assert(cargoWorkspace.exclude[0] === 'crates/old', 'parseCargoWorkspace reads exclude');
+assert(parsePnpmWorkspace("packages:\n  - 'apps/*'\n  - packages/*\ncatalog:\n  react: 18\n").join(',') === 'apps/*,packages/*', 'parsePnpmWorkspace');
+
+// codex config profiles
+import { profilesFromConfig } from './services/config';
+const codexProfiles = profilesFromConfig('model = "o3"\n[profiles.fast]\nmodel = "gpt-4.1" # quick\nsandbox_mode = "read-only"\n[profiles."deep work"]\nmodel_reasoning_effort = "high"\n');
+assert(codexProfiles.map((p) => p.name).join(',') === 'deep work,fast', 'profilesFromConfig lists profiles');
+assert(codexProfiles[1].model === 'gpt-4.1' && codexProfiles[1].sandbox === 'read-only', 'profilesFromConfig reads profile settings');
+
+import { mcpServersFromConfig } from './services/config';
+const mcpServers = mcpServersFromConfig('[mcp_servers.docs]\ncommand = "npx"\nargs = ["-y", "docs-mcp"]\n[mcp_servers.docs.env]\nTOKEN = "x"\n[mcp_servers.remote]\nurl = "https://mcp.example.com/mcp"\nenabled = false\n');
+assert(mcpServers.length === 2 && mcpServers[0].args.join(' ') === '-y docs-mcp', 'mcpServersFromConfig reads stdio servers');
+assert(mcpServers[0].env.TOKEN === 'x' && mcpServers[1].url !== null && !mcpServers[1].enabled, 'mcpServersFromConfig reads env, url and enabled');
+
+// image attachments
+import { decodeBase64Image } from './services/attachments';
+const [, , imageArgs] = buildCodexExecArgs('look', testConfig, undefined, undefined, ['/tmp/a.png', '/tmp/b.jpg']);
+assert(imageArgs.slice(2, 6).join(' ') === '-i /tmp/a.png -i /tmp/b.jpg', 'buildCodexExecArgs adds -i for images');
+assert(imageArgs[imageArgs.length - 1] === 'look', 'buildCodexExecArgs keeps the prompt last with images');
+const decodedImage = decodeBase64Image('data:image/png;base64,iVBORw0KGgo=');
+assert(decodedImage.mimeType === 'image/png' && decodedImage.bytes[1] === 0x50, 'decodeBase64Image reads data URLs');
+let rejectedImage = false;
+try { decodeBase64Image('aGVsbG8=', 'text/plain'); } catch { rejectedImage = true; }
+assert(rejectedImage, 'decodeBase64Image rejects non-images');
+
+// screenshot regions
+import { scaleRegion } from './services/screenshot';
+const scaledRegion = scaleRegion({ x: 10, y: 20, width: 100, height: 50 }, { width: 1440, height: 900 }, 2);
+assert(scaledRegion!.x === 20 && scaledRegion!.width === 200 && scaledRegion!.height === 100, 'scaleRegion scales to pixels');
+assert(scaleRegion({ x: 1400, y: 0, width: 100, height: 10 }, { width: 1440, height: 900 }, 1)!.width === 40, 'scaleRegion clamps to the display');
+assert(scaleRegion({ x: 2000, y: 0, width: 10, height: 10 }, { width: 1440, height: 900 }, 1) === null, 'scaleRegion rejects off-screen regions');
+
+// themed html export
+import { toHtml } from './services/conversation-export';
+import { THEME_PALETTES } from './services/themes';
+const htmlExport = toHtml({
+    version: 1,
+    exportedAt: '2025-01-01T00:00:00.000Z',
+    conversation: { id: 'c1', title: 'Fix <bug>', createdAt: '0', updatedAt: '0' },
+    messages: [{ id: 'm1', conversationId: 'c1', role: 'user', content: 'a < b', timestamp: '' }],
+}, 'colorblind-safe');
+assert(htmlExport.includes('Fix &#60;bug&#62;') && htmlExport.includes('a &#60; b'), 'toHtml escapes content');
+assert(htmlExport.includes(THEME_PALETTES['colorblind-safe'].accent), 'toHtml uses the theme palette');
+
+// mcp payload scrubbing
+import { scrubSecrets, capPayload } from './services/mcp';
+const scrubbed = scrubSecrets({ query: 'x', apiKey: 'abc', headers: { Authorization: 'Bearer abcdefghijkl' }, note: 'use sk-abcdefghijklmnopqrstu' }) as any;
+assert(scrubbed.apiKey === '[REDACTED]' && scrubbed.headers.Authorization === '[REDACTED]', 'scrubSecrets redacts secret keys');
+assert(scrubbed.query === 'x' && scrubbed.note === 'use [REDACTED]', 'scrubSecrets redacts secrets inside strings');
+const cappedPayload = capPayload({ data: 'a'.repeat(70 * 1024) });
+assert(cappedPayload.truncated && cappedPayload.bytes > 70 * 1024 && cappedPayload.text.endsWith('(truncated)'), 'capPayload truncates large payloads');
+import { remoteEndpoint } from './services/mcp';
+assert(remoteEndpoint('https://mcp.example.com/mcp').port === 443 && remoteEndpoint('http://localhost:8931/sse').port === 8931, 'remoteEndpoint picks ports');
+
+// turn cost estimate
+import { estimateTokens, estimateImageTokens, estimateCostRange } from './services/usage';
+assert(estimateTokens('') === 0 && estimateTokens('abcdefgh') === 2, 'estimateTokens counts ~4 chars per token');
+assert(estimateTokens('안녕하세요') === 5, 'estimateTokens counts Hangul per character');
+assert(estimateImageTokens(10) === 255 && estimateImageTokens(50 * 1024 * 1024) === 1105, 'estimateImageTokens clamps tiles');
+const costRange = estimateCostRange(10_000, [{ promptTokens: 90_000, cachedTokens: 0, completionTokens: 6000, totalTokens: 96_000 }], { prompt: 1, completion: 10 });
+assert(costRange.low.costUsd === 0.013, 'estimateCostRange prices the low end');
+assert(costRange.high.promptTokens === 100_000 && costRange.high.completionTokens === 6000, 'estimateCostRange uses recent turns for the high end');
+assert(estimateCostRange(1000, [], undefined).high.costUsd === null, 'estimateCostRange without pricing');
+
+// turns
+import { ensureSchema } from './services/db';
+import { createTurn, finishTurn, getTurns, linkMessageToTurn, markTurnRunning, recordTurnToolCall } from './services/turns';
+const turnsDb = new Database(':memory:');
+ensureSchema(turnsDb);
+dbCreateWorkspace(turnsDb, 'tw', 'Turns', '/tmp/turns');
+dbCreateConversation(turnsDb, 'tc', 'tw', 'Turns');
+dbCreateMessage(turnsDb, { id: 'old-q', conversationId: 'tc', role: 'user', content: 'legacy question', timestamp: '2024-01-01T00:00:00Z' });
+dbCreateMessage(turnsDb, { id: 'old-a', conversationId: 'tc', role: 'assistant', content: 'legacy answer', timestamp: '2024-01-01T00:00:05Z' });
+turnsDb.exec('DROP TABLE turn_tool_calls; DROP TABLE turns;');
+turnsDb.pragma('user_version = 0');
+ensureSchema(turnsDb);
+const legacyTurns = getTurns(turnsDb, 'tc');
+assert(legacyTurns.length === 1 && legacyTurns[0].userMessageId === 'old-q' && legacyTurns[0].assistantMessageId === 'old-a', 'migration builds turns from existing messages');
+dbCreateMessage(turnsDb, { id: 'new-q', conversationId: 'tc', role: 'user', content: 'run tests', timestamp: new Date().toISOString() });
+createTurn(turnsDb, 'turn-new', 'tc', 'run tests', 'running');
+markTurnRunning(turnsDb, 'turn-new', 'o4-mini');
+recordTurnToolCall(turnsDb, 'turn-new', { id: 'i1', type: 'command_execution', command: 'npm test', status: 'in_progress' }, false);
+recordTurnToolCall(turnsDb, 'turn-new', { id: 'i1', type: 'command_execution', command: 'npm test', status: 'completed' }, true);
+recordTurnToolCall(turnsDb, 'turn-new', { id: 'i2', type: 'agent_message', text: 'done' }, true);
+finishTurn(turnsDb, 'turn-new', { status: 'completed', exitCode: 0, usage: { promptTokens: 100, cachedTokens: 0, completionTokens: 20, totalTokens: 120 } });
+const reply = dbCreateMessage(turnsDb, { id: 'new-a', conversationId: 'tc', role: 'assistant', content: 'All green', timestamp: new Date().toISOString() });
+linkMessageToTurn(turnsDb, reply);
+const liveTurn = getTurns(turnsDb, 'tc')[1];
+assert(liveTurn.userMessageId === 'new-q' && liveTurn.assistantMessageId === 'new-a', 'turns link their prompt and reply');
+assert(liveTurn.status === 'completed' && liveTurn.model === 'o4-mini' && liveTurn.usage?.totalTokens === 120, 'finishTurn records status and usage');
+assert(liveTurn.toolCalls.length === 1 && liveTurn.toolCalls[0].status === 'completed' && liveTurn.toolCalls[0].name === 'npm test', 'recordTurnToolCall tracks tool items only');
+
+// turn events
+import { dbGetTurnEvents, dbRecordTurnEvent } from './services/db';
+dbRecordTurnEvent(turnsDb, 'turn-new', 'tc', { type: 'item.started', item: { id: 'i1', type: 'command_execution', command: 'npm test' } });
+dbRecordTurnEvent(turnsDb, 'turn-new', 'tc', { type: 'item.updated', item: { id: 'i1', type: 'command_execution', aggregated_output: 'PASS' } });
+dbRecordTurnEvent(turnsDb, 'turn-new', 'tc', { type: 'item.completed', item: { id: 'r1', type: 'Reasoning', text: 'checking' } });
+dbRecordTurnEvent(turnsDb, 'turn-new', 'tc', { type: 'item.completed', item: { id: 'big', type: 'command_execution', aggregated_output: 'x'.repeat(300_000) } });
+dbRecordTurnEvent(turnsDb, 'turn-new', 'tc', { type: 'turn.completed', usage: { input_tokens: 100 } });
+const turnEvents = dbGetTurnEvents(turnsDb, 'tc', 'turn-new');
+assert(turnEvents.map((e) => e.seq).join() === '1,2,3,4' && turnEvents.map((e) => e.type).join() === 'item.started,item.completed,item.completed,turn.completed', 'dbRecordTurnEvent keeps events in order and skips streaming updates');
+assert(turnEvents[0].itemId === 'i1' && turnEvents[1].itemType === 'reasoning' && (turnEvents[0].event as any).item.command === 'npm test', 'dbGetTurnEvents returns the item and the event');
+assert((turnEvents[2].event as any).truncated === true && turnEvents[2].itemId === 'big', 'oversized events are stored without their content');
+assert(dbGetTurnEvents(turnsDb, 'other', 'turn-new').length === 0, 'dbGetTurnEvents is scoped to the conversation');
+dbRecordTurnEvent(turnsDb, 'turn-missing', 'tc', { type: 'item.started', item: { id: 'x', type: 'agent_message' } });
+assert(dbGetTurnEvents(turnsDb, 'tc', 'turn-missing').length === 0, 'dbRecordTurnEvent needs a recorded turn');
+
+// turn outcomes
+import { rateTurn } from './services/turns';
+import { savePreset, applyPreset } from './services/presets';
+import { getOutcomeStats } from './services/usage';
+assert(rateTurn(turnsDb, 'turn-new', 'great' as any).success === false, 'rateTurn rejects unknown outcomes');
+const ratedTurn = rateTurn(turnsDb, 'turn-new', 'accepted', '  ship it ');
+assert(ratedTurn.success && ratedTurn.turn!.outcome === 'accepted' && ratedTurn.turn!.outcomeComment === 'ship it', 'rateTurn stores the outcome with the turn');
+const fastPreset = savePreset(turnsDb, { name: 'Fast', options: { model: 'o4-mini' } });
+applyPreset(turnsDb, fastPreset.id, 'tc');
+createTurn(turnsDb, 'turn-preset', 'tc', 'again', 'running');
+assert(rateTurn(turnsDb, 'turn-preset', 'rework').error === 'The turn has not finished', 'rateTurn waits for the turn to finish');
+markTurnRunning(turnsDb, 'turn-preset', 'o4-mini');
+finishTurn(turnsDb, 'turn-preset', { status: 'completed', exitCode: 0 });
+rateTurn(turnsDb, 'turn-preset', 'rework');
+const outcomeStats = getOutcomeStats(turnsDb, 'tw', 'all');
+const presetBucket = outcomeStats.find((b) => b.presetId === fastPreset.id)!;
+assert(presetBucket.presetName === 'Fast' && presetBucket.rework === 1 && presetBucket.acceptanceRate === 0, 'getOutcomeStats groups by preset');
+assert(outcomeStats.find((b) => b.model === 'o4-mini' && b.presetId === null)!.acceptanceRate === 1, 'getOutcomeStats groups by model');
+assert(rateTurn(turnsDb, 'turn-new', null).turn!.outcome === null, 'rateTurn with null clears the rating');
+turnsDb.close();
+
+// history trimming
+import { getHistorySettings, refreshHistorySummary, setHistorySettings, storedHistory, trimHistory } from './services/history';
+const longHistory = Array.from({ length: 10 }, (_, i) => ({ role: i % 2 ? 'assistant' : 'user', content: `${i}`.padEnd(400, '.') }));
+assert(trimHistory(longHistory.slice(0, 3), { tokenBudget: 500, keepRecent: 2 }).messages.length === 3, 'trimHistory keeps a history that fits');
+const overBudget = trimHistory(longHistory, { tokenBudget: 500, keepRecent: 2 });
+assert(overBudget.messages.length === 4 && overBudget.messages[0].content.startsWith('6') && overBudget.dropped === 6 && overBudget.tokens <= 500, 'trimHistory keeps the newest messages within the budget');
+assert(trimHistory(longHistory, { tokenBudget: 500, keepRecent: 6 }).messages.length === 6, 'trimHistory always keeps keepRecent messages');
+const withSummary = trimHistory(longHistory, { tokenBudget: 500, keepRecent: 2 }, { summary: 'Set up the repo.', coveredMessages: 5, updatedAt: '' });
+assert(withSummary.messages[0].role === 'system' && withSummary.messages[0].content.endsWith('Set up the repo.') && withSummary.summarized === 5 && withSummary.dropped === 1, 'trimHistory puts the summary in place of older messages');
+assert(trimHistory(longHistory, { tokenBudget: 500, keepRecent: 2 }, { summary: 'stale', coveredMessages: 20, updatedAt: '' }).summarized === 0, 'trimHistory ignores a summary of deleted messages');
+const historyDb = new Database(':memory:');
+ensureSchema(historyDb);
+dbCreateWorkspace(historyDb, 'hw', 'History', '/tmp/history');
+dbCreateConversation(historyDb, 'hc', 'hw', 'History');
+dbCreateMessage(historyDb, { id: 'h1', conversationId: 'hc', role: 'user', content: 'first', timestamp: '2024-01-01T00:00:00Z' });
+dbCreateMessage(historyDb, { id: 'h2', conversationId: 'hc', role: 'user', content: 'next question', timestamp: '2024-01-01T00:00:05Z' });
+assert(storedHistory(historyDb, 'hc', 'next question').map((m) => m.content).join() === 'first', 'storedHistory leaves out the prompt being sent');
+assert(storedHistory(historyDb, 'missing', 'q', [{ role: 'user', content: 'x' }]).length === 1, 'storedHistory falls back to the given history');
+assert(setHistorySettings(historyDb, { tokenBudget: 10, keepRecent: 99 }).tokenBudget === 500 && getHistorySettings(historyDb).keepRecent === 50, 'setHistorySettings clamps its values');
+setHistorySettings(historyDb, { keepRecent: 2, summaryModel: 'cheap-model' });
+const summaryRuns: Array<{ prompt: string; model: string; sandbox: string }> = [];
+const historyState = { db: historyDb, config: testConfig } as AppState;
+void refreshHistorySummary(historyState, 'hc', longHistory.slice(0, 5), (prompt, cfg) => {
+    summaryRuns.push({ prompt, model: cfg.model, sandbox: cfg.cliOptions.sandbox });
+    return new Promise(() => { });
+});
+assert(summaryRuns.length === 1 && summaryRuns[0].model === 'cheap-model' && summaryRuns[0].sandbox === 'read-only', 'refreshHistorySummary runs the summary model read-only');
+assert(summaryRuns[0].prompt.includes('User: 2') && !summaryRuns[0].prompt.includes('Assistant: 3'), 'refreshHistorySummary summarizes all but the recent messages');
+void refreshHistorySummary(historyState, 'hc', longHistory.slice(0, 5), () => Promise.reject(new Error('called twice')));
+assert(summaryRuns.length === 1, 'refreshHistorySummary does not start a second run for a conversation');
+
+// file diffs
+import { diffLines, extractFileDiffs, parseUnifiedDiff as parseFileDiff } from './services/diffs';
+const fileDiffParsed = parseFileDiff('--- a/x.ts\n+++ b/x.ts\n@@ -1,3 +1,3 @@\n a\n-b\n+B\n c\n@@ -10 +10,2 @@\n z\n+zz\n');
+assert(fileDiffParsed.hunks.length === 2 && fileDiffParsed.added === 2 && fileDiffParsed.removed === 1, 'parseUnifiedDiff counts lines per hunk');
+assert(fileDiffParsed.hunks[1].oldLines === 1 && fileDiffParsed.hunks[1].newLines === 2, 'parseUnifiedDiff defaults omitted hunk lengths to 1');
+const lineDiff = diffLines('1\n2\n3\n4\n5\n6\n7\n8\n9\n10\n', '1\n2\nthree\n4\n5\n6\n7\n8\n9\n10\n11\n');
+assert(lineDiff.added === 2 && lineDiff.removed === 1, 'diffLines counts added and removed lines');
+assert(lineDiff.hunks.length === 2 && lineDiff.hunks[0].oldStart === 1 && lineDiff.hunks[0].lines.includes('-3') && lineDiff.hunks[0].lines.includes('+three'), 'diffLines builds hunks with context');
+const addDiff = diffLines('', 'new\nfile\n');
+assert(addDiff.hunks[0].oldStart === 0 && addDiff.hunks[0].oldLines === 0 && addDiff.added === 2, 'diffLines on a new file');
+const mapDiffs = extractFileDiffs({ 'a.txt': { add: { content: 'x\ny\n' } }, 'b.txt': { update: { unified_diff: '@@ -1 +1 @@\n-old\n+new\n', move_path: 'c.txt' } } }, '/nonexistent');
+assert(mapDiffs.length === 2 && mapDiffs[0].kind === 'add' && mapDiffs[0].added === 2, 'extractFileDiffs reads added content');
+assert(mapDiffs[1].movePath === 'c.txt' && mapDiffs[1].added === 1 && mapDiffs[1].removed === 1, 'extractFileDiffs parses update diffs');
+const snapshotDiffs = extractFileDiffs([{ path: 'gone.txt', kind: 'delete' }], '/nonexistent', () => 'a\nb\n');
+assert(snapshotDiffs[0].removed === 2 && snapshotDiffs[0].added === 0, 'extractFileDiffs diffs against the snapshot original');
+
+// approval responses
+import { buildApprovalResponse, tryExtractApprovalRequest } from './services/utils';
+const rpcApproval = tryExtractApprovalRequest({ id: 7, method: 'execCommandApproval', params: { command: ['ls'] } })!;
+assert(rpcApproval.rpcId === 7 && rpcApproval.requestId === '7', 'tryExtractApprovalRequest keeps the JSON-RPC id');
+assert(buildApprovalResponse(rpcApproval, 'approve_for_session') === '{"id":7,"result":{"decision":"approved_for_session"}}', 'buildApprovalResponse answers legacy methods');
+const v2Approval = tryExtractApprovalRequest({ jsonrpc: '2.0', id: 'a1', method: 'item/commandExecution/requestApproval', params: {} })!;
+assert(buildApprovalResponse(v2Approval, 'deny', 'use npm ci') === '{"jsonrpc":"2.0","id":"a1","result":{"decision":"decline","reason":"use npm ci"}}', 'buildApprovalResponse answers v2 methods with a reason');
+const plainApproval = tryExtractApprovalRequest({ type: 'exec_approval_request', request_id: 'r1' })!;
+assert(JSON.parse(buildApprovalResponse(plainApproval, 'approve')).approved === true, 'buildApprovalResponse keeps the plain event shape');
+
+// notifications
+import { describeNotification } from './services/notifications';
+const notifyPrefs = { streamEnd: true, streamError: true, approvalRequest: false, onlyWhenUnfocused: true };
+assert(describeNotification(notifyPrefs, 'codex-stream-end', { cid: 'c1' }, 'Chat')?.body === 'Turn finished', 'describeNotification for finished turns');
+assert(describeNotification(notifyPrefs, 'codex-stream-end', { cid: 'c1', cancelled: true }, 'Chat') === null, 'describeNotification skips cancelled turns');
+assert(describeNotification(notifyPrefs, 'codex-approval-request', { cid: 'c1', title: 'rm -rf' }, 'Chat') === null, 'describeNotification honours prefs');
+assert(describeNotification(notifyPrefs, 'codex-stream-error', { data: 'x'.repeat(500) }, 'Chat')!.body.length === 200, 'describeNotification truncates long errors');
+
+// Notification routing
+import { routeSinks, saveNotificationRoute, getNotificationRoutes, deleteNotificationRoute } from './services/notifications';
+const slackSink = { type: 'slack' as const, url: 'https://hooks.slack.com/services/x' };
+const hookSink = { type: 'webhook' as const, url: 'https://example.com/hook' };
+const routes = [
+    { id: 'r1', name: 'errors', workspaceId: 'ws1', events: ['error' as const], sinks: [slackSink], enabled: true },
+    { id: 'r2', name: 'all', workspaceId: '*', events: ['error' as const, 'completion' as const], sinks: [slackSink, hookSink], enabled: true },
+    { id: 'r3', name: 'off', workspaceId: 'ws1', events: ['approval' as const], sinks: [hookSink], enabled: false },
+];
+assert(routeSinks(routes, 'ws1', 'error').length === 2, 'routeSinks dedupes sinks shared by routes');
+assert(routeSinks(routes, 'ws2', 'completion').length === 2, 'routeSinks applies * routes to every workspace');
+assert(routeSinks(routes, 'ws1', 'approval').length === 0, 'routeSinks skips disabled routes');
+const routesDb = new Database(':memory:');
+ensureSchema(routesDb);
+assert(!saveNotificationRoute(routesDb, { name: 'bad', workspaceId: 'ws1', events: ['error'], sinks: [{ type: 'slack', url: 'not a url' }] }).success, 'saveNotificationRoute rejects bad sink urls');
+assert(!saveNotificationRoute(routesDb, { name: 'none', workspaceId: 'ws1', events: [], sinks: [slackSink] }).success, 'saveNotificationRoute needs an event');
+const savedRoute = saveNotificationRoute(routesDb, { name: 'ok', workspaceId: 'ws1', events: ['completion'], sinks: [slackSink] });
+assert(savedRoute.success && getNotificationRoutes(routesDb).length === 1, 'saveNotificationRoute stores the route');
+saveNotificationRoute(routesDb, { ...savedRoute.route!, name: 'renamed' });
+assert(getNotificationRoutes(routesDb).length === 1 && getNotificationRoutes(routesDb)[0].name === 'renamed', 'saveNotificationRoute replaces by id');
+deleteNotificationRoute(routesDb, savedRoute.route!.id);
+assert(getNotificationRoutes(routesDb).length === 0, 'deleteNotificationRoute removes the route');
+routesDb.close();
+
+// Webhooks
+import { addWebhook, listWebhooks, removeWebhook, signWebhookPayload, webhookEventFor, buildWebhookPayload } from './services/webhooks';
+import * as crypto from 'crypto';
+const hooksDb = new Database(':memory:');
+ensureSchema(hooksDb);
+assert(!addWebhook(hooksDb, 'ftp://example.com', ['error']).success, 'addWebhook rejects non-http urls');
+assert(!addWebhook(hooksDb, 'https://example.com/hook', ['nope' as any]).success, 'addWebhook needs a known event');
+const addedHook = addWebhook(hooksDb, 'https://example.com/hook', ['turn-completed', 'error'], 'topsecret');
+assert(addedHook.success && addedHook.secret === 'topsecret' && listWebhooks(hooksDb)[0].secretHint === 'cret', 'addWebhook stores the hook and only lists a secret hint');
+assert(addWebhook(hooksDb, 'https://example.com/other', ['*']).secret!.length === 48, 'addWebhook generates a secret');
+assert(removeWebhook(hooksDb, addedHook.webhook!.id).success && listWebhooks(hooksDb).length === 1, 'removeWebhook deletes the hook');
+const expectedSignature = crypto.createHmac('sha256', 'k').update('{"a":1}').digest('hex');
+assert(signWebhookPayload('k', '{"a":1}') === `sha256=${expectedSignature}`, 'signWebhookPayload is an HMAC-SHA256 of the body');
+assert(webhookEventFor('codex-stream-end', { cid: 'c1' }) === 'turn-completed', 'webhookEventFor maps turn ends');
+assert(webhookEventFor('codex-stream-end', { cid: 'c1', cancelled: true }) === null, 'webhookEventFor skips cancelled turns');
+assert(webhookEventFor('codex-stream-delta', { cid: 'c1' }) === null, 'webhookEventFor ignores other channels');
+const pingPayload = buildWebhookPayload(hooksDb, 'ping', null, { message: 'hi' });
+assert(pingPayload.event === 'ping' && pingPayload.conversation === null && pingPayload.data.message === 'hi', 'buildWebhookPayload without a conversation');
+hooksDb.close();
+
+// Screen reader narration
+import { Narrator } from './services/narration';
+const narrator = new Narrator();
+assert(narrator.observe('codex-turn-status', { cid: 'c1', status: 'running' })[0]?.text === 'Turn started', 'Narrator announces a started turn');
+assert(narrator.observe('codex-stream-delta', { cid: 'c1', data: 'Fixed the parser. Also ' }).length === 0, 'Narrator stays quiet on deltas');
+narrator.observe('codex-file-diff', { cid: 'c1', path: 'a.ts' });
+narrator.observe('codex-file-diff', { cid: 'c1', path: 'b.ts' });
+narrator.observe('codex-file-diff', { cid: 'c1', path: 'a.ts' });
+assert(narrator.hasProgress('c1') && narrator.progress('c1')?.text === '2 files changed so far' && narrator.progress('c1') === null, 'Narrator coalesces file changes');
+narrator.observe('codex-file-diff', { cid: 'c1', path: 'c.ts' });
+const approvalNarration = narrator.observe('codex-approval-request', { cid: 'c1', title: 'Run npm install' });
+assert(approvalNarration.length === 2 && approvalNarration[1].text === 'Awaiting approval: Run npm install' && approvalNarration[1].priority === 'assertive', 'Narrator reports pending progress before an approval');
+narrator.observe('codex-tool-call', { cid: 'c1', title: 'npm test', status: 'done' });
+assert(narrator.observe('codex-stream-end', { cid: 'c1' })[0]?.text === 'Turn finished. 3 files changed. 1 command run. Fixed the parser.', 'Narrator summarizes a finished turn');
+assert(narrator.observe('codex-stream-end', { cid: 'c2', cancelled: true })[0]?.text === 'Turn cancelled', 'Narrator reports a cancelled turn');
+
+// watch rules
+import { buildWatchPrompt, withinTriggerCap } from './services/watch';
+import { globToRegExp } from './services/utils';
+assert(globToRegExp('*.ts').test('src/api/index.ts') && !globToRegExp('*.ts').test('src/index.tsx'), 'globToRegExp matches file names at any depth');
+assert(globToRegExp('src/**/*.rs').test('src/main.rs') && globToRegExp('src/**/*.rs').test('src/a/b.rs'), 'globToRegExp ** spans directories');
+assert(!globToRegExp('src/*.rs').test('src/a/b.rs'), 'globToRegExp * stays in one segment');
+const watchPrompt = buildWatchPrompt('Review {{files}}', ['a.ts'], '+const a = 1;');
+assert(watchPrompt.startsWith('Review - a.ts') && watchPrompt.includes('```diff\n+const a = 1;'), 'buildWatchPrompt fills files and appends diff');
+assert(buildWatchPrompt('Check:\n{{diff}}\nThanks', ['a.ts'], '').includes('(no diff available)\nThanks'), 'buildWatchPrompt fills diff placeholder');
+const watchTriggers = [0, Date.now() - 1000];
+assert(withinTriggerCap(watchTriggers, 2) && watchTriggers.length === 1, 'withinTriggerCap drops old triggers');
+assert(!withinTriggerCap([Date.now(), Date.now()], 2), 'withinTriggerCap enforces the hourly cap');
+
+// Workspace file watcher
+import { mergeFileChange, watchPaths } from './services/workspace-watcher';
+const pendingChanges = new Map<string, 'created' | 'modified' | 'deleted'>();
+mergeFileChange(pendingChanges, 'tmp.txt', 'created');
+mergeFileChange(pendingChanges, 'tmp.txt', 'deleted');
+mergeFileChange(pendingChanges, 'a.ts', 'deleted');
+mergeFileChange(pendingChanges, 'a.ts', 'created');
+mergeFileChange(pendingChanges, 'b.ts', 'created');
+mergeFileChange(pendingChanges, 'b.ts', 'modified');
+assert(!pendingChanges.has('tmp.txt'), 'mergeFileChange drops files created and deleted in one batch');
+assert(pendingChanges.get('a.ts') === 'modified' && pendingChanges.get('b.ts') === 'created', 'mergeFileChange folds atomic saves and edits');
+assert(watchPaths('ws-x', [' src/** ', '']).globs.join() === 'src/**' && watchPaths('ws-x', []).globs.length === 0, 'watchPaths trims and clears globs');
+
+// analyzeResponse
+import { analyzeResponse } from './services/stats';
+const responseStats = analyzeResponse('m1', 'Intro text here\n```ts\nconst a = 1;\nconst b = 2;\n```\nDone.', 6);
+assert(responseStats.codeLines === 2, 'analyzeResponse counts fenced code lines');
+assert(responseStats.proseLines === 2, 'analyzeResponse counts prose lines');
+assert(responseStats.codeRatio === 0.5, 'analyzeResponse computes code ratio');
+assert(responseStats.reviewRecommended === true, 'analyzeResponse flags many touched files');
+
+// findOutsidePathReferences
+import { findOutsidePathReferences } from './services/guardrails';
+const outside = findOutsidePathReferences('edit ../../etc/hosts and src/app.ts, see https://x.dev/a', '/tmp/ws/project');
+assert(outside.length === 1 && outside[0].reference === '../../etc/hosts', 'findOutsidePathReferences flags parent escapes');
+assert(findOutsidePathReferences('open /tmp/ws/project/src/a.ts', '/tmp/ws/project').length === 0, 'findOutsidePathReferences allows paths inside root');
+assert(findOutsidePathReferences('read /etc/passwd', '/tmp/ws/project').length === 1, 'findOutsidePathReferences flags absolute paths');
+
+// lintPrompt
+import { lintPrompt } from './services/guardrails';
+const lintNow = 1_700_000_000;
+const lintExists = (file: string) => file === '/tmp/ws/project/src/app.ts';
+const lintKinds = (prompt: string, cfg: RuntimeConfig, lastActivityAt: number | null = lintNow) =>
+    lintPrompt(prompt, cfg, '/tmp/ws/project', lastActivityAt, lintNow, lintExists).map((issue) => issue.kind);
+const lintMissing = lintPrompt('Update src/app.ts and src/gone.ts, see https://x.dev/a/b.html', testConfig, '/tmp/ws/project', lintNow, lintNow, lintExists);
+assert(lintMissing.length === 1 && lintMissing[0].kind === 'missing-file', 'lintPrompt flags only missing relative files');
+assert('Update src/app.ts and src/gone.ts'.slice(lintMissing[0].start, lintMissing[0].end) === 'src/gone.ts', 'lintPrompt reports the span of the issue');
+assert(lintKinds('and/or keep `src/app.ts`', testConfig).length === 0, 'lintPrompt ignores slashes in words');
+assert(lintKinds('fix it again', testConfig).length === 0, 'lintPrompt accepts pronouns right after activity');
+assert(lintKinds('fix it again', testConfig, lintNow - 24 * 3600).includes('ambiguous-reference'), 'lintPrompt flags pronouns after a long gap');
+assert(lintKinds('fix this bug', testConfig, null).length === 0, 'lintPrompt ignores determiners');
+const readOnlyConfig = { ...testConfig, cliOptions: { ...testConfig.cliOptions, sandbox: 'read-only' } };
+assert(lintKinds('Please refactor the parser', readOnlyConfig).includes('sandbox-forbidden'), 'lintPrompt flags writes in a read-only sandbox');
+assert(lintKinds('Explain how add works', readOnlyConfig).length === 0, 'lintPrompt only flags write verbs that start a request');
+assert(lintKinds('npm install lodash', testConfig).includes('sandbox-forbidden'), 'lintPrompt flags network use in the sandbox');
+assert(lintKinds('npm install lodash', { ...testConfig, yoloMode: true }).length === 0, 'lintPrompt allows everything in yolo mode');
+assert(lintKinds('edit /etc/hosts', testConfig).includes('sandbox-forbidden'), 'lintPrompt flags writes outside the workspace');
+assert(lintKinds('fix everything in the whole codebase', testConfig).filter((k) => k === 'broad-scope').length === 2, 'lintPrompt flags over-broad scopes');
+
+// fuzzyScore
+import { fuzzyScore } from './services/prompt-history';
+assert(fuzzyScore('upd deps', 'update dependencies') > 0, 'fuzzyScore matches subsequence');
+assert(fuzzyScore('xyz', 'update dependencies') === 0, 'fuzzyScore rejects non-match');
+assert(fuzzyScore('upd', 'update') > fuzzyScore('upd', 'u p d'), 'fuzzyScore prefers consecutive hits');
+
 // ===== 3. Database =====
 section('db.ts — SQLite Database');
 import { openDatabase, dbGetState, dbCreateWorkspace, dbDeleteWorkspace, dbCreateConversation, dbUpdateConversationTitle, dbDeleteConversation, dbCreateMessage, dbGetMessages, dbGetConversations } from './services/db';
@@ -148,8 +635,9 @@ testDb.pragma('foreign_keys = ON');
 testDb.pragma('journal_mode = WAL');
 testDb.exec(`
     CREATE TABLE IF NOT EXISTS workspaces (id TEXT PRIMARY KEY, name TEXT NOT NULL, path TEXT NOT NULL);
-    CREATE TABLE IF NOT EXISTS conversations (id TEXT PRIMARY KEY, workspace_id TEXT NOT NULL, title TEXT NOT NULL, created_at TEXT NOT NULL, updated_at TEXT NOT NULL, FOREIGN KEY(workspace_id) REFERENCES workspaces(id) ON DELETE CASCADE);
-    CREATE TABLE IF NOT EXISTS messages (id TEXT PRIMARY KEY, conversation_id TEXT NOT NULL, role TEXT NOT NULL, content TEXT NOT NULL, thinking TEXT, thinking_duration INTEGER, timestamp TEXT NOT NULL, FOREIGN KEY(conversation_id) REFERENCES conversations(id) ON DELETE CASCADE);
+    CREATE TABLE IF NOT EXISTS conversations (id TEXT PRIMARY KEY, workspace_id TEXT NOT NULL, title TEXT NOT NULL, created_at TEXT NOT NULL, updated_at TEXT NOT NULL, persona TEXT, cli_overrides TEXT, auto_translate_lang TEXT, codex_session_id TEXT, target_package TEXT, notes TEXT, notes_in_prompt INTEGER NOT NULL DEFAULT 0, last_read_message_id TEXT, FOREIGN KEY(workspace_id) REFERENCES workspaces(id) ON DELETE CASCADE);
+    CREATE TABLE IF NOT EXISTS messages (id TEXT PRIMARY KEY, conversation_id TEXT NOT NULL, role TEXT NOT NULL, content TEXT NOT NULL, thinking TEXT, thinking_duration INTEGER, attachments TEXT, part_count INTEGER NOT NULL DEFAULT 1, timestamp TEXT NOT NULL, FOREIGN KEY(conversation_id) REFERENCES conversations(id) ON DELETE CASCADE);
+    CREATE TABLE IF NOT EXISTS message_parts (message_id TEXT NOT NULL, part_index INTEGER NOT NULL, content TEXT NOT NULL, PRIMARY KEY(message_id, part_index), FOREIGN KEY(message_id) REFERENCES messages(id) ON DELETE CASCADE);
 `);
 
 // Test workspace CRUD
@@ -203,6 +691,38 @@ const state = dbGetState(testDb);
 assert(state.workspaces.length === 2, 'dbGetState returns 2 workspaces');
 const wsWithConvs = state.workspaces.find((w: any) => w.id === 'ws-1');
 assert(wsWithConvs?.conversations.length === 2, 'workspace has 2 conversations');
+const stateConv = wsWithConvs?.conversations.find((c: any) => c.id === 'conv-1');
+assert(stateConv?.messages.length === 0 && stateConv?.messageCount === 2, 'dbGetState returns message counts without messages');
+
+// Message pages
+import { dbGetMessagesPage } from './services/db';
+const latestPage = dbGetMessagesPage(testDb, 'conv-1', null, 1);
+assert(latestPage.messages.length === 1 && latestPage.messages[0].id === msgs[1].id && latestPage.hasMore, 'dbGetMessagesPage returns the latest messages first');
+const olderPage = dbGetMessagesPage(testDb, 'conv-1', latestPage.messages[0].id, 10);
+assert(olderPage.messages.length === 1 && olderPage.messages[0].id === msgs[0].id && !olderPage.hasMore, 'dbGetMessagesPage pages backwards from beforeId');
+assert(dbGetMessagesPage(testDb, 'conv-1', 'msg-missing').messages.length === 0, 'dbGetMessagesPage with an unknown beforeId is empty');
+
+// Incremental sync
+import { dbGetChanges } from './services/db';
+const syncDb = new Database(':memory:');
+ensureSchema(syncDb);
+dbCreateWorkspace(syncDb, 'ws-s', 'Sync', '/tmp/sync');
+dbCreateConversation(syncDb, 'c-s1', 'ws-s', 'One');
+dbCreateConversation(syncDb, 'c-s2', 'ws-s', 'Two');
+const syncState = dbGetState(syncDb);
+assert(!!syncState.syncToken && !!syncState.workspaces[0].etag, 'dbGetState returns a sync token and etags');
+assert(dbGetChanges(syncDb, syncState.syncToken!).conversations.length === 0, 'dbGetChanges is empty when nothing changed');
+dbCreateMessage(syncDb, { id: 'm-s1', conversationId: 'c-s1', role: 'user', content: 'hi', timestamp: '1' });
+dbDeleteConversation(syncDb, 'c-s2');
+const syncChanges = dbGetChanges(syncDb, syncState.syncToken!);
+assert(syncChanges.conversations.length === 1 && syncChanges.conversations[0].messageCount === 1 && syncChanges.messages[0].id === 'm-s1', 'dbGetChanges returns changed conversations and messages');
+assert(syncChanges.deleted.conversations[0] === 'c-s2' && syncChanges.etags['ws-s'] !== syncState.workspaces[0].etag, 'dbGetChanges reports deletions and moves the etag');
+assert(dbGetChanges(syncDb, syncChanges.token).messages.length === 0, 'dbGetChanges starts after the returned token');
+dbDeleteWorkspace(syncDb, 'ws-s');
+const workspaceGone = dbGetChanges(syncDb, syncChanges.token);
+assert(workspaceGone.deleted.workspaces[0] === 'ws-s' && workspaceGone.deleted.messages.length === 0, 'deleting a workspace leaves a single tombstone');
+assert(dbGetChanges(syncDb, 'other:0').reset && dbGetChanges(syncDb, 'garbage').reset, 'dbGetChanges asks for a reload on foreign tokens');
+syncDb.close();
 
 // Test cascade delete
 dbDeleteConversation(testDb, 'conv-2');
@@ -213,6 +733,439 @@ dbDeleteWorkspace(testDb, 'ws-2');
 const afterDeleteState = dbGetState(testDb);
 assert(afterDeleteState.workspaces.length === 1, 'dbDeleteWorkspace removed workspace');
 
+// FTS query building
+import { toFtsQuery } from './services/db';
+assert(toFtsQuery('answer about migr') === '"answer" "about" "migr"*', 'toFtsQuery quotes terms and prefixes last');
+assert(toFtsQuery('  ') === '', 'toFtsQuery empty input');
+
+// Export / import round trip and backup
+import { dbBackup } from './services/db';
+import { exportConversation, importConversation } from './services/conversation-export';
+const exportedJson = exportConversation(testDb, 'conv-1', 'json');
+const exportPath = path.join(os.tmpdir(), `codex-export-${Date.now()}.json`);
+fs.writeFileSync(exportPath, exportedJson.content!);
+const imported = importConversation(testDb, exportPath, 'ws-1');
+assert(imported.success && imported.conversation!.id !== 'conv-1', 'importConversation creates a new conversation');
+const importedMsgs = dbGetMessages(testDb, imported.conversation!.id);
+assert(importedMsgs.length === 2 && importedMsgs[0].id !== 'msg-1', 'importConversation copies messages with new ids');
+assert(importedMsgs[1].timestamp === msgs[1].timestamp, 'importConversation preserves message timestamps');
+assert(importConversation(testDb, exportPath, 'ws-missing').success === false, 'importConversation requires a workspace');
+fs.unlinkSync(exportPath);
+
+const backupPath = path.join(os.tmpdir(), `codex-backup-${Date.now()}.sqlite3`);
+const backup = dbBackup(testDb, backupPath);
+const backupDb = new Database(backupPath, { readonly: true });
+assert(backup.success && (backupDb.prepare('SELECT COUNT(*) AS n FROM messages').get() as any).n === 4, 'dbBackup writes a complete copy');
+backupDb.close();
+fs.unlinkSync(backupPath);
+
+// Schema migrations
+import { dbGetSchemaVersion, SCHEMA_VERSION } from './services/db';
+assert(dbGetSchemaVersion(testDb).version === SCHEMA_VERSION, 'ensureSchema records the latest schema version');
+const migrateDir = fs.mkdtempSync(path.join(os.tmpdir(), 'codex-migrate-'));
+const migrateDb = new Database(path.join(migrateDir, 'state.sqlite3'));
+migrateDb.exec("CREATE TABLE workspaces (id TEXT PRIMARY KEY, name TEXT NOT NULL, path TEXT NOT NULL); INSERT INTO workspaces VALUES ('w', 'W', '/tmp/w');");
+ensureSchema(migrateDb);
+const migrateBackup = new Database(path.join(migrateDir, `state.sqlite3.before-v${SCHEMA_VERSION}`), { readonly: true });
+assert(dbGetSchemaVersion(migrateDb).version === SCHEMA_VERSION && (migrateBackup.prepare('SELECT COUNT(*) AS n FROM workspaces').get() as any).n === 1, 'ensureSchema backs up an unversioned database before migrating it');
+migrateBackup.close();
+migrateDb.pragma(`user_version = ${SCHEMA_VERSION + 1}`);
+let newerSchemaRefused = false;
+try {
+    ensureSchema(migrateDb);
+} catch {
+    newerSchemaRefused = true;
+}
+assert(newerSchemaRefused, 'ensureSchema refuses a database from a newer app');
+migrateDb.close();
+fs.rmSync(migrateDir, { recursive: true });
+
+// Read / unread
+import { dbMarkConversationRead, dbGetUnreadCount, dbGetUnreadTotal } from './services/db';
+assert(dbMarkConversationRead(testDb, 'conv-1').unreadCount === 0, 'dbMarkConversationRead clears unread');
+dbCreateMessage(testDb, { id: 'msg-unread', conversationId: 'conv-1', role: 'assistant', content: 'Background result', timestamp: new Date().toISOString() });
+assert(dbGetUnreadCount(testDb, 'conv-1') === 1 && dbGetUnreadTotal(testDb) >= 1, 'new assistant messages count as unread');
+assert(dbGetConversations(testDb, 'ws-1').find((c) => c.id === 'conv-1')?.unreadCount === 1, 'dbGetConversations includes unread counts');
+assert(dbMarkConversationRead(testDb, 'conv-1', 'msg-unread').unreadCount === 0, 'dbMarkConversationRead up to a message');
+
+// Message segmentation
+import { splitMessageContent, dbGetMessageContent, dbGetMessagePart, dbUpdateMessageContent } from './services/db';
+const longContent = ('x'.repeat(70) + '\n').repeat(1000);
+const messageParts = splitMessageContent(longContent, 10_000);
+assert(messageParts.length === 8 && messageParts.every((p) => p.length <= 10_000 && p.endsWith('\n')), 'splitMessageContent cuts at line breaks');
+assert(messageParts.join('') === longContent, 'splitMessageContent is lossless');
+dbCreateMessage(testDb, { id: 'msg-long', conversationId: 'conv-1', role: 'assistant', content: 'y'.repeat(70_000), timestamp: new Date().toISOString() });
+const headOnly = dbGetMessages(testDb, 'conv-1', false).find((m) => m.id === 'msg-long')!;
+assert(headOnly.partCount === 3 && headOnly.content.length === 32_000, 'dbGetMessages can return the first part only');
+assert(dbGetMessages(testDb, 'conv-1').find((m) => m.id === 'msg-long')!.content.length === 70_000, 'dbGetMessages reassembles parts');
+assert(dbGetMessagePart(testDb, 'msg-long', 2)?.content.length === 6000 && dbGetMessagePart(testDb, 'msg-long', 3) === null, 'dbGetMessagePart pages through parts');
+assert(dbUpdateMessageContent(testDb, 'msg-long', 'short').partCount === 1 && dbGetMessageContent(testDb, 'msg-long') === 'short', 'dbUpdateMessageContent re-segments');
+assert((testDb.prepare("SELECT COUNT(*) AS n FROM message_parts WHERE message_id = 'msg-long'").get() as any).n === 0, 'dbUpdateMessageContent drops old parts');
+
+// Streaming drafts
+import { dbFinalizeMessageDraft, dbFinalizeOrphanedDrafts, dbSaveMessageDraft } from './services/db';
+import { MessageDraft } from './services/message-drafts';
+const draftTime = new Date().toISOString();
+dbSaveMessageDraft(testDb, { id: 'msg-draft', conversationId: 'conv-1', content: 'Hal', timestamp: draftTime });
+dbSaveMessageDraft(testDb, { id: 'msg-draft', conversationId: 'conv-1', content: 'z'.repeat(40_000), thinking: 'pondering', timestamp: draftTime });
+const savedDraft = dbGetMessages(testDb, 'conv-1').find((m) => m.id === 'msg-draft')!;
+assert(savedDraft.inProgress === true && savedDraft.role === 'assistant' && savedDraft.content.length === 40_000 && savedDraft.thinking === 'pondering', 'dbSaveMessageDraft upserts an in-progress assistant message');
+dbFinalizeMessageDraft(testDb, 'msg-draft');
+dbSaveMessageDraft(testDb, { id: 'msg-draft', conversationId: 'conv-1', content: 'late', timestamp: draftTime });
+assert(!dbGetMessages(testDb, 'conv-1').find((m) => m.id === 'msg-draft')!.inProgress && dbGetMessageContent(testDb, 'msg-draft')!.length === 40_000, 'a finalized draft is not overwritten by later drafts');
+dbCreateMessage(testDb, { id: 'msg-draft', conversationId: 'conv-1', role: 'assistant', content: 'Final answer', thinkingDuration: 3, timestamp: draftTime });
+const finalDraft = dbGetMessages(testDb, 'conv-1').filter((m) => m.id === 'msg-draft');
+assert(finalDraft.length === 1 && finalDraft[0].content === 'Final answer' && finalDraft[0].thinkingDuration === 3 && dbGetMessagePart(testDb, 'msg-draft', 1) === null, 'dbCreateMessage replaces the draft under the same id');
+dbSaveMessageDraft(testDb, { id: 'msg-empty-draft', conversationId: 'conv-1', content: '', timestamp: draftTime });
+dbFinalizeMessageDraft(testDb, 'msg-empty-draft');
+assert(!dbGetMessages(testDb, 'conv-1').some((m) => m.id === 'msg-empty-draft'), 'dbFinalizeMessageDraft removes empty drafts');
+dbSaveMessageDraft(testDb, { id: 'msg-crashed-draft', conversationId: 'conv-1', content: 'Cut off', timestamp: draftTime });
+dbSaveMessageDraft(testDb, { id: 'msg-crashed-empty', conversationId: 'conv-1', content: '', timestamp: draftTime });
+assert(dbFinalizeOrphanedDrafts(testDb) === 1, 'dbFinalizeOrphanedDrafts counts the drafts it kept');
+const crashedDrafts = dbGetMessages(testDb, 'conv-1').filter((m) => m.id.startsWith('msg-crashed'));
+assert(crashedDrafts.length === 1 && crashedDrafts[0].content === 'Cut off' && !crashedDrafts[0].inProgress, 'dbFinalizeOrphanedDrafts keeps partial output and drops empty drafts');
+const draftCache = new StreamParseCache();
+const streamDraft = new MessageDraft(testDb, 'conv-1', draftCache);
+draftCache.appendAgentText('Partial');
+streamDraft.save();
+assert(dbGetMessages(testDb, 'conv-1').find((m) => m.id === streamDraft.messageId)?.content === 'Partial', 'MessageDraft saves the streamed text');
+draftCache.appendAgentText(' output');
+streamDraft.touch();
+streamDraft.finish();
+const finishedDraft = dbGetMessages(testDb, 'conv-1').find((m) => m.id === streamDraft.messageId)!;
+assert(finishedDraft.content === 'Partial output' && !finishedDraft.inProgress, 'MessageDraft.finish writes the rest and clears the flag');
+const idleDraft = new MessageDraft(testDb, 'conv-1', new StreamParseCache());
+idleDraft.finish();
+assert(!dbGetMessages(testDb, 'conv-1').some((m) => m.id === idleDraft.messageId), 'a turn without output leaves no message');
+
+// Workspace bundle round trip
+import { exportWorkspaceBundle, importWorkspaceBundle } from './services/workspace-bundle';
+import { saveChore } from './services/chores';
+const bundleDir = fs.mkdtempSync(path.join(os.tmpdir(), 'codex-bundle-'));
+testDb.prepare("UPDATE workspaces SET path = ? WHERE id = 'ws-1'").run(bundleDir);
+fs.writeFileSync(path.join(bundleDir, 'AGENTS.md'), '# Rules');
+saveChore(testDb, { workspaceId: 'ws-1', name: 'Nightly', prompt: 'Run tests', runAt: '02:00', enabled: true });
+const bundleExport = exportWorkspaceBundle(testDb, 'ws-1', path.join(bundleDir, 'out', 'ws.zip'), true);
+assert(bundleExport.success && bundleExport.conversations === dbGetConversations(testDb, 'ws-1').length, 'exportWorkspaceBundle writes every conversation');
+const bundleTarget = fs.mkdtempSync(path.join(os.tmpdir(), 'codex-bundle-target-'));
+const bundleImport = importWorkspaceBundle(testDb, bundleExport.path!, bundleTarget, 'Copy');
+assert(bundleImport.success && bundleImport.result!.conversations === bundleExport.conversations, 'importWorkspaceBundle re-creates conversations');
+assert(bundleImport.result!.workspace.name === 'Copy' && fs.readFileSync(path.join(bundleTarget, 'AGENTS.md'), 'utf-8') === '# Rules', 'importWorkspaceBundle restores instructions');
+const importedChores = testDb.prepare('SELECT * FROM chores WHERE workspace_id = ?').all(bundleImport.result!.workspace.id) as any[];
+assert(importedChores.length === 1 && importedChores[0].enabled === 0, 'importWorkspaceBundle imports chores disabled');
+assert(importWorkspaceBundle(testDb, path.join(bundleDir, 'AGENTS.md'), bundleTarget).success === false, 'importWorkspaceBundle rejects non-bundles');
+fs.rmSync(bundleDir, { recursive: true });
+fs.rmSync(bundleTarget, { recursive: true });
+
+// Housekeeping
+import { findStaleWorkspaces, archiveWorkspace, exportAndPruneWorkspace } from './services/housekeeping';
+const houseDb = new Database(':memory:');
+ensureSchema(houseDb);
+dbCreateWorkspace(houseDb, 'ws-old', 'Old', '/tmp/old');
+dbCreateWorkspace(houseDb, 'ws-new', 'New', '/tmp/new');
+dbCreateConversation(houseDb, 'c-old', 'ws-old', 'Old chat');
+dbCreateConversation(houseDb, 'c-new', 'ws-new', 'New chat');
+dbCreateMessage(houseDb, { id: 'm-old', conversationId: 'c-old', role: 'user', content: 'x'.repeat(100), timestamp: '1' });
+houseDb.prepare("UPDATE conversations SET last_activity_at = '1000', updated_at = '1000' WHERE id = 'c-old'").run();
+const houseNow = 1000 + 40 * 86400;
+houseDb.prepare('UPDATE conversations SET last_activity_at = ? WHERE id = ?').run(String(houseNow - 86400), 'c-new');
+const staleFound = findStaleWorkspaces(houseDb, 30, houseNow, () => undefined);
+assert(staleFound.length === 1 && staleFound[0].workspaceId === 'ws-old' && staleFound[0].idleDays === 40, 'findStaleWorkspaces flags idle workspaces');
+assert(staleFound[0].missing && staleFound[0].footprintBytes >= 100 && staleFound[0].actions.includes('archive'), 'findStaleWorkspaces reports footprint and actions');
+assert(findStaleWorkspaces(houseDb, 30, houseNow, () => houseNow).length === 0, 'findStaleWorkspaces counts file activity');
+assert(archiveWorkspace(houseDb, 'ws-old').archived === 1, 'archiveWorkspace archives conversations');
+assert(!findStaleWorkspaces(houseDb, 30, houseNow, () => null)[0].actions.includes('archive'), 'archived workspaces only offer export-prune');
+const pruneDir = fs.mkdtempSync(path.join(os.tmpdir(), 'codex-prune-'));
+const pruned = exportAndPruneWorkspace(houseDb, 'ws-old', path.join(pruneDir, 'old.zip'));
+assert(pruned.success && fs.existsSync(pruned.path!) && pruned.freedBytes! >= 100, 'exportAndPruneWorkspace writes the bundle');
+assert(!houseDb.prepare("SELECT 1 FROM conversations WHERE workspace_id = 'ws-old'").get(), 'exportAndPruneWorkspace deletes the workspace');
+fs.rmSync(pruneDir, { recursive: true });
+houseDb.close();
+
+// Scheduled tasks
+import { deleteScheduledTask, isTaskDue, listScheduledTasks, nextCronRun, saveScheduledTask, validateCron } from './services/scheduler';
+const cronFrom = new Date(2026, 9, 16, 14, 30);
+assert(nextCronRun('0 3 * * *', cronFrom)!.getTime() === new Date(2026, 9, 17, 3, 0).getTime(), 'nextCronRun finds the next daily time');
+assert(nextCronRun('*/15 * * * *', cronFrom)!.getTime() === new Date(2026, 9, 16, 14, 45).getTime(), 'nextCronRun handles steps');
+assert(nextCronRun('0 9 * * mon-fri', new Date(2026, 9, 16, 9, 0))!.getDay() === 1, 'nextCronRun skips to the next weekday');
+assert(nextCronRun('0 0 1 * 5', cronFrom)!.getDate() === 23, 'nextCronRun matches either day field when both are set');
+assert(nextCronRun('0 0 31 2 *', cronFrom) === null, 'nextCronRun gives up on impossible dates');
+assert(nextCronRun('@weekly', cronFrom)!.getDay() === 0, 'nextCronRun expands macros');
+assert(validateCron('61 * * * *') !== null && validateCron('* * *') !== null && validateCron('0 0 * * 7') === null, 'validateCron checks fields');
+const schedDb = new Database(':memory:');
+ensureSchema(schedDb);
+dbCreateWorkspace(schedDb, 'ws-sched', 'Sched', '/tmp/sched');
+assert(saveScheduledTask(schedDb, { workspaceId: 'ws-sched', name: 'Bad', cron: 'nightly', prompt: 'x', enabled: true }).success === false, 'saveScheduledTask rejects invalid cron');
+const nightly = saveScheduledTask(schedDb, {
+    workspaceId: 'ws-sched', name: 'Deps', cron: '0 2 * * *', prompt: 'Update dependencies and summarize breakage', enabled: true,
+    cliOptions: { sandbox: 'danger-full-access' },
+});
+assert(nightly.success && nightly.task!.cliOptions.sandbox === 'danger-full-access' && !isTaskDue(nightly.task!), 'saveScheduledTask stores a task that is not due yet');
+schedDb.prepare('UPDATE scheduled_tasks SET created_at = ? WHERE id = ?').run(String(Math.floor(Date.now() / 1000) - 2 * 86400), nightly.task!.id);
+assert(isTaskDue(listScheduledTasks(schedDb, 'ws-sched')[0]), 'isTaskDue flags a missed run');
+assert(saveScheduledTask(schedDb, { ...nightly.task!, enabled: false }).task!.nextRunAt === null, 'disabled tasks have no next run');
+assert(deleteScheduledTask(schedDb, nightly.task!.id).success && listScheduledTasks(schedDb).length === 0, 'deleteScheduledTask removes the task');
+schedDb.close();
+
+// API server
+import { handleApiQuery, isAuthorized, setApiServerSettings } from './services/api-server';
+const apiDb = new Database(':memory:');
+ensureSchema(apiDb);
+const apiSettings = setApiServerSettings(apiDb, { enabled: true, port: 80 });
+assert(apiSettings.token.length === 48 && apiSettings.port === 8787, 'setApiServerSettings generates a token and rejects privileged ports');
+assert(isAuthorized(`Bearer ${apiSettings.token}`, apiSettings.token), 'isAuthorized accepts the bearer token');
+assert(!isAuthorized('Bearer wrong', apiSettings.token) && !isAuthorized(undefined, apiSettings.token) && !isAuthorized('Bearer x', ''), 'isAuthorized rejects other tokens');
+dbCreateWorkspace(apiDb, 'ws-api', 'Api', '/tmp/api');
+dbCreateConversation(apiDb, 'c-api', 'ws-api', 'Api chat');
+dbCreateMessage(apiDb, { id: 'm-api', conversationId: 'c-api', role: 'user', content: 'hello', timestamp: '1' });
+const apiUrl = (p: string) => new URL(p, 'http://127.0.0.1');
+const apiWorkspaces = handleApiQuery(apiDb, 'GET', apiUrl('/workspaces'))!.body as any;
+assert(apiWorkspaces.workspaces[0].id === 'ws-api' && apiWorkspaces.workspaces[0].conversationCount === 1, 'GET /workspaces lists workspaces');
+const apiConversations = handleApiQuery(apiDb, 'GET', apiUrl('/conversations?workspaceId=ws-api'))!.body as any;
+assert(apiConversations.conversations.length === 1 && apiConversations.conversations[0].messages === undefined, 'GET /conversations lists conversations without messages');
+const apiMessages = handleApiQuery(apiDb, 'GET', apiUrl('/conversations/c-api/messages'))!.body as any;
+assert(apiMessages.messages[0].content === 'hello', 'GET /conversations/:id/messages pages messages');
+assert(handleApiQuery(apiDb, 'GET', apiUrl('/conversations/nope/messages'))!.status === 404 && handleApiQuery(apiDb, 'POST', apiUrl('/workspaces')) === null, 'handleApiQuery rejects unknown conversations and methods');
+apiDb.close();
+
+// Jobs registry
+import { cancelJob, dismissJob, listJobs, registerJobKind, retryJob, startJob } from './services/jobs';
+registerJobKind('test-job', async (_params, job) => {
+    for (let i = 0; i < 1000; i++) await job.progress(i, 1000);
+});
+assert(startJob(() => null, testDb, 'no-such-kind', 'x', {}).success === false, 'startJob rejects unknown kinds');
+const testJob = startJob(() => null, testDb, 'test-job', 'Test job', { n: 1 });
+assert(testJob.success && testJob.job!.status === 'running', 'startJob starts a running job');
+assert(listJobs(testDb)[0].id === testJob.job!.id, 'listJobs lists session jobs newest first');
+assert(cancelJob(testJob.job!.id).success && cancelJob('job-missing').success === false, 'cancelJob aborts running jobs only');
+testDb.prepare(`
+  INSERT INTO failed_jobs (id, kind, title, params, error, started_at, failed_at) VALUES ('job-old', 'test-job', 'Old', '{"n":2}', 'boom', '1', '2')
+`).run();
+const failedJob = listJobs(testDb).find((j) => j.id === 'job-old');
+assert(failedJob?.status === 'failed' && failedJob.params.n === 2 && failedJob.error === 'boom', 'listJobs includes persisted failed jobs');
+const retried = retryJob(() => null, testDb, 'job-old');
+assert(retried.success && retried.job!.params.n === 2 && !listJobs(testDb).some((j) => j.id === 'job-old'), 'retryJob restarts a failed job with its params');
+cancelJob(retried.job!.id);
+assert(retryJob(() => null, testDb, 'job-old').success === false && dismissJob(testDb, 'job-old').success, 'retryJob needs a failed job');
+
+// Conversation forks
+import { dbForkConversation, dbSetConversationSessionId } from './services/db';
+dbCreateConversation(testDb, 'conv-fork', 'ws-1', 'Explore');
+dbSetConversationSessionId(testDb, 'conv-fork', 'session-1');
+for (const [i, role] of ['user', 'assistant', 'user', 'assistant'].entries()) {
+    dbCreateMessage(testDb, { id: `fork-msg-${i}`, conversationId: 'conv-fork', role, content: `m${i}`, timestamp: String(i) } as any);
+}
+const midFork = dbForkConversation(testDb, 'conv-fork', 'fork-msg-1', 'Branch');
+assert(midFork.success && midFork.conversation!.title === 'Branch' && midFork.conversation!.messages.map((m) => m.content).join(',') === 'm0,m1', 'dbForkConversation copies messages up to the fork point');
+assert(midFork.conversation!.forkedFrom === 'conv-fork' && !midFork.conversation!.codexSessionId && midFork.conversation!.unreadCount === 0, 'dbForkConversation drops the session when forking mid-thread');
+const tipFork = dbForkConversation(testDb, 'conv-fork', 'fork-msg-3');
+assert(tipFork.conversation!.codexSessionId === 'session-1' && tipFork.conversation!.title === 'Explore (fork)', 'dbForkConversation keeps the session when forking at the last message');
+assert(dbGetMessages(testDb, 'conv-fork').length === 4 && !dbForkConversation(testDb, 'conv-fork', 'nope').success, 'dbForkConversation leaves the original untouched');
+
+// Conversation archive/pin listing
+import { dbListConversations, dbSetConversationArchived, dbSetConversationPinned } from './services/db';
+dbCreateWorkspace(testDb, 'ws-list', 'Listing', '/tmp/listing');
+for (const name of ['alpha', 'Bravo', 'charlie', 'delta']) dbCreateConversation(testDb, `list-${name}`, 'ws-list', name);
+assert(dbSetConversationPinned(testDb, 'list-charlie', true).success && dbSetConversationArchived(testDb, 'list-delta', true).success, 'dbSetConversationPinned/Archived update flags');
+const byTitle = dbListConversations(testDb, 'ws-list', { sort: 'title' });
+assert(byTitle.total === 3 && byTitle.conversations.map((c) => c.title).join(',') === 'charlie,alpha,Bravo', 'dbListConversations lists pinned first, hides archived');
+const titlePage = dbListConversations(testDb, 'ws-list', { sort: 'title', offset: 1, limit: 1 });
+assert(titlePage.total === 3 && titlePage.conversations.length === 1 && titlePage.conversations[0].title === 'alpha', 'dbListConversations paginates');
+assert(dbListConversations(testDb, 'ws-list', { archived: true }).conversations[0].id === 'list-delta', 'dbListConversations lists archived conversations');
+assert(dbListConversations(testDb, 'ws-list', { pinned: false }).total === 2, 'dbListConversations filters by pinned');
+dbSetConversationArchived(testDb, 'list-delta', false);
+dbSetConversationPinned(testDb, 'list-delta', true);
+dbSetConversationArchived(testDb, 'list-delta', true);
+assert(!dbListConversations(testDb, 'ws-list', { archived: true }).conversations[0].pinned, 'dbSetConversationArchived unpins');
+
+// Conversation cwd
+import { setConversationCwd } from './services/codex';
+import { dbGetConversationOverrides } from './services/db';
+const cwdRoot = fs.mkdtempSync(path.join(os.tmpdir(), 'codex-cwd-'));
+fs.mkdirSync(path.join(cwdRoot, 'packages', 'frontend'), { recursive: true });
+dbCreateWorkspace(testDb, 'ws-cwd', 'Cwd', cwdRoot);
+dbCreateConversation(testDb, 'conv-cwd', 'ws-cwd', 'Scoped');
+const cwdState = { db: testDb } as any;
+assert(setConversationCwd(cwdState, 'conv-cwd', 'packages/frontend').cwd === 'packages/frontend', 'setConversationCwd stores the path relative to the workspace');
+assert(dbGetConversationOverrides(testDb, 'conv-cwd').cwd === path.join(cwdRoot, 'packages/frontend'), 'dbGetConversationOverrides resolves the conversation cwd');
+assert(!setConversationCwd(cwdState, 'conv-cwd', '..').success && !setConversationCwd(cwdState, 'conv-cwd', 'missing').success, 'setConversationCwd rejects paths outside the workspace or missing');
+assert(setConversationCwd(cwdState, 'conv-cwd', null).success && !dbGetConversationOverrides(testDb, 'conv-cwd').cwd, 'setConversationCwd clears the cwd');
+fs.rmSync(cwdRoot, { recursive: true });
+
+// Conversation date buckets
+import { dbGetConversationGroups } from './services/db';
+const groupsDb = new Database(':memory:');
+ensureSchema(groupsDb);
+dbCreateWorkspace(groupsDb, 'ws-groups', 'Groups', '/tmp/groups');
+const dayMs = 24 * 60 * 60 * 1000;
+const groupTimes: Array<[string, string]> = [
+    ['conv-today-epoch', String(Math.floor(Date.now() / 1000))],
+    ['conv-yesterday-iso', new Date(Date.now() - dayMs).toISOString()],
+    ['conv-old-epoch', String(Math.floor((Date.now() - 400 * dayMs) / 1000))],
+    ['conv-old-iso', new Date(Date.now() - 401 * dayMs).toISOString()],
+];
+for (const [id] of groupTimes) dbCreateConversation(groupsDb, id, 'ws-groups', id);
+dbCreateMessage(groupsDb, { id: 'groups-m1', conversationId: 'conv-today-epoch', role: 'user', content: 'a', timestamp: new Date().toISOString() });
+dbCreateMessage(groupsDb, { id: 'groups-m2', conversationId: 'conv-old-iso', role: 'user', content: 'b', timestamp: new Date().toISOString() });
+dbCreateMessage(groupsDb, { id: 'groups-m3', conversationId: 'conv-old-iso', role: 'assistant', content: 'c', timestamp: new Date().toISOString() });
+// Set last, since adding a message touches updated_at.
+for (const [id, updatedAt] of groupTimes) groupsDb.prepare('UPDATE conversations SET updated_at = ? WHERE id = ?').run(updatedAt, id);
+const conversationGroups = dbGetConversationGroups(groupsDb, 'ws-groups');
+const bucketOf = (id: string) => conversationGroups.groups.find((g) => g.conversationIds.includes(id))?.bucket;
+assert(bucketOf('conv-today-epoch') === 'today' && bucketOf('conv-yesterday-iso') === 'yesterday', 'dbGetConversationGroups buckets epoch and ISO timestamps by local day');
+assert(conversationGroups.groups.map((g) => g.bucket).join() === 'today,yesterday,older' && conversationGroups.groups[2].conversationIds.join() === 'conv-old-epoch,conv-old-iso', 'dbGetConversationGroups orders buckets and conversations newest first');
+assert(conversationGroups.dailyTurns.length === 1 && conversationGroups.dailyTurns[0].turns === 2, 'dbGetConversationGroups counts user turns per day');
+groupsDb.close();
+
+// Startup intent
+import { recordLastSession, resolveStartupIntent, setStartupSettings } from './services/startup';
+const startupDb = new Database(':memory:');
+ensureSchema(startupDb);
+const startupRoot = fs.mkdtempSync(path.join(os.tmpdir(), 'codex-startup-'));
+dbCreateWorkspace(startupDb, 'ws-start', 'Start', startupRoot);
+dbCreateWorkspace(startupDb, 'ws-start-2', 'Start 2', startupRoot);
+dbCreateWorkspace(startupDb, 'ws-gone', 'Gone', path.join(startupRoot, 'missing'));
+dbCreateConversation(startupDb, 'conv-start-a', 'ws-start', 'A');
+dbCreateConversation(startupDb, 'conv-start-b', 'ws-start', 'B');
+dbCreateConversation(startupDb, 'conv-start-other', 'ws-start-2', 'Other');
+assert(resolveStartupIntent(startupDb).workspaceId === null, 'resolveStartupIntent starts blank with no last session');
+recordLastSession(startupDb, 'ws-start', 'conv-start-a');
+const resumed = resolveStartupIntent(startupDb);
+assert(resumed.mode === 'restore-last' && resumed.workspacePath === startupRoot && resumed.conversationId === 'conv-start-a', 'restore-last resumes the last workspace and conversation');
+recordLastSession(startupDb, 'ws-start', 'conv-start-other');
+assert(resolveStartupIntent(startupDb).conversationId === 'conv-start-b', 'restore-last falls back to the latest conversation of the workspace');
+setStartupSettings(startupDb, { mode: 'workspace', workspaceId: 'ws-start-2' });
+assert(resolveStartupIntent(startupDb).conversationId === 'conv-start-other', 'workspace mode opens the configured workspace');
+setStartupSettings(startupDb, { mode: 'workspace', workspaceId: 'ws-gone' });
+const missingStart = resolveStartupIntent(startupDb);
+assert(missingStart.workspaceId === null && missingStart.missingWorkspace?.id === 'ws-gone', 'a missing workspace folder starts blank and is reported');
+setStartupSettings(startupDb, { mode: 'blank' });
+assert(resolveStartupIntent(startupDb).conversationId === null, 'blank mode opens nothing');
+startupDb.close();
+fs.rmSync(startupRoot, { recursive: true });
+
+// Chore scheduling
+import { dismissChoreRun, isChoreDue, listChoreRuns, listChores } from './services/chores';
+const choresDb = new Database(':memory:');
+ensureSchema(choresDb);
+dbCreateWorkspace(choresDb, 'ws-chores', 'Chores', '/tmp/chores');
+assert(!saveChore(choresDb, { workspaceId: 'ws-chores', name: 'Bad', prompt: 'x', runAt: '25:00', enabled: true }).success, 'saveChore rejects times that are not HH:MM');
+const depsChore = saveChore(choresDb, { workspaceId: 'ws-chores', name: 'Deps', prompt: 'Check dependencies', runAt: '09:30', enabled: true }).chore!;
+saveChore(choresDb, { workspaceId: 'ws-chores', name: 'Early', prompt: 'Lint', runAt: '06:00', enabled: true });
+assert(listChores(choresDb, 'ws-chores').map((c) => c.runAt).join() === '06:00,09:30', 'listChores orders chores by time of day');
+const choreMorning = new Date(2026, 4, 20, 9, 0);
+const choreLater = new Date(2026, 4, 20, 10, 0);
+const epochOf = (date: Date) => String(Math.floor(date.getTime() / 1000));
+assert(!isChoreDue(depsChore, choreMorning) && isChoreDue(depsChore, choreLater), 'isChoreDue waits for the time of day');
+assert(!isChoreDue({ ...depsChore, lastRunAt: epochOf(new Date(2026, 4, 20, 9, 45)) }, choreLater), 'isChoreDue runs a chore once per day');
+assert(isChoreDue({ ...depsChore, lastRunAt: epochOf(new Date(2026, 4, 19, 9, 45)) }, choreLater), 'isChoreDue runs again the next day');
+assert(!isChoreDue({ ...depsChore, enabled: false }, choreLater), 'disabled chores are never due');
+const editedChore = saveChore(choresDb, { ...depsChore, runAt: '11:00' }).chore!;
+assert(editedChore.id === depsChore.id && listChores(choresDb).length === 2 && !isChoreDue(editedChore, choreLater), 'saveChore updates an existing chore in place');
+choresDb.prepare("INSERT INTO chore_runs (id, chore_id, workspace_id, status, plan, started_at) VALUES ('run-1', ?, 'ws-chores', 'draft', 'Plan', ?)").run(depsChore.id, epochOf(choreLater));
+dismissChoreRun(choresDb, 'run-1');
+assert(listChoreRuns(choresDb, 'ws-chores', 'dismissed').length === 1 && listChoreRuns(choresDb, 'ws-chores', 'draft').length === 0, 'dismissChoreRun retires a draft plan');
+choresDb.close();
+
+// Changelog prompt
+import { buildChangelogPrompt } from './services/changelog';
+const changelogPrompt = buildChangelogPrompt('v1.2.0', [
+    { hash: 'abc1234def', shortHash: 'abc1234', author: 'A', email: 'a@x', timestamp: 0, subject: 'Fix login redirect' },
+], [
+    { conversationTitle: 'Search', prompt: 'Add fuzzy\n  search to the sidebar ' + 'x'.repeat(400), timestamp: new Date().toISOString(), filesTouched: 3 },
+    { conversationTitle: 'Docs', prompt: 'Explain the API', timestamp: new Date().toISOString(), filesTouched: null },
+]);
+assert(changelogPrompt.includes('since v1.2.0') && changelogPrompt.includes('- abc1234 Fix login redirect'), 'buildChangelogPrompt lists commits by short hash');
+assert(changelogPrompt.includes('- [Search] Add fuzzy search to the sidebar') && changelogPrompt.includes('(3 files changed)') && changelogPrompt.endsWith('- [Docs] Explain the API'), 'buildChangelogPrompt summarizes turns with their file counts');
+assert(!changelogPrompt.includes('x'.repeat(400)), 'buildChangelogPrompt shortens long prompts');
+assert(buildChangelogPrompt('yesterday', [], []).includes('## Git commits\n(none)\n\n## Agent turns\n(none)'), 'buildChangelogPrompt marks empty sections');
+
+// Turn snapshots and rollback
+import { recordFileDiffs } from './services/diffs';
+import { captureTurnBaseline, getTurnSnapshot, listTurnSnapshots, readSnapshotOriginal, releaseTurnBaseline, rollbackTurn, snapshotFileChange } from './services/snapshots';
+const snapshotDb = new Database(':memory:');
+ensureSchema(snapshotDb);
+const snapshotRoot = fs.mkdtempSync(path.join(os.tmpdir(), 'codex-snapshot-'));
+dbCreateWorkspace(snapshotDb, 'ws-snap', 'Snapshots', snapshotRoot);
+dbCreateConversation(snapshotDb, 'conv-snap', 'ws-snap', 'Snapshots');
+const snapshotTurn = `turn-snapshot-${Date.now()}`;
+const snapshotPath = (name: string) => path.join(snapshotRoot, name);
+const snapshotGit = (...args: string[]) =>
+    execFileSync('git', ['-c', 'user.name=Test', '-c', 'user.email=test@example.com', ...args], { cwd: snapshotRoot });
+fs.mkdirSync(snapshotPath('src'));
+fs.writeFileSync(snapshotPath('src/app.ts'), 'original\n');
+fs.writeFileSync(snapshotPath('notes.md'), 'committed\n');
+snapshotGit('init', '-q');
+snapshotGit('add', '.');
+snapshotGit('commit', '-q', '-m', 'init');
+fs.writeFileSync(snapshotPath('notes.md'), 'uncommitted\n');
+const outsideFile = path.join(os.tmpdir(), `codex-snapshot-outside-${Date.now()}.txt`);
+fs.writeFileSync(outsideFile, 'not the turn\'s\n');
+pendingTests.push((async () => {
+    const baseline = await captureTurnBaseline(snapshotTurn, snapshotRoot);
+    // codex applies the patch first and reports the file_change only once it completed.
+    fs.writeFileSync(snapshotPath('src/app.ts'), 'changed by the turn\n');
+    fs.writeFileSync(snapshotPath('notes.md'), 'rewritten by the turn\n');
+    fs.writeFileSync(snapshotPath('src/new.ts'), 'created\n');
+    const fileChange = {
+        type: 'file_change',
+        status: 'completed',
+        changes: [
+            { path: 'src/app.ts', kind: 'update' },
+            { path: 'notes.md', kind: 'update' },
+            { path: 'src/new.ts', kind: 'add' },
+            { path: outsideFile, kind: 'update' },
+        ],
+    };
+    await snapshotFileChange(snapshotDb, snapshotTurn, 'conv-snap', snapshotRoot, fileChange);
+    releaseTurnBaseline(snapshotTurn, baseline);
+    assert(readSnapshotOriginal(snapshotDb, snapshotTurn, snapshotPath('src/app.ts')) === 'original\n', 'snapshotFileChange reads a clean file\'s pre-turn content from HEAD');
+    assert(readSnapshotOriginal(snapshotDb, snapshotTurn, snapshotPath('notes.md')) === 'uncommitted\n', 'snapshotFileChange keeps uncommitted edits recorded at turn start');
+    assert(readSnapshotOriginal(snapshotDb, snapshotTurn, snapshotPath('src/new.ts')) === null && readSnapshotOriginal(snapshotDb, snapshotTurn, outsideFile) === undefined, 'snapshotFileChange records added files as new and refuses paths outside the cwd');
+    const turnDiffs = recordFileDiffs(snapshotDb, snapshotTurn, 'conv-snap', snapshotRoot, fileChange);
+    const appDiff = turnDiffs.find((d) => d.path.endsWith('app.ts'));
+    assert(appDiff?.added === 1 && appDiff.removed === 1, 'recordFileDiffs diffs the turn\'s change against the pre-turn content');
+    const snapshotList = listTurnSnapshots(snapshotDb, 'conv-snap');
+    assert(snapshotList.length === 1 && snapshotList[0].files.map((f) => `${path.basename(f.path)}:${f.existed}`).join() === 'app.ts:true,notes.md:true,new.ts:false', 'listTurnSnapshots lists the turn and its files');
+    const rollback = rollbackTurn(snapshotDb, snapshotTurn);
+    assert(rollback.success && rollback.restored.length === 2 && rollback.removed.length === 1, 'rollbackTurn restores changed files and removes created ones');
+    assert(fs.readFileSync(snapshotPath('src/app.ts'), 'utf-8') === 'original\n' && fs.readFileSync(snapshotPath('notes.md'), 'utf-8') === 'uncommitted\n' && !fs.existsSync(snapshotPath('src/new.ts')), 'rollbackTurn round-trips the workspace to its pre-turn state');
+    assert(getTurnSnapshot(snapshotDb, snapshotTurn)?.status === 'rolled_back' && !rollbackTurn(snapshotDb, snapshotTurn).success, 'a turn can be rolled back once');
+    assert(rollbackTurn(snapshotDb, 'turn-unknown').error === 'Snapshot not found', 'rollbackTurn needs a snapshot');
+    snapshotDb.prepare("INSERT INTO turn_snapshots (id, conversation_id, cwd, created_at) VALUES ('turn-outside', 'conv-snap', ?, '1')").run(snapshotRoot);
+    snapshotDb.prepare("INSERT INTO turn_snapshot_files (turn_id, path, existed, backup_path) VALUES ('turn-outside', ?, 0, NULL)").run(outsideFile);
+    assert(!rollbackTurn(snapshotDb, 'turn-outside').success && fs.existsSync(outsideFile), 'rollbackTurn leaves files outside the turn\'s cwd alone');
+})().finally(() => {
+    snapshotDb.close();
+    fs.rmSync(snapshotRoot, { recursive: true });
+    fs.rmSync(outsideFile, { force: true });
+    fs.rmSync(path.join(homeDir()!, '.codex-wui', 'snapshots', snapshotTurn), { recursive: true, force: true });
+}));
+
+// Move conversation
+import { moveConversation } from './services/codex';
+import { dbUpdateConversationCliOverrides } from './services/db';
+const moveDb = new Database(':memory:');
+ensureSchema(moveDb);
+dbCreateWorkspace(moveDb, 'ws-move-a', 'Move A', '/tmp/move-a');
+dbCreateWorkspace(moveDb, 'ws-move-b', 'Move B', '/tmp/move-b');
+dbCreateConversation(moveDb, 'conv-move', 'ws-move-a', 'Moving');
+dbSetConversationSessionId(moveDb, 'conv-move', 'session-move');
+dbUpdateConversationCliOverrides(moveDb, 'conv-move', { cwdOverride: '/tmp/move-a/app' });
+const moveEvents: Array<{ channel: string; data: any }> = [];
+const moveWebContents = { send: (channel: string, data: any) => moveEvents.push({ channel, data }) } as any;
+let moveKilled = false;
+const moveState = { db: moveDb, runningCodex: new Map(), pendingApprovals: new Map(), tempDirs: new Map() } as any;
+moveState.runningCodex.set('conv-move', { child: { kill: () => { moveKilled = true; } }, turnId: 'turn-move' });
+const moveResult = moveConversation(moveWebContents, moveState, 'conv-move', 'ws-move-b', true);
+assert(moveResult.success && moveResult.cwd === '/tmp/move-b/app' && dbGetConversationOverrides(moveDb, 'conv-move').cliOverrides?.cwdOverride === '/tmp/move-b/app', 'moveConversation rebases a cwd override inside the old workspace');
+assert(moveResult.cancelled === true && moveKilled && !moveState.runningCodex.has('conv-move'), 'moveConversation cancels the running turn');
+assert(moveEvents.some((e) => e.channel === 'codex-conversation-moved' && e.data.cancelled === true), 'codex-conversation-moved reports the cancelled turn');
+assert(!dbGetConversationOverrides(moveDb, 'conv-move').sessionId, 'moveConversation clears the codex session');
+assert(moveConversation(moveWebContents, moveState, 'conv-move', 'ws-move-a', false).cancelled === false, 'moveConversation without a running turn cancels nothing');
+assert(!moveConversation(moveWebContents, moveState, 'conv-move', 'ws-missing', false).success, 'moveConversation needs an existing workspace');
+moveDb.close();
+
 // Cleanup test DB
 testDb.close();
 fs.unlinkSync(testDbPath);
@@ -225,7 +1178,7 @@ realDb.close();
 
 // ===== 4. FS Ops =====
 section('fs-ops.ts — File System Operations');
-import { searchFiles, readFileContent, writeFile, listDirectory, fileExists, openInEditor } from './services/fs-ops';
+import { closeFileIndexes, fuzzyScore as fuzzyPathScore, searchFiles, scanFileContents, readFileContent, readFileRange, detectEncoding, readFileHex, writeFile, restoreFileBackup, fileBackupsDir, listDirectory, fileExists, createDirectory, deletePath, renamePath, copyPath, movePath, openInEditor } from './services/fs-ops';
 
 const testDir = path.join(os.tmpdir(), `codex-fstest-${Date.now()}`);
 fs.mkdirSync(testDir, { recursive: true });
@@ -234,20 +1187,103 @@ fs.writeFileSync(path.join(testDir, 'readme.md'), '# Test');
 fs.mkdirSync(path.join(testDir, 'subdir'));
 fs.writeFileSync(path.join(testDir, 'subdir', 'nested.ts'), 'export const x = 1;');
 
-// searchFiles
-const searchResults = searchFiles(testDir, 'test');
-assert(searchResults.length >= 1, 'searchFiles finds files');
-assert(searchResults.some((r: any) => r.name === 'test.txt'), 'searchFiles finds test.txt');
+// searchFiles and scanFileContents (the fallback used without ripgrep) index files
+// asynchronously, so they run on a fixture of their own.
+const searchDir = fs.mkdtempSync(path.join(os.tmpdir(), 'codex-search-'));
+fs.cpSync(testDir, searchDir, { recursive: true });
+fs.writeFileSync(path.join(searchDir, 'subdir', 'usage.ts'), 'import { x } from "./nested";\nconsole.log(x);\nexport const y = x + 1;\n');
+pendingTests.push((async () => {
+    const searchResults = await searchFiles(searchDir, 'test');
+    assert(searchResults.length >= 1, 'searchFiles finds files');
+    assert(searchResults.some((r: any) => r.name === 'test.txt'), 'searchFiles finds test.txt');
+    assert((await searchFiles(searchDir, 'nstd'))[0]?.name === 'nested.ts', 'searchFiles matches fuzzily');
+    const contentMatches = (await scanFileContents(path.join(searchDir, 'subdir'), 'X', { contextLines: 1 })).filter((m) => m.relativePath.endsWith('usage.ts'));
+    assert(contentMatches.length === 3 && contentMatches[1].lineNumber === 2 && contentMatches[1].before[0].startsWith('import'), 'scanFileContents finds matches with context');
+    assert((await scanFileContents(path.join(searchDir, 'subdir'), 'X', { caseSensitive: true })).length === 0, 'scanFileContents honours case sensitivity');
+    assert((await scanFileContents(path.join(searchDir, 'subdir'), 'const \\w+ = x', { regex: true, glob: '*.ts' }))[0]?.column === 8, 'scanFileContents supports regex and glob');
+    fs.writeFileSync(path.join(searchDir, '.env'), 'API_KEY=secret');
+    closeFileIndexes();
+    assert(!(await searchFiles(searchDir, 'env')).some((r: any) => r.name === '.env'), 'searchFiles hides protected files from mentions');
+    assert((await scanFileContents(searchDir, 'API_KEY')).length === 0, 'scanFileContents skips protected files');
+    if (process.platform !== 'win32') {
+        fs.symlinkSync('subdir', path.join(searchDir, 'linked'));
+        fs.symlinkSync('..', path.join(searchDir, 'subdir', 'loop'));
+        assert((await searchFiles(path.join(searchDir, 'linked'), 'nested')).length >= 1, 'searchFiles terminates on symlink cycles');
+    }
+})().finally(() => {
+    closeFileIndexes();
+    fs.rmSync(searchDir, { recursive: true, force: true });
+}));
+assert(fuzzyPathScore('rdm', 'readme.md') !== null && fuzzyPathScore('xyz', 'readme.md') === null, 'fs-ops fuzzyScore requires an in-order subsequence');
+assert(fuzzyPathScore('fs', 'fs-ops.ts')! > fuzzyPathScore('fs', 'refs.ts')!, 'fs-ops fuzzyScore prefers prefix and boundary matches');
+fs.writeFileSync(path.join(testDir, 'subdir', 'usage.ts'), 'import { x } from "./nested";\nconsole.log(x);\nexport const y = x + 1;\n');
 
 // readFileContent
 const readResult = readFileContent(path.join(testDir, 'test.txt'), testDir);
 assert(readResult.success === true, 'readFileContent succeeds');
 assert(readResult.content === 'Hello World', 'readFileContent reads correct content');
+fs.writeFileSync(path.join(testDir, 'blob.bin'), Buffer.from([0x89, 0x50, 0x00, 0x01]));
+const binaryRead = readFileContent('blob.bin', testDir);
+assert(binaryRead.success && binaryRead.binary === true && binaryRead.content === undefined, 'readFileContent flags binary files');
+assert(detectEncoding(Buffer.from([0xff, 0xfe, 0x41, 0x00])) === 'utf-16le' && detectEncoding(Buffer.from('caf\xe9', 'latin1')) === 'latin1', 'detectEncoding reads BOMs and Latin-1');
+fs.writeFileSync(path.join(testDir, 'multi.txt'), 'aé한b'.repeat(50));
+let rangeOffset: number | null = 0;
+let rangeText = '';
+while (rangeOffset !== null) {
+    const range = readFileRange('multi.txt', rangeOffset, 7, testDir).range!;
+    rangeText += range.content;
+    rangeOffset = range.nextOffset;
+}
+assert(rangeText === 'aé한b'.repeat(50), 'readFileRange pages without splitting characters');
+const midRange = readFileRange('multi.txt', 2, 16, testDir).range!;
+assert(midRange.offset === 3 && midRange.totalSize === 350 && midRange.encoding === 'utf-8', 'readFileRange aligns to character boundaries');
+
+// readFileHex
+fs.writeFileSync(path.join(testDir, 'blob.bin'), Buffer.from([...Array(20).keys()].map((i) => (i === 1 ? 0x41 : i))));
+const hexPage = readFileHex(path.join(testDir, 'blob.bin'), 0, 16, testDir).page!;
+assert(hexPage.rows.length === 1 && hexPage.rows[0].hex.startsWith('00 41 02') && hexPage.rows[0].ascii.startsWith('.A.'), 'readFileHex formats hex and ASCII');
+assert(hexPage.nextOffset === 16 && hexPage.totalSize === 20, 'readFileHex paginates');
+const hexTail = readFileHex(path.join(testDir, 'blob.bin'), 17, 16, testDir).page!;
+assert(hexTail.offset === 16 && hexTail.length === 4 && hexTail.nextOffset === null, 'readFileHex aligns offsets and stops at EOF');
+assert(readFileHex('/etc/hosts', 0, 16, testDir).success === false, 'readFileHex stays inside the workspace');
+
+// Protected paths
+import { allowProtectedFile, DEFAULT_PROTECTED_PATTERNS, isProtectedPath, revokeProtectedFile } from './services/fs-ops';
+const protectedDefaults = { patterns: DEFAULT_PROTECTED_PATTERNS, allowed: [] };
+assert(isProtectedPath('/ws/.env', '/ws', protectedDefaults) && isProtectedPath('/ws/app/.env.local', '/ws', protectedDefaults), 'isProtectedPath matches .env files at any depth');
+assert(!isProtectedPath('/ws/.env.example', '/ws', protectedDefaults), 'isProtectedPath honours negated patterns');
+assert(isProtectedPath('/home/u/.ssh/id_rsa', undefined, protectedDefaults) && isProtectedPath('/ws/certs/server.pem', '/ws', protectedDefaults), 'isProtectedPath matches keys');
+assert(!isProtectedPath('/ws/src/env.ts', '/ws', protectedDefaults), 'isProtectedPath leaves ordinary files alone');
+assert(isProtectedPath('/ws/config/prod.yml', '/ws', { patterns: ['config/*.yml'], allowed: [] }), 'isProtectedPath supports workspace-relative globs');
+fs.writeFileSync(path.join(testDir, '.env'), 'API_KEY=secret');
+const protectedRead = readFileContent('.env', testDir);
+assert(!protectedRead.success && protectedRead.protected === true && protectedRead.content === undefined, 'readFileContent refuses protected files');
+assert(readFileRange('.env', 0, 16, testDir).protected === true && readFileHex('.env', 0, 16, testDir).protected === true, 'ranged reads refuse protected files');
+const protectedDb = new Database(':memory:');
+ensureSchema(protectedDb);
+assert(allowProtectedFile(protectedDb, '.env', testDir).success && readFileContent('.env', testDir).content === 'API_KEY=secret', 'allowProtectedFile lets one file through');
+revokeProtectedFile(protectedDb, fs.realpathSync(path.join(testDir, '.env')));
+assert(readFileContent('.env', testDir).protected === true, 'revokeProtectedFile protects the file again');
+protectedDb.close();
+fs.rmSync(path.join(testDir, '.env'));
 
 // writeFile
 const writeResult = writeFile(path.join(testDir, 'new.txt'), 'New Content', testDir);
 assert(writeResult.success === true, 'writeFile succeeds');
 assert(fs.readFileSync(path.join(testDir, 'new.txt'), 'utf-8') === 'New Content', 'writeFile writes correct content');
+if (process.platform !== 'win32') {
+    fs.chmodSync(path.join(testDir, 'new.txt'), 0o755);
+    writeFile('new.txt', 'Rewritten', testDir);
+    assert((fs.statSync(path.join(testDir, 'new.txt')).mode & 0o777) === 0o755, 'writeFile keeps file permissions');
+}
+assert(!fs.readdirSync(testDir).some((name) => name.endsWith('.tmp')), 'writeFile leaves no temp files');
+const backedUp = writeFile('new.txt', 'Replaced', testDir, { createBackup: true });
+assert(backedUp.success && !!backedUp.backupId, 'writeFile can back up the previous content');
+assert(restoreFileBackup(backedUp.backupId!, testDir).success, 'restoreFileBackup succeeds');
+assert(fs.readFileSync(path.join(testDir, 'new.txt'), 'utf-8') === 'Rewritten', 'restoreFileBackup restores the previous content');
+assert(restoreFileBackup('../escape', testDir).success === false, 'restoreFileBackup rejects invalid ids');
+fs.rmSync(path.join(fileBackupsDir(), `${backedUp.backupId}.bak`), { force: true });
+fs.rmSync(path.join(fileBackupsDir(), `${backedUp.backupId}.json`), { force: true });
 
 // listDirectory
 const dirResult = listDirectory(testDir, testDir);
@@ -255,20 +1291,205 @@ assert(dirResult.success === true, 'listDirectory succeeds');
 assert(dirResult.entries!.length >= 3, 'listDirectory returns entries');
 assert(dirResult.entries!.some((e: any) => e.name === 'subdir' && e.isDirectory), 'listDirectory finds subdir');
 assert(dirResult.entries!.some((e: any) => e.name === 'test.txt' && !e.isDirectory), 'listDirectory finds file');
+if (process.platform !== 'win32') {
+    fs.symlinkSync('subdir', path.join(testDir, 'linked'));
+    fs.symlinkSync('..', path.join(testDir, 'subdir', 'loop'));
+    fs.chmodSync(path.join(testDir, 'readme.md'), 0o444);
+    const followed = listDirectory(testDir, testDir).entries!;
+    const link = followed.find((e) => e.name === 'linked')!;
+    assert(link.fileType === 'symlink' && link.symlinkTarget === 'subdir' && link.isDirectory, 'listDirectory follows symlinks');
+    assert(!listDirectory(testDir, testDir, { followSymlinks: false }).entries!.find((e) => e.name === 'linked')!.isDirectory, 'listDirectory can describe links without following');
+    assert(followed.find((e) => e.name === 'readme.md')!.readonly && followed.find((e) => e.name === 'readme.md')!.mode === 0o444, 'listDirectory reports mode and readonly');
+    fs.chmodSync(path.join(testDir, 'readme.md'), 0o644);
+}
 
 // fileExists
 assert(fileExists(path.join(testDir, 'test.txt'), testDir) === true, 'fileExists returns true for existing');
 assert(fileExists(path.join(testDir, 'nonexistent.txt'), testDir) === false, 'fileExists returns false for missing');
 
+// file management
+assert(createDirectory('manage/deep', testDir).success && fs.statSync(path.join(testDir, 'manage', 'deep')).isDirectory(), 'createDirectory creates parents');
+assert(!createDirectory('manage', testDir).success, 'createDirectory refuses existing paths');
+assert(!createDirectory('../outside-dir', testDir).success, 'createDirectory stays inside the workspace');
+fs.writeFileSync(path.join(testDir, 'manage', 'deep', 'a.txt'), 'a');
+assert(renamePath('manage/deep/a.txt', 'b.txt', testDir).success && fs.existsSync(path.join(testDir, 'manage', 'deep', 'b.txt')), 'renamePath renames in place');
+assert(!renamePath('manage/deep/b.txt', '../b.txt', testDir).success, 'renamePath rejects names with separators');
+assert(copyPath('manage/deep', 'manage/copy', testDir).success && fs.readFileSync(path.join(testDir, 'manage', 'copy', 'b.txt'), 'utf-8') === 'a', 'copyPath copies directories');
+assert(!copyPath('manage/deep', 'manage/copy', testDir).success, 'copyPath does not overwrite by default');
+assert(!copyPath('manage', 'manage/deep/inner', testDir).success, 'copyPath refuses copying a directory into itself');
+assert(movePath('manage/copy/b.txt', 'manage/moved.txt', testDir).success && !fs.existsSync(path.join(testDir, 'manage', 'copy', 'b.txt')), 'movePath moves files');
+assert(!movePath('manage/moved.txt', '/tmp/escaped.txt', testDir).success, 'movePath stays inside the workspace');
+deletePath('manage/deep', testDir);
+assert(fs.existsSync(path.join(testDir, 'manage', 'deep', 'b.txt')), 'deletePath keeps non-empty directories without recursive');
+deletePath('manage', testDir, { recursive: true });
+assert(!fs.existsSync(path.join(testDir, 'manage')), 'deletePath removes directories recursively');
+deletePath('.', testDir, { recursive: true });
+assert(fs.existsSync(testDir), 'deletePath refuses the workspace root');
+
+// archives
+import { archiveTargetPath, extractArchive, listArchive } from './services/archives';
+import { execFileSync } from 'child_process';
+function storedZip(files: Array<[string, string]>): Buffer {
+    const locals: Buffer[] = [];
+    const centrals: Buffer[] = [];
+    let offset = 0;
+    for (const [name, text] of files) {
+        const nameBuf = Buffer.from(name);
+        const body = Buffer.from(text);
+        const local = Buffer.alloc(30);
+        local.writeUInt32LE(0x04034b50, 0);
+        local.writeUInt32LE(body.length, 18);
+        local.writeUInt32LE(body.length, 22);
+        local.writeUInt16LE(nameBuf.length, 26);
+        const central = Buffer.alloc(46);
+        central.writeUInt32LE(0x02014b50, 0);
+        central.writeUInt32LE(body.length, 20);
+        central.writeUInt32LE(body.length, 24);
+        central.writeUInt16LE(nameBuf.length, 28);
+        central.writeUInt32LE(offset, 42);
+        locals.push(local, nameBuf, body);
+        centrals.push(central, nameBuf);
+        offset += 30 + nameBuf.length + body.length;
+    }
+    const centralDir = Buffer.concat(centrals);
+    const end = Buffer.alloc(22);
+    end.writeUInt32LE(0x06054b50, 0);
+    end.writeUInt16LE(files.length, 8);
+    end.writeUInt16LE(files.length, 10);
+    end.writeUInt32LE(centralDir.length, 12);
+    end.writeUInt32LE(offset, 16);
+    return Buffer.concat([...locals, centralDir, end]);
+}
+fs.writeFileSync(path.join(testDir, 'bundle.zip'), storedZip([['docs/a.txt', 'alpha'], ['docs/b.txt', 'beta'], ['../evil.txt', 'x']]));
+const zipListing = listArchive('bundle.zip', testDir);
+assert(zipListing.format === 'zip' && zipListing.entries!.length === 3 && zipListing.entries![0].size === 5, 'listArchive lists zip entries');
+const zipExtract = extractArchive('bundle.zip', 'out', testDir, ['docs/a.txt', '../evil.txt']);
+assert(zipExtract.success && zipExtract.extracted!.length === 1 && fs.readFileSync(path.join(testDir, 'out', 'docs', 'a.txt'), 'utf-8') === 'alpha', 'extractArchive extracts selected members');
+assert(zipExtract.skipped![0].name === '../evil.txt' && !fs.existsSync(path.join(testDir, 'evil.txt')), 'extractArchive blocks zip-slip');
+assert(archiveTargetPath('/dest', '/etc/passwd') === null && archiveTargetPath('/dest', 'C:/x') === null, 'archiveTargetPath rejects absolute paths');
+let tarAvailable = true;
+try {
+    execFileSync('tar', ['-czf', 'bundle.tar.gz', 'subdir'], { cwd: testDir });
+} catch {
+    tarAvailable = false;
+}
+if (tarAvailable) {
+    const tarListing = listArchive('bundle.tar.gz', testDir);
+    assert(tarListing.format === 'tar.gz' && !!tarListing.entries?.some((e) => e.name.endsWith('nested.ts') && e.type === 'file'), 'listArchive lists tar.gz entries');
+    assert(extractArchive('bundle.tar.gz', 'untar', testDir).success && fs.existsSync(path.join(testDir, 'untar', 'subdir', 'nested.ts')), 'extractArchive extracts tar.gz');
+}
+
+// AGENTS.md management
+import { appendConventions, createStarterInstructions, instructionsStatus, readInstructions } from './services/instructions';
+const instrRoot = path.join(testDir, 'instr');
+fs.mkdirSync(path.join(instrRoot, '.git'), { recursive: true });
+fs.mkdirSync(path.join(instrRoot, 'app'));
+fs.writeFileSync(path.join(instrRoot, 'Cargo.toml'), '[package]\nname = "instr"\n');
+const starter = createStarterInstructions(instrRoot);
+assert(starter.success && starter.content!.includes('`cargo test`') && !createStarterInstructions(instrRoot).success, 'createStarterInstructions writes a starter once');
+appendConventions(instrRoot, '- Use snake_case.');
+appendConventions(instrRoot, '- No unwrap in library code.');
+assert(/## Team conventions\n\n- Use snake_case\.\n\n- No unwrap/.test(readInstructions(instrRoot).content!), 'appendConventions adds to one conventions section');
+fs.writeFileSync(path.join(instrRoot, 'app', 'AGENTS.override.md'), '# App');
+fs.writeFileSync(path.join(instrRoot, 'app', 'AGENTS.md'), '# Ignored');
+const instrStatus = instructionsStatus(path.join(instrRoot, 'app'));
+const projectInstructions = instrStatus.files.filter((f) => f.scope === 'project').map((f) => path.relative(instrRoot, f.path));
+assert(instrStatus.projectRoot === instrRoot && projectInstructions.join(',') === `AGENTS.md,${path.join('app', 'AGENTS.override.md')}`, 'instructionsStatus lists the files codex reads from the git root down');
+
+// Workspace summary
+import { buildSummaryPrompt, freshWorkspaceSummary, getWorkspaceSummary, readProjectSources } from './services/workspace-summary';
+const summarySources = readProjectSources(instrRoot);
+assert(summarySources.entries.join() === 'AGENTS.md,Cargo.toml,app/' && summarySources.manifests[0]?.name === 'Cargo.toml' && !summarySources.readme, 'readProjectSources lists manifests and visible top-level entries');
+fs.writeFileSync(path.join(instrRoot, 'README.md'), '# instr\nParses invoices.');
+const summarySourcesWithReadme = readProjectSources(instrRoot);
+assert(summarySourcesWithReadme.fingerprint !== summarySources.fingerprint && summarySourcesWithReadme.readme?.name === 'README.md', 'readProjectSources fingerprint changes with the README');
+assert(buildSummaryPrompt('instr', summarySourcesWithReadme).includes('Parses invoices.'), 'buildSummaryPrompt includes the README');
+const summaryDb = new Database(':memory:');
+ensureSchema(summaryDb);
+assert(getWorkspaceSummary(summaryDb, 'ws-instr') === null && freshWorkspaceSummary(summaryDb, 'ws-instr', instrRoot) === undefined, 'no summary before one is generated');
+summaryDb.prepare('INSERT INTO settings (key, value) VALUES (?, ?)').run('workspaceSummary.ws-instr', JSON.stringify({
+    summary: 'An invoice parser.', fingerprint: summarySourcesWithReadme.fingerprint, generatedAt: new Date().toISOString(),
+}));
+assert(freshWorkspaceSummary(summaryDb, 'ws-instr', instrRoot) === 'An invoice parser.', 'freshWorkspaceSummary returns a matching cached summary');
+fs.writeFileSync(path.join(instrRoot, 'Cargo.toml'), '[package]\nname = "instr"\nversion = "0.2.0"\n');
+assert(freshWorkspaceSummary(summaryDb, 'ws-instr', instrRoot) === undefined, 'freshWorkspaceSummary skips a summary of changed files');
+summaryDb.close();
+
+// Prompt templates
+import { listPromptTemplates, renderPromptTemplate, savePromptTemplate, templatePlaceholders } from './services/templates';
+assert(templatePlaceholders('{{file}} {{ tone }} {{file}}').join() === 'file,tone', 'templatePlaceholders lists distinct names');
+const promptDb = new Database(':memory:');
+ensureSchema(promptDb);
+dbCreateWorkspace(promptDb, 'ws-prompt', 'Prompt WS', testDir);
+fs.writeFileSync(path.join(testDir, 'notes.txt'), 'first line\n');
+assert(!savePromptTemplate(promptDb, { name: ' ', body: 'x' }).success && !savePromptTemplate(promptDb, { name: 'Bad', body: 'x', variables: ['a b'] }).success, 'savePromptTemplate validates the name and variables');
+const reviewTemplate = savePromptTemplate(promptDb, { name: 'Review', body: 'Review {{file}} in {{workspace}} for {{focus}}.\n{{selection}}', variables: ['focus'] }).template!;
+assert(reviewTemplate.variables.join() === 'focus' && listPromptTemplates(promptDb).length === 1, 'savePromptTemplate stores custom variables only');
+const renderedPrompt = renderPromptTemplate(promptDb, reviewTemplate.id, { file: 'notes.txt', focus: 'typos', selection: 'line' }, 'ws-prompt');
+assert(renderedPrompt.text === 'Review notes.txt:\n```\nfirst line\n``` in Prompt WS for typos.\nline', 'renderPromptTemplate reads the file and fills in values');
+assert(renderPromptTemplate(promptDb, reviewTemplate.id, { file: 'notes.txt' }, 'ws-prompt').missing?.join() === 'focus,selection', 'renderPromptTemplate reports missing values');
+assert(!renderPromptTemplate(promptDb, reviewTemplate.id, { file: '.env', focus: 'x', selection: '' }, 'ws-prompt').success, 'renderPromptTemplate refuses protected files');
+assert(renderPromptTemplate(promptDb, reviewTemplate.id, { focus: 'x' }).error === 'This template needs a workspace', 'renderPromptTemplate needs a workspace for {{file}}');
+promptDb.close();
+
+// @-mentions
+import { attachMentionedContext, findMentions } from './services/mentions';
+assert(findMentions('see @notes.txt, @src/ and me@example.com (@notes.txt)').join() === 'notes.txt,src/', 'findMentions skips e-mail addresses and duplicates');
+fs.mkdirSync(path.join(testDir, 'mention-dir', 'node_modules'), { recursive: true });
+fs.writeFileSync(path.join(testDir, 'mention-dir', 'a.ts'), 'export const a = 1;\n');
+fs.writeFileSync(path.join(testDir, 'mention-dir', 'blob.bin'), Buffer.from([0, 1, 2, 0, 255]));
+fs.writeFileSync(path.join(testDir, 'mention-dir', 'big.txt'), 'x'.repeat(40 * 1024));
+const attached = attachMentionedContext('Fix @mention-dir/a.ts using @mention-dir/ and @nobody', testDir);
+assert(attached.mentions.length === 2 && attached.mentions.every((m) => m.included), 'attachMentionedContext reports files and directories, not plain words');
+assert(attached.prompt.includes('### mention-dir/a.ts\n```\nexport const a = 1;\n```') && attached.prompt.includes('### mention-dir/ (directory)\na.ts (20 B)'), 'attachMentionedContext inlines files and lists directories');
+assert(!attached.prompt.includes('node_modules'), 'attachMentionedContext leaves build directories out of listings');
+const skippedMentions = attachMentionedContext('@mention-dir/blob.bin @mention-dir/big.txt @.env', testDir).mentions.map((m) => m.skipped).join();
+assert(skippedMentions === 'binary,too-large,protected', 'attachMentionedContext skips binary, large and protected files');
+assert(attachMentionedContext('plain prompt', testDir).prompt === 'plain prompt', 'attachMentionedContext leaves prompts without mentions alone');
+
 // Cleanup
+closeFileIndexes();
 fs.rmSync(testDir, { recursive: true });
 
 // ===== 5. Auth =====
 section('auth.ts — Authentication');
-import { codexLoginMethods, getUser } from './services/auth';
+import { cancelLogin, codexLoginMethods, extractLoginPrompt, getUser, parseCodexUser, scrubApiKey } from './services/auth';
 
 const loginMethods = codexLoginMethods();
 assert(Array.isArray(loginMethods), 'codexLoginMethods returns array');
+assert(scrubApiKey('invalid key: my-custom-key-value', 'my-custom-key-value') === 'invalid key: [REDACTED]', 'scrubApiKey removes the entered key');
+assert(scrubApiKey('bad sk-abcdefghijklmnopqrstu') === 'bad [REDACTED]', 'scrubApiKey removes key-like strings');
+const fakeJwt = (payload: any) => `h.${Buffer.from(JSON.stringify(payload)).toString('base64url')}.s`;
+const chatgptAuth = { auth_mode: 'chatgpt', tokens: { id_token: fakeJwt({ email: 'dev@example.com', exp: 1000 }), access_token: fakeJwt({ exp: 2000 }) } };
+const chatgptUser = parseCodexUser(chatgptAuth, 1_500_000)!;
+assert(chatgptUser.email === 'dev@example.com' && chatgptUser.expiresAt === new Date(2_000_000).toISOString() && !chatgptUser.expired, 'parseCodexUser reads the access token expiry');
+assert(parseCodexUser(chatgptAuth, 2_000_000)!.expired, 'parseCodexUser flags expired credentials');
+const apiKeyUser = parseCodexUser({ auth_mode: 'api_key', tokens: { id_token: fakeJwt({ exp: 1 }) } })!;
+assert(!apiKeyUser.expired && apiKeyUser.expiresAt === undefined, 'parseCodexUser never expires API keys');
+const browserPrompt = extractLoginPrompt('Starting local login server on http://localhost:1455.\nIf your browser did not open, navigate to this URL to authenticate:\n\n\x1B[94mhttps://auth.openai.com/oauth/authorize?client_id=app&state=x\x1B[0m\n');
+assert(browserPrompt.url === 'https://auth.openai.com/oauth/authorize?client_id=app&state=x' && !browserPrompt.code, 'extractLoginPrompt finds the sign-in URL');
+assert(extractLoginPrompt('Open https://auth.openai.com/codex/device and enter code ABCD-1234').code === 'ABCD-1234', 'extractLoginPrompt finds the device code');
+assert(!cancelLogin().success, 'cancelLogin reports when no login is running');
+
+// Secrets
+import { getSecretForRenderer, initSecrets, rememberSecret, setSecretSettings } from './services/secrets';
+const secretsDb = new Database(':memory:');
+ensureSchema(secretsDb);
+initSecrets(secretsDb);
+assert(!getSecretForRenderer('bad name').success, 'secret names are validated');
+assert(!rememberSecret('github.token', 'ghp_x'), 'rememberSecret keeps secrets out of the keychain until it is turned on');
+assert(setSecretSettings(secretsDb, { useKeychain: true }).useKeychain, 'setSecretSettings turns the keychain on');
+assert(setSecretSettings(secretsDb, {}).useKeychain, 'a partial setSecretSettings update keeps the keychain setting');
+const apiKeySecret = getSecretForRenderer('codex.apiKey');
+assert(apiKeySecret.success && !('value' in apiKeySecret), 'codex secrets are never returned to the renderer');
+assert(!('value' in getSecretForRenderer('github.token')) && !('value' in getSecretForRenderer('webhook.wh1')), 'GitHub and webhook secrets only report whether they exist');
+setSecretSettings(secretsDb, { useKeychain: false });
+secretsDb.close();
+
+// Accounts
+import { removeAccount } from './services/accounts';
+assert(removeAccount('../outside').error?.startsWith('Account names may only use') === true, 'account names cannot leave the accounts directory');
+assert(removeAccount(`missing-${Date.now()}`).error?.startsWith('No account named') === true, 'removeAccount reports unknown accounts');
 
 // getUser — may return null if not logged in, but should not throw
 try {
@@ -280,7 +1501,7 @@ try {
 
 // ===== 6. Shell =====
 section('shell.ts — Command Execution');
-import { runCommand, killCommand } from './services/shell';
+import { startCommand, commandWriteStdin, killCommand } from './services/shell';
 
 // Fake webContents for testing
 const fakeWebContents = {
@@ -293,15 +1514,51 @@ const shellTestState: AppState = {
     runningCodex: new Map(),
     pendingApprovals: new Map(),
     ptyTerminals: new Map(),
+    turnFileChanges: new Map(),
+    turnUsage: new Map(),
+    promptQueues: new Map(),
+    maxConcurrentTurns: 0,
+    tempDirs: new Map(),
+    pendingReviews: new Map(),
+    runningCommands: new Map(),
+    streamBuffer: { lastSeq: 0, conversations: new Map() },
 };
 
-const cmdResult = runCommand(fakeWebContents, 'echo "hello from shell test"', process.cwd(), shellTestState);
-assert(cmdResult.success === true, 'runCommand succeeds');
-assert(typeof cmdResult.commandId === 'string', 'runCommand returns commandId');
+const started = startCommand(fakeWebContents, 'cat', process.cwd(), shellTestState);
+assert(typeof started.commandId === 'string', 'startCommand returns commandId');
+assert(shellTestState.runningCommands.has(started.commandId), 'startCommand registers running command');
+assert(commandWriteStdin(shellTestState, started.commandId, 'hello\n').success === true, 'commandWriteStdin writes to running command');
+assert(killCommand(shellTestState, started.commandId).success === true, 'killCommand stops running command');
 
 // killCommand for non-existent — should not throw
-const killResult = killCommand('nonexistent-id');
-assert(killResult.success === false || killResult.success === true, 'killCommand handles missing id');
+const killResult = killCommand(shellTestState, 'nonexistent-id');
+assert(killResult.success === false, 'killCommand handles missing id');
+
+// Run comparison
+import { compareRunOutputs, parseTestResults } from './services/runs';
+const parsedTests = parseTestResults('test a::b ... ok\ntest a::c ... FAILED\n--- FAIL: TestX (0.1s)\nFAILED tests/t.py::test_z - assert\n  ✓ adds (3 ms)\n  ✕ divides (12 ms)');
+assert(parsedTests.passed.join(',') === 'a::b,adds', 'parseTestResults reads passing cargo and jest tests');
+assert(parsedTests.failed.join(',') === 'a::c,TestX,tests/t.py::test_z,divides', 'parseTestResults reads failing cargo, go, pytest and jest tests');
+const baseRun = { command: 'npm test', cwd: '/tmp', startedAt: '1', finishedAt: '2', errorOutput: '' };
+const runDelta = compareRunOutputs(
+    { ...baseRun, commandId: 'cmd-a', exitCode: 1, output: '  ✕ divides (12 ms)\n  ✕ rounds (3 ms)\n  ✓ adds (1 ms)\nDone in 1.2s' },
+    { ...baseRun, commandId: 'cmd-b', exitCode: 1, output: '  ✓ divides (9 ms)\n  ✕ rounds (4 ms)\n  ✕ adds (1 ms)\nDone in 0.8s' },
+);
+assert(runDelta.fixedFailures.join() === 'divides' && runDelta.stillFailing.join() === 'rounds' && runDelta.newFailures.join() === 'adds', 'compareRunOutputs finds fixed, still failing and new failures');
+assert(!runDelta.addedLines.some((l) => l.includes('Done')), 'compareRunOutputs ignores changed durations');
+assert(runDelta.summary === '1 fixed, 1 new failure, 1 still failing', 'compareRunOutputs summarizes the delta');
+import { listAvailableShells, resolveShell } from './services/shell';
+const winEnv = { Path: 'C:\\Windows\\System32', SystemRoot: 'C:\\Windows', ComSpec: 'C:\\Windows\\System32\\cmd.exe', ProgramFiles: 'C:\\Program Files' };
+const winFiles = new Set(['C:\\Windows\\System32\\cmd.exe', 'C:\\Program Files\\Git\\bin\\bash.exe']);
+const winShells = listAvailableShells('win32', winEnv, (p) => winFiles.has(p));
+assert(winShells.map((s) => s.name).join(',') === 'cmd,git-bash', 'listAvailableShells skips missing PowerShell on Windows');
+const winFallback = resolveShell('powershell', 'win32', winEnv, (p) => winFiles.has(p));
+assert(winFallback?.name === 'cmd' && winFallback.fallbackFrom === 'powershell', 'resolveShell falls back to cmd and reports it');
+const unixFiles = new Set(['/usr/bin/bash', '/bin/sh']);
+const unixShells = listAvailableShells('linux', { PATH: '/usr/bin:/bin' }, (p) => unixFiles.has(p));
+assert(unixShells.map((s) => `${s.name}=${s.path}`).join(',') === 'bash=/usr/bin/bash,sh=/bin/sh', 'listAvailableShells finds Unix shells on PATH in order');
+assert(resolveShell('sh', 'linux', { PATH: '/usr/bin:/bin' }, (p) => unixFiles.has(p))?.path === '/bin/sh', 'resolveShell honours an available requested shell');
+assert(resolveShell(undefined, 'linux', { PATH: '' }, () => false) === null, 'resolveShell returns null when no shell exists');
 
 // ===== 7. Teams =====
 section('teams.ts — MS Teams Integration');
@@ -317,7 +1574,10 @@ assert(true, 'sendToTeams called without crash');
 
 // ===== 8. Codex Service =====
 section('codex.ts — Codex CLI Integration');
-import { setMode, getMode, setYoloMode, getYoloMode, getModels, setModel, getModel, setCliOptions, getCliOptions, checkCodex, initAcp, switchWorkspace, debugLog, updateTitleBarOverlay } from './services/codex';
+import { getCancelGracePeriod, setCancelGracePeriod } from './services/codex';
+import { setMode, getMode, setYoloMode, getYoloMode, getModels, getCustomModels, setCustomModels, setModel, getModel, setCliOptions, getCliOptions, checkCodex, initAcp, switchWorkspace, debugLog, updateTitleBarOverlay } from './services/codex';
+
+import { dbSetSetting } from './services/db';
 
 const testState: AppState = {
     config: { ...testConfig },
@@ -325,8 +1585,21 @@ const testState: AppState = {
     runningCodex: new Map(),
     pendingApprovals: new Map(),
     ptyTerminals: new Map(),
+    turnFileChanges: new Map(),
+    turnUsage: new Map(),
+    promptQueues: new Map(),
+    maxConcurrentTurns: 0,
+    tempDirs: new Map(),
+    pendingReviews: new Map(),
+    runningCommands: new Map(),
+    streamBuffer: { lastSeq: 0, conversations: new Map() },
 };
 
+// Cancel grace period
+assert(getCancelGracePeriod(testState) === 3000, 'getCancelGracePeriod defaults to 3s');
+assert(setCancelGracePeriod(testState, 60000) === 30000, 'setCancelGracePeriod clamps to 30s');
+assert(setCancelGracePeriod(testState, -5) === 0 && getCancelGracePeriod(testState) === 0, 'setCancelGracePeriod allows 0 (kill immediately)');
+
 // Mode
 assert(setMode(testState, 'planning') === 'planning', 'setMode returns new mode');
 assert(getMode(testState) === 'planning', 'getMode returns correct mode');
@@ -338,8 +1611,12 @@ setYoloMode(testState, false);
 assert(getYoloMode(testState) === false, 'setYoloMode/getYoloMode round trip');
 
 // Models
-const codexModels = getModels();
-assert(codexModels.length > 0, 'getModels returns models');
+dbSetSetting(testState.db, 'models.cache', { models: [{ id: 'live-model', name: 'Live', description: '' }], fetchedAt: Date.now() });
+setCustomModels(testState, [{ id: 'my-finetune', name: '', description: 'Custom' }]);
+const codexModels = getModels(testState);
+assert(codexModels.some((m) => m.id === 'live-model'), 'getModels returns cached live models');
+assert(codexModels.some((m) => m.id === 'my-finetune' && m.name === 'my-finetune'), 'getModels merges custom models');
+assert(getCustomModels(testState).length === 1, 'getCustomModels round trip');
 
 assert(setModel(testState, 'o4-mini') === 'o4-mini', 'setModel works');
 assert(getModel(testState) === 'o4-mini', 'getModel returns set model');
@@ -354,8 +1631,9 @@ const retrievedOpts = getCliOptions(testState);
 assert(retrievedOpts.profile === 'test-profile', 'getCliOptions returns merged options');
 
 // Check codex
-const codexCheck = checkCodex();
-assert(typeof codexCheck.installed === 'boolean', 'checkCodex returns { installed: boolean }');
+pendingTests.push(checkCodex().then((codexCheck) => {
+    assert(typeof codexCheck.installed === 'boolean', 'checkCodex returns { installed: boolean }');
+}));
 if (codexCheck.installed) {
     console.log('    ℹ️  Codex CLI is installed');
 } else {
@@ -379,19 +1657,307 @@ assert(true, 'debugLog does not throw');
 const overlayResult = updateTitleBarOverlay('#000', '#fff');
 assert(overlayResult.success === true, 'updateTitleBarOverlay returns success');
 
+// Stream snapshot
+import { getStreamSnapshot } from './services/codex';
+assert(!getStreamSnapshot(testState, 'conv-idle').running && getStreamSnapshot(testState, 'conv-idle').toolCalls.length === 0, 'getStreamSnapshot is empty without a running turn');
+const snapshotStream = new StreamParseCache();
+snapshotStream.appendAgentText('Hello');
+testState.runningCodex.set('conv-live', { child: null as any, stdin: null, turnId: 'turn-live', stream: snapshotStream });
+testState.pendingApprovals.set('req-live', { conversationId: 'conv-live', request: { requestId: 'req-live', title: 'Run ls', description: 'ls' } });
+const liveSnapshot = getStreamSnapshot(testState, 'conv-live');
+assert(liveSnapshot.running && liveSnapshot.turnId === 'turn-live' && liveSnapshot.text === 'Hello', 'getStreamSnapshot returns buffered text');
+assert(liveSnapshot.approvals.length === 1 && liveSnapshot.approvals[0].requestId === 'req-live', 'getStreamSnapshot returns pending approvals');
+testState.runningCodex.delete('conv-live');
+testState.pendingApprovals.delete('req-live');
+
+// Concurrent turn limit
+import { getRunningTurns, setMaxConcurrentTurns } from './services/codex';
+const limitDb = new Database(':memory:');
+ensureSchema(limitDb);
+const limitState: AppState = { ...testState, db: limitDb, runningCodex: new Map(), promptQueues: new Map(), maxConcurrentTurns: 0 };
+limitState.runningCodex.set('conv-a', { child: null as any, stdin: null, turnId: 'turn-a', startedAt: '2024-01-01T00:00:00.000Z' });
+limitState.promptQueues.set('conv-c', [{ id: 'turn-c', prompt: 'later', queuedAt: '2024-01-01T00:02:00.000Z', waitingForSlot: true }]);
+limitState.promptQueues.set('conv-b', [
+    { id: 'turn-b', prompt: 'first', queuedAt: '2024-01-01T00:01:00.000Z', waitingForSlot: true },
+    { id: 'turn-b2', prompt: 'then', queuedAt: '2024-01-01T00:03:00.000Z' },
+]);
+const queuedEvents: any[] = [];
+const queuedWebContents = { send: (channel: string, payload: any) => channel === 'codex-turn-queued' && queuedEvents.push(payload) } as any;
+assert(setMaxConcurrentTurns(queuedWebContents, limitState, 1.7) === 1 && limitState.runningCodex.size === 1, 'setMaxConcurrentTurns keeps turns waiting at the limit');
+assert(queuedEvents.map((e) => `${e.cid}:${e.position}`).join(',') === 'conv-b:1,conv-c:2', 'waiting turns are told their FIFO position');
+const runningTurns = getRunningTurns(limitState);
+assert(runningTurns.limit === 1 && runningTurns.running === 1 && runningTurns.turns[0].status === 'running', 'getRunningTurns reports the limit and running turns');
+assert(runningTurns.turns[1].turnId === 'turn-b' && runningTurns.turns[1].queued === 1 && runningTurns.turns[2].position === 2, 'getRunningTurns lists waiting turns oldest first');
+limitDb.close();
+
+// Prompt queue mode
+import { cancelPrompt, clearPromptQueue, setQueueMode, streamCodex } from './services/codex';
+const queueDb = new Database(':memory:');
+ensureSchema(queueDb);
+dbCreateWorkspace(queueDb, 'ws-queue', 'Queue', os.tmpdir());
+dbCreateConversation(queueDb, 'conv-queue', 'ws-queue', 'Queue');
+const queueState: AppState = { ...testState, db: queueDb, runningCodex: new Map(), promptQueues: new Map(), maxConcurrentTurns: 0 };
+const queueEvents: any[] = [];
+const queueWebContents = { send: (channel: string, payload: any) => channel === 'codex-queue-updated' && queueEvents.push(payload) } as any;
+setQueueMode(queueState, true);
+queueState.promptQueues.set('conv-queue', [{ id: 'turn-left', prompt: 'left behind', queuedAt: '2024-01-01T00:00:00.000Z' }]);
+const queuedBehind = streamCodex(queueWebContents, 'conv-queue', 'newer', undefined, queueState);
+assert(queuedBehind.queued && queuedBehind.position === 2 && queueState.runningCodex.size === 0, 'queue mode appends a new prompt behind queued ones even with no turn running');
+assert(clearPromptQueue(queueWebContents, queueState, 'conv-queue').cleared === 2 && queueEvents[queueEvents.length - 1].items.length === 0, 'clearPromptQueue empties the queue and reports it');
+queueState.runningCodex.set('conv-queue', { child: null, stdin: null, turnId: 'turn-stopped' });
+streamCodex(queueWebContents, 'conv-queue', 'after the stopped one', undefined, queueState);
+cancelPrompt(queueWebContents, 'conv-queue', queueState);
+assert(!queueState.promptQueues.has('conv-queue') && queueEvents[queueEvents.length - 1].items.length === 0, 'cancelling a turn drops the prompts queued behind it');
+assert((queueDb.prepare("SELECT status FROM turns WHERE id = ?").get(queuedBehind.turnId) as any).status === 'cancelled', 'dropped queued turns are marked cancelled');
+queueDb.close();
+
+// Stream subscriptions
+import { subscribeStream, unsubscribeStream, wantsStreamEvent } from './services/subscriptions';
+const subscriber = { id: 4242, once: () => undefined } as any;
+assert(wantsStreamEvent(4242, 'codex-stream-delta', { cid: 'conv-a' }), 'windows without subscriptions receive every event');
+assert(subscribeStream(subscriber, 'conv-a', ['delta', 'status']).success && !subscribeStream(subscriber, 'conv-a', ['bogus']).success, 'subscribeStream validates kinds');
+assert(wantsStreamEvent(4242, 'codex-stream-delta', { cid: 'conv-a' }) && !wantsStreamEvent(4242, 'codex-thinking', { cid: 'conv-a' }), 'subscriptions filter by kind');
+assert(!wantsStreamEvent(4242, 'codex-stream-delta', { cid: 'conv-b' }) && wantsStreamEvent(4242, 'job-updated', {}), 'subscriptions filter by conversation only for stream events');
+assert(unsubscribeStream(subscriber).subscriptions.length === 0 && wantsStreamEvent(4242, 'codex-thinking', { cid: 'conv-b' }), 'unsubscribeStream restores every event');
+
+// Temp directories
+import { allocateTempDir, listTempDirs, releaseTempDirs } from './services/tempdirs';
+const scratch = allocateTempDir(testState, 'conv-scratch');
+assert(scratch.success && fs.statSync(scratch.path!).isDirectory() && listTempDirs(testState, 'conv-scratch').length === 1, 'allocateTempDir creates and tracks a directory');
+fs.writeFileSync(path.join(scratch.path!, 'staged.txt'), 'x');
+assert(releaseTempDirs(testState, 'conv-scratch') === 1 && !fs.existsSync(scratch.path!), 'releaseTempDirs removes the directories');
+assert(!allocateTempDir(testState, '../escape').success, 'allocateTempDir rejects invalid conversation ids');
+
+// Safety review
+import { executePlan, getPendingReview, getReviewMode, rejectReview, setReviewMode } from './services/codex';
+import { buildExecutionPrompt, parsePlanSteps, parseReviewProposal, reviewRunConfig, reviewViolations, stepsScope } from './services/review';
+const wasReviewMode = getReviewMode(testState);
+assert(setReviewMode(testState, true) && getReviewMode(testState), 'setReviewMode round trip');
+setReviewMode(testState, wasReviewMode);
+const planConfig = reviewRunConfig({ ...testConfig, yoloMode: true }, 'plan');
+assert(!planConfig.yoloMode && planConfig.cliOptions.sandbox === 'read-only' && reviewRunConfig(testConfig, 'execute').cliOptions.askForApproval === 'untrusted', 'reviewRunConfig sandboxes each pass');
+const proposal = parseReviewProposal('Plan:\n```json\n{"commands": ["npm test", 3], "files": ["src/a.ts", "docs/"]}\n```\nDone.');
+assert(proposal.commands.join() === 'npm test' && proposal.files.join() === 'src/a.ts,docs/', 'parseReviewProposal reads the json block');
+assert(parseReviewProposal('no block').commands.length === 0, 'parseReviewProposal without a block proposes nothing');
+const reviewScope = { commands: ['npm test', 'git add *'], files: ['src/a.ts', 'docs/'] };
+assert(reviewViolations({ params: { command: ['bash', '-lc', 'npm  test'] } }, reviewScope, '/ws').length === 0 && reviewViolations({ command: 'git add src' }, reviewScope, '/ws').length === 0, 'reviewViolations allows approved commands');
+assert(reviewViolations({ command: 'rm -rf src' }, reviewScope, '/ws').join() === 'rm -rf src', 'reviewViolations reports other commands');
+const fileViolations = reviewViolations({ item: { type: 'file_change', changes: [{ path: '/ws/docs/guide.md' }, { path: 'src/a.ts' }, { path: 'src/b.ts' }] } }, reviewScope, '/ws');
+assert(fileViolations.join() === 'src/b.ts', 'reviewViolations checks changed files against approved files and directories');
+assert(buildExecutionPrompt('do it', 'the plan', reviewScope).includes('- git add *'), 'buildExecutionPrompt lists the approved scope');
+testState.pendingReviews.set('conv-review', {
+    conversationId: 'conv-review', turnId: 'turn-plan', prompt: 'do it', task: 'do it', images: [],
+    runConfig: testConfig, plan: 'the plan', proposed: reviewScope, steps: [], createdAt: new Date().toISOString(),
+});
+assert(getPendingReview(testState, 'conv-review')?.proposed.files.length === 2 && getPendingReview(testState, 'conv-other') === null, 'getPendingReview returns the waiting plan');
+assert(rejectReview(fakeWebContents, testState, 'conv-review').success && !testState.pendingReviews.has('conv-review'), 'rejectReview drops the plan');
+const stepPlan = 'Steps:\n```json\n{"steps": [{"title": "Add test", "commands": ["npm test"], "files": ["src/a.test.ts"]}, {"commands": ["npm run lint"], "files": ["src/a.ts"]}], "files": ["README.md"]}\n```';
+const planSteps = parsePlanSteps(stepPlan);
+assert(planSteps.length === 2 && planSteps[0].title === 'Add test' && planSteps[1].title === 'Step 2' && planSteps[1].files.join() === 'src/a.ts', 'parsePlanSteps reads the json steps');
+assert(parsePlanSteps('1. Read the code\n2) Fix the bug\nDone.').map((s) => s.title).join('|') === 'Read the code|Fix the bug', 'parsePlanSteps falls back to a numbered list');
+assert(parseReviewProposal(stepPlan).files.join() === 'README.md,src/a.test.ts,src/a.ts', 'parseReviewProposal joins the steps\' scopes');
+assert(stepsScope([planSteps[0], planSteps[0]]).commands.join() === 'npm test', 'stepsScope drops duplicates');
+const stepsPrompt = buildExecutionPrompt('do it', 'the whole plan', stepsScope([planSteps[1]]), [{ ...planSteps[1], index: 1 }]);
+assert(stepsPrompt.includes('[Approved steps]\n2. Step 2') && !stepsPrompt.includes('the whole plan'), 'buildExecutionPrompt lists only the approved steps');
+assert(executePlan(fakeWebContents, testState, 'conv-review', [0]).error === 'No plan is waiting for review', 'executePlan needs a pending plan');
+testState.pendingReviews.set('conv-steps', {
+    conversationId: 'conv-steps', turnId: 'turn-steps', prompt: 'do it', task: 'do it', images: [],
+    runConfig: testConfig, plan: stepPlan, proposed: parseReviewProposal(stepPlan), steps: planSteps, createdAt: new Date().toISOString(),
+});
+assert(executePlan(fakeWebContents, testState, 'conv-steps', [2]).error === 'No step 2 in the plan' && !executePlan(fakeWebContents, testState, 'conv-steps', []).success, 'executePlan rejects unknown or missing steps');
+testState.pendingReviews.delete('conv-steps');
+
+// Low-memory mode
+import { getLowMemoryMode, getResourceUsage, resourceLimits, setLowMemoryMode } from './services/resources';
+const wasLowMemory = getLowMemoryMode(testState.db);
+const lowMemory = setLowMemoryMode(testState.db, true);
+assert(lowMemory.lowMemory && resourceLimits().maxConcurrentTurns === 2 && resourceLimits().fileIndexes < 8, 'setLowMemoryMode tightens limits');
+const resourceUsage = getResourceUsage(testState, { indexes: 1, entries: 10 });
+assert(resourceUsage.lowMemory && resourceUsage.memory.rss > 0 && resourceUsage.fileIndexes.entries === 10, 'getResourceUsage reports memory and caches');
+assert(setLowMemoryMode(testState.db, wasLowMemory).lowMemory === wasLowMemory, 'setLowMemoryMode restores the previous mode');
+
+// Web search providers
+import { buildSearchRequest, getWebSearchSettings, parseSearchResponse, reserveSearchSlot, setWebSearchSettings } from './services/web-search';
+const searchDb = new Database(':memory:');
+ensureSchema(searchDb);
+assert(getWebSearchSettings(searchDb).provider === 'duckduckgo', 'web search defaults to DuckDuckGo');
+const searchSettings = setWebSearchSettings(searchDb, { provider: 'brave', maxResults: 99, braveApiKey: 'bk' });
+assert(searchSettings.provider === 'brave' && searchSettings.maxResults === 20 && getWebSearchSettings(searchDb).braveApiKey === 'bk', 'setWebSearchSettings stores the provider and clamps maxResults');
+assert(setWebSearchSettings(searchDb, { provider: 'altavista' as any }).provider === 'duckduckgo', 'setWebSearchSettings rejects unknown providers');
+const braveRequest = buildSearchRequest('brave', searchSettings, 'rust async', 3);
+assert('url' in braveRequest && braveRequest.url.includes('q=rust%20async&count=3') && braveRequest.headers['X-Subscription-Token'] === 'bk', 'buildSearchRequest sends the Brave key');
+assert('error' in buildSearchRequest('google', searchSettings, 'q', 3) && 'error' in buildSearchRequest('searxng', searchSettings, 'q', 3), 'buildSearchRequest requires provider settings');
+const googleRequest = buildSearchRequest('google', { ...searchSettings, googleApiKey: 'gk', googleCx: 'cx1' }, 'q', 15);
+assert('url' in googleRequest && googleRequest.url.includes('cx=cx1') && googleRequest.url.endsWith('num=10'), 'buildSearchRequest caps Google at 10 results');
+const searxRequest = buildSearchRequest('searxng', { ...searchSettings, searxngUrl: 'https://searx.example/' }, 'q', 5);
+assert('url' in searxRequest && searxRequest.url === 'https://searx.example/search?q=q&format=json', 'buildSearchRequest targets the SearXNG instance');
+const ddgResults = parseSearchResponse('duckduckgo', { Heading: 'Rust', AbstractURL: 'https://rust-lang.org', Abstract: 'A language', RelatedTopics: [{ Text: 'Cargo - the package manager', FirstURL: 'https://doc.rust-lang.org/cargo' }, { Name: 'group' }] }, 'rust');
+assert(ddgResults.length === 2 && ddgResults[1].title === 'Cargo', 'parseSearchResponse reads DuckDuckGo answers');
+const braveResults = parseSearchResponse('brave', { web: { results: [{ title: '<strong>Tokio</strong>', url: 'https://tokio.rs', description: 'An &amp; runtime' }, { title: 'no url' }] } }, 'q');
+assert(braveResults.length === 1 && braveResults[0].title === 'Tokio' && braveResults[0].snippet === 'An & runtime', 'parseSearchResponse strips markup and drops results without a url');
+assert(parseSearchResponse('google', { items: [{ title: 'G', link: 'https://g.example', snippet: 's' }] }, 'q')[0].url === 'https://g.example', 'parseSearchResponse reads Google items');
+assert(parseSearchResponse('bing', { webPages: { value: [{ name: 'B', url: 'https://b.example', snippet: 's' }] } }, 'q')[0].title === 'B', 'parseSearchResponse reads Bing pages');
+assert(parseSearchResponse('searxng', {}, 'q').length === 0, 'parseSearchResponse tolerates empty responses');
+const slotStart = Date.now() + 60_000;
+assert(reserveSearchSlot('bing', slotStart) === 0 && reserveSearchSlot('bing', slotStart) === 350, 'reserveSearchSlot spaces requests to a provider');
+for (let i = 0; i < 30; i++) reserveSearchSlot('bing', slotStart);
+assert(reserveSearchSlot('bing', slotStart) === null && reserveSearchSlot('google', slotStart) === 0, 'reserveSearchSlot refuses long bursts per provider');
+searchDb.close();
+
+// Stream replay buffer
+import { getStreamBuffer, recordStreamEvent, tapStreamBuffer } from './services/stream-buffer';
+const replayState = { ...testState, streamBuffer: { lastSeq: 0, conversations: new Map() } } as AppState;
+const replaySent: Array<[string, any]> = [];
+const replayContents = tapStreamBuffer({ send: (channel: string, payload: any) => replaySent.push([channel, payload]) } as any, replayState);
+replayContents.send('codex-stream-delta', { cid: 'conv-replay', data: 'Hel' });
+replayContents.send('codex-login-progress', { status: 'waiting' });
+replayContents.send('codex-stream-delta', { cid: 'conv-replay', data: 'lo' });
+replayContents.send('acp-ready', true);
+assert(replaySent.map(([, payload]) => payload?.seq).join() === '1,2,3,' && replaySent[3][1] === true, 'tapStreamBuffer numbers codex-* events only');
+const replayed = getStreamBuffer(replayState, 'conv-replay', 1);
+assert(replayed.complete && replayed.lastSeq === 3 && replayed.events.length === 1 && (replayed.events[0].payload as any).data === 'lo', 'getStreamBuffer returns events after sinceSeq');
+assert(getStreamBuffer(replayState, 'conv-none').events.length === 0 && getStreamBuffer(replayState, 'conv-none').complete, 'getStreamBuffer is empty for other conversations');
+for (let i = 0; i < 2000; i++) recordStreamEvent(replayState.streamBuffer, 'codex-stream-delta', { cid: 'conv-replay', data: 'x' });
+assert(!getStreamBuffer(replayState, 'conv-replay', 1).complete && getStreamBuffer(replayState, 'conv-replay', 1).events.length === 2000, 'getStreamBuffer reports dropped events');
+for (let i = 0; i < 60; i++) recordStreamEvent(replayState.streamBuffer, 'codex-turn-status', { cid: `conv-many-${i}`, status: 'running' });
+assert(replayState.streamBuffer.conversations.size === 50 && !replayState.streamBuffer.conversations.has('conv-replay'), 'the replay buffer keeps a bounded number of conversations');
+recordStreamEvent(replayState.streamBuffer, 'codex-stream-delta', { cid: 'conv-many-10', data: 'again' });
+recordStreamEvent(replayState.streamBuffer, 'codex-stream-delta', { cid: 'conv-extra', data: 'new' });
+assert(replayState.streamBuffer.conversations.has('conv-many-10') && !replayState.streamBuffer.conversations.has('conv-many-11') && replayState.streamBuffer.conversations.size === 50, 'the replay buffer evicts the conversation written to least recently');
+import { apiSink } from './services/api-server';
+const apiEvents: any[] = [];
+const apiForwarded: string[] = [];
+const apiSinkContents = apiSink(replayState, 'conv-api', (_channel, payload) => apiEvents.push(payload), () => ({ send: (channel: string) => apiForwarded.push(channel) }) as any);
+apiSinkContents.send('codex-stream-delta', { cid: 'conv-api', data: 'api' });
+apiSinkContents.send('codex-stream-delta', { cid: 'conv-other', data: 'gui' });
+assert(apiEvents.length === 1 && apiEvents[0].seq === replayState.streamBuffer.lastSeq && getStreamBuffer(replayState, 'conv-api').events.length === 1, 'API turn events are numbered and buffered for replay');
+assert(apiForwarded.join() === 'codex-stream-delta' && !getStreamBuffer(replayState, 'conv-other').events.length, 'apiSink hands other conversations to the GUI WebContents');
+
+// URL content extraction
+import { capContent, extractMainContent, htmlToMarkdown } from './services/url-content';
+const pageHtml = `<html><head><title>Guide</title><script>track()</script></head><body><nav><a href="/">Home</a></nav>
+<article><h2>Setup</h2><p>Run <code>&lt;cmd&gt;</code>, see <a href="/docs?a=1&amp;b=2">the docs</a> and <b>restart</b>.</p>
+<ul><li>one</li><li>two</li></ul><pre><code>let x = 1;
+  x += 1;</code></pre>${'<p>More text.</p>'.repeat(30)}</article><footer>Copyright</footer></body></html>`;
+const mainContent = extractMainContent(pageHtml);
+assert(mainContent.includes('Setup') && !mainContent.includes('Home') && !mainContent.includes('track()') && !mainContent.includes('Copyright'), 'extractMainContent keeps the article and drops page chrome');
+const pageMarkdown = htmlToMarkdown(mainContent, 'https://example.com/guide/');
+assert(pageMarkdown.startsWith('## Setup\n\nRun `<cmd>`, see [the docs](https://example.com/docs?a=1&b=2) and **restart**.'), 'htmlToMarkdown converts headings, code, links and emphasis');
+assert(pageMarkdown.includes('- one\n- two') && pageMarkdown.includes('```\nlet x = 1;\n  x += 1;\n```'), 'htmlToMarkdown keeps lists and preformatted code');
+assert(htmlToMarkdown(mainContent, 'https://example.com/', false).startsWith('Setup\n\nRun <cmd>, see the docs and restart.'), 'htmlToMarkdown can produce plain text');
+const cappedContent = capContent(`${'a'.repeat(80)}\n\n${'b'.repeat(80)}`, 100);
+assert(cappedContent.truncated && cappedContent.content === 'a'.repeat(80) && !capContent('short', 100).truncated, 'capContent cuts at a paragraph break');
+
+// Event log retention and buffering
+import { EventRecorder, pruneEventLogs } from './services/event-log';
+const eventLogDir = fs.mkdtempSync(path.join(os.tmpdir(), 'codex-event-log-'));
+const logDay = (daysAgo: number) => {
+    const d = new Date(2026, 4, 20);
+    d.setDate(d.getDate() - daysAgo);
+    return `${d.getFullYear()}-${String(d.getMonth() + 1).padStart(2, '0')}-${String(d.getDate()).padStart(2, '0')}`;
+};
+for (const name of [`events-${logDay(0)}.jsonl`, `events-${logDay(0)}.1.jsonl`, `events-${logDay(0)}.2.jsonl`, `events-${logDay(3)}.jsonl`, `events-${logDay(10)}.jsonl`, 'notes.txt']) {
+    fs.writeFileSync(path.join(eventLogDir, name), 'x'.repeat(100));
+}
+const prunedLogs = pruneEventLogs(eventLogDir, new Date(2026, 4, 20), { maxDays: 7, maxFiles: 3, maxBytes: 1024 });
+assert(prunedLogs.sort().join() === [`events-${logDay(10)}.jsonl`, `events-${logDay(3)}.jsonl`].sort().join(), 'pruneEventLogs drops old days and files past the count cap');
+assert(fs.readdirSync(eventLogDir).length === 4, 'pruneEventLogs keeps recent logs and unrelated files');
+pruneEventLogs(eventLogDir, new Date(2026, 4, 20), { maxDays: 7, maxFiles: 10, maxBytes: 250 });
+assert(fs.existsSync(path.join(eventLogDir, `events-${logDay(0)}.jsonl`)) && fs.existsSync(path.join(eventLogDir, `events-${logDay(0)}.2.jsonl`)) && !fs.existsSync(path.join(eventLogDir, `events-${logDay(0)}.1.jsonl`)), 'pruneEventLogs keeps the active file and the newest rotations within the byte cap');
+const eventLogDb = new Database(':memory:');
+ensureSchema(eventLogDb);
+const recorderDir = path.join(eventLogDir, 'recorder');
+const recorder = new EventRecorder(eventLogDb, recorderDir);
+recorder.record('codex-stream-delta', { cid: 'c1', data: 'ignored while disabled' });
+recorder.setEnabled(true);
+recorder.record('codex-stream-delta', { cid: 'c1', data: 'hi' });
+recorder.record('db-changed', {});
+assert(!fs.existsSync(recorderDir), 'EventRecorder buffers events instead of writing each one');
+recorder.flushSync();
+const recordedLines = fs.readFileSync(path.join(recorderDir, fs.readdirSync(recorderDir)[0]), 'utf-8').trim().split('\n');
+assert(recordedLines.length === 1 && JSON.parse(recordedLines[0]).payload.data === 'hi', 'EventRecorder writes buffered codex events on flush');
+eventLogDb.close();
+fs.rmSync(eventLogDir, { recursive: true });
+
+// Auto-translate
+import { appendTranslation, getConversationAutoTranslate, setConversationAutoTranslate } from './services/translate';
+const translateDb = new Database(':memory:');
+ensureSchema(translateDb);
+dbCreateWorkspace(translateDb, 'trw', 'Translate', '/tmp/translate');
+dbCreateConversation(translateDb, 'trc', 'trw', 'Translate');
+assert(getConversationAutoTranslate(translateDb, 'trc') === null, 'auto-translate is off by default');
+setConversationAutoTranslate(translateDb, 'trc', ' Korean ');
+assert(getConversationAutoTranslate(translateDb, 'trc') === 'Korean', 'setConversationAutoTranslate stores the trimmed language');
+setConversationAutoTranslate(translateDb, 'trc', '');
+assert(getConversationAutoTranslate(translateDb, 'trc') === null, 'an empty language turns auto-translate off');
+translateDb.close();
+assert(appendTranslation('Done.', 'Korean', ' 완료. \n') === 'Done.\n\n---\n\n*Korean*\n\n완료.', 'appendTranslation keeps the original above the translation');
+
+// Spell checking
+import { maskNonProse, parseHunspellOutput } from './services/spellcheck';
+const spellLines = maskNonProse('Helo world\n\n`someCode()`\nteh end').split('\n');
+assert(spellLines[2].trim() === '' && spellLines[2].length === '`someCode()`'.length, 'maskNonProse blanks code and keeps columns');
+const hunspellOutput = '@(#) International Ispell Version 3.2.06 (but really Hunspell 1.7.0)\n& Helo 5 0: Hello, Halo\n*\n\n\n\n& teh 3 0: the, ten\n*\n\n';
+const spellIssues = parseHunspellOutput(hunspellOutput, spellLines);
+assert(spellIssues.length === 2 && spellIssues[0].line === 1 && spellIssues[1].line === 4 && spellIssues[1].column === 1, 'parseHunspellOutput keeps line numbers after blank and masked lines');
+assert(spellIssues[0].suggestions.join() === 'Hello,Halo' && parseHunspellOutput('banner\n# Zzyzx 0\n\n', ['Zzyzx'])[0].suggestions.length === 0, 'parseHunspellOutput reads suggestions and words without any');
+
+// Workspace permissions
+import { answerPermission, hasPermission, listPermissions, revokePermission } from './services/permissions';
+const permissionsDbPath = path.join(os.tmpdir(), `codex-permissions-${Date.now()}.sqlite3`);
+let permissionsDb = new Database(permissionsDbPath);
+ensureSchema(permissionsDb);
+const deniedPermission = answerPermission(permissionsDb, '/tmp/perm-ws/', 'delete', false);
+assert(deniedPermission?.permissionDenied.capability === 'delete' && deniedPermission.permissionDenied.workspacePath === '/tmp/perm-ws', 'answerPermission returns a denial for Deny');
+assert(!hasPermission(permissionsDb, '/tmp/perm-ws', 'delete'), 'a denied capability is not remembered');
+assert(answerPermission(permissionsDb, '/tmp/perm-ws', 'run', true) === null && hasPermission(permissionsDb, '/tmp/perm-ws/', 'run'), 'answerPermission remembers Allow');
+assert(!hasPermission(permissionsDb, '/tmp/perm-ws', 'write') && !hasPermission(permissionsDb, '/tmp/other-ws', 'run'), 'a grant covers one capability in one workspace');
+permissionsDb.close();
+permissionsDb = new Database(permissionsDbPath);
+assert(hasPermission(permissionsDb, '/tmp/perm-ws', 'run') && listPermissions(permissionsDb).length === 1, 'granted capabilities persist across restarts');
+revokePermission(permissionsDb, '/tmp/perm-ws', 'run');
+assert(!hasPermission(permissionsDb, '/tmp/perm-ws', 'run'), 'revokePermission removes the grant');
+permissionsDb.close();
+fs.rmSync(permissionsDbPath, { force: true });
+
+// Orphan watchdog
+import { findOrphanProcesses, isCodexProcess } from './services/watchdog';
+const codexPaths = ['/usr/local/bin/codex', '/usr/local/lib/node_modules/@openai/codex/bin/codex.js', '/usr/local/lib/node_modules/@openai/codex/vendor/'];
+const fakeProcesses = [
+    { pid: 900001, ppid: process.pid, command: '/Applications/Codex UI.app/Contents/Frameworks/Codex UI Helper.app/Contents/MacOS/Codex UI Helper --type=renderer' },
+    { pid: 900002, ppid: process.pid, command: '/bin/bash' },
+    { pid: 900003, ppid: 900002, command: '/usr/local/bin/codex exec --json' },
+    { pid: 900004, ppid: process.pid, command: '/usr/local/bin/codex exec --json -' },
+    { pid: 900005, ppid: process.pid, command: 'node /usr/local/lib/node_modules/@openai/codex/bin/codex.js exec' },
+    { pid: 900006, ppid: 900005, command: '/usr/local/lib/node_modules/@openai/codex/vendor/x86_64-unknown-linux-musl/codex/codex exec' },
+    { pid: 900007, ppid: process.pid, command: 'npm install -g @openai/codex' },
+    { pid: 900008, ppid: process.pid, command: 'vim codex.md' },
+    { pid: 900009, ppid: 1, command: '/usr/local/bin/codex exec' },
+];
+const orphanPids = findOrphanProcesses(testState, fakeProcesses, codexPaths, [900002]).map((p) => p.pid);
+assert(JSON.stringify(orphanPids) === JSON.stringify([900004, 900005, 900006]), 'findOrphanProcesses picks only untracked codex binaries below the app');
+assert(!isCodexProcess('/usr/local/bin/codex-wui --flag', codexPaths) && !isCodexProcess('/opt/other/codex exec', codexPaths), 'isCodexProcess needs the resolved codex path as argv[0]');
+assert(isCodexProcess('"C:\\Program Files\\nodejs\\node.exe" C:\\npm\\node_modules\\@openai\\codex\\bin\\codex.js exec', ['C:\\npm\\codex.cmd', 'C:\\npm\\node_modules\\@openai\\codex\\bin\\codex.js']), 'isCodexProcess accepts a quoted node with a Windows launcher');
+assert(findOrphanProcesses(testState, fakeProcesses, [], []).length === 0, 'findOrphanProcesses reaps nothing when codex is not installed');
+
 // Cleanup
 testState.db.close();
 
 // ===== SUMMARY =====
-console.log(`\n${'═'.repeat(50)}`);
-console.log(`  🏁 VERIFICATION COMPLETE`);
-console.log(`${'═'.repeat(50)}`);
-console.log(`  ✅ Passed: ${passed}`);
-console.log(`  ❌ Failed: ${failed}`);
-if (errors.length > 0) {
-    console.log(`\n  Failed tests:`);
-    errors.forEach(e => console.log(`    - ${e}`));
-}
-console.log(`${'═'.repeat(50)}\n`);
+Promise.allSettled(pendingTests).then((results) => {
+    for (const result of results) {
+        if (result.status === 'rejected') assert(false, `async check threw: ${result.reason}`);
+    }
+    console.log(`\n${'═'.repeat(50)}`);
+    console.log(`  🏁 VERIFICATION COMPLETE`);
+    console.log(`${'═'.repeat(50)}`);
+    console.log(`  ✅ Passed: ${passed}`);
+    console.log(`  ❌ Failed: ${failed}`);
+    if (errors.length > 0) {
+        console.log(`\n  Failed tests:`);
+        errors.forEach(e => console.log(`    - ${e}`));
+    }
+    console.log(`${'═'.repeat(50)}\n`);
 
-process.exit(failed > 0 ? 1 : 0);
+    process.exit(failed > 0 ? 1 : 0);
+});
@@ -1,5 +1,6 @@
 ﻿import { useState, useRef, useEffect, useCallback, useMemo, lazy, Suspense } from 'react'
 import codexApi from './tauri-api'
+import { type ApprovalDecision } from './api/tauri-api'
 import { Sidebar } from './components/Sidebar'
 import { ChatPanel } from './components/ChatPanel'
 import { useI18n } from './i18n'
@@ -12,6 +13,7 @@ import { type CliOptions } from './components/CliControlPanel'
 import { type SettingsTabId } from './components/SettingsPanel'
 import { getSavedTheme, applyTheme, type Theme } from './themes'
 import { requestNotificationPermission } from './utils/notifications'
+import { applyDbChanges, loadCachedState, saveCachedState } from './utils/stateCache'
 
 // Lazy-loaded modal components (not needed on initial render)
 const SettingsPanel = lazy(() => import('./components/SettingsPanel').then(m => ({ default: m.SettingsPanel })))
@@ -33,6 +35,8 @@ interface Message {
     content: string
     thinking?: string
     thinkingDuration?: number
+    /** Set when `content` is only the first part of a segmented message. */
+    partCount?: number
     timestamp: string
 }
 
@@ -42,6 +46,11 @@ interface Conversation {
     title: string
     createdAt: string
     updatedAt: string
+    unreadCount?: number
+    messageCount?: number
+    /** The latest page of messages has been fetched. */
+    messagesLoaded?: boolean
+    hasOlderMessages?: boolean
     messages: Message[]
 }
 
@@ -85,6 +94,7 @@ const DEFAULT_STREAM_TYPING_DELAY_MS = 18
 const MIN_STREAM_TYPING_DELAY_MS = 4
 const MAX_STREAM_TYPING_DELAY_MS = 120
 const STREAM_TYPING_CHARS_PER_TICK = 2
+const MESSAGE_PAGE_SIZE = 50
 
 const BLOCK_BREAK_START_PATTERN = /^(?:#{1,6}\s+|\*\*[^*\n]{1,80}\*\*:?|[-*]\s+|\d+\.\s+|>\s+|```|결과(?:\s|:|$)|검증(?:\s|:|$)|제약(?:\s|:|$)|산출물(?:\s|:|$)|다음 단계(?:\s|:|$)|result(?:\s|:|$)|verification(?:\s|:|$)|constraints?(?:\s|:|$)|artifacts?(?:\s|:|$)|next steps?(?:\s|:|$))/i
 const SECTION_LABEL_LINE_PATTERN = /^(결과|검증|제약|산출물|다음 단계|Result|Verification|Constraints?|Artifacts?|Next steps?)\s*:?\s*$/gim
@@ -229,6 +239,87 @@ function App() {
         inputRef.current?.focus()
     }, [appState.activeConversationId])
 
+    const setConversationUnread = useCallback((conversationId: string, unreadCount: number) => {
+        setAppState(prev => ({
+            ...prev,
+            workspaces: prev.workspaces.map(w => ({
+                ...w,
+                conversations: w.conversations.map(c =>
+                    c.id === conversationId && c.unreadCount !== unreadCount ? { ...c, unreadCount } : c
+                )
+            }))
+        }))
+    }, [])
+
+    // Mark the open conversation read whenever it is shown or gets new messages
+    const activeConversationId = appState.activeConversationId
+    useEffect(() => {
+        if (!activeConversationId) return
+        codexApi.db.markConversationRead(activeConversationId)
+            .then(() => setConversationUnread(activeConversationId, 0))
+            .catch(error => console.error('[App] Failed to mark conversation read:', error))
+    }, [activeConversationId, messages.length])
+
+    // Startup state has no messages; fetch a conversation's latest page when it is opened
+    const loadMessagesPage = useCallback(async (conversationId: string, beforeId?: string) => {
+        try {
+            const page = await codexApi.db.getMessagesPage(conversationId, beforeId, MESSAGE_PAGE_SIZE)
+            const pageIds = new Set(page.messages.map((m: Message) => m.id))
+            setAppState(prev => ({
+                ...prev,
+                workspaces: prev.workspaces.map(w => ({
+                    ...w,
+                    conversations: w.conversations.map(c => c.id === conversationId
+                        ? {
+                            ...c,
+                            messages: [...page.messages, ...c.messages.filter(m => !pageIds.has(m.id))],
+                            messagesLoaded: true,
+                            hasOlderMessages: page.hasMore
+                        }
+                        : c)
+                }))
+            }))
+        } catch (error) {
+            console.error('[App] Failed to load messages:', error)
+        }
+    }, [])
+
+    useEffect(() => {
+        if (!activeConversation || activeConversation.messagesLoaded || !activeConversation.messageCount) return
+        loadMessagesPage(activeConversation.id)
+    }, [activeConversation, loadMessagesPage])
+
+    const loadOlderMessages = useCallback(() => {
+        if (!activeConversation?.hasOlderMessages || messages.length === 0) return
+        loadMessagesPage(activeConversation.id, messages[0].id)
+    }, [activeConversation, messages, loadMessagesPage])
+
+    // Long messages are loaded with their first part only; fetch the rest when opened
+    useEffect(() => {
+        const partial = messages.filter(m => m.partCount)
+        if (partial.length === 0) return
+        let cancelled = false
+        Promise.all(partial.map(async m => [m.id, await codexApi.db.getMessageContent(m.id)] as const))
+            .then(entries => {
+                if (cancelled) return
+                const full = new Map(entries.filter(([, content]) => content !== null))
+                setAppState(prev => ({
+                    ...prev,
+                    workspaces: prev.workspaces.map(w => ({
+                        ...w,
+                        conversations: w.conversations.map(c => ({
+                            ...c,
+                            messages: c.messages.map(m => full.has(m.id)
+                                ? { ...m, content: full.get(m.id)!, partCount: undefined }
+                                : m)
+                        }))
+                    }))
+                }))
+            })
+            .catch(error => console.error('[App] Failed to load message parts:', error))
+        return () => { cancelled = true }
+    }, [messages])
+
     // Ctrl+Y shortcut to toggle YOLO mode
     useEffect(() => {
         const handleKeyDown = async (e: KeyboardEvent) => {
@@ -279,27 +370,64 @@ function App() {
         })
     }, [cliOptions])
 
-    // Load state from SQLite on startup
+    // Load state from SQLite on startup. A cached copy of the lists is shown first and
+    // then brought up to date with only the changes made since it was saved.
     useEffect(() => {
+        async function showInitialState(workspaces: Workspace[]) {
+            const initialWorkspace = workspaces[0]
+            const initialWorkspaceId = initialWorkspace?.id || null
+            const initialConversationId = initialWorkspace?.conversations[0]?.id || null
+            setAppState({
+                workspaces,
+                activeWorkspaceId: initialWorkspaceId,
+                activeConversationId: initialConversationId
+            })
+            if (initialWorkspaceId && initialWorkspace?.path) {
+                try {
+                    await codexApi.switchWorkspace(initialWorkspaceId, initialWorkspace.path)
+                } catch (switchError) {
+                    console.error('[App] Failed to sync initial workspace cwd:', switchError)
+                }
+            }
+            setDbLoaded(true)
+        }
+
+        // Keep the selection unless the reconciled lists no longer contain it.
+        function reconcile(update: (workspaces: Workspace[]) => Workspace[]) {
+            setAppState(prev => {
+                const workspaces = update(prev.workspaces)
+                const workspace = workspaces.find(w => w.id === prev.activeWorkspaceId)
+                if (workspace) {
+                    const keepConversation = workspace.conversations.some(c => c.id === prev.activeConversationId)
+                    return { ...prev, workspaces, activeConversationId: keepConversation ? prev.activeConversationId : workspace.conversations[0]?.id || null }
+                }
+                return {
+                    workspaces,
+                    activeWorkspaceId: workspaces[0]?.id || null,
+                    activeConversationId: workspaces[0]?.conversations[0]?.id || null
+                }
+            })
+        }
+
         async function loadFromDb() {
             try {
-                const state = await codexApi.db.getState()
-                const initialWorkspace = state.workspaces[0]
-                const initialWorkspaceId = initialWorkspace?.id || null
-                const initialConversationId = initialWorkspace?.conversations[0]?.id || null
-                setAppState({
-                    workspaces: state.workspaces,
-                    activeWorkspaceId: initialWorkspaceId,
-                    activeConversationId: initialConversationId
-                })
-                if (initialWorkspaceId && initialWorkspace?.path) {
-                    try {
-                        await codexApi.switchWorkspace(initialWorkspaceId, initialWorkspace.path)
-                    } catch (switchError) {
-                        console.error('[App] Failed to sync initial workspace cwd:', switchError)
+                const cached = loadCachedState()
+                if (cached) {
+                    await showInitialState(cached.workspaces)
+                    const changes = await codexApi.db.getChanges(cached.token)
+                    if (!changes.reset) {
+                        const workspaces = applyDbChanges(cached.workspaces, changes)
+                        reconcile(current => applyDbChanges(current, changes))
+                        saveCachedState(changes.token, workspaces)
+                        console.log('[App] Loaded state from cache:', workspaces.length, 'workspaces,', changes.conversations.length, 'changed conversations')
+                        return
                     }
                 }
-                setDbLoaded(true)
+
+                const state = await codexApi.db.getState()
+                if (cached) reconcile(() => state.workspaces)
+                else await showInitialState(state.workspaces)
+                if (state.syncToken) saveCachedState(state.syncToken, state.workspaces)
                 console.log('[App] Loaded state from DB:', state.workspaces.length, 'workspaces')
             } catch (error) {
                 console.error('[App] Failed to load from DB:', error)
@@ -703,7 +831,7 @@ function App() {
             enqueueStreamingChunk(cid, token)
         }))
 
-        unlisteners.push(codexApi.onStreamEnd((cid: string) => {
+        unlisteners.push(codexApi.onStreamEnd((cid: string, info) => {
             flushPendingStreamQueue(cid, true)
             const stream = getConversationStream(cid)
             if (!stream) return
@@ -717,7 +845,7 @@ function App() {
 
             if (content || thinking) {
                 const newMessage: Message = {
-                    id: crypto.randomUUID(),
+                    id: info?.messageId || crypto.randomUUID(),
                     conversationId: cid,
                     role: 'assistant',
                     content: content,
@@ -739,6 +867,11 @@ function App() {
             }
         }))
 
+        unlisteners.push(codexApi.onUnreadCount((cid: string, unreadCount: number) => {
+            if (cid === appStateRef.current.activeConversationId) return
+            setConversationUnread(cid, unreadCount)
+        }))
+
         unlisteners.push(codexApi.onStreamError((cid: string, error: string) => {
             console.error('Stream error:', error)
             flushPendingStreamQueue(cid, false)
@@ -792,7 +925,7 @@ function App() {
                     if (c.id !== message.conversationId) return c
                     return {
                         ...c,
-                        messages: [...c.messages, message],
+                        messages: [...c.messages.filter(m => m.id !== message.id), message],
                         updatedAt: new Date().toISOString()
                     }
                 })
@@ -1305,8 +1438,8 @@ function App() {
 
 
 
-    const handleApprovalResponse = useCallback(async (requestId: string, approved: boolean) => {
-        await codexApi.respondToApproval(requestId, approved)
+    const handleApprovalResponse = useCallback(async (requestId: string, decision: ApprovalDecision | boolean, message?: string) => {
+        await codexApi.respondToApproval(requestId, decision, message)
         setApprovalRequest(null)
     }, [])
 
@@ -1339,7 +1472,7 @@ function App() {
         if (!conversationId) return
         flushPendingStreamQueue(conversationId, true)
         setConversationLoading(conversationId, false)
-        await codexApi.cancelPrompt(conversationId)
+        await codexApi.cancelPrompt(conversationId, true)
         setToolCalls([])
     }, [appState.activeConversationId, flushPendingStreamQueue, setConversationLoading])
 
@@ -1496,6 +1629,8 @@ function App() {
 
                         <ChatPanel
                             messages={messages}
+                            hasOlderMessages={!!activeConversation?.hasOlderMessages}
+                            onLoadOlder={loadOlderMessages}
                             streamingContent={streamingContent}
                             streamingThinking={streamingThinking}
                             isLoading={isLoading}
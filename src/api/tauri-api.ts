@@ -18,195 +18,1997 @@ export interface CliOptions {
     cwdOverride: string
     extraArgs: string
     enableWebSearch: boolean
+    reasoningEffort?: string
+    /** Agent CLI that runs turns; unset means codex. */
+    agent?: AgentBackendId
 }
 
+export type AgentBackendId = 'codex' | 'claude' | 'gemini'
+
+export type CliOverrides = Partial<CliOptions> & { model?: string }
+
 export interface CodexUser {
     id: string
     email: string
     name: string
     picture: string
+    authMode: string
+    authProvider: string
+    /** When the sign-in's token runs out; unset for API keys. */
+    expiresAt?: string
+    /** The token has run out; prompt for a new sign-in. */
+    expired: boolean
+}
+
+export interface FileSearchResult {
+    name: string
+    path: string
+    relativePath: string
+    isDirectory: boolean
+}
+
+type UnlistenFn = () => void
+
+// ===== Get codexApi from preload =====
+const api = () => (window as any).codexApi
+
+// ===== Commands =====
+
+export async function setMode(mode: ModelMode): Promise<ModelMode> {
+    return api().setMode(mode)
+}
+
+export async function getMode(): Promise<ModelMode> {
+    return api().getMode()
+}
+
+export async function setYoloMode(enabled: boolean): Promise<boolean> {
+    return api().setYoloMode(enabled)
+}
+
+export async function getYoloMode(): Promise<boolean> {
+    return api().getYoloMode()
+}
+
+export interface ModelInfo {
+    id: string
+    name: string
+    description: string
+}
+
+export async function getModels(): Promise<ModelInfo[]> {
+    return api().getModels()
+}
+
+export async function refreshModels(): Promise<{ success: boolean; models: ModelInfo[]; error?: string }> {
+    return api().refreshModels()
+}
+
+export async function getCustomModels(): Promise<ModelInfo[]> {
+    return api().getCustomModels()
+}
+
+export async function setCustomModels(models: ModelInfo[]): Promise<ModelInfo[]> {
+    return api().setCustomModels(models)
+}
+
+export async function getModel(): Promise<string> {
+    return api().getModel()
+}
+
+export async function setModel(modelId: string): Promise<string> {
+    return api().setModel(modelId)
+}
+
+/** Rejects when `profile` names a profile that ~/.codex/config.toml does not define. */
+export async function setCliOptions(options: Partial<CliOptions>): Promise<CliOptions> {
+    return api().setCliOptions(options)
+}
+
+export async function getCliOptions(): Promise<CliOptions> {
+    return api().getCliOptions()
+}
+
+/** Workspace defaults layered between the global options and conversation overrides. */
+export type WorkspaceCliOptions = Partial<CliOptions> & { model?: string; yoloMode?: boolean }
+
+export async function getWorkspaceCliOptions(workspaceId: string): Promise<WorkspaceCliOptions> {
+    return api().getWorkspaceCliOptions(workspaceId)
+}
+
+/** Replace a workspace's CLI options; an empty object clears them. */
+export async function setWorkspaceCliOptions(workspaceId: string, options: WorkspaceCliOptions): Promise<WorkspaceCliOptions> {
+    return api().setWorkspaceCliOptions(workspaceId, options)
+}
+
+export interface CodexProfile {
+    name: string
+    model: string | null
+    modelProvider: string | null
+    sandbox: string | null
+    approvalPolicy: string | null
+    reasoningEffort: string | null
+}
+
+export async function getCodexProfiles(): Promise<{ success: boolean; profiles: CodexProfile[]; path: string; error?: string }> {
+    return api().getCodexProfiles()
+}
+
+/** Returns an error message, or null when the profile exists (or is empty). */
+export async function validateCodexProfile(name: string): Promise<string | null> {
+    return api().validateCodexProfile(name)
+}
+
+export type StartupMode = 'restore-last' | 'workspace' | 'blank'
+
+export interface StartupIntent {
+    mode: StartupMode
+    workspaceId: string | null
+    workspacePath: string | null
+    conversationId: string | null
+    missingWorkspace?: { id: string; path: string }
+}
+
+export async function getStartupIntent(): Promise<StartupIntent> {
+    return api().getStartupIntent()
+}
+
+export async function getStartupSettings(): Promise<{ mode: StartupMode; workspaceId: string }> {
+    return api().getStartupSettings()
+}
+
+export async function setStartupSettings(settings: Partial<{ mode: StartupMode; workspaceId: string }>): Promise<{ mode: StartupMode; workspaceId: string }> {
+    return api().setStartupSettings(settings)
+}
+
+export async function recordLastSession(workspaceId: string, conversationId?: string): Promise<void> {
+    return api().recordLastSession(workspaceId, conversationId)
+}
+
+export async function checkCodex(): Promise<{ installed: boolean }> {
+    return api().checkCodex()
+}
+
+export type CodexInstallMethod = 'npm' | 'pnpm' | 'bun' | 'brew' | 'binary'
+
+export interface InstallMethodInfo {
+    id: CodexInstallMethod
+    label: string
+    available: boolean
+}
+
+/** Without a method, the preferred one is used when available, else the first available. */
+export async function installCodex(method?: CodexInstallMethod): Promise<{ success: boolean; method?: CodexInstallMethod; error?: string }> {
+    return api().installCodex(method)
+}
+
+/** Install the latest codex over the current one; progress arrives via onCodexInstallProgress. */
+export async function upgradeCodex(method?: CodexInstallMethod): Promise<{ success: boolean; method?: CodexInstallMethod; error?: string }> {
+    return api().upgradeCodex(method)
+}
+
+export async function getCodexInstallMethods(): Promise<{ methods: InstallMethodInfo[]; preferred: CodexInstallMethod | 'auto' }> {
+    return api().getCodexInstallMethods()
+}
+
+export async function setCodexInstallMethod(method: CodexInstallMethod | 'auto'): Promise<{ success: boolean; error?: string }> {
+    return api().setCodexInstallMethod(method)
+}
+
+export interface CodexVersionInfo {
+    installed: boolean
+    version: string | null
+    /** The latest release on npm; null when npm could not be reached. */
+    latest: string | null
+    updateAvailable: boolean
+    minimumVersion: string
+    /** False when codex is older than `minimumVersion`; `warning` explains it. */
+    supported: boolean
+    warning?: string
+}
+
+export async function getCodexVersion(): Promise<CodexVersionInfo> {
+    return api().getCodexVersion()
+}
+
+export type CodexProtocolId = 'exec-msg' | 'exec-items'
+
+export interface CodexProtocolInfo {
+    /** `codex --version`; null when codex is missing or printed no version. */
+    version: string | null
+    /** `exec-msg` is the `{id, msg}` output of codex before item events. */
+    protocol: CodexProtocolId
+    /** Newest codex release this app's event mappings were checked against. */
+    newestKnownVersion: string
+}
+
+export interface ProtocolMismatch {
+    turnId: string
+    cliVersion: string | null
+    protocol: CodexProtocolId
+    /** Which side to update: codex, the app, or either when the versions look compatible. */
+    advice: 'update-cli' | 'update-app' | 'update-either'
+    message: string
+    details: string[]
+}
+
+export async function getCodexProtocol(): Promise<CodexProtocolInfo> {
+    return api().getCodexProtocol()
+}
+
+export interface AgentBackendInfo {
+    id: AgentBackendId
+    label: string
+    installed: boolean
+}
+
+/** Agent CLIs a workspace can pick with its `agent` CLI option, and whether each is on PATH. */
+export async function listAgentBackends(): Promise<AgentBackendInfo[]> {
+    return api().listAgentBackends()
+}
+
+export async function initAcp(): Promise<{ success: boolean; error?: string }> {
+    return api().initAcp()
+}
+
+export async function openWorkspace(): Promise<{ path: string; name: string } | null> {
+    return api().openWorkspace()
+}
+
+export async function switchWorkspace(workspaceId: string, cwd: string): Promise<{ success: boolean; sessionId?: string; error?: string }> {
+    return api().switchWorkspace(workspaceId, cwd)
+}
+
+export interface WorkspaceFileChange {
+    path: string
+    kind: 'created' | 'modified' | 'deleted'
+}
+
+/** Only report changes under these globs (relative to the workspace root); `[]` watches everything. */
+export async function watchPaths(workspaceId: string, globs: string[]): Promise<{ success: boolean; globs: string[] }> {
+    return api().watchPaths(workspaceId, globs)
+}
+
+/** Batched file changes in the open workspace, debounced by 200ms (agent edits, external editors, git). */
+export function onWorkspaceFileChanged(callback: (payload: { workspaceId: string; changes: WorkspaceFileChange[] }) => void): UnlistenFn {
+    return api().onWorkspaceFileChanged(callback)
+}
+
+export interface MessageAttachment {
+    id: string
+    kind: 'image'
+    name: string
+    path: string
+    mimeType: string
+    size: number
+}
+
+/** An image to send with a prompt: a file path, or base64 data (a data URL or with `mimeType`). */
+export interface PromptAttachment {
+    path?: string
+    data?: string
+    name?: string
+    mimeType?: string
+}
+
+/**
+ * In queue mode a prompt sent while a turn is running is queued instead of replacing it.
+ * Stored attachment metadata is returned for the user message.
+ */
+export async function streamCodex(
+    conversationId: string,
+    prompt: string,
+    conversationHistory?: Array<{ role: 'user' | 'assistant'; content: string }>,
+    attachments?: PromptAttachment[],
+): Promise<{ queued: boolean; position?: number; turnId?: string; attachments?: MessageAttachment[] }> {
+    return api().streamCodex(conversationId, prompt, conversationHistory, attachments)
+}
+
+/**
+ * Plan first: the turn runs read-only whatever the review mode, and its plan and steps
+ * arrive with onReviewRequest. Run all of it with approveReview or some steps with executePlan.
+ */
+export async function streamCodexPlan(
+    conversationId: string,
+    prompt: string,
+    conversationHistory?: Array<{ role: 'user' | 'assistant'; content: string }>,
+    attachments?: PromptAttachment[],
+): Promise<{ queued: boolean; position?: number; turnId?: string; attachments?: MessageAttachment[] }> {
+    return api().streamCodexPlan(conversationId, prompt, conversationHistory, attachments)
+}
+
+export interface CaptureRegion {
+    x: number
+    y: number
+    width: number
+    height: number
+}
+
+/**
+ * Screenshot the display under the cursor (optionally a region of it) into the attachments
+ * store. Pass `{ path: attachment.path }` to the next streamCodex call to include it.
+ */
+export async function captureScreen(
+    region?: CaptureRegion,
+    displayId?: number,
+    hideWindow?: boolean,
+): Promise<{ success: boolean; attachment?: MessageAttachment; error?: string }> {
+    return api().captureScreen(region, displayId, hideWindow)
+}
+
+export async function debugLog(msg: string): Promise<void> {
+    return api().debugLog(msg)
+}
+
+export async function setEventLogEnabled(enabled: boolean): Promise<{ enabled: boolean }> {
+    return api().setEventLogEnabled(enabled)
+}
+
+export async function getEventLogStatus(): Promise<{ enabled: boolean; directory: string; currentFile: string; sizeBytes: number }> {
+    return api().getEventLogStatus()
+}
+
+export async function getRecentEventLog(limit?: number): Promise<Array<{ ts: string; channel: string; payload: unknown }>> {
+    return api().getRecentEventLog(limit)
+}
+
+// ===== Notifications =====
+
+export interface NotificationPrefs {
+    streamEnd: boolean
+    streamError: boolean
+    approvalRequest: boolean
+    onlyWhenUnfocused: boolean
+}
+
+export async function getNotificationPrefs(): Promise<NotificationPrefs> {
+    return api().getNotificationPrefs()
+}
+
+/** Choose which turn events raise OS notifications; omitted keys keep their value. */
+export async function setNotificationPrefs(prefs: Partial<NotificationPrefs>): Promise<NotificationPrefs> {
+    return api().setNotificationPrefs(prefs)
+}
+
+export type RouteEvent = 'error' | 'completion' | 'approval'
+
+export interface NotificationSink {
+    type: 'teams' | 'slack' | 'webhook'
+    url: string
+}
+
+/** Sends a workspace's turn events (`*` for every workspace) to Teams, Slack or webhooks. */
+export interface NotificationRoute {
+    id: string
+    name: string
+    workspaceId: string
+    events: RouteEvent[]
+    sinks: NotificationSink[]
+    enabled: boolean
+}
+
+export async function getNotificationRoutes(): Promise<NotificationRoute[]> {
+    return api().getNotificationRoutes()
+}
+
+/** Add a route, or replace the one with the same id. */
+export async function saveNotificationRoute(route: Partial<NotificationRoute>): Promise<{ success: boolean; route?: NotificationRoute; error?: string }> {
+    return api().saveNotificationRoute(route)
+}
+
+export async function deleteNotificationRoute(id: string): Promise<{ success: boolean }> {
+    return api().deleteNotificationRoute(id)
+}
+
+/** The sinks an event of the workspace goes to; unless `dryRun`, each gets a test message. */
+export async function testNotificationRoute(workspaceId: string, event: RouteEvent, dryRun = false): Promise<{ success: boolean; results: Array<{ sink: NotificationSink; success: boolean; error?: string }>; error?: string }> {
+    return api().testNotificationRoute(workspaceId, event, dryRun)
+}
+
+/** Fired when the user clicks a notification; `cid` is the conversation it was about. */
+export function onNotificationClicked(callback: (payload: { cid?: string; channel: string }) => void): UnlistenFn {
+    return api().onNotificationClicked(callback)
+}
+
+/** Fired when a conversation gets a new assistant message (e.g. a background turn finished). */
+export function onUnreadCount(callback: (cid: string, unreadCount: number) => void): UnlistenFn {
+    return api().onUnreadCount(callback)
+}
+
+// ===== Accessibility =====
+
+/** A condensed line for a screen reader; use `priority` as the aria-live politeness. */
+export interface Narration {
+    cid: string
+    text: string
+    priority: 'polite' | 'assertive'
+}
+
+export async function getNarrationEnabled(): Promise<boolean> {
+    return api().getNarrationEnabled()
+}
+
+/** Turn on onNarration events (turn started, files changed, awaiting approval, turn finished). */
+export async function setNarrationEnabled(enabled: boolean): Promise<boolean> {
+    return api().setNarrationEnabled(enabled)
+}
+
+export function onNarration(callback: (narration: Narration) => void): UnlistenFn {
+    return api().onNarration(callback)
+}
+
+// ===== Webhooks =====
+
+export type WebhookEvent = 'turn-completed' | 'approval-requested' | 'error'
+
+/**
+ * An automation endpoint (n8n, Zapier, ...). Each delivery is a JSON POST with
+ * `X-Codex-Event` and an `X-Codex-Signature: sha256=<hmac>` of the body.
+ */
+export interface Webhook {
+    id: string
+    url: string
+    /** `*` receives every event. */
+    events: Array<WebhookEvent | '*'>
+    /** Last four characters of the signing secret. */
+    secretHint: string
+    enabled: boolean
+    createdAt: string
+    lastDeliveryAt: string | null
+    lastStatus: number | null
+    lastError: string | null
+}
+
+export async function listWebhooks(): Promise<Webhook[]> {
+    return api().listWebhooks()
+}
+
+/** Without `secret` one is generated; the returned `secret` is the only time it is shown. */
+export async function addWebhook(url: string, events: Array<WebhookEvent | '*'>, secret?: string): Promise<{ success: boolean; webhook?: Webhook; secret?: string; error?: string }> {
+    return api().addWebhook(url, events, secret)
+}
+
+export async function removeWebhook(id: string): Promise<{ success: boolean }> {
+    return api().removeWebhook(id)
+}
+
+export async function setWebhookEnabled(id: string, enabled: boolean): Promise<{ success: boolean }> {
+    return api().setWebhookEnabled(id, enabled)
+}
+
+/** Send a `ping` event and report the response status. */
+export async function testWebhook(id: string): Promise<{ success: boolean; status?: number; error?: string }> {
+    return api().testWebhook(id)
+}
+
+// ===== API Server =====
+
+/**
+ * Local HTTP API for scripts and editors, on 127.0.0.1 only. Every request needs
+ * `Authorization: Bearer <token>`. Routes: `GET /workspaces`, `GET /conversations`,
+ * `GET /conversations/:id/messages`, `POST /prompt` (server-sent events) and
+ * `POST /approvals/:requestId`.
+ */
+export interface ApiServerSettings {
+    enabled: boolean
+    port: number
+    token: string
+}
+
+export interface ApiServerStatus {
+    running: boolean
+    url: string | null
+    error?: string
+}
+
+export async function getApiServerSettings(): Promise<ApiServerSettings> {
+    return api().getApiServerSettings()
+}
+
+/** Saves the settings and starts, restarts or stops the server to match. An empty token is regenerated. */
+export async function setApiServerSettings(settings: Partial<ApiServerSettings>): Promise<{ settings: ApiServerSettings; status: ApiServerStatus }> {
+    return api().setApiServerSettings(settings)
+}
+
+export async function getApiServerStatus(): Promise<ApiServerStatus> {
+    return api().getApiServerStatus()
+}
+
+/** A turn started over the API finished; its messages are in the database. */
+export function onApiConversationUpdated(callback: (cid: string) => void): UnlistenFn {
+    return api().onApiConversationUpdated(callback)
+}
+
+export async function checkPromptPaths(conversationId: string, prompt: string): Promise<{ applies: boolean; paths: Array<{ reference: string; resolved: string }> }> {
+    return api().checkPromptPaths(conversationId, prompt)
+}
+
+export type PromptIssueKind = 'missing-file' | 'ambiguous-reference' | 'sandbox-forbidden' | 'broad-scope'
+
+export interface PromptIssue {
+    kind: PromptIssueKind
+    severity: 'warning' | 'info'
+    message: string
+    suggestion: string
+    /** Character range of the flagged text in the prompt. */
+    start: number
+    end: number
+}
+
+/**
+ * Check a prompt before sending it: missing files, pronouns with nothing to refer to,
+ * actions the sandbox forbids and over-broad requests. Pass the conversation to use
+ * its overrides and last activity.
+ */
+export async function analyzePrompt(text: string, workspaceId: string, conversationId?: string): Promise<{ success: boolean; issues: PromptIssue[]; error?: string }> {
+    return api().analyzePrompt(text, workspaceId, conversationId)
 }
 
-export interface FileSearchResult {
+/**
+ * Stop a running turn. `graceful` lets the CLI flush its last items for the configured
+ * grace period first; the stream then ends with `partial: true`.
+ */
+export async function cancelPrompt(conversationId: string, graceful = false): Promise<{ success: boolean; error?: string }> {
+    return api().cancelPrompt(conversationId, graceful)
+}
+
+export interface StreamToolCall {
+    id: string
+    title: string
+    status: 'running' | 'done' | 'error'
+    output: string
+    terminalId?: string
+    exitCode?: number | null
+}
+
+export interface StreamSnapshot {
+    cid: string
+    running: boolean
+    turnId: string | null
+    interrupting: boolean
+    text: string
+    thinking: string
+    truncated: boolean
+    toolCalls: StreamToolCall[]
+    approvals: Array<{ requestId: string; title: string; description: string }>
+}
+
+/**
+ * What the running turn of a conversation has streamed so far, so a reloaded UI can
+ * rebuild the live view and then continue with the stream events.
+ */
+export async function getStreamSnapshot(conversationId: string): Promise<StreamSnapshot> {
+    return api().getStreamSnapshot(conversationId)
+}
+
+/** A codex-* event as it was sent; every codex-* payload carries the same `seq`. */
+export interface BufferedStreamEvent {
+    seq: number
+    channel: string
+    payload: unknown
+}
+
+/**
+ * The conversation's recent events after `sinceSeq` (the last `seq` the UI saw), to
+ * replay after a reload. When `complete` is false some were already dropped; rebuild
+ * from getStreamSnapshot instead.
+ */
+export async function getStreamBuffer(conversationId: string, sinceSeq = 0): Promise<{ conversationId: string; events: BufferedStreamEvent[]; lastSeq: number; complete: boolean }> {
+    return api().getStreamBuffer(conversationId, sinceSeq)
+}
+
+export type StreamEventKind = 'delta' | 'thinking' | 'tools' | 'approvals' | 'progress' | 'usage' | 'status'
+
+export type StreamSubscription = { conversationId: string; kinds: StreamEventKind[] }
+
+/**
+ * Only receive the given kinds of stream events for a conversation (`*` for all
+ * conversations; no kinds for every kind). Until a window subscribes, it receives
+ * every event. Follow with getStreamSnapshot to catch up.
+ */
+export async function subscribeStream(conversationId: string, kinds: StreamEventKind[] = []): Promise<{ success: boolean; subscriptions?: StreamSubscription[]; error?: string }> {
+    return api().subscribeStream(conversationId, kinds)
+}
+
+/** Drop one subscription, or all of them (back to receiving every event) without an id. */
+export async function unsubscribeStream(conversationId?: string): Promise<{ success: boolean; subscriptions: StreamSubscription[] }> {
+    return api().unsubscribeStream(conversationId)
+}
+
+export interface QueuedPrompt {
+    id: string
+    prompt: string
+    conversationHistory?: Array<{ role: string; content: string }>
+    queuedAt: string
+}
+
+export async function getQueueMode(): Promise<boolean> {
+    return api().getQueueMode()
+}
+
+export async function setQueueMode(enabled: boolean): Promise<boolean> {
+    return api().setQueueMode(enabled)
+}
+
+export interface ReviewScope {
+    /** Exact command lines; one ending in `*` approves every command starting with the rest. */
+    commands: string[]
+    /** Paths relative to the turn's cwd; one ending in `/` approves everything below it. */
+    files: string[]
+}
+
+export interface PlanStep {
+    title: string
+    commands: string[]
+    files: string[]
+}
+
+export interface PendingReview {
+    turnId: string
+    prompt: string
+    plan: string
+    proposed: ReviewScope
+    steps: PlanStep[]
+    createdAt: string
+}
+
+export async function getReviewMode(): Promise<boolean> {
+    return api().getReviewMode()
+}
+
+/**
+ * Safety review mode: turns first run read-only and propose the commands and files they
+ * need (onReviewRequest); approveReview reruns them with write access limited to that.
+ */
+export async function setReviewMode(enabled: boolean): Promise<boolean> {
+    return api().setReviewMode(enabled)
+}
+
+export async function getPendingReview(conversationId: string): Promise<PendingReview | null> {
+    return api().getPendingReview(conversationId)
+}
+
+/** Run a reviewed plan; `scope` replaces what the plan proposed. */
+export async function approveReview(conversationId: string, scope?: Partial<ReviewScope>): Promise<{ success: boolean; turnId?: string; scope?: ReviewScope; error?: string }> {
+    return api().approveReview(conversationId, scope)
+}
+
+/** Run only the given steps (0-based) of a reviewed plan, limited to their commands and files. */
+export async function executePlan(conversationId: string, stepIndices: number[]): Promise<{ success: boolean; turnId?: string; scope?: ReviewScope; error?: string }> {
+    return api().executePlan(conversationId, stepIndices)
+}
+
+export async function rejectReview(conversationId: string): Promise<{ success: boolean }> {
+    return api().rejectReview(conversationId)
+}
+
+export async function getCancelGracePeriod(): Promise<number> {
+    return api().getCancelGracePeriod()
+}
+
+/** Milliseconds a graceful cancel waits before killing the CLI (0-30000; 0 kills immediately). */
+export async function setCancelGracePeriod(ms: number): Promise<number> {
+    return api().setCancelGracePeriod(ms)
+}
+
+export interface HistorySettings {
+    /** Tokens of pasted history per turn, summary included (at least 500). */
+    tokenBudget: number
+    /** The newest messages always pasted verbatim (1-50). */
+    keepRecent: number
+    /** Model for the rolling summary of older messages; empty uses the conversation's. */
+    summaryModel: string
+}
+
+export interface HistorySummary {
+    summary: string
+    /** The summary stands in for the conversation's first this many messages. */
+    coveredMessages: number
+    updatedAt: string
+}
+
+export async function getHistorySettings(): Promise<HistorySettings> {
+    return api().getHistorySettings()
+}
+
+/**
+ * History beyond the budget is replaced by a rolling summary, written in the background
+ * by the summary model once older messages stop fitting.
+ */
+export async function setHistorySettings(settings: Partial<HistorySettings>): Promise<HistorySettings> {
+    return api().setHistorySettings(settings)
+}
+
+export async function getHistorySummary(conversationId: string): Promise<HistorySummary | null> {
+    return api().getHistorySummary(conversationId)
+}
+
+export async function clearHistorySummary(conversationId: string): Promise<boolean> {
+    return api().clearHistorySummary(conversationId)
+}
+
+export async function getPromptQueue(conversationId: string): Promise<QueuedPrompt[]> {
+    return api().getPromptQueue(conversationId)
+}
+
+export async function clearPromptQueue(conversationId: string): Promise<{ success: boolean; cleared: number }> {
+    return api().clearPromptQueue(conversationId)
+}
+
+export async function getMaxConcurrentTurns(): Promise<number> {
+    return api().getMaxConcurrentTurns()
+}
+
+/** Turns running at once across conversations; 0 is unlimited. Further turns wait, oldest first. */
+export async function setMaxConcurrentTurns(limit: number): Promise<number> {
+    return api().setMaxConcurrentTurns(limit)
+}
+
+export interface RunningTurnStatus {
+    conversationId: string
+    turnId: string
+    status: 'running' | 'waiting'
+    /** When it started running, or when it was queued. */
+    since?: string
+    /** 1-based place among all waiting turns. */
+    position?: number
+    /** Further prompts queued behind it in the same conversation. */
+    queued: number
+}
+
+/** `limit` is the one in effect, the stricter of the setting and low-memory mode; 0 is unlimited. */
+export async function getRunningTurns(): Promise<{ limit: number; running: number; turns: RunningTurnStatus[] }> {
+    return api().getRunningTurns()
+}
+
+/**
+ * A scratch directory under ~/.codex-wui/tmp for the conversation. It is removed when the
+ * conversation's turn ends or is cancelled, and left-overs are cleared when the app starts.
+ */
+export async function allocateTempDir(conversationId: string): Promise<{ success: boolean; path?: string; error?: string }> {
+    return api().allocateTempDir(conversationId)
+}
+
+export async function releaseTempDirs(conversationId: string): Promise<{ success: boolean; removed: number }> {
+    return api().releaseTempDirs(conversationId)
+}
+
+/**
+ * Run the conversation's turns in a directory of its workspace (relative or absolute);
+ * null goes back to the workspace cwd. `cwd` comes back relative to the workspace root.
+ */
+export async function setConversationCwd(conversationId: string, cwdPath: string | null): Promise<{ success: boolean; cwd?: string | null; error?: string }> {
+    return api().setConversationCwd(conversationId, cwdPath)
+}
+
+export async function moveConversation(conversationId: string, targetWorkspaceId: string, migrateCwd: boolean): Promise<{ success: boolean; cwd?: string; cancelled?: boolean; error?: string }> {
+    return api().moveConversation(conversationId, targetWorkspaceId, migrateCwd)
+}
+
+export async function updateTitleBarOverlay(color: string, symbolColor: string): Promise<{ success: boolean; error?: string }> {
+    return api().updateTitleBarOverlay(color, symbolColor)
+}
+
+export type ApprovalDecision = 'approve' | 'approve_for_session' | 'deny' | 'abort'
+
+/**
+ * Answer an approval request. `true`/`false` mean approve/deny; `message` is given to
+ * codex as the reason for a denial.
+ */
+export async function respondToApproval(
+    requestId: string,
+    decision: ApprovalDecision | boolean,
+    message?: string,
+): Promise<{ success: boolean; error?: string }> {
+    return api().respondToApproval(requestId, decision, message)
+}
+
+export async function listOrphanProcesses(): Promise<Array<{ pid: number; ppid: number; command: string }>> {
+    return api().listOrphanProcesses()
+}
+
+/** `api-key` asks for the key in a native password dialog; it never reaches the renderer. */
+export async function codexLogin(method?: 'browser' | 'device-auth' | 'api-key'): Promise<{ success: boolean; user?: CodexUser; error?: string }> {
+    return api().codexLogin(method)
+}
+
+/** Stop a login stuck waiting on the browser or a device code. */
+export async function cancelLogin(): Promise<{ success: boolean }> {
+    return api().cancelLogin()
+}
+
+export async function codexLogout(): Promise<{ success: boolean; error?: string }> {
+    return api().codexLogout()
+}
+
+export interface LoginProgress {
+    status: 'started' | 'waiting' | 'complete' | 'error' | 'cancelled'
+    method: string
+    /** Where to sign in when the browser did not open; show it as a link or QR code. */
+    url?: string
+    /** The one-time code for the device-auth flow. */
+    code?: string
+    message?: string
+}
+
+export async function codexLoginMethods(): Promise<{ methods: Array<{ id: 'browser' | 'device-auth' | 'api-key'; label: string }> }> {
+    return api().codexLoginMethods()
+}
+
+export async function getUser(): Promise<CodexUser | null> {
+    return api().getUser()
+}
+
+/** A sign-in stored under `~/.codex-wui/accounts/<name>`. */
+export interface CodexAccount {
+    name: string
+    user: CodexUser | null
+    /** The account codex is signed in as now. */
+    active: boolean
+}
+
+export async function listAccounts(): Promise<CodexAccount[]> {
+    return api().listAccounts()
+}
+
+/** Run a fresh login and store it as `name`; the previous sign-in is kept when it fails. */
+export async function addAccount(name: string, method?: 'browser' | 'device-auth' | 'api-key'): Promise<{ success: boolean; account?: CodexAccount; error?: string }> {
+    return api().addAccount(name, method)
+}
+
+export async function saveCurrentAccount(name: string): Promise<{ success: boolean; account?: CodexAccount; error?: string }> {
+    return api().saveCurrentAccount(name)
+}
+
+/** Cancels running turns, then signs codex in as the account. */
+export async function switchAccount(name: string): Promise<{ success: boolean; account?: CodexAccount; cancelledTurns?: number; error?: string }> {
+    return api().switchAccount(name)
+}
+
+export async function removeAccount(name: string): Promise<{ success: boolean; error?: string }> {
+    return api().removeAccount(name)
+}
+
+// ===== Secrets =====
+
+export interface SecretSettings {
+    /** Keep the API key, GitHub token and webhook secrets in the OS keychain. */
+    useKeychain: boolean
+}
+
+/** Store a secret encrypted with the OS keychain, e.g. `codex.apiKey` or `github.token`. */
+export async function secretSet(name: string, value: string): Promise<{ success: boolean; error?: string }> {
+    return api().secretSet(name, value)
+}
+
+/** `codex.*` secrets are write-only: only `exists` is reported for them. */
+export async function secretGet(name: string): Promise<{ success: boolean; exists: boolean; error?: string }> {
+    return api().secretGet(name)
+}
+
+export async function secretDelete(name: string): Promise<{ success: boolean; error?: string }> {
+    return api().secretDelete(name)
+}
+
+export async function getSecretSettings(): Promise<SecretSettings & { keychainAvailable: boolean }> {
+    return api().getSecretSettings()
+}
+
+export async function setSecretSettings(settings: Partial<SecretSettings>): Promise<SecretSettings> {
+    return api().setSecretSettings(settings)
+}
+
+// ===== File System =====
+
+/** With `conversationId`, the search is limited to the conversation's target package. */
+export async function searchFiles(workspacePath: string, query: string, conversationId?: string): Promise<FileSearchResult[]> {
+    return api().searchFiles(workspacePath, query, conversationId)
+}
+
+export interface ContentSearchOptions {
+    regex?: boolean
+    caseSensitive?: boolean
+    contextLines?: number
+    maxResults?: number
+    glob?: string
+}
+
+export interface ContentSearchMatch {
+    path: string
+    relativePath: string
+    lineNumber: number
+    column: number
+    line: string
+    before: string[]
+    after: string[]
+}
+
+/** Search inside workspace files (ripgrep when installed, respecting .gitignore). */
+export async function searchFileContents(workspacePath: string, query: string, options?: ContentSearchOptions): Promise<{ success: boolean; matches?: ContentSearchMatch[]; engine?: 'ripgrep' | 'builtin'; error?: string }> {
+    return api().searchFileContents(workspacePath, query, options)
+}
+
+export type FileEncoding = 'utf-8' | 'utf-16le' | 'utf-16be' | 'latin1' | 'binary'
+
+/**
+ * Binary files come back with `binary: true` and no content; files over 10MB with `tooLarge: true` (use readFileRange).
+ * Protected files (.env, keys) fail with `protected: true` until allowed with allowProtectedFile.
+ */
+export async function readFileContent(filePath: string, workspacePath?: string): Promise<{ success: boolean; content?: string; encoding?: FileEncoding; binary?: boolean; tooLarge?: boolean; size?: number; protected?: boolean; error?: string }> {
+    return api().readFileContent(filePath, workspacePath)
+}
+
+export interface FileRange {
+    offset: number
+    length: number
+    totalSize: number
+    /** null at end of file. */
+    nextOffset: number | null
+    encoding: FileEncoding
+    /** null for binary files. */
+    content: string | null
+}
+
+/** A slice of a file (default 1MB, max 8MB) decoded to whole characters, for paging through large files. */
+export async function readFileRange(filePath: string, offset = 0, maxBytes = 1024 * 1024, workspacePath?: string): Promise<{ success: boolean; range?: FileRange; protected?: boolean; error?: string }> {
+    return api().readFileRange(filePath, offset, maxBytes, workspacePath)
+}
+
+export interface HexRow {
+    offset: number
+    hex: string
+    ascii: string
+}
+
+export interface HexPage {
+    offset: number
+    length: number
+    totalSize: number
+    nextOffset: number | null
+    rows: HexRow[]
+}
+
+/** A page (max 64KB, 16-byte rows) of a workspace file as hex and ASCII. */
+export async function readFileHex(filePath: string, offset = 0, length = 4096, workspacePath?: string): Promise<{ success: boolean; page?: HexPage; protected?: boolean; error?: string }> {
+    return api().readFileHex(filePath, offset, length, workspacePath)
+}
+
+export interface ProtectedPathSettings {
+    /** Globs as in .gitignore; a leading `!` exempts files an earlier pattern caught. */
+    patterns: string[]
+    /** Absolute paths of protected files allowed one by one. */
+    allowed: string[]
+}
+
+/** Files matching these never reach mentions, content search, file reads or attachments. */
+export async function getProtectedPaths(): Promise<ProtectedPathSettings> {
+    return api().getProtectedPaths()
+}
+
+export async function setProtectedPaths(settings: Partial<ProtectedPathSettings>): Promise<ProtectedPathSettings> {
+    return api().setProtectedPaths(settings)
+}
+
+/** Let one protected file be read from now on; call only after the user confirmed it. */
+export async function allowProtectedFile(filePath: string, workspacePath: string): Promise<{ success: boolean; settings?: ProtectedPathSettings; error?: string }> {
+    return api().allowProtectedFile(filePath, workspacePath)
+}
+
+export async function revokeProtectedFile(filePath: string): Promise<ProtectedPathSettings> {
+    return api().revokeProtectedFile(filePath)
+}
+
+/**
+ * Replaces the file atomically, keeping its permissions. With `createBackup` the previous
+ * content is saved and `backupId` can be passed to restoreFileBackup.
+ */
+export async function writeFile(filePath: string, content: string, workspacePath?: string, options?: { createBackup?: boolean }): Promise<{ success: boolean; backupId?: string; error?: string }> {
+    return api().writeFile(filePath, content, workspacePath, options)
+}
+
+export async function restoreFileBackup(backupId: string, workspacePath?: string): Promise<{ success: boolean; path?: string; error?: string }> {
+    return api().restoreFileBackup(backupId, workspacePath)
+}
+
+/** With `followSymlinks: false`, symlinks are described as links instead of by their targets. */
+export async function listDirectory(dirPath: string, workspacePath?: string, options?: { followSymlinks?: boolean }): Promise<{ success: boolean; entries?: DirectoryEntry[]; error?: string }> {
+    return api().listDirectory(dirPath, workspacePath, options)
+}
+
+export async function fileExists(filePath: string, workspacePath?: string): Promise<boolean> {
+    return api().fileExists(filePath, workspacePath)
+}
+
+export type PathOperationResult = { success: boolean; path?: string; error?: string }
+
+/** Creates missing parent directories too. */
+export async function createDirectory(dirPath: string, workspacePath?: string): Promise<PathOperationResult> {
+    return api().createDirectory(dirPath, workspacePath)
+}
+
+/** A non-empty directory needs `recursive`; `toTrash` moves the entry to the system trash. */
+export async function deletePath(targetPath: string, workspacePath?: string, options?: { recursive?: boolean; toTrash?: boolean }): Promise<PathOperationResult> {
+    return api().deletePath(targetPath, workspacePath, options)
+}
+
+export async function renamePath(targetPath: string, newName: string, workspacePath?: string): Promise<PathOperationResult> {
+    return api().renamePath(targetPath, newName, workspacePath)
+}
+
+/** `destinationPath` is the full new path; an existing one is only replaced with `overwrite`. */
+export async function copyPath(sourcePath: string, destinationPath: string, workspacePath?: string, overwrite = false): Promise<PathOperationResult> {
+    return api().copyPath(sourcePath, destinationPath, workspacePath, overwrite)
+}
+
+/** `destinationPath` is the full new path; an existing one is only replaced with `overwrite`. */
+export async function movePath(sourcePath: string, destinationPath: string, workspacePath?: string, overwrite = false): Promise<PathOperationResult> {
+    return api().movePath(sourcePath, destinationPath, workspacePath, overwrite)
+}
+
+export async function openInEditor(filePath: string, editor?: string): Promise<{ success: boolean; editor?: string; error?: string }> {
+    return api().openInEditor(filePath, editor)
+}
+
+// ===== Archives =====
+
+export type ArchiveFormat = 'zip' | 'tar' | 'tar.gz'
+
+export interface ArchiveEntry {
+    name: string
+    type: 'file' | 'directory' | 'symlink' | 'other'
+    size: number
+    compressedSize: number
+    modifiedAt: string | null
+}
+
+export async function listArchive(archivePath: string, workspacePath?: string): Promise<{ success: boolean; format?: ArchiveFormat; entries?: ArchiveEntry[]; error?: string }> {
+    return api().listArchive(archivePath, workspacePath)
+}
+
+/**
+ * Extract into `dest` (inside the workspace); `members` limits it to those entries and
+ * their children. Entries escaping `dest` and links are reported in `skipped`.
+ */
+export async function extractArchive(archivePath: string, dest: string, workspacePath?: string, members?: string[]): Promise<{ success: boolean; extracted?: string[]; skipped?: Array<{ name: string; reason: string }>; error?: string }> {
+    return api().extractArchive(archivePath, dest, workspacePath, members)
+}
+
+// ===== Permissions =====
+
+export type PermissionCapability = 'write' | 'delete' | 'pty' | 'run'
+
+// Grants only come from the main process's permission dialog.
+export const permissions = {
+    revoke: (workspacePath: string, capability?: PermissionCapability): Promise<{ success: boolean; error?: string }> =>
+        api().permissions.revoke(workspacePath, capability),
+    list: (workspacePath?: string): Promise<Array<{ workspacePath: string; capability: PermissionCapability; grantedAt: string }>> =>
+        api().permissions.list(workspacePath),
+}
+
+// ===== Terminal =====
+
+/** Large output is cut in `output`/`errorOutput`; the full text is then in `outputFile`/`errorOutputFile`. */
+export async function runCommand(command: string, cwd: string): Promise<{ success: boolean; commandId: string; output?: string; errorOutput?: string; outputFile?: string; errorOutputFile?: string; exitCode?: number; error?: string }> {
+    return api().runCommand(command, cwd)
+}
+
+export async function runCodexCommand(subcommand: string, args: string[], cwd?: string): Promise<{ success: boolean; stdout: string; stderr: string; exitCode: number; error?: string }> {
+    return api().runCodexCommand(subcommand, args, cwd)
+}
+
+/** Start a command without waiting for it; follow it through onCommandOutput / onCommandExit. */
+export async function startCommand(command: string, cwd: string): Promise<{ success: boolean; commandId: string }> {
+    return api().startCommand(command, cwd)
+}
+
+export async function commandWriteStdin(commandId: string, data: string): Promise<{ success: boolean; error?: string }> {
+    return api().commandWriteStdin(commandId, data)
+}
+
+export async function killCommand(commandId: string): Promise<{ success: boolean; error?: string }> {
+    return api().killCommand(commandId)
+}
+
+/** Start a finished command again in the same directory; the new commandId can be compared with the old one. */
+export async function rerunCommand(commandId: string): Promise<{ success: boolean; commandId?: string; error?: string }> {
+    return api().rerunCommand(commandId)
+}
+
+/** A finished command, identified by its commandId. Run target tests are recorded too. */
+export interface CommandRunSummary {
+    commandId: string
+    command: string
+    cwd: string
+    exitCode: number
+    startedAt: string
+    finishedAt: string
+}
+
+export interface RunComparison {
+    runA: CommandRunSummary
+    runB: CommandRunSummary
+    sameCommand: boolean
+    /** Tests failing in B but not in A. */
+    newFailures: string[]
+    /** Tests failing in A that pass in B. */
+    fixedFailures: string[]
+    stillFailing: string[]
+    /** Tests failing in A that B did not report at all. */
+    notRerun: string[]
+    /** Output lines that differ once durations and timestamps are masked. */
+    addedLines: string[]
+    removedLines: string[]
+    truncated: boolean
+    /** One line, e.g. `exit 1 → 0, 2 fixed, 1 still failing`. */
+    summary: string
+}
+
+/** The last 50 finished commands, newest first. */
+export async function listCommandRuns(): Promise<CommandRunSummary[]> {
+    return api().listCommandRuns()
+}
+
+export async function compareCommandRuns(runA: string, runB: string): Promise<{ success: boolean; comparison?: RunComparison; error?: string }> {
+    return api().compareCommandRuns(runA, runB)
+}
+
+// ===== Git =====
+
+export interface GitStatusEntry {
+    path: string
+    oldPath: string | null
+    status: string
+}
+
+export interface GitStatus {
+    success: boolean
+    branch: string | null
+    staged: GitStatusEntry[]
+    unstaged: GitStatusEntry[]
+    untracked: string[]
+    conflicted: string[]
+    error?: string
+}
+
+export interface GitDiffHunk {
+    header: string
+    oldStart: number
+    oldLines: number
+    newStart: number
+    newLines: number
+    lines: Array<{ type: 'context' | 'add' | 'del'; content: string; oldLine: number | null; newLine: number | null }>
+}
+
+export interface GitFileDiff {
+    path: string
+    oldPath: string | null
+    staged: boolean
+    binary: boolean
+    hunks: GitDiffHunk[]
+}
+
+export interface GitCommit {
+    hash: string
+    shortHash: string
+    author: string
+    email: string
+    timestamp: number
+    subject: string
+}
+
+export const git = {
+    status: (workspacePath: string): Promise<GitStatus> =>
+        api().git.status(workspacePath),
+    diff: (workspacePath: string, file?: string): Promise<{ success: boolean; files: GitFileDiff[]; error?: string }> =>
+        api().git.diff(workspacePath, file),
+    log: (workspacePath: string, limit?: number): Promise<{ success: boolean; commits: GitCommit[]; error?: string }> =>
+        api().git.log(workspacePath, limit),
+}
+
+// ===== GitHub =====
+
+export interface GitHubSettings {
+    token: string
+    /** REST endpoint; GitHub Enterprise servers use `https://<host>/api/v3`. */
+    apiUrl: string
+}
+
+export async function getGitHubSettings(): Promise<GitHubSettings> {
+    return api().getGitHubSettings()
+}
+
+export async function setGitHubSettings(settings: Partial<GitHubSettings>): Promise<GitHubSettings> {
+    return api().setGitHubSettings(settings)
+}
+
+/**
+ * Commit the workspace's changes on a new `codex/...` branch, push it and open a pull
+ * request against `base` (origin's default branch when omitted).
+ */
+export async function createPrFromChanges(workspaceId: string, title: string, body?: string, base?: string): Promise<{ success: boolean; branch?: string; url?: string; number?: number; error?: string }> {
+    return api().createPrFromChanges(workspaceId, title, body, base)
+}
+
+// ===== Changelog =====
+
+/** `since` accepts anything `git log --since` understands, e.g. `2024-05-01` or `2 weeks ago`. */
+export async function generateChangelog(workspaceId: string, since: string): Promise<{ success: boolean; path?: string; content?: string; error?: string }> {
+    return api().generateChangelog(workspaceId, since)
+}
+
+// ===== Release =====
+
+export type ReleaseStep =
+    | { type: 'bump'; files: string[] }
+    | { type: 'commit'; message?: string }
+    | { type: 'tag'; name?: string; message?: string }
+    | { type: 'command'; command: string }
+    | { type: 'notify'; sink: 'teams' | 'webhook'; url: string; message?: string }
+
+export type ReleaseStepStatus = 'pending' | 'running' | 'done' | 'failed' | 'rolled_back' | 'rollback_failed'
+
+export interface ReleaseStepResult {
+    index: number
+    type: ReleaseStep['type']
+    description: string
+    status: ReleaseStepStatus
+    output: string
+    error?: string
+}
+
+export interface ReleaseResult {
+    success: boolean
+    releaseId: string
+    dryRun: boolean
+    steps: ReleaseStepResult[]
+    rolledBack: boolean
+    error?: string
+}
+
+/** `{{version}}` in commit/tag/notify messages and commands is replaced with the version. */
+export async function runRelease(workspaceId: string, version: string, steps: ReleaseStep[], dryRun = false): Promise<ReleaseResult> {
+    return api().runRelease(workspaceId, version, steps, dryRun)
+}
+
+export function onReleaseStep(callback: (data: ReleaseStepResult & { releaseId: string; dryRun: boolean }) => void): UnlistenFn {
+    return api().onReleaseStep(callback)
+}
+
+export function onReleaseOutput(callback: (data: { releaseId: string; index: number; data: string }) => void): UnlistenFn {
+    return api().onReleaseOutput(callback)
+}
+
+// ===== Agent Instructions =====
+
+export interface InstructionsStatus {
+    projectRoot: string
+    /** AGENTS.md files codex combines for the cwd, in reading order. */
+    files: Array<{ path: string; scope: 'global' | 'project'; bytes: number }>
+    totalBytes: number
+    maxBytes: number
+    truncated: boolean
+}
+
+export interface ProjectKind {
+    kind: 'node' | 'rust' | 'python' | 'go'
+    build?: string
+    test?: string
+    lint?: string
+}
+
+/** AGENTS.md and AGENTS.override.md at the workspace root; null when missing. */
+export async function readInstructions(workspacePath: string): Promise<{ path: string; content: string | null; override: string | null }> {
+    return api().readInstructions(workspacePath)
+}
+
+export async function writeInstructions(workspacePath: string, content: string, override = false): Promise<{ success: boolean; path?: string; error?: string }> {
+    return api().writeInstructions(workspacePath, content, override)
+}
+
+/** Preview of a starter AGENTS.md for the detected project kinds. */
+export async function generateStarterInstructions(workspacePath: string): Promise<{ content: string; kinds: ProjectKind[] }> {
+    return api().generateStarterInstructions(workspacePath)
+}
+
+/** Writes the starter AGENTS.md; fails when one already exists. */
+export async function createStarterInstructions(workspacePath: string): Promise<{ success: boolean; path?: string; content?: string; error?: string }> {
+    return api().createStarterInstructions(workspacePath)
+}
+
+/** Adds to the "Team conventions" section of AGENTS.md, creating it if needed. */
+export async function appendInstructionsConventions(workspacePath: string, conventions: string): Promise<{ success: boolean; path?: string; content?: string; error?: string }> {
+    return api().appendInstructionsConventions(workspacePath, conventions)
+}
+
+/** Which instruction files codex will read when run in `cwd`. */
+export async function getInstructionsStatus(cwd: string): Promise<InstructionsStatus> {
+    return api().getInstructionsStatus(cwd)
+}
+
+export interface WorkspaceSummary {
+    summary: string
+    /** Hash of the README, manifests and top-level entries the summary was made from. */
+    fingerprint: string
+    generatedAt: string
+}
+
+/**
+ * Summarize the workspace from its README, manifests and layout with a read-only codex run.
+ * The result is cached until those files change (or `force`), and a conversation's first
+ * turn starts from it so the agent need not rediscover the project.
+ */
+export async function summarizeWorkspace(workspaceId: string, force?: boolean): Promise<{ success: boolean; summary?: WorkspaceSummary; cached?: boolean; error?: string }> {
+    return api().summarizeWorkspace(workspaceId, force)
+}
+
+export async function getWorkspaceSummary(workspaceId: string): Promise<WorkspaceSummary | null> {
+    return api().getWorkspaceSummary(workspaceId)
+}
+
+// ===== Monorepo Packages =====
+
+export interface WorkspacePackage {
+    name: string
+    kind: 'cargo' | 'node' | 'nx'
+    path: string
+    absolutePath: string
+    testCommand: string
+}
+
+export async function detectPackages(workspacePath: string): Promise<WorkspacePackage[]> {
+    return api().detectPackages(workspacePath)
+}
+
+/** Pass null to target the whole workspace again. */
+export async function setConversationTargetPackage(conversationId: string, packagePath: string | null): Promise<{ success: boolean; targetPackage?: WorkspacePackage | null; error?: string }> {
+    return api().setConversationTargetPackage(conversationId, packagePath)
+}
+
+export async function runTargetTests(conversationId: string): Promise<{ success: boolean; commandId?: string; error?: string }> {
+    return api().runTargetTests(conversationId)
+}
+
+// ===== Dependencies =====
+
+export type DependencyEcosystem = 'cargo' | 'npm' | 'pip'
+
+export interface OutdatedDependency {
+    ecosystem: DependencyEcosystem
+    name: string
+    current: string
+    wanted: string | null
+    latest: string
+    kind: string | null
+}
+
+export async function checkOutdatedDependencies(workspacePath: string): Promise<{ success: boolean; ecosystems: DependencyEcosystem[]; dependencies: OutdatedDependency[]; errors: Array<{ ecosystem: DependencyEcosystem; error: string }> }> {
+    return api().checkOutdatedDependencies(workspacePath)
+}
+
+export async function buildDependencyUpgradePrompt(selected: OutdatedDependency[]): Promise<string> {
+    return api().buildDependencyUpgradePrompt(selected)
+}
+
+// ===== Teams Integration =====
+
+export async function sendToTeams(webhookUrl: string, title: string, content: string): Promise<{ success: boolean; status?: number; error?: string }> {
+    return api().sendToTeams(webhookUrl, title, content)
+}
+
+// ===== Turns =====
+
+export type TurnStatus = 'queued' | 'running' | 'completed' | 'failed' | 'cancelled'
+
+export type TurnOutcome = 'accepted' | 'rework' | 'rejected'
+
+export interface TurnToolCall {
+    itemId: string
+    kind: string
+    name: string
+    status: string
+    startedAt: string
+    finishedAt: string | null
+}
+
+/** One prompt/response cycle with its lifecycle, tool calls and metrics. */
+export interface Turn {
+    id: string
+    conversationId: string
+    userMessageId: string | null
+    assistantMessageId: string | null
+    status: TurnStatus
+    prompt: string
+    model: string | null
+    exitCode: number | null
+    error: string | null
+    usage: TokenUsage | null
+    costUsd: number | null
+    createdAt: string
+    startedAt: string | null
+    finishedAt: string | null
+    /** Launch preset the conversation had when the turn started. */
+    presetId: string | null
+    outcome: TurnOutcome | null
+    outcomeComment: string | null
+    ratedAt: string | null
+    toolCalls: TurnToolCall[]
+}
+
+export async function getTurns(conversationId: string, status?: TurnStatus): Promise<Turn[]> {
+    return api().getTurns(conversationId, status)
+}
+
+export async function getTurn(turnId: string): Promise<Turn | null> {
+    return api().getTurn(turnId)
+}
+
+/** The turn a message was the prompt or the reply of. */
+export async function getTurnForMessage(messageId: string): Promise<Turn | null> {
+    return api().getTurnForMessage(messageId)
+}
+
+/** Label a finished turn's work; `null` clears the rating. Feeds the outcome stats of getUsageSummary. */
+export async function rateTurn(turnId: string, outcome: TurnOutcome | null, comment?: string): Promise<{ success: boolean; turn?: Turn; error?: string }> {
+    return api().rateTurn(turnId, outcome, comment)
+}
+
+export interface DiffHunk {
+    oldStart: number
+    oldLines: number
+    newStart: number
+    newLines: number
+    /** Lines prefixed with ' ', '+' or '-'. */
+    lines: string[]
+}
+
+export interface FileDiff {
+    path: string
+    kind: 'add' | 'delete' | 'update'
+    movePath: string | null
+    hunks: DiffHunk[]
+    added: number
+    removed: number
+    truncated: boolean
+}
+
+export interface TurnDiffs {
+    turnId: string
+    createdAt: string
+    added: number
+    removed: number
+    files: FileDiff[]
+}
+
+/** A codex JSON event of a turn, as it was streamed. */
+export interface TurnEvent {
+    seq: number
+    type: string
+    itemType: string | null
+    itemId: string | null
+    event: unknown
+    createdAt: string
+}
+
+/** Stored file diffs of a conversation grouped by turn, newest first. */
+export async function getTurnDiffs(conversationId: string, turnId?: string): Promise<TurnDiffs[]> {
+    return api().getTurnDiffs(conversationId, turnId)
+}
+
+// ===== MCP =====
+
+export interface McpCallRecord {
+    id: string
+    turnId: string
+    conversationId: string
+    server: string
+    tool: string
+    status: string
+    /** Scrubbed, size-capped JSON of the arguments. */
+    request: string
+    response: string | null
+    error: string | null
+    requestBytes: number
+    responseBytes: number
+    truncated: boolean
+    startedAt: string
+    finishedAt: string | null
+}
+
+/** Full request/response payloads of the MCP calls made in a turn. */
+export async function getMcpCalls(turnId: string): Promise<McpCallRecord[]> {
+    return api().getMcpCalls(turnId)
+}
+
+export interface McpServerConfig {
+    name: string
+    command: string | null
+    args: string[]
+    env: Record<string, string>
+    url: string | null
+    enabled: boolean
+    startupTimeoutSec: number | null
+}
+
+export interface McpServerStatus {
+    name: string
+    transport: 'stdio' | 'remote'
+    status: 'healthy' | 'unhealthy' | 'disabled' | 'checking'
+    serverInfo: string | null
+    latencyMs: number | null
+    error: string | null
+    checkedAt: string
+}
+
+/** MCP servers configured in ~/.codex/config.toml. */
+export async function getMcpServers(): Promise<McpServerConfig[]> {
+    return api().getMcpServers()
+}
+
+export async function getMcpServerStatuses(): Promise<McpServerStatus[]> {
+    return api().getMcpServerStatuses()
+}
+
+/** Kill a hanging health probe and check the server again right away. */
+export async function restartMcpServer(name: string): Promise<{ success: boolean; status?: McpServerStatus; error?: string }> {
+    return api().restartMcpServer(name)
+}
+
+export function onMcpServerStatus(callback: (status: McpServerStatus) => void): UnlistenFn {
+    return api().onMcpServerStatus(callback)
+}
+
+/** Palette used for exported HTML and notification cards. */
+export type ArtifactTheme = 'default' | 'high-contrast' | 'colorblind-safe'
+
+export async function getArtifactTheme(): Promise<ArtifactTheme> {
+    return api().getArtifactTheme()
+}
+
+export async function setArtifactTheme(theme: ArtifactTheme): Promise<ArtifactTheme> {
+    return api().setArtifactTheme(theme)
+}
+
+// ===== PTY Terminal =====
+
+export interface AvailableShell {
+    /** `pwsh`, `powershell`, `cmd`, `git-bash`, or the executable name on Unix. */
     name: string
     path: string
-    relativePath: string
-    isDirectory: boolean
 }
 
-type UnlistenFn = () => void
+/** `fallbackFrom` is set when the requested shell was not found and another one started. */
+export interface PtySession {
+    id: string
+    shell: string
+    shellName: string
+    fallbackFrom?: string
+}
 
-// ===== Get codexApi from preload =====
-const api = () => (window as any).codexApi
+export const pty = {
+    /** `shell` is a name from `listAvailableShells` or an executable; omit it for the first available. */
+    create: (cwd?: string, shell?: string): Promise<PtySession | { success: false; error: string }> =>
+        api().pty.create(cwd, shell),
+    write: (id: string, data: string): Promise<{ success: boolean; error?: string }> =>
+        api().pty.write(id, data),
+    kill: (id: string): Promise<{ success: boolean; error?: string }> =>
+        api().pty.kill(id),
+    list: (): Promise<string[]> =>
+        api().pty.list(),
+    /** Installed shells in fallback order, for the terminal settings. */
+    listAvailableShells: (): Promise<AvailableShell[]> =>
+        api().pty.listAvailableShells(),
+    onData: (callback: (id: string, data: string) => void): UnlistenFn =>
+        api().pty.onData(callback),
+    onExit: (callback: (id: string, exitCode: number) => void): UnlistenFn =>
+        api().pty.onExit(callback),
+}
 
-// ===== Commands =====
+// ===== Web Search =====
 
-export async function setMode(mode: ModelMode): Promise<ModelMode> {
-    return api().setMode(mode)
+export type WebSearchProvider = 'duckduckgo' | 'brave' | 'searxng' | 'google' | 'bing'
+
+export interface SearchResult {
+    title: string
+    url: string
+    snippet: string
 }
 
-export async function getMode(): Promise<ModelMode> {
-    return api().getMode()
+export interface WebSearchSettings {
+    provider: WebSearchProvider
+    maxResults: number
+    braveApiKey: string
+    searxngUrl: string
+    googleApiKey: string
+    googleCx: string
+    bingApiKey: string
+    bingEndpoint: string
 }
 
-export async function setYoloMode(enabled: boolean): Promise<boolean> {
-    return api().setYoloMode(enabled)
+export async function webSearch(
+    query: string,
+    provider?: WebSearchProvider,
+    maxResults?: number,
+): Promise<{ success: boolean; provider: WebSearchProvider; results: SearchResult[]; error?: string }> {
+    return api().webSearch(query, provider, maxResults)
 }
 
-export async function getYoloMode(): Promise<boolean> {
-    return api().getYoloMode()
+export type UrlContentMode = 'markdown' | 'text' | 'raw'
+
+export interface UrlContent {
+    url: string
+    title: string
+    content: string
+    mode: UrlContentMode
+    contentType: string
+    truncated: boolean
+    cached: boolean
 }
 
-export async function getModels(): Promise<Array<{ id: string; name: string; description: string }>> {
-    return api().getModels()
+/** A page's main content as Markdown or text, to add to a prompt as context. */
+export async function fetchUrlContent(url: string, mode?: UrlContentMode): Promise<{ success: boolean; content?: UrlContent; error?: string }> {
+    return api().fetchUrlContent(url, mode)
 }
 
-export async function getModel(): Promise<string> {
-    return api().getModel()
+export async function getWebSearchSettings(): Promise<WebSearchSettings> {
+    return api().getWebSearchSettings()
 }
 
-export async function setModel(modelId: string): Promise<string> {
-    return api().setModel(modelId)
+export async function setWebSearchSettings(settings: Partial<WebSearchSettings>): Promise<WebSearchSettings> {
+    return api().setWebSearchSettings(settings)
 }
 
-export async function setCliOptions(options: Partial<CliOptions>): Promise<CliOptions> {
-    return api().setCliOptions(options)
+// ===== Chores =====
+
+export interface Chore {
+    id: string
+    workspaceId: string
+    name: string
+    prompt: string
+    runAt: string
+    enabled: boolean
+    lastRunAt: string | null
 }
 
-export async function getCliOptions(): Promise<CliOptions> {
-    return api().getCliOptions()
+export type ChoreRunStatus = 'running' | 'draft' | 'failed' | 'applied' | 'dismissed'
+
+export interface ChoreRun {
+    id: string
+    choreId: string
+    workspaceId: string
+    status: ChoreRunStatus
+    plan: string | null
+    error: string | null
+    conversationId: string | null
+    startedAt: string
+    finishedAt: string | null
 }
 
-export async function checkCodex(): Promise<{ installed: boolean }> {
-    return api().checkCodex()
+export const chores = {
+    list: (workspaceId?: string): Promise<Chore[]> =>
+        api().chores.list(workspaceId),
+    save: (chore: Omit<Chore, 'id' | 'lastRunAt'> & { id?: string }): Promise<{ success: boolean; chore?: Chore; error?: string }> =>
+        api().chores.save(chore),
+    delete: (id: string): Promise<{ success: boolean }> =>
+        api().chores.delete(id),
+    runNow: (id: string): Promise<{ success: boolean; run?: ChoreRun; error?: string }> =>
+        api().chores.runNow(id),
+    listRuns: (workspaceId?: string, status?: ChoreRunStatus): Promise<ChoreRun[]> =>
+        api().chores.listRuns(workspaceId, status),
+    applyRun: (runId: string): Promise<{ success: boolean; conversation?: any; error?: string }> =>
+        api().chores.applyRun(runId),
+    dismissRun: (runId: string): Promise<{ success: boolean }> =>
+        api().chores.dismissRun(runId),
+    onRunCompleted: (callback: (run: ChoreRun) => void): UnlistenFn =>
+        api().chores.onRunCompleted(callback),
 }
 
-export async function installCodex(): Promise<{ success: boolean; error?: string }> {
-    return api().installCodex()
+// ===== Scheduled Tasks =====
+
+export interface ScheduledTask {
+    id: string
+    workspaceId: string
+    name: string
+    /** Five-field cron expression in local time, or a macro such as `@daily`. */
+    cron: string
+    prompt: string
+    cliOptions: CliOverrides
+    enabled: boolean
+    createdAt: string
+    lastRunAt: string | null
+    /** Unix seconds of the next run, or null while disabled. */
+    nextRunAt: string | null
+    lastStatus: 'running' | 'completed' | 'failed' | null
+    lastError: string | null
+    lastConversationId: string | null
 }
 
-export async function initAcp(): Promise<{ success: boolean; error?: string }> {
-    return api().initAcp()
+export interface ScheduledTaskResult {
+    taskId: string
+    workspaceId: string
+    name: string
+    success: boolean
+    /** The new conversation holding the prompt and the agent's answer. */
+    conversationId: string
+    error?: string
+    startedAt: string
+    finishedAt: string
 }
 
-export async function openWorkspace(): Promise<{ path: string; name: string } | null> {
-    return api().openWorkspace()
+export type ScheduledTaskInput = Pick<ScheduledTask, 'workspaceId' | 'name' | 'cron' | 'prompt' | 'enabled'> & {
+    id?: string
+    cliOptions?: CliOverrides
 }
 
-export async function switchWorkspace(workspaceId: string, cwd: string): Promise<{ success: boolean; sessionId?: string; error?: string }> {
-    return api().switchWorkspace(workspaceId, cwd)
+/** Cron-scheduled prompts, run headlessly; each run becomes a new conversation. */
+export const scheduler = {
+    list: (workspaceId?: string): Promise<ScheduledTask[]> =>
+        api().scheduler.list(workspaceId),
+    save: (task: ScheduledTaskInput): Promise<{ success: boolean; task?: ScheduledTask; error?: string }> =>
+        api().scheduler.save(task),
+    delete: (id: string): Promise<{ success: boolean }> =>
+        api().scheduler.delete(id),
+    runNow: (id: string): Promise<{ success: boolean; result?: ScheduledTaskResult; error?: string }> =>
+        api().scheduler.runNow(id),
+    /** Null when valid, else the problem with the expression. */
+    validateCron: (expression: string): Promise<string | null> =>
+        api().scheduler.validateCron(expression),
+    onTaskCompleted: (callback: (result: ScheduledTaskResult) => void): UnlistenFn =>
+        api().scheduler.onTaskCompleted(callback),
 }
 
-export async function streamCodex(conversationId: string, prompt: string, conversationHistory?: Array<{ role: 'user' | 'assistant'; content: string }>): Promise<void> {
-    return api().streamCodex(conversationId, prompt, conversationHistory)
+// ===== Watch Rules =====
+
+export interface WatchRule {
+    id: string
+    workspaceId: string
+    glob: string
+    promptTemplate: string
+    debounceMs: number
+    maxPerHour: number
+    enabled: boolean
+    conversationId: string | null
+    lastTriggeredAt: string | null
 }
 
-export async function debugLog(msg: string): Promise<void> {
-    return api().debugLog(msg)
+export const watchRules = {
+    list: (workspaceId?: string): Promise<WatchRule[]> =>
+        api().watchRules.list(workspaceId),
+    create: (
+        workspaceId: string,
+        glob: string,
+        promptTemplate: string,
+        debounceMs?: number,
+        maxPerHour?: number,
+    ): Promise<{ success: boolean; rule?: WatchRule; error?: string }> =>
+        api().watchRules.create(workspaceId, glob, promptTemplate, debounceMs, maxPerHour),
+    setEnabled: (ruleId: string, enabled: boolean): Promise<{ success: boolean; rule?: WatchRule; error?: string }> =>
+        api().watchRules.setEnabled(ruleId, enabled),
+    delete: (ruleId: string): Promise<{ success: boolean }> =>
+        api().watchRules.delete(ruleId),
+    onTriggered: (callback: (data: { ruleId: string; conversationId: string; files: string[] }) => void): UnlistenFn =>
+        api().watchRules.onTriggered(callback),
+    onSkipped: (callback: (data: { ruleId: string; files: string[]; reason: string }) => void): UnlistenFn =>
+        api().watchRules.onSkipped(callback),
+}
+
+// ===== Launch Presets =====
+
+export interface LaunchPreset {
+    id: string
+    name: string
+    starred: boolean
+    options: CliOverrides
+    createdAt: string
 }
 
-export async function cancelPrompt(conversationId: string): Promise<{ success: boolean; error?: string }> {
-    return api().cancelPrompt(conversationId)
+export const presets = {
+    list: (): Promise<LaunchPreset[]> =>
+        api().presets.list(),
+    save: (preset: { id?: string; name: string; starred?: boolean; options: CliOverrides }): Promise<LaunchPreset> =>
+        api().presets.save(preset),
+    setStarred: (id: string, starred: boolean): Promise<{ success: boolean }> =>
+        api().presets.setStarred(id, starred),
+    delete: (id: string): Promise<{ success: boolean }> =>
+        api().presets.delete(id),
+    apply: (presetId: string, conversationId: string): Promise<{ success: boolean; cliOverrides?: CliOverrides; error?: string }> =>
+        api().presets.apply(presetId, conversationId),
 }
 
-export async function updateTitleBarOverlay(color: string, symbolColor: string): Promise<{ success: boolean; error?: string }> {
-    return api().updateTitleBarOverlay(color, symbolColor)
+// ===== Prompt History =====
+
+export interface PromptHistoryEntry {
+    id: number
+    workspaceId: string
+    prompt: string
+    useCount: number
+    lastUsedAt: string
 }
 
-export async function respondToApproval(requestId: string, approved: boolean): Promise<{ success: boolean }> {
-    return api().respondToApproval(requestId, approved)
+export async function getPromptHistory(workspaceId?: string, prefix?: string, limit?: number): Promise<PromptHistoryEntry[]> {
+    return api().getPromptHistory(workspaceId, prefix, limit)
 }
 
-export async function codexLogin(method?: 'browser' | 'device-auth' | 'api-key', apiKey?: string): Promise<{ success: boolean; user?: CodexUser; error?: string }> {
-    return api().codexLogin(method, apiKey)
+export async function clearPromptHistory(workspaceId?: string): Promise<{ success: boolean }> {
+    return api().clearPromptHistory(workspaceId)
 }
 
-export async function codexLogout(): Promise<{ success: boolean; error?: string }> {
-    return api().codexLogout()
+// ===== Spell Check =====
+
+export async function checkPromptText(text: string, lang?: string): Promise<{ success: boolean; issues: Array<{ word: string; line: number; column: number; suggestions: string[] }>; error?: string }> {
+    return api().checkPromptText(text, lang)
 }
 
-export async function codexLoginMethods(): Promise<{ methods: Array<{ id: 'browser' | 'device-auth' | 'api-key'; label: string }> }> {
-    return api().codexLoginMethods()
+// ===== Translation =====
+
+export interface TranslationSettings {
+    provider: 'ollama' | 'openai'
+    endpoint: string
+    model: string
+    apiKey: string
 }
 
-export async function getUser(): Promise<CodexUser | null> {
-    return api().getUser()
+export async function translateText(text: string, targetLang: string): Promise<{ success: boolean; text?: string; error?: string }> {
+    return api().translateText(text, targetLang)
 }
 
-// ===== File System =====
+export async function getTranslationSettings(): Promise<TranslationSettings> {
+    return api().getTranslationSettings()
+}
 
-export async function searchFiles(workspacePath: string, query: string): Promise<FileSearchResult[]> {
-    return api().searchFiles(workspacePath, query)
+export async function setTranslationSettings(settings: Partial<TranslationSettings>): Promise<TranslationSettings> {
+    return api().setTranslationSettings(settings)
 }
 
-export async function readFileContent(filePath: string, workspacePath?: string): Promise<{ success: boolean; content?: string; error?: string }> {
-    return api().readFileContent(filePath, workspacePath)
+export async function setConversationAutoTranslate(conversationId: string, targetLang: string | null): Promise<{ success: boolean }> {
+    return api().setConversationAutoTranslate(conversationId, targetLang)
 }
 
-export async function writeFile(filePath: string, content: string, workspacePath?: string): Promise<{ success: boolean; error?: string }> {
-    return api().writeFile(filePath, content, workspacePath)
+/** An assistant message got its auto-translation appended; `content` is the full new text. */
+export function onMessageTranslated(callback: (event: { cid: string; messageId: string; targetLang: string; content: string }) => void): UnlistenFn {
+    return api().onMessageTranslated(callback)
 }
 
-export async function listDirectory(dirPath: string, workspacePath?: string): Promise<{ success: boolean; entries?: Array<{ name: string; path: string; isDirectory: boolean; size: number }>; error?: string }> {
-    return api().listDirectory(dirPath, workspacePath)
+// ===== Conversation Notes / Export =====
+
+export async function getConversationNotes(conversationId: string): Promise<{ notes: string; includeInPrompt: boolean }> {
+    return api().getConversationNotes(conversationId)
 }
 
-export async function fileExists(filePath: string, workspacePath?: string): Promise<boolean> {
-    return api().fileExists(filePath, workspacePath)
+/** `includeInPrompt` is left unchanged when omitted. */
+export async function setConversationNotes(conversationId: string, notes: string, includeInPrompt?: boolean): Promise<{ success: boolean }> {
+    return api().setConversationNotes(conversationId, notes, includeInPrompt)
 }
 
-export async function openInEditor(filePath: string, editor?: string): Promise<{ success: boolean; editor?: string; error?: string }> {
-    return api().openInEditor(filePath, editor)
+export async function exportConversation(conversationId: string, format: 'markdown' | 'json' | 'html' = 'markdown', includeNotes = false): Promise<{ success: boolean; fileName?: string; content?: string; error?: string }> {
+    return api().exportConversation(conversationId, format, includeNotes)
 }
 
-// ===== Terminal =====
+/** Re-create a conversation from a JSON export in the given workspace (new ids, original timestamps). */
+export async function importConversation(jsonPath: string, workspaceId: string): Promise<{ success: boolean; conversation?: any; error?: string }> {
+    return api().importConversation(jsonPath, workspaceId)
+}
 
-export async function runCommand(command: string, cwd: string): Promise<{ success: boolean; commandId: string; output?: string; errorOutput?: string; exitCode?: number; error?: string }> {
-    return api().runCommand(command, cwd)
+export interface WorkspaceBundleImport {
+    workspace: any
+    conversations: number
+    templates: number
+    chores: number
+    watchRules: number
+    instructions: string[]
+    skipped: string[]
 }
 
-export async function runCodexCommand(subcommand: string, args: string[], cwd?: string): Promise<{ success: boolean; stdout: string; stderr: string; exitCode: number; error?: string }> {
-    return api().runCodexCommand(subcommand, args, cwd)
+/**
+ * Zip a workspace's conversations, settings, templates and AGENTS.md (no source code) to
+ * `destPath`; checkpoint metadata is added when `includeCheckpoints` is set.
+ */
+export async function exportWorkspaceBundle(workspaceId: string, destPath: string, includeCheckpoints = false): Promise<{ success: boolean; path?: string; size?: number; conversations?: number; error?: string }> {
+    return api().exportWorkspaceBundle(workspaceId, destPath, includeCheckpoints)
 }
 
-export async function killCommand(commandId: string): Promise<{ success: boolean; error?: string }> {
-    return api().killCommand(commandId)
+/** Create a workspace at `workspacePath` from a bundle. Chores and watch rules arrive disabled. */
+export async function importWorkspaceBundle(bundlePath: string, workspacePath: string, name?: string): Promise<{ success: boolean; result?: WorkspaceBundleImport; error?: string }> {
+    return api().importWorkspaceBundle(bundlePath, workspacePath, name)
 }
 
-// ===== Teams Integration =====
+// ===== Housekeeping =====
 
-export async function sendToTeams(webhookUrl: string, title: string, content: string): Promise<{ success: boolean; status?: number; error?: string }> {
-    return api().sendToTeams(webhookUrl, title, content)
+export interface HousekeepingSettings {
+    enabled: boolean
+    /** A workspace with no turns and no file changes for this many days is stale. */
+    staleDays: number
 }
 
-// ===== PTY Terminal =====
+export interface HousekeepingSuggestion {
+    workspaceId: string
+    name: string
+    path: string
+    /** Unix seconds of the latest activity; null if there was none. */
+    lastActivityAt: number | null
+    idleDays: number | null
+    /** The workspace folder no longer exists. */
+    missing: boolean
+    conversations: number
+    messages: number
+    /** Approximate database bytes of the workspace's conversations. */
+    footprintBytes: number
+    actions: Array<'archive' | 'export-prune'>
+}
 
-export const pty = {
-    create: (cwd?: string, shell?: string): Promise<{ id: string; shell: string }> =>
-        api().pty.create(cwd, shell),
-    write: (id: string, data: string): Promise<{ success: boolean; error?: string }> =>
-        api().pty.write(id, data),
-    kill: (id: string): Promise<{ success: boolean; error?: string }> =>
-        api().pty.kill(id),
-    list: (): Promise<string[]> =>
-        api().pty.list(),
-    onData: (callback: (id: string, data: string) => void): UnlistenFn =>
-        api().pty.onData(callback),
-    onExit: (callback: (id: string, exitCode: number) => void): UnlistenFn =>
-        api().pty.onExit(callback),
+export async function getHousekeepingSuggestions(): Promise<HousekeepingSuggestion[]> {
+    return api().getHousekeepingSuggestions()
 }
 
-// ===== Web Search =====
+export async function getHousekeepingSettings(): Promise<HousekeepingSettings> {
+    return api().getHousekeepingSettings()
+}
+
+export async function setHousekeepingSettings(settings: Partial<HousekeepingSettings>): Promise<HousekeepingSettings> {
+    return api().setHousekeepingSettings(settings)
+}
+
+/** Archive all of the workspace's conversations. */
+export async function archiveWorkspace(workspaceId: string): Promise<{ success: boolean; archived: number }> {
+    return api().archiveWorkspace(workspaceId)
+}
+
+/** Export the workspace bundle to `destPath`, then remove the workspace from the database. */
+export async function exportAndPruneWorkspace(workspaceId: string, destPath: string): Promise<{ success: boolean; path?: string; size?: number; freedBytes?: number; error?: string }> {
+    return api().exportAndPruneWorkspace(workspaceId, destPath)
+}
 
-export async function webSearch(query: string): Promise<{ success: boolean; results: Array<{ title: string; url: string; snippet: string }>; error?: string }> {
-    return api().webSearch(query)
+/** Stale workspaces found by the background check (sent after launch and twice a day). */
+export function onHousekeepingSuggestions(callback: (suggestions: HousekeepingSuggestion[]) => void): UnlistenFn {
+    return api().onHousekeepingSuggestions(callback)
 }
 
 // ===== Database =====
 
+export interface ConversationSummary {
+    id: string
+    workspaceId: string
+    title: string
+    createdAt: string
+    updatedAt: string
+    lastActivityAt: string
+    archived: boolean
+    pinned: boolean
+    forkedFrom?: string
+    messageCount: number
+    unreadCount: number
+}
+
+export interface ConversationListOptions {
+    /** List archived conversations instead of active ones. */
+    archived?: boolean
+    pinned?: boolean
+    sort?: 'activity' | 'created' | 'title'
+    offset?: number
+    /** 1-200, default 50. */
+    limit?: number
+}
+
+export interface DbChanges {
+    token: string
+    /** The token is unknown or too far behind; reload with getState. */
+    reset: boolean
+    /** Changed workspaces, with empty `conversations`. */
+    workspaces: any[]
+    /** Changed conversations, and those whose messages changed, with empty `messages`. */
+    conversations: any[]
+    messages: any[]
+    deleted: { workspaces: string[]; conversations: string[]; messages: string[] }
+    /** Change counter per workspace id. */
+    etags: Record<string, string>
+}
+
 export const db = {
-    getState: (): Promise<{ workspaces: any[] }> =>
+    /** Workspaces and conversations with `messageCount` but empty `messages`; see getMessagesPage. */
+    getState: (): Promise<{ workspaces: any[]; syncToken?: string }> =>
         api().db.getState(),
+    /** What was written or deleted since `syncToken` from getState or an earlier call. */
+    getChanges: (sinceToken: string): Promise<DbChanges> =>
+        api().db.getChanges(sinceToken),
     createWorkspace: (id: string, name: string, path: string) =>
         api().db.createWorkspace(id, name, path),
     deleteWorkspace: (id: string) =>
@@ -215,16 +2017,327 @@ export const db = {
         api().db.updateWorkspaceName(id, name),
     getConversations: (workspaceId: string) =>
         api().db.getConversations(workspaceId),
+    getConversationGroups: (workspaceId: string): Promise<{ groups: Array<{ bucket: 'today' | 'yesterday' | 'thisWeek' | 'thisMonth' | 'older'; conversationIds: string[] }>; dailyTurns: Array<{ day: string; turns: number }> }> =>
+        api().db.getConversationGroups(workspaceId),
     createConversation: (id: string, workspaceId: string, title: string) =>
         api().db.createConversation(id, workspaceId, title),
+    /** A page of conversations without messages, pinned first. */
+    listConversations: (workspaceId: string, options?: ConversationListOptions): Promise<{ conversations: ConversationSummary[]; total: number }> =>
+        api().db.listConversations(workspaceId, options),
+    /** Archiving also unpins. */
+    setConversationArchived: (id: string, archived: boolean): Promise<{ success: boolean }> =>
+        api().db.setConversationArchived(id, archived),
+    setConversationPinned: (id: string, pinned: boolean): Promise<{ success: boolean }> =>
+        api().db.setConversationPinned(id, pinned),
+    /** Copy of the conversation up to and including `fromMessageId`, as a new conversation. */
+    forkConversation: (conversationId: string, fromMessageId: string, newTitle?: string): Promise<{ success: boolean; conversation?: any; error?: string }> =>
+        api().db.forkConversation(conversationId, fromMessageId, newTitle),
     updateConversationTitle: (id: string, title: string) =>
         api().db.updateConversationTitle(id, title),
     deleteConversation: (id: string) =>
         api().db.deleteConversation(id),
     getMessages: (conversationId: string) =>
         api().db.getMessages(conversationId),
+    /** Up to `limit` (default 50) messages before `beforeId`, or the latest ones, oldest first. */
+    getMessagesPage: (conversationId: string, beforeId?: string | null, limit?: number): Promise<{ messages: any[]; hasMore: boolean }> =>
+        api().db.getMessagesPage(conversationId, beforeId, limit),
     createMessage: (message: { id: string; conversationId: string; role: string; content: string; thinking?: string; thinkingDuration?: number; timestamp: string }) =>
         api().db.createMessage(message),
+    /** Mark read up to `messageId` (default: the latest message). */
+    markConversationRead: (conversationId: string, messageId?: string): Promise<{ success: boolean; unreadCount: number }> =>
+        api().db.markConversationRead(conversationId, messageId),
+    /** Full content of a message; long ones are loaded with only their first part. */
+    getMessageContent: (messageId: string): Promise<string | null> =>
+        api().db.getMessageContent(messageId),
+    getMessagePart: (messageId: string, index: number): Promise<{ index: number; partCount: number; content: string } | null> =>
+        api().db.getMessagePart(messageId, index),
+    updateMessageContent: (messageId: string, content: string): Promise<{ success: boolean; partCount?: number; error?: string }> =>
+        api().db.updateMessageContent(messageId, content),
+    /** The codex events a turn streamed (items started and completed, turn and error events), to rebuild its tool timeline. */
+    getTurnEvents: (conversationId: string, turnId: string): Promise<TurnEvent[]> =>
+        api().db.getTurnEvents(conversationId, turnId),
+    searchMessages: (query: string, workspaceId?: string): Promise<Array<{ messageId: string; conversationId: string; conversationTitle: string; workspaceId: string; workspaceName: string; role: string; timestamp: string; snippet: string }>> =>
+        api().db.searchMessages(query, workspaceId),
+    getUsageSummary: (workspaceId?: string, range?: UsageRange): Promise<UsageSummary> =>
+        api().db.getUsageSummary(workspaceId, range),
+    /** Write a compacted copy of the database (VACUUM INTO) to `destPath`. */
+    backup: (destPath: string): Promise<{ success: boolean; path?: string; size?: number; error?: string }> =>
+        api().db.backup(destPath),
+    /** Replace the database with a backup; the previous one is kept as state.sqlite3.before-restore. */
+    restore: (srcPath: string): Promise<{ success: boolean; error?: string }> =>
+        api().db.restore(srcPath),
+    /** The database's schema version and the latest one this app migrates to. */
+    getSchemaVersion: (): Promise<{ version: number; latest: number }> =>
+        api().db.getSchemaVersion(),
+}
+
+// ===== Token Usage =====
+
+export interface TokenUsage {
+    promptTokens: number
+    cachedTokens: number
+    completionTokens: number
+    totalTokens: number
+}
+
+export type UsageRange = 'today' | 'week' | 'month' | 'all'
+
+export interface UsageBucket extends TokenUsage {
+    turns: number
+    costUsd: number | null
+}
+
+/** Ratings of finished turns for one model and launch preset combination. */
+export interface OutcomeBucket {
+    model: string | null
+    presetId: string | null
+    presetName: string | null
+    /** Finished turns, rated or not. */
+    turns: number
+    accepted: number
+    rework: number
+    rejected: number
+    /** Accepted share of the rated turns; null when none was rated. */
+    acceptanceRate: number | null
+}
+
+export interface UsageSummary {
+    range: UsageRange
+    totals: UsageBucket
+    byDay: Array<UsageBucket & { day: string }>
+    byModel: Array<UsageBucket & { model: string }>
+    outcomes: OutcomeBucket[]
+}
+
+/** USD per million tokens. */
+export interface ModelPricing {
+    prompt: number
+    cachedPrompt?: number
+    completion: number
+}
+
+export async function getUsagePricing(): Promise<Record<string, ModelPricing>> {
+    return api().getUsagePricing()
+}
+
+export async function setUsagePricing(pricing: Record<string, ModelPricing>): Promise<Record<string, ModelPricing>> {
+    return api().setUsagePricing(pricing)
+}
+
+export interface CostEstimatePoint {
+    promptTokens: number
+    completionTokens: number
+    costUsd: number | null
+}
+
+export interface TurnCostEstimate {
+    model: string
+    input: { prompt: number; history: number; attachments: number; overhead: number }
+    low: CostEstimatePoint
+    high: CostEstimatePoint
+    thresholdUsd: number | null
+    requiresConfirmation: boolean
+}
+
+/** Estimated cost range of sending `prompt`, shown before the turn starts. */
+export async function estimateTurnCost(
+    conversationId: string,
+    prompt: string,
+    attachments?: PromptAttachment[],
+): Promise<TurnCostEstimate> {
+    return api().estimateTurnCost(conversationId, prompt, attachments)
+}
+
+export async function getCostConfirmThreshold(): Promise<number | null> {
+    return api().getCostConfirmThreshold()
+}
+
+export async function setCostConfirmThreshold(thresholdUsd: number | null): Promise<number | null> {
+    return api().setCostConfirmThreshold(thresholdUsd)
+}
+
+// ===== Jobs =====
+
+export type JobKind = 'file-index' | 'export-workspace-bundle' | 'import-workspace-bundle'
+
+export interface Job {
+    id: string
+    kind: JobKind
+    title: string
+    status: 'running' | 'completed' | 'failed' | 'cancelled'
+    /** 0-100, null while unknown. */
+    progress: number | null
+    etaMs: number | null
+    message: string | null
+    params: any
+    result: any
+    error: string | null
+    startedAt: string
+    finishedAt: string | null
+}
+
+/** Jobs of this session, newest first, then failed jobs kept from earlier sessions. */
+export async function listJobs(): Promise<Job[]> {
+    return api().listJobs()
+}
+
+/**
+ * Start a background job. Params per kind: `file-index` { workspacePath },
+ * `export-workspace-bundle` { workspaceId, destPath, includeCheckpoints? },
+ * `import-workspace-bundle` { bundlePath, workspacePath, name? }.
+ */
+export async function startJob(kind: JobKind, title: string, params: Record<string, unknown>): Promise<{ success: boolean; job?: Job; error?: string }> {
+    return api().startJob(kind, title, params)
+}
+
+/** The job stops at its next progress step and ends as `cancelled`. */
+export async function cancelJob(jobId: string): Promise<{ success: boolean; error?: string }> {
+    return api().cancelJob(jobId)
+}
+
+export async function retryJob(jobId: string): Promise<{ success: boolean; job?: Job; error?: string }> {
+    return api().retryJob(jobId)
+}
+
+export async function dismissJob(jobId: string): Promise<{ success: boolean }> {
+    return api().dismissJob(jobId)
+}
+
+/** Fired when a job starts, makes progress or finishes. */
+export function onJobUpdated(callback: (job: Job) => void): UnlistenFn {
+    return api().onJobUpdated(callback)
+}
+
+// ===== Resources =====
+
+export interface ResourceLimits {
+    fileIndexes: number
+    indexedEntries: number
+    /** 0 is unlimited. */
+    streamTextChars: number
+    outputSpillBytes: number
+    /** 0 is unlimited. */
+    maxConcurrentTurns: number
+}
+
+export interface ResourceUsage {
+    lowMemory: boolean
+    limits: ResourceLimits
+    memory: { rss: number; heapUsed: number; heapTotal: number; external: number; arrayBuffers: number }
+    system: { totalMemory: number; freeMemory: number }
+    runningTurns: number
+    queuedPrompts: number
+    runningCommands: number
+    terminals: number
+    fileIndexes: { indexes: number; entries: number }
+}
+
+export async function getLowMemoryMode(): Promise<boolean> {
+    return api().getLowMemoryMode()
+}
+
+/**
+ * Smaller caches, earlier spilling of command output to disk and at most two turns at
+ * once; further prompts wait in their conversation's queue.
+ */
+export async function setLowMemoryMode(enabled: boolean): Promise<{ lowMemory: boolean; limits: ResourceLimits }> {
+    return api().setLowMemoryMode(enabled)
+}
+
+/** Memory use (bytes) of the backend process and the size of its caches. */
+export async function getResourceUsage(): Promise<ResourceUsage> {
+    return api().getResourceUsage()
+}
+
+// ===== Turn Snapshots =====
+
+export interface TurnSnapshot {
+    turnId: string
+    conversationId: string
+    cwd: string
+    status: 'active' | 'rolled_back'
+    createdAt: string
+    rolledBackAt: string | null
+    files: Array<{ path: string; existed: boolean }>
+}
+
+export async function listTurnSnapshots(conversationId: string): Promise<TurnSnapshot[]> {
+    return api().listTurnSnapshots(conversationId)
+}
+
+export async function rollbackTurn(turnId: string): Promise<{ success: boolean; restored: string[]; removed: string[]; errors: string[]; error?: string }> {
+    return api().rollbackTurn(turnId)
+}
+
+// ===== Response Stats =====
+
+export interface MessageStats {
+    messageId: string
+    length: number
+    words: number
+    readingSeconds: number
+    codeLines: number
+    proseLines: number
+    codeRatio: number
+    filesTouched: number
+    reviewRecommended: boolean
+}
+
+export async function getMessageStats(messageId: string): Promise<MessageStats | null> {
+    return api().getMessageStats(messageId)
+}
+
+export async function getConversationStats(conversationId: string): Promise<{ messages: MessageStats[]; totals: { responses: number; words: number; readingSeconds: number; codeLines: number; filesTouched: number } }> {
+    return api().getConversationStats(conversationId)
+}
+
+// ===== Conversation Templates =====
+
+export interface ConversationTemplate {
+    id: string
+    name: string
+    titlePattern: string
+    systemMessage?: string
+    initialMessages: Array<{ role: string; content: string }>
+    persona?: string
+    cliOverrides?: CliOverrides
+    createdAt: string
+    updatedAt: string
+}
+
+export const templates = {
+    list: (): Promise<ConversationTemplate[]> =>
+        api().templates.list(),
+    save: (template: Omit<ConversationTemplate, 'id' | 'createdAt' | 'updatedAt'> & { id?: string }): Promise<ConversationTemplate> =>
+        api().templates.save(template),
+    delete: (id: string): Promise<{ success: boolean }> =>
+        api().templates.delete(id),
+    createConversation: (templateId: string, conversationId: string, workspaceId: string): Promise<{ success: boolean; conversation?: any; error?: string }> =>
+        api().templates.createConversation(templateId, conversationId, workspaceId),
+}
+
+// ===== Prompt Templates =====
+
+/** A reusable prompt; `{{file}}`, `{{selection}}` and `{{workspace}}` are filled in by the backend. */
+export interface PromptTemplate {
+    id: string
+    name: string
+    body: string
+    variables: string[]
+    createdAt: string
+    updatedAt: string
+}
+
+export const promptTemplates = {
+    list: (): Promise<PromptTemplate[]> =>
+        api().promptTemplates.list(),
+    save: (template: { id?: string; name: string; body: string; variables?: string[] }): Promise<{ success: boolean; template?: PromptTemplate; error?: string }> =>
+        api().promptTemplates.save(template),
+    delete: (id: string): Promise<{ success: boolean }> =>
+        api().promptTemplates.delete(id),
+    /** `values.file` is a path in the workspace whose contents replace `{{file}}`. */
+    render: (id: string, values: Record<string, string>, workspaceId?: string): Promise<{ success: boolean; text?: string; missing?: string[]; error?: string }> =>
+        api().promptTemplates.render(id, values, workspaceId),
 }
 
 // ===== Events =====
@@ -245,7 +2358,18 @@ export function onThinkingDelta(callback: (cid: string, delta: string) => void):
     return api().onThinkingDelta(callback)
 }
 
-export function onStreamEnd(callback: (cid: string) => void): UnlistenFn {
+export interface StreamEndInfo {
+    cancelled?: boolean
+    /** The turn was interrupted gracefully; `text` is the agent output streamed before it stopped. */
+    partial?: boolean
+    text?: string
+    /** `text` stopped at the low-memory limit; the streamed deltas were complete. */
+    truncated?: boolean
+    /** The draft saved while the turn streamed; save the final message under this id to replace it. */
+    messageId?: string
+}
+
+export function onStreamEnd(callback: (cid: string, info: StreamEndInfo) => void): UnlistenFn {
     return api().onStreamEnd(callback)
 }
 
@@ -273,11 +2397,96 @@ export function onApprovalRequest(callback: (cid: string, data: { requestId: str
     return api().onApprovalRequest(callback)
 }
 
+/** A planning pass finished; the plan waits for approveReview or rejectReview. */
+export function onReviewRequest(callback: (cid: string, data: { turnId: string; plan: string; steps: PlanStep[] } & ReviewScope) => void): UnlistenFn {
+    return api().onReviewRequest(callback)
+}
+
+/**
+ * The approved pass tried something outside its scope: a refused approval (`blocked`),
+ * or a file change that already happened and stopped the turn.
+ */
+export function onReviewViolation(callback: (cid: string, data: { turnId: string; items: string[]; blocked: boolean }) => void): UnlistenFn {
+    return api().onReviewViolation(callback)
+}
+
 export function onProgress(callback: (cid: string, text: string) => void): UnlistenFn {
     return api().onProgress(callback)
 }
 
-export function onCodexInstallProgress(callback: (data: { status: string; message: string }) => void): UnlistenFn {
+export function onQueueUpdated(callback: (cid: string, items: Array<{ id: string; prompt: string; queuedAt: string; position: number }>) => void): UnlistenFn {
+    return api().onQueueUpdated(callback)
+}
+
+export function onUsage(callback: (cid: string, usage: TokenUsage) => void): UnlistenFn {
+    return api().onUsage(callback)
+}
+
+export function onTurnStatus(callback: (cid: string, turnId: string, status: TurnStatus) => void): UnlistenFn {
+    return api().onTurnStatus(callback)
+}
+
+/**
+ * Codex printed events this app could not read (renamed fields, unknown event types), so
+ * the turn may show less than codex did. Sent at most once per turn.
+ */
+export function onProtocolMismatch(callback: (cid: string, mismatch: ProtocolMismatch) => void): UnlistenFn {
+    return api().onProtocolMismatch(callback)
+}
+
+/** A turn is waiting for a free slot; sent again whenever its place in line changes. */
+export function onTurnQueued(callback: (cid: string, turnId: string, position: number, limit: number) => void): UnlistenFn {
+    return api().onTurnQueued(callback)
+}
+
+/** A file_change item completed; the diff is cumulative for the file within the turn. */
+export function onFileDiff(callback: (cid: string, diff: FileDiff & { turnId: string }) => void): UnlistenFn {
+    return api().onFileDiff(callback)
+}
+
+/** One MCP tool call of a turn changed status; fetch payloads with getMcpCalls(turnId). */
+export function onMcpCall(callback: (cid: string, call: { turnId: string; callId: string; server: string; tool: string; status: string }) => void): UnlistenFn {
+    return api().onMcpCall(callback)
+}
+
+export function onPathWarning(callback: (cid: string, paths: Array<{ reference: string; resolved: string }>) => void): UnlistenFn {
+    return api().onPathWarning(callback)
+}
+
+/** An `@file` or `@dir/` mention of a prompt; `included` when its contents went with the turn. */
+export interface ContextMention {
+    mention: string
+    path: string
+    kind: 'file' | 'directory'
+    included: boolean
+    skipped?: 'outside-workspace' | 'protected' | 'binary' | 'too-large' | 'over-budget' | 'unreadable'
+    /** Bytes of a file, or entries of a directory. */
+    size: number
+    truncated?: boolean
+}
+
+export function onContextAttached(callback: (cid: string, turnId: string, mentions: ContextMention[]) => void): UnlistenFn {
+    return api().onContextAttached(callback)
+}
+
+export function onConversationMoved(callback: (cid: string, data: { workspaceId: string; cwd: string; cancelled: boolean }) => void): UnlistenFn {
+    return api().onConversationMoved(callback)
+}
+
+export function onOrphanWarning(callback: (data: { pid: number; command: string }) => void): UnlistenFn {
+    return api().onOrphanWarning(callback)
+}
+
+export function onLoginProgress(callback: (data: LoginProgress) => void): UnlistenFn {
+    return api().onLoginProgress(callback)
+}
+
+/** auth.json changed outside the app, or the credentials in it ran out. */
+export function onAuthChanged(callback: (data: { reason: 'login' | 'logout' | 'refresh' | 'updated' | 'expired'; user: CodexUser | null }) => void): UnlistenFn {
+    return api().onAuthChanged(callback)
+}
+
+export function onCodexInstallProgress(callback: (data: { status: string; message: string; percent?: number; method?: CodexInstallMethod }) => void): UnlistenFn {
     return api().onCodexInstallProgress(callback)
 }
 
@@ -285,6 +2494,10 @@ export function onCommandOutput(callback: (data: { commandId: string; type: 'std
     return api().onCommandOutput(callback)
 }
 
+export function onCommandExit(callback: (data: { commandId: string; exitCode: number }) => void): UnlistenFn {
+    return api().onCommandExit(callback)
+}
+
 // ===== Convenience codexApi-compatible object =====
 export const codexApi = {
     setMode,
@@ -292,37 +2505,227 @@ export const codexApi = {
     setYoloMode,
     getYoloMode,
     getModels,
+    refreshModels,
+    getCustomModels,
+    setCustomModels,
     getModel,
     setModel,
     setCliOptions,
     getCliOptions,
+    getWorkspaceCliOptions,
+    setWorkspaceCliOptions,
+    getCodexProfiles,
+    validateCodexProfile,
+    getStartupIntent,
+    getStartupSettings,
+    setStartupSettings,
+    recordLastSession,
     checkCodex,
+    getCodexProtocol,
     installCodex,
+    upgradeCodex,
+    getCodexInstallMethods,
+    setCodexInstallMethod,
+    getCodexVersion,
+    listAgentBackends,
     initAcp,
     openWorkspace,
     switchWorkspace,
+    watchPaths,
+    onWorkspaceFileChanged,
     streamCodex,
+    streamCodexPlan,
+    captureScreen,
     debugLog,
+    setEventLogEnabled,
+    getEventLogStatus,
+    getRecentEventLog,
+    getNotificationPrefs,
+    setNotificationPrefs,
+    getNotificationRoutes,
+    saveNotificationRoute,
+    deleteNotificationRoute,
+    testNotificationRoute,
+    onNotificationClicked,
+    onUnreadCount,
+    getNarrationEnabled,
+    setNarrationEnabled,
+    onNarration,
+    listWebhooks,
+    addWebhook,
+    removeWebhook,
+    setWebhookEnabled,
+    testWebhook,
+    getApiServerSettings,
+    setApiServerSettings,
+    getApiServerStatus,
+    onApiConversationUpdated,
+    checkPromptPaths,
+    analyzePrompt,
     cancelPrompt,
+    getStreamSnapshot,
+    getStreamBuffer,
+    subscribeStream,
+    unsubscribeStream,
+    getQueueMode,
+    setQueueMode,
+    getReviewMode,
+    setReviewMode,
+    getPendingReview,
+    approveReview,
+    executePlan,
+    rejectReview,
+    getCancelGracePeriod,
+    setCancelGracePeriod,
+    getHistorySettings,
+    setHistorySettings,
+    getHistorySummary,
+    clearHistorySummary,
+    getPromptQueue,
+    clearPromptQueue,
+    getMaxConcurrentTurns,
+    setMaxConcurrentTurns,
+    getRunningTurns,
+    allocateTempDir,
+    releaseTempDirs,
+    setConversationCwd,
+    moveConversation,
     updateTitleBarOverlay,
     respondToApproval,
+    listOrphanProcesses,
     codexLogin,
+    cancelLogin,
     codexLogout,
     codexLoginMethods,
     getUser,
+    secretSet,
+    secretGet,
+    secretDelete,
+    getSecretSettings,
+    setSecretSettings,
+    listAccounts,
+    addAccount,
+    saveCurrentAccount,
+    switchAccount,
+    removeAccount,
     searchFiles,
+    searchFileContents,
     readFileContent,
+    readFileRange,
+    readFileHex,
+    getProtectedPaths,
+    setProtectedPaths,
+    allowProtectedFile,
+    revokeProtectedFile,
     writeFile,
+    restoreFileBackup,
     listDirectory,
     fileExists,
+    createDirectory,
+    deletePath,
+    renamePath,
+    copyPath,
+    movePath,
     openInEditor,
+    listArchive,
+    extractArchive,
+    permissions,
     runCommand,
     runCodexCommand,
+    startCommand,
+    commandWriteStdin,
     killCommand,
+    rerunCommand,
+    listCommandRuns,
+    compareCommandRuns,
+    git,
+    getGitHubSettings,
+    setGitHubSettings,
+    createPrFromChanges,
+    generateChangelog,
+    runRelease,
+    onReleaseStep,
+    onReleaseOutput,
+    readInstructions,
+    writeInstructions,
+    generateStarterInstructions,
+    createStarterInstructions,
+    appendInstructionsConventions,
+    getInstructionsStatus,
+    summarizeWorkspace,
+    getWorkspaceSummary,
+    detectPackages,
+    setConversationTargetPackage,
+    runTargetTests,
+    checkOutdatedDependencies,
+    buildDependencyUpgradePrompt,
     sendToTeams,
+    getTurns,
+    getTurn,
+    getTurnForMessage,
+    rateTurn,
+    onTurnStatus,
+    onTurnQueued,
+    onProtocolMismatch,
+    getTurnDiffs,
+    onFileDiff,
+    getMcpCalls,
+    getMcpServers,
+    getMcpServerStatuses,
+    restartMcpServer,
+    onMcpServerStatus,
+    getArtifactTheme,
+    setArtifactTheme,
     pty,
     webSearch,
+    fetchUrlContent,
+    getWebSearchSettings,
+    setWebSearchSettings,
+    chores,
+    scheduler,
+    watchRules,
+    presets,
+    getPromptHistory,
+    clearPromptHistory,
+    checkPromptText,
+    translateText,
+    getTranslationSettings,
+    setTranslationSettings,
+    setConversationAutoTranslate,
+    onMessageTranslated,
+    getConversationNotes,
+    setConversationNotes,
+    exportConversation,
+    importConversation,
+    exportWorkspaceBundle,
+    importWorkspaceBundle,
+    getHousekeepingSuggestions,
+    getHousekeepingSettings,
+    setHousekeepingSettings,
+    archiveWorkspace,
+    exportAndPruneWorkspace,
+    onHousekeepingSuggestions,
     db,
+    getUsagePricing,
+    setUsagePricing,
+    estimateTurnCost,
+    getCostConfirmThreshold,
+    setCostConfirmThreshold,
+    listJobs,
+    startJob,
+    cancelJob,
+    retryJob,
+    dismissJob,
+    onJobUpdated,
+    getLowMemoryMode,
+    setLowMemoryMode,
+    getResourceUsage,
+    listTurnSnapshots,
+    rollbackTurn,
+    getMessageStats,
+    getConversationStats,
+    templates,
+    promptTemplates,
     // Event listeners
     onStreamToken,
     onStreamDelta,
@@ -335,9 +2738,21 @@ export const codexApi = {
     onToolCall,
     onTerminalOutput,
     onApprovalRequest,
+    onReviewRequest,
+    onReviewViolation,
     onProgress,
+    onQueueUpdated,
+    onUsage,
+    onMcpCall,
+    onPathWarning,
+    onContextAttached,
+    onConversationMoved,
+    onOrphanWarning,
     onCodexInstallProgress,
+    onLoginProgress,
+    onAuthChanged,
     onCommandOutput,
+    onCommandExit,
 }
 
 export default codexApi
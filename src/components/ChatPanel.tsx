@@ -6,6 +6,7 @@ import 'highlight.js/styles/github-dark.css'
 import { FileIcon } from './FileIcon'
 import { ApprovalDialog } from './ApprovalDialog'
 import { useI18n } from '../i18n'
+import { type ApprovalDecision } from '../api/tauri-api'
 
 
 interface Message {
@@ -51,6 +52,9 @@ interface BackgroundCommand {
 
 interface ChatPanelProps {
     messages: Message[]
+    // Earlier messages that are not loaded yet
+    hasOlderMessages?: boolean
+    onLoadOlder?: () => void
     streamingContent: string
     streamingThinking: string
     isLoading: boolean
@@ -68,7 +72,7 @@ interface ChatPanelProps {
     onApprove?: (messageId: string) => void
     // Approval request
     approvalRequest?: { requestId: string; title: string; description: string } | null
-    onApprovalResponse?: (requestId: string, approved: boolean) => void
+    onApprovalResponse?: (requestId: string, decision: ApprovalDecision | boolean, message?: string) => void
     // Teams integration
     onSendToTeams?: (content: string) => void
 }
@@ -551,6 +555,8 @@ function MarkdownContent({ content }: { content: string }) {
 
 export const ChatPanel = memo(function ChatPanel({
     messages,
+    hasOlderMessages = false,
+    onLoadOlder,
     streamingContent,
     streamingThinking,
     isLoading,
@@ -618,6 +624,15 @@ export const ChatPanel = memo(function ChatPanel({
             ) : (
                 <div className="max-w-3xl mx-auto py-4 px-4 w-full">
 
+                    {hasOlderMessages && onLoadOlder && (
+                        <button
+                            onClick={onLoadOlder}
+                            className="block mx-auto mb-4 text-[11px] text-[var(--color-text-muted)] hover:text-[var(--color-text-primary)]"
+                        >
+                            {t('loadEarlierMessages')}
+                        </button>
+                    )}
+
                     {messages.map((message) => (
                         <div key={message.id} className="mb-4">
                             {message.role === 'user' ? (
@@ -738,6 +753,12 @@ export const ChatPanel = memo(function ChatPanel({
                                     >
                                         {t('approvalReject')}
                                     </button>
+                                    <button
+                                        onClick={() => onApprovalResponse?.(approvalRequest.requestId, 'approve_for_session')}
+                                        className="px-4 py-1.5 text-[12px] rounded bg-[var(--color-bg-card)] text-[var(--color-text-secondary)] hover:bg-[var(--color-bg-hover)] border border-[var(--color-border)] transition-colors"
+                                    >
+                                        {t('approvalApproveSession')}
+                                    </button>
                                     <button
                                         onClick={() => onApprovalResponse?.(approvalRequest.requestId, true)}
                                         className="px-4 py-1.5 text-[12px] rounded bg-[var(--color-primary)] text-white hover:opacity-90 transition-opacity"
@@ -14,6 +14,7 @@ interface Conversation {
     title: string
     createdAt: string
     updatedAt: string
+    unreadCount?: number
     messages: unknown[]
 }
 
@@ -360,6 +361,14 @@ export const Sidebar = memo(function Sidebar({
                                                             }`}
                                                     >
                                                         <span className="truncate flex-1">{conv.title}</span>
+                                                        {conv.id !== activeConversationId && (conv.unreadCount ?? 0) > 0 && (
+                                                            <span
+                                                                className="min-w-[18px] px-1.5 py-0.5 text-[10px] text-center rounded-full font-medium bg-[var(--color-primary)] text-white"
+                                                                title={`${conv.unreadCount} unread`}
+                                                            >
+                                                                {conv.unreadCount}
+                                                            </span>
+                                                        )}
                                                         {showApproval && (
                                                             <span
                                                                 className="px-2 py-0.5 text-[11px] rounded-full font-medium"
@@ -24,6 +24,7 @@ const en = {
     chatPlaceholderNoWorkspace: 'Open a workspace to get started...',
     chatPlaceholderLoading: 'Type to cancel current response...',
     startConversation: 'Start a conversation with Codex',
+    loadEarlierMessages: 'Load earlier messages',
     stopResponse: 'Stop response',
     responseCancelled: '(Response cancelled)',
     errorOccurred: 'An error occurred: ',
@@ -42,6 +43,7 @@ const en = {
     approvalReject: 'Reject',
     approvalApproving: 'Approving...',
     approvalApprove: 'Approve',
+    approvalApproveSession: 'Approve for session',
 
     // Model Selector
     selectModel: 'Select AI Model',
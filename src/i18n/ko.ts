@@ -26,6 +26,7 @@ const ko: Translations = {
     chatPlaceholderNoWorkspace: '워크스페이스를 열어 시작하세요...',
     chatPlaceholderLoading: '질문을 입력하면 현재 응답을 취소합니다...',
     startConversation: 'Codex와 대화를 시작하세요',
+    loadEarlierMessages: '이전 메시지 불러오기',
     stopResponse: '응답 중단',
     responseCancelled: '(응답이 취소됨)',
     errorOccurred: '오류가 발생했습니다: ',
@@ -44,6 +45,7 @@ const ko: Translations = {
     approvalReject: '거절',
     approvalApproving: '진행 중...',
     approvalApprove: '승인',
+    approvalApproveSession: '세션 동안 승인',
 
     // Model Selector
     selectModel: 'AI 모델 선택',
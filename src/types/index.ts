@@ -7,6 +7,15 @@ export interface FileSearchResult {
     isDirectory: boolean
 }
 
+export interface MessageAttachment {
+    id: string
+    kind: 'image'
+    name: string
+    path: string
+    mimeType: string
+    size: number
+}
+
 export interface Message {
     id: string
     conversationId: string
@@ -14,6 +23,11 @@ export interface Message {
     content: string
     thinking?: string
     thinkingDuration?: number
+    attachments?: MessageAttachment[]
+    /** Set when `content` is only the first part of a segmented message. */
+    partCount?: number
+    /** Output of a turn saved while it streamed; still set when the app stopped mid-turn. */
+    inProgress?: boolean
     timestamp: string
     needsApproval?: boolean
 }
@@ -24,6 +38,17 @@ export interface Conversation {
     title: string
     createdAt: string
     updatedAt: string
+    /** Directory codex runs in, relative to the workspace root. */
+    cwd?: string
+    /** Conversation this one was forked from. */
+    forkedFrom?: string
+    archived?: boolean
+    pinned?: boolean
+    lastActivityAt?: string
+    /** Assistant messages the user has not seen yet. */
+    unreadCount?: number
+    /** All messages of the conversation; `messages` starts empty and is loaded in pages. */
+    messageCount?: number
     messages: Message[]
 }
 
@@ -31,6 +56,8 @@ export interface Workspace {
     id: string
     name: string
     path: string
+    /** Change counter; it moves whenever anything in the workspace is written. */
+    etag?: string
     conversations: Conversation[]
 }
 
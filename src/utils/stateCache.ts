@@ -0,0 +1,98 @@
+/**
+ * Startup cache of the workspace and conversation lists
+ *
+ * The last state read from the database is kept in localStorage with its sync token,
+ * so the sidebar renders before the database answers. db.getChanges(token) then
+ * returns only what changed, which applyDbChanges merges in.
+ */
+
+import type { DbChanges } from '../api/tauri-api'
+import type { Conversation, Message, Workspace } from '../types'
+
+const STORAGE_KEY = 'codex.stateCache.v1'
+
+interface CachedState {
+    token: string
+    workspaces: Workspace[]
+}
+
+export function loadCachedState(): CachedState | null {
+    try {
+        const raw = localStorage.getItem(STORAGE_KEY)
+        if (!raw) return null
+        const cached = JSON.parse(raw)
+        return typeof cached?.token === 'string' && Array.isArray(cached.workspaces) ? cached : null
+    } catch {
+        return null
+    }
+}
+
+/** Messages are not cached; they are paged in when a conversation is opened. */
+export function saveCachedState(token: string, workspaces: Workspace[]) {
+    const stripped = workspaces.map(w => ({
+        ...w,
+        // Undefined fields are dropped by JSON.stringify.
+        conversations: w.conversations.map(c => ({ ...c, messages: [], messagesLoaded: undefined, hasOlderMessages: undefined })),
+    }))
+    try {
+        localStorage.setItem(STORAGE_KEY, JSON.stringify({ token, workspaces: stripped }))
+    } catch (error) {
+        // Over the storage quota: start from the database next time.
+        localStorage.removeItem(STORAGE_KEY)
+        console.warn('[stateCache] Failed to save state cache:', error)
+    }
+}
+
+function mergeMessages(messages: Message[], changes: DbChanges, conversationId: string): Message[] {
+    // Only pages already on screen are patched; unloaded conversations fetch fresh pages.
+    if (messages.length === 0) return messages
+    const deleted = new Set(changes.deleted.messages)
+    const merged = messages.filter(m => !deleted.has(m.id))
+    for (const message of changes.messages as Message[]) {
+        if (message.conversationId !== conversationId) continue
+        const index = merged.findIndex(m => m.id === message.id)
+        if (index >= 0) merged[index] = { ...merged[index], ...message }
+        else merged.push(message)
+    }
+    return merged
+}
+
+/** Apply the result of db.getChanges to workspace lists; unknown conversations are appended. */
+export function applyDbChanges<W extends Workspace>(workspaces: W[], changes: DbChanges): W[] {
+    const deletedWorkspaces = new Set(changes.deleted.workspaces)
+    const deletedConversations = new Set(changes.deleted.conversations)
+    const changedConversations = new Map((changes.conversations as Conversation[]).map(c => [c.id, c]))
+    const placed = new Set<string>()
+
+    const result = workspaces
+        .filter(w => !deletedWorkspaces.has(w.id))
+        .map(w => {
+            const updated = (changes.workspaces as Workspace[]).find(u => u.id === w.id)
+            const conversations = w.conversations
+                .filter(c => !deletedConversations.has(c.id))
+                // A conversation moved to another workspace is re-added there below.
+                .filter(c => (changedConversations.get(c.id)?.workspaceId ?? w.id) === w.id)
+                .map(c => {
+                    const changed = changedConversations.get(c.id)
+                    if (!changed) return c
+                    placed.add(c.id)
+                    return { ...c, ...changed, messages: mergeMessages(c.messages, changes, c.id) }
+                })
+            return {
+                ...w,
+                ...(updated ? { name: updated.name, path: updated.path } : {}),
+                etag: changes.etags[w.id] ?? w.etag,
+                conversations,
+            } as W
+        })
+
+    for (const workspace of changes.workspaces as Workspace[]) {
+        if (!result.some(w => w.id === workspace.id)) result.push({ ...workspace, conversations: [] } as unknown as W)
+    }
+    for (const conversation of changedConversations.values()) {
+        if (placed.has(conversation.id)) continue
+        const workspace = result.find(w => w.id === conversation.workspaceId)
+        workspace?.conversations.push({ ...conversation, messages: [] })
+    }
+    return result
+}
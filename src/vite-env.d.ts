@@ -62,6 +62,13 @@ interface DirectoryEntry {
     path: string
     isDirectory: boolean
     size: number
+    fileType: 'file' | 'directory' | 'symlink' | 'other'
+    symlinkTarget: string | null
+    brokenLink: boolean
+    isHidden: boolean
+    mode: number
+    readonly: boolean
+    modifiedAt: string
 }
 
 interface CommandResult {
@@ -69,6 +76,8 @@ interface CommandResult {
     commandId: string
     output?: string
     errorOutput?: string
+    outputFile?: string
+    errorOutputFile?: string
     exitCode?: number
     error?: string
 }